@@ -0,0 +1,31 @@
+//! Captures the version of rustc and the commit for `environment::Environment`.
+
+use std::env;
+use std::process::Command;
+
+/// The trimmed standard output of `command`, if it succeeds.
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version =
+        output(Command::new(rustc).arg("--version")).unwrap_or_else(|| "unknown".to_string());
+    let mut commit = output(Command::new("git").args(&["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    let status =
+        output(Command::new("git").args(&["status", "--porcelain", "--untracked-files=no"]));
+    if status.map_or(false, |s| !s.is_empty()) {
+        commit += "-dirty";
+    }
+    println!("cargo:rustc-env=BUILD_RUSTC_VERSION={}", version);
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", commit);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=src");
+}
@@ -0,0 +1,130 @@
+/*
+ * A hash map with a lock per bucket that implements pebr_bench.h, to check the bridge and as a
+ * template for adapting a C or C++ library.
+ *
+ *     cc -O2 -shared -fPIC -o example_map.so ffi/example_map.c -lpthread
+ *     cargo run --release -- -d Ffi --ffi-lib ./example_map.so -m NR -t 4
+ */
+
+#include <pthread.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "pebr_bench.h"
+
+#define BUCKETS 30000
+
+struct node {
+    struct node *next;
+    size_t len;
+    char key[];
+};
+
+struct bucket {
+    pthread_mutex_t lock;
+    struct node *head;
+};
+
+struct map {
+    struct bucket buckets[BUCKETS];
+    size_t len;
+};
+
+static struct bucket *bucket_of(struct map *m, const char *key, size_t len) {
+    /* FNV-1a */
+    unsigned long long h = 14695981039346656037ULL;
+    for (size_t i = 0; i < len; i++) {
+        h = (h ^ (unsigned char)key[i]) * 1099511628211ULL;
+    }
+    return &m->buckets[h % BUCKETS];
+}
+
+/* The link that points to the node of the key, or the last link of the bucket. */
+static struct node **find(struct bucket *b, const char *key, size_t len) {
+    struct node **link = &b->head;
+    while (*link && ((*link)->len != len || memcmp((*link)->key, key, len) != 0)) {
+        link = &(*link)->next;
+    }
+    return link;
+}
+
+void *pebr_map_new(void) {
+    struct map *m = calloc(1, sizeof(struct map));
+    for (size_t i = 0; i < BUCKETS; i++) {
+        pthread_mutex_init(&m->buckets[i].lock, NULL);
+    }
+    return m;
+}
+
+void pebr_map_free(void *map) {
+    struct map *m = map;
+    for (size_t i = 0; i < BUCKETS; i++) {
+        struct node *n = m->buckets[i].head;
+        while (n) {
+            struct node *next = n->next;
+            free(n);
+            n = next;
+        }
+        pthread_mutex_destroy(&m->buckets[i].lock);
+    }
+    free(m);
+}
+
+void *pebr_thread_register(void *map) {
+    (void)map;
+    return NULL;
+}
+
+void pebr_thread_unregister(void *map, void *thread) {
+    (void)map;
+    (void)thread;
+}
+
+bool pebr_map_get(void *map, void *thread, const char *key, size_t len) {
+    (void)thread;
+    struct bucket *b = bucket_of(map, key, len);
+    pthread_mutex_lock(&b->lock);
+    bool found = *find(b, key, len) != NULL;
+    pthread_mutex_unlock(&b->lock);
+    return found;
+}
+
+bool pebr_map_insert(void *map, void *thread, const char *key, size_t len) {
+    (void)thread;
+    struct map *m = map;
+    struct bucket *b = bucket_of(m, key, len);
+    pthread_mutex_lock(&b->lock);
+    struct node **link = find(b, key, len);
+    bool inserted = *link == NULL;
+    if (inserted) {
+        struct node *n = malloc(sizeof(struct node) + len);
+        n->next = NULL;
+        n->len = len;
+        memcpy(n->key, key, len);
+        *link = n;
+        __atomic_fetch_add(&m->len, 1, __ATOMIC_RELAXED);
+    }
+    pthread_mutex_unlock(&b->lock);
+    return inserted;
+}
+
+bool pebr_map_remove(void *map, void *thread, const char *key, size_t len) {
+    (void)thread;
+    struct map *m = map;
+    struct bucket *b = bucket_of(m, key, len);
+    pthread_mutex_lock(&b->lock);
+    struct node **link = find(b, key, len);
+    struct node *n = *link;
+    if (n) {
+        *link = n->next;
+        __atomic_fetch_sub(&m->len, 1, __ATOMIC_RELAXED);
+    }
+    pthread_mutex_unlock(&b->lock);
+    free(n);
+    return n != NULL;
+}
+
+size_t pebr_map_len(void *map) {
+    struct map *m = map;
+    return __atomic_load_n(&m->len, __ATOMIC_RELAXED);
+}
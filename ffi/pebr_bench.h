@@ -0,0 +1,39 @@
+/*
+ * The interface that a C or C++ concurrent map implements to be benchmarked by pebr-benchmark
+ * with `-d Ffi --ffi-lib <path to the shared library>`.
+ *
+ * The map reclaims its own memory with its own scheme: the benchmark only registers the worker
+ * threads and calls the operations. Keys are the same strings as for the maps of the benchmark,
+ * passed as bytes that are not NUL-terminated and only valid during the call.
+ *
+ * The functions must be exported with C linkage (`extern "C"` in C++).
+ */
+
+#ifndef PEBR_BENCH_H
+#define PEBR_BENCH_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/* Creates an empty map. */
+void *pebr_map_new(void);
+/* Destroys the map, after every thread has unregistered. */
+void pebr_map_free(void *map);
+
+/* Registers the calling thread, e.g., to the reclamation scheme of the map. The result is passed to
+ * the operations of the thread. */
+void *pebr_thread_register(void *map);
+/* Unregisters the calling thread. */
+void pebr_thread_unregister(void *map, void *thread);
+
+/* Whether the key is in the map. */
+bool pebr_map_get(void *map, void *thread, const char *key, size_t len);
+/* Inserts the key, with any value. Fails if the key is already in the map. */
+bool pebr_map_insert(void *map, void *thread, const char *key, size_t len);
+/* Removes the key. Fails if the key is not in the map. */
+bool pebr_map_remove(void *map, void *thread, const char *key, size_t len);
+
+/* The approximate number of keys, for the time series. */
+size_t pebr_map_len(void *map);
+
+#endif
@@ -0,0 +1,13 @@
+//! Runs the program decoded from the input on two maps, and fails on the first divergence of their
+//! outcomes (`pebr_benchmark::differential`).
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pebr_benchmark::differential;
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(divergence) = differential::run(data) {
+        panic!("{}", divergence);
+    }
+});
@@ -0,0 +1,85 @@
+//! The bag workload: each operation either adds a value or removes an arbitrary one (stealing from
+//! the other threads if necessary) with equal probability.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::pebr;
+
+use super::Pebr;
+use crate::{Config, Perf};
+
+/// Benchmarks the EBR port of bag `B` under NR or EBR.
+pub(crate) fn ebr<B: ebr::ConcurrentBag<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+) -> Perf {
+    let bag = &B::new();
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let mut handle = bag.handle();
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let value = config.key_dist.sample(rng).to_string();
+        bag.add(&mut handle, value, guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| bag.len_approx(),
+        |_| bag.handle(),
+        |handle, guard, worker| {
+            let found = if worker.rng.gen() {
+                let value = config.key_dist.sample(&mut worker.rng).to_string();
+                bag.add(handle, value, guard);
+                true
+            } else {
+                bag.try_remove_any(handle, guard).is_some()
+            };
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the PEBR port of bag `B`.
+pub(crate) fn pebr<B: pebr::ConcurrentBag<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+) -> Perf {
+    let bag = &B::new();
+    let guard = unsafe { crossbeam_pebr::unprotected() };
+    let mut handle = bag.handle(guard);
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let value = config.key_dist.sample(rng).to_string();
+        bag.add(&mut handle, value, guard);
+    });
+
+    let ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        || bag.len_approx(),
+        |_, guard| bag.handle(guard),
+        B::clear,
+        |local, worker| {
+            let Pebr {
+                guard,
+                data: handle,
+                repin,
+                ..
+            } = local;
+            // The bag repins every batch.
+            repin.write();
+            let found = if worker.rng.gen() {
+                let value = config.key_dist.sample(&mut worker.rng).to_string();
+                bag.add(handle, value, guard);
+                true
+            } else {
+                bag.try_remove_any(handle, guard).is_some()
+            };
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,66 @@
+//! The bitmap workload: the keys are the bits of a bitmap of the range.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Op, Perf};
+
+/// One operation of the workload of `Bitmap`: a get tests the bit of the key, and an insert or a
+/// remove sets or clears it.
+fn bitmap_op<R: Rng>(
+    config: &Config,
+    bitmap: &ebr::Bitmap,
+    tid: usize,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) {
+    let bit = config.key_dist.sample(rng);
+    match Op::OPS[config.op_dist(tid).sample(rng)] {
+        Op::Get => {
+            bitmap.test(bit, guard);
+        }
+        Op::Insert => {
+            bitmap.set(bit, guard);
+        }
+        Op::Remove => {
+            bitmap.clear(bit, guard);
+        }
+    }
+}
+
+/// Prints how many segments the bitmap allocated and retired, its garbage.
+fn report_bitmap(bitmap: &ebr::Bitmap) {
+    let (allocated, retired) = bitmap.segments();
+    println!(
+        "bitmap: {} bits set, {} segments allocated, {} retired",
+        bitmap.len_approx(),
+        allocated,
+        retired
+    );
+}
+
+/// Benchmarks `Bitmap` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let bitmap = &ebr::Bitmap::new(config.range);
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |i, rng| {
+        bitmap.set(config.prefill_key(i, rng), guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| bitmap.len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            bitmap_op(config, bitmap, worker.tid, &mut worker.rng, guard);
+            Some(true)
+        },
+    );
+    report_bitmap(bitmap);
+    ran.perf(config, build_ops_per_sec)
+}
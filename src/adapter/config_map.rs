@@ -0,0 +1,71 @@
+//! The read-mostly workload of a configuration: the workers read the entries of the keys of
+//! `ConfigMap`, and rarely update them, replacing the whole snapshot.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Perf};
+
+const CONFIG_UPDATE_ONE_IN: u32 = 10_000;
+
+/// Creates the map of the prefill at once, as inserting the keys one by one would copy it each time.
+fn prefill_config_map(config: &Config) -> (ebr::ConfigMap<usize, usize>, u64) {
+    let mut rng = config.rng(config.threads);
+    let start = Instant::now();
+    let map = ebr::ConfigMap::with_entries((0..config.prefill).map(|i| {
+        let k = config.prefill_key(i, &mut rng);
+        (k, k)
+    }));
+    let build_ops_per_sec = super::report_build(config, start);
+    (map, build_ops_per_sec)
+}
+
+/// One operation of the workload of `ConfigMap`: a get of the key, or once in
+/// `CONFIG_UPDATE_ONE_IN` operations, an update that removes the key, or inserts it if absent.
+fn config_map_op<R: Rng>(
+    map: &ebr::ConfigMap<usize, usize>,
+    config: &Config,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) {
+    let k = config.key_dist.sample(rng);
+    if rng.gen_range(0, CONFIG_UPDATE_ONE_IN) == 0 {
+        if map.remove(&k, guard).is_none() {
+            map.insert(k, k, guard);
+        }
+    } else {
+        map.get(&k, guard);
+    }
+}
+
+/// Prints how many snapshots the map published, each of which retired its predecessor.
+fn report_config_map(map: &ebr::ConfigMap<usize, usize>) {
+    println!(
+        "config map: {} entries, {} snapshots published and retired",
+        map.len_approx(),
+        map.published()
+    );
+}
+
+/// Benchmarks `ConfigMap` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let (map, build_ops_per_sec) = prefill_config_map(config);
+    let map = &map;
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| map.len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            config_map_op(map, config, &mut worker.rng, guard);
+            Some(true)
+        },
+    );
+    report_config_map(map);
+    ran.perf(config, build_ops_per_sec)
+}
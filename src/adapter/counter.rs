@@ -0,0 +1,55 @@
+//! The counter workload: each operation reads, increments or decrements the counter, with the
+//! weights of the map workload (`-g`).
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Op, Perf};
+
+/// One operation of the counter workload of worker `tid`. Returns what it added to the counter.
+fn counter_op<C: ebr::ConcurrentCounter, R: Rng>(
+    config: &Config,
+    counter: &C,
+    tid: usize,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) -> i64 {
+    let delta = match Op::OPS[config.op_dist(tid).sample(rng)] {
+        Op::Get => {
+            counter.get(guard);
+            return 0;
+        }
+        Op::Insert => 1,
+        Op::Remove => -1,
+    };
+    counter.add(tid, delta, guard);
+    delta
+}
+
+/// Checks that the counter holds the sum of what the workers added, once they are done.
+fn check_counter<C: ebr::ConcurrentCounter>(counter: &C, sum: i64) {
+    let value = counter.get(unsafe { crossbeam_ebr::unprotected() });
+    assert_eq!(value, sum, "the counter lost updates");
+}
+
+/// Benchmarks counter `C` under NR or EBR.
+pub(crate) fn ebr<C: ebr::ConcurrentCounter + Send + Sync, N: Unsigned>(config: &Config) -> Perf {
+    let counter = &C::new(config.threads);
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |guard| counter.get(guard).max(0) as usize,
+        |_| (),
+        |_, guard, worker| {
+            worker.sum += counter_op(config, counter, worker.tid, &mut worker.rng, guard);
+            Some(true)
+        },
+    );
+    check_counter(counter, ran.sum());
+    ran.perf(config, 0)
+}
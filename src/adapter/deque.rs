@@ -0,0 +1,46 @@
+//! The deque workload: each operation pushes or pops at either end with equal probability.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Perf};
+
+/// One operation of the deque workload: a push or a pop at either end, with equal probability.
+/// Returns `false` if the operation found no work, i.e., it popped from an empty deque.
+fn deque_op<R: Rng>(
+    config: &Config,
+    deque: &ebr::Deque<String>,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) -> bool {
+    match rng.gen_range(0, 4) {
+        0 => deque.push_left(config.key_dist.sample(rng).to_string(), guard),
+        1 => deque.push_right(config.key_dist.sample(rng).to_string(), guard),
+        2 => return deque.pop_left(guard).is_some(),
+        _ => return deque.pop_right(guard).is_some(),
+    }
+    true
+}
+
+/// Benchmarks `Deque` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let deque = &ebr::Deque::new();
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let value = config.key_dist.sample(rng).to_string();
+        deque.push_right(value, guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| deque.len_approx(),
+        |_| (),
+        |_, guard, worker| Some(deque_op(config, deque, &mut worker.rng, guard)),
+    );
+    ran.perf(config, build_ops_per_sec)
+}
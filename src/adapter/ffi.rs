@@ -0,0 +1,51 @@
+//! The map workload on the map of the shared library of `--ffi-lib`.
+
+use std::process;
+use std::time::Instant;
+
+use pebr_benchmark::ffi::FfiMap;
+use pebr_benchmark::registry::Prefill;
+
+use super::Reclaim;
+use crate::{Config, History, Op, Perf};
+
+/// Benchmarks the map of the shared library of `--ffi-lib`, which reclaims its own memory.
+pub(crate) fn run(config: &Config, prefill: Prefill) -> Perf {
+    let path = config.ffi_lib.as_ref().unwrap();
+    let map = &FfiMap::load(path).unwrap_or_else(|e| {
+        eprintln!("cannot load {}: {}", path, e);
+        process::exit(1);
+    });
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let handle = map.register();
+    let build_ops_per_sec = super::prefill(config, prefill, &mut prefill_history, |op, k| {
+        let key = config.map_key(k);
+        match op {
+            Op::Get => handle.get(&key),
+            Op::Insert => handle.insert(&key),
+            Op::Remove => handle.remove(&key),
+        }
+    });
+    drop(handle);
+
+    let mut ran = super::run(
+        config,
+        Reclaim::None,
+        history_start,
+        || map.len_approx(),
+        |_| ((), map.register()),
+        |(_, handle), worker| {
+            worker.map_op(|op, k, _| {
+                let key = config.map_key(k);
+                match op {
+                    Op::Get => handle.get(&key),
+                    Op::Insert => handle.insert(&key),
+                    Op::Remove => handle.remove(&key),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
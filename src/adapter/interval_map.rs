@@ -0,0 +1,61 @@
+//! The interval map workload: the gets are stabbing queries, each of which reads all intervals that
+//! start up to `--interval-len` points before its point.
+
+use rand::prelude::*;
+use std::cmp::min;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{unit_hash, Config, Op, Perf};
+
+/// The interval `[k, k + len)` of the workload of `IntervalMap`, whose length is determined by
+/// `k`.
+fn interval(config: &Config, k: usize) -> (usize, usize) {
+    let len = 1 + (unit_hash(k) * config.interval_len as f64) as usize;
+    (k, k + min(len, config.interval_len))
+}
+
+/// One operation of the workload of `IntervalMap`: a get is a stabbing query of the point, and an
+/// insert or a remove adds or removes the interval that starts at the point.
+fn interval_map_op<R: Rng>(
+    config: &Config,
+    map: &ebr::IntervalMap<String>,
+    tid: usize,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) {
+    let op = Op::OPS[config.op_dist(tid).sample(rng)];
+    let (start, end) = interval(config, config.key_dist.sample(rng));
+    match op {
+        Op::Get => drop(map.find_containing(start, guard)),
+        Op::Insert => {
+            map.insert(start, end, start.to_string(), guard);
+        }
+        Op::Remove => drop(map.remove(start, end, guard)),
+    }
+}
+
+/// Benchmarks `IntervalMap` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let map = &ebr::IntervalMap::new(config.range, config.interval_len);
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |i, rng| {
+        let (start, end) = interval(config, config.prefill_key(i, rng));
+        map.insert(start, end, start.to_string(), guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| map.len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            interval_map_op(config, map, worker.tid, &mut worker.rng, guard);
+            Some(true)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,358 @@
+//! The map workload, on the maps and on the sets made of them (`--set`).
+
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::audit;
+use pebr_benchmark::ebr;
+use pebr_benchmark::pebr;
+use pebr_benchmark::profiling;
+#[cfg(feature = "lists")]
+use pebr_benchmark::rc;
+use pebr_benchmark::registry::Prefill;
+use pebr_benchmark::stw;
+
+use super::Pebr;
+#[cfg(feature = "lists")]
+use super::Reclaim;
+use crate::{BenchKey, Config, History, Op, Perf, Value, MM};
+
+/// Prefills `map` of the EBR port (see `adapter::prefill`).
+pub(super) fn prefill_ebr<K: BenchKey, V: From<String>, M: ebr::ConcurrentMap<K, V>>(
+    config: &Config,
+    map: &M,
+    prefill: Prefill,
+    history: &mut History,
+) -> u64 {
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    super::prefill(config, prefill, history, |op, k| {
+        let key = K::from(config.map_key(k));
+        match op {
+            Op::Get => map.get(&key, guard).is_some(),
+            Op::Insert => {
+                let value = V::from(key.to_string());
+                map.insert(key, value, guard)
+            }
+            Op::Remove => map.remove(&key, guard).is_some(),
+        }
+    })
+}
+
+/// Prefills `map` of the PEBR port (see `adapter::prefill`).
+pub(super) fn prefill_pebr<K: BenchKey, V: From<String>, M: pebr::ConcurrentMap<K, V>>(
+    config: &Config,
+    map: &M,
+    prefill: Prefill,
+    history: &mut History,
+) -> u64 {
+    let guard = unsafe { crossbeam_pebr::unprotected() };
+    let mut handle = M::handle(guard);
+    super::prefill(config, prefill, history, |op, k| {
+        let key = K::from(config.map_key(k));
+        match op {
+            Op::Get => map.get(&mut handle, &key, guard).is_some(),
+            Op::Insert => {
+                let value = V::from(key.to_string());
+                map.insert(&mut handle, key, value, guard)
+            }
+            Op::Remove => map.remove(&mut handle, &key, guard).is_some(),
+        }
+    })
+}
+
+/// Prefills `map` of the RC port (see `adapter::prefill`).
+#[cfg(feature = "lists")]
+fn prefill_rc<K: BenchKey, V: From<String>, M: rc::ConcurrentMap<K, V>>(
+    config: &Config,
+    map: &M,
+    prefill: Prefill,
+    history: &mut History,
+) -> u64 {
+    super::prefill(config, prefill, history, |op, k| {
+        let key = K::from(config.map_key(k));
+        match op {
+            Op::Get => map.get(&key).is_some(),
+            Op::Insert => {
+                let value = V::from(key.to_string());
+                map.insert(key, value)
+            }
+            Op::Remove => map.remove(&key).is_some(),
+        }
+    })
+}
+
+/// Benchmarks the EBR port of map `M` under NR, STW or EBR, with `--instances` instances of it.
+pub(crate) fn ebr<K: BenchKey, M: ebr::ConcurrentMap<K, Value> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    // Under STW, the EBR ports of NR retire their nodes to the collector.
+    stw::set_enabled(config.mm == MM::STW);
+    let maps = &(0..config.instances).map(|_| M::new()).collect::<Vec<_>>();
+    let len = || maps.iter().map(|map| map.len_approx()).sum();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    // Each instance is prefilled like a map of its own.
+    let build_ops_per_sec = maps
+        .iter()
+        .map(|map| prefill_ebr(config, map, prefill, &mut prefill_history))
+        .sum::<u64>()
+        / maps.len() as u64;
+    audit_prefill(config, len());
+
+    let mut ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        history_start,
+        |_| len(),
+        |_| (),
+        |_, guard, worker| {
+            let tid = worker.tid;
+            worker.map_op(|op, k, rng| {
+                let map = &maps[config.instance(rng)];
+                let key = K::from(config.map_key(k));
+                match op {
+                    Op::Get => profiling::get(|| map.get(&key, guard).is_some()),
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        profiling::insert(|| map.insert(key, value, guard))
+                    }
+                    Op::Remove => profiling::remove(|| config.remove_ebr(map, &key, guard)),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    audit_map(config, len());
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the PEBR port of map `M`, with `--instances` instances of it.
+pub(crate) fn pebr<K: BenchKey, M: pebr::ConcurrentMap<K, Value> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let maps = &(0..config.instances).map(|_| M::new()).collect::<Vec<_>>();
+    let len = || maps.iter().map(|map| map.len_approx()).sum();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    // Each instance is prefilled like a map of its own.
+    let build_ops_per_sec = maps
+        .iter()
+        .map(|map| prefill_pebr(config, map, prefill, &mut prefill_history))
+        .sum::<u64>()
+        / maps.len() as u64;
+    audit_prefill(config, len());
+
+    let mut ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        history_start,
+        len,
+        |_, guard| M::handle(guard),
+        M::clear,
+        |local, worker| {
+            let tid = worker.tid;
+            let Pebr {
+                guard,
+                data: handle,
+                repin,
+                ..
+            } = local;
+            worker.map_op(|op, k, rng| {
+                let map = &maps[config.instance(rng)];
+                let key = K::from(config.map_key(k));
+                match op {
+                    Op::Get => profiling::get(|| map.get(handle, &key, guard).is_some()),
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        repin.write();
+                        profiling::insert(|| map.insert(handle, key, value, guard))
+                    }
+                    Op::Remove => {
+                        repin.write();
+                        profiling::remove(|| map.remove(handle, &key, guard).is_some())
+                    }
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    audit_map(config, len());
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the RC port of map `M`, with `--instances` instances of it.
+#[cfg(feature = "lists")]
+pub(crate) fn rc<K: BenchKey, M: rc::ConcurrentMap<K, Value> + Send + Sync>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let maps = &(0..config.instances).map(|_| M::new()).collect::<Vec<_>>();
+    let len = || maps.iter().map(|map| map.len_approx()).sum();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    // Each instance is prefilled like a map of its own.
+    let build_ops_per_sec = maps
+        .iter()
+        .map(|map| prefill_rc(config, map, prefill, &mut prefill_history))
+        .sum::<u64>()
+        / maps.len() as u64;
+
+    let mut ran = super::run(
+        config,
+        Reclaim::None,
+        history_start,
+        len,
+        |_| (),
+        |_, worker| {
+            let tid = worker.tid;
+            worker.map_op(|op, k, rng| {
+                let map = &maps[config.instance(rng)];
+                let key = K::from(config.map_key(k));
+                match op {
+                    Op::Get => map.get(&key).is_some(),
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        map.insert(key, value)
+                    }
+                    Op::Remove => map.remove(&key).is_some(),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    audit_map(config, len());
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the EBR port of set `S` under NR or EBR.
+pub(crate) fn set_ebr<S: ebr::ConcurrentSet<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let set = &S::new();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill(config, prefill, &mut prefill_history, |op, k| {
+        let key = config.map_key(k);
+        match op {
+            Op::Get => set.contains(&key, guard),
+            Op::Insert => set.insert(key, guard),
+            Op::Remove => set.remove(&key, guard),
+        }
+    });
+
+    let len = |_: &_| set.len_approx();
+    let mut ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        history_start,
+        len,
+        |_| (),
+        |_, guard, worker| {
+            worker.map_op(|op, k, _| {
+                let key = config.map_key(k);
+                match op {
+                    Op::Get => set.contains(&key, guard),
+                    Op::Insert => set.insert(key, guard),
+                    Op::Remove => set.remove(&key, guard),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the PEBR port of set `S`.
+pub(crate) fn set_pebr<S: pebr::ConcurrentSet<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let set = &S::new();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let guard = unsafe { crossbeam_pebr::unprotected() };
+    let mut handle = S::handle(guard);
+    let build_ops_per_sec = super::prefill(config, prefill, &mut prefill_history, |op, k| {
+        let key = config.map_key(k);
+        match op {
+            Op::Get => set.contains(&mut handle, &key, guard),
+            Op::Insert => set.insert(&mut handle, key, guard),
+            Op::Remove => set.remove(&mut handle, &key, guard),
+        }
+    });
+
+    let mut ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        history_start,
+        || set.len_approx(),
+        |_, guard| S::handle(guard),
+        S::clear,
+        |local, worker| {
+            let Pebr {
+                guard,
+                data: handle,
+                repin,
+                ..
+            } = local;
+            worker.map_op(|op, k, _| {
+                let key = config.map_key(k);
+                match op {
+                    Op::Get => set.contains(handle, &key, guard),
+                    Op::Insert => {
+                        repin.write();
+                        set.insert(handle, key, guard)
+                    }
+                    Op::Remove => {
+                        repin.write();
+                        set.remove(handle, &key, guard)
+                    }
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Sets aside with `--audit` the values that the prefill of the maps, which hold `len` entries,
+/// left alive in its garbage. The prefill retires through an unprotected guard, which never frees.
+pub(super) fn audit_prefill(config: &Config, len: usize) {
+    if !config.audit {
+        return;
+    }
+    let leaked = audit::live().saturating_sub(len);
+    if leaked > 0 {
+        println!("audit: the prefill leaked {} values", leaked);
+        audit::leak(leaked);
+    }
+}
+
+/// Checks with `--audit` that the values alive after a run of the maps, once their garbage is
+/// freed, are exactly the `len` entries of the maps. A scheme that never frees the removed values
+/// leaks them instead.
+pub(super) fn audit_map(config: &Config, len: usize) {
+    if !config.audit {
+        return;
+    }
+    let live = audit::live();
+    if config.mm == MM::NR || !config.reclaim {
+        println!(
+            "audit: {} values alive, {} in the map and {} removed and leaked",
+            live,
+            len,
+            live.saturating_sub(len)
+        );
+        return;
+    }
+    assert_eq!(
+        live, len,
+        "audit: {} values are alive, but the map holds {}",
+        live, len
+    );
+    println!("audit: {} values alive, as many as in the map", live);
+}
@@ -0,0 +1,569 @@
+//! The adapters of the structures to the workloads of the driver.
+//!
+//! Each module adapts a kind of structure: it creates and prefills the structure, performs one
+//! operation of its workload at a time, and checks or reports the structure after the run. The
+//! workers, the auxiliary threads and the accounting of a run are the same for every structure,
+//! and live here, in `run`. `run_ebr` and `run_pebr` set up the worker threads of the schemes on
+//! top of it, so that an adapter of the EBR port of a structure writes its operation once for NR,
+//! STW and EBR.
+
+#[cfg(feature = "queues")]
+pub(crate) mod bag;
+#[cfg(feature = "hash-maps")]
+pub(crate) mod bitmap;
+#[cfg(feature = "counters")]
+pub(crate) mod config_map;
+#[cfg(feature = "counters")]
+pub(crate) mod counter;
+#[cfg(feature = "queues")]
+pub(crate) mod deque;
+pub(crate) mod ffi;
+#[cfg(feature = "lists")]
+pub(crate) mod interval_map;
+pub(crate) mod map;
+mod monitor;
+#[cfg(feature = "queues")]
+pub(crate) mod mpsc_queue;
+#[cfg(feature = "queues")]
+pub(crate) mod pipeline;
+#[cfg(feature = "queues")]
+pub(crate) mod priority_queue;
+#[cfg(feature = "queues")]
+pub(crate) mod queue_map;
+pub(crate) mod scan;
+#[cfg(feature = "hash-maps")]
+pub(crate) mod sharded;
+#[cfg(feature = "trees")]
+pub(crate) mod transfer;
+pub(crate) mod unguarded;
+
+use crossbeam_utils::thread::scope;
+use rand::prelude::*;
+use std::cmp::max;
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Barrier};
+use std::time::{Duration, Instant};
+
+use pebr_benchmark::drop_cost;
+use pebr_benchmark::ebr::gc_policy::{Collector, GcPolicy, LocalHandle};
+use pebr_benchmark::op_latency;
+use pebr_benchmark::op_stats;
+use pebr_benchmark::perf_counters;
+use pebr_benchmark::profiling;
+use pebr_benchmark::registry::Prefill;
+use pebr_benchmark::restarts;
+use pebr_benchmark::stw;
+
+use crate::{Config, History, Op, Outcomes, Perf, Repin, Staller, MM};
+
+/// A worker of a run, which is handed over from thread to thread with `--thread-churn`.
+struct Worker<'a> {
+    config: &'a Config,
+    tid: usize,
+    ops: u64,
+    rng: StdRng,
+    history: History,
+    outcomes: Outcomes,
+    /// The operations that found no work.
+    idle: u64,
+    /// What the operations added up, e.g., to a counter.
+    sum: i64,
+}
+
+impl<'a> Worker<'a> {
+    fn new(config: &'a Config, tid: usize, history_start: Instant) -> Self {
+        Self {
+            config,
+            tid,
+            ops: 0,
+            rng: config.rng(tid),
+            history: History::new(config, tid, history_start),
+            outcomes: Outcomes::default(),
+            idle: 0,
+            sum: 0,
+        }
+    }
+
+    /// Performs the next operation of the map workload with `run`, which is given the operation,
+    /// the index of its key and the random number generator of the worker, and returns whether it
+    /// succeeded. Logs the operation in the history and records its outcome. Returns `None` once
+    /// the worker has performed its share of the workload.
+    #[inline]
+    fn map_op(&mut self, run: impl FnOnce(Op, usize, &mut StdRng) -> bool) -> Option<bool> {
+        let (op, k) = self.config.next_op(self.tid, self.ops, &mut self.rng)?;
+        let call = self.history.invoke(k);
+        let ok = run(op, k, &mut self.rng);
+        if let Some(call) = call {
+            self.history.respond(call, op, k, ok);
+        }
+        self.outcomes.record(op, ok);
+        restarts::finish_op();
+        op_latency::finish_op();
+        Some(true)
+    }
+}
+
+/// What a thread of a worker holds across its operations for the reclamation scheme.
+trait Local {
+    /// Called after the `ops`th operation of the worker. The worker is `idle` if the operation
+    /// found no work or the worker has just thought.
+    #[inline]
+    fn after(&mut self, _ops: u64, _idle: bool) {}
+}
+
+impl Local for () {}
+
+/// The local of a scheme and the state of the thread for the structure, e.g., its handle.
+impl<L: Local, S> Local for (L, S) {
+    #[inline]
+    fn after(&mut self, ops: u64, idle: bool) {
+        self.0.after(ops, idle);
+    }
+}
+
+/// A thread of EBR, which holds a guard that it repins every `period` operations and when idle.
+struct Ebr<'c> {
+    /// Always `Some` between the operations.
+    guard: Option<crossbeam_ebr::Guard>,
+    handle: LocalHandle<'c>,
+    period: u64,
+}
+
+impl<'c> Ebr<'c> {
+    fn new(collector: &'c Collector, period: u64) -> Self {
+        let handle = collector.register();
+        Self {
+            guard: Some(handle.pin()),
+            handle,
+            period,
+        }
+    }
+
+    #[inline]
+    fn guard(&self) -> &crossbeam_ebr::Guard {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl Local for Ebr<'_> {
+    #[inline]
+    fn after(&mut self, ops: u64, idle: bool) {
+        let mut guard = self.guard.take().unwrap();
+        if idle {
+            guard = self.handle.idle(guard);
+        }
+        if ops % self.period == 0 {
+            guard = self.handle.repin(guard);
+        }
+        self.guard = Some(guard);
+    }
+}
+
+/// A thread of PEBR, which holds a guard and `data`, e.g., the shields of the structure. Every
+/// `period` operations, it clears `data` and repins, unless `repin` extends a read-only critical
+/// section. An update records itself with `repin.write()`.
+struct Pebr<S> {
+    guard: crossbeam_pebr::Guard,
+    data: S,
+    _handle: crossbeam_pebr::LocalHandle,
+    repin: Repin,
+    clear: fn(&mut S),
+    period: u64,
+}
+
+impl<S> Local for Pebr<S> {
+    #[inline]
+    fn after(&mut self, ops: u64, _idle: bool) {
+        if ops % self.period == 0 && self.repin.due() {
+            (self.clear)(&mut self.data);
+            let guard = &mut self.guard;
+            profiling::pin(|| guard.repin());
+        }
+    }
+}
+
+/// The collector of a run, which the auxiliary thread samples and interferes with.
+#[derive(Clone, Copy)]
+enum Reclaim<'a> {
+    /// NR, STW, RC or a structure that reclaims its memory itself.
+    None,
+    Ebr(&'a Collector),
+    Pebr(&'a crossbeam_pebr::Collector),
+}
+
+/// The result of `run`.
+struct Ran {
+    ops: u64,
+    /// The time of the slowest worker.
+    elapsed: Duration,
+    /// The peak and the average memory usage.
+    mem: (usize, usize),
+    /// The operations, the idle operations and the sums of the workers, in the order of their ids.
+    workers: Vec<(u64, u64, i64)>,
+    /// The histories of the workers, in the order of their ids.
+    histories: Vec<History>,
+}
+
+impl Ran {
+    /// The measurements of the run, whose prefill built the structure at `build_ops_per_sec`.
+    fn perf(&self, config: &Config, build_ops_per_sec: u64) -> Perf {
+        Perf {
+            ops_per_sec: config.throughput(self.ops, self.elapsed),
+            peak_mem: self.mem.0,
+            avg_mem: self.mem.1,
+            build_ops_per_sec,
+        }
+    }
+
+    /// What the operations of the workers added up.
+    #[cfg(feature = "counters")]
+    fn sum(&self) -> i64 {
+        self.workers.iter().map(|w| w.2).sum()
+    }
+
+    /// Checks or writes the histories of the workers after `prefill`, the history of the prefill.
+    fn finish_history(&mut self, config: &Config, prefill: History) {
+        let mut histories = Vec::with_capacity(self.histories.len() + 1);
+        histories.push(prefill);
+        histories.append(&mut self.histories);
+        crate::finish_history(config, histories);
+    }
+}
+
+/// Runs the workers, along with the auxiliary threads, on a structure whose size is `len`. Each
+/// thread of worker `tid` creates its local with `local(tid)`, and the worker performs its
+/// operations with `op`, which returns whether the operation found work, or `None` once the worker
+/// has performed its share of the workload.
+fn run<'a, L: Local>(
+    config: &'a Config,
+    reclaim: Reclaim<'_>,
+    history_start: Instant,
+    len: impl Fn() -> usize + Sync,
+    local: impl Fn(usize) -> L + Sync,
+    op: impl Fn(&mut L, &mut Worker<'a>) -> Option<bool> + Sync,
+) -> Ran {
+    let (len, local, op) = (&len, &local, &op);
+    let barrier = &Barrier::new(config.parties());
+    let (worker_sender, worker_receiver) = mpsc::channel();
+    let (mem_sender, mem_receiver) = mpsc::channel();
+    let finished = &AtomicUsize::new(0);
+    let staller = &Staller::new(config);
+
+    scope(|s| {
+        if config.stall_threads > 0 {
+            s.spawn(move |_| staller.run(config, finished));
+        }
+        if config.mm == MM::STW {
+            s.spawn(move |_| monitor::collect_stw(config, finished));
+        }
+        if config.progress.is_some() {
+            s.spawn(move |_| monitor::report_progress(config, barrier, finished));
+        }
+        if config.time_series.is_some() {
+            s.spawn(move |_| monitor::record_time_series(config, barrier, finished, len));
+        }
+        // sampling & interference thread
+        if config.aux_thread > 0 {
+            let mem_sender = mem_sender.clone();
+            s.spawn(move |_| {
+                let mem = match reclaim {
+                    Reclaim::None => monitor::sample_mem_nr(config, barrier, finished),
+                    Reclaim::Ebr(collector) => {
+                        monitor::aux_ebr(config, collector, barrier, finished)
+                    }
+                    Reclaim::Pebr(collector) => {
+                        monitor::aux_pebr(config, collector, barrier, finished)
+                    }
+                };
+                mem_sender.send(mem).unwrap();
+            });
+        } else {
+            mem_sender.send((0, 0)).unwrap();
+        }
+
+        for tid in 0..config.threads {
+            let worker_sender = worker_sender.clone();
+            s.spawn(move |_| {
+                let worker = Worker::new(config, tid, history_start);
+                config.pin(tid);
+                staller.register();
+                barrier.wait();
+                let start = Instant::now();
+                perf_counters::start();
+
+                let worker = run_churned(config, tid, worker, |mut worker, deadline| {
+                    let mut local = local(tid);
+                    while config.running(start, worker.ops)
+                        && !worker.history.done()
+                        && before(deadline)
+                    {
+                        let found = match op(&mut local, &mut worker) {
+                            Some(found) => found,
+                            None => break,
+                        };
+                        worker.ops += 1;
+                        worker.idle += !found as u64;
+                        config.publish_progress(tid, worker.ops);
+                        let thought = config.think_time.think(&mut worker.rng);
+                        local.after(worker.ops, thought || !found);
+                    }
+                    drop_cost::finish_worker();
+                    worker
+                });
+
+                let elapsed = start.elapsed();
+                config.record_group(tid, worker.ops, elapsed);
+                config.outcomes.lock().unwrap().merge(&worker.outcomes);
+                perf_counters::stop(worker.ops);
+                config.record_cpu(tid, worker.ops, elapsed);
+                finished.fetch_add(1, Ordering::Release);
+                worker_sender.send((worker, elapsed)).unwrap();
+                staller.leave();
+            });
+        }
+    })
+    .unwrap();
+    println!("end");
+    staller.report();
+    report_churn(config);
+    if config.mm == MM::STW {
+        // The workers have exited and moved the objects they retired to the global list.
+        stw::collect();
+        stw::set_enabled(false);
+        monitor::report_stw();
+    }
+
+    let mut workers = worker_receiver.try_iter().collect::<Vec<_>>();
+    workers.sort_by_key(|(worker, _)| worker.tid);
+    let mut ran = Ran {
+        ops: 0,
+        elapsed: Duration::default(),
+        mem: mem_receiver.recv().unwrap(),
+        workers: Vec::with_capacity(workers.len()),
+        histories: Vec::with_capacity(workers.len()),
+    };
+    for (worker, elapsed) in workers {
+        ran.ops += worker.ops;
+        ran.elapsed = max(ran.elapsed, elapsed);
+        ran.workers.push((worker.ops, worker.idle, worker.sum));
+        ran.histories.push(worker.history);
+    }
+    ran
+}
+
+/// Runs the workers on the EBR port of a structure under the memory manager of the configuration:
+/// NR, STW or EBR, repinning every `period` operations. The size of the structure is `len`, given a guard. The state of each thread of
+/// worker `tid` for the structure is `state(tid)`, and the worker performs its operations with `op`
+/// (see `run`), given the state and a guard.
+fn run_ebr<'a, S>(
+    config: &'a Config,
+    period: u64,
+    history_start: Instant,
+    len: impl Fn(&crossbeam_ebr::Guard) -> usize + Sync,
+    state: impl Fn(usize) -> S + Sync,
+    op: impl Fn(&mut S, &crossbeam_ebr::Guard, &mut Worker<'a>) -> Option<bool> + Sync,
+) -> Ran {
+    match config.mm {
+        MM::NR | MM::STW => run(
+            config,
+            Reclaim::None,
+            history_start,
+            || len(unsafe { crossbeam_ebr::leaking() }),
+            |tid| ((), state(tid)),
+            |(_, state), worker| {
+                // The collector of STW waits for the operation.
+                let _reading = stw::read();
+                op(state, unsafe { crossbeam_ebr::leaking() }, worker)
+            },
+        ),
+        MM::EBR => {
+            let collector = &Collector::new(config.gc_policy, config.gc_threshold);
+            let ran = run(
+                config,
+                Reclaim::Ebr(collector),
+                history_start,
+                || len(&collector.register().pin()),
+                |tid| (Ebr::new(collector, period), state(tid)),
+                |(ebr, state), worker| op(state, ebr.guard(), worker),
+            );
+            if config.gc_policy != GcPolicy::Lazy {
+                println!("gc: {} flushes", collector.flushes());
+            }
+            // The garbage is freed with the collector.
+            ran
+        }
+        MM::PEBR | MM::RC => unreachable!("checked by setup"),
+    }
+}
+
+/// Runs the workers on the PEBR port of a structure, repinning every `period` operations. The data of each thread of worker `tid` is
+/// `data(tid, guard)`, which `clear` clears before the thread repins, and the worker performs its
+/// operations with `op` (see `run`).
+fn run_pebr<'a, S>(
+    config: &'a Config,
+    period: u64,
+    history_start: Instant,
+    len: impl Fn() -> usize + Sync,
+    data: impl Fn(usize, &crossbeam_pebr::Guard) -> S + Sync,
+    clear: fn(&mut S),
+    op: impl Fn(&mut Pebr<S>, &mut Worker<'a>) -> Option<bool> + Sync,
+) -> Ran {
+    let collector = &crossbeam_pebr::Collector::new();
+    // The garbage is freed with the collector.
+    run(
+        config,
+        Reclaim::Pebr(collector),
+        history_start,
+        len,
+        |tid| {
+            let handle = collector.register();
+            let data = data(tid, &handle.pin());
+            Pebr {
+                guard: handle.pin(),
+                data,
+                _handle: handle,
+                repin: Repin::new(config),
+                clear,
+                period,
+            }
+        },
+        op,
+    )
+}
+
+/// Prefills a map with `run`, which performs an operation on the key of an index and returns
+/// whether it succeeded, and logs the operations in `history`. Loads the trace of `--ycsb`, or
+/// inserts the keys of the prefill in the order of `prefill`. Returns the throughput of the prefill
+/// (`report_build`).
+fn prefill(
+    config: &Config,
+    prefill: Prefill,
+    history: &mut History,
+    mut run: impl FnMut(Op, usize) -> bool,
+) -> u64 {
+    let mut logged = |op, k| {
+        let call = history.invoke(k);
+        let ok = run(op, k);
+        if let Some(call) = call {
+            history.respond(call, op, k, ok);
+        }
+    };
+    let mut rng = config.rng(config.threads);
+    let start;
+    if let Some(trace) = &config.ycsb {
+        start = Instant::now();
+        for &(op, k) in &trace.load {
+            logged(Op::from(op), k);
+        }
+    } else {
+        match prefill {
+            Prefill::Random => {
+                start = Instant::now();
+                for i in 0..config.prefill {
+                    logged(Op::Insert, config.prefill_key(i, &mut rng));
+                }
+            }
+            Prefill::Decreasing => {
+                let mut keys = Vec::with_capacity(config.prefill);
+                for i in 0..config.prefill {
+                    keys.push(config.prefill_key(i, &mut rng));
+                }
+                keys.sort_by(|a, b| b.cmp(a));
+                start = Instant::now();
+                for k in keys {
+                    logged(Op::Insert, k);
+                }
+            }
+        }
+    }
+    report_build(config, start)
+}
+
+/// Prefills a structure other than a map with `add`, which adds the element of an index, given the
+/// random number generator of the prefill. Returns the throughput of the prefill (`report_build`).
+#[cfg(any(feature = "lists", feature = "hash-maps", feature = "queues"))]
+fn prefill_elements(config: &Config, mut add: impl FnMut(usize, &mut StdRng)) -> u64 {
+    let mut rng = config.rng(config.threads);
+    let start = Instant::now();
+    for i in 0..config.prefill {
+        add(i, &mut rng);
+    }
+    report_build(config, start)
+}
+
+/// Prints and returns the throughput of the prefill of `config.prefill` elements, which started at
+/// `start`, or 0 for an empty prefill.
+///
+/// The prefill inserts on a single thread before the workers start, and retires nothing, so the
+/// figure is the cost of building the structure, about the same under every scheme, and not a
+/// measure of the scheme.
+fn report_build(config: &Config, start: Instant) -> u64 {
+    let elapsed = start.elapsed().as_secs_f64();
+    let build_ops_per_sec = if config.prefill == 0 || elapsed == 0.0 {
+        0
+    } else {
+        (config.prefill as f64 / elapsed) as u64
+    };
+    print!("prefilled ({} ops/s)... ", build_ops_per_sec);
+    stdout().flush().unwrap();
+    build_ops_per_sec
+}
+
+/// The worker threads replaced by new ones with `--thread-churn`.
+static CHURNED: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs the operation loop of worker `tid`, `run`, from `state`. With `--thread-churn`, the loop
+/// runs on a succession of threads, each of which runs it until the end of its period and exits,
+/// handing the state over to the next one. Each thread is set up like the worker (`Config::pin`)
+/// and hands over its counts of `op_stats` too, so that the worker takes the counts of all of them.
+/// `run` is given the end of its period, if any, and returns the state. The worker is done once a
+/// thread returns before the end of its period.
+fn run_churned<S: Send>(
+    config: &Config,
+    tid: usize,
+    mut state: S,
+    run: impl Fn(S, Option<Instant>) -> S + Sync,
+) -> S {
+    let period = match config.thread_churn {
+        Some(period) => period,
+        None => return run(state, None),
+    };
+    let run = &run;
+    let mut counts = op_stats::take_local();
+    loop {
+        let deadline = Instant::now() + period;
+        let (next, taken) = scope(|s| {
+            s.spawn(move |_| {
+                config.pin(tid);
+                op_stats::add_local(counts);
+                let state = run(state, Some(deadline));
+                (state, op_stats::take_local())
+            })
+            .join()
+            .unwrap()
+        })
+        .unwrap();
+        state = next;
+        counts = taken;
+        if Instant::now() < deadline {
+            op_stats::add_local(counts);
+            return state;
+        }
+        CHURNED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Whether the current thread of a worker may go on, before `deadline` of `--thread-churn`.
+#[inline]
+fn before(deadline: Option<Instant>) -> bool {
+    deadline.map_or(true, |d| Instant::now() < d)
+}
+
+/// Reports the worker threads replaced with `--thread-churn`.
+fn report_churn(config: &Config) {
+    let churned = CHURNED.swap(0, Ordering::Relaxed);
+    if config.thread_churn.is_some() {
+        println!("thread churn: {} threads replaced", churned);
+    }
+}
@@ -0,0 +1,284 @@
+//! The auxiliary threads of a run, which report on the workers and the memory usage, and interfere
+//! with the workers.
+
+use std::cmp::max;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pebr_benchmark::ebr::gc_policy::Collector;
+use pebr_benchmark::epoch_latency;
+use pebr_benchmark::progress::Snapshot;
+use pebr_benchmark::stw;
+
+use crate::{failed, interrupted, Config, PROGRESS_PERIOD};
+
+/// Samples the memory usage until the end of the benchmark. Returns the peak and the average.
+/// Prints the progress of the workers every `PROGRESS_PERIOD` until they finish.
+pub(super) fn report_progress(config: &Config, barrier: &Barrier, finished: &AtomicUsize) {
+    let progress = config.progress.as_ref().unwrap();
+    progress.reset();
+    barrier.wait();
+
+    // The view and the last counters of each worker.
+    #[cfg(feature = "tui")]
+    let mut tui = if config.tui {
+        let title = format!("{}: {}, {} threads", config.ds, config.mm, config.threads);
+        let workers = vec![Snapshot::default(); progress.workers()];
+        Some((pebr_benchmark::tui::Tui::new(title), workers))
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    let mut last = (start, Snapshot::default());
+    loop {
+        let next = last.0 + PROGRESS_PERIOD;
+        while Instant::now() < next && config.aux_running(start, finished) {
+            std::thread::sleep(config.aux_thread_period);
+        }
+        if !config.aux_running(start, finished) {
+            break;
+        }
+        let now = (Instant::now(), progress.total());
+        let rate = |ops: u64, elapsed: Duration| (ops as f64 / elapsed.as_secs_f64()) as u64;
+        #[cfg(feature = "tui")]
+        {
+            if let Some((tui, last_workers)) = &mut tui {
+                let workers = (0..progress.workers())
+                    .map(|tid| progress.read(tid))
+                    .collect::<Vec<_>>();
+                let frame = pebr_benchmark::tui::Frame {
+                    elapsed: now.0 - start,
+                    ops_per_sec: rate(now.1.ops - last.1.ops, now.0 - last.0),
+                    overall_ops_per_sec: rate(now.1.ops, now.0 - start),
+                    retired: now.1.retires,
+                    retired_per_sec: rate(now.1.retires - last.1.retires, now.0 - last.0),
+                    mem: config.mem_sampler.sample(),
+                    workers: workers
+                        .iter()
+                        .zip(last_workers.iter())
+                        .map(|(w, l)| rate(w.ops - l.ops, now.0 - last.0))
+                        .collect(),
+                };
+                // The view is best effort: a closed terminal doesn't stop the run.
+                let _ = tui.draw(&frame);
+                *last_workers = workers;
+                last = now;
+                continue;
+            }
+        }
+        println!(
+            "progress: {:.1}s, ops/s: {} (overall {}), retired: {}, mem: {}",
+            (now.0 - start).as_secs_f64(),
+            rate(now.1.ops - last.1.ops, now.0 - last.0),
+            rate(now.1.ops, now.0 - start),
+            now.1.retires,
+            config.mem_sampler.sample()
+        );
+        last = now;
+    }
+}
+
+/// Samples the memory usage until the end of the benchmark. Returns the peak and the average.
+pub(super) fn sample_mem_nr(
+    config: &Config,
+    barrier: &Barrier,
+    finished: &AtomicUsize,
+) -> (usize, usize) {
+    assert!(config.sampling);
+    let mut samples = 0usize;
+    let mut acc = 0usize;
+    let mut peak = 0usize;
+    barrier.wait();
+
+    let start = Instant::now();
+    let mut next_sampling = start + config.sampling_period;
+    while config.aux_running(start, finished) {
+        let now = Instant::now();
+        if now > next_sampling {
+            let allocated = config.mem_sampler.sample();
+            samples += 1;
+            acc += allocated;
+            peak = max(peak, allocated);
+            next_sampling = now + config.sampling_period;
+        }
+        std::thread::sleep(config.aux_thread_period);
+    }
+    (peak, acc / samples)
+}
+
+/// Records the approximate size of the structure, the memory usage and, if the workers publish
+/// their progress, the number of retired nodes every `time_series_period` until the workers
+/// finish, and writes them to the time series file.
+pub(super) fn record_time_series(
+    config: &Config,
+    barrier: &Barrier,
+    finished: &AtomicUsize,
+    len: impl Fn() -> usize,
+) {
+    barrier.wait();
+
+    let start = Instant::now();
+    let mut rows = Vec::new();
+    loop {
+        let retired = config.progress.as_ref().map(|p| p.total().retires);
+        let (len, allocated) = (len(), config.mem_sampler.sample());
+        if let Some(storm) = &config.storm {
+            storm.sample(len, allocated);
+        }
+        rows.push((start.elapsed(), len, allocated, retired));
+        if !config.aux_running(start, finished) {
+            break;
+        }
+        std::thread::sleep(config.time_series_period);
+    }
+
+    let path = config.time_series.as_ref().unwrap();
+    let mut output = csv::Writer::from_path(path).unwrap();
+    output
+        .write_record(&["time_ms", "len", "allocated", "retired"])
+        .unwrap();
+    for (time, len, allocated, retired) in rows {
+        output
+            .write_record(&[
+                time.as_millis().to_string(),
+                len.to_string(),
+                allocated.to_string(),
+                retired.map_or("".to_string(), |r| r.to_string()),
+            ])
+            .unwrap();
+    }
+    output.flush().unwrap();
+}
+
+/// Samples the memory usage and interferes with the workers by holding a guard that is repinned
+/// every `non_coop_period`. Returns the peak and the average memory usage.
+pub(super) fn aux_ebr(
+    config: &Config,
+    collector: &Collector,
+    barrier: &Barrier,
+    finished: &AtomicUsize,
+) -> (usize, usize) {
+    let mut samples = 0usize;
+    let mut acc = 0usize;
+    let mut peak = 0usize;
+    let handle = collector.register();
+    barrier.wait();
+
+    let start = Instant::now();
+    // Immediately drop if no non-coop else keep it and repin periodically.
+    let mut guard = ManuallyDrop::new(handle.pin());
+    if config.non_coop == 0 {
+        unsafe { ManuallyDrop::drop(&mut guard) };
+    }
+    let mut next_sampling = start + config.sampling_period;
+    let mut next_repin = start + config.non_coop_period;
+    let mut next_probe = start + epoch_latency::PROBE_PERIOD;
+    while config.aux_running(start, finished) {
+        let now = Instant::now();
+        if now > next_sampling {
+            let allocated = config.mem_sampler.sample();
+            samples += 1;
+            acc += allocated;
+            peak = max(peak, allocated);
+            next_sampling = now + config.sampling_period;
+        }
+        if now > next_repin {
+            (*guard).repin();
+            next_repin = now + config.non_coop_period;
+        }
+        if config.epoch_latency && now > next_probe {
+            epoch_latency::ebr::probe(&handle.pin());
+            next_probe = now + epoch_latency::PROBE_PERIOD;
+        }
+        std::thread::sleep(config.aux_thread_period);
+    }
+
+    if config.non_coop > 0 {
+        unsafe { ManuallyDrop::drop(&mut guard) };
+    }
+
+    if config.sampling {
+        (peak, acc / samples)
+    } else {
+        (0, 0)
+    }
+}
+
+/// Samples the memory usage and interferes with the workers by holding a guard that is repinned
+/// every `non_coop_period`. Returns the peak and the average memory usage.
+pub(super) fn aux_pebr(
+    config: &Config,
+    collector: &crossbeam_pebr::Collector,
+    barrier: &Barrier,
+    finished: &AtomicUsize,
+) -> (usize, usize) {
+    let mut samples = 0usize;
+    let mut acc = 0usize;
+    let mut peak = 0usize;
+    let handle = collector.register();
+    barrier.wait();
+
+    let start = Instant::now();
+    // Immediately drop if no non-coop else keep it and repin periodically.
+    let mut guard = ManuallyDrop::new(handle.pin());
+    if config.non_coop == 0 {
+        unsafe { ManuallyDrop::drop(&mut guard) };
+    }
+    let mut next_sampling = start + config.sampling_period;
+    let mut next_repin = start + config.non_coop_period;
+    let mut next_probe = start + epoch_latency::PROBE_PERIOD;
+    while config.aux_running(start, finished) {
+        let now = Instant::now();
+        if now > next_sampling {
+            let allocated = config.mem_sampler.sample();
+            samples += 1;
+            acc += allocated;
+            peak = max(peak, allocated);
+            next_sampling = now + config.sampling_period;
+        }
+        if now > next_repin {
+            (*guard).repin();
+            next_repin = now + config.non_coop_period;
+        }
+        if config.epoch_latency && now > next_probe {
+            epoch_latency::pebr::probe(&handle.pin());
+            next_probe = now + epoch_latency::PROBE_PERIOD;
+        }
+        std::thread::sleep(config.aux_thread_period);
+    }
+
+    if config.non_coop > 0 {
+        unsafe { ManuallyDrop::drop(&mut guard) };
+    }
+
+    if config.sampling {
+        (peak, acc / samples)
+    } else {
+        (0, 0)
+    }
+}
+
+/// Collects the garbage of `-m STW` every `stw::PERIOD` until the workers finish.
+pub(super) fn collect_stw(config: &Config, finished: &AtomicUsize) {
+    while finished.load(Ordering::Acquire) < config.threads && !interrupted() && !failed() {
+        thread::sleep(stw::PERIOD);
+        stw::collect();
+    }
+}
+
+/// Prints the collections of `-m STW` and the pauses they imposed on the workers.
+pub(super) fn report_stw() {
+    let stats = stw::take();
+    let collections = max(stats.collections, 1) as f64;
+    println!(
+        "stw: {} collections, {} objects freed, pause: mean {:.1} us, max {:.1} us",
+        stats.collections,
+        stats.freed,
+        stats.pause_nanos as f64 / collections / 1000.0,
+        stats.max_pause_nanos as f64 / 1000.0
+    );
+}
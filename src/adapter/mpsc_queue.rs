@@ -0,0 +1,114 @@
+//! The MPSC queue workload: worker 0 is the consumer and the others are producers. Each producer
+//! owns a pool of nodes, which is also an MPSC queue. A producer moves a node from its pool to the
+//! queue, and the consumer moves a node from the queue back to the pool of its owner, so that no node
+//! is allocated during the run. An operation is a single attempt to move a node.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::pebr;
+
+use super::Pebr;
+use crate::{Config, Perf};
+
+/// Benchmarks `MpscQueue` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let queue = &ebr::MpscQueue::new();
+    let pools = &(0..config.threads - 1)
+        .map(|_| ebr::MpscQueue::new())
+        .collect::<Vec<_>>();
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |i, rng| {
+        let owner = i % pools.len();
+        let node = ebr::mpsc_queue::Node::new((owner, config.key_dist.sample(rng)));
+        pools[owner].push(node, guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| queue.len_approx(),
+        // The consumer pops from the queue, and a producer pops from its pool.
+        |tid| {
+            if tid == 0 {
+                queue.consumer()
+            } else {
+                pools[tid - 1].consumer()
+            }
+            .unwrap()
+        },
+        |consumer, guard, worker| {
+            let popped = consumer.pop(guard);
+            let found = popped.is_some();
+            if let Some(mut node) = popped {
+                if worker.tid == 0 {
+                    let owner = node.value().0;
+                    pools[owner].push(node, guard);
+                } else {
+                    node.value_mut().1 = config.key_dist.sample(&mut worker.rng);
+                    queue.push(node, guard);
+                }
+            }
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the PEBR port of `MpscQueue`.
+pub(crate) fn pebr<N: Unsigned>(config: &Config) -> Perf {
+    let queue = &pebr::MpscQueue::new();
+    let pools = &(0..config.threads - 1)
+        .map(|_| pebr::MpscQueue::new())
+        .collect::<Vec<_>>();
+    let guard = unsafe { crossbeam_pebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |i, rng| {
+        let owner = i % pools.len();
+        let node = pebr::mpsc_queue::Node::new((owner, config.key_dist.sample(rng)));
+        pools[owner].push(node, guard);
+    });
+
+    let ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        || queue.len_approx(),
+        // The consumer pops from the queue, and a producer pops from its pool.
+        |tid, _| {
+            if tid == 0 {
+                queue.consumer()
+            } else {
+                pools[tid - 1].consumer()
+            }
+            .unwrap()
+        },
+        // The consumer holds no shields.
+        |_| (),
+        |local, worker| {
+            let Pebr {
+                guard,
+                data: consumer,
+                repin,
+                ..
+            } = local;
+            // The queue repins every batch.
+            repin.write();
+            let popped = consumer.pop(guard);
+            let found = popped.is_some();
+            if let Some(mut node) = popped {
+                if worker.tid == 0 {
+                    let owner = node.value().0;
+                    pools[owner].push(node, guard);
+                } else {
+                    node.value_mut().1 = config.key_dist.sample(&mut worker.rng);
+                    queue.push(node, guard);
+                }
+            }
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,119 @@
+//! The pipeline workload: the workers are divided into stages that pass the values around a ring
+//! of queues or stacks.
+
+use rand::prelude::*;
+use std::cmp::max;
+use std::time::{Duration, Instant};
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Perf};
+
+/// A structure that the stages of `Pipeline` pass the values through.
+pub(crate) trait Channel: Send + Sync {
+    fn new() -> Self;
+    fn push(&self, value: String, guard: &crossbeam_ebr::Guard);
+    fn try_pop(&self, guard: &crossbeam_ebr::Guard) -> Option<String>;
+}
+
+#[cfg(feature = "queues")]
+impl Channel for ebr::Deque<String> {
+    fn new() -> Self {
+        ebr::ConcurrentQueue::new()
+    }
+
+    #[inline]
+    fn push(&self, value: String, guard: &crossbeam_ebr::Guard) {
+        ebr::ConcurrentQueue::push(self, value, guard);
+    }
+
+    #[inline]
+    fn try_pop(&self, guard: &crossbeam_ebr::Guard) -> Option<String> {
+        ebr::ConcurrentQueue::try_pop(self, guard)
+    }
+}
+
+#[cfg(feature = "queues")]
+impl Channel for ebr::bag::Stack<String> {
+    fn new() -> Self {
+        ebr::ConcurrentStack::new()
+    }
+
+    #[inline]
+    fn push(&self, value: String, guard: &crossbeam_ebr::Guard) {
+        ebr::ConcurrentStack::push(self, value, guard);
+    }
+
+    #[inline]
+    fn try_pop(&self, guard: &crossbeam_ebr::Guard) -> Option<String> {
+        ebr::ConcurrentStack::try_pop(self, guard)
+    }
+}
+
+/// One transfer of worker `tid`, whose stage pops a value from its channel and pushes it to the
+/// channel of the next stage, the last stage to the first. The node of the value is thus retired
+/// by a thread of another stage than the one that allocated it. Returns `false` if the channel was
+/// empty.
+#[inline]
+fn pipeline_op<C: Channel>(
+    config: &Config,
+    channels: &[C],
+    tid: usize,
+    guard: &crossbeam_ebr::Guard,
+) -> bool {
+    let stage = tid % config.stages;
+    match channels[stage].try_pop(guard) {
+        Some(value) => {
+            channels[(stage + 1) % config.stages].push(value, guard);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Prints the throughput of each stage of `Pipeline` and how often its pops found no value.
+fn print_pipeline_stats(config: &Config, stats: &[(u64, u64)], elapsed: Duration) {
+    for (stage, (ops, empty)) in stats.iter().enumerate() {
+        println!(
+            "pipeline: stage {}: {} transfers/s, {:.2}% of the pops found the {} empty",
+            stage,
+            config.throughput(ops - empty, elapsed),
+            100.0 * *empty as f64 / max(*ops, 1) as f64,
+            if config.pipeline_stack {
+                "stack"
+            } else {
+                "queue"
+            }
+        );
+    }
+}
+
+/// Benchmarks `Pipeline` on channels `C` under NR or EBR.
+pub(crate) fn ebr<C: Channel, N: Unsigned>(config: &Config) -> Perf {
+    // The channels of the stages are prefilled in turn.
+    let channels = &(0..config.stages).map(|_| C::new()).collect::<Vec<_>>();
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |i, rng| {
+        let value = config.key_dist.sample(rng).to_string();
+        channels[i % config.stages].push(value, guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        // The transfers keep the number of values in the channels.
+        |_| config.prefill,
+        |_| (),
+        |_, guard, worker| Some(pipeline_op(config, channels, worker.tid, guard)),
+    );
+    let mut stats = vec![(0, 0); config.stages];
+    for (tid, &(ops, empty, _)) in ran.workers.iter().enumerate() {
+        let stage = &mut stats[tid % config.stages];
+        stage.0 += ops;
+        stage.1 += empty;
+    }
+    print_pipeline_stats(config, &stats, ran.elapsed);
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,84 @@
+//! The priority queue workload: each operation either inserts a random key or removes the smallest
+//! one with equal probability.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::pebr;
+
+use super::Pebr;
+use crate::{Config, Perf};
+
+/// Benchmarks the EBR port of priority queue `Q` under NR or EBR.
+pub(crate) fn ebr<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+) -> Perf {
+    let queue = &Q::new();
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let key = config.key_dist.sample(rng);
+        queue.insert(key, key.to_string(), guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| queue.len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            let found = if worker.rng.gen() {
+                let key = config.key_dist.sample(&mut worker.rng);
+                queue.insert(key, key.to_string(), guard);
+                true
+            } else {
+                queue.try_remove_min(guard).is_some()
+            };
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Benchmarks the PEBR port of priority queue `Q`.
+pub(crate) fn pebr<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+) -> Perf {
+    let queue = &Q::new();
+    let guard = unsafe { crossbeam_pebr::unprotected() };
+    let mut handle = queue.handle(guard);
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let key = config.key_dist.sample(rng);
+        queue.insert(&mut handle, key, key.to_string(), guard);
+    });
+
+    let ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        || queue.len_approx(),
+        |_, guard| queue.handle(guard),
+        Q::clear,
+        |local, worker| {
+            let Pebr {
+                guard,
+                data: handle,
+                repin,
+                ..
+            } = local;
+            // The queue repins every batch.
+            repin.write();
+            let found = if worker.rng.gen() {
+                let key = config.key_dist.sample(&mut worker.rng);
+                queue.insert(handle, key, key.to_string(), guard);
+                true
+            } else {
+                queue.try_remove_min(handle, guard).is_some()
+            };
+            Some(found)
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,62 @@
+//! The map of queues workload: an insert appends to the queue of its key, a get pops from it, and a
+//! remove deletes the key and retires its whole queue.
+
+use rand::prelude::*;
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+
+use crate::{Config, Op, Perf};
+
+const QUEUE_MAP_BUCKETS: usize = 30000;
+
+/// One operation of the workload of `QueueMap`: an insert appends to the queue of the key, a get
+/// pops from it, and a remove deletes the key with its queue. Returns `false` if the operation
+/// found no work, i.e., it popped from an absent or empty queue.
+fn queue_map_op<R: Rng>(
+    config: &Config,
+    map: &ebr::QueueMap<String, ebr::Deque<String>>,
+    tid: usize,
+    rng: &mut R,
+    guard: &crossbeam_ebr::Guard,
+) -> bool {
+    let op = Op::OPS[config.op_dist(tid).sample(rng)];
+    let key = config.key_dist.sample(rng).to_string();
+    match op {
+        Op::Get => return map.pop(&key, guard).is_some(),
+        Op::Insert => map.append(&key, key.clone(), guard),
+        Op::Remove => {
+            map.delete(&key, guard);
+        }
+    }
+    true
+}
+
+/// Benchmarks `QueueMap` under NR or EBR.
+pub(crate) fn ebr<N: Unsigned>(config: &Config) -> Perf {
+    let map = &ebr::QueueMap::with_capacity(QUEUE_MAP_BUCKETS);
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let build_ops_per_sec = super::prefill_elements(config, |_, rng| {
+        let key = config.key_dist.sample(rng).to_string();
+        map.append(&key, key.clone(), guard);
+    });
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        Instant::now(),
+        |_| map.len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            Some(queue_map_op(
+                config,
+                map,
+                worker.tid,
+                &mut worker.rng,
+                guard,
+            ))
+        },
+    );
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,118 @@
+//! The map workload with `--scan`, whose gets are scans of the ordered maps.
+
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::pebr;
+use pebr_benchmark::registry::Prefill;
+
+use super::map::{prefill_ebr, prefill_pebr};
+use super::Pebr;
+use crate::{Config, History, Op, Perf};
+
+/// Like `map::ebr`, but the gets are scans.
+pub(crate) fn ebr<M: ebr::OrderedMap<String, String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let map = &M::new();
+    let len = config.scan.unwrap();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let build_ops_per_sec = prefill_ebr(config, map, prefill, &mut prefill_history);
+
+    let size = |_: &_| map.len_approx();
+    let mut ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        history_start,
+        size,
+        |_| (),
+        |_, guard, worker| {
+            let tid = worker.tid;
+            worker.map_op(|op, k, _| {
+                let key = config.map_key(k);
+                match op {
+                    Op::Get => {
+                        let mut steps = 0;
+                        let mut key = key;
+                        while let Some((next, _)) = map.next(&key, guard) {
+                            key = next.clone();
+                            steps += 1;
+                            if steps == len {
+                                break;
+                            }
+                        }
+                        steps > 0
+                    }
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        map.insert(key, value, guard)
+                    }
+                    Op::Remove => map.remove(&key, guard).is_some(),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
+
+/// Like `map::pebr`, but the gets are scans.
+pub(crate) fn pebr<M: pebr::OrderedMap<String, String> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let map = &M::new();
+    let len = config.scan.unwrap();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let build_ops_per_sec = prefill_pebr(config, map, prefill, &mut prefill_history);
+
+    let mut ran = super::run_pebr(
+        config,
+        N::to_u64(),
+        history_start,
+        || map.len_approx(),
+        |_, guard| M::handle(guard),
+        M::clear,
+        |local, worker| {
+            let tid = worker.tid;
+            let Pebr {
+                guard,
+                data: handle,
+                repin,
+                ..
+            } = local;
+            worker.map_op(|op, k, _| {
+                let key = config.map_key(k);
+                match op {
+                    Op::Get => {
+                        let mut steps = 0;
+                        let mut key = key;
+                        while let Some((next, _)) = map.next(handle, &key, guard) {
+                            key = next.clone();
+                            steps += 1;
+                            if steps == len {
+                                break;
+                            }
+                        }
+                        steps > 0
+                    }
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        repin.write();
+                        map.insert(handle, key, value, guard)
+                    }
+                    Op::Remove => {
+                        repin.write();
+                        map.remove(handle, &key, guard).is_some()
+                    }
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
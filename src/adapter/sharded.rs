@@ -0,0 +1,58 @@
+//! The map workload on `ShardedHashMap`.
+
+use std::hash::Hash;
+use std::time::Instant;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::ebr::gc_policy::Collector;
+use pebr_benchmark::registry::Prefill;
+
+use super::Reclaim;
+use crate::{BenchKey, Config, History, Op, Perf};
+
+/// Benchmarks `ShardedHashMap`, whose operations pin the collectors of the shards themselves.
+pub(crate) fn ebr<K: BenchKey + Ord + Hash + Send + Sync>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let map = &ebr::ShardedHashMap::<K, String>::with_shards(config.shards);
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let handle = &map.handle();
+    let build_ops_per_sec = super::prefill(config, prefill, &mut prefill_history, |op, k| {
+        let key = K::from(config.map_key(k));
+        match op {
+            Op::Get => map.get(handle, &key, |_| ()).is_some(),
+            Op::Insert => {
+                let value = key.to_string();
+                map.insert(handle, key, value)
+            }
+            Op::Remove => map.remove(handle, &key).is_some(),
+        }
+    });
+
+    let collector = &Collector::new(config.gc_policy, config.gc_threshold);
+    let mut ran = super::run(
+        config,
+        Reclaim::Ebr(collector),
+        history_start,
+        || map.len_approx(),
+        |_| ((), map.handle()),
+        |(_, handle), worker| {
+            let tid = worker.tid;
+            worker.map_op(|op, k, _| {
+                let key = K::from(config.map_key(k));
+                match op {
+                    Op::Get => map.get(handle, &key, |_| ()).is_some(),
+                    Op::Insert => {
+                        let value = config.map_value(tid, &key);
+                        map.insert(handle, key, value)
+                    }
+                    Op::Remove => map.remove(handle, &key).is_some(),
+                }
+            })
+        },
+    );
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
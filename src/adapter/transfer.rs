@@ -0,0 +1,70 @@
+//! The transfer workload (`--transfer`), which moves keys between two maps.
+
+use std::time::Instant;
+use typenum::Unsigned;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::registry::Prefill;
+
+use super::map::prefill_ebr;
+use crate::{Config, History, Op, Perf, Value};
+
+/// Performs an operation of the transfer workload: a get looks the key up in the first map and
+/// then in the second, an insert moves the key from the first map to the second, and a remove
+/// moves it back. Returns whether it found or moved the key. A get may miss a key that moves back
+/// meanwhile.
+// The maps take a `&K`, which is a `&String`.
+#[allow(clippy::ptr_arg)]
+fn transfer_op<M: ebr::TransferMap<String, Value>>(
+    maps: &[M; 2],
+    op: Op,
+    key: &String,
+    guard: &crossbeam_ebr::Guard,
+) -> bool {
+    match op {
+        Op::Get => maps[0].get(key, guard).is_some() || maps[1].get(key, guard).is_some(),
+        Op::Insert => maps[0].transfer(key, &maps[1], guard),
+        Op::Remove => maps[1].transfer(key, &maps[0], guard),
+    }
+}
+
+/// Checks that the `len` keys of the prefill are each in exactly one of the maps, once the workers
+/// are done.
+fn check_transfers<M: ebr::TransferMap<String, Value>>(config: &Config, maps: &[M; 2], len: usize) {
+    let guard = unsafe { crossbeam_ebr::unprotected() };
+    let mut found = 0;
+    for k in 0..config.range {
+        let key = config.map_key(k);
+        let (a, b) = (maps[0].get(&key, guard), maps[1].get(&key, guard));
+        assert!(a.is_none() || b.is_none(), "key {} is in both maps", key);
+        found += (a.is_some() || b.is_some()) as usize;
+    }
+    assert_eq!(found, len, "the transfers lost or duplicated keys");
+    assert_eq!(maps[0].len_approx() + maps[1].len_approx(), len);
+}
+
+/// The transfer workload on two maps, of which the prefill fills the first. The keys only move
+/// between the maps, and a transfer's MCAS descriptor is installed in both of them.
+pub(crate) fn ebr<M: ebr::TransferMap<String, Value> + Send + Sync, N: Unsigned>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let maps = &[M::new(), M::new()];
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let build_ops_per_sec = prefill_ebr(config, &maps[0], prefill, &mut prefill_history);
+    let len = maps[0].len_approx();
+
+    let ran = super::run_ebr(
+        config,
+        N::to_u64(),
+        history_start,
+        |_| maps[1].len_approx(),
+        |_| (),
+        |_, guard, worker| {
+            worker.map_op(|op, k, _| transfer_op(maps, op, &config.map_key(k), guard))
+        },
+    );
+    check_transfers(config, maps, len);
+    ran.perf(config, build_ops_per_sec)
+}
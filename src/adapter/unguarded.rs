@@ -0,0 +1,80 @@
+//! The map workload with `--unguarded`, whose gets read the maps without a guard.
+
+use std::time::Instant;
+
+use pebr_benchmark::ebr;
+use pebr_benchmark::ebr::gc_policy::Collector;
+use pebr_benchmark::registry::Prefill;
+
+use super::map::prefill_ebr;
+use super::{Reclaim, Worker};
+use crate::{Config, History, Op, Perf, Value, MM};
+
+/// Like `map::ebr`, but the gets are unguarded. Under EBR, only the updates pin, each for itself,
+/// and reclamation is off, so that the reads cannot reach freed nodes.
+pub(crate) fn ebr<M: ebr::UnguardedMap<String, Value> + Send + Sync>(
+    config: &Config,
+    prefill: Prefill,
+) -> Perf {
+    let map = &M::new();
+    let history_start = Instant::now();
+    let mut prefill_history = History::new(config, config.threads, history_start);
+    let build_ops_per_sec = prefill_ebr(config, map, prefill, &mut prefill_history);
+
+    let op = |worker: &mut Worker, update: &dyn Fn(Op, String) -> bool| {
+        worker.map_op(|op, k, _| {
+            let key = config.map_key(k);
+            match op {
+                // Nothing is reclaimed.
+                Op::Get => unsafe { map.get_unguarded(&key) }.is_some(),
+                _ => update(op, key),
+            }
+        })
+    };
+    let len = || map.len_approx();
+    let mut ran = match config.mm {
+        MM::NR => super::run(
+            config,
+            Reclaim::None,
+            history_start,
+            len,
+            |_| (),
+            |_, worker| {
+                let tid = worker.tid;
+                op(worker, &|op, key| {
+                    let guard = unsafe { crossbeam_ebr::leaking() };
+                    match op {
+                        Op::Insert => {
+                            let value = config.map_value(tid, &key);
+                            map.insert(key, value, guard)
+                        }
+                        _ => config.remove_ebr(map, &key, guard),
+                    }
+                })
+            },
+        ),
+        MM::EBR => {
+            let collector = &Collector::new(config.gc_policy, config.gc_threshold);
+            super::run(
+                config,
+                Reclaim::Ebr(collector),
+                history_start,
+                len,
+                |_| ((), collector.register()),
+                |(_, handle), worker| {
+                    let tid = worker.tid;
+                    op(worker, &|op, key| match op {
+                        Op::Insert => {
+                            let value = config.map_value(tid, &key);
+                            map.insert(key, value, &handle.pin())
+                        }
+                        _ => config.remove_ebr(map, &key, &handle.pin()),
+                    })
+                },
+            )
+        }
+        _ => unreachable!("checked by setup"),
+    };
+    ran.finish_history(config, prefill_history);
+    ran.perf(config, build_ops_per_sec)
+}
@@ -0,0 +1,88 @@
+//! Fallible allocation of the nodes of the maps, with injected failures, to exercise the failure
+//! paths of `ConcurrentMap::try_insert`.
+//!
+//! `insert` allocates its nodes with `Owned::new`, which aborts the process when the allocator runs
+//! out of memory. `try_insert` allocates them with `try_box` instead, which returns the value back
+//! when the allocator returns null, so that the map frees whatever the insert allocated so far and
+//! fails with `AllocError`, leaving itself unchanged. With the `alloc-fault` feature, `try_box` also
+//! fails a fraction of the calls without trying the allocator, as it would under memory pressure.
+//! The fraction is read from `ALLOC_FAULT_RATE` (default 0.1), e.g.,
+//!
+//! ```text
+//! ALLOC_FAULT_RATE=0.3 cargo test --features alloc-fault try_insert
+//! ```
+//!
+//! The feature is meant for testing only.
+
+use rand::Rng;
+use std::alloc::{self, Layout};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Once;
+
+/// The failure rate as a fraction of `u32::max_value()`.
+static THRESHOLD: AtomicU32 = AtomicU32::new(0);
+static INIT: Once = Once::new();
+
+/// The failure of `try_insert` to allocate a node. The map is unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to allocate a node")
+    }
+}
+
+impl Error for AllocError {}
+
+/// The fraction of the allocations of `try_box` that fail.
+pub fn rate() -> f64 {
+    INIT.call_once(|| {
+        let rate = env::var("ALLOC_FAULT_RATE")
+            .ok()
+            .and_then(|r| r.parse::<f64>().ok())
+            .unwrap_or(0.1);
+        assert!(
+            (0.0..1.0).contains(&rate),
+            "ALLOC_FAULT_RATE must be in [0, 1)"
+        );
+        THRESHOLD.store(
+            (rate * f64::from(u32::max_value())) as u32,
+            Ordering::Relaxed,
+        );
+    });
+    f64::from(THRESHOLD.load(Ordering::Relaxed)) / f64::from(u32::max_value())
+}
+
+/// Whether to fail the current allocation.
+#[inline]
+fn fail() -> bool {
+    if !cfg!(feature = "alloc-fault") {
+        return false;
+    }
+    rate();
+    rand::thread_rng().gen::<u32>() < THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Boxes `value`, or returns it back if the allocation fails.
+#[inline]
+pub fn try_box<T>(value: T) -> Result<Box<T>, T> {
+    if fail() {
+        return Err(value);
+    }
+    if mem::size_of::<T>() == 0 {
+        return Ok(Box::new(value));
+    }
+    unsafe {
+        let ptr = alloc::alloc(Layout::new::<T>()) as *mut T;
+        if ptr.is_null() {
+            return Err(value);
+        }
+        ptr.write(value);
+        Ok(Box::from_raw(ptr))
+    }
+}
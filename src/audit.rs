@@ -0,0 +1,141 @@
+//! Values that account for their own construction and drop, to audit the memory safety of a run.
+//!
+//! Each `Audited` value gets a unique id, which it adds to a concurrent registry when it is
+//! constructed and removes when it is dropped. A value that is dropped twice finds its id missing
+//! and panics, and the values that are never dropped stay in the registry. With the `audit`
+//! feature, the benchmark stores `Audited` values in the maps, and after a run checks that the
+//! registry holds exactly as many values as the map once its garbage is freed, and none once the
+//! map is dropped. The registry is sharded by id, but it still takes a lock per construction and
+//! drop, so the throughput of an audited run is not comparable to that of a normal run.
+
+use crossbeam_utils::CachePadded;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+
+const SHARDS: usize = 64;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static LEAKED: AtomicUsize = AtomicUsize::new(0);
+static INIT: Once = Once::new();
+static mut REGISTRY: *const Vec<CachePadded<Mutex<HashSet<u64>>>> = ptr::null();
+
+fn shard(id: u64) -> &'static Mutex<HashSet<u64>> {
+    INIT.call_once(|| unsafe {
+        let shards = (0..SHARDS)
+            .map(|_| CachePadded::new(Mutex::new(HashSet::new())))
+            .collect::<Vec<_>>();
+        REGISTRY = Box::into_raw(Box::new(shards));
+    });
+    unsafe { &(*REGISTRY)[id as usize % SHARDS] }
+}
+
+/// A value registered in the registry of the live values.
+pub struct Audited<T> {
+    id: u64,
+    value: T,
+}
+
+impl<T> Audited<T> {
+    pub fn new(value: T) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        shard(id).lock().unwrap().insert(id);
+        Audited { id, value }
+    }
+}
+
+impl<T> From<T> for Audited<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Deref for Audited<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone> Clone for Audited<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+/// Compares the values, not their identities in the registry.
+impl<T: PartialEq> PartialEq for Audited<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Audited<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> Drop for Audited<T> {
+    fn drop(&mut self) {
+        if !shard(self.id).lock().unwrap().remove(&self.id) {
+            panic!("audit: value {} is dropped twice", self.id);
+        }
+    }
+}
+
+/// The number of values that are constructed and not dropped yet, except the leaked ones.
+pub fn live() -> usize {
+    let live = (0..SHARDS as u64)
+        .map(|id| shard(id).lock().unwrap().len())
+        .sum::<usize>();
+    live - LEAKED.load(Ordering::Relaxed)
+}
+
+/// Sets aside `count` live values that are known to be leaked, so that `live` no longer counts
+/// them.
+pub fn leak(count: usize) {
+    LEAKED.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Forgets the live values, e.g., those that a run without reclamation leaked.
+pub fn reset() {
+    for id in 0..SHARDS as u64 {
+        shard(id).lock().unwrap().clear();
+    }
+    LEAKED.store(0, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{leak, live, Audited};
+    use std::mem;
+    use std::panic;
+    use std::ptr;
+
+    #[test]
+    fn exactly_once() {
+        let base = live();
+        let values = (0..100).map(Audited::new).collect::<Vec<_>>();
+        let clones = values.clone();
+        assert_eq!(*clones[42], 42);
+        assert_eq!(live(), base + 200);
+        drop(values);
+        assert_eq!(live(), base + 100);
+
+        let copy = unsafe { ptr::read(&clones[0]) };
+        drop(clones);
+        assert_eq!(live(), base);
+        assert!(panic::catch_unwind(move || drop(copy)).is_err());
+        assert_eq!(live(), base);
+
+        mem::forget(Audited::new(0));
+        assert_eq!(live(), base + 1);
+        leak(1);
+        assert_eq!(live(), base);
+    }
+}
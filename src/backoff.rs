@@ -0,0 +1,79 @@
+//! The back-off of the waits of the structures that wait for a lock, with a spin limit to sweep.
+//!
+//! The optimistic readers of ART wait for a node that a writer holds, and the operations of
+//! CuckooHashMap wait for the buckets that another thread is moving entries between. `Backoff` is
+//! the exponential back-off of `crossbeam_utils::Backoff`: step `i` of a wait spins `2^i` times up
+//! to the spin limit, and yields the CPU after that. The limit defaults to that of crossbeam (6),
+//! and `set_spin_limit` changes it for all the waits, so that the sensitivity of a structure to its
+//! back-off can be measured. The other structures retry without back-off.
+
+use std::cell::Cell;
+use std::sync::atomic::{self, AtomicU32, Ordering};
+use std::thread;
+
+/// The default spin limit, that of `crossbeam_utils::Backoff`.
+pub const DEFAULT_SPIN_LIMIT: u32 = 6;
+/// The largest spin limit, at which a step spins about a million times.
+pub const MAX_SPIN_LIMIT: u32 = 20;
+
+static SPIN_LIMIT: AtomicU32 = AtomicU32::new(DEFAULT_SPIN_LIMIT);
+
+/// Sets the last step of the waits that spins instead of yielding.
+pub fn set_spin_limit(limit: u32) {
+    assert!(limit <= MAX_SPIN_LIMIT);
+    SPIN_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// The exponential back-off of a wait.
+pub struct Backoff {
+    step: Cell<u32>,
+    limit: u32,
+}
+
+impl Backoff {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            step: Cell::new(0),
+            limit: SPIN_LIMIT.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Waits for another thread to make progress, spinning for longer at each step up to the
+    /// limit, and yielding after that.
+    #[inline]
+    pub fn snooze(&self) {
+        let step = self.step.get();
+        if step <= self.limit {
+            for _ in 0..1u32 << step {
+                atomic::spin_loop_hint();
+            }
+            self.step.set(step + 1);
+        } else {
+            thread::yield_now();
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_spin_limit, Backoff, DEFAULT_SPIN_LIMIT};
+
+    #[test]
+    fn spins_up_to_the_limit() {
+        set_spin_limit(2);
+        let backoff = Backoff::new();
+        for _ in 0..5 {
+            backoff.snooze();
+        }
+        // Steps 0, 1 and 2 spin, and the later ones yield.
+        assert_eq!(backoff.step.get(), 3);
+        set_spin_limit(DEFAULT_SPIN_LIMIT);
+    }
+}
@@ -0,0 +1,201 @@
+//! Reports the build time and the code size of each family of data structures and of each
+//! structure of each memory manager, and appends them to a results store.
+//!
+//! Each family is built on its own, with `--no-default-features --features <family>`, in a target
+//! directory of its own. The dependencies are built first, and the benchmark is then cleaned and
+//! built again, so that the build time is that of the benchmark alone. The code size of a structure
+//! is read from the symbols of the binary (`code_size`).
+
+extern crate clap;
+extern crate pebr_benchmark;
+
+use clap::{value_t, values_t, App, Arg};
+use pebr_benchmark::code_size;
+use pebr_benchmark::environment::Environment;
+use pebr_benchmark::results::{self, Record, Store};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::Instant;
+
+const FAMILIES: [&str; 5] = ["lists", "hash-maps", "trees", "queues", "counters"];
+
+/// The metrics of the builds, which are all better when lower.
+const METRICS: [&str; 3] = ["build_secs", "binary_bytes", "code_bytes"];
+
+fn main() {
+    let m = App::new("report-size")
+        .about(
+            "Builds each family of data structures separately, and records the build time, the \
+             size of the binary and the code size of each structure of each memory manager",
+        )
+        .arg(
+            Arg::with_name("families")
+                .short("f")
+                .value_name("FAMILY")
+                .possible_values(&FAMILIES)
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Families of data structures [default: all]"),
+        )
+        .arg(
+            Arg::with_name("target dir")
+                .long("target-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of the target directories of the builds")
+                .default_value("target/report-size"),
+        )
+        .arg(
+            Arg::with_name("store")
+                .long("store")
+                .value_name("DIR")
+                .takes_value(true)
+                .help(
+                    "Append the results to the results store in DIR, and report the metrics that \
+                     regress against the previous results",
+                )
+                .default_value("results"),
+        )
+        .arg(
+            Arg::with_name("regression threshold")
+                .long("regression-threshold")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .help("Minimum relative change of a metric reported as a regression")
+                .default_value("5"),
+        )
+        .get_matches();
+    let families = if m.is_present("families") {
+        values_t!(m, "families", String).unwrap_or_else(|e| e.exit())
+    } else {
+        FAMILIES.iter().map(|f| f.to_string()).collect()
+    };
+    let target_dir = Path::new(m.value_of("target dir").unwrap());
+    let store = Store::open(Path::new(m.value_of("store").unwrap())).unwrap_or_else(|e| {
+        eprintln!("report-size: cannot open the store: {}", e);
+        process::exit(2);
+    });
+    let threshold = value_t!(m, "regression threshold", f64).unwrap_or_else(|e| e.exit());
+    let env = Environment::capture().fields();
+
+    let mut regressed = false;
+    for family in &families {
+        let dir = target_dir.join(family);
+        let (build_secs, binary) = build(family, &dir);
+        let binary_bytes = binary.metadata().map(|m| m.len()).unwrap_or_else(|e| {
+            eprintln!("report-size: cannot read {}: {}", binary.display(), e);
+            process::exit(2);
+        });
+        println!(
+            "{}: built in {:.1} s, binary: {} bytes",
+            family, build_secs, binary_bytes
+        );
+        let mut records = vec![Record {
+            key: format!("report-size,family={}", family),
+            env: env.clone(),
+            metrics: vec![
+                ("build_secs".to_string(), build_secs),
+                ("binary_bytes".to_string(), binary_bytes as f64),
+            ],
+        }];
+        for ((backend, module), bytes) in code_size::sizes(&symbols(&binary)) {
+            println!("  {:>4} {:<24} {:>9} bytes", backend, module, bytes);
+            records.push(Record {
+                key: format!(
+                    "report-size,family={},mm={},structure={}",
+                    family, backend, module
+                ),
+                env: env.clone(),
+                metrics: vec![("code_bytes".to_string(), bytes as f64)],
+            });
+        }
+        for record in &records {
+            regressed |= record_result(&store, record, threshold);
+        }
+    }
+    if regressed {
+        process::exit(1);
+    }
+}
+
+/// Builds the benchmark with only `family` into `dir`, and returns the build time of the benchmark
+/// alone and the path of the binary.
+fn build(family: &str, dir: &Path) -> (f64, PathBuf) {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let run = |args: &[&str]| {
+        let status = Command::new(&cargo)
+            .args(args)
+            .arg("--target-dir")
+            .arg(dir)
+            .status()
+            .unwrap_or_else(|e| {
+                eprintln!("report-size: cannot run {}: {}", cargo, e);
+                process::exit(2);
+            });
+        if !status.success() {
+            eprintln!("report-size: {} {} failed", cargo, args.join(" "));
+            process::exit(2);
+        }
+    };
+    let build = [
+        "build",
+        "--release",
+        "--bin",
+        "pebr-benchmark",
+        "--no-default-features",
+        "--features",
+        family,
+    ];
+    run(&build);
+    run(&["clean", "--release", "-p", "pebr-benchmark"]);
+    let start = Instant::now();
+    run(&build);
+    let secs = start.elapsed().as_secs_f64();
+    (secs, dir.join("release").join("pebr-benchmark"))
+}
+
+/// The sized symbols of `binary`, demangled.
+fn symbols(binary: &Path) -> String {
+    let output = Command::new("nm")
+        .args(&["--demangle", "--print-size"])
+        .arg(binary)
+        .output()
+        .unwrap_or_else(|e| {
+            eprintln!("report-size: cannot run nm: {}", e);
+            process::exit(2);
+        });
+    if !output.status.success() {
+        eprintln!("report-size: nm {} failed", binary.display());
+        process::exit(2);
+    }
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Compares `record` with its previous results and appends it to the store. Returns whether it
+/// regressed.
+fn record_result(store: &Store, record: &Record, threshold: f64) -> bool {
+    let baseline = store
+        .load(&record.key)
+        .and_then(|baseline| {
+            store.append(record)?;
+            Ok(baseline)
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("report-size: {}", e);
+            process::exit(2);
+        });
+    let regressions = results::regressions(&baseline, record, threshold, &METRICS);
+    for r in &regressions {
+        println!(
+            "REGRESSION: {}: {} {:.1} -> {:.1} ({:+.2}%) against {} runs",
+            record.key,
+            r.metric,
+            r.baseline,
+            r.value,
+            r.change,
+            baseline.len()
+        );
+    }
+    !regressions.is_empty()
+}
@@ -0,0 +1,98 @@
+//! Statistics and tuning of the path copying of `BonsaiTreeMap`.
+//!
+//! An update of the Bonsai tree copies the path from the root to the updated node, and the nodes
+//! that the rotations rebuild, into a new version of the tree that is installed with a CAS on the
+//! root. The old nodes of the path become garbage once the CAS succeeds, and the copies are freed
+//! if it fails. When enabled, each thread counts the updates it commits, the nodes they copied, the
+//! copies of the attempts that lost the race, and the bytes of the nodes they retired. The counts
+//! are merged into the global ones when the thread exits.
+//!
+//! The tree is weight-balanced: a node is rebuilt with a rotation when one of its subtrees is more
+//! than `weight` times as large as the other. A larger weight rotates less often, so that updates
+//! copy fewer nodes, at the cost of deeper paths.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static WEIGHT: AtomicUsize = AtomicUsize::new(2);
+static UPDATES: AtomicU64 = AtomicU64::new(0);
+static COPIES: AtomicU64 = AtomicU64::new(0);
+static ABORTED_COPIES: AtomicU64 = AtomicU64::new(0);
+static GARBAGE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The counts of the path copying of the updates.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Stats {
+    /// The committed updates.
+    pub updates: u64,
+    /// The nodes that the committed updates copied.
+    pub copies: u64,
+    /// The nodes that the attempts that lost the race copied and freed.
+    pub aborted_copies: u64,
+    /// The bytes of the nodes that the committed updates retired.
+    pub garbage_bytes: u64,
+}
+
+struct Local(Stats);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        UPDATES.fetch_add(self.0.updates, Ordering::Relaxed);
+        COPIES.fetch_add(self.0.copies, Ordering::Relaxed);
+        ABORTED_COPIES.fetch_add(self.0.aborted_copies, Ordering::Relaxed);
+        GARBAGE_BYTES.fetch_add(self.0.garbage_bytes, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(Stats::default()));
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Sets the ratio of the sizes of sibling subtrees above which the tree rotates.
+pub fn set_weight(weight: usize) {
+    assert!(weight > 0);
+    WEIGHT.store(weight, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn weight() -> usize {
+    WEIGHT.load(Ordering::Relaxed)
+}
+
+/// Records that an update copied `copies` nodes and retired `garbage_bytes` bytes of nodes.
+#[inline]
+pub fn record_commit(copies: usize, garbage_bytes: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    LOCAL.with(|local| {
+        let stats = &mut local.borrow_mut().0;
+        stats.updates += 1;
+        stats.copies += copies as u64;
+        stats.garbage_bytes += garbage_bytes as u64;
+    });
+}
+
+/// Records that an attempt of an update lost the race after copying `copies` nodes.
+#[inline]
+pub fn record_abort(copies: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    LOCAL.with(|local| local.borrow_mut().0.aborted_copies += copies as u64);
+}
+
+/// Takes the counts recorded by the exited threads.
+pub fn take() -> Stats {
+    Stats {
+        updates: UPDATES.swap(0, Ordering::Relaxed),
+        copies: COPIES.swap(0, Ordering::Relaxed),
+        aborted_copies: ABORTED_COPIES.swap(0, Ordering::Relaxed),
+        garbage_bytes: GARBAGE_BYTES.swap(0, Ordering::Relaxed),
+    }
+}
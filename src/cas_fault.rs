@@ -0,0 +1,120 @@
+//! Injected failures of the CASes of the Harris lists, to exercise their retry and cleanup paths.
+//!
+//! The lists update their links through `CompareAndSet::cas`, which is just `compare_and_set`
+//! unless the crate is built with the `cas-fault` feature. With the feature, `cas` fails a fraction
+//! of the calls without trying them, as a weak CAS may, and returns the current value of the link.
+//! The lists treat every failed CAS as a lost race and retry or leave the cleanup to a later
+//! traversal, so the injected failures drive them through the interleavings that are otherwise
+//! rare. The fraction is read from `CAS_FAULT_RATE` (default 0.1), e.g.,
+//!
+//! ```text
+//! CAS_FAULT_RATE=0.3 cargo test --features cas-fault list
+//! ```
+//!
+//! The feature is meant for testing only.
+
+use rand::Rng;
+use std::env;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Once;
+
+/// The failure rate as a fraction of `u32::max_value()`.
+static THRESHOLD: AtomicU32 = AtomicU32::new(0);
+static INIT: Once = Once::new();
+
+/// The fraction of the CASes that fail.
+pub fn rate() -> f64 {
+    INIT.call_once(|| {
+        let rate = env::var("CAS_FAULT_RATE")
+            .ok()
+            .and_then(|r| r.parse::<f64>().ok())
+            .unwrap_or(0.1);
+        assert!(
+            (0.0..1.0).contains(&rate),
+            "CAS_FAULT_RATE must be in [0, 1)"
+        );
+        THRESHOLD.store(
+            (rate * f64::from(u32::max_value())) as u32,
+            Ordering::Relaxed,
+        );
+    });
+    f64::from(THRESHOLD.load(Ordering::Relaxed)) / f64::from(u32::max_value())
+}
+
+/// Whether to fail the current CAS.
+#[inline]
+fn fail() -> bool {
+    if !cfg!(feature = "cas-fault") {
+        return false;
+    }
+    rate();
+    rand::thread_rng().gen::<u32>() < THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// CASes with injected failures, for the EBR structures.
+pub mod ebr {
+    use crossbeam_ebr::{Atomic, CompareAndSetError, Guard, Pointer, Shared};
+    use std::sync::atomic::Ordering;
+
+    pub trait CompareAndSet<T> {
+        /// `compare_and_set`, but fails spuriously with the `cas-fault` feature.
+        fn cas<'g, P: Pointer<T>>(
+            &self,
+            current: Shared<'_, T>,
+            new: P,
+            ord: Ordering,
+            guard: &'g Guard,
+        ) -> Result<Shared<'g, T>, CompareAndSetError<'g, T, P>>;
+    }
+
+    impl<T> CompareAndSet<T> for Atomic<T> {
+        #[inline]
+        fn cas<'g, P: Pointer<T>>(
+            &self,
+            current: Shared<'_, T>,
+            new: P,
+            ord: Ordering,
+            guard: &'g Guard,
+        ) -> Result<Shared<'g, T>, CompareAndSetError<'g, T, P>> {
+            if super::fail() {
+                let current = self.load(Ordering::Acquire, guard);
+                return Err(CompareAndSetError { current, new });
+            }
+            self.compare_and_set(current, new, ord, guard)
+        }
+    }
+}
+
+/// CASes with injected failures, for the PEBR structures.
+pub mod pebr {
+    use crossbeam_pebr::{Atomic, CompareAndSetError, Guard, Pointer, Shared};
+    use std::sync::atomic::Ordering;
+
+    pub trait CompareAndSet<T> {
+        /// `compare_and_set`, but fails spuriously with the `cas-fault` feature.
+        fn cas<'g, P: Pointer<T>>(
+            &self,
+            current: Shared<'_, T>,
+            new: P,
+            ord: Ordering,
+            guard: &'g Guard,
+        ) -> Result<Shared<'g, T>, CompareAndSetError<'g, T, P>>;
+    }
+
+    impl<T> CompareAndSet<T> for Atomic<T> {
+        #[inline]
+        fn cas<'g, P: Pointer<T>>(
+            &self,
+            current: Shared<'_, T>,
+            new: P,
+            ord: Ordering,
+            guard: &'g Guard,
+        ) -> Result<Shared<'g, T>, CompareAndSetError<'g, T, P>> {
+            if super::fail() {
+                let current = self.load(Ordering::Acquire, guard);
+                return Err(CompareAndSetError { current, new });
+            }
+            self.compare_and_set(current, new, ord, guard)
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Checksums of the sequences of the operations of the workers and of their results.
+//!
+//! With a fixed seed and a fixed number of operations, a single thread performs the same sequence
+//! of operations whatever the memory manager, so the EBR and the PEBR ports of a data structure,
+//! and NR as the reference, should return the same results in the same order. Each thread folds
+//! the operation, the key and the result of each of its operations into a 64-bit FNV-1a hash, and
+//! the checksums of the threads are folded in the order of their ids, so that two runs diverged if
+//! their checksums differ. With more threads, the results depend on the interleaving, and the
+//! checksums of two runs differ even if both are correct.
+
+const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checksum(u64);
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Checksum(OFFSET)
+    }
+}
+
+impl Checksum {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(PRIME);
+        }
+    }
+
+    /// Adds an operation of kind `op` on `key` that returned `result`.
+    #[inline]
+    pub fn add(&mut self, op: u8, key: u64, result: bool) {
+        self.write(&[op]);
+        self.write(&key.to_le_bytes());
+        self.write(&[result as u8]);
+    }
+
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Folds the checksums of the threads, in the order of their ids.
+    pub fn combine(checksums: &[u64]) -> u64 {
+        let mut combined = Checksum::default();
+        for c in checksums {
+            combined.write(&c.to_le_bytes());
+        }
+        combined.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checksum;
+
+    fn checksum(ops: &[(u8, u64, bool)]) -> u64 {
+        let mut c = Checksum::default();
+        for &(op, key, result) in ops {
+            c.add(op, key, result);
+        }
+        c.value()
+    }
+
+    #[test]
+    fn sequences() {
+        let ops = [(1, 3, true), (0, 3, true), (2, 3, true), (0, 3, false)];
+        assert_eq!(checksum(&ops), checksum(&ops));
+        // The order, the keys, the kinds and the results all count.
+        assert_ne!(checksum(&ops), checksum(&[ops[1], ops[0], ops[2], ops[3]]));
+        assert_ne!(
+            checksum(&ops),
+            checksum(&[(1, 4, true), ops[1], ops[2], ops[3]])
+        );
+        assert_ne!(
+            checksum(&ops),
+            checksum(&[(2, 3, true), ops[1], ops[2], ops[3]])
+        );
+        assert_ne!(
+            checksum(&ops),
+            checksum(&[ops[0], ops[1], ops[2], (0, 3, true)])
+        );
+        assert_ne!(checksum(&ops), checksum(&ops[..3]));
+        assert_eq!(checksum(&[]), Checksum::default().value());
+    }
+
+    #[test]
+    fn combine() {
+        let (a, b) = (checksum(&[(0, 1, true)]), checksum(&[(0, 2, false)]));
+        assert_eq!(Checksum::combine(&[a, b]), Checksum::combine(&[a, b]));
+        assert_ne!(Checksum::combine(&[a, b]), Checksum::combine(&[b, a]));
+        assert_ne!(Checksum::combine(&[a]), Checksum::combine(&[a, b]));
+    }
+}
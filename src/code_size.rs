@@ -0,0 +1,105 @@
+//! The code size of each structure of each memory manager in a build of the benchmark, for the
+//! `report-size` tool.
+//!
+//! The structures are generic, so their code is generated where the driver instantiates them, and
+//! the size of a structure is the total size of the functions in the binary whose paths lie in its
+//! module, e.g., `pebr_benchmark::pebr::list`, including the trait implementations such as
+//! `<pebr_benchmark::pebr::list::HList<K, V> as ...>::insert`. The code that the driver inlines
+//! into its own functions is not attributed to the structures.
+
+use std::collections::BTreeMap;
+
+/// The memory managers whose structures have modules of their own. NR runs the structures of EBR.
+pub const BACKENDS: [&str; 3] = ["ebr", "pebr", "rc"];
+
+/// The memory manager and the module of the structure that the demangled `symbol` belongs to.
+pub fn component(symbol: &str) -> Option<(&str, &str)> {
+    const CRATE: &str = "pebr_benchmark::";
+    let mut rest = symbol;
+    while let Some(i) = rest.find(CRATE) {
+        rest = &rest[i + CRATE.len()..];
+        let mut segments = rest.splitn(3, "::");
+        let backend = segments.next()?;
+        let module = match segments.next() {
+            Some(module) => module,
+            None => continue,
+        };
+        // A module is followed by an item, and an item of the backend itself is no structure.
+        if BACKENDS.contains(&backend) && segments.next().is_some() {
+            let end = module
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or_else(|| module.len());
+            return Some((backend, &module[..end]));
+        }
+    }
+    None
+}
+
+/// Sums the sizes of the functions of each memory manager and structure in the output of
+/// `nm --demangle --print-size`.
+pub fn sizes(nm: &str) -> BTreeMap<(String, String), u64> {
+    let mut sizes = BTreeMap::new();
+    for line in nm.lines() {
+        // The address, the size, the type and the symbol, which may contain spaces.
+        let fields = line.splitn(4, ' ').collect::<Vec<_>>();
+        if fields.len() < 4 {
+            continue;
+        }
+        let (size, kind, symbol) = (fields[1], fields[2], fields[3]);
+        if !["t", "T", "w", "W"].contains(&kind) {
+            continue;
+        }
+        let size = match u64::from_str_radix(size, 16) {
+            Ok(size) => size,
+            Err(_) => continue,
+        };
+        if let Some((backend, module)) = component(symbol) {
+            *sizes
+                .entry((backend.to_string(), module.to_string()))
+                .or_insert(0) += size;
+        }
+    }
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{component, sizes};
+
+    #[test]
+    fn components() {
+        assert_eq!(
+            component("pebr_benchmark::ebr::list::List<K,V,M>::find"),
+            Some(("ebr", "list"))
+        );
+        assert_eq!(
+            component(
+                "<pebr_benchmark::pebr::natarajan_mittal_tree::NMTreeMap<K,V> as \
+                 pebr_benchmark::pebr::concurrent_map::ConcurrentMap<K,V>>::insert"
+            ),
+            Some(("pebr", "natarajan_mittal_tree"))
+        );
+        assert_eq!(
+            component("core::ptr::drop_in_place<pebr_benchmark::rc::list::Node<K,V>>"),
+            Some(("rc", "list"))
+        );
+        assert_eq!(component("pebr_benchmark::bench_map_ebr"), None);
+        assert_eq!(component("pebr_benchmark::restarts::record"), None);
+        assert_eq!(component("crossbeam_epoch::guard::Guard::defer"), None);
+    }
+
+    #[test]
+    fn sums() {
+        let nm = concat!(
+            "0000000000001000 0000000000000040 T pebr_benchmark::ebr::list::List::find\n",
+            "0000000000001040 0000000000000010 t pebr_benchmark::ebr::list::List::get\n",
+            "0000000000001050 0000000000000020 T pebr_benchmark::pebr::list::List::get\n",
+            "0000000000002000 0000000000000100 D pebr_benchmark::ebr::list::TABLE\n",
+            "                 U pebr_benchmark::ebr::list::List::remove\n",
+        );
+        let sizes = sizes(nm);
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[&("ebr".to_string(), "list".to_string())], 0x50);
+        assert_eq!(sizes[&("pebr".to_string(), "list".to_string())], 0x20);
+    }
+}
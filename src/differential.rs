@@ -0,0 +1,499 @@
+//! Differential testing of two maps, for the `differential` fuzz target (`fuzz/`).
+//!
+//! The input of the fuzzer is decoded into a `Program`, which names two registered ports of maps,
+//! e.g., HList with EBR and NMTree with PEBR, or the EBR and the PEBR ports of the same map, and
+//! the operations of a few workers in rounds. Each worker applies each of its operations to both
+//! maps and compares their outcomes. The workers run concurrently, but each of them owns the keys
+//! that are congruent to its index, so the outcomes of its operations are determined by its own
+//! operations, while the other workers update the maps around them. At the end of each round, the
+//! workers meet at a barrier, and the first one compares the entries of all the keys.
+
+#[cfg(feature = "lists")]
+use crate::rc;
+use crate::registry::{Driver, Kind, Prefill, Registry, Run, Scheme};
+use crate::slow_key::SlowKey;
+use crate::{ebr, pebr, tagged};
+use crossbeam_utils::thread;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Barrier, Mutex};
+
+/// The keys of the programs.
+pub const KEYS: usize = 64;
+pub const MAX_THREADS: usize = 4;
+
+/// The byte of an operation that ends the current round.
+const END_ROUND: u8 = u8::max_value();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Get,
+    Insert,
+    Remove,
+}
+
+/// What a worker observes of an operation: the value that a get or a remove returned, or whether
+/// an insert inserted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Value(Option<String>),
+    Inserted(bool),
+}
+
+/// A map under test, behind the interface of its memory manager.
+pub trait Subject: Sync {
+    fn apply(&self, op: Op, key: usize, value: &str) -> Outcome;
+}
+
+struct Ebr<M>(M);
+
+impl<M: ebr::ConcurrentMap<String, String> + Sync> Subject for Ebr<M> {
+    fn apply(&self, op: Op, key: usize, value: &str) -> Outcome {
+        let guard = &crossbeam_ebr::pin();
+        let key = key.to_string();
+        match op {
+            Op::Get => Outcome::Value(self.0.get(&key, guard).cloned()),
+            Op::Insert => Outcome::Inserted(self.0.insert(key, value.to_string(), guard)),
+            Op::Remove => Outcome::Value(self.0.remove(&key, guard)),
+        }
+    }
+}
+
+struct Pebr<M>(M);
+
+impl<M: pebr::ConcurrentMap<String, String> + Sync> Subject for Pebr<M> {
+    fn apply(&self, op: Op, key: usize, value: &str) -> Outcome {
+        let guard = &mut crossbeam_pebr::pin();
+        let handle = &mut M::handle(guard);
+        let key = key.to_string();
+        match op {
+            Op::Get => Outcome::Value(self.0.get(handle, &key, guard).cloned()),
+            Op::Insert => Outcome::Inserted(self.0.insert(handle, key, value.to_string(), guard)),
+            Op::Remove => Outcome::Value(self.0.remove(handle, &key, guard)),
+        }
+    }
+}
+
+#[cfg(feature = "lists")]
+struct Rc<M>(M);
+
+#[cfg(feature = "lists")]
+impl<M: rc::ConcurrentMap<String, String> + Sync> Subject for Rc<M> {
+    fn apply(&self, op: Op, key: usize, value: &str) -> Outcome {
+        let key = key.to_string();
+        match op {
+            Op::Get => Outcome::Value(self.0.get(&key).map(|v| (*v).clone())),
+            Op::Insert => Outcome::Inserted(self.0.insert(key, value.to_string())),
+            Op::Remove => Outcome::Value(self.0.remove(&key)),
+        }
+    }
+}
+
+/// The first operation on which the two maps disagreed.
+#[derive(Debug)]
+pub struct Divergence {
+    pub subjects: [(&'static str, Scheme); 2],
+    pub round: usize,
+    /// The worker, or `None` for the comparison at the end of the round.
+    pub thread: Option<usize>,
+    pub op: Op,
+    pub key: usize,
+    pub outcomes: [Outcome; 2],
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [(a, a_scheme), (b, b_scheme)] = self.subjects;
+        write!(
+            f,
+            "{} ({:?}) and {} ({:?}) diverge on {:?} of key {} in round {}",
+            a, a_scheme, b, b_scheme, self.op, self.key, self.round
+        )?;
+        match self.thread {
+            Some(t) => write!(f, " by thread {}", t)?,
+            None => write!(f, " at its end")?,
+        }
+        write!(f, ": {:?} != {:?}", self.outcomes[0], self.outcomes[1])
+    }
+}
+
+impl Error for Divergence {}
+
+/// The operations of the workers on two maps.
+#[derive(Debug)]
+pub struct Program {
+    /// The names and the schemes of the registered ports of the maps.
+    pub subjects: [(&'static str, Scheme); 2],
+    pub threads: usize,
+    /// The operations of each worker in each round, `rounds[round][thread]`.
+    pub rounds: Vec<Vec<Vec<(Op, usize)>>>,
+}
+
+impl Program {
+    /// Decodes a program from `data`: a byte for each of the two maps among `ports`, which are
+    /// distinct, a byte for the number of workers, and then two bytes for each operation, its kind
+    /// and its key, which are dealt to the workers in turn. `END_ROUND` as the kind ends the round.
+    pub fn decode(data: &[u8], ports: &[(&'static str, Scheme)]) -> Option<Self> {
+        if data.len() < 3 || ports.len() < 2 {
+            return None;
+        }
+        let first = data[0] as usize % ports.len();
+        let second = (first + 1 + data[1] as usize % (ports.len() - 1)) % ports.len();
+        let threads = 1 + data[2] as usize % MAX_THREADS;
+        let mut rounds = vec![vec![Vec::new(); threads]];
+        let mut next = 0;
+        for chunk in data[3..].chunks_exact(2) {
+            if chunk[0] == END_ROUND {
+                rounds.push(vec![Vec::new(); threads]);
+                continue;
+            }
+            let op = [Op::Get, Op::Insert, Op::Remove][chunk[0] as usize % 3];
+            let key = chunk[1] as usize % (KEYS / threads) * threads + next;
+            rounds.last_mut().unwrap()[next].push((op, key));
+            next = (next + 1) % threads;
+        }
+        Some(Self {
+            subjects: [ports[first], ports[second]],
+            threads,
+            rounds,
+        })
+    }
+}
+
+/// The registered ports of the maps that can run here.
+pub fn ports() -> Vec<(&'static str, Scheme)> {
+    let registry = Registry::<Runner<'_>>::new();
+    let mut ports = Vec::new();
+    for entry in registry.entries().iter().filter(|e| e.kind == Kind::Map) {
+        for &scheme in &[Scheme::EBR, Scheme::PEBR, Scheme::RC] {
+            if entry.port(scheme).is_some() && (scheme != Scheme::RC || tagged::dwcas_available()) {
+                ports.push((entry.name, scheme));
+            }
+        }
+    }
+    ports
+}
+
+/// Runs the program decoded from `data`, and returns the first divergence of its maps. An input
+/// too short for a program passes.
+pub fn run(data: &[u8]) -> Result<(), Divergence> {
+    match Program::decode(data, &ports()) {
+        Some(program) => Runner {
+            program: &program,
+            first: None,
+        }
+        .start(0),
+        None => Ok(()),
+    }
+}
+
+/// Creates the maps of a program, given their types by the registry: the first, and then, with the
+/// first, the second, with which it runs the program.
+struct Runner<'a> {
+    program: &'a Program,
+    first: Option<&'a dyn Subject>,
+}
+
+impl<'a> Runner<'a> {
+    /// Creates the map of `self.program.subjects[index]` with this runner.
+    fn start(&self, index: usize) -> Result<(), Divergence> {
+        let (name, scheme) = self.program.subjects[index];
+        let registry = Registry::new();
+        let run: Run<Self> = registry
+            .get(name)
+            .and_then(|entry| entry.port(scheme))
+            .expect("decoded from the registry")
+            .map;
+        run(self, Prefill::Random)
+    }
+
+    fn with<S: Subject>(&self, subject: S) -> Result<(), Divergence> {
+        match self.first {
+            None => Runner {
+                program: self.program,
+                first: Some(&subject),
+            }
+            .start(1),
+            Some(first) => execute(self.program, [first, &subject]),
+        }
+    }
+}
+
+impl<'a> Driver for Runner<'a> {
+    type Value = String;
+    type Output = Result<(), Divergence>;
+
+    fn ebr_map<M: ebr::ConcurrentMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        self.with(Ebr(M::new()))
+    }
+    fn ebr_set<S: ebr::ConcurrentSet<String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn ebr_slow_key<M: ebr::ConcurrentMap<SlowKey, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn pebr_map<M: pebr::ConcurrentMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        self.with(Pebr(M::new()))
+    }
+    fn pebr_set<S: pebr::ConcurrentSet<String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn pebr_slow_key<M: pebr::ConcurrentMap<SlowKey, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "lists")]
+    fn rc_map<M: rc::ConcurrentMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        self.with(Rc(M::new()))
+    }
+    #[cfg(feature = "lists")]
+    fn rc_slow_key<M: rc::ConcurrentMap<SlowKey, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn ebr_scan<M: ebr::OrderedMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn pebr_scan<M: pebr::OrderedMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn ebr_unguarded<M: ebr::UnguardedMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "trees")]
+    fn ebr_transfers<M: ebr::TransferMap<String, String> + Send + Sync>(
+        &self,
+        _prefill: Prefill,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map(&self, _prefill: Prefill) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map_slow_key(&self, _prefill: Prefill) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    fn ffi_map(&self, _prefill: Prefill) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_bag<B: ebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_bag<B: pebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_priority_queue<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+        &self,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_priority_queue<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+        &self,
+    ) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "counters")]
+    fn ebr_counter<C: ebr::ConcurrentCounter + Send + Sync>(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_mpsc_queue(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_mpsc_queue(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_deque(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_pipeline(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_queue_map(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "lists")]
+    fn ebr_interval_map(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "hash-maps")]
+    fn ebr_bitmap(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+    #[cfg(feature = "counters")]
+    fn ebr_config_map(&self) -> Self::Output {
+        unreachable!("only the maps are compared")
+    }
+}
+
+/// Runs `program` on `subjects`. The workers go on after a divergence, so that they all meet at
+/// the barriers, and the first divergence is returned.
+fn execute(program: &Program, subjects: [&dyn Subject; 2]) -> Result<(), Divergence> {
+    let barrier = &Barrier::new(program.threads);
+    let divergence = &Mutex::new(None);
+    let compare = |round, thread, op, key, value: &str| {
+        let outcomes = [
+            subjects[0].apply(op, key, value),
+            subjects[1].apply(op, key, value),
+        ];
+        if outcomes[0] != outcomes[1] {
+            divergence.lock().unwrap().get_or_insert(Divergence {
+                subjects: program.subjects,
+                round,
+                thread,
+                op,
+                key,
+                outcomes,
+            });
+        }
+    };
+    let compare = &compare;
+
+    thread::scope(|s| {
+        for t in 0..program.threads {
+            s.spawn(move |_| {
+                for (r, round) in program.rounds.iter().enumerate() {
+                    for (i, &(op, key)) in round[t].iter().enumerate() {
+                        compare(r, Some(t), op, key, &format!("{}-{}-{}", t, r, i));
+                    }
+                    barrier.wait();
+                    if t == 0 {
+                        for key in 0..KEYS {
+                            compare(r, None, Op::Get, key, "");
+                        }
+                    }
+                    barrier.wait();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let divergence = divergence.lock().unwrap().take();
+    match divergence {
+        Some(divergence) => Err(divergence),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "lists")]
+    use super::{execute, Ebr, Outcome, Subject};
+    use super::{ports, run, Op, Program, END_ROUND};
+    use rand::prelude::*;
+
+    #[test]
+    fn decode() {
+        let ports = ports();
+        let data = [0, 0, 1, 1, 5, 0, 6, END_ROUND, 0, 2, 7];
+        let program = Program::decode(&data, &ports).unwrap();
+        assert_eq!(program.subjects, [ports[0], ports[1]]);
+        assert_eq!(program.threads, 2);
+        assert_eq!(
+            program.rounds,
+            vec![
+                vec![vec![(Op::Insert, 10)], vec![(Op::Get, 13)]],
+                vec![vec![(Op::Remove, 14)], vec![]],
+            ]
+        );
+        assert!(Program::decode(&data[..2], &ports).is_none());
+    }
+
+    #[test]
+    fn ports_agree() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..64 {
+            let data = (0..256).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+            if let Err(divergence) = run(&data) {
+                panic!("{}", divergence);
+            }
+        }
+    }
+
+    /// Drops the inserts of a key.
+    #[cfg(feature = "lists")]
+    struct Forgetful<S>(S, usize);
+
+    #[cfg(feature = "lists")]
+    impl<S: Subject> Subject for Forgetful<S> {
+        fn apply(&self, op: Op, key: usize, value: &str) -> Outcome {
+            if op == Op::Insert && key == self.1 {
+                return Outcome::Inserted(true);
+            }
+            self.0.apply(op, key, value)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "lists")]
+    fn divergence() {
+        use crate::ebr::{ConcurrentMap, HList, HMList};
+
+        let ports = ports();
+        // Two workers insert keys 0 to 7, and the first then gets its own.
+        let mut data = vec![0, 0, 1];
+        for key in 0..4 {
+            data.extend_from_slice(&[1, key, 1, key]);
+        }
+        data.extend_from_slice(&[END_ROUND, 0]);
+        for key in 0..4 {
+            data.extend_from_slice(&[0, key, 0, key]);
+        }
+        let program = Program::decode(&data, &ports).unwrap();
+
+        let a = Ebr(HList::<String, String>::new());
+        let b = Forgetful(Ebr(HMList::<String, String>::new()), 7);
+        let divergence = execute(&program, [&a, &b]).unwrap_err();
+        assert_eq!((divergence.round, divergence.thread), (0, None));
+        assert_eq!((divergence.op, divergence.key), (Op::Get, 7));
+        assert_eq!(
+            divergence.outcomes,
+            [
+                Outcome::Value(Some("1-0-3".to_string())),
+                Outcome::Value(None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! An artificial cost of destroying each retired object.
+//!
+//! When the cost is set, the destruction of every retired object spins for that many nanoseconds
+//! first, as if the objects had expensive destructors, so that the reclamation itself is heavy. The
+//! schemes differ in who pays for it: with EBR and PEBR the retired objects are destroyed by the
+//! thread that happens to collect them, which is mostly a worker but also the auxiliary thread or
+//! the teardown of the collector at the end of a run, and with RC by the worker that drops the last
+//! reference. Each thread counts the destructions it paid for, and a worker adds its count to the
+//! workers' share when it finishes.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static COST_NS: AtomicU64 = AtomicU64::new(0);
+static WORKERS: AtomicU64 = AtomicU64::new(0);
+static OTHERS: AtomicU64 = AtomicU64::new(0);
+
+struct Local(u64);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        OTHERS.fetch_add(self.0, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(0));
+}
+
+/// Makes the destruction of every retired object take at least `ns` nanoseconds.
+pub fn set_cost(ns: u64) {
+    COST_NS.store(ns, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn cost() -> u64 {
+    COST_NS.load(Ordering::Relaxed)
+}
+
+/// Spins for the cost of a destruction, and counts it for the current thread.
+#[inline]
+pub fn pay() {
+    let ns = cost();
+    if ns == 0 {
+        return;
+    }
+    let delay = Duration::from_nanos(ns);
+    let start = Instant::now();
+    while start.elapsed() < delay {}
+    // The garbage of an exiting thread may be destroyed after its counter.
+    if LOCAL.try_with(|l| l.borrow_mut().0 += 1).is_err() {
+        OTHERS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Adds the destructions the current worker paid for to the workers' share.
+pub fn finish_worker() {
+    if cost() == 0 {
+        return;
+    }
+    let paid = LOCAL.with(|l| std::mem::replace(&mut l.borrow_mut().0, 0));
+    WORKERS.fetch_add(paid, Ordering::Relaxed);
+}
+
+/// Takes the numbers of destructions paid for by the workers and by the other threads, including
+/// the current thread.
+pub fn take() -> (u64, u64) {
+    let own = LOCAL.with(|l| std::mem::replace(&mut l.borrow_mut().0, 0));
+    (
+        WORKERS.swap(0, Ordering::Relaxed),
+        OTHERS.swap(0, Ordering::Relaxed) + own,
+    )
+}
@@ -0,0 +1,1214 @@
+//! An adaptive radix tree (ART) with optimistic lock coupling, after Leis et al., "The ART of
+//! Practical Synchronization" (DaMoN 2016).
+//!
+//! The tree branches on the bytes of the keys, so it keeps the keys in the lexicographic order of
+//! their bytes. An inner node has room for 4, 16, 48 or 256 children and is replaced by a node of
+//! the next size when it is full, or of the previous size when it gets sparse. The common bytes of
+//! the keys below a node are compressed into the prefix of the node, a key that ends at a node is
+//! in its `end` slot, and a subtree of a single key is just the leaf of the key.
+//!
+//! A reader takes no lock: it reads the version of each node before and after reading the node,
+//! and restarts from the root if the version changed meanwhile or the node became obsolete. A
+//! writer locks the node it changes, and also the parent of the node if it replaces the node, in
+//! which case the old node becomes obsolete and is retired. The prefixes are immutable, so an
+//! insert that splits the prefix of a node replaces the node with a copy as well. Thus the updates
+//! retire whole nodes of various sizes, up to an array of 256 children, besides the leaves.
+//!
+//! A node left with a single inner child is kept rather than merged with the child.
+
+use super::concurrent_map::ConcurrentMap;
+use super::ordered_map::OrderedMap;
+use super::unguarded_map::UnguardedMap;
+use crate::backoff::Backoff;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Shared};
+use std::borrow::Cow;
+use std::cmp;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{self, AtomicU8, AtomicUsize, Ordering};
+
+/// The keys of `ARTMap`, as byte strings whose lexicographic order is the order of the keys.
+pub trait ArtKey {
+    fn bytes(&self) -> Cow<'_, [u8]>;
+}
+
+impl ArtKey for String {
+    #[inline]
+    fn bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+}
+
+impl ArtKey for i32 {
+    #[inline]
+    fn bytes(&self) -> Cow<'_, [u8]> {
+        // Flipping the sign bit puts the negative numbers first.
+        Cow::Owned(((*self as u32) ^ (1 << 31)).to_be_bytes().to_vec())
+    }
+}
+
+/// Set while a writer holds the node.
+const LOCKED: usize = 1;
+/// Set when the node is replaced, which is final.
+const OBSOLETE: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    Leaf,
+    Node4,
+    Node16,
+    Node48,
+    Node256,
+}
+
+/// The first field of the leaves and the inner nodes, which tells them apart. Aligned as the
+/// nodes, so that a pointer to the header can be cast to one to its node.
+#[repr(C, align(8))]
+struct Header {
+    kind: Kind,
+}
+
+#[repr(C)]
+struct Leaf<K, V> {
+    header: Header,
+    key: K,
+    value: V,
+}
+
+/// The first field of the inner nodes of every size.
+#[repr(C)]
+struct Inner {
+    header: Header,
+    /// `LOCKED` and `OBSOLETE` in the low bits, and the number of updates in the others.
+    version: AtomicUsize,
+    /// The number of children, not counting `end`.
+    count: AtomicUsize,
+    prefix: Box<[u8]>,
+    /// The leaf of the key that ends right after the prefix.
+    end: Atomic<Header>,
+}
+
+/// The children are sorted by their bytes.
+#[repr(C)]
+struct Node4 {
+    inner: Inner,
+    keys: [AtomicU8; 4],
+    children: [Atomic<Header>; 4],
+}
+
+/// The children are sorted by their bytes.
+#[repr(C)]
+struct Node16 {
+    inner: Inner,
+    keys: [AtomicU8; 16],
+    children: [Atomic<Header>; 16],
+}
+
+#[repr(C)]
+struct Node48 {
+    inner: Inner,
+    /// One plus the slot of the child of each byte, or 0 if the byte has no child.
+    index: [AtomicU8; 256],
+    children: [Atomic<Header>; 48],
+}
+
+#[repr(C)]
+struct Node256 {
+    inner: Inner,
+    children: [Atomic<Header>; 256],
+}
+
+impl Inner {
+    fn new(kind: Kind, prefix: &[u8], end: Shared<'_, Header>) -> Self {
+        Inner {
+            header: Header { kind },
+            version: AtomicUsize::new(0),
+            count: AtomicUsize::new(0),
+            prefix: prefix.into(),
+            end: Atomic::from(end),
+        }
+    }
+
+    /// The node of the size of `self`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the node type of `self.header.kind`.
+    #[inline]
+    unsafe fn cast<T>(&self) -> &T {
+        &*(self as *const Inner as *const T)
+    }
+
+    /// The version of the node once no writer holds it. Fails if the node is obsolete.
+    fn read_lock(&self) -> Result<usize, ()> {
+        let backoff = Backoff::new();
+        loop {
+            let version = self.version.load(Ordering::Acquire);
+            if version & OBSOLETE != 0 {
+                return Err(());
+            }
+            if version & LOCKED == 0 {
+                return Ok(version);
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Fails if the node changed since `version`, i.e., the reads since then may be inconsistent.
+    #[inline]
+    fn check(&self, version: usize) -> Result<(), ()> {
+        atomic::fence(Ordering::Acquire);
+        if self.version.load(Ordering::Relaxed) == version {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Locks the node if it has not changed since `version`.
+    fn upgrade(&self, version: usize) -> Result<(), ()> {
+        self.version
+            .compare_exchange(
+                version,
+                version + LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .map(|_| ())
+            .map_err(|_| ())
+    }
+
+    fn unlock(&self) {
+        self.version.fetch_add(4 - LOCKED, Ordering::Release);
+    }
+
+    fn unlock_obsolete(&self) {
+        self.version.fetch_add(OBSOLETE - LOCKED, Ordering::Release);
+    }
+
+    fn capacity(&self) -> usize {
+        match self.header.kind {
+            Kind::Node4 => 4,
+            Kind::Node16 => 16,
+            Kind::Node48 => 48,
+            Kind::Node256 => 256,
+            Kind::Leaf => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn count(&self) -> usize {
+        // A torn read of a node being changed must not index out of bounds.
+        cmp::min(self.count.load(Ordering::Relaxed), self.capacity())
+    }
+
+    fn is_full(&self) -> bool {
+        self.count() == self.capacity()
+    }
+
+    /// Whether the node should be replaced by a smaller one once it has `count` children.
+    fn is_sparse(&self, count: usize) -> bool {
+        match self.header.kind {
+            Kind::Node4 => false,
+            Kind::Node16 => count <= 3,
+            Kind::Node48 => count <= 12,
+            Kind::Node256 => count <= 37,
+            Kind::Leaf => unreachable!(),
+        }
+    }
+
+    /// The keys and the children of a node with sorted children.
+    fn sorted(&self) -> (&[AtomicU8], &[Atomic<Header>]) {
+        unsafe {
+            match self.header.kind {
+                Kind::Node4 => {
+                    let node = self.cast::<Node4>();
+                    (&node.keys, &node.children)
+                }
+                Kind::Node16 => {
+                    let node = self.cast::<Node16>();
+                    (&node.keys, &node.children)
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn find_child<'g>(&self, byte: u8, guard: &'g Guard) -> Shared<'g, Header> {
+        match self.header.kind {
+            Kind::Node4 | Kind::Node16 => {
+                let (keys, children) = self.sorted();
+                (0..self.count())
+                    .find(|&i| keys[i].load(Ordering::Relaxed) == byte)
+                    .map(|i| children[i].load(Ordering::Acquire, guard))
+                    .unwrap_or_else(Shared::null)
+            }
+            Kind::Node48 => {
+                let node = unsafe { self.cast::<Node48>() };
+                match node.index[byte as usize].load(Ordering::Relaxed) {
+                    0 => Shared::null(),
+                    slot => node.children[slot as usize - 1].load(Ordering::Acquire, guard),
+                }
+            }
+            Kind::Node256 => {
+                let node = unsafe { self.cast::<Node256>() };
+                node.children[byte as usize].load(Ordering::Acquire, guard)
+            }
+            Kind::Leaf => unreachable!(),
+        }
+    }
+
+    /// The children with their bytes, in the order of the bytes.
+    fn children<'g>(&self, guard: &'g Guard) -> Vec<(u8, Shared<'g, Header>)> {
+        match self.header.kind {
+            Kind::Node4 | Kind::Node16 => {
+                let (keys, children) = self.sorted();
+                (0..self.count())
+                    .map(|i| {
+                        (
+                            keys[i].load(Ordering::Relaxed),
+                            children[i].load(Ordering::Acquire, guard),
+                        )
+                    })
+                    .collect()
+            }
+            Kind::Node48 => {
+                let node = unsafe { self.cast::<Node48>() };
+                (0..=255u8)
+                    .filter_map(
+                        |byte| match node.index[byte as usize].load(Ordering::Relaxed) {
+                            0 => None,
+                            slot => Some((
+                                byte,
+                                node.children[slot as usize - 1].load(Ordering::Acquire, guard),
+                            )),
+                        },
+                    )
+                    .collect()
+            }
+            Kind::Node256 => {
+                let node = unsafe { self.cast::<Node256>() };
+                (0..=255u8)
+                    .map(|byte| {
+                        (
+                            byte,
+                            node.children[byte as usize].load(Ordering::Acquire, guard),
+                        )
+                    })
+                    .filter(|(_, child)| !child.is_null())
+                    .collect()
+            }
+            Kind::Leaf => unreachable!(),
+        }
+    }
+
+    /// Adds the child of a byte that has none. The node must be locked and not full.
+    fn add_child(&self, byte: u8, child: Shared<'_, Header>) {
+        let count = self.count();
+        match self.header.kind {
+            Kind::Node4 | Kind::Node16 => {
+                let (keys, children) = self.sorted();
+                let mut i = count;
+                while i > 0 && keys[i - 1].load(Ordering::Relaxed) > byte {
+                    keys[i].store(keys[i - 1].load(Ordering::Relaxed), Ordering::Relaxed);
+                    children[i].store(
+                        children[i - 1].load(Ordering::Relaxed, unsafe { unprotected() }),
+                        Ordering::Release,
+                    );
+                    i -= 1;
+                }
+                keys[i].store(byte, Ordering::Relaxed);
+                children[i].store(child, Ordering::Release);
+            }
+            Kind::Node48 => {
+                let node = unsafe { self.cast::<Node48>() };
+                let slot = node
+                    .children
+                    .iter()
+                    .position(|c| {
+                        c.load(Ordering::Relaxed, unsafe { unprotected() })
+                            .is_null()
+                    })
+                    .unwrap();
+                node.children[slot].store(child, Ordering::Release);
+                node.index[byte as usize].store(slot as u8 + 1, Ordering::Relaxed);
+            }
+            Kind::Node256 => {
+                let node = unsafe { self.cast::<Node256>() };
+                node.children[byte as usize].store(child, Ordering::Release);
+            }
+            Kind::Leaf => unreachable!(),
+        }
+        self.count.store(count + 1, Ordering::Relaxed);
+    }
+
+    /// Replaces the child of a byte. The node must be locked.
+    fn set_child(&self, byte: u8, child: Shared<'_, Header>) {
+        match self.header.kind {
+            Kind::Node4 | Kind::Node16 => {
+                let (keys, children) = self.sorted();
+                let i = (0..self.count())
+                    .find(|&i| keys[i].load(Ordering::Relaxed) == byte)
+                    .unwrap();
+                children[i].store(child, Ordering::Release);
+            }
+            Kind::Node48 => {
+                let node = unsafe { self.cast::<Node48>() };
+                let slot = node.index[byte as usize].load(Ordering::Relaxed) as usize;
+                node.children[slot - 1].store(child, Ordering::Release);
+            }
+            Kind::Node256 => {
+                let node = unsafe { self.cast::<Node256>() };
+                node.children[byte as usize].store(child, Ordering::Release);
+            }
+            Kind::Leaf => unreachable!(),
+        }
+    }
+
+    /// Removes the child of a byte. The node must be locked.
+    fn remove_child(&self, byte: u8) {
+        let count = self.count();
+        match self.header.kind {
+            Kind::Node4 | Kind::Node16 => {
+                let (keys, children) = self.sorted();
+                let mut i = (0..count)
+                    .find(|&i| keys[i].load(Ordering::Relaxed) == byte)
+                    .unwrap();
+                while i + 1 < count {
+                    keys[i].store(keys[i + 1].load(Ordering::Relaxed), Ordering::Relaxed);
+                    children[i].store(
+                        children[i + 1].load(Ordering::Relaxed, unsafe { unprotected() }),
+                        Ordering::Release,
+                    );
+                    i += 1;
+                }
+                children[count - 1].store(Shared::null(), Ordering::Release);
+            }
+            Kind::Node48 => {
+                let node = unsafe { self.cast::<Node48>() };
+                let slot = node.index[byte as usize].swap(0, Ordering::Relaxed) as usize;
+                node.children[slot - 1].store(Shared::null(), Ordering::Release);
+            }
+            Kind::Node256 => {
+                let node = unsafe { self.cast::<Node256>() };
+                node.children[byte as usize].store(Shared::null(), Ordering::Release);
+            }
+            Kind::Leaf => unreachable!(),
+        }
+        self.count.store(count - 1, Ordering::Relaxed);
+    }
+}
+
+/// A new node of the smallest size that holds `children`, which are sorted by their bytes.
+fn build<'g>(
+    prefix: &[u8],
+    end: Shared<'g, Header>,
+    children: &[(u8, Shared<'g, Header>)],
+) -> Shared<'g, Header> {
+    // The arrays of atomics are zeroed, i.e., empty.
+    let ptr = match children.len() {
+        0..=4 => Box::into_raw(Box::new(Node4 {
+            inner: Inner::new(Kind::Node4, prefix, end),
+            keys: unsafe { mem::zeroed() },
+            children: unsafe { mem::zeroed() },
+        })) as *const Header,
+        5..=16 => Box::into_raw(Box::new(Node16 {
+            inner: Inner::new(Kind::Node16, prefix, end),
+            keys: unsafe { mem::zeroed() },
+            children: unsafe { mem::zeroed() },
+        })) as *const Header,
+        17..=48 => Box::into_raw(Box::new(Node48 {
+            inner: Inner::new(Kind::Node48, prefix, end),
+            index: unsafe { mem::zeroed() },
+            children: unsafe { mem::zeroed() },
+        })) as *const Header,
+        _ => Box::into_raw(Box::new(Node256 {
+            inner: Inner::new(Kind::Node256, prefix, end),
+            children: unsafe { mem::zeroed() },
+        })) as *const Header,
+    };
+    let inner = unsafe { &*(ptr as *const Inner) };
+    for &(byte, child) in children {
+        inner.add_child(byte, child);
+    }
+    Shared::from(ptr)
+}
+
+/// The inner node of `node`, which must not be a leaf.
+#[inline]
+unsafe fn as_inner(node: Shared<'_, Header>) -> &Inner {
+    &*(node.as_raw() as *const Inner)
+}
+
+#[inline]
+unsafe fn as_leaf<'g, K, V>(node: Shared<'g, Header>) -> &'g Leaf<K, V> {
+    &*(node.as_raw() as *const Leaf<K, V>)
+}
+
+#[inline]
+fn is_leaf(node: Shared<'_, Header>) -> bool {
+    unsafe { node.deref() }.kind == Kind::Leaf
+}
+
+/// Retires an inner node that is no longer reachable, but not its children.
+unsafe fn retire_inner(node: &Inner, guard: &Guard) {
+    op_stats::retire();
+    let ptr = node as *const Inner;
+    match node.header.kind {
+        Kind::Node4 => guard.retire(Shared::from(ptr as *const Node4)),
+        Kind::Node16 => guard.retire(Shared::from(ptr as *const Node16)),
+        Kind::Node48 => guard.retire(Shared::from(ptr as *const Node48)),
+        Kind::Node256 => guard.retire(Shared::from(ptr as *const Node256)),
+        Kind::Leaf => unreachable!(),
+    }
+}
+
+/// Frees the subtree of `node`.
+unsafe fn free<K, V>(node: Shared<'_, Header>) {
+    if is_leaf(node) {
+        drop(Box::from_raw(node.as_raw() as *mut Leaf<K, V>));
+        return;
+    }
+    let inner = as_inner(node);
+    free_children::<K, V>(inner);
+    let ptr = node.as_raw();
+    match inner.header.kind {
+        Kind::Node4 => drop(Box::from_raw(ptr as *mut Node4)),
+        Kind::Node16 => drop(Box::from_raw(ptr as *mut Node16)),
+        Kind::Node48 => drop(Box::from_raw(ptr as *mut Node48)),
+        Kind::Node256 => drop(Box::from_raw(ptr as *mut Node256)),
+        Kind::Leaf => unreachable!(),
+    }
+}
+
+unsafe fn free_children<K, V>(node: &Inner) {
+    let guard = unprotected();
+    let end = node.end.load(Ordering::Relaxed, guard);
+    if !end.is_null() {
+        free::<K, V>(end);
+    }
+    for (_, child) in node.children(guard) {
+        free::<K, V>(child);
+    }
+}
+
+/// The length of the common prefix of `a` and `b`.
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+pub struct ARTMap<K, V> {
+    /// The root is never replaced, and its prefix is empty.
+    root: Box<Node256>,
+    len: StripedCounter,
+    _marker: PhantomData<(K, V)>,
+}
+
+unsafe impl<K: Send, V: Send> Send for ARTMap<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for ARTMap<K, V> {}
+
+impl<K, V> Default for ARTMap<K, V>
+where
+    K: ArtKey,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for ARTMap<K, V> {
+    fn drop(&mut self) {
+        unsafe { free_children::<K, V>(&self.root.inner) };
+    }
+}
+
+impl<K, V> ARTMap<K, V>
+where
+    K: ArtKey,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        ARTMap {
+            root: Box::new(Node256 {
+                inner: Inner::new(Kind::Node256, &[], Shared::null()),
+                children: unsafe { mem::zeroed() },
+            }),
+            len: StripedCounter::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn leaf<'g>(node: Shared<'g, Header>) -> &'g Leaf<K, V> {
+        unsafe { as_leaf(node) }
+    }
+
+    /// Replaces `node`, read at `version`, with the node that `new` makes of it once it is
+    /// locked, in the slot of `byte` in `parent`, read at `parent_version`. Then retires `node`.
+    fn replace<'g, F>(
+        parent: &Inner,
+        parent_version: usize,
+        byte: u8,
+        node: &'g Inner,
+        version: usize,
+        guard: &'g Guard,
+        new: F,
+    ) -> Result<(), ()>
+    where
+        F: FnOnce(&'g Inner) -> Shared<'g, Header>,
+    {
+        parent.upgrade(parent_version)?;
+        if node.upgrade(version).is_err() {
+            parent.unlock();
+            return Err(());
+        }
+        parent.set_child(byte, new(node));
+        node.unlock_obsolete();
+        parent.unlock();
+        unsafe { retire_inner(node, guard) };
+        Ok(())
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let key = key.bytes();
+        loop {
+            if let Ok(found) = self.try_get(&key, guard) {
+                return found.map(|leaf| &leaf.value);
+            }
+            op_stats::retry();
+        }
+    }
+
+    fn try_get<'g>(&'g self, key: &[u8], guard: &'g Guard) -> Result<Option<&'g Leaf<K, V>>, ()> {
+        let mut node = &self.root.inner;
+        let mut version = node.read_lock()?;
+        let mut depth = 0;
+        loop {
+            if !key[depth..].starts_with(&node.prefix) {
+                node.check(version)?;
+                return Ok(None);
+            }
+            depth += node.prefix.len();
+            let child = if depth == key.len() {
+                node.end.load(Ordering::Acquire, guard)
+            } else {
+                node.find_child(key[depth], guard)
+            };
+            node.check(version)?;
+            if child.is_null() {
+                return Ok(None);
+            }
+            if is_leaf(child) {
+                let leaf = Self::leaf(child);
+                return Ok(if *leaf.key.bytes() == *key {
+                    Some(leaf)
+                } else {
+                    None
+                });
+            }
+            let next = unsafe { as_inner(child) };
+            let next_version = next.read_lock()?;
+            node.check(version)?;
+            node = next;
+            version = next_version;
+            depth += 1;
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let leaf = Box::into_raw(Box::new(Leaf {
+            header: Header { kind: Kind::Leaf },
+            key,
+            value,
+        }));
+        let key = unsafe { (*leaf).key.bytes() };
+        let inserted = loop {
+            if let Ok(inserted) = self.try_insert(&key, leaf, guard) {
+                break inserted;
+            }
+            op_stats::retry();
+        };
+        drop(key);
+        if inserted {
+            self.len.add(1);
+        } else {
+            drop(unsafe { Box::from_raw(leaf) });
+        }
+        inserted
+    }
+
+    fn try_insert(&self, key: &[u8], leaf: *mut Leaf<K, V>, guard: &Guard) -> Result<bool, ()> {
+        let leaf = Shared::from(leaf as *const Header);
+        // The parent of the node, its version, and the byte of the node in it.
+        let mut parent: Option<(&Inner, usize, u8)> = None;
+        let mut node = &self.root.inner;
+        let mut version = node.read_lock()?;
+        let mut depth = 0;
+        loop {
+            let prefix = &node.prefix;
+            let matched = common_prefix(prefix, &key[depth..]);
+            if matched < prefix.len() {
+                // The key leaves the path in the prefix, which is split: the node is replaced by
+                // a node with the common part of the prefix, and a copy of the node with the rest
+                // of the prefix and the leaf as its children.
+                let (parent, parent_version, byte) = parent.unwrap();
+                let split = depth + matched;
+                Self::replace(parent, parent_version, byte, node, version, guard, |node| {
+                    let copy = build(
+                        &prefix[matched + 1..],
+                        node.end.load(Ordering::Relaxed, guard),
+                        &node.children(guard),
+                    );
+                    let mut children = vec![(prefix[matched], copy)];
+                    let end = if split == key.len() {
+                        leaf
+                    } else {
+                        children.push((key[split], leaf));
+                        children.sort_by_key(|&(byte, _)| byte);
+                        Shared::null()
+                    };
+                    build(&prefix[..matched], end, &children)
+                })?;
+                return Ok(true);
+            }
+            depth += prefix.len();
+
+            if depth == key.len() {
+                let end = node.end.load(Ordering::Acquire, guard);
+                node.check(version)?;
+                if !end.is_null() {
+                    return Ok(false);
+                }
+                node.upgrade(version)?;
+                node.end.store(leaf, Ordering::Release);
+                node.unlock();
+                return Ok(true);
+            }
+
+            let byte = key[depth];
+            let child = node.find_child(byte, guard);
+            let full = node.is_full();
+            node.check(version)?;
+            if child.is_null() {
+                if full {
+                    // The root has room for every byte.
+                    let (parent, parent_version, parent_byte) = parent.unwrap();
+                    Self::replace(
+                        parent,
+                        parent_version,
+                        parent_byte,
+                        node,
+                        version,
+                        guard,
+                        |node| {
+                            let mut children = node.children(guard);
+                            let i = children.iter().position(|&(b, _)| b > byte);
+                            children.insert(i.unwrap_or_else(|| children.len()), (byte, leaf));
+                            build(prefix, node.end.load(Ordering::Relaxed, guard), &children)
+                        },
+                    )?;
+                } else {
+                    node.upgrade(version)?;
+                    node.add_child(byte, leaf);
+                    node.unlock();
+                }
+                return Ok(true);
+            }
+
+            if is_leaf(child) {
+                let other = Self::leaf(child).key.bytes();
+                if *other == *key {
+                    return Ok(false);
+                }
+                // The leaf is expanded into a node with the common part of the two keys as its
+                // prefix and the two leaves as its children.
+                node.upgrade(version)?;
+                let depth = depth + 1;
+                let split = depth + common_prefix(&key[depth..], &other[depth..]);
+                let mut end = Shared::null();
+                let mut children = Vec::with_capacity(2);
+                for &(k, l) in &[(key, leaf), (&other[..], child)] {
+                    if split == k.len() {
+                        end = l;
+                    } else {
+                        children.push((k[split], l));
+                    }
+                }
+                children.sort_by_key(|&(byte, _)| byte);
+                node.set_child(byte, build(&key[depth..split], end, &children));
+                node.unlock();
+                return Ok(true);
+            }
+
+            let next = unsafe { as_inner(child) };
+            let next_version = next.read_lock()?;
+            node.check(version)?;
+            parent = Some((node, version, byte));
+            node = next;
+            version = next_version;
+            depth += 1;
+        }
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove_get(key, guard).cloned()
+    }
+
+    /// Like `remove`, but returns the value in the retired leaf.
+    pub fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let key = key.bytes();
+        let leaf = loop {
+            if let Ok(leaf) = self.try_remove(&key, guard) {
+                break leaf?;
+            }
+            op_stats::retry();
+        };
+        self.len.add(-1);
+        op_stats::retire();
+        unsafe { guard.retire(Shared::from(leaf.as_raw() as *const Leaf<K, V>)) };
+        Some(&Self::leaf(leaf).value)
+    }
+
+    /// Unlinks the leaf of `key`, if any.
+    fn try_remove<'g>(
+        &self,
+        key: &[u8],
+        guard: &'g Guard,
+    ) -> Result<Option<Shared<'g, Header>>, ()> {
+        let mut parent: Option<(&Inner, usize, u8)> = None;
+        let mut node = &self.root.inner;
+        let mut version = node.read_lock()?;
+        let mut depth = 0;
+        loop {
+            if !key[depth..].starts_with(&node.prefix) {
+                node.check(version)?;
+                return Ok(None);
+            }
+            depth += node.prefix.len();
+            let byte = key.get(depth).cloned();
+            let end = node.end.load(Ordering::Acquire, guard);
+            let child = match byte {
+                None => end,
+                Some(byte) => node.find_child(byte, guard),
+            };
+            let count = node.count();
+            node.check(version)?;
+            if child.is_null() {
+                return Ok(None);
+            }
+
+            if !is_leaf(child) {
+                let next = unsafe { as_inner(child) };
+                let next_version = next.read_lock()?;
+                node.check(version)?;
+                parent = Some((node, version, byte.unwrap()));
+                node = next;
+                version = next_version;
+                depth += 1;
+                continue;
+            }
+            if *Self::leaf(child).key.bytes() != *key {
+                return Ok(None);
+            }
+
+            let (parent, parent_version, parent_byte) = match parent {
+                Some(parent) => parent,
+                None => {
+                    // The root keeps its size.
+                    node.upgrade(version)?;
+                    match byte {
+                        None => node.end.store(Shared::null(), Ordering::Release),
+                        Some(byte) => node.remove_child(byte),
+                    }
+                    node.unlock();
+                    return Ok(Some(child));
+                }
+            };
+            let entries = count + !end.is_null() as usize;
+            let others = if entries == 2 {
+                node.children(guard)
+            } else {
+                Vec::new()
+            };
+            node.check(version)?;
+            let last = match (byte, others.as_slice()) {
+                (_, []) => None,
+                (None, [(_, last)]) => Some(*last),
+                (Some(_), _) if !end.is_null() => Some(end),
+                (Some(byte), others) => others.iter().find(|&&(b, _)| b != byte).map(|&(_, c)| c),
+                _ => None,
+            };
+            match last {
+                // The node would be left with a single leaf, which takes the place of the node.
+                Some(last) if is_leaf(last) => Self::replace(
+                    parent,
+                    parent_version,
+                    parent_byte,
+                    node,
+                    version,
+                    guard,
+                    |_| last,
+                )?,
+                _ => match byte {
+                    Some(byte) if node.is_sparse(count - 1) => Self::replace(
+                        parent,
+                        parent_version,
+                        parent_byte,
+                        node,
+                        version,
+                        guard,
+                        |node| {
+                            let mut children = node.children(guard);
+                            children.retain(|&(b, _)| b != byte);
+                            build(&node.prefix, end, &children)
+                        },
+                    )?,
+                    _ => {
+                        node.upgrade(version)?;
+                        match byte {
+                            None => node.end.store(Shared::null(), Ordering::Release),
+                            Some(byte) => node.remove_child(byte),
+                        }
+                        node.unlock();
+                    }
+                },
+            }
+            return Ok(Some(child));
+        }
+    }
+
+    /// The entry with the smallest key greater than `key`, or with the largest key less than
+    /// `key` if `rev`.
+    fn bound<'g>(&'g self, key: &K, rev: bool, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let key = key.bytes();
+        loop {
+            let root = &self.root.inner;
+            let found = root
+                .read_lock()
+                .and_then(|version| Self::bound_in(root, version, &key, 0, rev, guard));
+            if let Ok(found) = found {
+                return found.map(|leaf| (&leaf.key, &leaf.value));
+            }
+            op_stats::retry();
+        }
+    }
+
+    /// `bound` in the subtree of `node`, read at `version`, at `depth` bytes of the keys.
+    fn bound_in<'g>(
+        node: &'g Inner,
+        version: usize,
+        key: &[u8],
+        depth: usize,
+        rev: bool,
+        guard: &'g Guard,
+    ) -> Result<Option<&'g Leaf<K, V>>, ()> {
+        let prefix = &node.prefix;
+        let rest = &key[depth..];
+        let n = cmp::min(prefix.len(), rest.len());
+        // If the key ends in the prefix, the keys of the subtree are greater.
+        let order = prefix[..n].cmp(&rest[..n]).then(prefix.len().cmp(&n));
+        match (order, rev) {
+            (cmp::Ordering::Greater, false) => return Self::extreme(node, version, false, guard),
+            (cmp::Ordering::Less, true) => return Self::extreme(node, version, true, guard),
+            (cmp::Ordering::Equal, _) => {}
+            _ => {
+                node.check(version)?;
+                return Ok(None);
+            }
+        }
+
+        let depth = depth + prefix.len();
+        let end = node.end.load(Ordering::Acquire, guard);
+        let children = node.children(guard);
+        node.check(version)?;
+        if depth == key.len() {
+            // The key is at the end, and the children are greater.
+            return if rev {
+                Ok(None)
+            } else {
+                Self::first(&children, false, guard)
+            };
+        }
+
+        let byte = key[depth];
+        let i = children.iter().position(|&(b, _)| b >= byte);
+        let i = i.unwrap_or_else(|| children.len());
+        if let Some(&(b, child)) = children.get(i) {
+            if b == byte {
+                if is_leaf(child) {
+                    let leaf = Self::leaf(child);
+                    if (*leaf.key.bytes() < *key) == rev && *leaf.key.bytes() != *key {
+                        return Ok(Some(leaf));
+                    }
+                } else {
+                    let child = unsafe { as_inner(child) };
+                    let child_version = child.read_lock()?;
+                    if let Some(leaf) =
+                        Self::bound_in(child, child_version, key, depth + 1, rev, guard)?
+                    {
+                        return Ok(Some(leaf));
+                    }
+                }
+            }
+        }
+        if rev {
+            // The key at the end is a proper prefix of the key.
+            if let Some(leaf) = Self::first(&children[..i], true, guard)? {
+                return Ok(Some(leaf));
+            }
+            Ok(if end.is_null() {
+                None
+            } else {
+                Some(Self::leaf(end))
+            })
+        } else {
+            let after = match children.get(i) {
+                Some(&(b, _)) if b == byte => i + 1,
+                _ => i,
+            };
+            Self::first(&children[after..], false, guard)
+        }
+    }
+
+    /// The smallest leaf of the first of `children` that has one, or the largest of the last if
+    /// `rev`.
+    fn first<'g>(
+        children: &[(u8, Shared<'g, Header>)],
+        rev: bool,
+        guard: &'g Guard,
+    ) -> Result<Option<&'g Leaf<K, V>>, ()> {
+        let visit = |child: Shared<'g, Header>| {
+            if is_leaf(child) {
+                return Ok(Some(Self::leaf(child)));
+            }
+            let child = unsafe { as_inner(child) };
+            let version = child.read_lock()?;
+            Self::extreme(child, version, rev, guard)
+        };
+        if rev {
+            for &(_, child) in children.iter().rev() {
+                if let Some(leaf) = visit(child)? {
+                    return Ok(Some(leaf));
+                }
+            }
+        } else {
+            for &(_, child) in children {
+                if let Some(leaf) = visit(child)? {
+                    return Ok(Some(leaf));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The smallest leaf in the subtree of `node`, read at `version`, or the largest if `rev`.
+    fn extreme<'g>(
+        node: &'g Inner,
+        version: usize,
+        rev: bool,
+        guard: &'g Guard,
+    ) -> Result<Option<&'g Leaf<K, V>>, ()> {
+        let end = node.end.load(Ordering::Acquire, guard);
+        let children = node.children(guard);
+        node.check(version)?;
+        // The key at the end is less than the keys of the children.
+        if !rev && !end.is_null() {
+            return Ok(Some(Self::leaf(end)));
+        }
+        if let Some(leaf) = Self::first(&children, rev, guard)? {
+            return Ok(Some(leaf));
+        }
+        Ok(if end.is_null() {
+            None
+        } else {
+            Some(Self::leaf(end))
+        })
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for ARTMap<K, V>
+where
+    K: ArtKey,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        self.insert(key, value, guard)
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove(key, guard)
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.remove_get(key, guard)
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// The get validates the versions of the nodes it reads instead of locking them.
+impl<K, V> UnguardedMap<K, V> for ARTMap<K, V>
+where
+    K: ArtKey,
+    V: Clone,
+{
+}
+
+impl<K, V> OrderedMap<K, V> for ARTMap<K, V>
+where
+    K: ArtKey,
+    V: Clone,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.bound(key, false, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.bound(key, true, guard)
+    }
+}
+
+register_maps! {
+    ebr;
+    ART => ARTMap, Random, without slow_key, with scan unguarded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ARTMap;
+    use crate::ebr::{concurrent_map, ordered_map, unguarded_map, OrderedMap};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+
+    #[test]
+    fn smoke_art() {
+        concurrent_map::tests::smoke::<ARTMap<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_art() {
+        concurrent_map::tests::shadow::<ARTMap<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_art() {
+        concurrent_map::tests::remove_get::<ARTMap<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_art() {
+        ordered_map::tests::ordered::<ARTMap<i32, String>>();
+    }
+
+    #[test]
+    fn unguarded_art() {
+        unguarded_map::tests::unguarded::<ARTMap<i32, String>>();
+    }
+
+    #[test]
+    fn strings() {
+        // Keys that are prefixes of each other, and long common prefixes that are split.
+        let map = ARTMap::new();
+        let guard = &pin();
+        let keys = [
+            "", "a", "ab", "abc", "abd", "abcdefgh", "abcdefgi", "b", "ba", "0000017",
+        ];
+        for key in keys.iter() {
+            assert!(map.insert(key.to_string(), key.len(), guard));
+            assert!(!map.insert(key.to_string(), 0, guard));
+        }
+        for key in keys.iter() {
+            assert_eq!(map.get(&key.to_string(), guard), Some(&key.len()));
+        }
+        assert_eq!(map.get(&"abcdefg".to_string(), guard), None);
+        assert_eq!(map.get(&"abe".to_string(), guard), None);
+
+        let mut sorted = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        sorted.sort();
+        for w in sorted.windows(2) {
+            assert_eq!(map.next(&w[0], guard).unwrap().0, &w[1]);
+            assert_eq!(map.prev(&w[1], guard).unwrap().0, &w[0]);
+        }
+        assert_eq!(
+            map.next(&"abcdefgh0".to_string(), guard).unwrap().0,
+            "abcdefgi"
+        );
+        assert_eq!(map.prev(&"abcd".to_string(), guard).unwrap().0, "abc");
+
+        for key in keys.iter() {
+            assert_eq!(map.remove(&key.to_string(), guard), Some(key.len()));
+            assert_eq!(map.remove(&key.to_string(), guard), None);
+            assert_eq!(map.get(&key.to_string(), guard), None);
+        }
+        assert_eq!(map.next(&String::new(), guard), None);
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        // The keys share their first three bytes, so the node of their last bytes grows to 256
+        // children and shrinks back.
+        let map = ARTMap::new();
+        let guard = &pin();
+        let key = |b: i32| 0x0102_0300 | b;
+        for b in 0..=255 {
+            assert!(map.insert(key(b), b, guard));
+        }
+        for b in 0..=255 {
+            assert_eq!(map.get(&key(b), guard), Some(&b));
+        }
+        for b in (0..=255).filter(|b| b % 8 != 0) {
+            assert_eq!(map.remove(&key(b), guard), Some(b));
+        }
+        for b in 0..=255 {
+            let expected = if b % 8 == 0 { Some(&b) } else { None };
+            assert_eq!(map.get(&key(b), guard), expected);
+        }
+        assert_eq!(map.len.approx(), 32);
+    }
+
+    #[test]
+    fn concurrent_splits() {
+        // The keys that are never removed must stay while the other keys grow, shrink and split
+        // the nodes around them.
+        let map = &ARTMap::new();
+        for i in 0..500 {
+            assert!(map.insert(format!("{:04}", i * 2), i, &pin()));
+        }
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for i in 0..20000 {
+                        let key = format!("{:04}{}", (i * 7 + t) % 1000, t);
+                        if i % 2 == 0 {
+                            map.insert(key, i, &pin());
+                        } else {
+                            map.remove(&key, &pin());
+                        }
+                        let stable = (i * 13 + t) % 500;
+                        let guard = &pin();
+                        assert_eq!(map.get(&format!("{:04}", stable * 2), guard), Some(&stable));
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+use super::concurrent_bag::ConcurrentBag;
+use super::concurrent_stack::ConcurrentStack;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned};
+
+use std::cmp;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug)]
+struct Node<T> {
+    data: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+/// Treiber stack of the values added by the threads owning a slot of the bag.
+pub struct Stack<T> {
+    head: Atomic<Node<T>>,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack {
+            head: Atomic::null(),
+        }
+    }
+
+    pub fn push(&self, data: T, guard: &Guard) {
+        let mut node = Owned::new(Node {
+            data: ManuallyDrop::new(data),
+            next: Atomic::null(),
+        });
+
+        loop {
+            let head = self.head.load(Ordering::Relaxed, guard);
+            node.next.store(head, Ordering::Relaxed);
+            match self
+                .head
+                .compare_and_set(head, node, Ordering::Release, guard)
+            {
+                Ok(_) => return,
+                Err(e) => node = e.new,
+            }
+        }
+    }
+
+    pub fn pop(&self, guard: &Guard) -> Option<T> {
+        loop {
+            let head = self.head.load(Ordering::Acquire, guard);
+            let head_ref = some_or!(unsafe { head.as_ref() }, return None);
+            let next = head_ref.next.load(Ordering::Relaxed, guard);
+
+            if self
+                .head
+                .compare_and_set(head, next, Ordering::Relaxed, guard)
+                .is_ok()
+            {
+                unsafe {
+                    let data = ptr::read(&head_ref.data);
+                    op_stats::retire();
+                    guard.retire(head);
+                    return Some(ManuallyDrop::into_inner(data));
+                }
+            }
+        }
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+
+            while !curr.is_null() {
+                let curr_ref = curr.deref_mut();
+                let next = curr_ref.next.load(Ordering::Relaxed, unprotected());
+                ManuallyDrop::drop(&mut curr_ref.data);
+                drop(curr.into_owned());
+                curr = next;
+            }
+        }
+    }
+}
+
+/// A lock-free bag consisting of per-thread stacks. A thread adds values to its own stack, and
+/// steals values from the other threads' stacks when its own stack is empty.
+pub struct Bag<T> {
+    stacks: Vec<Stack<T>>,
+    /// The number of handles created so far. Threads are assigned to the stacks round-robin.
+    registered: AtomicUsize,
+    len: StripedCounter,
+}
+
+pub struct BagHandle {
+    /// The index of the thread's own stack.
+    index: usize,
+}
+
+impl<T> Bag<T> {
+    pub fn with_slots(n: usize) -> Self {
+        assert!(n > 0);
+        let mut stacks = Vec::with_capacity(n);
+        for _ in 0..n {
+            stacks.push(Stack::new());
+        }
+
+        Bag {
+            stacks,
+            registered: AtomicUsize::new(0),
+            len: StripedCounter::new(),
+        }
+    }
+
+    pub fn handle(&self) -> BagHandle {
+        let index = self.registered.fetch_add(1, Ordering::Relaxed) % self.stacks.len();
+        BagHandle { index }
+    }
+
+    pub fn add(&self, handle: &mut BagHandle, value: T, guard: &Guard) {
+        self.stacks[handle.index].push(value, guard);
+    }
+
+    pub fn try_remove_any(&self, handle: &mut BagHandle, guard: &Guard) -> Option<T> {
+        if let Some(value) = self.stacks[handle.index].pop(guard) {
+            return Some(value);
+        }
+
+        // Steal from the other stacks that are in use.
+        let slots = cmp::min(self.registered.load(Ordering::Relaxed), self.stacks.len());
+        for i in 1..slots {
+            let victim = (handle.index + i) % slots;
+            if let Some(value) = self.stacks[victim].pop(guard) {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+impl<T> ConcurrentStack<T> for Stack<T> {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn push(&self, value: T, guard: &Guard) {
+        self.push(value, guard);
+    }
+    #[inline]
+    fn try_pop(&self, guard: &Guard) -> Option<T> {
+        self.pop(guard)
+    }
+}
+
+impl<T> ConcurrentBag<T> for Bag<T> {
+    type Handle = BagHandle;
+
+    fn new() -> Self {
+        Self::with_slots(256)
+    }
+
+    fn handle(&self) -> Self::Handle {
+        self.handle()
+    }
+
+    #[inline]
+    fn add(&self, handle: &mut Self::Handle, value: T, guard: &Guard) {
+        self.add(handle, value, guard);
+        self.len.add(1);
+    }
+    #[inline]
+    fn try_remove_any(&self, handle: &mut Self::Handle, guard: &Guard) -> Option<T> {
+        let removed = self.try_remove_any(handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// Registers the bag.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("Bag", Scheme::EBR, |d: &D, _| d.ebr_bag::<Bag<String>>());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bag, Stack};
+    use crate::ebr::{concurrent_bag, concurrent_stack};
+
+    #[test]
+    fn smoke_bag() {
+        concurrent_bag::tests::smoke::<Bag<i32>>();
+    }
+
+    #[test]
+    fn smoke_stack() {
+        concurrent_stack::tests::smoke::<Stack<usize>>();
+    }
+
+    #[test]
+    fn lifo_stack() {
+        concurrent_stack::tests::lifo::<Stack<usize>>();
+    }
+}
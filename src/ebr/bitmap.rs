@@ -0,0 +1,266 @@
+//! A concurrent bitmap of a fixed range of bits, whose storage is allocated and retired by segments.
+//!
+//! The range is split into segments of `SEGMENT_BITS` bits. A segment is allocated by the first set
+//! of a bit in it, and retired once its last set bit is cleared, so the bitmap takes memory only
+//! for the regions in use, and retires a segment, not a node, when a region empties. Unlike the
+//! maps, which allocate and retire a node per element, it makes little garbage: the updates of the
+//! bits of a live segment allocate nothing.
+//!
+//! A segment counts its reservations, which are its set bits and the sets in progress. A set
+//! reserves a bit before it sets it, and the clear of a bit releases its reservation. The release
+//! of the last reservation marks the segment retired, after which no set may reserve a bit in it,
+//! and unlinks it. A set that finds the segment retired helps to unlink it and retries, allocating a
+//! new segment. The segment is thus unlinked only when all its bits are clear, and whoever unlinks
+//! it retires it. The tests of the bits read the segments that are retired concurrently, which is
+//! what the reclamation scheme protects.
+
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// The words of a segment, a cache line of them.
+const WORDS: usize = 8;
+pub const SEGMENT_BITS: usize = WORDS * 64;
+
+/// Set in the reservations of a segment once it is empty and about to be unlinked.
+const RETIRED: usize = !(usize::max_value() >> 1);
+
+#[derive(Default)]
+struct Segment {
+    reservations: AtomicUsize,
+    words: [AtomicU64; WORDS],
+}
+
+impl Segment {
+    /// Reserves a bit, unless the segment is retired.
+    fn reserve(&self) -> bool {
+        let mut reservations = self.reservations.load(Ordering::Relaxed);
+        loop {
+            if reservations & RETIRED != 0 {
+                return false;
+            }
+            match self.reservations.compare_exchange_weak(
+                reservations,
+                reservations + 1,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(r) => reservations = r,
+            }
+        }
+    }
+}
+
+pub struct Bitmap {
+    segments: Box<[Atomic<Segment>]>,
+    len: StripedCounter,
+    /// The segments allocated and retired so far.
+    allocated: AtomicUsize,
+    retired: AtomicUsize,
+}
+
+impl Drop for Bitmap {
+    fn drop(&mut self) {
+        unsafe {
+            for slot in self.segments.iter() {
+                let segment = slot.load(Ordering::Relaxed, unprotected());
+                if !segment.is_null() {
+                    drop(segment.into_owned());
+                }
+            }
+        }
+    }
+}
+
+impl Bitmap {
+    /// Creates a bitmap of `bits` bits, all clear, without any segment.
+    pub fn new(bits: usize) -> Self {
+        let segments = (bits + SEGMENT_BITS - 1) / SEGMENT_BITS;
+        Self {
+            segments: (0..segments).map(|_| Atomic::null()).collect(),
+            len: StripedCounter::new(),
+            allocated: AtomicUsize::new(0),
+            retired: AtomicUsize::new(0),
+        }
+    }
+
+    /// The slot of the segment of `bit`, and the index and the mask of its word in the segment.
+    #[inline]
+    fn position(&self, bit: usize) -> (&Atomic<Segment>, usize, u64) {
+        let offset = bit % SEGMENT_BITS;
+        (
+            &self.segments[bit / SEGMENT_BITS],
+            offset / 64,
+            1 << (offset % 64),
+        )
+    }
+
+    pub fn test(&self, bit: usize, guard: &Guard) -> bool {
+        let (slot, word, mask) = self.position(bit);
+        let segment = slot.load(Ordering::Acquire, guard);
+        match unsafe { segment.as_ref() } {
+            Some(s) => s.words[word].load(Ordering::Acquire) & mask != 0,
+            None => false,
+        }
+    }
+
+    /// Sets `bit`. Returns whether it was clear.
+    pub fn set(&self, bit: usize, guard: &Guard) -> bool {
+        let (slot, word, mask) = self.position(bit);
+        loop {
+            let segment = slot.load(Ordering::Acquire, guard);
+            let segment_ref = match unsafe { segment.as_ref() } {
+                Some(s) => s,
+                None => {
+                    let new = Owned::new(Segment::default());
+                    new.reservations.store(1, Ordering::Relaxed);
+                    new.words[word].store(mask, Ordering::Relaxed);
+                    if slot
+                        .compare_and_set(Shared::null(), new, Ordering::AcqRel, guard)
+                        .is_ok()
+                    {
+                        self.allocated.fetch_add(1, Ordering::Relaxed);
+                        self.len.add(1);
+                        return true;
+                    }
+                    continue;
+                }
+            };
+            if !segment_ref.reserve() {
+                self.unlink(slot, segment, guard);
+                continue;
+            }
+            if segment_ref.words[word].fetch_or(mask, Ordering::AcqRel) & mask != 0 {
+                self.release(slot, segment, guard);
+                return false;
+            }
+            self.len.add(1);
+            return true;
+        }
+    }
+
+    /// Clears `bit`. Returns whether it was set.
+    pub fn clear(&self, bit: usize, guard: &Guard) -> bool {
+        let (slot, word, mask) = self.position(bit);
+        let segment = slot.load(Ordering::Acquire, guard);
+        let segment_ref = match unsafe { segment.as_ref() } {
+            Some(s) => s,
+            None => return false,
+        };
+        if segment_ref.words[word].fetch_and(!mask, Ordering::AcqRel) & mask == 0 {
+            return false;
+        }
+        self.len.add(-1);
+        self.release(slot, segment, guard);
+        true
+    }
+
+    /// Releases a reservation of `segment`, and retires the segment if it was the last one.
+    fn release(&self, slot: &Atomic<Segment>, segment: Shared<'_, Segment>, guard: &Guard) {
+        let segment_ref = unsafe { segment.deref() };
+        if segment_ref.reservations.fetch_sub(1, Ordering::AcqRel) == 1
+            && segment_ref
+                .reservations
+                .compare_exchange(0, RETIRED, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.unlink(slot, segment, guard);
+        }
+    }
+
+    /// Unlinks the retired `segment` from `slot`, and retires it if this thread unlinked it.
+    fn unlink(&self, slot: &Atomic<Segment>, segment: Shared<'_, Segment>, guard: &Guard) {
+        if slot
+            .compare_and_set(segment, Shared::null(), Ordering::AcqRel, guard)
+            .is_ok()
+        {
+            self.retired.fetch_add(1, Ordering::Relaxed);
+            op_stats::retire();
+            unsafe { guard.retire(segment) };
+        }
+    }
+
+    /// The approximate number of set bits. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+
+    /// The numbers of segments allocated and retired so far.
+    pub fn segments(&self) -> (usize, usize) {
+        (
+            self.allocated.load(Ordering::Relaxed),
+            self.retired.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Registers the bitmap.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("Bitmap", Scheme::EBR, |d: &D, _| d.ebr_bitmap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bitmap, SEGMENT_BITS};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn smoke_bitmap() {
+        const THREADS: usize = 8;
+        const BITS: usize = 16 * SEGMENT_BITS;
+        let bitmap = &Bitmap::new(BITS);
+
+        // Each thread owns the bits congruent to its id, and sets them all, then clears the even
+        // ones, so that every segment is shared.
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for bit in (t..BITS).step_by(THREADS) {
+                        assert!(bitmap.set(bit, &pin()));
+                        assert!(!bitmap.set(bit, &pin()));
+                    }
+                    for bit in (t..BITS).step_by(THREADS) {
+                        if bit % 2 == 0 {
+                            assert!(bitmap.clear(bit, &pin()));
+                            assert!(!bitmap.clear(bit, &pin()));
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        let guard = &pin();
+        for bit in 0..BITS {
+            assert_eq!(bitmap.test(bit, guard), bit % 2 == 1);
+        }
+        assert_eq!(bitmap.len_approx(), BITS / 2);
+    }
+
+    #[test]
+    fn segments_are_retired() {
+        let bitmap = Bitmap::new(4 * SEGMENT_BITS);
+        let guard = &pin();
+        for round in 0..3 {
+            for bit in (0..4 * SEGMENT_BITS).step_by(3) {
+                assert!(bitmap.set(bit, guard));
+            }
+            assert_eq!(bitmap.segments(), (4 * (round + 1), 4 * round));
+            for bit in (0..4 * SEGMENT_BITS).step_by(3) {
+                assert!(bitmap.clear(bit, guard));
+            }
+            assert_eq!(bitmap.segments(), (4 * (round + 1), 4 * (round + 1)));
+            assert!(bitmap
+                .segments
+                .iter()
+                .all(|s| s.load(Ordering::Relaxed, guard).is_null()));
+            assert_eq!(bitmap.len_approx(), 0);
+        }
+    }
+}
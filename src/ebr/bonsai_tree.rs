@@ -1,12 +1,16 @@
 use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
 
 use super::concurrent_map::ConcurrentMap;
+use crate::bonsai_stats;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
 
 use std::cmp;
+use std::mem;
 use std::sync::atomic::Ordering;
 
-static WEIGHT: usize = 2;
-
 // TODO: optimization from the paper? IBR paper doesn't do that
 
 bitflags! {
@@ -94,7 +98,7 @@ where
     K: Ord + Clone,
     V: Clone,
 {
-    fn new<'g>() -> Self {
+    fn new() -> Self {
         Self {
             retired_nodes: Vec::new(),
             new_nodes: Vec::new(),
@@ -103,6 +107,7 @@ where
 
     /// Destroy the newly created state (self) that lost the race (reclaim_state)
     fn abort(&mut self) {
+        bonsai_stats::record_abort(self.new_nodes.len());
         self.retired_nodes.clear();
 
         for node in self.new_nodes.drain(..) {
@@ -112,6 +117,10 @@ where
 
     /// Retire the old state replaced by the new_state and the new_state.retired_nodes
     fn commit(&mut self, guard: &Guard) {
+        bonsai_stats::record_commit(
+            self.new_nodes.len(),
+            self.retired_nodes.len() * mem::size_of::<Node<K, V>>(),
+        );
         self.new_nodes.clear();
 
         for node in self.retired_nodes.drain(..) {
@@ -123,7 +132,8 @@ where
                 node.deref()
                     .right
                     .store(Node::retired_node(), Ordering::Release);
-                guard.defer_destroy(node);
+                op_stats::retire();
+                guard.retire(node);
             }
         }
     }
@@ -184,12 +194,13 @@ where
 
         let l_size = Node::node_size(left);
         let r_size = Node::node_size(right);
+        let weight = bonsai_stats::weight();
         let res = if r_size > 0
-            && ((l_size > 0 && r_size > WEIGHT * l_size) || (l_size == 0 && r_size > WEIGHT))
+            && ((l_size > 0 && r_size > weight * l_size) || (l_size == 0 && r_size > weight))
         {
             self.mk_balanced_left(left, right, key, value, guard)
         } else if l_size > 0
-            && ((r_size > 0 && l_size > WEIGHT * r_size) || (r_size == 0 && l_size > WEIGHT))
+            && ((r_size > 0 && l_size > weight * r_size) || (r_size == 0 && l_size > weight))
         {
             self.mk_balanced_right(left, right, key, value, guard)
         } else {
@@ -222,10 +233,11 @@ where
         }
 
         // double left rotation
-        return self.double_left(left, right, right_left, right_right, key, value, guard);
+        self.double_left(left, right, right_left, right_right, key, value, guard)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn single_left<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -246,10 +258,11 @@ where
             guard,
         );
         self.retire_node(right);
-        return res;
+        res
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn double_left<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -313,10 +326,11 @@ where
             return self.single_right(left, right, left_right, left_left, key, value, guard);
         }
         // double right rotation
-        return self.double_right(left, right, left_right, left_left, key, value, guard);
+        self.double_right(left, right, left_right, left_left, key, value, guard)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn single_right<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -337,10 +351,11 @@ where
             guard,
         );
         self.retire_node(left);
-        return res;
+        res
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn double_right<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -477,6 +492,7 @@ where
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn pull_leftmost<'g>(
         &mut self,
         node: Shared<'g, Node<K, V>>,
@@ -507,9 +523,10 @@ where
             guard,
         );
         self.retire_node(node);
-        return (right, succ);
+        (right, succ)
     }
 
+    #[allow(clippy::type_complexity)]
     fn pull_rightmost<'g>(
         &mut self,
         node: Shared<'g, Node<K, V>>,
@@ -540,12 +557,23 @@ where
             guard,
         );
         self.retire_node(node);
-        return (left, succ);
+        (left, succ)
     }
 }
 
 pub struct BonsaiTreeMap<K, V> {
     root: Atomic<Node<K, V>>,
+    len: StripedCounter,
+}
+
+impl<K, V> Default for BonsaiTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<K, V> BonsaiTreeMap<K, V>
@@ -556,6 +584,7 @@ where
     pub fn new() -> Self {
         Self {
             root: Atomic::null(),
+            len: StripedCounter::new(),
         }
     }
 
@@ -572,6 +601,7 @@ where
             }
 
             if Node::is_retired_spot(node, guard) {
+                restarts::record();
                 continue;
             }
 
@@ -592,6 +622,7 @@ where
 
             if Node::is_retired(new_root) {
                 state.abort();
+                restarts::record();
                 continue;
             }
 
@@ -605,6 +636,7 @@ where
             }
 
             state.abort();
+            restarts::record();
         }
     }
 
@@ -616,6 +648,7 @@ where
 
             if Node::is_retired(new_root) {
                 state.abort();
+                restarts::record();
                 continue;
             }
 
@@ -629,6 +662,7 @@ where
             }
 
             state.abort();
+            restarts::record();
         }
     }
 }
@@ -669,14 +703,31 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.insert(key, value, guard)
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.remove(key, guard)
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
+register_maps! {
+    ebr;
+    BonsaiTree => BonsaiTreeMap, Random;
+}
+
 #[cfg(test)]
 mod tests {
     use super::BonsaiTreeMap;
@@ -686,4 +737,11 @@ mod tests {
     fn smoke_bonsai_tree() {
         concurrent_map::tests::smoke::<BonsaiTreeMap<i32, String>>();
     }
+
+    #[test]
+    fn smoke_bonsai_tree_weight() {
+        crate::bonsai_stats::set_weight(5);
+        concurrent_map::tests::smoke::<BonsaiTreeMap<i32, String>>();
+        crate::bonsai_stats::set_weight(2);
+    }
 }
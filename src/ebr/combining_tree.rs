@@ -0,0 +1,199 @@
+//! A combining tree of counters, after the software combining trees of Yew et al. and of Herlihy and
+//! Shavit, with lock-free lists of pending updates in place of the locked nodes.
+//!
+//! Each pair of threads shares a leaf of a static binary tree. An update pushes a record of its
+//! delta onto the list of its leaf, and then tries to become the combiner of the leaf. The combiner
+//! detaches the whole list, pushes a single record of its sum onto the list of the parent and
+//! retires the detached records, and so on up to the root, whose combiner adds the sum to the value.
+//! An update that finds a node taken returns right away, as the combiner checks the list of the
+//! node again before moving up, and thus carries the update along. The records on the lists are the
+//! updates that are not applied yet, and a read adds them to the value. It reads the nodes from the
+//! root down, so that it misses a sum that moves up meanwhile rather than counting it twice, and it
+//! is exact in the absence of concurrent updates. The reads traverse the records that the combiners
+//! retire concurrently, which is what the reclamation scheme protects.
+
+use super::concurrent_counter::ConcurrentCounter;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+use crossbeam_utils::CachePadded;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+
+/// A pending update, immutable once pushed.
+struct Record {
+    delta: i64,
+    next: Atomic<Record>,
+}
+
+#[derive(Default)]
+struct Node {
+    pending: Atomic<Record>,
+    /// Set while a thread combines the records of the node.
+    combining: AtomicBool,
+}
+
+pub struct CombiningTree {
+    value: CachePadded<AtomicI64>,
+    /// The nodes in heap order: the root is at 1 and the children of `i` at `2i` and `2i + 1`.
+    nodes: Box<[CachePadded<Node>]>,
+    leaves: usize,
+}
+
+impl Drop for CombiningTree {
+    fn drop(&mut self) {
+        unsafe {
+            for node in self.nodes.iter() {
+                let mut curr = node.pending.load(Ordering::Relaxed, unprotected());
+                while !curr.is_null() {
+                    let record = curr.into_owned();
+                    curr = record.next.load(Ordering::Relaxed, unprotected());
+                }
+            }
+        }
+    }
+}
+
+impl CombiningTree {
+    /// Creates a tree with a leaf for every 2 of `threads` threads.
+    pub fn with_threads(threads: usize) -> Self {
+        let leaves = (threads.next_power_of_two() / 2).max(1);
+        Self {
+            value: CachePadded::new(AtomicI64::new(0)),
+            nodes: (0..2 * leaves)
+                .map(|_| CachePadded::new(Node::default()))
+                .collect(),
+            leaves,
+        }
+    }
+
+    fn push(&self, index: usize, delta: i64, guard: &Guard) {
+        let mut record = Owned::new(Record {
+            delta,
+            next: Atomic::null(),
+        });
+        let pending = &self.nodes[index].pending;
+        loop {
+            let head = pending.load(Ordering::Relaxed, guard);
+            record.next.store(head, Ordering::Relaxed);
+            // SeqCst, so that either the combiner sees the record when it checks the list again, or
+            // the update sees that the combiner left (see `add`).
+            match pending.compare_and_set(head, record, Ordering::SeqCst, guard) {
+                Ok(_) => return,
+                Err(e) => record = e.new,
+            }
+        }
+    }
+
+    /// Detaches the records of node `index`, retires them and returns their sum.
+    fn detach(&self, index: usize, guard: &Guard) -> i64 {
+        let mut curr = self.nodes[index]
+            .pending
+            .swap(Shared::null(), Ordering::AcqRel, guard);
+        let mut sum = 0;
+        while let Some(record) = unsafe { curr.as_ref() } {
+            sum += record.delta;
+            let next = record.next.load(Ordering::Relaxed, guard);
+            op_stats::retire();
+            unsafe { guard.retire(curr) };
+            curr = next;
+        }
+        sum
+    }
+
+    pub fn add(&self, tid: usize, delta: i64, guard: &Guard) {
+        let mut index = self.leaves + tid / 2 % self.leaves;
+        self.push(index, delta, guard);
+        loop {
+            let node = &self.nodes[index];
+            if node
+                .combining
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                // The combiner checks the list again after it leaves.
+                return;
+            }
+            let sum = self.detach(index, guard);
+            if index == 1 {
+                self.value.fetch_add(sum, Ordering::Release);
+            } else if sum != 0 {
+                self.push(index / 2, sum, guard);
+            }
+            node.combining.store(false, Ordering::SeqCst);
+            if !node.pending.load(Ordering::SeqCst, guard).is_null() {
+                continue;
+            }
+            if index == 1 {
+                return;
+            }
+            index /= 2;
+        }
+    }
+
+    pub fn get(&self, guard: &Guard) -> i64 {
+        let mut sum = self.value.load(Ordering::Acquire);
+        for node in self.nodes[1..].iter() {
+            let mut curr = node.pending.load(Ordering::Acquire, guard);
+            while let Some(record) = unsafe { curr.as_ref() } {
+                sum += record.delta;
+                curr = record.next.load(Ordering::Acquire, guard);
+            }
+        }
+        sum
+    }
+}
+
+impl ConcurrentCounter for CombiningTree {
+    fn new(threads: usize) -> Self {
+        Self::with_threads(threads)
+    }
+
+    #[inline]
+    fn add(&self, tid: usize, delta: i64, guard: &Guard) {
+        self.add(tid, delta, guard);
+    }
+
+    #[inline]
+    fn get(&self, guard: &Guard) -> i64 {
+        self.get(guard)
+    }
+}
+
+/// Registers the counter.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("CombiningTree", Scheme::EBR, |d: &D, _| {
+        d.ebr_counter::<CombiningTree>()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CombiningTree;
+    use crate::ebr::concurrent_counter;
+    use crossbeam_ebr::pin;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn smoke_combining_tree() {
+        concurrent_counter::tests::smoke::<CombiningTree>();
+    }
+
+    #[test]
+    fn shapes() {
+        for &(threads, leaves) in &[(1, 1), (2, 1), (3, 2), (8, 4), (9, 8)] {
+            let counter = CombiningTree::with_threads(threads);
+            assert_eq!(counter.leaves, leaves);
+            let guard = &pin();
+            for tid in 0..threads {
+                counter.add(tid, tid as i64 + 1, guard);
+            }
+            assert_eq!(counter.get(guard), (threads * (threads + 1) / 2) as i64);
+            // Without contention, every update reaches the root right away.
+            assert!(counter
+                .nodes
+                .iter()
+                .all(|n| n.pending.load(Ordering::Relaxed, guard).is_null()));
+        }
+    }
+}
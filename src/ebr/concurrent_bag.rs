@@ -0,0 +1,69 @@
+use crossbeam_ebr::Guard;
+
+/// An unordered collection of values, e.g. a pool of resources.
+pub trait ConcurrentBag<T> {
+    type Handle;
+
+    fn new() -> Self;
+    fn handle(&self) -> Self::Handle;
+
+    fn add(&self, handle: &mut Self::Handle, value: T, guard: &Guard);
+    fn try_remove_any(&self, handle: &mut Self::Handle, guard: &Guard) -> Option<T>;
+    /// The approximate number of values. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::ConcurrentBag;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: i32 = 30;
+    const ELEMENTS_PER_THREADS: i32 = 1000;
+
+    pub fn smoke<B: ConcurrentBag<i32> + Send + Sync>() {
+        let bag = &B::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let mut handle = bag.handle();
+                    for i in 0..ELEMENTS_PER_THREADS {
+                        bag.add(&mut handle, i * THREADS + t, &pin());
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(bag.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
+
+        // No more values are added, so a failed `try_remove_any` means that the bag is empty.
+        let mut removed = thread::scope(|s| {
+            let handles = (0..THREADS)
+                .map(|_| {
+                    s.spawn(move |_| {
+                        let mut handle = bag.handle();
+                        let mut removed = Vec::new();
+                        while let Some(v) = bag.try_remove_any(&mut handle, &pin()) {
+                            removed.push(v);
+                        }
+                        removed
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        assert_eq!(bag.len_approx(), 0);
+        removed.sort();
+        assert_eq!(
+            removed,
+            (0..THREADS * ELEMENTS_PER_THREADS).collect::<Vec<_>>()
+        );
+    }
+}
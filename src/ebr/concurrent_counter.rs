@@ -0,0 +1,80 @@
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::Guard;
+
+/// A counter shared by the threads, e.g. a statistic that every operation updates.
+pub trait ConcurrentCounter {
+    /// Creates a counter for `threads` threads.
+    fn new(threads: usize) -> Self;
+
+    /// Adds `delta` on behalf of thread `tid`.
+    fn add(&self, tid: usize, delta: i64, guard: &Guard);
+    /// The value. It is exact in the absence of concurrent updates.
+    fn get(&self, guard: &Guard) -> i64;
+}
+
+/// The striped counter of the sizes of the structures allocates nothing, so it is the baseline of
+/// the counters.
+impl ConcurrentCounter for StripedCounter {
+    fn new(_threads: usize) -> Self {
+        StripedCounter::new()
+    }
+
+    #[inline]
+    fn add(&self, _tid: usize, delta: i64, _guard: &Guard) {
+        StripedCounter::add(self, delta as isize);
+    }
+
+    #[inline]
+    fn get(&self, _guard: &Guard) -> i64 {
+        self.sum() as i64
+    }
+}
+
+/// Registers the striped counter.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("StripedCounter", Scheme::EBR, |d: &D, _| {
+        d.ebr_counter::<StripedCounter>()
+    });
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::ConcurrentCounter;
+    use crate::striped_counter::StripedCounter;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 30;
+    const ELEMENTS_PER_THREADS: i64 = 1000;
+
+    pub fn smoke<C: ConcurrentCounter + Send + Sync>() {
+        let counter = &C::new(THREADS);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for i in 0..ELEMENTS_PER_THREADS {
+                        let delta = if t % 3 == 0 { -1 } else { i % 3 };
+                        counter.add(t, delta, &pin());
+                        // The reads race with the updates.
+                        if i % 64 == 0 {
+                            counter.get(&pin());
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        // Of every 3 threads, one subtracts 1 and the others add 0, 1 and 2 in turn.
+        let expected = (THREADS as i64 / 3)
+            * (2 * (0..ELEMENTS_PER_THREADS).map(|i| i % 3).sum::<i64>() - ELEMENTS_PER_THREADS);
+        assert_eq!(counter.get(&pin()), expected);
+    }
+
+    #[test]
+    fn smoke_striped_counter() {
+        smoke::<StripedCounter>();
+    }
+}
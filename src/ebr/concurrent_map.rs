@@ -1,10 +1,33 @@
-use crossbeam_ebr::Guard;
+use crate::alloc_fault::AllocError;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crossbeam_ebr::{Guard, Owned};
 
 pub trait ConcurrentMap<K, V> {
     fn new() -> Self;
     fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V>;
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool;
+    /// Like `insert`, but fails with `AllocError`, leaving the map unchanged, instead of aborting
+    /// if a node cannot be allocated (`alloc_fault`). The lists, the hash map and the NMTree
+    /// allocate fallibly. By default, the map allocates as `insert` does and never fails.
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        Ok(self.insert(key, value, guard))
+    }
     fn remove(&self, key: &K, guard: &Guard) -> Option<V>;
+    /// Removes the entry of `key` like `remove`, but returns its value by reference, valid as long
+    /// as `guard` is pinned, instead of moving it out. The maps whose removed nodes keep their
+    /// values, e.g., those that clone the value on `remove`, return the value in the node. By
+    /// default, the value that `remove` moves out is boxed and retired.
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let value = Owned::new(self.remove(key, guard)?).into_shared(guard);
+        unsafe {
+            op_stats::retire();
+            guard.retire(value);
+            Some(value.deref())
+        }
+    }
+    /// The approximate number of entries. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
 }
 
 #[cfg(test)]
@@ -14,11 +37,18 @@ pub mod tests {
     use crossbeam_ebr::pin;
     use crossbeam_utils::thread;
     use rand::prelude::*;
+    use std::collections::BTreeMap;
 
     const THREADS: i32 = 30;
     const ELEMENTS_PER_THREADS: i32 = 1000;
 
     pub fn smoke<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        smoke_keyed::<i32, M>(|i| i);
+    }
+
+    /// Like `smoke`, with the keys that `key` makes of the integers, e.g., to smoke-test the
+    /// maps of strings of the registry.
+    pub fn smoke_keyed<K, M: ConcurrentMap<K, String> + Send + Sync>(key: fn(i32) -> K) {
         let map = &M::new();
 
         thread::scope(|s| {
@@ -29,12 +59,13 @@ pub mod tests {
                         (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
                     keys.shuffle(&mut rng);
                     for i in keys {
-                        assert!(map.insert(i, i.to_string(), &pin()));
+                        assert!(map.insert(key(i), i.to_string(), &pin()));
                     }
                 });
             }
         })
         .unwrap();
+        assert_eq!(map.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
 
         thread::scope(|s| {
             for t in 0..(THREADS / 2) {
@@ -44,12 +75,16 @@ pub mod tests {
                         (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
                     keys.shuffle(&mut rng);
                     for i in keys {
-                        assert_eq!(i.to_string(), map.remove(&i, &pin()).unwrap());
+                        assert_eq!(i.to_string(), map.remove(&key(i), &pin()).unwrap());
                     }
                 });
             }
         })
         .unwrap();
+        assert_eq!(
+            map.len_approx(),
+            (THREADS / 2 * ELEMENTS_PER_THREADS) as usize
+        );
 
         thread::scope(|s| {
             for t in (THREADS / 2)..THREADS {
@@ -59,11 +94,124 @@ pub mod tests {
                         (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
                     keys.shuffle(&mut rng);
                     for i in keys {
-                        assert_eq!(i.to_string(), *map.get(&i, &pin()).unwrap());
+                        assert_eq!(i.to_string(), *map.get(&key(i), &pin()).unwrap());
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+
+    /// Validates every operation against a shadow map. Each thread owns the keys that are
+    /// congruent to its id, so the results of its operations on them are determined by its own
+    /// shadow, while the keys of the other threads are updated around them.
+    pub fn shadow<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        const THREADS: i32 = 8;
+        const KEYS: i32 = 400;
+        const OPS: usize = 10_000;
+        let map = &M::new();
+
+        let shadows = thread::scope(|s| {
+            let threads = (0..THREADS)
+                .map(|t| {
+                    s.spawn(move |_| {
+                        let mut rng = rand::thread_rng();
+                        let mut shadow = BTreeMap::new();
+                        for i in 0..OPS {
+                            let k = rng.gen_range(0, KEYS / THREADS) * THREADS + t;
+                            let guard = &pin();
+                            match rng.gen_range(0, 3) {
+                                0 => assert_eq!(map.get(&k, guard), shadow.get(&k)),
+                                1 => {
+                                    let value = format!("{}-{}", k, i);
+                                    let inserted = map.insert(k, value.clone(), guard);
+                                    assert_eq!(inserted, !shadow.contains_key(&k));
+                                    shadow.entry(k).or_insert(value);
+                                }
+                                _ => assert_eq!(map.remove(&k, guard), shadow.remove(&k)),
+                            }
+                        }
+                        shadow
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|t| t.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        let guard = &pin();
+        let expected = shadows.into_iter().flatten().collect::<BTreeMap<_, _>>();
+        for k in 0..KEYS {
+            assert_eq!(map.get(&k, guard), expected.get(&k));
+        }
+        assert_eq!(map.len_approx(), expected.len());
+    }
+
+    /// Inserts the keys of each thread with `try_insert`, retrying the failed allocations, which
+    /// the `alloc-fault` feature injects. A failed insert must leave the map unchanged, whether the
+    /// key was absent or present.
+    pub fn try_insert<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        const THREADS: i32 = 8;
+        const KEYS_PER_THREAD: i32 = 500;
+        let map = &M::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let keys = (0..KEYS_PER_THREAD).map(|k| k * THREADS + t);
+                    for k in keys.clone() {
+                        while map.try_insert(k, k.to_string(), &pin()).is_err() {
+                            assert_eq!(map.get(&k, &pin()), None);
+                        }
+                    }
+                    for k in keys {
+                        if let Ok(inserted) = map.try_insert(k, "duplicate".to_string(), &pin()) {
+                            assert!(!inserted);
+                        }
+                        assert_eq!(map.get(&k, &pin()).cloned(), Some(k.to_string()));
                     }
                 });
             }
         })
         .unwrap();
+        assert_eq!(map.len_approx(), (THREADS * KEYS_PER_THREAD) as usize);
+    }
+
+    /// Races the threads to remove every key with `remove_get`, so that exactly one of them gets
+    /// each value, which they read while the removed nodes are retired.
+    pub fn remove_get<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        const THREADS: i32 = 8;
+        const KEYS: i32 = 1000;
+        let map = &M::new();
+
+        for k in 0..KEYS {
+            assert!(map.insert(k, k.to_string(), &pin()));
+        }
+        let removed = thread::scope(|s| {
+            let threads = (0..THREADS)
+                .map(|_| {
+                    s.spawn(move |_| {
+                        let mut removed = 0;
+                        for k in 0..KEYS {
+                            let guard = &pin();
+                            if let Some(v) = map.remove_get(&k, guard) {
+                                assert_eq!(*v, k.to_string());
+                                removed += 1;
+                            }
+                            assert_eq!(map.get(&k, guard), None);
+                        }
+                        removed
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads.into_iter().map(|t| t.join().unwrap()).sum::<i32>()
+        })
+        .unwrap();
+        assert_eq!(removed, KEYS);
+        assert_eq!(map.len_approx(), 0);
+        assert_eq!(map.remove_get(&0, &pin()), None);
     }
 }
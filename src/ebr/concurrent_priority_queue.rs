@@ -0,0 +1,99 @@
+use crossbeam_ebr::Guard;
+
+/// A collection of values ordered by their keys, where a smaller key means a higher priority.
+pub trait ConcurrentPriorityQueue<V> {
+    fn new() -> Self;
+
+    /// Inserts `value` with the key `key`. Returns `false` if `key` is already in the queue.
+    fn insert(&self, key: usize, value: V, guard: &Guard) -> bool;
+    /// Removes a value with the smallest key, if any.
+    fn try_remove_min(&self, guard: &Guard) -> Option<(usize, V)>;
+    /// The approximate number of values. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate rand;
+    use super::ConcurrentPriorityQueue;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+    use rand::prelude::*;
+    use std::collections::BTreeSet;
+
+    const THREADS: usize = 30;
+    const ELEMENTS_PER_THREADS: usize = 1000;
+
+    pub fn smoke<Q: ConcurrentPriorityQueue<String> + Send + Sync>() {
+        let queue = &Q::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<usize> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert!(queue.insert(i, i.to_string(), &pin()));
+                    }
+                    assert!(!queue.insert(t, "dup".to_string(), &pin()));
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(queue.len_approx(), THREADS * ELEMENTS_PER_THREADS);
+
+        // No more keys are inserted, so each thread removes the keys in the increasing order, and a
+        // failed `try_remove_min` means that the queue is empty.
+        let mut removed = thread::scope(|s| {
+            let handles = (0..THREADS)
+                .map(|_| {
+                    s.spawn(move |_| {
+                        let mut removed = Vec::new();
+                        while let Some((k, v)) = queue.try_remove_min(&pin()) {
+                            assert_eq!(k.to_string(), v);
+                            if let Some(&last) = removed.last() {
+                                assert!(last < k);
+                            }
+                            removed.push(k);
+                        }
+                        removed
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        assert_eq!(queue.len_approx(), 0);
+        removed.sort();
+        assert_eq!(
+            removed,
+            (0..THREADS * ELEMENTS_PER_THREADS).collect::<Vec<_>>()
+        );
+
+        // Reinsert the removed keys while removing the smallest ones.
+        let mut rng = rand::thread_rng();
+        let mut keys = (0..ELEMENTS_PER_THREADS).collect::<Vec<_>>();
+        keys.shuffle(&mut rng);
+        let mut expected = BTreeSet::new();
+        for (i, &k) in keys.iter().enumerate() {
+            assert!(queue.insert(k, k.to_string(), &pin()));
+            expected.insert(k);
+            if i % 3 == 2 {
+                let min = *expected.iter().next().unwrap();
+                expected.remove(&min);
+                assert_eq!(queue.try_remove_min(&pin()), Some((min, min.to_string())));
+            }
+        }
+        for min in expected {
+            assert_eq!(queue.try_remove_min(&pin()), Some((min, min.to_string())));
+        }
+        assert_eq!(queue.try_remove_min(&pin()), None);
+        assert_eq!(queue.len_approx(), 0);
+    }
+}
@@ -0,0 +1,97 @@
+use crossbeam_ebr::Guard;
+
+/// A collection of values that are removed in the order they were added (FIFO).
+pub trait ConcurrentQueue<T> {
+    fn new() -> Self;
+
+    fn push(&self, value: T, guard: &Guard);
+    /// Removes the oldest value, if any.
+    fn try_pop(&self, guard: &Guard) -> Option<T>;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::ConcurrentQueue;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const PRODUCERS: usize = 8;
+    const CONSUMERS: usize = 8;
+    const ELEMENTS_PER_PRODUCER: usize = 2000;
+
+    pub fn smoke<Q: ConcurrentQueue<usize>>() {
+        let queue = &Q::new();
+        let guard = &pin();
+        assert_eq!(queue.try_pop(guard), None);
+        for i in 0..100 {
+            queue.push(i, guard);
+        }
+        for i in 0..50 {
+            assert_eq!(queue.try_pop(guard), Some(i));
+        }
+        for i in 100..150 {
+            queue.push(i, guard);
+        }
+        for i in 50..150 {
+            assert_eq!(queue.try_pop(guard), Some(i));
+        }
+        assert_eq!(queue.try_pop(guard), None);
+    }
+
+    /// Producers push their values in increasing order while consumers pop. Checks that each value
+    /// is popped exactly once, and that each consumer pops the values of each producer in the order
+    /// they were pushed.
+    pub fn fifo<Q: ConcurrentQueue<usize> + Send + Sync>() {
+        let queue = &Q::new();
+        let finished = &AtomicUsize::new(0);
+
+        let popped = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                s.spawn(move |_| {
+                    for i in 0..ELEMENTS_PER_PRODUCER {
+                        queue.push(i * PRODUCERS + p, &pin());
+                    }
+                    finished.fetch_add(1, Ordering::Release);
+                });
+            }
+            let handles = (0..CONSUMERS)
+                .map(|_| {
+                    s.spawn(move |_| {
+                        let mut popped = Vec::new();
+                        loop {
+                            // A failed `try_pop` after all pushes means that the queue is empty.
+                            let done = finished.load(Ordering::Acquire) == PRODUCERS;
+                            match queue.try_pop(&pin()) {
+                                Some(v) => popped.push(v),
+                                None if done => break,
+                                None => {}
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        for popped in &popped {
+            let mut last = [None; PRODUCERS];
+            for &v in popped {
+                let (p, i) = (v % PRODUCERS, v / PRODUCERS);
+                assert!(last[p].map_or(true, |l| l < i), "out of order: {}", v);
+                last[p] = Some(i);
+            }
+        }
+        let mut all = popped.into_iter().flatten().collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(
+            all,
+            (0..PRODUCERS * ELEMENTS_PER_PRODUCER).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crossbeam_ebr::Guard;
+
+/// A collection of values that are removed in the reverse order they were added (LIFO).
+pub trait ConcurrentStack<T> {
+    fn new() -> Self;
+
+    fn push(&self, value: T, guard: &Guard);
+    /// Removes the newest value, if any.
+    fn try_pop(&self, guard: &Guard) -> Option<T>;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::ConcurrentStack;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 16;
+    const ELEMENTS_PER_THREADS: usize = 2000;
+
+    pub fn smoke<S: ConcurrentStack<usize>>() {
+        let stack = &S::new();
+        let guard = &pin();
+        assert_eq!(stack.try_pop(guard), None);
+        for i in 0..100 {
+            stack.push(i, guard);
+        }
+        for i in (50..100).rev() {
+            assert_eq!(stack.try_pop(guard), Some(i));
+        }
+        for i in 100..150 {
+            stack.push(i, guard);
+        }
+        for i in (100..150).rev().chain((0..50).rev()) {
+            assert_eq!(stack.try_pop(guard), Some(i));
+        }
+        assert_eq!(stack.try_pop(guard), None);
+    }
+
+    /// Each thread pushes its values in increasing order and pops after every other push. Checks
+    /// that each value is popped exactly once, and that the values left at the end are popped in
+    /// the reverse order each thread pushed them.
+    pub fn lifo<S: ConcurrentStack<usize> + Send + Sync>() {
+        let stack = &S::new();
+
+        let popped = thread::scope(|s| {
+            let handles = (0..THREADS)
+                .map(|t| {
+                    s.spawn(move |_| {
+                        let mut popped = Vec::new();
+                        for i in 0..ELEMENTS_PER_THREADS {
+                            stack.push(i * THREADS + t, &pin());
+                            if i % 2 == 1 {
+                                // This thread pushed more values than it popped.
+                                popped.push(stack.try_pop(&pin()).unwrap());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        // No more values are pushed, so the rest are popped from the newest to the oldest.
+        let guard = &pin();
+        let mut last = [None; THREADS];
+        let mut left = Vec::new();
+        while let Some(v) = stack.try_pop(guard) {
+            let (t, i) = (v % THREADS, v / THREADS);
+            assert!(last[t].map_or(true, |l| l > i), "out of order: {}", v);
+            last[t] = Some(i);
+            left.push(v);
+        }
+        assert_eq!(left.len(), THREADS * ELEMENTS_PER_THREADS / 2);
+
+        let mut all = popped.into_iter().chain(left).collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, (0..THREADS * ELEMENTS_PER_THREADS).collect::<Vec<_>>());
+    }
+}
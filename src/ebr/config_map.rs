@@ -0,0 +1,221 @@
+//! A read-mostly map in the style of RCU, such as the global configuration of a program.
+//!
+//! The map is an immutable snapshot of its entries behind a single pointer. A read loads the
+//! pointer and searches the snapshot, without a write to shared memory. An update copies the
+//! snapshot, changes the copy, publishes it with a single store and retires the old snapshot, which
+//! the reads that loaded it may still be searching. The updates are serialized by a lock, as in
+//! RCU, and copy the whole map, so the map suits a few entries that rarely change: this is the
+//! classic use case that motivates epoch-based reclamation, where the readers pay nothing and the
+//! writers pay for everything.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The entries of the map, sorted by key.
+struct Snapshot<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord, V> Snapshot<K, V> {
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+}
+
+pub struct ConfigMap<K, V> {
+    snapshot: Atomic<Snapshot<K, V>>,
+    /// Serializes the updates.
+    writer: Mutex<()>,
+    /// The number of entries of the latest snapshot.
+    len: AtomicUsize,
+    /// The snapshots published so far, each of which retired its predecessor.
+    published: AtomicUsize,
+}
+
+impl<K, V> Drop for ConfigMap<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            drop(
+                self.snapshot
+                    .load(Ordering::Relaxed, unprotected())
+                    .into_owned(),
+            );
+        }
+    }
+}
+
+impl<K, V> ConfigMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            snapshot: Atomic::new(Snapshot {
+                entries: Vec::new(),
+            }),
+            writer: Mutex::new(()),
+            len: AtomicUsize::new(0),
+            published: AtomicUsize::new(0),
+        }
+    }
+
+    /// Creates a map of `entries`, of which the first of each key is kept.
+    pub fn with_entries<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries.dedup_by(|(a, _), (b, _)| a == b);
+        Self {
+            len: AtomicUsize::new(entries.len()),
+            snapshot: Atomic::new(Snapshot { entries }),
+            writer: Mutex::new(()),
+            published: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let snapshot = unsafe { self.snapshot.load(Ordering::Acquire, guard).deref() };
+        snapshot.search(key).ok().map(|i| &snapshot.entries[i].1)
+    }
+
+    /// Applies `update` to a copy of the entries, and publishes the copy if `update` returns
+    /// `Some`.
+    fn update<F, R>(&self, update: F, guard: &Guard) -> Option<R>
+    where
+        F: FnOnce(&Snapshot<K, V>, &mut Vec<(K, V)>) -> Option<R>,
+    {
+        let _writer = self.writer.lock().unwrap();
+        // The lock orders the updates, so the snapshot is the latest one.
+        let old = self.snapshot.load(Ordering::Acquire, guard);
+        let old_ref = unsafe { old.deref() };
+        let mut entries = old_ref.entries.clone();
+        let result = update(old_ref, &mut entries)?;
+        self.len.store(entries.len(), Ordering::Relaxed);
+        self.snapshot
+            .store(Owned::new(Snapshot { entries }), Ordering::Release);
+        self.published.fetch_add(1, Ordering::Relaxed);
+        unsafe {
+            op_stats::retire();
+            guard.retire(old);
+        }
+        Some(result)
+    }
+
+    /// Inserts the entry if the key is absent. Returns whether it inserted.
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        // Most inserts of a configuration find their key, and are decided without the lock.
+        if self.get(&key, guard).is_some() {
+            return false;
+        }
+        self.update(
+            |old, entries| {
+                let i = old.search(&key).err()?;
+                entries.insert(i, (key, value));
+                Some(())
+            },
+            guard,
+        )
+        .is_some()
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.update(
+            |old, entries| {
+                let i = old.search(key).ok()?;
+                Some(entries.remove(i).1)
+            },
+            guard,
+        )
+    }
+
+    /// The approximate number of entries. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// The number of snapshots published so far, which is also the number retired.
+    pub fn published(&self) -> usize {
+        self.published.load(Ordering::Relaxed)
+    }
+}
+
+impl<K, V> Default for ConfigMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for ConfigMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        self.insert(key, value, guard)
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove(key, guard)
+    }
+    fn len_approx(&self) -> usize {
+        self.len_approx()
+    }
+}
+
+/// Registers the map of configurations.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("ConfigMap", Scheme::EBR, |d: &D, _| d.ebr_config_map());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConfigMap;
+    use crate::ebr::concurrent_map;
+    use crossbeam_ebr::pin;
+
+    #[test]
+    fn shadow_config_map() {
+        concurrent_map::tests::shadow::<ConfigMap<i32, String>>();
+    }
+
+    #[test]
+    fn snapshots() {
+        let map = ConfigMap::new();
+        let guard = &pin();
+        assert!(map.insert(1, "a", guard));
+        assert!(map.insert(0, "b", guard));
+        // A read keeps the snapshot it loaded across the updates.
+        let value = map.get(&1, guard).unwrap();
+        assert!(!map.insert(1, "c", guard));
+        assert_eq!(map.remove(&1, guard), Some("a"));
+        assert_eq!(map.remove(&1, guard), None);
+        assert_eq!(*value, "a");
+        assert_eq!(map.get(&0, guard), Some(&"b"));
+        // The failed insert and remove publish nothing.
+        assert_eq!(map.published(), 3);
+        assert_eq!(map.len_approx(), 1);
+
+        let map = ConfigMap::with_entries(vec![(2, "a"), (0, "b"), (2, "c")]);
+        assert_eq!(map.len_approx(), 2);
+        assert_eq!(map.get(&2, guard), Some(&"a"));
+        assert_eq!(map.get(&1, guard), None);
+    }
+}
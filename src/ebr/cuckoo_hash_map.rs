@@ -0,0 +1,456 @@
+//! An optimistic cuckoo hash map, after the concurrent cuckoo hashing of MemC3 and libcuckoo.
+//!
+//! Each key has a bucket of `SLOTS` slots in each of the two tables, and its entry is in one of
+//! them. A lookup takes no lock: it reads the versions of the two buckets, searches them, and
+//! retries if a version changed meanwhile. An update locks the two buckets of its key. An insert
+//! whose two buckets are full first searches for a path of displacements that ends with a free
+//! slot, where each entry on the path moves to its bucket in the other table, and then moves the
+//! entries from the end of the path, each while holding the two buckets it moves between.
+//!
+//! The entries are immutable, so moving an entry copies it into the new slot and retires the old
+//! one, which the lookups that have just found it may still read. Thus the inserts that displace
+//! entries produce garbage as well as the removals, which clear their slot without a tombstone.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::backoff::Backoff;
+use crate::hashing::Hashing;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+use std::hash::Hash;
+use std::sync::atomic::{self, AtomicUsize, Ordering};
+
+/// The slots of a bucket.
+const SLOTS: usize = 4;
+/// The number of buckets that the search for a displacement path visits at most.
+const MAX_SEARCH: usize = 512;
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(1 << 16);
+
+/// Makes the maps created by `ConcurrentMap::new` hold at least `n` entries.
+pub fn set_capacity(n: usize) {
+    CAPACITY.store(n, Ordering::Relaxed);
+}
+
+struct Entry<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+struct Bucket<K, V> {
+    /// Odd while a writer holds the bucket. Changes with every update of the bucket.
+    version: AtomicUsize,
+    slots: [Atomic<Entry<K, V>>; SLOTS],
+}
+
+impl<K, V> Bucket<K, V> {
+    fn new() -> Self {
+        Bucket {
+            version: AtomicUsize::new(0),
+            slots: [
+                Atomic::null(),
+                Atomic::null(),
+                Atomic::null(),
+                Atomic::null(),
+            ],
+        }
+    }
+
+    fn lock(&self) {
+        let backoff = Backoff::new();
+        loop {
+            let version = self.version.load(Ordering::Relaxed);
+            if version & 1 == 0
+                && self
+                    .version
+                    .compare_exchange_weak(
+                        version,
+                        version + 1,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                return;
+            }
+            backoff.snooze();
+        }
+    }
+
+    fn unlock(&self) {
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// The slot of `key` and its entry, if any.
+    #[allow(clippy::type_complexity)]
+    fn find<'g>(
+        &'g self,
+        key: &K,
+        guard: &'g Guard,
+    ) -> Option<(&'g Atomic<Entry<K, V>>, Shared<'g, Entry<K, V>>)>
+    where
+        K: Eq,
+    {
+        self.slots.iter().find_map(|slot| {
+            let entry = slot.load(Ordering::Acquire, guard);
+            match unsafe { entry.as_ref() } {
+                Some(e) if e.key == *key => Some((slot, entry)),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// The bucket of an entry in the other table, and where the entry is.
+#[derive(Clone, Copy)]
+struct Displacement {
+    table: usize,
+    bucket: usize,
+    /// The position of the displacement whose entry moves into the bucket of this one, and its slot
+    /// there. `None` for the two buckets of the key being inserted.
+    from: Option<(usize, usize)>,
+}
+
+pub struct CuckooHashMap<K, V> {
+    tables: [Box<[Bucket<K, V>]>; 2],
+    len: StripedCounter,
+    hashing: Hashing,
+}
+
+impl<K, V> Drop for CuckooHashMap<K, V> {
+    fn drop(&mut self) {
+        for table in &self.tables {
+            for bucket in table.iter() {
+                for slot in &bucket.slots {
+                    let entry = slot.load(Ordering::Relaxed, unsafe { unprotected() });
+                    if !entry.is_null() {
+                        drop(unsafe { entry.into_owned() });
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> CuckooHashMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// A map with room for at least `n` entries, with at most half of the slots full.
+    pub fn with_capacity(n: usize) -> Self {
+        let buckets = (n / SLOTS + 1).next_power_of_two();
+        let table = || (0..buckets).map(|_| Bucket::new()).collect::<Vec<_>>();
+        CuckooHashMap {
+            tables: [table().into_boxed_slice(), table().into_boxed_slice()],
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+        }
+    }
+
+    #[inline]
+    fn hash(&self, key: &K) -> u64 {
+        self.hashing.hash(key)
+    }
+
+    /// The bucket in `table` of the entries with `hash`.
+    #[inline]
+    fn index(&self, hash: u64, table: usize) -> usize {
+        let hash = if table == 0 {
+            hash
+        } else {
+            hash.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(32)
+        };
+        hash as usize & (self.tables[table].len() - 1)
+    }
+
+    #[inline]
+    fn buckets(&self, hash: u64) -> (&Bucket<K, V>, &Bucket<K, V>) {
+        (
+            &self.tables[0][self.index(hash, 0)],
+            &self.tables[1][self.index(hash, 1)],
+        )
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let (b0, b1) = self.buckets(self.hash(key));
+        let backoff = Backoff::new();
+        loop {
+            let v0 = b0.version.load(Ordering::Acquire);
+            let v1 = b1.version.load(Ordering::Acquire);
+            if v0 & 1 == 1 || v1 & 1 == 1 {
+                backoff.snooze();
+                continue;
+            }
+            let found = b0.find(key, guard).or_else(|| b1.find(key, guard));
+            // An entry may have moved from the bucket not yet searched to the one searched.
+            atomic::fence(Ordering::Acquire);
+            if b0.version.load(Ordering::Relaxed) == v0 && b1.version.load(Ordering::Relaxed) == v1
+            {
+                return found.map(|(_, entry)| unsafe { &entry.deref().value });
+            }
+            op_stats::retry();
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let hash = self.hash(&key);
+        let entry = Owned::new(Entry { hash, key, value });
+        loop {
+            let (b0, b1) = self.buckets(hash);
+            b0.lock();
+            b1.lock();
+            let found = b0
+                .find(&entry.key, guard)
+                .or_else(|| b1.find(&entry.key, guard))
+                .is_some();
+            let free = if found {
+                None
+            } else {
+                b0.slots
+                    .iter()
+                    .chain(b1.slots.iter())
+                    .find(|s| s.load(Ordering::Relaxed, guard).is_null())
+            };
+            if found || free.is_some() {
+                if let Some(slot) = free {
+                    slot.store(entry, Ordering::Release);
+                    self.len.add(1);
+                }
+                b1.unlock();
+                b0.unlock();
+                return !found;
+            }
+            b1.unlock();
+            b0.unlock();
+
+            // The two buckets are full, and the displacements lock them.
+            if !self.make_room(hash, guard) {
+                panic!("CuckooHashMap: no displacement frees a slot, the map is too full");
+            }
+        }
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove_get(key, guard).cloned()
+    }
+
+    /// Like `remove`, but returns the value in the retired entry.
+    pub fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let (b0, b1) = self.buckets(self.hash(key));
+        b0.lock();
+        b1.lock();
+        let removed = b0
+            .find(key, guard)
+            .or_else(|| b1.find(key, guard))
+            .map(|(slot, entry)| {
+                slot.store(Shared::null(), Ordering::Release);
+                entry
+            });
+        b1.unlock();
+        b0.unlock();
+        let entry = removed?;
+        self.len.add(-1);
+        op_stats::retire();
+        unsafe { guard.retire(entry) };
+        Some(&unsafe { entry.deref() }.value)
+    }
+
+    /// Frees a slot in a bucket of `hash` by moving entries along a path of displacements. Returns
+    /// `false` if no path within `MAX_SEARCH` buckets ends with a free slot.
+    fn make_room(&self, hash: u64, guard: &Guard) -> bool {
+        // A breadth-first search without locks, for the shortest path.
+        let mut path = vec![
+            Displacement {
+                table: 0,
+                bucket: self.index(hash, 0),
+                from: None,
+            },
+            Displacement {
+                table: 1,
+                bucket: self.index(hash, 1),
+                from: None,
+            },
+        ];
+        let mut i = 0;
+        while i < path.len() && path.len() < MAX_SEARCH {
+            let d = path[i];
+            let bucket = &self.tables[d.table][d.bucket];
+            for (s, slot) in bucket.slots.iter().enumerate() {
+                let entry = slot.load(Ordering::Acquire, guard);
+                match unsafe { entry.as_ref() } {
+                    None => return self.displace(&path, i, s, guard),
+                    Some(e) => path.push(Displacement {
+                        table: 1 - d.table,
+                        bucket: self.index(e.hash, 1 - d.table),
+                        from: Some((i, s)),
+                    }),
+                }
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Moves the entries of the path that ends with the free slot `slot` of `path[end]`, from the
+    /// end. Returns `true` also if another thread changed the path first, so that the caller
+    /// retries.
+    fn displace(
+        &self,
+        path: &[Displacement],
+        mut end: usize,
+        mut slot: usize,
+        guard: &Guard,
+    ) -> bool {
+        while let Some((from, from_slot)) = path[end].from {
+            let (src, dst) = (path[from], path[end]);
+            let src_bucket = &self.tables[src.table][src.bucket];
+            let dst_bucket = &self.tables[dst.table][dst.bucket];
+            // The buckets are locked in the order of the tables, like in `insert` and `remove`.
+            let (first, second) = if src.table == 0 {
+                (src_bucket, dst_bucket)
+            } else {
+                (dst_bucket, src_bucket)
+            };
+            first.lock();
+            second.lock();
+            let entry = src_bucket.slots[from_slot].load(Ordering::Relaxed, guard);
+            let valid = match unsafe { entry.as_ref() } {
+                Some(e) => {
+                    self.index(e.hash, dst.table) == dst.bucket
+                        && dst_bucket.slots[slot]
+                            .load(Ordering::Relaxed, guard)
+                            .is_null()
+                }
+                None => false,
+            };
+            if valid {
+                let e = unsafe { entry.deref() };
+                let copy = Owned::new(Entry {
+                    hash: e.hash,
+                    key: e.key.clone(),
+                    value: e.value.clone(),
+                });
+                // The copy is visible before the entry disappears, and the versions make the
+                // lookups that searched the buckets in between retry.
+                dst_bucket.slots[slot].store(copy, Ordering::Release);
+                src_bucket.slots[from_slot].store(Shared::null(), Ordering::Release);
+            }
+            second.unlock();
+            first.unlock();
+            if !valid {
+                return true;
+            }
+            op_stats::retire();
+            unsafe { guard.retire(entry) };
+            end = from;
+            slot = from_slot;
+        }
+        true
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for CuckooHashMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::with_capacity(CAPACITY.load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        self.insert(key, value, guard)
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove(key, guard)
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.remove_get(key, guard)
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    ebr;
+    CuckooHashMap => CuckooHashMap, Random;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CuckooHashMap;
+    use crate::ebr::concurrent_map;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+
+    #[test]
+    fn smoke_cuckoo_hash_map() {
+        concurrent_map::tests::smoke::<CuckooHashMap<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_cuckoo_hash_map() {
+        concurrent_map::tests::remove_get::<CuckooHashMap<i32, String>>();
+    }
+
+    #[test]
+    fn displacements() {
+        // 2048 slots filled to 90%, so that most inserts displace entries.
+        let map = CuckooHashMap::with_capacity(1000);
+        let guard = &pin();
+        for i in 0..1800 {
+            assert!(map.insert(i, i, guard));
+        }
+        for i in 0..1800 {
+            assert_eq!(map.get(&i, guard), Some(&i));
+        }
+        for i in (0..1800).step_by(2) {
+            assert_eq!(map.remove(&i, guard), Some(i));
+        }
+        for i in 0..1800 {
+            assert_eq!(map.get(&i, guard), if i % 2 == 0 { None } else { Some(&i) });
+        }
+    }
+
+    #[test]
+    fn concurrent_displacements() {
+        // The gets must find the keys that are never removed while the inserts of the other keys
+        // move them between their buckets.
+        let map = &CuckooHashMap::with_capacity(1000);
+        for i in 0..800 {
+            assert!(map.insert(i, i, &pin()));
+        }
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for i in 0..20000 {
+                        let key = 1000 + t * 100 + i % 100;
+                        if i % 200 < 100 {
+                            assert!(map.insert(key, key, &pin()));
+                        } else {
+                            assert_eq!(map.remove(&key, &pin()), Some(key));
+                        }
+                        let stable = (i * 7 + t) % 800;
+                        assert_eq!(map.get(&stable, &pin()), Some(&stable));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(map.len.approx(), 800);
+    }
+}
@@ -0,0 +1,618 @@
+//! Sundell and Tsigas's lock-free deque, a doubly linked list between two sentinels.
+//!
+//! A node is deleted by marking its `next` and then its `prev` link. The `next` links are always
+//! consistent, while the `prev` links are hints that the operations repair (`help_insert`), so a
+//! deleted node may stay the target of the `prev` link of a node, or of the links of the other
+//! deleted nodes, well after it is unlinked from the `next` links. The paper reclaims the nodes with
+//! reference counting for this reason, and breaks the cycles among the deleted nodes by redirecting
+//! their links to the live nodes (`remove_cross_reference`).
+//!
+//! Here each node counts the links that point to it instead of all references, and the guards
+//! protect the nodes that a thread reaches by following the links. Once no link points to a
+//! deleted node, no link can point to it again (`Node::link` fails), so it is retired by whoever
+//! removes its last link: often a helper repairing a `prev` link, or another node that is retired.
+
+use super::concurrent_queue::ConcurrentQueue;
+use super::concurrent_stack::ConcurrentStack;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of links of a node that no link can point to any more.
+const RELEASED: usize = usize::max_value();
+
+struct Node<T> {
+    /// `None` only for the sentinels.
+    value: Option<ManuallyDrop<T>>,
+    /// Mark: tag(), the node is being deleted.
+    prev: Atomic<Node<T>>,
+    /// Mark: tag(), the node is deleted.
+    next: Atomic<Node<T>>,
+    /// The number of links that point to the node, or `RELEASED`. The links to the sentinels are
+    /// not counted.
+    links: AtomicUsize,
+}
+
+impl<T> Node<T> {
+    fn new(value: Option<T>, links: usize) -> Self {
+        Self {
+            value: value.map(ManuallyDrop::new),
+            prev: Atomic::null(),
+            next: Atomic::null(),
+            links: AtomicUsize::new(links),
+        }
+    }
+
+    /// Counts a new link to the node. Fails if the node is released.
+    fn link(&self) -> bool {
+        if self.value.is_none() {
+            return true;
+        }
+        let mut links = self.links.load(Ordering::Relaxed);
+        loop {
+            if links == RELEASED {
+                return false;
+            }
+            match self.links.compare_exchange_weak(
+                links,
+                links + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(l) => links = l,
+            }
+        }
+    }
+
+    /// Uncounts a link to the node. Returns whether it was the last one, in which case the node is
+    /// released.
+    fn unlink(&self) -> bool {
+        if self.value.is_none() {
+            return false;
+        }
+        let mut links = self.links.load(Ordering::Relaxed);
+        loop {
+            debug_assert!(links != RELEASED && links > 0);
+            let new = if links == 1 { RELEASED } else { links - 1 };
+            match self
+                .links
+                .compare_exchange_weak(links, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => return new == RELEASED,
+                Err(l) => links = l,
+            }
+        }
+    }
+}
+
+/// Uncounts a link to `node`, and retires it if it was the last one. The links of a retired node
+/// are uncounted in turn.
+fn unlink<'g, T>(node: Shared<'g, Node<T>>, guard: &'g Guard) {
+    if !unsafe { node.deref() }.unlink() {
+        return;
+    }
+    let mut released = vec![node];
+    while let Some(node) = released.pop() {
+        let node_ref = unsafe { node.deref() };
+        let prev = node_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+        let next = node_ref.next.load(Ordering::Acquire, guard).with_tag(0);
+        unsafe {
+            op_stats::retire();
+            guard.retire(node);
+        }
+        for &link in &[prev, next] {
+            if unsafe { link.deref() }.unlink() {
+                released.push(link);
+            }
+        }
+    }
+}
+
+pub struct Deque<T> {
+    head: Atomic<Node<T>>,
+    tail: Atomic<Node<T>>,
+    len: StripedCounter,
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let guard = unprotected();
+            let mut chain = Vec::new();
+            let mut curr = self.head.load(Ordering::Relaxed, guard);
+            while !curr.is_null() {
+                chain.push(curr);
+                curr = curr.deref().next.load(Ordering::Relaxed, guard);
+            }
+            // The deleted nodes that are still linked are linked by the stale `prev` links of the
+            // nodes in the chain, as the deleted nodes have redirected their own links.
+            for w in chain.windows(2) {
+                let prev = w[1].deref().prev.load(Ordering::Relaxed, guard);
+                if prev != w[0] {
+                    unlink(prev.with_tag(0), guard);
+                }
+            }
+            for node in chain {
+                let mut node = node.into_owned();
+                if let Some(value) = &mut node.value {
+                    ManuallyDrop::drop(value);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        let guard = unsafe { unprotected() };
+        let head = Owned::new(Node::new(None, 0)).into_shared(guard);
+        let tail = Owned::new(Node::new(None, 0)).into_shared(guard);
+        unsafe {
+            head.deref().next.store(tail, Ordering::Relaxed);
+            tail.deref().prev.store(head, Ordering::Relaxed);
+        }
+        Deque {
+            head: Atomic::from(head),
+            tail: Atomic::from(tail),
+            len: StripedCounter::new(),
+        }
+    }
+
+    pub fn push_left(&self, value: T, guard: &Guard) {
+        // The link from `prev.next` is counted in advance.
+        let node = Owned::new(Node::new(Some(value), 1)).into_shared(guard);
+        let node_ref = unsafe { node.deref() };
+        let prev = self.head.load(Ordering::Relaxed, guard);
+        let prev_ref = unsafe { prev.deref() };
+
+        let next = loop {
+            let next = prev_ref.next.load(Ordering::Acquire, guard);
+            node_ref.prev.store(prev, Ordering::Relaxed);
+            // The link from `prev.next` to `next` becomes the one from `node.next`.
+            node_ref.next.store(next, Ordering::Relaxed);
+            if prev_ref
+                .next
+                .compare_and_set(next, node, Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                break next;
+            }
+            restarts::record();
+        };
+        self.push_common(node, next, guard);
+        self.len.add(1);
+    }
+
+    pub fn push_right(&self, value: T, guard: &Guard) {
+        let node = Owned::new(Node::new(Some(value), 1)).into_shared(guard);
+        let node_ref = unsafe { node.deref() };
+        let next = self.tail.load(Ordering::Relaxed, guard);
+        let mut prev = unsafe { next.deref() }.prev.load(Ordering::Acquire, guard);
+
+        loop {
+            let prev_ref = unsafe { prev.deref() };
+            if prev_ref.next.load(Ordering::Acquire, guard) != next {
+                prev = self.help_insert(prev, next, guard);
+                continue;
+            }
+            if !prev_ref.link() {
+                continue;
+            }
+            node_ref.prev.store(prev, Ordering::Relaxed);
+            node_ref.next.store(next, Ordering::Relaxed);
+            if prev_ref
+                .next
+                .compare_and_set(next, node, Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                break;
+            }
+            unlink(prev, guard);
+            restarts::record();
+        }
+        self.push_common(node, next, guard);
+        self.len.add(1);
+    }
+
+    /// Points `next.prev` to the inserted `node`.
+    fn push_common<'g>(
+        &self,
+        node: Shared<'g, Node<T>>,
+        next: Shared<'g, Node<T>>,
+        guard: &'g Guard,
+    ) {
+        let node_ref = unsafe { node.deref() };
+        let next_ref = unsafe { next.deref() };
+        loop {
+            let link = next_ref.prev.load(Ordering::Acquire, guard);
+            if link.tag() == 1 || node_ref.next.load(Ordering::Acquire, guard) != next {
+                break;
+            }
+            if !node_ref.link() {
+                break;
+            }
+            if next_ref
+                .prev
+                .compare_and_set(link, node, Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                unlink(link, guard);
+                if node_ref.prev.load(Ordering::Acquire, guard).tag() == 1 {
+                    self.help_insert(node, next, guard);
+                }
+                break;
+            }
+            unlink(node, guard);
+        }
+    }
+
+    pub fn pop_left(&self, guard: &Guard) -> Option<T> {
+        let prev = self.head.load(Ordering::Relaxed, guard);
+        let tail = self.tail.load(Ordering::Relaxed, guard);
+
+        let node = loop {
+            let node = unsafe { prev.deref() }.next.load(Ordering::Acquire, guard);
+            if node == tail {
+                return None;
+            }
+            let node_ref = unsafe { node.deref() };
+            let link = node_ref.next.load(Ordering::Acquire, guard);
+            if link.tag() == 1 {
+                self.help_delete(node, guard);
+                continue;
+            }
+            // Keeps `node` from being released until its links are redirected.
+            if !node_ref.link() {
+                continue;
+            }
+            if node_ref
+                .next
+                .compare_and_set(link, link.with_tag(1), Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                self.help_delete(node, guard);
+                let next = node_ref.next.load(Ordering::Acquire, guard).with_tag(0);
+                self.help_insert(prev, next, guard);
+                break node;
+            }
+            unlink(node, guard);
+            restarts::record();
+        };
+        Some(self.finish_pop(node, guard))
+    }
+
+    pub fn pop_right(&self, guard: &Guard) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed, guard);
+        let next = self.tail.load(Ordering::Relaxed, guard);
+        let mut node = unsafe { next.deref() }.prev.load(Ordering::Acquire, guard);
+
+        loop {
+            let node_ref = unsafe { node.deref() };
+            if node_ref.next.load(Ordering::Acquire, guard) != next {
+                node = self.help_insert(node, next, guard);
+                continue;
+            }
+            if node == head {
+                return None;
+            }
+            if !node_ref.link() {
+                continue;
+            }
+            if node_ref
+                .next
+                .compare_and_set(next, next.with_tag(1), Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                self.help_delete(node, guard);
+                let prev = node_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+                self.help_insert(prev, next, guard);
+                break;
+            }
+            unlink(node, guard);
+            restarts::record();
+        }
+        Some(self.finish_pop(node, guard))
+    }
+
+    /// Takes the value of the deleted `node`, redirects its links, and uncounts the link that
+    /// kept it from being released meanwhile.
+    fn finish_pop<'g>(&self, node: Shared<'g, Node<T>>, guard: &'g Guard) -> T {
+        let node_ref = unsafe { node.deref() };
+        let value =
+            unsafe { ManuallyDrop::into_inner(ptr::read(node_ref.value.as_ref().unwrap())) };
+        self.remove_cross_reference(node, guard);
+        unlink(node, guard);
+        self.len.add(-1);
+        value
+    }
+
+    /// Marks `node.prev`, and unlinks `node` from the `next` links.
+    fn help_delete<'g>(&self, node: Shared<'g, Node<T>>, guard: &'g Guard) {
+        let node_ref = unsafe { node.deref() };
+        loop {
+            let link = node_ref.prev.load(Ordering::Acquire, guard);
+            if link.tag() == 1
+                || node_ref
+                    .prev
+                    .compare_and_set(link, link.with_tag(1), Ordering::AcqRel, guard)
+                    .is_ok()
+            {
+                break;
+            }
+        }
+
+        // Whether `prev` was reached by a `prev` link, i.e., `prev.next` was already examined.
+        let mut last_link = true;
+        let mut prev = node_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+        let mut next = node_ref.next.load(Ordering::Acquire, guard).with_tag(0);
+        loop {
+            if prev == next {
+                break;
+            }
+            let next_ref = unsafe { next.deref() };
+            let next_next = next_ref.next.load(Ordering::Acquire, guard);
+            if next_next.tag() == 1 {
+                next = next_next.with_tag(0);
+                continue;
+            }
+            let prev_ref = unsafe { prev.deref() };
+            let prev_next = prev_ref.next.load(Ordering::Acquire, guard);
+            if prev_next.tag() == 1 {
+                if !last_link {
+                    self.help_delete(prev, guard);
+                    last_link = true;
+                }
+                prev = prev_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+                continue;
+            }
+            if prev_next != node {
+                last_link = false;
+                prev = prev_next;
+                continue;
+            }
+            if !next_ref.link() {
+                continue;
+            }
+            if prev_ref
+                .next
+                .compare_and_set(node, next, Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                unlink(node, guard);
+                break;
+            }
+            unlink(next, guard);
+        }
+    }
+
+    /// Points `node.prev` to its predecessor, starting the search from `prev`. Returns the
+    /// predecessor.
+    fn help_insert<'g>(
+        &self,
+        prev: Shared<'g, Node<T>>,
+        node: Shared<'g, Node<T>>,
+        guard: &'g Guard,
+    ) -> Shared<'g, Node<T>> {
+        let node_ref = unsafe { node.deref() };
+        let mut last_link = true;
+        let mut prev = prev;
+        loop {
+            let prev_ref = unsafe { prev.deref() };
+            let prev_next = prev_ref.next.load(Ordering::Acquire, guard);
+            if prev_next.tag() == 1 {
+                if !last_link {
+                    self.help_delete(prev, guard);
+                    last_link = true;
+                }
+                prev = prev_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+                continue;
+            }
+            // A deleted node may be unlinked by the helpers of its predecessor before its `prev` is
+            // marked, so `prev` can pass it.
+            let link = node_ref.prev.load(Ordering::Acquire, guard);
+            if link.tag() == 1 || node_ref.next.load(Ordering::Acquire, guard).tag() == 1 {
+                break;
+            }
+            if prev_next != node {
+                last_link = false;
+                prev = prev_next;
+                continue;
+            }
+            if link != prev {
+                if !prev_ref.link() {
+                    continue;
+                }
+                if node_ref
+                    .prev
+                    .compare_and_set(link, prev, Ordering::AcqRel, guard)
+                    .is_err()
+                {
+                    unlink(prev, guard);
+                    continue;
+                }
+                unlink(link, guard);
+            }
+            if prev_ref.prev.load(Ordering::Acquire, guard).tag() == 1 {
+                continue;
+            }
+            break;
+        }
+        prev
+    }
+
+    /// Redirects the links of the deleted `node` past its deleted neighbors, so that the deleted
+    /// nodes don't link each other in cycles.
+    fn remove_cross_reference<'g>(&self, node: Shared<'g, Node<T>>, guard: &'g Guard) {
+        // Only the thread that deleted `node` updates its links, which are marked.
+        let node_ref = unsafe { node.deref() };
+        loop {
+            let prev = node_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+            let prev_ref = unsafe { prev.deref() };
+            if prev_ref.next.load(Ordering::Acquire, guard).tag() == 1 {
+                let prev_prev = prev_ref.prev.load(Ordering::Acquire, guard).with_tag(0);
+                if unsafe { prev_prev.deref() }.link() {
+                    node_ref
+                        .prev
+                        .store(prev_prev.with_tag(1), Ordering::Release);
+                    unlink(prev, guard);
+                }
+                continue;
+            }
+            let next = node_ref.next.load(Ordering::Acquire, guard).with_tag(0);
+            let next_next = unsafe { next.deref() }.next.load(Ordering::Acquire, guard);
+            if next_next.tag() == 1 {
+                let next_next = next_next.with_tag(0);
+                if unsafe { next_next.deref() }.link() {
+                    node_ref
+                        .next
+                        .store(next_next.with_tag(1), Ordering::Release);
+                    unlink(next, guard);
+                }
+                continue;
+            }
+            break;
+        }
+    }
+
+    /// The approximate number of values. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// A queue that pushes to the right end and pops from the left end.
+impl<T> ConcurrentQueue<T> for Deque<T> {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn push(&self, value: T, guard: &Guard) {
+        self.push_right(value, guard);
+    }
+    #[inline]
+    fn try_pop(&self, guard: &Guard) -> Option<T> {
+        self.pop_left(guard)
+    }
+}
+
+/// A stack that pushes to and pops from the right end.
+impl<T> ConcurrentStack<T> for Deque<T> {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn push(&self, value: T, guard: &Guard) {
+        self.push_right(value, guard);
+    }
+    #[inline]
+    fn try_pop(&self, guard: &Guard) -> Option<T> {
+        self.pop_right(guard)
+    }
+}
+
+/// Registers the deque, and the pipeline of stages that pass items through deques, or through
+/// stacks with `--pipeline-stack`.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("Deque", Scheme::EBR, |d: &D, _| d.ebr_deque());
+    registry.register_other("Pipeline", Scheme::EBR, |d: &D, _| d.ebr_pipeline());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deque;
+    use crate::ebr::{concurrent_queue, concurrent_stack};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 16;
+    const ELEMENTS_PER_THREADS: usize = 1000;
+
+    #[test]
+    fn smoke_deque() {
+        let deque = &Deque::new();
+        let guard = &pin();
+        for i in 0..10 {
+            deque.push_right(i, guard);
+        }
+        for i in 0..10 {
+            deque.push_left(-i - 1, guard);
+        }
+        assert_eq!(deque.len_approx(), 20);
+        for i in (0..10).rev() {
+            assert_eq!(deque.pop_right(guard), Some(i));
+        }
+        for i in (0..10).rev() {
+            assert_eq!(deque.pop_left(guard), Some(-i - 1));
+        }
+        assert_eq!(deque.pop_left(guard), None);
+        assert_eq!(deque.pop_right(guard), None);
+
+        // Each thread pushes and pops at both ends, so the threads delete neighboring nodes.
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for i in 0..ELEMENTS_PER_THREADS {
+                        let v = (t * ELEMENTS_PER_THREADS + i) as i32;
+                        if (t + i) % 2 == 0 {
+                            deque.push_left(v, &pin());
+                        } else {
+                            deque.push_right(v, &pin());
+                        }
+                        if i % 3 == 0 {
+                            deque.pop_left(&pin()).unwrap();
+                        } else if i % 3 == 1 {
+                            deque.pop_right(&pin()).unwrap();
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+        let expected = THREADS * (0..ELEMENTS_PER_THREADS).filter(|i| i % 3 == 2).count();
+        assert_eq!(deque.len_approx(), expected);
+
+        let mut popped = Vec::new();
+        while let Some(v) = deque.pop_left(guard) {
+            popped.push(v);
+        }
+        assert_eq!(popped.len(), expected);
+        popped.sort();
+        popped.dedup();
+        assert_eq!(popped.len(), expected);
+
+        // The values left in the deque are dropped with it.
+        for i in 0..10 {
+            deque.push_left(i, guard);
+            deque.push_right(i, guard);
+            deque.pop_left(guard);
+        }
+    }
+
+    #[test]
+    fn fifo_deque() {
+        concurrent_queue::tests::smoke::<Deque<usize>>();
+        concurrent_queue::tests::fifo::<Deque<usize>>();
+    }
+
+    #[test]
+    fn lifo_deque() {
+        concurrent_stack::tests::smoke::<Deque<usize>>();
+        concurrent_stack::tests::lifo::<Deque<usize>>();
+    }
+}
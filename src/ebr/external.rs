@@ -0,0 +1,155 @@
+//! `ConcurrentMap` adapters of the concurrent maps of other crates, each behind the feature of its
+//! crate, to compare the algorithms of this crate against them in the same harness.
+//!
+//! The maps reclaim their own nodes with their own schemes, but `get` has to return a reference
+//! that lives as long as the EBR guard of the caller. So the adapters store a pointer to a boxed
+//! value in the map, and the thread that removes the entry retires the box through the guard. This
+//! adds an indirection and an allocation per entry, the same for all the adapters.
+
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Kind, Port, Prefill, Registry, Scheme};
+use crossbeam_ebr::{Guard, Shared};
+use std::hash::Hash;
+
+/// A pointer to a boxed value. The box is owned by the map while the entry is in it, and then by
+/// the thread that removes the entry.
+struct Boxed<V>(*mut V);
+
+impl<V> Clone for Boxed<V> {
+    fn clone(&self) -> Self {
+        Boxed(self.0)
+    }
+}
+
+impl<V> Copy for Boxed<V> {}
+
+impl<V> PartialEq for Boxed<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+unsafe impl<V: Send + Sync> Send for Boxed<V> {}
+unsafe impl<V: Send + Sync> Sync for Boxed<V> {}
+
+impl<V> Boxed<V> {
+    fn new(value: V) -> Self {
+        Boxed(Box::into_raw(Box::new(value)))
+    }
+
+    /// # Safety
+    ///
+    /// The entry of the box must have been in the map after `guard` was pinned.
+    unsafe fn get(self, _guard: &Guard) -> &V {
+        &*self.0
+    }
+
+    /// Takes the value out of the box of a removed entry, and retires the box.
+    ///
+    /// # Safety
+    ///
+    /// The caller must be the only thread that removed the entry.
+    unsafe fn take(self, guard: &Guard) -> V {
+        let value = std::ptr::read(self.0);
+        // Readers may still look at the value, so the box is freed without dropping it again.
+        let boxed = self.0 as *const std::mem::ManuallyDrop<V>;
+        op_stats::retire();
+        guard.retire(Shared::from(boxed));
+        value
+    }
+
+    /// Frees the box of an entry that is not in the map.
+    unsafe fn free(self) -> V {
+        *Box::from_raw(self.0)
+    }
+}
+
+#[cfg(feature = "dashmap")]
+pub use self::dash::DashMap;
+
+#[cfg(feature = "dashmap")]
+mod dash {
+    use super::{Boxed, Hash};
+    use crate::ebr::concurrent_map::ConcurrentMap;
+    use crossbeam_ebr::Guard;
+    use dashmap::mapref::entry::Entry;
+
+    /// `dashmap::DashMap`, a hash map of shards protected by readers-writer locks.
+    pub struct DashMap<K, V>
+    where
+        K: Eq + Hash,
+    {
+        inner: dashmap::DashMap<K, Boxed<V>>,
+    }
+
+    impl<K, V> ConcurrentMap<K, V> for DashMap<K, V>
+    where
+        K: Eq + Hash,
+        V: Send + Sync,
+    {
+        fn new() -> Self {
+            DashMap {
+                inner: dashmap::DashMap::new(),
+            }
+        }
+
+        fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+            let boxed = *self.inner.get(key)?.value();
+            Some(unsafe { boxed.get(guard) })
+        }
+
+        fn insert(&self, key: K, value: V, _guard: &Guard) -> bool {
+            match self.inner.entry(key) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(Boxed::new(value));
+                    true
+                }
+            }
+        }
+
+        fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+            let (_, boxed) = self.inner.remove(key)?;
+            Some(unsafe { boxed.take(guard) })
+        }
+
+        fn len_approx(&self) -> usize {
+            self.inner.len()
+        }
+    }
+
+    impl<K, V> Drop for DashMap<K, V>
+    where
+        K: Eq + Hash,
+    {
+        fn drop(&mut self) {
+            for entry in self.inner.iter() {
+                drop(unsafe { entry.value().free() });
+            }
+        }
+    }
+}
+
+/// Registers the adapters of the features that are enabled.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    #[cfg(feature = "dashmap")]
+    registry.register(
+        "DashMap",
+        Kind::External,
+        Prefill::Random,
+        Scheme::EBR,
+        Port::new(|d, p| d.ebr_map::<DashMap<String, D::Value>>(p)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ebr::concurrent_map;
+
+    #[cfg(feature = "dashmap")]
+    #[test]
+    fn smoke_dash_map() {
+        concurrent_map::tests::smoke::<super::DashMap<i32, String>>();
+    }
+}
@@ -0,0 +1,169 @@
+//! A wrapper around the EBR collector that throttles or boosts the collection of garbage.
+//!
+//! Crossbeam collects garbage on its own every few pins and whenever a thread-local bag of garbage
+//! fills up. On top of that, the handles of the wrapper flush their garbage (which also tries to
+//! advance the epoch and collects the global garbage) when a critical section ends:
+//!
+//! - `Lazy` never flushes, leaving the collection entirely to Crossbeam.
+//! - `Eager` flushes at the end of every critical section.
+//! - `Adaptive` flushes only while the outstanding garbage, i.e., the nodes that are retired but
+//!   not reclaimed yet, exceeds a threshold.
+//! - `Idle` flushes only when the worker is idle, i.e., when an operation finds no work (e.g., a
+//!   pop from an empty queue) or right after a think time, and then also starts a new critical
+//!   section, so that the collection happens off the critical path of the operations.
+//!
+//! The outstanding garbage is estimated from the retires counted by `op_stats`. After a thread
+//! adds the nodes it retired in a critical section to the counter, it defers subtracting them
+//! again, which happens when the garbage of the critical section is reclaimed.
+
+use crate::op_stats;
+use crate::profiling;
+use crossbeam_ebr::Guard;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcPolicy {
+    Lazy,
+    Eager,
+    Adaptive,
+    Idle,
+}
+
+impl GcPolicy {
+    pub const NAMES: [&'static str; 4] = ["lazy", "eager", "adaptive", "idle"];
+}
+
+impl FromStr for GcPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lazy" => Ok(GcPolicy::Lazy),
+            "eager" => Ok(GcPolicy::Eager),
+            "adaptive" => Ok(GcPolicy::Adaptive),
+            "idle" => Ok(GcPolicy::Idle),
+            _ => Err(format!("invalid GC policy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for GcPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GcPolicy::Lazy => "lazy",
+            GcPolicy::Eager => "eager",
+            GcPolicy::Adaptive => "adaptive",
+            GcPolicy::Idle => "idle",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct Collector {
+    collector: crossbeam_ebr::Collector,
+    policy: GcPolicy,
+    /// `Adaptive` flushes while the outstanding garbage exceeds this many nodes.
+    threshold: usize,
+    /// Shared with the deferred functions, which may outlive the collector.
+    outstanding: Arc<AtomicUsize>,
+    flushes: AtomicUsize,
+}
+
+impl Collector {
+    pub fn new(policy: GcPolicy, threshold: usize) -> Self {
+        if policy == GcPolicy::Adaptive {
+            op_stats::enable();
+        }
+        Self {
+            collector: crossbeam_ebr::Collector::new(),
+            policy,
+            threshold,
+            outstanding: Arc::new(AtomicUsize::new(0)),
+            flushes: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn register(&self) -> LocalHandle<'_> {
+        LocalHandle {
+            handle: self.collector.register(),
+            collector: self,
+            retires: op_stats::retires(),
+            flushes: 0,
+        }
+    }
+
+    /// The number of flushes triggered by the policy in the handles dropped so far.
+    pub fn flushes(&self) -> usize {
+        self.flushes.load(Ordering::Relaxed)
+    }
+}
+
+pub struct LocalHandle<'c> {
+    handle: crossbeam_ebr::LocalHandle,
+    collector: &'c Collector,
+    /// The retires of the current thread that are already added to the outstanding garbage.
+    retires: u64,
+    /// The flushes of the handle, which are added to the collector when the handle is dropped.
+    flushes: usize,
+}
+
+impl Drop for LocalHandle<'_> {
+    fn drop(&mut self) {
+        self.collector
+            .flushes
+            .fetch_add(self.flushes, Ordering::Relaxed);
+    }
+}
+
+impl LocalHandle<'_> {
+    pub fn pin(&self) -> Guard {
+        profiling::pin(|| self.handle.pin())
+    }
+
+    /// Ends the critical section of `guard`, collecting garbage according to the policy, and
+    /// starts a new one.
+    pub fn repin(&mut self, guard: Guard) -> Guard {
+        if self.should_flush(&guard) {
+            self.flushes += 1;
+            profiling::collect(|| guard.flush());
+        }
+        drop(guard);
+        self.pin()
+    }
+
+    /// Tells the handle that the worker is idle, i.e., its last operation found no work or it has
+    /// just thought. Under `Idle`, flushes the garbage and starts a new critical section, and
+    /// otherwise returns `guard` as is.
+    pub fn idle(&mut self, guard: Guard) -> Guard {
+        if self.collector.policy != GcPolicy::Idle {
+            return guard;
+        }
+        self.flushes += 1;
+        profiling::collect(|| guard.flush());
+        drop(guard);
+        self.pin()
+    }
+
+    fn should_flush(&mut self, guard: &Guard) -> bool {
+        match self.collector.policy {
+            GcPolicy::Lazy | GcPolicy::Idle => false,
+            GcPolicy::Eager => true,
+            GcPolicy::Adaptive => {
+                let retires = op_stats::retires();
+                let new = retires.saturating_sub(self.retires) as usize;
+                self.retires = retires;
+                let outstanding = &self.collector.outstanding;
+                if new > 0 {
+                    outstanding.fetch_add(new, Ordering::Relaxed);
+                    let outstanding = outstanding.clone();
+                    guard.defer(move || outstanding.fetch_sub(new, Ordering::Relaxed));
+                }
+                outstanding.load(Ordering::Relaxed) > self.collector.threshold
+            }
+        }
+    }
+}
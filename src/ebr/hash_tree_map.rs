@@ -0,0 +1,129 @@
+//! A hash map whose buckets are Natarajan-Mittal trees instead of lists.
+//!
+//! With fewer buckets than `HashMap`, each bucket holds many keys, but a traversal still visits
+//! only the logarithmic path of its key in the tree of the bucket. The bucket count spans the
+//! design space between a single `NMTreeMap` (one bucket) and `HashMap` (buckets of a key or two).
+//! It is read from `set_buckets` when a map is created.
+
+use super::concurrent_map::ConcurrentMap;
+use super::natarajan_mittal_tree::NMTreeMap;
+use crate::hashing::Hashing;
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::Guard;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUCKETS: AtomicUsize = AtomicUsize::new(1024);
+
+/// Sets the number of buckets of the maps created afterwards.
+pub fn set_buckets(buckets: usize) {
+    assert!(buckets > 0);
+    BUCKETS.store(buckets, Ordering::Relaxed);
+}
+
+pub struct HashTreeMap<K, V> {
+    buckets: Vec<NMTreeMap<K, V>>,
+    len: StripedCounter,
+    hashing: Hashing,
+}
+
+impl<K, V> HashTreeMap<K, V>
+where
+    K: Ord + Clone + Hash,
+    V: Clone,
+{
+    pub fn with_buckets(n: usize) -> Self {
+        assert!(n > 0);
+        HashTreeMap {
+            buckets: (0..n).map(|_| NMTreeMap::new()).collect(),
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+        }
+    }
+
+    #[inline]
+    fn bucket(&self, k: &K) -> &NMTreeMap<K, V> {
+        let index = self.hashing.hash(k) as usize % self.buckets.len();
+        unsafe { self.buckets.get_unchecked(index) }
+    }
+
+    pub fn get<'g>(&'g self, k: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.bucket(k).get(k, guard)
+    }
+
+    pub fn insert(&self, k: K, v: V, guard: &Guard) -> bool {
+        self.bucket(&k).insert(k, v, guard).is_ok()
+    }
+
+    pub fn remove(&self, k: &K, guard: &Guard) -> Option<V> {
+        self.bucket(k).remove(k, guard)
+    }
+
+    pub fn remove_get<'g>(&'g self, k: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.bucket(k).remove_get(k, guard)
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for HashTreeMap<K, V>
+where
+    K: Ord + Clone + Hash,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::with_buckets(BUCKETS.load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let removed = self.remove_get(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    ebr;
+    HashTreeMap => HashTreeMap, Random;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashTreeMap;
+    use crate::ebr::concurrent_map;
+
+    #[test]
+    fn smoke_hash_tree_map() {
+        concurrent_map::tests::smoke::<HashTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_hash_tree_map() {
+        concurrent_map::tests::shadow::<HashTreeMap<i32, String>>();
+    }
+}
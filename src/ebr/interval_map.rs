@@ -0,0 +1,167 @@
+//! A map from half-open intervals of points to values that answers stabbing queries.
+//!
+//! The points are split into partitions of `max_len` consecutive points, and an interval is stored
+//! in the Harris-Michael list of the partition of its start, keyed by its start and end. Since no
+//! interval is longer than `max_len`, the intervals that contain a point start in the partition of
+//! the point or in the one before, so a stabbing query walks the prefixes of two lists and reads
+//! every interval that starts at most `max_len` points before the point.
+
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::Guard;
+
+use super::list::List;
+
+pub struct IntervalMap<V> {
+    partitions: Vec<List<(usize, usize), V>>,
+    max_len: usize,
+    len: StripedCounter,
+}
+
+impl<V> IntervalMap<V> {
+    /// A map of the intervals that start in `[0, range)` and are at most `max_len` points long.
+    pub fn new(range: usize, max_len: usize) -> Self {
+        assert!(max_len > 0);
+        let partitions = (range + max_len - 1) / max_len;
+        Self {
+            partitions: (0..partitions).map(|_| List::new()).collect(),
+            max_len,
+            len: StripedCounter::new(),
+        }
+    }
+
+    fn partition(&self, start: usize) -> &List<(usize, usize), V> {
+        &self.partitions[start / self.max_len]
+    }
+
+    /// Inserts the interval `[start, end)`. Fails if the interval is already in the map.
+    pub fn insert(&self, start: usize, end: usize, value: V, guard: &Guard) -> bool {
+        assert!(start < end && end - start <= self.max_len);
+        let inserted = self
+            .partition(start)
+            .harris_michael_insert((start, end), value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+
+    pub fn remove(&self, start: usize, end: usize, guard: &Guard) -> Option<V> {
+        let removed = self
+            .partition(start)
+            .harris_michael_remove(&(start, end), guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+
+    /// Returns the intervals that contain `point` with their values, in increasing order of their
+    /// starts.
+    ///
+    /// The query is not atomic: it reflects the concurrent updates to the part of the lists that
+    /// it has not visited yet.
+    pub fn find_containing<'g>(
+        &'g self,
+        point: usize,
+        guard: &'g Guard,
+    ) -> Vec<(&'g (usize, usize), &'g V)> {
+        let mut found = Vec::new();
+        let first = (point + 1).saturating_sub(self.max_len) / self.max_len;
+        let last = point / self.max_len;
+        for partition in self.partitions.iter().take(last + 1).skip(first) {
+            partition.for_each_below(&(point + 1, 0), guard, |interval, value| {
+                if interval.1 > point {
+                    found.push((interval, value));
+                }
+            });
+        }
+        found
+    }
+
+    /// The approximate number of intervals. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// Registers the interval map.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("IntervalMap", Scheme::EBR, |d: &D, _| d.ebr_interval_map());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalMap;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+    const RANGE: usize = 1000;
+    const MAX_LEN: usize = 10;
+
+    #[test]
+    fn smoke_interval_map() {
+        let map = &IntervalMap::new(RANGE, MAX_LEN);
+        let guard = &pin();
+        let intervals = (0..RANGE / 3)
+            .map(|i| (i * 3, i * 3 + 1 + i % MAX_LEN))
+            .collect::<Vec<_>>();
+        for &(start, end) in &intervals {
+            assert!(map.insert(start, end, start, guard));
+            assert!(!map.insert(start, end, start, guard));
+        }
+        for &(start, end) in intervals.iter().step_by(2) {
+            assert_eq!(map.remove(start, end, guard), Some(start));
+        }
+        assert_eq!(map.len_approx(), intervals.len() / 2);
+        for point in 0..RANGE + MAX_LEN {
+            let found = map
+                .find_containing(point, guard)
+                .into_iter()
+                .map(|(&interval, &value)| {
+                    assert_eq!(interval.0, value);
+                    interval
+                })
+                .collect::<Vec<_>>();
+            let expected = intervals
+                .iter()
+                .skip(1)
+                .step_by(2)
+                .cloned()
+                .filter(|&(start, end)| start <= point && point < end)
+                .collect::<Vec<_>>();
+            assert_eq!(found, expected);
+        }
+
+        // The intervals that start at the multiples of `MAX_LEN` stay, while the others come and
+        // go, so every point is in a staying interval.
+        let map = &IntervalMap::new(RANGE, MAX_LEN);
+        for start in (0..RANGE).step_by(MAX_LEN) {
+            assert!(map.insert(start, start + MAX_LEN, (), guard));
+        }
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let guard = &pin();
+                    for i in 0..1000 {
+                        let point = (i * 37 + t) % RANGE;
+                        let start = point / MAX_LEN * MAX_LEN;
+                        let found = map.find_containing(point, guard);
+                        assert!(found.iter().any(|(&i, _)| i == (start, start + MAX_LEN)));
+                        assert!(found.iter().all(|(&(s, e), _)| s <= point && point < e));
+                        if point != start {
+                            let end = point + 1 + t % (MAX_LEN - 1);
+                            if i % 2 == 0 {
+                                map.insert(point, end, (), guard);
+                            } else {
+                                map.remove(point, end, guard);
+                            }
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+}
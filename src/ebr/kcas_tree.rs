@@ -0,0 +1,437 @@
+//! A leaf-oriented binary search tree whose updates are multi-word CASes.
+//!
+//! The entries are in the leaves, and each internal node has two children. An insertion replaces a
+//! leaf with an internal node whose children are the leaf and a new one, with an MCAS of the child
+//! pointer of the parent. A removal replaces the parent of the leaf with the sibling of the leaf,
+//! and marks both child pointers of the parent so that no other update modifies the removed parent,
+//! with a single MCAS of the three words.
+
+use crossbeam_ebr::{unprotected, Guard, Owned, Shared};
+
+use super::concurrent_map::ConcurrentMap;
+use super::mcas::{self, Mcas, Word};
+use super::transfer_map::TransferMap;
+use crate::op_stats;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use std::cmp;
+use std::ptr;
+
+/// Marks the child pointers of a removed internal node. The low 2 bits are reserved for MCAS.
+const MARK: usize = 4;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Key<K> {
+    Fin(K),
+    Inf,
+}
+
+impl<K: Ord> Key<K> {
+    fn cmp(&self, rhs: &K) -> cmp::Ordering {
+        match self {
+            Key::Fin(k) => k.cmp(rhs),
+            Key::Inf => cmp::Ordering::Greater,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Direction {
+    L,
+    R,
+}
+
+impl Direction {
+    fn other(self) -> Self {
+        match self {
+            Direction::L => Direction::R,
+            Direction::R => Direction::L,
+        }
+    }
+}
+
+/// Aligned so that the pointers to nodes leave room for the mark.
+#[repr(align(8))]
+struct Node<K, V> {
+    key: Key<K>,
+    value: Option<V>,
+    /// The children of an internal node, possibly marked. Zero in a leaf.
+    left: Word,
+    right: Word,
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    fn leaf(key: Key<K>, value: Option<V>) -> Self {
+        Self {
+            key,
+            value,
+            left: Word::new(0),
+            right: Word::new(0),
+        }
+    }
+
+    /// Keys less than `key` go left.
+    fn internal(key: Key<K>, left: usize, right: usize) -> Self {
+        Self {
+            key,
+            value: None,
+            left: Word::new(left),
+            right: Word::new(right),
+        }
+    }
+
+    fn child(&self, dir: Direction) -> &Word {
+        match dir {
+            Direction::L => &self.left,
+            Direction::R => &self.right,
+        }
+    }
+
+    fn dir(&self, key: &K) -> Direction {
+        if self.key.cmp(key) == cmp::Ordering::Greater {
+            Direction::L
+        } else {
+            Direction::R
+        }
+    }
+}
+
+/// Dereferences a possibly marked pointer to a node.
+unsafe fn node<'g, K, V>(ptr: usize) -> &'g Node<K, V> {
+    &*((ptr & !MARK) as *const Node<K, V>)
+}
+
+fn into_word<K, V>(node: Shared<'_, Node<K, V>>) -> usize {
+    node.as_raw() as usize
+}
+
+struct Cursor {
+    gp: usize,
+    gp_dir: Direction,
+    p: usize,
+    p_dir: Direction,
+    /// The child pointer of `p` to the leaf, possibly marked.
+    l: usize,
+}
+
+pub struct KCASTreeMap<K, V> {
+    r: Node<K, V>,
+    len: StripedCounter,
+}
+
+impl<K, V> Default for KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for KCASTreeMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack = vec![self.r.left.value(), self.r.right.value()];
+        while let Some(ptr) = stack.pop() {
+            if ptr == 0 {
+                continue;
+            }
+            let mut node = unsafe { Shared::from(ptr as *const Node<K, V>).into_owned() };
+            stack.push(node.left.value() & !MARK);
+            stack.push(node.right.value() & !MARK);
+        }
+    }
+}
+
+impl<K, V> KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        // As in the Natarajan-Mittal tree, the sentinels with infinite keys make sure that a leaf
+        // with a finite key has a parent and a grandparent.
+        //          r
+        //         / \
+        //        s  inf2
+        //       / \
+        //   inf0   inf1
+        let guard = unsafe { unprotected() };
+        let leaf = || into_word(Owned::new(Node::<K, V>::leaf(Key::Inf, None)).into_shared(guard));
+        let s = Owned::new(Node::<K, V>::internal(Key::Inf, leaf(), leaf())).into_shared(guard);
+        Self {
+            r: Node::internal(Key::Inf, into_word(s), leaf()),
+            len: StripedCounter::new(),
+        }
+    }
+
+    fn seek(&self, key: &K, guard: &Guard) -> Cursor {
+        let mut cursor = Cursor {
+            gp: 0,
+            gp_dir: Direction::L,
+            p: &self.r as *const _ as usize,
+            p_dir: Direction::L,
+            l: self.r.left.read(guard),
+        };
+        loop {
+            let l = unsafe { node::<K, V>(cursor.l) };
+            let dir = l.dir(key);
+            let next = l.child(dir).read(guard);
+            if next == 0 {
+                return cursor;
+            }
+            cursor.gp = cursor.p;
+            cursor.gp_dir = cursor.p_dir;
+            cursor.p = cursor.l & !MARK;
+            cursor.p_dir = dir;
+            cursor.l = next;
+        }
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let cursor = self.seek(key, guard);
+        let l = unsafe { node::<K, V>(cursor.l) };
+        if l.key.cmp(key) == cmp::Ordering::Equal {
+            l.value.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let leaf =
+            Owned::new(Node::<K, V>::leaf(Key::Fin(key.clone()), Some(value))).into_shared(guard);
+        loop {
+            let cursor = self.seek(&key, guard);
+            if cursor.l & MARK != 0 {
+                // The parent is removed.
+                restarts::record();
+                continue;
+            }
+            let l = unsafe { node::<K, V>(cursor.l) };
+            let (left, right) = match l.key.cmp(&key) {
+                cmp::Ordering::Equal => {
+                    drop(unsafe { leaf.into_owned() });
+                    return false;
+                }
+                cmp::Ordering::Greater => (leaf, Shared::from(cursor.l as *const Node<K, V>)),
+                cmp::Ordering::Less => (Shared::from(cursor.l as *const Node<K, V>), leaf),
+            };
+            let internal_key = unsafe { right.deref() }.key.clone();
+            let internal = Owned::new(Node::<K, V>::internal(
+                internal_key,
+                into_word(left),
+                into_word(right),
+            ))
+            .into_shared(guard);
+
+            let p = unsafe { node::<K, V>(cursor.p) };
+            let mut op = Mcas::new();
+            op.add(p.child(cursor.p_dir), cursor.l, into_word(internal));
+            if op.execute(guard) {
+                return true;
+            }
+            drop(unsafe { internal.into_owned() });
+            restarts::record();
+        }
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove_get(key, guard).cloned()
+    }
+
+    /// Like `remove`, but returns the value in the retired leaf.
+    pub fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        loop {
+            let cursor = self.seek(key, guard);
+            let l = unsafe { node::<K, V>(cursor.l) };
+            if l.key.cmp(key) != cmp::Ordering::Equal {
+                return None;
+            }
+            let p = unsafe { node::<K, V>(cursor.p) };
+            let sibling_dir = cursor.p_dir.other();
+            let sibling = p.child(sibling_dir).read(guard);
+            if (cursor.l | sibling) & MARK != 0 {
+                // The parent is removed.
+                restarts::record();
+                continue;
+            }
+
+            let value = l.value.as_ref();
+            let gp = unsafe { node::<K, V>(cursor.gp) };
+            let mut op = Mcas::new();
+            op.add(gp.child(cursor.gp_dir), cursor.p, sibling);
+            op.add(p.child(cursor.p_dir), cursor.l, cursor.l | MARK);
+            op.add(p.child(sibling_dir), sibling, sibling | MARK);
+            if op.execute(guard) {
+                unsafe {
+                    op_stats::retire();
+                    mcas::retire(Shared::from(cursor.p as *const Node<K, V>), guard);
+                    op_stats::retire();
+                    mcas::retire(Shared::from(cursor.l as *const Node<K, V>), guard);
+                }
+                return value;
+            }
+            restarts::record();
+        }
+    }
+
+    /// Moves the entry of `key` to `to`, with a single MCAS of the three words of the removal from
+    /// `self` and the word of the insertion into `to`. The new leaf holds a clone of the value.
+    pub fn transfer(&self, key: &K, to: &Self, guard: &Guard) -> bool {
+        assert!(!ptr::eq(self, to), "a map cannot transfer to itself");
+        loop {
+            let cursor = self.seek(key, guard);
+            let l = unsafe { node::<K, V>(cursor.l) };
+            if l.key.cmp(key) != cmp::Ordering::Equal {
+                return false;
+            }
+            let p = unsafe { node::<K, V>(cursor.p) };
+            let sibling_dir = cursor.p_dir.other();
+            let sibling = p.child(sibling_dir).read(guard);
+            if (cursor.l | sibling) & MARK != 0 {
+                // The parent is removed.
+                restarts::record();
+                continue;
+            }
+
+            let to_cursor = to.seek(key, guard);
+            if to_cursor.l & MARK != 0 {
+                restarts::record();
+                continue;
+            }
+            let to_l = unsafe { node::<K, V>(to_cursor.l) };
+            let leaf = Owned::new(Node::<K, V>::leaf(Key::Fin(key.clone()), l.value.clone()))
+                .into_shared(guard);
+            let (left, right) = match to_l.key.cmp(key) {
+                cmp::Ordering::Equal => {
+                    drop(unsafe { leaf.into_owned() });
+                    return false;
+                }
+                cmp::Ordering::Greater => (leaf, Shared::from(to_cursor.l as *const Node<K, V>)),
+                cmp::Ordering::Less => (Shared::from(to_cursor.l as *const Node<K, V>), leaf),
+            };
+            let internal_key = unsafe { right.deref() }.key.clone();
+            let internal = Owned::new(Node::<K, V>::internal(
+                internal_key,
+                into_word(left),
+                into_word(right),
+            ))
+            .into_shared(guard);
+
+            let gp = unsafe { node::<K, V>(cursor.gp) };
+            let to_p = unsafe { node::<K, V>(to_cursor.p) };
+            let mut op = Mcas::new();
+            op.add(gp.child(cursor.gp_dir), cursor.p, sibling);
+            op.add(p.child(cursor.p_dir), cursor.l, cursor.l | MARK);
+            op.add(p.child(sibling_dir), sibling, sibling | MARK);
+            op.add(
+                to_p.child(to_cursor.p_dir),
+                to_cursor.l,
+                into_word(internal),
+            );
+            if op.execute(guard) {
+                unsafe {
+                    op_stats::retire();
+                    mcas::retire(Shared::from(cursor.p as *const Node<K, V>), guard);
+                    op_stats::retire();
+                    mcas::retire(Shared::from(cursor.l as *const Node<K, V>), guard);
+                }
+                return true;
+            }
+            unsafe {
+                drop(internal.into_owned());
+                drop(leaf.into_owned());
+            }
+            restarts::record();
+        }
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let removed = self.remove_get(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V> TransferMap<K, V> for KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    #[inline]
+    fn transfer(&self, key: &K, to: &Self, guard: &Guard) -> bool {
+        let transferred = self.transfer(key, to, guard);
+        if transferred {
+            self.len.add(-1);
+            to.len.add(1);
+        }
+        transferred
+    }
+}
+
+register_maps! {
+    ebr;
+    KCASTree => KCASTreeMap, Random, with transfers;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KCASTreeMap;
+    use crate::ebr::{concurrent_map, transfer_map};
+
+    #[test]
+    fn smoke_kcas_tree() {
+        concurrent_map::tests::smoke::<KCASTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_kcas_tree() {
+        concurrent_map::tests::remove_get::<KCASTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn transfer_kcas_tree() {
+        transfer_map::tests::transfer::<KCASTreeMap<i32, String>>();
+    }
+}
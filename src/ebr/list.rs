@@ -1,8 +1,25 @@
 use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use super::ordered_map::OrderedMap;
+use super::unguarded_map::UnguardedMap;
+use crate::alloc_fault::{self, AllocError};
+use crate::cas_fault::ebr::CompareAndSet;
+use crate::harris_stats;
+use crate::load_ordering;
+use crate::op_stats;
+use crate::profiling;
+use crate::reclaim::ebr::Retire;
+use crate::remove_phases::{self, Phase};
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crate::tagged::{FetchOr, MarkOp};
 use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
 
 use std::cmp::Ordering::{Equal, Greater, Less};
+use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
+#[cfg(feature = "sentinel-lists")]
+use std::mem::MaybeUninit;
 use std::ptr;
 use std::sync::atomic::Ordering;
 
@@ -14,46 +31,146 @@ struct Node<K, V> {
     value: ManuallyDrop<V>,
 }
 
-struct List<K, V> {
+#[cfg(feature = "sentinel-lists")]
+impl<K, V> Node<K, V> {
+    /// Allocates a sentinel that links to `next`. Its key and value are left uninitialized, as
+    /// they are never read.
+    fn sentinel(next: Shared<'_, Self>) -> Shared<'_, Self> {
+        let mut node = Box::new(MaybeUninit::<Self>::uninit());
+        unsafe { ptr::write(&mut (*node.as_mut_ptr()).next, Atomic::from(next)) };
+        Shared::from(Box::into_raw(node) as *const Self)
+    }
+
+    unsafe fn free_sentinel(node: Shared<'_, Self>) {
+        drop(Box::from_raw(node.as_raw() as *mut MaybeUninit<Self>));
+    }
+}
+
+/// With the `sentinel-lists` feature, the list starts with a head sentinel and ends with a tail
+/// sentinel that are never removed, so that no link of the list is null. Otherwise `head` links to
+/// the first node and the last node links to null.
+pub struct List<K, V, M = FetchOr> {
     head: Atomic<Node<K, V>>,
+    #[cfg(feature = "sentinel-lists")]
+    tail: Atomic<Node<K, V>>,
+    _marker: PhantomData<M>,
 }
 
-impl<K, V> Default for List<K, V>
+impl<K, V, M> Default for List<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<K, V> Drop for List<K, V> {
+impl<K, V, M> Drop for List<K, V, M> {
     fn drop(&mut self) {
         unsafe {
-            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+            let guard = unprotected();
+            let mut curr = self.first(guard).load(Ordering::Relaxed, guard);
+            let end = self.end(guard);
 
-            while !curr.is_null() {
+            while curr.with_tag(0) != end {
                 let curr_ref = curr.deref_mut();
-                let next = curr_ref.next.load(Ordering::Relaxed, unprotected());
+                let next = curr_ref.next.load(Ordering::Relaxed, guard);
                 if next.tag() == 0 {
                     ManuallyDrop::drop(&mut curr_ref.value);
                 }
                 drop(curr.into_owned());
                 curr = next;
             }
+
+            #[cfg(feature = "sentinel-lists")]
+            {
+                Node::free_sentinel(self.head.load(Ordering::Relaxed, guard));
+                Node::free_sentinel(end);
+            }
         }
     }
 }
 
+impl<K, V, M> List<K, V, M> {
+    /// The link to the first node.
+    #[cfg(feature = "sentinel-lists")]
+    #[inline]
+    fn first<'g>(&'g self, guard: &'g Guard) -> &'g Atomic<Node<K, V>> {
+        unsafe { &self.head.load(Ordering::Relaxed, guard).deref().next }
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
+    #[inline]
+    fn first<'g>(&'g self, _guard: &'g Guard) -> &'g Atomic<Node<K, V>> {
+        &self.head
+    }
+
+    /// The pointer that ends the list.
+    #[cfg(feature = "sentinel-lists")]
+    #[inline]
+    fn end<'g>(&self, guard: &'g Guard) -> Shared<'g, Node<K, V>> {
+        self.tail.load(Ordering::Relaxed, guard)
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
+    #[inline]
+    fn end<'g>(&self, _guard: &'g Guard) -> Shared<'g, Node<K, V>> {
+        Shared::null()
+    }
+
+    /// The number of entries, i.e. the unmarked nodes, counted without synchronization.
+    #[cfg(any(feature = "hash-maps", feature = "queues"))]
+    pub(crate) fn len_exclusive(&mut self) -> usize {
+        let mut len = 0;
+        unsafe {
+            let guard = unprotected();
+            let mut curr = self.first(guard).load(Ordering::Relaxed, guard);
+            let end = self.end(guard);
+            while curr.with_tag(0) != end {
+                let next = curr.deref().next.load(Ordering::Relaxed, guard);
+                len += (next.tag() == 0) as usize;
+                curr = next;
+            }
+        }
+        len
+    }
+}
+
+/// The node of `ptr`, or `None` if `ptr` is `end`. `ptr` may be tagged, as `find_harris_herlihy_shavit`
+/// follows the links of removed nodes.
+#[inline]
+fn node<'g, K, V>(
+    ptr: Shared<'g, Node<K, V>>,
+    end: Shared<'g, Node<K, V>>,
+) -> Option<&'g Node<K, V>> {
+    if cfg!(feature = "sentinel-lists") {
+        if ptr.with_tag(0) == end {
+            None
+        } else {
+            Some(unsafe { ptr.deref() })
+        }
+    } else {
+        unsafe { ptr.as_ref() }
+    }
+}
+
 struct Cursor<'g, K, V> {
     prev: &'g Atomic<Node<K, V>>,
     curr: Shared<'g, Node<K, V>>,
+    end: Shared<'g, Node<K, V>>,
 }
 
 impl<'g, K, V> Cursor<'g, K, V>
 where
     K: Ord,
 {
+    /// The node of `curr`, or `None` at the end of the list.
+    #[inline]
+    fn curr_node(&self) -> Option<&'g Node<K, V>> {
+        node(self.curr, self.end)
+    }
+
     /// Clean up a chain of logically removed nodes in each traversal.
     #[inline]
     fn find_harris(&mut self, key: &K, guard: &'g Guard) -> Result<bool, ()> {
@@ -61,25 +178,35 @@ where
         // - cursor.curr: first unmarked node w/ key >= search key (4)
         // - cursor.prev: the ref of .next in previous unmarked node (1 -> 2)
         // 1 -> 2 -x-> 3 -x-> 4 -> 5 -> ∅  (search key: 4)
+        let max_batch = harris_stats::max_batch();
         let mut prev_next = self.curr;
+        let mut batch = 0;
         let found = loop {
-            let curr_node = some_or!(unsafe { self.curr.as_ref() }, break false);
-            let next = curr_node.next.load(Ordering::Acquire, guard);
+            let curr_node = some_or!(self.curr_node(), break false);
+            let next = curr_node.next.load(load_ordering::NEXT, guard);
 
             // - finding stage is done if cursor.curr advancement stops
             // - advance cursor.curr if (.next is marked) || (cursor.curr < key)
             // - stop cursor.curr if (not marked) && (cursor.curr >= key)
             // - advance cursor.prev if not marked
             match (curr_node.key.cmp(key), next.tag()) {
-                (Less, tag) => {
+                (Less, 0) => {
+                    self.curr = next;
+                    self.prev = &curr_node.next;
+                    prev_next = next;
+                    batch = 0;
+                }
+                (cmp, 0) => break cmp == Equal,
+                _ => {
                     self.curr = next.with_tag(0);
-                    if tag == 0 {
-                        self.prev = &curr_node.next;
-                        prev_next = next;
+                    batch += 1;
+                    // Unlink the chain so far if it's too long.
+                    if batch == max_batch {
+                        self.unlink(prev_next, guard)?;
+                        prev_next = self.curr;
+                        batch = 0;
                     }
                 }
-                (cmp, 0) => break cmp == Equal,
-                _ => self.curr = next.with_tag(0),
             }
         };
 
@@ -89,37 +216,74 @@ where
         }
 
         // cleanup marked nodes between prev and curr
-        if self
-            .prev
-            .compare_and_set(prev_next, self.curr, Ordering::Release, guard)
-            .is_err()
-        {
-            return Err(());
-        }
+        self.unlink(prev_next, guard)?;
+        Ok(found)
+    }
+
+    /// Unlinks the marked nodes from `prev_next` to `self.curr` (exclusive) with a single CAS.
+    #[inline]
+    fn unlink(&self, prev_next: Shared<'g, Node<K, V>>, guard: &'g Guard) -> Result<(), ()> {
+        self.prev
+            .cas(prev_next, self.curr, Ordering::Release, guard)
+            .map_err(|_| ())?;
 
         // defer_destroy from cursor.prev.load() to cursor.curr (exclusive)
         let mut node = prev_next;
-        loop {
-            if node.with_tag(0) == self.curr {
-                return Ok(found);
-            }
+        let mut batch = 0;
+        while node.with_tag(0) != self.curr {
             let node_ref = unsafe { node.as_ref().unwrap() };
             let next = node_ref.next.load(Ordering::Relaxed, guard);
             unsafe {
-                guard.defer_destroy(node);
+                op_stats::retire();
+                guard.retire(node);
             }
             node = next;
+            batch += 1;
         }
+        harris_stats::record(batch);
+        Ok(())
     }
 
     /// Clean up a single logically removed node in each traversal.
     #[inline]
     fn find_harris_michael(&mut self, key: &K, guard: &'g Guard) -> Result<bool, ()> {
+        self.find_harris_michael_inner(key, false, guard)
+    }
+
+    /// Cleans up like `find_harris_michael`, but validates that `prev` still points to `curr`
+    /// before every step. When the validation or a cleanup CAS fails, it resumes from `prev` rather
+    /// than failing, and fails only if the node of `prev` is removed. The extra load makes it
+    /// slower, so `List::find` falls back to it only after many failures (see `restarts`).
+    #[inline]
+    fn find_validated(&mut self, key: &K, guard: &'g Guard) -> Result<bool, ()> {
+        self.find_harris_michael_inner(key, true, guard)
+    }
+
+    /// The traversal of `find_harris_michael`, and with `validate` of `find_validated`. The callers
+    /// pass a constant, so each of them gets a copy without the branches of the other.
+    #[inline(always)]
+    fn find_harris_michael_inner(
+        &mut self,
+        key: &K,
+        validate: bool,
+        guard: &'g Guard,
+    ) -> Result<bool, ()> {
         loop {
             debug_assert_eq!(self.curr.tag(), 0);
 
-            let curr_node = some_or!(unsafe { self.curr.as_ref() }, return Ok(false));
-            let mut next = curr_node.next.load(Ordering::Acquire, guard);
+            let curr_node = some_or!(self.curr_node(), return Ok(false));
+            let mut next = curr_node.next.load(load_ordering::NEXT, guard);
+
+            if validate {
+                let prev_next = self.prev.load(Ordering::Acquire, guard);
+                if prev_next != self.curr {
+                    if prev_next.tag() != 0 {
+                        return Err(());
+                    }
+                    self.curr = prev_next;
+                    continue;
+                }
+            }
 
             if next.tag() == 0 {
                 match curr_node.key.cmp(key) {
@@ -129,26 +293,41 @@ where
                 }
             } else {
                 next = next.with_tag(0);
-                match self
-                    .prev
-                    .compare_and_set(self.curr, next, Ordering::Release, guard)
-                {
+                match self.prev.cas(self.curr, next, Ordering::Release, guard) {
+                    // The validation resumes from `prev`.
+                    Err(_) if validate => continue,
                     Err(_) => return Err(()),
-                    Ok(_) => unsafe { guard.defer_destroy(self.curr) },
+                    Ok(_) => {
+                        op_stats::retire();
+                        unsafe { guard.retire(self.curr) };
+                    }
                 }
             }
             self.curr = next;
         }
     }
 
+    /// Clean up like `find_harris_michael`, or like `find_harris` while the current thread sees
+    /// many failed CASes (see `harris_stats::record_traversal`).
+    #[inline]
+    fn find_adaptive(&mut self, key: &K, guard: &'g Guard) -> Result<bool, ()> {
+        let result = if harris_stats::adaptive_harris() {
+            self.find_harris(key, guard)
+        } else {
+            self.find_harris_michael(key, guard)
+        };
+        harris_stats::record_traversal(result.is_err());
+        result
+    }
+
     /// Gotta go fast. Doesn't fail.
     #[inline]
     fn find_harris_herlihy_shavit(&mut self, key: &K, guard: &'g Guard) -> Result<bool, ()> {
         Ok(loop {
-            let curr_node = some_or!(unsafe { self.curr.as_ref() }, break false);
+            let curr_node = some_or!(self.curr_node(), break false);
             match curr_node.key.cmp(key) {
                 Less => {
-                    self.curr = curr_node.next.load(Ordering::Acquire, guard);
+                    self.curr = curr_node.next.load(load_ordering::NEXT, guard);
                     // NOTE: unnecessary (this function is expected to be used only for `get`)
                     self.prev = &curr_node.next;
                     continue;
@@ -160,13 +339,44 @@ where
     }
 }
 
-impl<K, V> List<K, V>
+impl<K, V, M> List<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
+    #[cfg(feature = "sentinel-lists")]
+    pub fn new() -> Self {
+        let tail = Node::sentinel(Shared::null());
+        List {
+            head: Atomic::from(Node::sentinel(tail)),
+            tail: Atomic::from(tail),
+            _marker: PhantomData,
+        }
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
     pub fn new() -> Self {
         List {
             head: Atomic::null(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Marks `link` and returns its previous value, like `fetch_or(1)`.
+    #[inline]
+    fn mark<'g>(link: &Atomic<Node<K, V>>, guard: &'g Guard) -> Shared<'g, Node<K, V>> {
+        if !M::CAS {
+            return link.fetch_or(1, Ordering::Relaxed, guard);
+        }
+        let mut next = link.load(Ordering::Relaxed, guard);
+        loop {
+            if next.tag() == 1 {
+                return next;
+            }
+            match link.cas(next, next.with_tag(1), Ordering::Relaxed, guard) {
+                Ok(_) => return next,
+                Err(e) => next = e.current,
+            }
         }
     }
 
@@ -175,14 +385,26 @@ where
     where
         F: Fn(&mut Cursor<'g, K, V>, &K, &'g Guard) -> Result<bool, ()>,
     {
+        let mut failures = 0;
         loop {
+            let prev = self.first(guard);
             let mut cursor = Cursor {
-                prev: &self.head,
-                curr: self.head.load(Ordering::Acquire, guard),
+                prev,
+                curr: prev.load(Ordering::Acquire, guard),
+                end: self.end(guard),
             };
-            if let Ok(r) = find(&mut cursor, key, guard) {
+            let result = profiling::find(|| {
+                if restarts::exhausted(failures) {
+                    cursor.find_validated(key, guard)
+                } else {
+                    find(&mut cursor, key, guard)
+                }
+            });
+            if let Ok(r) = result {
                 return (r, cursor);
             }
+            failures += 1;
+            restarts::record();
         }
     }
 
@@ -204,12 +426,43 @@ where
     where
         F: Fn(&mut Cursor<'g, K, V>, &K, &'g Guard) -> Result<bool, ()>,
     {
-        let mut node = Owned::new(Node {
+        let node = Owned::new(Node {
             key,
             value: ManuallyDrop::new(value),
             next: Atomic::null(),
         });
+        self.insert_node(node, find, guard)
+    }
+
+    /// Like `insert`, but fails if the node cannot be allocated.
+    #[inline]
+    fn try_insert<'g, F>(
+        &'g self,
+        key: K,
+        value: V,
+        find: F,
+        guard: &'g Guard,
+    ) -> Result<bool, AllocError>
+    where
+        F: Fn(&mut Cursor<'g, K, V>, &K, &'g Guard) -> Result<bool, ()>,
+    {
+        let node = alloc_fault::try_box(Node {
+            key,
+            value: ManuallyDrop::new(value),
+            next: Atomic::null(),
+        })
+        .map_err(|node| {
+            drop(ManuallyDrop::into_inner(node.value));
+            AllocError
+        })?;
+        Ok(self.insert_node(Owned::from(node), find, guard))
+    }
 
+    #[inline]
+    fn insert_node<'g, F>(&'g self, mut node: Owned<Node<K, V>>, find: F, guard: &'g Guard) -> bool
+    where
+        F: Fn(&mut Cursor<'g, K, V>, &K, &'g Guard) -> Result<bool, ()>,
+    {
         loop {
             let (found, cursor) = self.find(&node.key, &find, guard);
             if found {
@@ -220,43 +473,57 @@ where
             }
 
             node.next.store(cursor.curr, Ordering::Relaxed);
-            match cursor
-                .prev
-                .compare_and_set(cursor.curr, node, Ordering::Release, guard)
-            {
+            match cursor.prev.cas(cursor.curr, node, Ordering::Release, guard) {
                 Ok(_) => return true,
-                Err(e) => node = e.new,
+                Err(e) => {
+                    node = e.new;
+                    restarts::record();
+                }
             }
         }
     }
 
+    /// Removes the entry of `key` if `pred` holds for its value. The value of a node never changes,
+    /// so the node is marked only after `pred` is checked.
     #[inline]
-    fn remove<'g, F>(&'g self, key: &K, find: F, guard: &'g Guard) -> Option<V>
+    fn remove<'g, F, P>(&'g self, key: &K, mut pred: P, find: F, guard: &'g Guard) -> Option<V>
     where
         F: Fn(&mut Cursor<'g, K, V>, &K, &'g Guard) -> Result<bool, ()>,
+        P: FnMut(&V) -> bool,
     {
+        let mut timer = remove_phases::Timer::start();
         loop {
             let (found, cursor) = self.find(key, &find, guard);
             if !found {
+                timer.finish(Phase::Search, false);
                 return None;
             }
 
             let curr_node = unsafe { cursor.curr.as_ref() }.unwrap();
+            if !pred(&curr_node.value) {
+                timer.finish(Phase::Search, false);
+                return None;
+            }
             let value = unsafe { ptr::read(&curr_node.value) };
+            timer.lap(Phase::Search);
 
-            let next = curr_node.next.fetch_or(1, Ordering::Relaxed, guard);
+            let next = Self::mark(&curr_node.next, guard);
             if next.tag() == 1 {
+                restarts::record();
                 continue;
             }
+            timer.lap(Phase::Mark);
 
             if cursor
                 .prev
-                .compare_and_set(cursor.curr, next, Ordering::Release, guard)
+                .cas(cursor.curr, next, Ordering::Release, guard)
                 .is_ok()
             {
-                unsafe { guard.defer_destroy(cursor.curr) };
+                op_stats::retire();
+                unsafe { guard.retire(cursor.curr) };
             }
 
+            timer.finish(Phase::Unlink, true);
             return Some(ManuallyDrop::into_inner(value));
         }
     }
@@ -269,8 +536,19 @@ where
         self.insert(key, value, Cursor::find_harris, guard)
     }
 
+    pub fn harris_try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        self.try_insert(key, value, Cursor::find_harris, guard)
+    }
+
     pub fn harris_remove<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<V> {
-        self.remove(key, Cursor::find_harris, guard)
+        self.remove(key, |_| true, Cursor::find_harris, guard)
+    }
+
+    pub fn harris_remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris, guard)
     }
 
     pub fn harris_michael_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
@@ -281,8 +559,47 @@ where
         self.insert(key, value, Cursor::find_harris_michael, guard)
     }
 
+    pub fn harris_michael_try_insert(
+        &self,
+        key: K,
+        value: V,
+        guard: &Guard,
+    ) -> Result<bool, AllocError> {
+        self.try_insert(key, value, Cursor::find_harris_michael, guard)
+    }
+
     pub fn harris_michael_remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.remove(key, Cursor::find_harris_michael, guard)
+        self.remove(key, |_| true, Cursor::find_harris_michael, guard)
+    }
+
+    pub fn harris_michael_remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris_michael, guard)
+    }
+
+    pub fn adaptive_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, Cursor::find_adaptive, guard)
+    }
+
+    pub fn adaptive_insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        self.insert(key, value, Cursor::find_adaptive, guard)
+    }
+
+    pub fn adaptive_try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        self.try_insert(key, value, Cursor::find_adaptive, guard)
+    }
+
+    pub fn adaptive_remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove(key, |_| true, Cursor::find_adaptive, guard)
+    }
+
+    pub fn adaptive_remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_adaptive, guard)
     }
 
     pub fn harris_herlihy_shavit_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
@@ -293,21 +610,110 @@ where
         self.insert(key, value, Cursor::find_harris_michael, guard)
     }
 
+    pub fn harris_herlihy_shavit_try_insert(
+        &self,
+        key: K,
+        value: V,
+        guard: &Guard,
+    ) -> Result<bool, AllocError> {
+        self.try_insert(key, value, Cursor::find_harris_michael, guard)
+    }
+
     pub fn harris_herlihy_shavit_remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.remove(key, Cursor::find_harris_michael, guard)
+        self.remove(key, |_| true, Cursor::find_harris_michael, guard)
+    }
+
+    pub fn harris_herlihy_shavit_remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris_michael, guard)
+    }
+
+    /// Finds the first unmarked node, passing over the marked nodes without unlinking them.
+    pub fn first_entry<'g>(&'g self, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let mut curr = self.first(guard).load(Ordering::Acquire, guard);
+        let end = self.end(guard);
+        while let Some(curr_node) = node(curr, end) {
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                return Some((&curr_node.key, &*curr_node.value));
+            }
+            curr = next.with_tag(0);
+        }
+        None
+    }
+
+    /// Finds the first unmarked node with a key greater than `key`, passing over the marked nodes
+    /// without unlinking them.
+    pub fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let mut curr = self.first(guard).load(Ordering::Acquire, guard);
+        let end = self.end(guard);
+        while let Some(curr_node) = node(curr, end) {
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 && curr_node.key > *key {
+                return Some((&curr_node.key, &*curr_node.value));
+            }
+            curr = next.with_tag(0);
+        }
+        None
+    }
+
+    /// Finds the last unmarked node with a key less than `key`, passing over the marked nodes
+    /// without unlinking them.
+    pub fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let mut found = None;
+        let mut curr = self.first(guard).load(Ordering::Acquire, guard);
+        let end = self.end(guard);
+        while let Some(curr_node) = node(curr, end) {
+            if curr_node.key >= *key {
+                break;
+            }
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                found = Some((&curr_node.key, &*curr_node.value));
+            }
+            curr = next.with_tag(0);
+        }
+        found
+    }
+
+    /// Calls `f` with the entries of the unmarked nodes with keys less than `bound` in increasing
+    /// order of the keys, passing over the marked nodes without unlinking them.
+    pub fn for_each_below<'g, F>(&'g self, bound: &K, guard: &'g Guard, mut f: F)
+    where
+        F: FnMut(&'g K, &'g V),
+    {
+        let mut curr = self.first(guard).load(Ordering::Acquire, guard);
+        let end = self.end(guard);
+        while let Some(curr_node) = node(curr, end) {
+            if curr_node.key >= *bound {
+                break;
+            }
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                f(&curr_node.key, &*curr_node.value);
+            }
+            curr = next.with_tag(0);
+        }
     }
 }
 
-pub struct HList<K, V> {
-    inner: List<K, V>,
+pub struct HList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
 }
 
-impl<K, V> ConcurrentMap<K, V> for HList<K, V>
+impl<K, V, M> ConcurrentMap<K, V> for HList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
     fn new() -> Self {
-        HList { inner: List::new() }
+        HList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     #[inline]
@@ -316,24 +722,82 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.inner.harris_insert(key, value, guard)
+        let inserted = self.inner.harris_insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self.inner.harris_try_insert(key, value, guard)?;
+        if inserted {
+            self.len.add(1);
+        }
+        Ok(inserted)
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.inner.harris_remove(key, guard)
+        let removed = self.inner.harris_remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, M> ConditionalRemove<K, V> for HList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.harris_remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+impl<K, V, M> OrderedMap<K, V> for HList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, guard)
     }
 }
 
-pub struct HMList<K, V> {
-    inner: List<K, V>,
+pub struct HMList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
 }
 
-impl<K, V> ConcurrentMap<K, V> for HMList<K, V>
+impl<K, V, M> ConcurrentMap<K, V> for HMList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
     fn new() -> Self {
-        HMList { inner: List::new() }
+        HMList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     #[inline]
@@ -342,24 +806,82 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.inner.harris_michael_insert(key, value, guard)
+        let inserted = self.inner.harris_michael_insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self.inner.harris_michael_try_insert(key, value, guard)?;
+        if inserted {
+            self.len.add(1);
+        }
+        Ok(inserted)
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.inner.harris_michael_remove(key, guard)
+        let removed = self.inner.harris_michael_remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
-pub struct HHSList<K, V> {
-    inner: List<K, V>,
+impl<K, V, M> ConditionalRemove<K, V> for HMList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.harris_michael_remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
 }
 
-impl<K, V> ConcurrentMap<K, V> for HHSList<K, V>
+impl<K, V, M> OrderedMap<K, V> for HMList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, guard)
+    }
+}
+
+pub struct HHSList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
 {
     fn new() -> Self {
-        HHSList { inner: List::new() }
+        HHSList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     #[inline]
@@ -368,18 +890,175 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.inner.harris_herlihy_shavit_insert(key, value, guard)
+        let inserted = self.inner.harris_herlihy_shavit_insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self
+            .inner
+            .harris_herlihy_shavit_try_insert(key, value, guard)?;
+        if inserted {
+            self.len.add(1);
+        }
+        Ok(inserted)
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.inner.harris_herlihy_shavit_remove(key, guard)
+        let removed = self.inner.harris_herlihy_shavit_remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, M> ConditionalRemove<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.harris_herlihy_shavit_remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+/// The get of the Herlihy-Shavit list only reads.
+impl<K, V, M> UnguardedMap<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+}
+
+impl<K, V, M> OrderedMap<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, guard)
+    }
+}
+
+pub struct AdaptiveList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    fn new() -> Self {
+        AdaptiveList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.inner.adaptive_get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.inner.adaptive_insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self.inner.adaptive_try_insert(key, value, guard)?;
+        if inserted {
+            self.len.add(1);
+        }
+        Ok(inserted)
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.inner.adaptive_remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, M> ConditionalRemove<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.adaptive_remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
     }
 }
 
+impl<K, V, M> OrderedMap<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, guard)
+    }
+}
+
+register_maps! {
+    ebr;
+    HList => HList, Decreasing, with scan cas_mark compare_and_delete;
+    HMList => HMList, Decreasing, with scan cas_mark compare_and_delete;
+    HHSList => HHSList, Decreasing, with scan unguarded cas_mark compare_and_delete;
+    AdaptiveList => AdaptiveList, Decreasing, with scan cas_mark compare_and_delete;
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{HHSList, HList, HMList};
-    use crate::ebr::concurrent_map;
+    use super::{AdaptiveList, HHSList, HList, HMList};
+    use crate::ebr::{concurrent_map, conditional_remove, ordered_map, unguarded_map};
+    use crate::restarts;
+    use crate::tagged::CasMark;
 
     #[test]
     fn smoke_h_list() {
@@ -395,4 +1074,117 @@ mod tests {
     fn smoke_hhs_list() {
         concurrent_map::tests::smoke::<HHSList<i32, String>>();
     }
+
+    #[test]
+    fn smoke_adaptive_list() {
+        concurrent_map::tests::smoke::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn try_insert_h_list() {
+        concurrent_map::tests::try_insert::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn try_insert_hm_list() {
+        concurrent_map::tests::try_insert::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn try_insert_hhs_list() {
+        concurrent_map::tests::try_insert::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn try_insert_adaptive_list() {
+        concurrent_map::tests::try_insert::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_h_list() {
+        concurrent_map::tests::shadow::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_hm_list() {
+        concurrent_map::tests::shadow::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_hhs_list() {
+        concurrent_map::tests::shadow::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_adaptive_list() {
+        concurrent_map::tests::shadow::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_h_list() {
+        conditional_remove::tests::remove_if::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_hm_list() {
+        conditional_remove::tests::remove_if::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_hm_list() {
+        concurrent_map::tests::remove_get::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_hhs_list() {
+        conditional_remove::tests::remove_if::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_adaptive_list() {
+        conditional_remove::tests::remove_if::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_h_list_cas_mark() {
+        concurrent_map::tests::smoke::<HList<i32, String, CasMark>>();
+    }
+
+    #[test]
+    fn smoke_hm_list_cas_mark() {
+        concurrent_map::tests::smoke::<HMList<i32, String, CasMark>>();
+    }
+
+    #[test]
+    fn ordered_h_list() {
+        ordered_map::tests::ordered::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_hm_list() {
+        ordered_map::tests::ordered::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_hhs_list() {
+        ordered_map::tests::ordered::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn unguarded_hhs_list() {
+        unguarded_map::tests::unguarded::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_adaptive_list() {
+        ordered_map::tests::ordered::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_h_list_bounded_restarts() {
+        // Other tests may run under the bound too, which only makes them take the validated
+        // traversal after their first failure.
+        restarts::set_bound(1);
+        concurrent_map::tests::smoke::<HList<i32, String>>();
+    }
 }
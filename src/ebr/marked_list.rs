@@ -0,0 +1,286 @@
+//! Harris-Michael lists whose links are marked with tag bits or with version counters (see
+//! `crate::tagged`). Apart from the links they are the same, so that the difference in throughput
+//! is the cost of the marking.
+
+use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crate::tagged::{Link, Snapshot, TagLink, VersionLink};
+use crossbeam_ebr::{Guard, Owned, Shared};
+
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::marker::PhantomData;
+
+struct Node<K, V, L> {
+    next: L,
+    key: K,
+    value: V,
+}
+
+unsafe fn node<'g, K, V, L>(ptr: usize) -> Option<&'g Node<K, V, L>> {
+    (ptr as *const Node<K, V, L>).as_ref()
+}
+
+pub struct MarkedList<K, V, L: Link> {
+    head: L,
+    len: StripedCounter,
+    _marker: PhantomData<Box<Node<K, V, L>>>,
+}
+
+pub type TagList<K, V> = MarkedList<K, V, TagLink>;
+pub type VersionList<K, V> = MarkedList<K, V, VersionLink>;
+
+impl<K, V, L> Default for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, L> Drop for MarkedList<K, V, L>
+where
+    L: Link,
+{
+    fn drop(&mut self) {
+        let mut ptr = self.head.load().ptr;
+        while ptr != 0 {
+            let node = unsafe { Shared::from(ptr as *const Node<K, V, L>).into_owned() };
+            ptr = node.next.load().ptr;
+        }
+    }
+}
+
+impl<K, V, L> MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    pub fn new() -> Self {
+        L::audit::<Node<K, V, L>>();
+        Self {
+            head: L::new(0),
+            len: StripedCounter::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns whether `key` is found, the link to the first node whose key is not less than `key`
+    /// and the snapshot of the link. Unlinks the removed nodes on the way one by one.
+    fn find<'g>(&'g self, key: &K, guard: &'g Guard) -> (bool, &'g L, Snapshot) {
+        'retry: loop {
+            let mut prev = &self.head;
+            let mut prev_snap = prev.load();
+            loop {
+                let curr = some_or!(
+                    unsafe { node::<K, V, L>(prev_snap.ptr) },
+                    return (false, prev, prev_snap)
+                );
+                let next_snap = curr.next.load();
+                if next_snap.marked {
+                    match prev.cas(prev_snap, next_snap.ptr, false) {
+                        Ok(snap) => unsafe {
+                            op_stats::retire();
+                            guard.retire(Shared::from(curr as *const Node<K, V, L>));
+                            prev_snap = snap;
+                        },
+                        Err(_) => {
+                            restarts::record();
+                            continue 'retry;
+                        }
+                    }
+                    continue;
+                }
+                match curr.key.cmp(key) {
+                    Less => {
+                        prev = &curr.next;
+                        prev_snap = next_snap;
+                    }
+                    Equal => return (true, prev, prev_snap),
+                    Greater => return (false, prev, prev_snap),
+                }
+            }
+        }
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let (found, _, snap) = self.find(key, guard);
+        if found {
+            Some(&unsafe { node::<K, V, L>(snap.ptr) }.unwrap().value)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let mut new = Owned::new(Node {
+            next: L::new(0),
+            key,
+            value,
+        })
+        .into_shared(guard);
+        loop {
+            let (found, prev, prev_snap) = self.find(&unsafe { new.deref() }.key, guard);
+            if found {
+                drop(unsafe { new.into_owned() });
+                return false;
+            }
+            unsafe { new.deref_mut() }.next.set(prev_snap.ptr);
+            if prev.cas(prev_snap, new.as_raw() as usize, false).is_ok() {
+                return true;
+            }
+            restarts::record();
+        }
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove_if(key, |_| true, guard)
+    }
+
+    /// Removes the entry of `key` if `pred` holds for its value, which never changes.
+    pub fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove_get_if(key, pred, guard).cloned()
+    }
+
+    /// Like `remove_if`, but returns the value in the retired node.
+    pub fn remove_get_if<'g, P>(&'g self, key: &K, mut pred: P, guard: &'g Guard) -> Option<&'g V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        loop {
+            let (found, prev, prev_snap) = self.find(key, guard);
+            if !found {
+                return None;
+            }
+            let curr = unsafe { node::<K, V, L>(prev_snap.ptr) }.unwrap();
+            if !pred(&curr.value) {
+                return None;
+            }
+            let next_snap = curr.next.load();
+            if next_snap.marked || curr.next.cas(next_snap, next_snap.ptr, true).is_err() {
+                restarts::record();
+                continue;
+            }
+
+            if prev.cas(prev_snap, next_snap.ptr, false).is_ok() {
+                unsafe {
+                    op_stats::retire();
+                    guard.retire(Shared::from(curr as *const Node<K, V, L>));
+                }
+            } else {
+                // Let `find` unlink it.
+                self.find(key, guard);
+            }
+            return Some(&curr.value);
+        }
+    }
+}
+
+impl<K, V, L> ConcurrentMap<K, V> for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let removed = self.remove_get_if(key, |_| true, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, L> ConditionalRemove<K, V> for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+register_maps! {
+    ebr;
+    TagList => TagList, Decreasing, with compare_and_delete;
+    VersionList => VersionList, Decreasing, with compare_and_delete;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TagList, VersionList};
+    use crate::ebr::{concurrent_map, conditional_remove};
+    use crate::tagged;
+
+    #[test]
+    fn smoke_tag_list() {
+        concurrent_map::tests::smoke::<TagList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_tag_list() {
+        conditional_remove::tests::remove_if::<TagList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_tag_list() {
+        concurrent_map::tests::remove_get::<TagList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_version_list() {
+        if !tagged::dwcas_available() {
+            return;
+        }
+        concurrent_map::tests::smoke::<VersionList<i32, String>>();
+    }
+}
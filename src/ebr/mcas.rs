@@ -0,0 +1,363 @@
+//! Harris, Fraser and Pratt's multi-word compare-and-swap (MCAS), built from their restricted
+//! double-compare single-swap (RDCSS).
+//!
+//! See Timothy L. Harris, Keir Fraser and Ian A. Pratt, A Practical Multi-Word Compare-and-Swap
+//! Operation, DISC 2002.
+//!
+//! A `Word` holds a value or, while an operation is in progress on it, a pointer to a descriptor
+//! tagged in the low 2 bits, so the values must leave them zero (e.g. pointers to aligned nodes).
+//! An MCAS installs its descriptor in its words in the order of their addresses with RDCSS, which
+//! installs it only while the MCAS is undecided, then decides whether it succeeded, and finally
+//! replaces the descriptor with the new or the old values. An MCAS that finds another one in its
+//! way helps it first. A reader doesn't help: the descriptor tells the value of the word.
+//!
+//! An RDCSS descriptor is retired by the thread that installed it, after it has removed it. An MCAS
+//! descriptor is retired by its owner after it has released the words. However, a helper may reach
+//! a retired object through an MCAS descriptor: e.g. an MCAS doomed to fail on a word of a removed
+//! node may still be installed in other words, where a thread that pinned after the node was
+//! retired finds it and helps it, accessing the node. Such a thread pinned while the owner of the
+//! descriptor was still pinned, so the MCAS descriptors and the objects containing words are
+//! retired with `retire`, which waits for an extra grace period.
+
+use crate::drop_cost;
+use crate::reclaim::{self, ebr::Retire};
+use crossbeam_ebr::{Guard, Owned, Shared};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const RDCSS: usize = 1;
+const MCAS: usize = 2;
+const TAGS: usize = 3;
+
+const UNDECIDED: usize = 0;
+const SUCCEEDED: usize = 1;
+const FAILED: usize = 2;
+
+/// A word that MCAS operates on. The low 2 bits of its values must be zero.
+#[derive(Debug)]
+pub struct Word {
+    inner: AtomicUsize,
+}
+
+struct Entry {
+    word: *const Word,
+    old: usize,
+    new: usize,
+}
+
+struct McasDesc {
+    status: AtomicUsize,
+    /// Sorted by the addresses of the words.
+    entries: Vec<Entry>,
+}
+
+impl McasDesc {
+    /// The value of `word`, where the descriptor is installed.
+    fn value(&self, word: &Word) -> usize {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.word == word as *const _)
+            .unwrap();
+        if self.status.load(Ordering::Acquire) == SUCCEEDED {
+            entry.new
+        } else {
+            entry.old
+        }
+    }
+}
+
+/// Installs the tagged MCAS descriptor `mcas` in `word` if the word has `old` and the MCAS is
+/// undecided.
+struct RdcssDesc {
+    mcas: usize,
+    word: *const Word,
+    old: usize,
+}
+
+impl Word {
+    pub fn new(value: usize) -> Self {
+        debug_assert_eq!(value & TAGS, 0);
+        Self {
+            inner: AtomicUsize::new(value),
+        }
+    }
+
+    /// Reads the value. If an MCAS is in progress on the word, the value is the new one if the MCAS
+    /// has succeeded and the old one otherwise.
+    pub fn read(&self, _guard: &Guard) -> usize {
+        let value = self.inner.load(Ordering::Acquire);
+        match value & TAGS {
+            // An RDCSS is installed only while its MCAS is undecided.
+            RDCSS => unsafe { &*((value & !TAGS) as *const RdcssDesc) }.old,
+            MCAS => unsafe { &*((value & !TAGS) as *const McasDesc) }.value(self),
+            _ => value,
+        }
+    }
+
+    /// The value when no operation can be in progress, e.g. when dropping the structure.
+    pub fn value(&mut self) -> usize {
+        let value = *self.inner.get_mut();
+        debug_assert_eq!(value & TAGS, 0);
+        value
+    }
+}
+
+/// A multi-word CAS under construction.
+pub struct Mcas<'w> {
+    entries: Vec<Entry>,
+    _marker: PhantomData<&'w Word>,
+}
+
+impl<'w> Default for Mcas<'w> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'w> Mcas<'w> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds the CAS of `word` from `old` to `new`. The words of an MCAS must be distinct.
+    pub fn add(&mut self, word: &'w Word, old: usize, new: usize) {
+        debug_assert_eq!((old | new) & TAGS, 0);
+        self.entries.push(Entry { word, old, new });
+    }
+
+    /// Atomically replaces the old values of the words with the new ones if all of the words have
+    /// the old values. Returns whether it succeeded.
+    pub fn execute(mut self, guard: &Guard) -> bool {
+        self.entries.sort_by_key(|e| e.word as usize);
+        let desc = Owned::new(McasDesc {
+            status: AtomicUsize::new(UNDECIDED),
+            entries: self.entries,
+        })
+        .into_shared(guard);
+        unsafe {
+            let succeeded = help(desc.as_raw() as usize | MCAS, guard);
+            retire(desc, guard);
+            succeeded
+        }
+    }
+}
+
+/// Runs the tagged MCAS descriptor `desc` to completion. Returns whether it succeeded.
+unsafe fn help(desc: usize, guard: &Guard) -> bool {
+    let mcas = &*((desc & !TAGS) as *const McasDesc);
+    if mcas.status.load(Ordering::Acquire) == UNDECIDED {
+        let mut status = SUCCEEDED;
+        'entries: for entry in &mcas.entries {
+            loop {
+                let current = rdcss(desc, entry, guard);
+                if current & TAGS == MCAS && current != desc {
+                    help(current, guard);
+                    continue;
+                }
+                if current != desc && current != entry.old {
+                    status = FAILED;
+                    break 'entries;
+                }
+                break;
+            }
+        }
+        let _ =
+            mcas.status
+                .compare_exchange(UNDECIDED, status, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    let succeeded = mcas.status.load(Ordering::Acquire) == SUCCEEDED;
+    for entry in &mcas.entries {
+        let value = if succeeded { entry.new } else { entry.old };
+        release(&*entry.word, desc, value);
+    }
+    succeeded
+}
+
+/// Installs `desc` in the word of `entry` with RDCSS. Returns the old value if it did, and the
+/// value of the word otherwise, after completing the RDCSSes in the way.
+unsafe fn rdcss(desc: usize, entry: &Entry, guard: &Guard) -> usize {
+    let word = &(*entry.word).inner;
+    let mut rdcss_desc = None;
+    loop {
+        let current = word.load(Ordering::Acquire);
+        if current & TAGS == RDCSS {
+            complete(current);
+            continue;
+        }
+        if current != entry.old {
+            return current;
+        }
+
+        let shared = rdcss_desc
+            .take()
+            .unwrap_or_else(|| {
+                Owned::new(RdcssDesc {
+                    mcas: desc,
+                    word: entry.word,
+                    old: entry.old,
+                })
+            })
+            .into_shared(guard);
+        let tagged = shared.as_raw() as usize | RDCSS;
+        match word.compare_exchange(entry.old, tagged, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {
+                complete(tagged);
+                guard.retire(shared);
+                return entry.old;
+            }
+            Err(_) => rdcss_desc = Some(shared.into_owned()),
+        }
+    }
+}
+
+/// Replaces the tagged RDCSS descriptor `desc` with its MCAS descriptor if the MCAS is undecided,
+/// and with the old value otherwise.
+unsafe fn complete(desc: usize) {
+    let rdcss = &*((desc & !TAGS) as *const RdcssDesc);
+    let status = &(*((rdcss.mcas & !TAGS) as *const McasDesc)).status;
+    let value = if status.load(Ordering::Acquire) == UNDECIDED {
+        rdcss.mcas
+    } else {
+        rdcss.old
+    };
+    let _ = (*rdcss.word)
+        .inner
+        .compare_exchange(desc, value, Ordering::AcqRel, Ordering::Relaxed);
+}
+
+/// Replaces the decided MCAS descriptor `desc` in `word` with `value`.
+///
+/// An RDCSS in the way is completed, since it may have read the status of `desc` before it was
+/// decided and would install `desc` again after `desc` is retired.
+unsafe fn release(word: &Word, desc: usize, value: usize) {
+    loop {
+        let current = word.inner.load(Ordering::Acquire);
+        if current & TAGS == RDCSS {
+            complete(current);
+            continue;
+        }
+        if current != desc
+            || word
+                .inner
+                .compare_exchange(desc, value, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            return;
+        }
+    }
+}
+
+type Garbage = (usize, unsafe fn(usize));
+
+/// The objects retired with `retire` by the current thread, waiting for the first grace period.
+#[derive(Default)]
+struct Limbo {
+    /// Retired since the last seal.
+    open: Vec<Garbage>,
+    /// Waiting for the grace period that ends when the flag is set.
+    sealed: Option<(Arc<AtomicBool>, Vec<Garbage>)>,
+}
+
+thread_local! {
+    /// The objects in the limbo of an exiting thread are leaked, since it has no guard to defer
+    /// them with.
+    static LIMBO: RefCell<Limbo> = RefCell::new(Limbo::default());
+}
+
+impl Limbo {
+    unsafe fn retire(&mut self, garbage: Garbage, guard: &Guard) {
+        self.open.push(garbage);
+        if let Some((passed, _)) = &self.sealed {
+            if !passed.load(Ordering::Acquire) {
+                return;
+            }
+            let (_, sealed) = self.sealed.take().unwrap();
+            for (ptr, destroy) in sealed {
+                if reclaim::enabled() {
+                    guard.defer_unchecked(move || {
+                        drop_cost::pay();
+                        destroy(ptr)
+                    });
+                } else {
+                    reclaim::keep(ptr);
+                }
+            }
+        }
+        let passed = Arc::new(AtomicBool::new(false));
+        let flag = passed.clone();
+        guard.defer(move || flag.store(true, Ordering::Release));
+        self.sealed = Some((passed, mem::replace(&mut self.open, Vec::new())));
+    }
+}
+
+unsafe fn destroy<T>(ptr: usize) {
+    drop(Shared::from(ptr as *const T).into_owned());
+}
+
+/// Retires an object that MCAS descriptors may reference, i.e. an MCAS descriptor or an object
+/// containing words, destroying it after two grace periods.
+///
+/// # Safety
+///
+/// The same as `defer_destroy`.
+pub unsafe fn retire<T>(ptr: Shared<'_, T>, guard: &Guard) {
+    let garbage = (ptr.as_raw() as usize, destroy::<T> as unsafe fn(usize));
+    LIMBO.with(|limbo| limbo.borrow_mut().retire(garbage, guard));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mcas, Word};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+    const INCREMENTS: usize = 10_000;
+
+    #[test]
+    fn concurrent_increments() {
+        let words = &(0..4).map(|_| Word::new(0)).collect::<Vec<_>>();
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    // Each thread increments an overlapping set of 3 words atomically.
+                    let indices = [t % 4, (t + 1) % 4, (t + 2) % 4];
+                    for _ in 0..INCREMENTS {
+                        loop {
+                            let guard = &pin();
+                            let mut mcas = Mcas::new();
+                            for &i in &indices {
+                                let old = words[i].read(guard);
+                                mcas.add(&words[i], old, old + 4);
+                            }
+                            if mcas.execute(guard) {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        let guard = &pin();
+        let total = words.iter().map(|w| w.read(guard)).sum::<usize>();
+        assert_eq!(total, 4 * 3 * THREADS * INCREMENTS);
+
+        // An MCAS fails if any word has a different value.
+        let old = words[0].read(guard);
+        let mut mcas = Mcas::new();
+        mcas.add(&words[0], old, 0);
+        mcas.add(&words[1], 4, 0);
+        assert!(!mcas.execute(guard));
+        assert_eq!(words[0].read(guard), old);
+    }
+}
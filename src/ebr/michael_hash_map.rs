@@ -1,12 +1,34 @@
+// Clippy takes the `HashMap` of this module for the one of std, which has a hasher parameter.
+#![allow(clippy::implicit_hasher)]
+
 use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use crate::alloc_fault::AllocError;
+use crate::eviction::{self, Capacity, Eviction};
+use crate::hashing::{self, Hashing};
+use crate::striped_counter::StripedCounter;
 use crossbeam_ebr::Guard;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use rand::Rng;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use super::list::HMList;
+use super::list::List;
 
 pub struct HashMap<K, V> {
-    buckets: Vec<HMList<K, V>>,
+    buckets: Vec<List<K, V>>,
+    len: StripedCounter,
+    hashing: Hashing,
+    capacity: Option<Capacity>,
+    /// The bucket that the next eviction starts from under `Eviction::Sweep`.
+    hand: AtomicUsize,
+}
+
+impl<K, V> Drop for HashMap<K, V> {
+    fn drop(&mut self) {
+        if hashing::enabled() {
+            hashing::record_chains(self.buckets.iter_mut().map(List::len_exclusive));
+        }
+    }
 }
 
 impl<K, V> HashMap<K, V>
@@ -16,38 +38,84 @@ where
     pub fn with_capacity(n: usize) -> Self {
         let mut buckets = Vec::with_capacity(n);
         for _ in 0..n {
-            buckets.push(HMList::new());
+            buckets.push(List::new());
         }
 
-        HashMap { buckets }
+        HashMap {
+            buckets,
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+            capacity: Capacity::current(),
+            hand: AtomicUsize::new(0),
+        }
     }
 
     #[inline]
-    pub fn get_bucket(&self, index: usize) -> &HMList<K, V> {
+    pub fn get_bucket(&self, index: usize) -> &List<K, V> {
         unsafe { self.buckets.get_unchecked(index % self.buckets.len()) }
     }
 
     // TODO(@jeehoonkang): we're converting u64 to usize, which may lose information.
     #[inline]
-    fn hash(k: &K) -> usize {
-        let mut s = DefaultHasher::new();
-        k.hash(&mut s);
-        s.finish() as usize
+    fn hash(&self, k: &K) -> usize {
+        self.hashing.hash(k) as usize
     }
 
     pub fn get<'g>(&'g self, k: &'g K, guard: &'g Guard) -> Option<&'g V> {
-        let i = Self::hash(k);
-        self.get_bucket(i).get(k, guard)
+        let i = self.hash(k);
+        self.get_bucket(i).harris_michael_get(k, guard)
     }
 
     pub fn insert(&self, k: K, v: V, guard: &Guard) -> bool {
-        let i = Self::hash(&k);
-        self.get_bucket(i).insert(k, v, guard)
+        let i = self.hash(&k);
+        self.get_bucket(i).harris_michael_insert(k, v, guard)
+    }
+
+    pub fn try_insert(&self, k: K, v: V, guard: &Guard) -> Result<bool, AllocError> {
+        let i = self.hash(&k);
+        self.get_bucket(i).harris_michael_try_insert(k, v, guard)
     }
 
     pub fn remove(&self, k: &K, guard: &Guard) -> Option<V> {
-        let i = Self::hash(&k);
-        self.get_bucket(i).remove(k, guard)
+        let i = self.hash(&k);
+        self.get_bucket(i).harris_michael_remove(k, guard)
+    }
+
+    pub fn remove_if<P>(&self, k: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let i = self.hash(&k);
+        self.get_bucket(i).harris_michael_remove_if(k, pred, guard)
+    }
+
+    /// Counts an inserted entry, and evicts one if the insert took the map over its bound.
+    fn count_insert(&self, guard: &Guard) {
+        self.len.add(1);
+        if let Some(capacity) = self.capacity {
+            if self.len.approx() > capacity.bound {
+                self.evict(capacity.policy, guard);
+            }
+        }
+    }
+
+    /// Removes the first entry of the bucket chosen by `policy`, or of the next nonempty one, to
+    /// keep the map within its bound.
+    fn evict(&self, policy: Eviction, guard: &Guard) {
+        let start = match policy {
+            Eviction::Random => rand::thread_rng().gen(),
+            Eviction::Sweep => self.hand.fetch_add(1, Ordering::Relaxed),
+        };
+        for i in 0..self.buckets.len() {
+            let bucket = self.get_bucket(start.wrapping_add(i));
+            if let Some((key, _)) = bucket.first_entry(guard) {
+                if bucket.harris_michael_remove(key, guard).is_some() {
+                    self.len.add(-1);
+                    eviction::record();
+                    return;
+                }
+            }
+        }
     }
 }
 
@@ -65,21 +133,104 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.insert(key, value, guard)
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.count_insert(guard);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self.try_insert(key, value, guard)?;
+        if inserted {
+            self.count_insert(guard);
+        }
+        Ok(inserted)
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.remove(key, guard)
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
     }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V> ConditionalRemove<K, V> for HashMap<K, V>
+where
+    K: Ord + Hash,
+{
+    #[inline]
+    fn remove_if<P>(&self, key: &K, pred: P, guard: &Guard) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.remove_if(key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+register_maps! {
+    ebr;
+    HashMap => HashMap, Decreasing, with compare_and_delete;
 }
 
 #[cfg(test)]
 mod tests {
     use super::HashMap;
-    use crate::ebr::concurrent_map;
+    use crate::ebr::concurrent_map::{self, ConcurrentMap};
+    use crate::ebr::conditional_remove;
+    use crate::eviction::{Capacity, Eviction};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
 
     #[test]
     fn smoke_hashmap() {
         concurrent_map::tests::smoke::<HashMap<i32, String>>();
     }
+
+    #[test]
+    fn remove_if_hashmap() {
+        conditional_remove::tests::remove_if::<HashMap<i32, String>>();
+    }
+
+    #[test]
+    fn try_insert_hashmap() {
+        concurrent_map::tests::try_insert::<HashMap<i32, String>>();
+    }
+
+    #[test]
+    fn bounded_hashmap() {
+        for &policy in &[Eviction::Random, Eviction::Sweep] {
+            let mut map = HashMap::<i32, String>::with_capacity(64);
+            // Not with `set_capacity`, which would bound the maps of the other tests.
+            map.capacity = Some(Capacity { bound: 100, policy });
+            let map = &map;
+            thread::scope(|s| {
+                for t in 0..8 {
+                    s.spawn(move |_| {
+                        for i in 0..1000 {
+                            let key = t * 1000 + i;
+                            ConcurrentMap::insert(map, key, key.to_string(), &pin());
+                        }
+                    });
+                }
+            })
+            .unwrap();
+            // An insert may miss the concurrent inserts of the others when it compares the size
+            // with the bound, and leave the map a few entries over it.
+            let guard = &pin();
+            let entries = (0..8000).filter(|k| map.get(k, guard).is_some()).count();
+            assert_eq!(entries, map.len_approx());
+            assert!((100..=108).contains(&entries), "{} entries", entries);
+        }
+    }
 }
@@ -1,13 +1,118 @@
+pub mod concurrent_bag;
+#[cfg(feature = "counters")]
+pub mod concurrent_counter;
 pub mod concurrent_map;
+pub mod concurrent_priority_queue;
+pub mod concurrent_queue;
+pub mod concurrent_set;
+pub mod concurrent_stack;
+pub mod conditional_remove;
+pub mod gc_policy;
+#[cfg(feature = "trees")]
+pub mod mcas;
+pub mod ordered_map;
+#[cfg(feature = "trees")]
+pub mod transfer_map;
+pub mod unguarded_map;
 
+#[cfg(feature = "trees")]
+pub mod art;
+#[cfg(feature = "queues")]
+pub mod bag;
+#[cfg(feature = "hash-maps")]
+pub mod bitmap;
+#[cfg(feature = "trees")]
 pub mod bonsai_tree;
+#[cfg(feature = "counters")]
+pub mod combining_tree;
+#[cfg(feature = "counters")]
+pub mod config_map;
+#[cfg(feature = "hash-maps")]
+pub mod cuckoo_hash_map;
+#[cfg(feature = "queues")]
+pub mod deque;
+#[cfg(feature = "dashmap")]
+pub mod external;
+#[cfg(feature = "hash-maps")]
+pub mod hash_tree_map;
+#[cfg(feature = "lists")]
+pub mod interval_map;
+#[cfg(feature = "trees")]
+pub mod kcas_tree;
+#[cfg(any(feature = "lists", feature = "hash-maps", feature = "queues"))]
 pub mod list;
+#[cfg(feature = "lists")]
+pub mod marked_list;
+#[cfg(feature = "queues")]
+pub mod mdlist;
+#[cfg(any(feature = "hash-maps", feature = "queues"))]
 pub mod michael_hash_map;
+#[cfg(feature = "queues")]
+pub mod mpsc_queue;
+#[cfg(any(feature = "trees", feature = "hash-maps"))]
 pub mod natarajan_mittal_tree;
+#[cfg(feature = "queues")]
+pub mod queue_map;
+#[cfg(feature = "hash-maps")]
+pub mod sharded_hash_map;
+#[cfg(feature = "lists")]
+pub mod skip_list;
+#[cfg(feature = "lists")]
+pub mod unrolled_list;
 
+pub use self::concurrent_bag::ConcurrentBag;
+#[cfg(feature = "counters")]
+pub use self::concurrent_counter::ConcurrentCounter;
 pub use self::concurrent_map::ConcurrentMap;
+pub use self::concurrent_priority_queue::ConcurrentPriorityQueue;
+pub use self::concurrent_queue::ConcurrentQueue;
+pub use self::concurrent_set::{ConcurrentSet, MapSet};
+pub use self::concurrent_stack::ConcurrentStack;
+pub use self::conditional_remove::{CompareAndDelete, ConditionalRemove};
+pub use self::ordered_map::OrderedMap;
+#[cfg(feature = "trees")]
+pub use self::transfer_map::TransferMap;
+pub use self::unguarded_map::UnguardedMap;
 
+#[cfg(feature = "trees")]
+pub use self::art::ARTMap;
+#[cfg(feature = "queues")]
+pub use self::bag::Bag;
+#[cfg(feature = "hash-maps")]
+pub use self::bitmap::Bitmap;
+#[cfg(feature = "trees")]
 pub use self::bonsai_tree::BonsaiTreeMap;
-pub use self::list::{HHSList, HList, HMList};
+#[cfg(feature = "counters")]
+pub use self::combining_tree::CombiningTree;
+#[cfg(feature = "counters")]
+pub use self::config_map::ConfigMap;
+#[cfg(feature = "hash-maps")]
+pub use self::cuckoo_hash_map::CuckooHashMap;
+#[cfg(feature = "queues")]
+pub use self::deque::Deque;
+#[cfg(feature = "hash-maps")]
+pub use self::hash_tree_map::HashTreeMap;
+#[cfg(feature = "lists")]
+pub use self::interval_map::IntervalMap;
+#[cfg(feature = "trees")]
+pub use self::kcas_tree::KCASTreeMap;
+#[cfg(any(feature = "lists", feature = "hash-maps", feature = "queues"))]
+pub use self::list::{AdaptiveList, HHSList, HList, HMList};
+#[cfg(feature = "lists")]
+pub use self::marked_list::{TagList, VersionList};
+#[cfg(feature = "queues")]
+pub use self::mdlist::MDList;
+#[cfg(any(feature = "hash-maps", feature = "queues"))]
 pub use self::michael_hash_map::HashMap;
+#[cfg(feature = "queues")]
+pub use self::mpsc_queue::MpscQueue;
+#[cfg(any(feature = "trees", feature = "hash-maps"))]
 pub use self::natarajan_mittal_tree::NMTreeMap;
+#[cfg(feature = "queues")]
+pub use self::queue_map::QueueMap;
+#[cfg(feature = "hash-maps")]
+pub use self::sharded_hash_map::ShardedHashMap;
+#[cfg(feature = "lists")]
+pub use self::skip_list::SkipList;
+#[cfg(feature = "lists")]
+pub use self::unrolled_list::UnrolledList;
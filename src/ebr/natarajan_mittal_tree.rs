@@ -1,6 +1,15 @@
 use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
 
 use super::concurrent_map::ConcurrentMap;
+use super::ordered_map::OrderedMap;
+use super::unguarded_map::UnguardedMap;
+use crate::alloc_fault::{self, AllocError};
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::remove_phases::{self, Phase};
+use crate::restarts;
+use crate::seek_stats;
+use crate::striped_counter::StripedCounter;
 use std::cmp;
 use std::sync::atomic::Ordering;
 
@@ -121,6 +130,20 @@ where
     }
 }
 
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    /// The child toward `key`, and its direction.
+    fn child<'g>(&self, key: &K, guard: &'g Guard) -> (Direction, Shared<'g, Node<K, V>>) {
+        if self.key.cmp(key) == cmp::Ordering::Greater {
+            (Direction::L, self.left.load(Ordering::Acquire, guard))
+        } else {
+            (Direction::R, self.right.load(Ordering::Acquire, guard))
+        }
+    }
+}
+
 enum Direction {
     L,
     R,
@@ -171,6 +194,9 @@ impl<'g, K, V> SeekRecord<'g, K, V> {
 // COMMENT(@jeehoonkang): write down the invariant of the tree
 pub struct NMTreeMap<K, V> {
     r: Node<K, V>,
+    len: StripedCounter,
+    /// Whether a retry resumes its seek from the ancestor of the failed attempt (`seek_stats`).
+    reuse_seeks: bool,
 }
 
 impl<K, V> Default for NMTreeMap<K, V>
@@ -225,7 +251,11 @@ where
         let inf2 = Node::new_leaf(Key::Inf, None);
         let s = Node::new_internal(inf0, inf1);
         let r = Node::new_internal(s, inf2);
-        NMTreeMap { r }
+        NMTreeMap {
+            r,
+            len: StripedCounter::new(),
+            reuse_seeks: seek_stats::reuse(),
+        }
     }
 
     // All `Shared<_>` fields are unmarked.
@@ -238,7 +268,7 @@ where
             .with_tag(Marks::empty().bits());
         let leaf_node = unsafe { leaf.deref() };
 
-        let mut record = SeekRecord {
+        let record = SeekRecord {
             ancestor: Shared::from(&self.r as *const _),
             successor: s,
             successor_dir: Direction::L,
@@ -247,11 +277,75 @@ where
             leaf_dir: Direction::L,
         };
 
-        let mut prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
-        let mut curr_dir = Direction::L;
-        let mut curr = leaf_node.left.load(Ordering::Relaxed, guard);
+        let prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
+        let curr_dir = Direction::L;
+        let curr = leaf_node.left.load(Ordering::Relaxed, guard);
+        self.descend(key, record, prev_tag, curr_dir, curr, guard)
+    }
+
+    /// Seeks `key` again after a failed attempt of an update, whose seek record is `prev`. With
+    /// seek reuse, resumes from the ancestor of `prev` if it is still in the tree, and otherwise
+    /// seeks from the root.
+    fn reseek<'g>(
+        &'g self,
+        key: &K,
+        prev: &SeekRecord<'g, K, V>,
+        guard: &'g Guard,
+    ) -> SeekRecord<'g, K, V> {
+        if !self.reuse_seeks {
+            return self.seek(key, guard);
+        }
+        let resumed = self.resume_seek(key, prev.ancestor, guard);
+        seek_stats::record_retry(resumed.is_some());
+        resumed.unwrap_or_else(|| self.seek(key, guard))
+    }
 
+    /// Seeks `key` from `ancestor`, a node that was on the path of `key`. Returns `None` if the
+    /// edge of `ancestor` toward `key` is marked or leads to a leaf, as then `ancestor` may be
+    /// removed.
+    fn resume_seek<'g>(
+        &'g self,
+        key: &K,
+        ancestor: Shared<'g, Node<K, V>>,
+        guard: &'g Guard,
+    ) -> Option<SeekRecord<'g, K, V>> {
+        let ancestor_node = unsafe { ancestor.deref() };
+        let (successor_dir, successor) = ancestor_node.child(key, guard);
+        if successor.tag() != Marks::empty().bits() {
+            return None;
+        }
+        let (leaf_dir, leaf) = unsafe { successor.deref() }.child(key, guard);
+        if leaf.is_null() {
+            return None;
+        }
+
+        let record = SeekRecord {
+            ancestor,
+            successor,
+            successor_dir,
+            parent: successor,
+            leaf: leaf.with_tag(Marks::empty().bits()),
+            leaf_dir,
+        };
+        let prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
+        let (curr_dir, curr) = unsafe { leaf.deref() }.child(key, guard);
+        Some(self.descend(key, record, prev_tag, curr_dir, curr, guard))
+    }
+
+    /// Continues a seek of `key` from `record.leaf` down to a leaf. `prev_tag` is whether the edge
+    /// to `record.leaf` is tagged, and `curr` is its child toward `key`.
+    fn descend<'g>(
+        &'g self,
+        key: &K,
+        mut record: SeekRecord<'g, K, V>,
+        mut prev_tag: bool,
+        mut curr_dir: Direction,
+        mut curr: Shared<'g, Node<K, V>>,
+        guard: &'g Guard,
+    ) -> SeekRecord<'g, K, V> {
+        let mut visited = 1;
         while let Some(curr_node) = unsafe { curr.as_ref() } {
+            visited += 1;
             if !prev_tag {
                 // untagged edge: advance ancestor and successor pointers
                 record.ancestor = record.parent;
@@ -266,15 +360,12 @@ where
 
             // update other variables
             prev_tag = Marks::from_bits_truncate(curr.tag()).tag();
-            if curr_node.key.cmp(key) == cmp::Ordering::Greater {
-                curr_dir = Direction::L;
-                curr = curr_node.left.load(Ordering::Acquire, guard);
-            } else {
-                curr_dir = Direction::R;
-                curr = curr_node.right.load(Ordering::Acquire, guard);
-            }
+            let (dir, next) = curr_node.child(key, guard);
+            curr_dir = dir;
+            curr = next;
         }
 
+        seek_stats::record_seek(visited);
         record
     }
 
@@ -328,7 +419,8 @@ where
 
                     stack.push(node_ref.left.load(Ordering::Relaxed, guard));
                     stack.push(node_ref.right.load(Ordering::Relaxed, guard));
-                    guard.defer_destroy(node);
+                    op_stats::retire();
+                    guard.retire(node);
                 }
             }
         }
@@ -347,20 +439,141 @@ where
         Some(leaf_node.value.as_ref().unwrap())
     }
 
+    /// Returns the entries with keys in `[lo, hi)` in the increasing order of the keys.
+    ///
+    /// The in-order traversal is not atomic: it reflects the concurrent updates to the part of the
+    /// tree that it has not visited yet.
+    pub fn range(&self, lo: &K, hi: &K, guard: &Guard) -> Vec<(K, V)> {
+        let mut entries = Vec::new();
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            let node_ref = unsafe { node.with_tag(Marks::empty().bits()).deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                // Skip the leaves that are being removed.
+                if Marks::from_bits_truncate(node.tag()).flag() {
+                    continue;
+                }
+                if let Key::Fin(key) = &node_ref.key {
+                    if lo <= key && key < hi {
+                        entries.push((key.clone(), node_ref.value.clone().unwrap()));
+                    }
+                }
+                continue;
+            }
+
+            // The keys in the left subtree are less than the node's key, and the others are in the
+            // right subtree. Push the left one last to visit it first.
+            if node_ref.key.cmp(hi) == cmp::Ordering::Less {
+                stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            }
+            if node_ref.key.cmp(lo) == cmp::Ordering::Greater {
+                stack.push(left);
+            }
+        }
+
+        entries
+    }
+
+    /// Returns the entry with the smallest key greater than `key`.
+    ///
+    /// Like `range`, it walks the leaves in order from `key`, skipping the ones that are being
+    /// removed.
+    pub fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            let node_ref = unsafe { node.with_tag(Marks::empty().bits()).deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                if Marks::from_bits_truncate(node.tag()).flag() {
+                    continue;
+                }
+                if let Key::Fin(k) = &node_ref.key {
+                    if k > key {
+                        return Some((k, node_ref.value.as_ref().unwrap()));
+                    }
+                }
+                continue;
+            }
+
+            stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            if node_ref.key.cmp(key) == cmp::Ordering::Greater {
+                stack.push(left);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the entry with the largest key less than `key`.
+    pub fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            let node_ref = unsafe { node.with_tag(Marks::empty().bits()).deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                if Marks::from_bits_truncate(node.tag()).flag() {
+                    continue;
+                }
+                if let Key::Fin(k) = &node_ref.key {
+                    if k < key {
+                        return Some((k, node_ref.value.as_ref().unwrap()));
+                    }
+                }
+                continue;
+            }
+
+            // Visit the right subtree first, if it may have a key less than `key`.
+            stack.push(left);
+            if node_ref.key.cmp(key) == cmp::Ordering::Less {
+                stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            }
+        }
+
+        None
+    }
+
     pub fn insert(&self, key: K, value: V, guard: &Guard) -> Result<(), (K, V)> {
-        let mut new_leaf = Owned::new(Node::new_leaf(Key::Fin(key.clone()), Some(value)))
-            .into_shared(unsafe { unprotected() });
+        let new_leaf = Owned::new(Node::new_leaf(Key::Fin(key.clone()), Some(value)));
+        let new_internal = Owned::new(Node::new_leaf(Key::Inf, None)); // temporary placeholder
+        self.insert_nodes(key, new_leaf, new_internal, guard)
+    }
 
-        let mut new_internal = Owned::new(Node {
-            key: Key::Inf, // temporary placeholder
-            value: None,
-            left: Atomic::null(),
-            right: Atomic::null(),
-        })
-        .into_shared(unsafe { unprotected() });
+    /// Like `insert`, but fails if a node cannot be allocated.
+    pub fn try_insert(
+        &self,
+        key: K,
+        value: V,
+        guard: &Guard,
+    ) -> Result<Result<(), (K, V)>, AllocError> {
+        let new_leaf = alloc_fault::try_box(Node::new_leaf(Key::Fin(key.clone()), Some(value)))
+            .map_err(|_| AllocError)?;
+        // The leaf is freed with its value if the internal node cannot be allocated.
+        let new_internal =
+            alloc_fault::try_box(Node::new_leaf(Key::Inf, None)).map_err(|_| AllocError)?;
+        Ok(self.insert_nodes(key, Owned::from(new_leaf), Owned::from(new_internal), guard))
+    }
 
+    /// Inserts `new_leaf`, the leaf of `key`, under `new_internal`, whose fields are filled in at
+    /// each attempt.
+    fn insert_nodes(
+        &self,
+        key: K,
+        new_leaf: Owned<Node<K, V>>,
+        new_internal: Owned<Node<K, V>>,
+        guard: &Guard,
+    ) -> Result<(), (K, V)> {
+        let mut new_leaf = new_leaf.into_shared(unsafe { unprotected() });
+        let mut new_internal = new_internal.into_shared(unsafe { unprotected() });
+
+        let mut record = self.seek(&key, guard);
         loop {
-            let record = self.seek(&key, guard);
             let leaf = record.leaf;
 
             let (new_left, new_right) = match unsafe { leaf.deref() }.key.cmp(&key) {
@@ -389,6 +602,7 @@ where
             ) {
                 Ok(_) => return Ok(()),
                 Err(e) => {
+                    restarts::record();
                     // Insertion failed. Help the conflicting remove operation if needed.
                     // NOTE: The paper version checks if any of the mark is set, which is redundant.
                     if e.current.with_tag(Marks::empty().bits()) == record.leaf {
@@ -396,11 +610,18 @@ where
                     }
                 }
             }
+            record = self.reseek(&key, &record, guard);
         }
     }
 
     pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        let mut record;
+        self.remove_get(key, guard).cloned()
+    }
+
+    /// Like `remove`, but returns the value in the removed leaf.
+    pub fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let mut timer = remove_phases::Timer::start();
+        let mut record = self.seek(key, guard);
         // `leaf` and `value` are the snapshot of the node to be deleted.
         let leaf;
         let value;
@@ -408,18 +629,18 @@ where
         // NOTE: The paper version uses one big loop for both phases.
         // injection phase
         loop {
-            record = self.seek(key, guard);
-
             // candidates
             let temp_leaf = record.leaf;
             let temp_leaf_node = unsafe { record.leaf.as_ref().unwrap() };
 
             if temp_leaf_node.key.cmp(key) != cmp::Ordering::Equal {
+                timer.finish(Phase::Search, false);
                 return None;
             }
 
-            // Copy the value before the physical deletion.
-            let temp_value = temp_leaf_node.value.as_ref().unwrap().clone();
+            // The leaf keeps its value after the physical deletion.
+            let temp_value = temp_leaf_node.value.as_ref().unwrap();
+            timer.lap(Phase::Search);
 
             // Try injecting the deletion flag.
             match record.leaf_addr().compare_and_set(
@@ -429,16 +650,19 @@ where
                 &guard,
             ) {
                 Ok(_) => {
+                    timer.lap(Phase::Mark);
                     // Finalize the node to be removed
                     leaf = temp_leaf;
                     value = temp_value;
                     if self.cleanup(&record, guard) {
+                        timer.finish(Phase::Unlink, true);
                         return Some(value);
                     }
                     // In-place cleanup failed. Enter the cleanup phase.
                     break;
                 }
                 Err(e) => {
+                    restarts::record();
                     // Flagging failed.
                     // case 1. record.leaf_addr(e.current) points to another node: restart.
                     // case 2. Another thread flagged/tagged the edge to leaf: help and restart
@@ -446,22 +670,27 @@ where
                     if record.leaf == e.current.with_tag(Marks::empty().bits()) {
                         self.cleanup(&record, guard);
                     }
+                    timer.lap(Phase::Mark);
                 }
             }
+            record = self.reseek(key, &record, guard);
         }
 
         // cleanup phase
         loop {
-            record = self.seek(key, guard);
+            record = self.reseek(key, &record, guard);
             if record.leaf != leaf {
                 // The edge to leaf flagged for deletion was removed by a helping thread
+                timer.finish(Phase::Unlink, true);
                 return Some(value);
             }
 
             // leaf is still present in the tree.
             if self.cleanup(&record, guard) {
+                timer.finish(Phase::Unlink, true);
                 return Some(value);
             }
+            restarts::record();
         }
     }
 }
@@ -481,21 +710,155 @@ where
     }
     #[inline]
     fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
-        self.insert(key, value, guard).is_ok()
+        let inserted = self.insert(key, value, guard).is_ok();
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_insert(&self, key: K, value: V, guard: &Guard) -> Result<bool, AllocError> {
+        let inserted = self.try_insert(key, value, guard)?.is_ok();
+        if inserted {
+            self.len.add(1);
+        }
+        Ok(inserted)
     }
     #[inline]
     fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
-        self.remove(key, guard)
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let removed = self.remove_get(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
+/// The get only seeks, and leaves the cleanup to the updates.
+impl<K, V> UnguardedMap<K, V> for NMTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+}
+
+impl<K, V> OrderedMap<K, V> for NMTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    #[inline]
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.next(key, guard)
+    }
+    #[inline]
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.prev(key, guard)
+    }
+}
+
+register_maps! {
+    ebr;
+    NMTree => NMTreeMap, Random, with scan unguarded;
+}
+
 #[cfg(test)]
 mod tests {
     use super::NMTreeMap;
-    use crate::ebr::concurrent_map;
+    use crate::ebr::{concurrent_map, ordered_map, unguarded_map};
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
 
     #[test]
     fn smoke_nm_tree() {
         concurrent_map::tests::smoke::<NMTreeMap<i32, String>>();
     }
+
+    #[test]
+    fn try_insert_nm_tree() {
+        concurrent_map::tests::try_insert::<NMTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_nm_tree() {
+        concurrent_map::tests::remove_get::<NMTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_nm_tree() {
+        ordered_map::tests::ordered::<NMTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn unguarded_nm_tree() {
+        unguarded_map::tests::unguarded::<NMTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn range_nm_tree() {
+        let map = NMTreeMap::new();
+        let guard = &pin();
+        for i in (0..100).rev() {
+            assert!(map.insert(i * 2, i.to_string(), guard).is_ok());
+        }
+        for i in 0..10 {
+            assert!(map.remove(&(i * 10), guard).is_some());
+        }
+
+        let entries = map.range(&15, &61, guard);
+        let expected = (8..31)
+            .filter(|i| i % 5 != 0)
+            .map(|i| (i * 2, i.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+        assert!(map.range(&61, &61, guard).is_empty());
+        assert_eq!(map.range(&0, &1000, guard).len(), 90);
+    }
+
+    #[test]
+    fn seek_reuse_nm_tree() {
+        let mut map = NMTreeMap::new();
+        map.reuse_seeks = true;
+        let map = &map;
+
+        // Contended updates of a few keys, so that many retries resume their seeks.
+        let size = thread::scope(|s| {
+            let handles = (0..8)
+                .map(|t| {
+                    s.spawn(move |_| {
+                        let mut size = 0;
+                        for i in 0..10_000 {
+                            let key = (i * 7 + t) % 64;
+                            if i % 2 == 0 {
+                                if map.insert(key, key.to_string(), &pin()).is_ok() {
+                                    size += 1;
+                                }
+                            } else if map.remove(&key, &pin()).is_some() {
+                                size -= 1;
+                            }
+                        }
+                        size
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|h| h.join().unwrap()).sum::<i32>()
+        })
+        .unwrap();
+
+        let entries = map.range(&0, &64, &pin());
+        assert_eq!(entries.len() as i32, size);
+        assert!(entries.iter().all(|(k, v)| *v == k.to_string()));
+    }
 }
@@ -0,0 +1,78 @@
+use super::concurrent_map::ConcurrentMap;
+use crossbeam_ebr::Guard;
+
+/// A map that also answers successor and predecessor queries.
+///
+/// The queries skip the entries that are being removed. They are not atomic: a query reflects the
+/// concurrent updates to the part of the structure that it has not visited yet.
+pub trait OrderedMap<K, V>: ConcurrentMap<K, V> {
+    /// The entry with the smallest key greater than `key`.
+    fn next<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)>;
+    /// The entry with the largest key less than `key`.
+    fn prev<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<(&'g K, &'g V)>;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::OrderedMap;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+    use std::collections::BTreeSet;
+
+    const THREADS: i32 = 8;
+    const KEYS: i32 = 200;
+
+    pub fn ordered<M: OrderedMap<i32, String> + Send + Sync>() {
+        let map = &M::new();
+        let guard = &pin();
+        for i in (0..KEYS / 2).rev() {
+            assert!(map.insert(i * 2, i.to_string(), guard));
+        }
+        for i in 0..KEYS / 20 {
+            assert!(map.remove(&(i * 20), guard).is_some());
+        }
+        let keys = (0..KEYS / 2)
+            .map(|i| i * 2)
+            .filter(|k| k % 20 != 0)
+            .collect::<BTreeSet<_>>();
+        let entry = |k: &i32| (*k, (k / 2).to_string());
+        for k in -1..=KEYS {
+            let next = map.next(&k, guard).map(|(k, v)| (*k, v.clone()));
+            assert_eq!(next, keys.range(k + 1..).next().map(entry));
+            let prev = map.prev(&k, guard).map(|(k, v)| (*k, v.clone()));
+            assert_eq!(prev, keys.range(..k).next_back().map(entry));
+        }
+
+        // The multiples of 4 stay while the other keys come and go, so the successor of a multiple
+        // of 4 is at most 4 greater, and the predecessor at most 4 less.
+        let map = &M::new();
+        for k in (0..KEYS).step_by(4) {
+            assert!(map.insert(k, k.to_string(), guard));
+        }
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let guard = &pin();
+                    for i in 0..1000 {
+                        let k = (i * 37 + t) % KEYS;
+                        if k % 4 == 0 {
+                            if k + 4 < KEYS {
+                                let next = *map.next(&k, guard).unwrap().0;
+                                assert!(k < next && next <= k + 4);
+                            }
+                            if k > 0 {
+                                let prev = *map.prev(&k, guard).unwrap().0;
+                                assert!(k - 4 <= prev && prev < k);
+                            }
+                        } else if i % 2 == 0 {
+                            map.insert(k, k.to_string(), guard);
+                        } else {
+                            map.remove(&k, guard);
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+}
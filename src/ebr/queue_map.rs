@@ -0,0 +1,132 @@
+//! A hash map whose values are lock-free queues, for nested reclamation.
+//!
+//! The values of the other maps are scalars that are dropped with their nodes. Here a value is a
+//! whole queue that the other threads may still push to or pop from after its key is deleted, so
+//! deleting a key retires the queue itself, and the queue frees its remaining nodes only when the
+//! retired queue is destroyed, one grace period later.
+
+use super::concurrent_map::ConcurrentMap;
+use super::concurrent_queue::ConcurrentQueue;
+use super::michael_hash_map::HashMap;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crossbeam_ebr::{Guard, Owned};
+use std::hash::Hash;
+
+pub struct QueueMap<K, Q> {
+    map: HashMap<K, Q>,
+}
+
+impl<K, Q> QueueMap<K, Q>
+where
+    K: Ord + Hash + Clone,
+{
+    pub fn with_capacity(n: usize) -> Self {
+        QueueMap {
+            map: HashMap::with_capacity(n),
+        }
+    }
+
+    /// Appends `value` to the queue of `key`, creating the queue if `key` is absent.
+    pub fn append<T>(&self, key: &K, value: T, guard: &Guard)
+    where
+        Q: ConcurrentQueue<T>,
+    {
+        loop {
+            if let Some(queue) = self.map.get(key, guard) {
+                queue.push(value, guard);
+                return;
+            }
+            // If another thread inserts the key first, the new queue is dropped, and the next
+            // iteration appends to the queue of the other thread.
+            ConcurrentMap::insert(&self.map, key.clone(), Q::new(), guard);
+        }
+    }
+
+    /// Pops the oldest value of the queue of `key`, if any.
+    pub fn pop<T>(&self, key: &K, guard: &Guard) -> Option<T>
+    where
+        Q: ConcurrentQueue<T>,
+    {
+        self.map.get(key, guard)?.try_pop(guard)
+    }
+
+    /// Deletes `key` and retires its queue with the values left in it. Returns `false` if `key` is
+    /// absent.
+    pub fn delete(&self, key: &K, guard: &Guard) -> bool {
+        // The node of the key is retired, but the threads that found it may still access the queue
+        // through it, so the queue moved out of the node is retired as well.
+        match ConcurrentMap::remove(&self.map, key, guard) {
+            Some(queue) => {
+                op_stats::retire();
+                unsafe { guard.retire(Owned::new(queue).into_shared(guard)) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The approximate number of keys. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.map.len_approx()
+    }
+}
+
+/// Registers the queue map.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("QueueMap", Scheme::EBR, |d: &D, _| d.ebr_queue_map());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueMap;
+    use crate::ebr::Deque;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 16;
+    const KEYS: usize = 8;
+    const ELEMENTS_PER_THREADS: usize = 2000;
+
+    #[test]
+    fn smoke_queue_map() {
+        let map = &QueueMap::<usize, Deque<usize>>::with_capacity(64);
+        let guard = &pin();
+        assert_eq!(map.pop(&0, guard), None);
+        for i in 0..10 {
+            map.append(&(i % 2), i, guard);
+        }
+        assert_eq!(map.len_approx(), 2);
+        assert_eq!(map.pop(&0, guard), Some(0));
+        assert_eq!(map.pop(&1, guard), Some(1));
+        assert!(map.delete(&0, guard));
+        assert!(!map.delete(&0, guard));
+        assert_eq!(map.pop(&0, guard), None);
+        assert_eq!(map.pop(&1, guard), Some(3));
+
+        // The threads append to, pop from and delete a few keys, so that the queues they use are
+        // often retired while others still push to them.
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    for i in 0..ELEMENTS_PER_THREADS {
+                        let key = (t + i) % KEYS;
+                        match i % 4 {
+                            0 | 1 => map.append(&key, i, &pin()),
+                            2 => {
+                                map.pop(&key, &pin());
+                            }
+                            _ => {
+                                if i % 64 == 3 {
+                                    map.delete(&key, &pin());
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+//! Michael's hash map split into shards, each with its own EBR collector.
+//!
+//! With a single epoch domain, a thread that stalls while pinned keeps the garbage of the whole
+//! map from being reclaimed. Here each operation pins only the collector of the shard of its key,
+//! and only for the duration of the operation, so a stalled thread holds back at most one shard.
+//!
+//! Since no guard outlives an operation, the map doesn't implement `ConcurrentMap`: the threads
+//! operate through a `Handle` that holds their registrations to the collectors, and `get` passes
+//! the value to a closure instead of returning a reference.
+
+use crate::hashing::Hashing;
+use crate::registry::{Driver, Kind, Port, Prefill, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{Collector, LocalHandle};
+use std::hash::Hash;
+
+use super::list::List;
+
+struct Shard<K, V> {
+    collector: Collector,
+    buckets: Vec<List<K, V>>,
+}
+
+pub struct ShardedHashMap<K, V> {
+    shards: Vec<Shard<K, V>>,
+    len: StripedCounter,
+    hashing: Hashing,
+}
+
+/// The registrations of a thread to the collectors of the shards.
+pub struct Handle {
+    handles: Vec<LocalHandle>,
+}
+
+impl<K, V> ShardedHashMap<K, V>
+where
+    K: Ord + Hash,
+{
+    /// The number of buckets of all the shards together, the same as `HashMap`.
+    const BUCKETS: usize = 30000;
+
+    pub fn with_shards(n: usize) -> Self {
+        assert!(n > 0);
+        let shards = (0..n)
+            .map(|_| Shard {
+                collector: Collector::new(),
+                buckets: (0..Self::BUCKETS / n).map(|_| List::new()).collect(),
+            })
+            .collect();
+        ShardedHashMap {
+            shards,
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+        }
+    }
+
+    pub fn handle(&self) -> Handle {
+        Handle {
+            handles: self.shards.iter().map(|s| s.collector.register()).collect(),
+        }
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The index of the shard of the key, and the bucket of the key in the shard.
+    #[inline]
+    fn locate(&self, k: &K) -> (usize, &List<K, V>) {
+        let hash = self.hashing.hash(k) as usize;
+        let i = hash % self.shards.len();
+        let buckets = &self.shards[i].buckets;
+        (i, &buckets[hash / self.shards.len() % buckets.len()])
+    }
+
+    pub fn get<F, R>(&self, handle: &Handle, k: &K, f: F) -> Option<R>
+    where
+        F: FnOnce(&V) -> R,
+    {
+        let (i, bucket) = self.locate(k);
+        let guard = handle.handles[i].pin();
+        bucket.harris_michael_get(k, &guard).map(f)
+    }
+
+    pub fn insert(&self, handle: &Handle, k: K, v: V) -> bool {
+        let (i, bucket) = self.locate(&k);
+        let guard = handle.handles[i].pin();
+        let inserted = bucket.harris_michael_insert(k, v, &guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+
+    pub fn remove(&self, handle: &Handle, k: &K) -> Option<V> {
+        let (i, bucket) = self.locate(k);
+        let guard = handle.handles[i].pin();
+        let removed = bucket.harris_michael_remove(k, &guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+
+    /// The approximate number of keys. It is exact in the absence of concurrent updates.
+    pub fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// Registers the map, whose workload pins the collectors of the shards itself.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    let port = Port {
+        slow_key: Some(|d: &D, p| d.sharded_hash_map_slow_key(p)),
+        ..Port::new(|d: &D, p| d.sharded_hash_map(p))
+    };
+    registry.register(
+        "ShardedHashMap",
+        Kind::Standalone,
+        Prefill::Decreasing,
+        Scheme::EBR,
+        port,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+    use super::ShardedHashMap;
+    use crossbeam_utils::thread;
+    use rand::prelude::*;
+
+    const THREADS: i32 = 30;
+    const ELEMENTS_PER_THREADS: i32 = 1000;
+
+    #[test]
+    fn smoke_sharded_hash_map() {
+        let map = &ShardedHashMap::<i32, String>::with_shards(16);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let handle = map.handle();
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert!(map.insert(&handle, i, i.to_string()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(map.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
+
+        thread::scope(|s| {
+            for t in 0..(THREADS / 2) {
+                s.spawn(move |_| {
+                    let handle = map.handle();
+                    for i in (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t) {
+                        assert_eq!(i.to_string(), map.remove(&handle, &i).unwrap());
+                        assert!(map.get(&handle, &i, |_| ()).is_none());
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        thread::scope(|s| {
+            for t in (THREADS / 2)..THREADS {
+                s.spawn(move |_| {
+                    let handle = map.handle();
+                    for i in (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t) {
+                        assert_eq!(Some(i.to_string()), map.get(&handle, &i, |v| v.clone()));
+                        assert!(!map.insert(&handle, i, String::new()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            map.len_approx(),
+            (THREADS / 2 * ELEMENTS_PER_THREADS) as usize
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! A lock-free skiplist, after the lock-free skiplist of Herlihy and Shavit and Fraser's.
+//!
+//! Each level is a sorted list of the nodes that are at least that high, and a search descends
+//! from the top level of the head to level 0, snipping the marked nodes on its way with a CAS on
+//! the link of their predecessor. A removal marks the links of its node from the top down, and
+//! the mark of level 0 is its linearization point. An insert links its node into level 0 first,
+//! which is its linearization point, and then into the higher levels one by one, and stops as soon
+//! as it finds the node marked. The heights are drawn as described in `skiplist_stats`.
+//!
+//! A node may still be linked into a higher level by its inserter after its remover has snipped
+//! it, so it is retired only once both of them are done with it. Each of them releases the node
+//! after a search for its key, the inserter only if the node is marked by then, and the one that
+//! releases it last retires it. Either the inserter sees the mark after its last link, or the mark
+//! and the search of the remover come after it, so the last search snips every level of the node.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::restarts;
+use crate::skiplist_stats::{self, MAX_HEIGHT};
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+use rand::Rng;
+use std::cmp::Ordering::Less;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Node<K, V> {
+    key: K,
+    /// Moved out by the remover that marks level 0.
+    value: ManuallyDrop<V>,
+    /// The inserter and the remover each hold a reference until they are done with the node.
+    refs: AtomicUsize,
+    /// The links of the levels of the node, marked by its remover.
+    next: Box<[Atomic<Node<K, V>>]>,
+}
+
+/// The links to the position of a key at each level, and the nodes after them.
+struct Position<'g, K, V> {
+    preds: [&'g Atomic<Node<K, V>>; MAX_HEIGHT],
+    succs: [Shared<'g, Node<K, V>>; MAX_HEIGHT],
+}
+
+pub struct SkipList<K, V> {
+    /// The links of the head, one per level.
+    head: Box<[Atomic<Node<K, V>>]>,
+    /// The probability that a tower grows by each level, as a fraction of `u32::max_value()`.
+    p: u32,
+    len: StripedCounter,
+}
+
+impl<K, V> Drop for SkipList<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let mut curr = self.head[0].load(Ordering::Relaxed, unprotected());
+            while !curr.is_null() {
+                let mut node = curr.into_owned();
+                let next = node.next[0].load(Ordering::Relaxed, unprotected());
+                if next.tag() == 0 {
+                    ManuallyDrop::drop(&mut node.value);
+                }
+                curr = next.with_tag(0);
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    /// Creates a skiplist whose towers grow by each level with probability `p`, up to `max_level`
+    /// levels.
+    pub fn with_params(p: f64, max_level: usize) -> Self {
+        assert!((0.0..1.0).contains(&p));
+        assert!(max_level > 0 && max_level <= MAX_HEIGHT);
+        Self {
+            head: (0..max_level).map(|_| Atomic::null()).collect(),
+            p: (p * f64::from(u32::max_value())) as u32,
+            len: StripedCounter::new(),
+        }
+    }
+
+    fn random_height(&self) -> usize {
+        let mut rng = rand::thread_rng();
+        let mut height = 1;
+        while height < self.head.len() && rng.gen::<u32>() < self.p {
+            height += 1;
+        }
+        height
+    }
+
+    /// Fills `pos` with the position of `key` at each level, snipping the marked nodes on the way,
+    /// and returns whether the node after it at level 0 holds `key`. Fails if a CAS fails or a
+    /// predecessor is marked.
+    fn find<'g>(
+        &'g self,
+        key: &K,
+        pos: &mut Position<'g, K, V>,
+        guard: &'g Guard,
+    ) -> Result<bool, ()> {
+        let mut links = &self.head[..];
+        for level in (0..self.head.len()).rev() {
+            let mut curr = links[level].load(Ordering::Acquire, guard);
+            if curr.tag() != 0 {
+                return Err(());
+            }
+            while let Some(curr_node) = unsafe { curr.as_ref() } {
+                let succ = curr_node.next[level].load(Ordering::Acquire, guard);
+                if succ.tag() != 0 {
+                    links[level]
+                        .compare_and_set(curr, succ.with_tag(0), Ordering::Release, guard)
+                        .map_err(|_| ())?;
+                    curr = succ.with_tag(0);
+                    continue;
+                }
+                if curr_node.key.cmp(key) != Less {
+                    break;
+                }
+                links = &curr_node.next;
+                curr = succ;
+            }
+            pos.preds[level] = &links[level];
+            pos.succs[level] = curr;
+        }
+        Ok(unsafe { pos.succs[0].as_ref() }.map_or(false, |node| node.key == *key))
+    }
+
+    /// Searches for `key` until a search succeeds.
+    fn find_retrying<'g>(
+        &'g self,
+        key: &K,
+        pos: &mut Position<'g, K, V>,
+        guard: &'g Guard,
+    ) -> bool {
+        loop {
+            if let Ok(found) = self.find(key, pos, guard) {
+                return found;
+            }
+            restarts::record();
+        }
+    }
+
+    fn position(&self) -> Position<'_, K, V> {
+        Position {
+            preds: [&self.head[0]; MAX_HEIGHT],
+            succs: [Shared::null(); MAX_HEIGHT],
+        }
+    }
+
+    /// Drops a reference to `node`, and retires it if it was the last one.
+    unsafe fn release(node: Shared<'_, Node<K, V>>, guard: &Guard) {
+        if node.deref().refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+            op_stats::retire();
+            guard.retire(node);
+        }
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let mut links = &self.head[..];
+        let mut curr = Shared::null();
+        for level in (0..self.head.len()).rev() {
+            curr = links[level].load(Ordering::Acquire, guard).with_tag(0);
+            while let Some(curr_node) = unsafe { curr.as_ref() } {
+                let succ = curr_node.next[level].load(Ordering::Acquire, guard);
+                if succ.tag() != 0 {
+                    curr = succ.with_tag(0);
+                    continue;
+                }
+                if curr_node.key.cmp(key) != Less {
+                    break;
+                }
+                links = &curr_node.next;
+                curr = succ;
+            }
+        }
+        let node = unsafe { curr.as_ref() }?;
+        if node.key == *key {
+            Some(&*node.value)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let mut pos = self.position();
+        if self.find_retrying(&key, &mut pos, guard) {
+            return false;
+        }
+        let height = self.random_height();
+        let node = Owned::new(Node {
+            key,
+            value: ManuallyDrop::new(value),
+            refs: AtomicUsize::new(2),
+            next: pos.succs[..height]
+                .iter()
+                .map(|s| Atomic::from(*s))
+                .collect(),
+        })
+        .into_shared(guard);
+        let node_ref = unsafe { node.deref() };
+
+        while pos.preds[0]
+            .compare_and_set(pos.succs[0], node, Ordering::Release, guard)
+            .is_err()
+        {
+            restarts::record();
+            if self.find_retrying(&node_ref.key, &mut pos, guard) {
+                unsafe {
+                    let mut node = node.into_owned();
+                    ManuallyDrop::drop(&mut node.value);
+                }
+                return false;
+            }
+            node_ref.next[0].store(pos.succs[0], Ordering::Relaxed);
+        }
+        skiplist_stats::record(height);
+
+        'levels: for level in 1..height {
+            loop {
+                let next = node_ref.next[level].load(Ordering::Acquire, guard);
+                if next.tag() != 0 {
+                    break 'levels;
+                }
+                if next != pos.succs[level]
+                    && node_ref.next[level]
+                        .compare_and_set(next, pos.succs[level], Ordering::Release, guard)
+                        .is_err()
+                {
+                    break 'levels;
+                }
+                if pos.preds[level]
+                    .compare_and_set(pos.succs[level], node, Ordering::Release, guard)
+                    .is_ok()
+                {
+                    break;
+                }
+                restarts::record();
+                self.find_retrying(&node_ref.key, &mut pos, guard);
+                if pos.succs[0] != node {
+                    // Removed and snipped from level 0.
+                    break 'levels;
+                }
+            }
+        }
+
+        if node_ref.next[0].load(Ordering::Acquire, guard).tag() != 0 {
+            // The remover may have searched before the last link.
+            self.find_retrying(&node_ref.key, &mut pos, guard);
+        }
+        unsafe { Self::release(node, guard) };
+        true
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let mut pos = self.position();
+        if !self.find_retrying(key, &mut pos, guard) {
+            return None;
+        }
+        let node = pos.succs[0];
+        let node_ref = unsafe { node.deref() };
+
+        for level in (1..node_ref.next.len()).rev() {
+            let mut next = node_ref.next[level].load(Ordering::Acquire, guard);
+            while next.tag() == 0 {
+                match node_ref.next[level].compare_and_set(
+                    next,
+                    next.with_tag(1),
+                    Ordering::AcqRel,
+                    guard,
+                ) {
+                    Ok(_) => break,
+                    Err(e) => next = e.current,
+                }
+            }
+        }
+        let mut next = node_ref.next[0].load(Ordering::Acquire, guard);
+        loop {
+            if next.tag() != 0 {
+                // Another remover won.
+                return None;
+            }
+            match node_ref.next[0].compare_and_set(next, next.with_tag(1), Ordering::AcqRel, guard)
+            {
+                Ok(_) => break,
+                Err(e) => next = e.current,
+            }
+        }
+
+        let value = unsafe { ptr::read(&node_ref.value) };
+        self.find_retrying(key, &mut pos, guard);
+        unsafe { Self::release(node, guard) };
+        Some(ManuallyDrop::into_inner(value))
+    }
+}
+
+impl<K: Ord, V> ConcurrentMap<K, V> for SkipList<K, V> {
+    fn new() -> Self {
+        Self::with_params(
+            f64::from(skiplist_stats::p()) / f64::from(u32::max_value()),
+            skiplist_stats::max_level(),
+        )
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &'g K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    ebr;
+    SkipList => SkipList, Random;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipList;
+    use crate::ebr::concurrent_map;
+    use crossbeam_ebr::pin;
+
+    #[test]
+    fn smoke_skip_list() {
+        concurrent_map::tests::smoke::<SkipList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_skip_list() {
+        concurrent_map::tests::shadow::<SkipList<i32, String>>();
+    }
+
+    #[test]
+    fn heights() {
+        // With p = 0, every tower is a single level, and the skiplist is a sorted list.
+        for &(p, max_level) in &[(0.0, 8), (0.9, 1), (0.5, 32)] {
+            let map = SkipList::with_params(p, max_level);
+            let guard = &pin();
+            for i in (0..200).rev() {
+                assert!(map.insert(i, i, guard));
+            }
+            assert!(!map.insert(7, 7, guard));
+            for i in (0..200).step_by(3) {
+                assert_eq!(map.remove(&i, guard), Some(i));
+            }
+            for i in 0..200 {
+                assert_eq!(map.get(&i, guard), if i % 3 == 0 { None } else { Some(&i) });
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::concurrent_map::ConcurrentMap;
+use crossbeam_ebr::Guard;
+
+/// A map whose entries move to another map of the same type atomically, e.g., with a single MCAS
+/// of the words of the removal from one map and of the insertion into the other. A transfer thus
+/// installs its descriptors in both maps, whose readers and helpers reach the nodes of one map
+/// through the descriptors that the other one holds.
+pub trait TransferMap<K, V>: ConcurrentMap<K, V> {
+    /// Moves the entry of `key` from `self` to `to` if `self` has the key and `to` doesn't, as a
+    /// single update of the two maps. Returns whether it moved the entry.
+    fn transfer(&self, key: &K, to: &Self, guard: &Guard) -> bool;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::TransferMap;
+    use crossbeam_ebr::pin;
+    use crossbeam_utils::thread;
+    use rand::prelude::*;
+
+    const THREADS: i32 = 16;
+    const KEYS: i32 = 64;
+    const TRANSFERS_PER_THREAD: i32 = 2000;
+
+    /// Transfers the keys back and forth between two maps, and checks that each key ends up in
+    /// exactly one of them with its value.
+    pub fn transfer<M: TransferMap<i32, String> + Send + Sync>() {
+        let maps = &[M::new(), M::new()];
+        let guard = &pin();
+        for i in 0..KEYS {
+            assert!(maps[(i % 2) as usize].insert(i, i.to_string(), guard));
+        }
+        assert!(!maps[0].transfer(&1, &maps[1], guard));
+        assert!(!maps[0].transfer(&KEYS, &maps[1], guard));
+
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(move |_| {
+                    let mut rng = rand::thread_rng();
+                    for _ in 0..TRANSFERS_PER_THREAD {
+                        let key = rng.gen_range(0, KEYS);
+                        let from = rng.gen_range(0, 2);
+                        let guard = &pin();
+                        maps[from].transfer(&key, &maps[1 - from], guard);
+                        // The key is always in one of the maps, but a get of each map in turn may
+                        // miss it as it moves.
+                        maps[rng.gen_range(0, 2)].get(&key, guard);
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        let guard = &pin();
+        for i in 0..KEYS {
+            let values = (maps[0].get(&i, guard), maps[1].get(&i, guard));
+            match values {
+                (Some(v), None) | (None, Some(v)) => assert_eq!(*v, i.to_string()),
+                _ => panic!("key {} is in {:?}", i, values),
+            }
+        }
+        assert_eq!(maps[0].len_approx() + maps[1].len_approx(), KEYS as usize);
+    }
+}
@@ -0,0 +1,46 @@
+use super::concurrent_map::ConcurrentMap;
+use crossbeam_ebr::{unprotected, Guard};
+
+/// A map whose gets may run without a guard, because they only read: they never unlink, retire or
+/// help, e.g., the wait-free get of the Herlihy-Shavit list. A get that unlinks the removed nodes on
+/// its way, like that of the Harris-Michael list, would free them right away without a guard.
+///
+/// An unguarded get protects nothing, so it is the ceiling of the reads of a reclamation scheme.
+pub trait UnguardedMap<K, V>: ConcurrentMap<K, V> {
+    /// Like `get`, without a guard.
+    ///
+    /// # Safety
+    ///
+    /// No node of the map may be freed while the get and the returned value are in use, e.g.,
+    /// because the nodes are never reclaimed (NR, or reclamation turned off).
+    #[inline]
+    unsafe fn get_unguarded<'g>(&'g self, key: &'g K) -> Option<&'g V> {
+        let guard: &'g Guard = unprotected();
+        self.get(key, guard)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::UnguardedMap;
+    use crossbeam_ebr::pin;
+
+    const KEYS: i32 = 200;
+
+    /// Compares the unguarded gets with the guarded ones, without concurrent updates.
+    pub fn unguarded<M: UnguardedMap<i32, String>>() {
+        let map = &M::new();
+        let guard = &pin();
+        for i in (0..KEYS).rev() {
+            assert!(map.insert(i, i.to_string(), guard));
+        }
+        for i in (0..KEYS).step_by(3) {
+            assert!(map.remove(&i, guard).is_some());
+        }
+        for i in -1..=KEYS {
+            let value = unsafe { map.get_unguarded(&i) };
+            assert_eq!(value, map.get(&i, guard));
+            assert_eq!(value.is_some(), i >= 0 && i < KEYS && i % 3 != 0);
+        }
+    }
+}
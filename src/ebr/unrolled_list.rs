@@ -0,0 +1,317 @@
+//! Lock-free unrolled linked list.
+//!
+//! Each node stores a sorted chunk of up to `CHUNK_SIZE` entries, and only its `next` pointer is
+//! mutable. An update replaces the node containing the key with an updated copy (split into two
+//! nodes if the chunk overflows, or the successor if the chunk becomes empty): it first *freezes*
+//! the node by tagging its `next` with the replacement, whose `next` is the old successor, and then
+//! swings the predecessor's `next` to the replacement. Freezing linearizes the update, and any
+//! thread that encounters a frozen node helps unlinking it. A node is retired as a whole chunk.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::op_stats;
+use crate::reclaim::ebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crossbeam_ebr::{unprotected, Atomic, Guard, Owned, Shared};
+
+use std::sync::atomic::Ordering;
+
+const CHUNK_SIZE: usize = 16;
+
+struct Node<K, V> {
+    /// Tag 1: frozen, pointing to the replacement.
+    next: Atomic<Node<K, V>>,
+    /// Sorted by the keys. Empty only for the head sentinel.
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    fn new(entries: Vec<(K, V)>, next: Shared<'_, Node<K, V>>) -> Owned<Self> {
+        Owned::new(Node {
+            next: Atomic::from(next),
+            entries,
+        })
+    }
+
+    #[inline]
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+
+    /// Whether `key` belongs to this node, given that it doesn't belong to the previous ones.
+    #[inline]
+    fn covers(&self, key: &K, next: Shared<'_, Node<K, V>>) -> bool {
+        next.is_null() || self.entries.last().map_or(false, |(k, _)| k >= key)
+    }
+}
+
+pub struct UnrolledList<K, V> {
+    /// The head sentinel.
+    head: Atomic<Node<K, V>>,
+    len: StripedCounter,
+}
+
+impl<K, V> Drop for UnrolledList<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+            while !curr.is_null() {
+                let next = curr.deref().next.load(Ordering::Relaxed, unprotected());
+                drop(curr.into_owned());
+                curr = next.with_tag(0);
+            }
+        }
+    }
+}
+
+impl<K, V> Default for UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        UnrolledList {
+            head: Atomic::new(Node {
+                next: Atomic::null(),
+                entries: Vec::new(),
+            }),
+            len: StripedCounter::new(),
+        }
+    }
+
+    /// Returns the first unfrozen node that covers `key` and its predecessor, unlinking the frozen
+    /// nodes on the way. The node is null if the list is empty.
+    #[allow(clippy::type_complexity)]
+    fn find<'g>(
+        &'g self,
+        key: &K,
+        guard: &'g Guard,
+    ) -> Result<(Shared<'g, Node<K, V>>, Shared<'g, Node<K, V>>), ()> {
+        let mut prev = self.head.load(Ordering::Acquire, guard);
+        let mut curr = unsafe { prev.deref() }.next.load(Ordering::Acquire, guard);
+        loop {
+            let curr_node = some_or!(unsafe { curr.as_ref() }, return Ok((prev, curr)));
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                let next = next.with_tag(0);
+                unsafe { prev.deref() }
+                    .next
+                    .compare_and_set(curr, next, Ordering::Release, guard)
+                    .map_err(|_| restarts::record())?;
+                op_stats::retire();
+                unsafe { guard.retire(curr) };
+                curr = next;
+                continue;
+            }
+            if curr_node.covers(key, next) {
+                return Ok((prev, curr));
+            }
+            prev = curr;
+            curr = next;
+        }
+    }
+
+    /// Replaces `curr`, whose successor is `next`, with `new`. Returns false if `curr` has changed.
+    fn replace<'g>(
+        &'g self,
+        prev: Shared<'g, Node<K, V>>,
+        curr: Shared<'g, Node<K, V>>,
+        next: Shared<'g, Node<K, V>>,
+        new: Shared<'g, Node<K, V>>,
+        guard: &'g Guard,
+    ) -> bool {
+        if unsafe { curr.deref() }
+            .next
+            .compare_and_set(next, new.with_tag(1), Ordering::AcqRel, guard)
+            .is_err()
+        {
+            return false;
+        }
+        if unsafe { prev.deref() }
+            .next
+            .compare_and_set(curr, new, Ordering::Release, guard)
+            .is_ok()
+        {
+            op_stats::retire();
+            unsafe { guard.retire(curr) };
+        }
+        true
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let head = self.head.load(Ordering::Acquire, guard);
+        let mut curr = unsafe { head.deref() }.next.load(Ordering::Acquire, guard);
+        loop {
+            let curr_node = unsafe { curr.as_ref() }?;
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                // The replacement has the up-to-date entries.
+                curr = next.with_tag(0);
+                continue;
+            }
+            if curr_node.covers(key, next) {
+                return curr_node.search(key).ok().map(|i| &curr_node.entries[i].1);
+            }
+            curr = next;
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        loop {
+            let (prev, curr) = ok_or!(self.find(&key, guard), continue);
+            let curr_node = some_or!(unsafe { curr.as_ref() }, {
+                let new = Node::new(vec![(key.clone(), value.clone())], Shared::null());
+                if unsafe { prev.deref() }
+                    .next
+                    .compare_and_set(Shared::null(), new, Ordering::Release, guard)
+                    .is_ok()
+                {
+                    return true;
+                }
+                restarts::record();
+                continue;
+            });
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                restarts::record();
+                continue;
+            }
+            let index = match curr_node.search(&key) {
+                Ok(_) => return false,
+                Err(index) => index,
+            };
+
+            let mut entries = curr_node.entries.clone();
+            entries.insert(index, (key.clone(), value.clone()));
+            let split = entries.len() > CHUNK_SIZE;
+            let new = if split {
+                let right = entries.split_off(entries.len() / 2);
+                let right = Node::new(right, next).into_shared(guard);
+                Node::new(entries, right).into_shared(guard)
+            } else {
+                Node::new(entries, next).into_shared(guard)
+            };
+            if self.replace(prev, curr, next, new, guard) {
+                return true;
+            }
+            unsafe {
+                let new = new.into_owned();
+                if split {
+                    drop(new.next.load(Ordering::Relaxed, guard).into_owned());
+                }
+            }
+            restarts::record();
+        }
+    }
+
+    pub fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        self.remove_get(key, guard).cloned()
+    }
+
+    /// Like `remove`, but returns the value in the replaced node.
+    pub fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        loop {
+            let (prev, curr) = ok_or!(self.find(key, guard), continue);
+            let curr_node = unsafe { curr.as_ref() }?;
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                restarts::record();
+                continue;
+            }
+            let index = curr_node.search(key).ok()?;
+
+            let value = &curr_node.entries[index].1;
+            let new = if curr_node.entries.len() == 1 {
+                next
+            } else {
+                let mut entries = curr_node.entries.clone();
+                entries.remove(index);
+                Node::new(entries, next).into_shared(guard)
+            };
+            if self.replace(prev, curr, next, new, guard) {
+                return Some(value);
+            }
+            if new != next {
+                drop(unsafe { new.into_owned() });
+            }
+            restarts::record();
+        }
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn new() -> Self {
+        Self::new()
+    }
+
+    #[inline]
+    fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        self.get(key, guard)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V, guard: &Guard) -> bool {
+        let inserted = self.insert(key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K, guard: &Guard) -> Option<V> {
+        let removed = self.remove(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn remove_get<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g V> {
+        let removed = self.remove_get(key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    ebr;
+    UnrolledList => UnrolledList, Decreasing;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnrolledList;
+    use crate::ebr::concurrent_map;
+
+    #[test]
+    fn smoke_unrolled_list() {
+        concurrent_map::tests::smoke::<UnrolledList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_get_unrolled_list() {
+        concurrent_map::tests::remove_get::<UnrolledList<i32, String>>();
+    }
+}
@@ -0,0 +1,128 @@
+//! The environment of a run, embedded into every output record so that the result files describe
+//! the machine and the build that produced them.
+//!
+//! The host and the CPUs are read from procfs and sysfs, and are `unknown` (or 0) elsewhere. The
+//! version of rustc and the commit (suffixed with `-dirty` if the tracked files were modified) are
+//! captured by the build script.
+
+use std::collections::BTreeSet;
+use std::fs;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Environment {
+    pub hostname: String,
+    pub cpu_model: String,
+    /// The number of logical CPUs, physical cores and sockets of the machine.
+    pub cpus: usize,
+    pub cores: usize,
+    pub sockets: usize,
+    pub kernel: String,
+    pub rustc: String,
+    pub commit: String,
+    pub allocator: String,
+}
+
+impl Environment {
+    /// The names of the fields, in the order of `values`.
+    pub const COLUMNS: [&'static str; 9] = [
+        "hostname",
+        "cpu_model",
+        "cpus",
+        "cores",
+        "sockets",
+        "kernel",
+        "rustc",
+        "commit",
+        "allocator",
+    ];
+
+    pub fn capture() -> Self {
+        let (cpus, cores, sockets) = topology();
+        Environment {
+            hostname: read_line("/proc/sys/kernel/hostname"),
+            cpu_model: cpu_model(),
+            cpus,
+            cores,
+            sockets,
+            kernel: read_line("/proc/sys/kernel/osrelease"),
+            rustc: env!("BUILD_RUSTC_VERSION").to_string(),
+            commit: env!("BUILD_GIT_COMMIT").to_string(),
+            allocator: if cfg!(all(not(feature = "sanitize"), target_os = "linux")) {
+                "jemalloc"
+            } else {
+                "system"
+            }
+            .to_string(),
+        }
+    }
+
+    pub fn values(&self) -> Vec<String> {
+        vec![
+            self.hostname.clone(),
+            self.cpu_model.clone(),
+            self.cpus.to_string(),
+            self.cores.to_string(),
+            self.sockets.to_string(),
+            self.kernel.clone(),
+            self.rustc.clone(),
+            self.commit.clone(),
+            self.allocator.clone(),
+        ]
+    }
+
+    /// The pairs of the names and the values of the fields.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        Self::COLUMNS
+            .iter()
+            .map(|c| c.to_string())
+            .zip(self.values())
+            .collect()
+    }
+}
+
+fn read_line(path: &str) -> String {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.lines().next().map(|l| l.trim().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn cpu_model() -> String {
+    fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|info| {
+            info.lines()
+                .find(|l| l.starts_with("model name"))
+                .and_then(|l| l.splitn(2, ':').nth(1))
+                .map(|m| m.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The numbers of the logical CPUs, the physical cores and the sockets in sysfs.
+fn topology() -> (usize, usize, usize) {
+    let entries = match fs::read_dir("/sys/devices/system/cpu") {
+        Ok(entries) => entries,
+        Err(_) => return (0, 0, 0),
+    };
+    let mut cpus = 0;
+    let mut cores = BTreeSet::new();
+    let mut sockets = BTreeSet::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with("cpu") || name[3..].parse::<usize>().is_err() {
+            continue;
+        }
+        let topology = |file: &str| {
+            fs::read_to_string(entry.path().join("topology").join(file))
+                .ok()
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(0)
+        };
+        let socket = topology("physical_package_id");
+        cpus += 1;
+        cores.insert((socket, topology("core_id")));
+        sockets.insert(socket);
+    }
+    (cpus, cores.len(), sockets.len())
+}
@@ -0,0 +1,220 @@
+//! Histograms of how long retired objects wait, to tell a stuck epoch from a lazy collection.
+//!
+//! An object retired in epoch `e` may be freed once the global epoch reaches `e + 2`, but it is
+//! freed only when the bag of garbage that holds it is sealed, pushed to the global queue and
+//! popped by a collection after that. Two latencies are sampled when enabled:
+//!
+//! - **Advance**: the auxiliary thread periodically defers a probe and flushes it to the global
+//!   queue right away, so the probe runs as soon as the epoch has advanced twice and the next
+//!   collection reaches it. This is the latency of the epoch advance that makes an object
+//!   reclaimable, and grows when a thread holds the epoch back.
+//! - **Free**: every `SAMPLE`th object a thread retires is followed by a probe in the same bag,
+//!   which runs right after the object is freed. This is the latency of the actual reclamation,
+//!   and also grows when the bags fill slowly or the collections fall behind.
+//!
+//! A large advance latency means the epoch is stuck, while a small advance latency with a large
+//! free latency means the collection is lazy.
+//!
+//! The probes run on whichever thread collects them, which records their latencies in histograms
+//! of its own. The histograms are merged into the global ones when the thread exits.
+
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Bucket 0 counts the latencies below 2 microseconds, bucket `i > 0` those in `[2^i, 2^(i+1))`
+/// microseconds, and the last bucket also counts the longer ones.
+pub const BUCKETS: usize = 24;
+
+/// One in this many retires is followed by a probe.
+pub const SAMPLE: usize = 64;
+
+/// The period of the probes of the epoch advance.
+pub const PROBE_PERIOD: Duration = Duration::from_millis(10);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+macro_rules! histogram {
+    () => {
+        [
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+        ]
+    };
+}
+
+static ADVANCE: [AtomicUsize; BUCKETS] = histogram!();
+static FREE: [AtomicUsize; BUCKETS] = histogram!();
+
+#[derive(Clone, Copy)]
+enum Latency {
+    Advance,
+    Free,
+}
+
+struct Local {
+    advance: [usize; BUCKETS],
+    free: [usize; BUCKETS],
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        merge(&ADVANCE, &mut self.advance);
+        merge(&FREE, &mut self.free);
+    }
+}
+
+thread_local! {
+    static RETIRES: Cell<usize> = Cell::new(0);
+    static LOCAL: RefCell<Local> = RefCell::new(Local {
+        advance: [0; BUCKETS],
+        free: [0; BUCKETS],
+    });
+}
+
+/// Adds `local` to `global` and clears it.
+fn merge(global: &[AtomicUsize; BUCKETS], local: &mut [usize; BUCKETS]) {
+    for (global, local) in global.iter().zip(local.iter_mut()) {
+        global.fetch_add(*local, Ordering::Relaxed);
+        *local = 0;
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counts a retire of the current thread, and returns the time of the retire if it is sampled.
+#[inline]
+fn sample() -> Option<Instant> {
+    RETIRES.with(|r| {
+        let retires = r.get() + 1;
+        r.set(retires % SAMPLE);
+        if retires == SAMPLE {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    })
+}
+
+fn record(latency: Latency, start: Instant) {
+    let micros = start.elapsed().as_micros() as u64;
+    let bucket = if micros < 2 {
+        0
+    } else {
+        (63 - micros.leading_zeros()) as usize
+    };
+    let bucket = bucket.min(BUCKETS - 1);
+    let recorded = LOCAL.try_with(|local| {
+        let local = &mut *local.borrow_mut();
+        match latency {
+            Latency::Advance => local.advance[bucket] += 1,
+            Latency::Free => local.free[bucket] += 1,
+        }
+    });
+    // The garbage of an exiting thread may be collected after its histograms.
+    if recorded.is_err() {
+        let global = match latency {
+            Latency::Advance => &ADVANCE,
+            Latency::Free => &FREE,
+        };
+        global[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn take_histogram(histogram: &[AtomicUsize; BUCKETS]) -> [usize; BUCKETS] {
+    let mut taken = [0; BUCKETS];
+    for (t, global) in taken.iter_mut().zip(histogram.iter()) {
+        *t = global.swap(0, Ordering::Relaxed);
+    }
+    taken
+}
+
+/// Takes the histograms of the advance and the free latencies of the exited threads and the
+/// current thread.
+pub fn take() -> ([usize; BUCKETS], [usize; BUCKETS]) {
+    LOCAL.with(|local| {
+        let local = &mut *local.borrow_mut();
+        merge(&ADVANCE, &mut local.advance);
+        merge(&FREE, &mut local.free);
+    });
+    (take_histogram(&ADVANCE), take_histogram(&FREE))
+}
+
+/// Probes for the EBR structures.
+pub mod ebr {
+    use crossbeam_ebr::Guard;
+    use std::time::Instant;
+
+    /// Follows an object that `guard` just retired with a probe of the free latency, if sampled.
+    #[inline]
+    pub fn after_retire(guard: &Guard) {
+        // The functions deferred by an unprotected guard, e.g., while prefilling, run at once.
+        if !super::enabled() || guard.collector().is_none() {
+            return;
+        }
+        if let Some(start) = super::sample() {
+            guard.defer(move || super::record(super::Latency::Free, start));
+        }
+    }
+
+    /// Defers a probe of the advance latency and flushes it to the global queue.
+    pub fn probe(guard: &Guard) {
+        let start = Instant::now();
+        guard.defer(move || super::record(super::Latency::Advance, start));
+        guard.flush();
+    }
+}
+
+/// Probes for the PEBR structures.
+pub mod pebr {
+    use crossbeam_pebr::Guard;
+    use std::time::Instant;
+
+    /// Follows an object that `guard` just retired with a probe of the free latency, if sampled.
+    #[inline]
+    pub fn after_retire(guard: &Guard) {
+        // The functions deferred by an unprotected guard, e.g., while prefilling, run at once.
+        if !super::enabled() || guard.collector().is_none() {
+            return;
+        }
+        if let Some(start) = super::sample() {
+            guard.defer(move || super::record(super::Latency::Free, start));
+        }
+    }
+
+    /// Defers a probe of the advance latency and flushes it to the global queue.
+    pub fn probe(guard: &Guard) {
+        let start = Instant::now();
+        guard.defer(move || super::record(super::Latency::Advance, start));
+        guard.flush();
+    }
+}
@@ -0,0 +1,108 @@
+//! The capacity bound of `HashMap` (`--capacity`), and the evictions that keep it.
+//!
+//! Without a bound, the size of a map drifts with the operation mix: it grows under an
+//! insert-heavy mix and shrinks under a remove-heavy one, and so does the garbage that the removes
+//! retire. With a bound, an insert that takes the size of the map over it evicts an entry, i.e.,
+//! removes and retires one, so that the size stays at the bound and the inserts retire a node each
+//! whatever the mix. The victim is the first entry of a bucket, which is chosen by the policy:
+//!
+//! - `random` starts from a random bucket.
+//! - `sweep` starts from the bucket of a hand that moves to the next bucket at each eviction, as
+//!   the hand of the clock algorithm. An entry is thus evicted at most a sweep of the buckets after
+//!   its insert, which approximates evicting the oldest entries.
+//!
+//! If the starting bucket is empty, the eviction tries the following ones. A map reads the bound
+//! when it is created.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eviction {
+    Random,
+    Sweep,
+}
+
+impl Eviction {
+    pub const NAMES: [&'static str; 2] = ["random", "sweep"];
+}
+
+impl fmt::Display for Eviction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Eviction::Random => "random",
+            Eviction::Sweep => "sweep",
+        })
+    }
+}
+
+impl FromStr for Eviction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Eviction::Random),
+            "sweep" => Ok(Eviction::Sweep),
+            _ => Err(format!("invalid eviction policy: {}", s)),
+        }
+    }
+}
+
+/// The bound, or 0 for none.
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+static POLICY: AtomicUsize = AtomicUsize::new(0);
+static EVICTIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct Local(usize);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        EVICTIONS.fetch_add(self.0, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(0));
+}
+
+/// Sets the bound of the maps created afterwards, and how they evict their entries.
+pub fn set_capacity(capacity: Option<usize>, policy: Eviction) {
+    assert_ne!(capacity, Some(0));
+    CAPACITY.store(capacity.unwrap_or(0), Ordering::Relaxed);
+    POLICY.store(policy as usize, Ordering::Relaxed);
+}
+
+/// The bound of a map.
+#[derive(Clone, Copy, Debug)]
+pub struct Capacity {
+    pub bound: usize,
+    pub policy: Eviction,
+}
+
+impl Capacity {
+    /// The bound set by `set_capacity`, if any.
+    pub fn current() -> Option<Self> {
+        let bound = CAPACITY.load(Ordering::Relaxed);
+        if bound == 0 {
+            return None;
+        }
+        let policy = match POLICY.load(Ordering::Relaxed) {
+            0 => Eviction::Random,
+            _ => Eviction::Sweep,
+        };
+        Some(Self { bound, policy })
+    }
+}
+
+/// Records that the current thread evicted an entry.
+#[inline]
+pub fn record() {
+    LOCAL.with(|local| local.borrow_mut().0 += 1);
+}
+
+/// Takes the number of evictions of the exited threads.
+pub fn take() -> usize {
+    EVICTIONS.swap(0, Ordering::Relaxed)
+}
@@ -0,0 +1,167 @@
+//! Drives a concurrent map written in C or C++ through the interface of `ffi/pebr_bench.h`, so that
+//! it runs the same workloads as the maps of this crate.
+//!
+//! The map is loaded from a shared library at run time. It reclaims its own memory, so the bridge
+//! only registers the threads with it and forwards the operations.
+
+use crate::registry::{Driver, Kind, Port, Prefill, Registry, Scheme};
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
+type MapNew = unsafe extern "C" fn() -> *mut c_void;
+type MapFree = unsafe extern "C" fn(*mut c_void);
+type ThreadRegister = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+type ThreadUnregister = unsafe extern "C" fn(*mut c_void, *mut c_void);
+type MapOp = unsafe extern "C" fn(*mut c_void, *mut c_void, *const c_char, usize) -> bool;
+type MapLen = unsafe extern "C" fn(*mut c_void) -> usize;
+
+/// The functions of the interface.
+struct Functions {
+    map_new: MapNew,
+    map_free: MapFree,
+    thread_register: ThreadRegister,
+    thread_unregister: ThreadUnregister,
+    get: MapOp,
+    insert: MapOp,
+    remove: MapOp,
+    len: MapLen,
+}
+
+/// A map of a shared library.
+pub struct FfiMap {
+    functions: Functions,
+    map: *mut c_void,
+}
+
+// The interface requires the map to be thread-safe.
+unsafe impl Send for FfiMap {}
+unsafe impl Sync for FfiMap {}
+
+/// The registration of a thread to an `FfiMap`, through which the thread operates on the map.
+pub struct Handle<'m> {
+    map: &'m FfiMap,
+    thread: *mut c_void,
+}
+
+impl FfiMap {
+    /// Loads the shared library at `path` and creates a map with it.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let functions = unsafe { load(path)? };
+        let map = unsafe { (functions.map_new)() };
+        Ok(FfiMap { functions, map })
+    }
+
+    /// Registers the current thread.
+    pub fn register(&self) -> Handle<'_> {
+        let thread = unsafe { (self.functions.thread_register)(self.map) };
+        Handle { map: self, thread }
+    }
+
+    pub fn len_approx(&self) -> usize {
+        unsafe { (self.functions.len)(self.map) }
+    }
+}
+
+impl Drop for FfiMap {
+    fn drop(&mut self) {
+        unsafe { (self.functions.map_free)(self.map) };
+    }
+}
+
+impl<'m> Handle<'m> {
+    #[inline]
+    fn call(&self, op: MapOp, key: &str) -> bool {
+        unsafe {
+            op(
+                self.map.map,
+                self.thread,
+                key.as_ptr() as *const c_char,
+                key.len(),
+            )
+        }
+    }
+
+    pub fn get(&self, key: &str) -> bool {
+        self.call(self.map.functions.get, key)
+    }
+
+    pub fn insert(&self, key: &str) -> bool {
+        self.call(self.map.functions.insert, key)
+    }
+
+    pub fn remove(&self, key: &str) -> bool {
+        self.call(self.map.functions.remove, key)
+    }
+}
+
+impl<'m> Drop for Handle<'m> {
+    fn drop(&mut self) {
+        unsafe { (self.map.functions.thread_unregister)(self.map.map, self.thread) };
+    }
+}
+
+/// Looks up the functions of the library at `path`. The library stays loaded.
+#[cfg(target_os = "linux")]
+unsafe fn load(path: &str) -> Result<Functions, String> {
+    use std::ffi::{CStr, CString};
+    use std::mem;
+
+    let error = || {
+        let message = libc::dlerror();
+        if message.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        }
+    };
+    let c_path = CString::new(path).map_err(|e| e.to_string())?;
+    let library = libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+    if library.is_null() {
+        return Err(error());
+    }
+    let symbol = |name: &str| {
+        let c_name = CString::new(name).unwrap();
+        let address = libc::dlsym(library, c_name.as_ptr());
+        if address.is_null() {
+            Err(format!("{}: {}", name, error()))
+        } else {
+            Ok(address)
+        }
+    };
+    Ok(Functions {
+        map_new: mem::transmute(symbol("pebr_map_new")?),
+        map_free: mem::transmute(symbol("pebr_map_free")?),
+        thread_register: mem::transmute(symbol("pebr_thread_register")?),
+        thread_unregister: mem::transmute(symbol("pebr_thread_unregister")?),
+        get: mem::transmute(symbol("pebr_map_get")?),
+        insert: mem::transmute(symbol("pebr_map_insert")?),
+        remove: mem::transmute(symbol("pebr_map_remove")?),
+        len: mem::transmute(symbol("pebr_map_len")?),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+unsafe fn load(path: &str) -> Result<Functions, String> {
+    Err(format!(
+        "{}: loading shared libraries is only supported on Linux",
+        path
+    ))
+}
+
+/// Registers the map. It reclaims its own memory, so it runs only under NR, whose ports are those
+/// of EBR.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    let port = Port::new(|d: &D, p| d.ffi_map(p));
+    registry.register("Ffi", Kind::Standalone, Prefill::Random, Scheme::EBR, port);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FfiMap;
+
+    #[test]
+    fn missing_library() {
+        let error = FfiMap::load("/nonexistent/libmap.so").err().unwrap();
+        assert!(error.contains("libmap.so"));
+    }
+}
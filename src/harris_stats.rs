@@ -0,0 +1,156 @@
+//! Statistics and tuning of the cleanup in Harris's list traversal (`HList`).
+//!
+//! `find_harris` unlinks a chain of logically removed nodes with a single CAS. When enabled, the
+//! number of nodes unlinked by each successful CAS is recorded in a per-thread histogram, which is
+//! merged into the global one when the thread exits. Chains longer than the maximum batch size are
+//! unlinked with several CASes, so that the maximum of 1 unlinks the nodes one by one like
+//! Harris-Michael's list.
+//!
+//! `AdaptiveList` starts with Harris-Michael's eager unlinking and switches to Harris's batched
+//! cleanup under contention. Each thread counts the traversals that failed because of a failed
+//! cleanup CAS. At the end of every `ADAPTIVE_WINDOW` traversals, the thread switches to Harris's
+//! cleanup if more than the threshold percentage of them failed, and back if less than half of the
+//! threshold failed. The switches are counted per thread as well.
+
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Bucket `i` counts the batches of size in `[2^i, 2^(i+1))`, and the last bucket also counts the
+/// larger ones.
+pub const BUCKETS: usize = 16;
+
+/// The number of traversals after which an `AdaptiveList` thread reconsiders its cleanup.
+pub const ADAPTIVE_WINDOW: usize = 256;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MAX_BATCH: AtomicUsize = AtomicUsize::new(usize::max_value());
+static ADAPTIVE_THRESHOLD: AtomicUsize = AtomicUsize::new(5);
+static ADAPTIVE_SWITCHES: AtomicUsize = AtomicUsize::new(0);
+static HISTOGRAM: [AtomicUsize; BUCKETS] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+struct Local {
+    histogram: [usize; BUCKETS],
+    adaptive_switches: usize,
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        for (global, local) in HISTOGRAM.iter().zip(self.histogram.iter()) {
+            global.fetch_add(*local, Ordering::Relaxed);
+        }
+        ADAPTIVE_SWITCHES.fetch_add(self.adaptive_switches, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Adaptive {
+    harris: bool,
+    traversals: usize,
+    failures: usize,
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local {
+        histogram: [0; BUCKETS],
+        adaptive_switches: 0,
+    });
+    static ADAPTIVE: Cell<Adaptive> = Cell::new(Adaptive::default());
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn set_max_batch(max_batch: usize) {
+    assert!(max_batch > 0);
+    MAX_BATCH.store(max_batch, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn max_batch() -> usize {
+    MAX_BATCH.load(Ordering::Relaxed)
+}
+
+/// Records that a CAS unlinked `batch` nodes.
+#[inline]
+pub fn record(batch: usize) {
+    if !ENABLED.load(Ordering::Relaxed) || batch == 0 {
+        return;
+    }
+    let bucket = (0usize.leading_zeros() - batch.leading_zeros() - 1) as usize;
+    LOCAL.with(|local| local.borrow_mut().histogram[bucket.min(BUCKETS - 1)] += 1);
+}
+
+/// Sets the percentage of failed traversals above which `AdaptiveList` switches to Harris's cleanup.
+pub fn set_adaptive_threshold(percent: usize) {
+    ADAPTIVE_THRESHOLD.store(percent, Ordering::Relaxed);
+}
+
+/// Whether the current thread cleans up `AdaptiveList` like Harris's list.
+#[inline]
+pub fn adaptive_harris() -> bool {
+    ADAPTIVE.with(|a| a.get().harris)
+}
+
+/// Records whether a traversal of `AdaptiveList` failed, and switches the cleanup of the current
+/// thread at the end of a window.
+#[inline]
+pub fn record_traversal(failed: bool) {
+    ADAPTIVE.with(|a| {
+        let mut adaptive = a.get();
+        adaptive.traversals += 1;
+        adaptive.failures += failed as usize;
+        if adaptive.traversals == ADAPTIVE_WINDOW {
+            let percent = 100 * adaptive.failures / ADAPTIVE_WINDOW;
+            let threshold = ADAPTIVE_THRESHOLD.load(Ordering::Relaxed);
+            let harris = if adaptive.harris {
+                2 * percent >= threshold
+            } else {
+                percent > threshold
+            };
+            if harris != adaptive.harris {
+                LOCAL.with(|local| local.borrow_mut().adaptive_switches += 1);
+            }
+            adaptive = Adaptive {
+                harris,
+                traversals: 0,
+                failures: 0,
+            };
+        }
+        a.set(adaptive);
+    });
+}
+
+/// Takes the number of times the exited threads and the current thread switched the cleanup of
+/// `AdaptiveList`.
+pub fn take_adaptive_switches() -> usize {
+    let own = LOCAL.with(|local| mem::replace(&mut local.borrow_mut().adaptive_switches, 0));
+    ADAPTIVE_SWITCHES.swap(0, Ordering::Relaxed) + own
+}
+
+/// Takes the histogram recorded by the exited threads.
+pub fn take() -> [usize; BUCKETS] {
+    let mut histogram = [0; BUCKETS];
+    for (h, global) in histogram.iter_mut().zip(HISTOGRAM.iter()) {
+        *h = global.swap(0, Ordering::Relaxed);
+    }
+    histogram
+}
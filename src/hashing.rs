@@ -0,0 +1,282 @@
+//! The hash function of the hash maps, and the statistics of the chains of `HashMap`.
+//!
+//! The hash maps used to hash with `DefaultHasher`, whose keys are fixed, so that every run put the
+//! same keys into the same buckets. `--hasher` picks another function, keyed with the seed of the
+//! run: SipHash, the hash of `std`, FxHash, the multiply-and-rotate hash of rustc, which is much
+//! faster but spreads similar keys poorly, or aHash (with the `ahash` feature). The function
+//! changes the lengths of the chains, and thus how long the traversals are and how many nodes they
+//! pass. A map reads the function when it is created.
+//!
+//! When enabled, `HashMap` counts the entries of each of its chains when it is dropped, after a run.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    /// `DefaultHasher` with its fixed keys, regardless of the seed.
+    Default,
+    Sip,
+    Fx,
+    AHash,
+}
+
+impl HashFunction {
+    const ALL: [HashFunction; 4] = [
+        HashFunction::Default,
+        HashFunction::Sip,
+        HashFunction::Fx,
+        HashFunction::AHash,
+    ];
+}
+
+impl fmt::Display for HashFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashFunction::Default => "default",
+            HashFunction::Sip => "sip",
+            HashFunction::Fx => "fx",
+            HashFunction::AHash => "ahash",
+        })
+    }
+}
+
+impl FromStr for HashFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|f| f.to_string() == s)
+            .cloned()
+            .ok_or_else(|| format!("unknown hash function {}", s))
+    }
+}
+
+static FUNCTION: AtomicUsize = AtomicUsize::new(0);
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the hash function of the maps created afterwards, and the seed it is keyed with.
+pub fn set_function(function: HashFunction, seed: u64) {
+    let index = HashFunction::ALL.iter().position(|&f| f == function);
+    FUNCTION.store(index.unwrap(), Ordering::Relaxed);
+    SEED.store(seed, Ordering::Relaxed);
+}
+
+/// The hash function of a map.
+#[derive(Clone, Copy, Debug)]
+pub struct Hashing {
+    function: HashFunction,
+    seed: u64,
+}
+
+impl Default for Hashing {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+impl Hashing {
+    /// The function set by `set_function`.
+    pub fn current() -> Self {
+        Self {
+            function: HashFunction::ALL[FUNCTION.load(Ordering::Relaxed)],
+            seed: SEED.load(Ordering::Relaxed),
+        }
+    }
+
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        match self.function {
+            HashFunction::Default => {
+                let mut s = DefaultHasher::new();
+                key.hash(&mut s);
+                s.finish()
+            }
+            HashFunction::Sip => {
+                // The SipHash-1-3 of `DefaultHasher` cannot be keyed on stable.
+                #[allow(deprecated)]
+                let mut s = std::hash::SipHasher::new_with_keys(self.seed, !self.seed);
+                key.hash(&mut s);
+                s.finish()
+            }
+            HashFunction::Fx => {
+                let mut s = FxHasher { hash: self.seed };
+                key.hash(&mut s);
+                s.finish()
+            }
+            #[cfg(feature = "ahash")]
+            HashFunction::AHash => {
+                use std::hash::BuildHasher;
+                let state = ahash::RandomState::with_seeds(self.seed, !self.seed);
+                let mut s = state.build_hasher();
+                key.hash(&mut s);
+                s.finish()
+            }
+            #[cfg(not(feature = "ahash"))]
+            HashFunction::AHash => unreachable!("checked by setup"),
+        }
+    }
+}
+
+/// The hash of rustc (`FxHasher` of the `rustc-hash` crate), which folds each word into the state
+/// with a rotation, a xor and a multiplication.
+pub struct FxHasher {
+    hash: u64,
+}
+
+const FX_K: u64 = 0x517c_c1b7_2722_0a95;
+
+impl FxHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FX_K);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+        for &byte in chunks.remainder() {
+            self.add(u64::from(byte));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BUCKETS: AtomicU64 = AtomicU64::new(0);
+static EMPTY: AtomicU64 = AtomicU64::new(0);
+static ENTRIES: AtomicU64 = AtomicU64::new(0);
+static SQUARES: AtomicU64 = AtomicU64::new(0);
+static LONGEST: AtomicU64 = AtomicU64::new(0);
+
+/// The chains of the hash maps dropped since the last `take`.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Chains {
+    pub buckets: u64,
+    pub empty: u64,
+    pub entries: u64,
+    /// The sum of the squares of the lengths of the chains.
+    pub squares: u64,
+    pub longest: u64,
+}
+
+impl Chains {
+    /// The entries that share their bucket with an entry before them.
+    pub fn collisions(&self) -> u64 {
+        self.entries - (self.buckets - self.empty)
+    }
+
+    /// The mean length of the chain of an entry, i.e., of the chain that a get of a present key
+    /// walks.
+    pub fn mean_chain(&self) -> f64 {
+        self.squares as f64 / self.entries as f64
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Accounts the lengths of the chains of a map.
+pub fn record_chains<I: Iterator<Item = usize>>(lens: I) {
+    let mut chains = Chains::default();
+    for len in lens {
+        let len = len as u64;
+        chains.buckets += 1;
+        chains.empty += (len == 0) as u64;
+        chains.entries += len;
+        chains.squares += len * len;
+        chains.longest = chains.longest.max(len);
+    }
+    BUCKETS.fetch_add(chains.buckets, Ordering::Relaxed);
+    EMPTY.fetch_add(chains.empty, Ordering::Relaxed);
+    ENTRIES.fetch_add(chains.entries, Ordering::Relaxed);
+    SQUARES.fetch_add(chains.squares, Ordering::Relaxed);
+    let mut longest = LONGEST.load(Ordering::Relaxed);
+    while longest < chains.longest {
+        match LONGEST.compare_exchange_weak(
+            longest,
+            chains.longest,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => break,
+            Err(current) => longest = current,
+        }
+    }
+}
+
+/// Returns the chains recorded so far and resets them.
+pub fn take() -> Chains {
+    Chains {
+        buckets: BUCKETS.swap(0, Ordering::Relaxed),
+        empty: EMPTY.swap(0, Ordering::Relaxed),
+        entries: ENTRIES.swap(0, Ordering::Relaxed),
+        squares: SQUARES.swap(0, Ordering::Relaxed),
+        longest: LONGEST.swap(0, Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashFunction, Hashing};
+
+    #[test]
+    fn functions() {
+        for &function in HashFunction::ALL.iter() {
+            if cfg!(not(feature = "ahash")) && function == HashFunction::AHash {
+                continue;
+            }
+            assert_eq!(function.to_string().parse(), Ok(function));
+            let a = Hashing { function, seed: 1 };
+            let b = Hashing { function, seed: 2 };
+            assert_eq!(a.hash("key"), a.hash("key"));
+            assert_ne!(a.hash("key"), a.hash("kez"));
+            // Only the default function ignores the seed.
+            assert_eq!(
+                a.hash("key") == b.hash("key"),
+                function == HashFunction::Default
+            );
+        }
+    }
+}
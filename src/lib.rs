@@ -1,6 +1,9 @@
 // TODO
 // #![warn(missing_docs)]
 // #![warn(missing_debug_implementations)]
+// The double-width CAS of `tagged` is written in assembly.
+#![feature(asm)]
+
 #[macro_use]
 extern crate cfg_if;
 
@@ -12,17 +15,61 @@ cfg_if! {
     }
 }
 
+#[cfg_attr(any(feature = "trees", feature = "hash-maps"), macro_use)]
+extern crate bitflags;
 extern crate crossbeam_ebr;
 extern crate crossbeam_pebr;
 extern crate crossbeam_utils;
-#[macro_use]
-extern crate bitflags;
-extern crate typenum;
-#[macro_use]
+#[cfg_attr(
+    any(feature = "lists", feature = "hash-maps", feature = "queues"),
+    macro_use
+)]
 extern crate scopeguard;
+extern crate typenum;
 
 #[macro_use]
 mod utils;
 
+pub mod alloc_fault;
+pub mod audit;
+pub mod backoff;
+pub mod bonsai_stats;
+pub mod cas_fault;
+pub mod checksum;
+pub mod code_size;
+pub mod differential;
+pub mod drop_cost;
 pub mod ebr;
+pub mod environment;
+pub mod epoch_latency;
+pub mod eviction;
+pub mod ffi;
+pub mod harris_stats;
+pub mod hashing;
+pub mod linearizability;
+pub mod load_ordering;
+pub mod op_latency;
+pub mod op_stats;
 pub mod pebr;
+pub mod perf_counters;
+pub mod prefill_file;
+pub mod profiling;
+pub mod progress;
+#[cfg(feature = "lists")]
+pub mod rc;
+pub mod reclaim;
+pub mod registry;
+pub mod remove_phases;
+pub mod restarts;
+pub mod results;
+pub mod seek_stats;
+pub mod skiplist_stats;
+pub mod slow_key;
+pub mod striped_counter;
+pub mod stw;
+pub mod summary;
+pub mod tagged;
+pub mod token_bucket;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod ycsb;
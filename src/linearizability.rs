@@ -0,0 +1,228 @@
+//! Linearizability checker for histories of map operations.
+//!
+//! Linearizability is compositional, so a history is checked key by key against the sequential
+//! specification of a set: the state of a key is whether it is present. Each per-key history is
+//! checked with the algorithm of Wing and Gong, with Lowe's memoization of the visited
+//! configurations.
+//!
+//! See Gavin Lowe, Testing for linearizability, Concurrency and Computation: Practice and
+//! Experience, 2017.
+
+use std::collections::{HashMap, HashSet};
+
+/// A map operation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapOp {
+    Get,
+    Insert,
+    Remove,
+}
+
+/// A completed operation in a history.
+#[derive(Clone, Copy, Debug)]
+pub struct Operation {
+    pub client: usize,
+    pub op: MapOp,
+    pub key: usize,
+    /// Whether `get` found the key, `insert` inserted it, or `remove` removed it.
+    pub output: bool,
+    /// The invocation time.
+    pub call: u64,
+    /// The response time.
+    pub ret: u64,
+}
+
+/// Checks if the history is linearizable, starting from the empty map. On failure, returns a key
+/// whose operations are not linearizable.
+pub fn check(history: &[Operation]) -> Result<(), usize> {
+    let mut by_key = HashMap::<usize, Vec<&Operation>>::new();
+    for op in history {
+        by_key.entry(op.key).or_default().push(op);
+    }
+    let mut keys = by_key.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        if !check_key(&by_key[&key]) {
+            return Err(key);
+        }
+    }
+    Ok(())
+}
+
+/// Applies the operation to the state of a key. Returns the new state if the output of the
+/// operation is consistent with the state.
+fn step(present: bool, op: &Operation) -> Option<bool> {
+    match op.op {
+        MapOp::Get if op.output == present => Some(present),
+        MapOp::Insert if op.output != present => Some(true),
+        MapOp::Remove if op.output == present => Some(false),
+        _ => None,
+    }
+}
+
+/// An entry of the doubly linked list of invocation and response events.
+#[derive(Clone, Copy)]
+struct Entry {
+    op: usize,
+    /// The entry of the response if this is an invocation.
+    ret: Option<usize>,
+    prev: usize,
+    next: usize,
+}
+
+fn check_key(ops: &[&Operation]) -> bool {
+    let n = ops.len();
+    let head = 2 * n;
+    let end = 2 * n + 1;
+
+    // Invocations precede responses at the same time, making such operations concurrent.
+    let mut events = Vec::with_capacity(2 * n);
+    for (i, op) in ops.iter().enumerate() {
+        events.push((op.call, false, i));
+        events.push((op.ret, true, i));
+    }
+    events.sort();
+
+    let mut entries = vec![
+        Entry {
+            op: 0,
+            ret: None,
+            prev: 0,
+            next: 0,
+        };
+        2 * n + 2
+    ];
+    let mut ret_entry = vec![0; n];
+    let mut prev = head;
+    for (e, &(_, is_ret, i)) in events.iter().enumerate() {
+        entries[e].op = i;
+        entries[e].prev = prev;
+        entries[prev].next = e;
+        if is_ret {
+            ret_entry[i] = e;
+        }
+        prev = e;
+    }
+    entries[prev].next = end;
+    entries[end].prev = prev;
+    for (e, &(_, is_ret, i)) in events.iter().enumerate() {
+        if !is_ret {
+            entries[e].ret = Some(ret_entry[i]);
+        }
+    }
+
+    let unlink = |entries: &mut Vec<Entry>, e: usize| {
+        let Entry { prev, next, .. } = entries[e];
+        entries[prev].next = next;
+        entries[next].prev = prev;
+    };
+    let relink = |entries: &mut Vec<Entry>, e: usize| {
+        let Entry { prev, next, .. } = entries[e];
+        entries[prev].next = e;
+        entries[next].prev = e;
+    };
+
+    let mut state = false;
+    let mut linearized = vec![0u64; (n + 63) / 64];
+    let mut cache = HashSet::new();
+    // The linearized invocations and the states before them.
+    let mut stack = Vec::<(usize, bool)>::new();
+    let mut e = entries[head].next;
+    while e != end {
+        let entry = entries[e];
+        match entry.ret {
+            Some(ret) => {
+                if let Some(next_state) = step(state, ops[entry.op]) {
+                    linearized[entry.op / 64] |= 1 << (entry.op % 64);
+                    if cache.insert((linearized.clone(), next_state)) {
+                        stack.push((e, state));
+                        state = next_state;
+                        unlink(&mut entries, e);
+                        unlink(&mut entries, ret);
+                        e = entries[head].next;
+                        continue;
+                    }
+                    linearized[entry.op / 64] &= !(1 << (entry.op % 64));
+                }
+                e = entry.next;
+            }
+            None => {
+                // An operation has returned before any of the pending ones can be linearized.
+                let (call, prev_state) = some_or!(stack.pop(), return false);
+                let op = entries[call].op;
+                state = prev_state;
+                linearized[op / 64] &= !(1 << (op % 64));
+                let ret = entries[call].ret.unwrap();
+                relink(&mut entries, ret);
+                relink(&mut entries, call);
+                e = entries[call].next;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, MapOp, Operation};
+
+    fn op(client: usize, op: MapOp, output: bool, call: u64, ret: u64) -> Operation {
+        Operation {
+            client,
+            op,
+            key: 0,
+            output,
+            call,
+            ret,
+        }
+    }
+
+    #[test]
+    fn sequential() {
+        let history = [
+            op(0, MapOp::Get, false, 0, 1),
+            op(0, MapOp::Insert, true, 2, 3),
+            op(1, MapOp::Insert, false, 4, 5),
+            op(1, MapOp::Get, true, 6, 7),
+            op(0, MapOp::Remove, true, 8, 9),
+            op(1, MapOp::Remove, false, 10, 11),
+        ];
+        assert_eq!(check(&history), Ok(()));
+    }
+
+    #[test]
+    fn concurrent() {
+        // The get is linearized before the insert even though it is invoked later.
+        let history = [
+            op(0, MapOp::Insert, true, 0, 10),
+            op(1, MapOp::Get, false, 1, 2),
+            op(1, MapOp::Get, true, 3, 4),
+            op(2, MapOp::Remove, true, 5, 12),
+            op(1, MapOp::Insert, true, 13, 14),
+        ];
+        assert_eq!(check(&history), Ok(()));
+    }
+
+    #[test]
+    fn violation() {
+        // Both removes succeed with only one insert.
+        let mut history = vec![
+            op(0, MapOp::Insert, true, 0, 10),
+            op(1, MapOp::Remove, true, 1, 11),
+            op(2, MapOp::Remove, true, 2, 12),
+        ];
+        history.push(Operation {
+            key: 1,
+            ..op(0, MapOp::Insert, true, 20, 21)
+        });
+        assert_eq!(check(&history), Err(0));
+
+        // The key is found after it is removed.
+        let history = [
+            op(0, MapOp::Insert, true, 0, 1),
+            op(0, MapOp::Remove, true, 2, 3),
+            op(1, MapOp::Get, true, 4, 5),
+        ];
+        assert_eq!(check(&history), Err(0));
+    }
+}
@@ -0,0 +1,36 @@
+//! The memory ordering of the loads of the next fields in the find loops of the Harris lists, to
+//! measure what the ordering costs on each architecture.
+//!
+//! The traversals load the next field of every node they visit, so the ordering of that load is on
+//! the hottest path of the lists. It is `Acquire` unless the crate is built with one of the
+//! features below, which are meant for experiments only:
+//!
+//! - `next-load-relaxed` loads with `Relaxed`. This is unsound: a traversal may follow a pointer to
+//!   a node whose initialization (its key, its value and its own next field) it does not see yet.
+//!   On x86, whose loads are all acquire loads, it compiles to the same instruction and only frees
+//!   the compiler to reorder, while on ARM and POWER it drops the barrier or the dependency
+//!   ordering and the lists may read garbage, so a failure of a run is a finding, not a bug.
+//! - `next-load-seqcst` loads with `SeqCst`, which is stronger than the lists need. It takes
+//!   precedence over `next-load-relaxed`, so that a build with all the features is sound.
+//!
+//! A build with either feature should be validated before it is measured, by running the `shadow_*`
+//! tests, which check every operation of several threads against a sequential map:
+//!
+//! ```text
+//! cargo test --release --features next-load-relaxed shadow
+//! ```
+
+use std::sync::atomic::Ordering;
+
+cfg_if! {
+    if #[cfg(feature = "next-load-seqcst")] {
+        /// The ordering of the loads of the next fields in the find loops.
+        pub const NEXT: Ordering = Ordering::SeqCst;
+    } else if #[cfg(feature = "next-load-relaxed")] {
+        /// The ordering of the loads of the next fields in the find loops.
+        pub const NEXT: Ordering = Ordering::Relaxed;
+    } else {
+        /// The ordering of the loads of the next fields in the find loops.
+        pub const NEXT: Ordering = Ordering::Acquire;
+    }
+}
@@ -7,41 +7,145 @@ extern crate crossbeam_ebr;
 extern crate crossbeam_pebr;
 extern crate pebr_benchmark;
 
-use clap::{arg_enum, value_t, App, Arg, ArgMatches};
+mod adapter;
+
+use clap::{arg_enum, value_t, values_t, App, AppSettings, Arg, ArgMatches, SubCommand};
 use crossbeam_utils::thread::scope;
 use csv::Writer;
 use rand::distributions::{Uniform, WeightedIndex};
 use rand::prelude::*;
-use std::cmp::max;
+use std::cell::Cell;
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::env;
 use std::fmt;
-use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{stdout, Write};
-use std::mem::ManuallyDrop;
-use std::sync::{mpsc, Arc, Barrier};
+use std::fs::{self, create_dir_all, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::process::{self, Command};
+use std::ptr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
 use std::time::{Duration, Instant};
 use typenum::{Unsigned, U1, U4};
 
+use pebr_benchmark::audit;
+use pebr_benchmark::backoff;
+use pebr_benchmark::bonsai_stats;
+use pebr_benchmark::checksum::Checksum;
+use pebr_benchmark::drop_cost;
 use pebr_benchmark::ebr;
+use pebr_benchmark::ebr::gc_policy::GcPolicy;
+use pebr_benchmark::environment::Environment;
+use pebr_benchmark::epoch_latency;
+use pebr_benchmark::eviction::{self, Eviction};
+use pebr_benchmark::harris_stats;
+use pebr_benchmark::hashing::{self, HashFunction};
+use pebr_benchmark::linearizability::{self, MapOp, Operation};
+use pebr_benchmark::load_ordering;
+use pebr_benchmark::op_latency;
+use pebr_benchmark::op_stats;
 use pebr_benchmark::pebr;
+use pebr_benchmark::perf_counters;
+use pebr_benchmark::prefill_file::PrefillKeys;
+use pebr_benchmark::progress::{Progress, Snapshot};
+#[cfg(feature = "lists")]
+use pebr_benchmark::rc;
+use pebr_benchmark::registry::{Driver, Entry, Kind, Prefill, Registry, Scheme};
+use pebr_benchmark::remove_phases::{self, Phase};
+use pebr_benchmark::restarts;
+use pebr_benchmark::results::{self, Record, Store};
+use pebr_benchmark::seek_stats;
+use pebr_benchmark::skiplist_stats;
+use pebr_benchmark::slow_key::{self, SlowKey};
+use pebr_benchmark::striped_counter::StripedCounter;
+use pebr_benchmark::summary::{self, Format};
+use pebr_benchmark::tagged;
+use pebr_benchmark::token_bucket::TokenBucket;
+use pebr_benchmark::ycsb::Trace;
 
-arg_enum! {
-    #[derive(PartialEq, Debug)]
-    pub enum DS {
-        HList,
-        HMList,
-        HHSList,
-        HashMap,
-        NMTree,
-        BonsaiTree,
+/// A data structure of the registry, by name.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct DS(&'static str);
+
+impl DS {
+    /// The names of the structures that are compiled in.
+    fn variants() -> Vec<&'static str> {
+        structures().entries().iter().map(|e| e.name).collect()
+    }
+
+    fn kind(self) -> Kind {
+        structures()
+            .get(self.0)
+            .expect("parsed from the registry")
+            .kind
+    }
+
+    fn is_map(self) -> bool {
+        self.kind() != Kind::Other
+    }
+}
+
+impl FromStr for DS {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        structures()
+            .entries()
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(s))
+            .map(|e| DS(e.name))
+            .ok_or_else(|| format!("{} is not a data structure of this build", s))
+    }
+}
+
+impl fmt::Display for DS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
     }
 }
 
+/// The registry of the structures that are compiled in, for their names and their ports. A run
+/// benchmarks its structure with the registry of its period, whose entries are the same.
+fn structures() -> Registry<Bench<'static, U1>> {
+    Registry::new()
+}
+
 arg_enum! {
-    #[derive(PartialEq, Debug)]
+    #[derive(PartialEq, Debug, Clone, Copy)]
     pub enum MM {
         NR,
         EBR,
         PEBR,
+        RC,
+        STW,
+    }
+}
+
+impl MM {
+    /// The scheme of the ports that the memory manager runs.
+    fn scheme(self) -> Scheme {
+        match self {
+            MM::NR | MM::EBR | MM::STW => Scheme::EBR,
+            MM::PEBR => Scheme::PEBR,
+            MM::RC => Scheme::RC,
+        }
+    }
+}
+
+arg_enum! {
+    /// Predefined workloads that override some of the arguments.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum Preset {
+        GarbageBomb,
+        DeleteStorm,
+        Monotonic,
     }
 }
 
@@ -59,7 +163,213 @@ impl fmt::Display for OpsPerCs {
     }
 }
 
-#[derive(PartialEq, Debug)]
+/// The most batches of operations over which `--extend-read-sections` extends a read-only critical section of PEBR.
+const READ_ONLY_BATCHES: u64 = 16;
+
+/// Decides whether a worker of PEBR repins at the end of a batch of operations. It always does by
+/// default. With `--extend-read-sections`, a critical section that performed only reads is
+/// extended instead, since it created no garbage, but for at most `READ_ONLY_BATCHES` batches, so
+/// that a reader still repins periodically rather than leave it to the ejections to advance the
+/// epoch.
+struct Repin {
+    extend_reads: bool,
+    /// Whether the current critical section performed an update.
+    written: bool,
+    /// The batches that the current critical section was extended over.
+    extended: u64,
+}
+
+impl Repin {
+    fn new(config: &Config) -> Self {
+        Self {
+            extend_reads: config.extend_read_sections,
+            written: false,
+            extended: 0,
+        }
+    }
+
+    /// Records an update of the current critical section.
+    #[inline]
+    fn write(&mut self) {
+        self.written = true;
+    }
+
+    /// Whether to repin at the end of the current batch.
+    #[inline]
+    fn due(&mut self) -> bool {
+        if self.extend_reads && !self.written && self.extended + 1 < READ_ONLY_BATCHES {
+            self.extended += 1;
+            return false;
+        }
+        self.written = false;
+        self.extended = 0;
+        true
+    }
+}
+
+/// The distribution of the lengths of the string keys. The length of a key is determined by its
+/// hash so that a key always maps to the same string.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum KeySize {
+    /// The decimal representation of the key.
+    Fixed,
+    Exponential {
+        mean: f64,
+    },
+    Pareto {
+        scale: f64,
+        shape: f64,
+    },
+}
+
+const MAX_KEY_SIZE: usize = 1 << 12;
+/// The size of the values inserted by the bomber of the garbage bomb.
+const GARBAGE_BOMB_VALUE_SIZE: usize = 1 << 12;
+
+impl KeySize {
+    fn key(&self, k: usize) -> String {
+        let len = match *self {
+            KeySize::Fixed => return k.to_string(),
+            KeySize::Exponential { mean } => -mean * (1.0 - unit_hash(k)).ln(),
+            KeySize::Pareto { scale, shape } => scale / (1.0 - unit_hash(k)).powf(1.0 / shape),
+        };
+        // Clamped before the cast, which is undefined for the out-of-range (e.g. infinite) lengths
+        // of the heavy tails. Zero-padding keeps the keys distinct.
+        format!("{:0>1$}", k, len.min(MAX_KEY_SIZE as f64) as usize)
+    }
+}
+
+/// The keys of the maps: the strings themselves, or `SlowKey`s with `--slow-cmp`.
+trait BenchKey: From<String> + Deref<Target = str> {}
+
+impl<K: From<String> + Deref<Target = str>> BenchKey for K {}
+
+/// The values of the maps, which account for their drops with the `audit` feature (`--audit`).
+#[cfg(not(feature = "audit"))]
+type Value = String;
+#[cfg(feature = "audit")]
+type Value = audit::Audited<String>;
+
+/// Maps `k` to [0, 1) with the finalizer of splitmix64.
+fn unit_hash(k: usize) -> f64 {
+    let mut z = (k as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Whether key `k` is among the `fraction` of the keys that are long-lived. The hash of the
+/// complement is independent of the size of the key.
+fn is_long_lived(fraction: f64, k: usize) -> bool {
+    unit_hash(!k) < fraction
+}
+
+impl FromStr for KeySize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = s.split(':').collect::<Vec<_>>();
+        let parse = |p: &str| match p.parse::<f64>() {
+            Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+            _ => Err(format!("invalid key size parameter: {}", p)),
+        };
+        match params.as_slice() {
+            ["fixed"] => Ok(KeySize::Fixed),
+            ["exp", mean] => Ok(KeySize::Exponential { mean: parse(mean)? }),
+            ["pareto", scale, shape] => Ok(KeySize::Pareto {
+                scale: parse(scale)?,
+                shape: parse(shape)?,
+            }),
+            _ => Err(format!(
+                "invalid key size: {} (expected fixed, exp:MEAN or pareto:SCALE:SHAPE)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for KeySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySize::Fixed => write!(f, "fixed"),
+            KeySize::Exponential { mean } => write!(f, "exp:{}", mean),
+            KeySize::Pareto { scale, shape } => write!(f, "pareto:{}:{}", scale, shape),
+        }
+    }
+}
+
+/// The delay a worker spends between two operations, as a mean and a distribution.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ThinkTime {
+    ns: u64,
+    dist: ThinkDist,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ThinkDist {
+    Fixed,
+    /// Exponentially distributed, as the gaps between the arrivals of a Poisson process.
+    Exponential,
+    /// Uniform in `[0, 2 * ns]`.
+    Uniform,
+}
+
+impl ThinkTime {
+    /// Spins for a think time drawn from the distribution. The worker stays on its CPU and keeps
+    /// its guard while thinking. Returns whether there is a think time at all.
+    #[inline]
+    fn think<R: Rng>(&self, rng: &mut R) -> bool {
+        if self.ns == 0 {
+            return false;
+        }
+        let ns = match self.dist {
+            ThinkDist::Fixed => self.ns,
+            ThinkDist::Exponential => (-(self.ns as f64) * (1.0 - rng.gen::<f64>()).ln()) as u64,
+            ThinkDist::Uniform => rng.gen_range(0, 2 * self.ns + 1),
+        };
+        let delay = Duration::from_nanos(ns);
+        let start = Instant::now();
+        while start.elapsed() < delay {}
+        true
+    }
+}
+
+impl FromStr for ThinkTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = s.split(':').collect::<Vec<_>>();
+        let (ns, dist) = match params.as_slice() {
+            [ns] => (ns, ThinkDist::Fixed),
+            [ns, "fixed"] => (ns, ThinkDist::Fixed),
+            [ns, "exp"] => (ns, ThinkDist::Exponential),
+            [ns, "uniform"] => (ns, ThinkDist::Uniform),
+            _ => {
+                return Err(format!(
+                    "invalid think time: {} (expected N, N:fixed, N:exp or N:uniform)",
+                    s
+                ))
+            }
+        };
+        let ns = ns
+            .parse()
+            .map_err(|_| format!("invalid think time: {}", ns))?;
+        Ok(ThinkTime { ns, dist })
+    }
+}
+
+impl fmt::Display for ThinkTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.dist {
+            ThinkDist::Fixed => write!(f, "{}", self.ns),
+            ThinkDist::Exponential => write!(f, "{}:exp", self.ns),
+            ThinkDist::Uniform => write!(f, "{}:uniform", self.ns),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Op {
     Get,
     Insert,
@@ -68,6 +378,443 @@ pub enum Op {
 
 impl Op {
     const OPS: [Op; 3] = [Op::Get, Op::Insert, Op::Remove];
+    const NAMES: [&'static str; 3] = ["get", "insert", "remove"];
+}
+
+/// The numbers of the map operations of each kind and of those that failed, i.e. the gets and the
+/// removes of absent keys and the inserts of present keys, indexed like `Op::OPS`.
+#[derive(Default, Debug)]
+struct Outcomes {
+    ops: [u64; 3],
+    failed: [u64; 3],
+}
+
+impl Outcomes {
+    #[inline]
+    fn record(&mut self, op: Op, ok: bool) {
+        self.ops[op as usize] += 1;
+        self.failed[op as usize] += !ok as u64;
+    }
+
+    fn merge(&mut self, other: &Outcomes) {
+        for i in 0..3 {
+            self.ops[i] += other.ops[i];
+            self.failed[i] += other.failed[i];
+        }
+    }
+
+    /// The fraction of the operations of kind `op` that failed, if any were performed.
+    fn fail_rate(&self, op: Op) -> Option<f64> {
+        match self.ops[op as usize] {
+            0 => None,
+            n => Some(self.failed[op as usize] as f64 / n as f64),
+        }
+    }
+}
+
+impl From<MapOp> for Op {
+    fn from(op: MapOp) -> Self {
+        match op {
+            MapOp::Get => Op::Get,
+            MapOp::Insert => Op::Insert,
+            MapOp::Remove => Op::Remove,
+        }
+    }
+}
+
+/// Threads that perform their own mix of map operations, e.g. `16:get=100` for 16 threads that
+/// only perform `get`s, optionally at a limited rate, e.g. `4:insert=100@100000` for 4 threads that
+/// insert 100k keys per second in total.
+struct ThreadGroup {
+    name: &'static str,
+    threads: usize,
+    /// The weights of `Op::OPS`.
+    weights: [i32; 3],
+    op_dist: WeightedIndex<i32>,
+    /// The limit of the rate of the operations of the threads, which holds a burst of an operation
+    /// per thread.
+    limit: Option<TokenBucket>,
+    /// The number of operations and the longest elapsed time of the threads in the current run.
+    stats: Mutex<(u64, Duration)>,
+}
+
+impl ThreadGroup {
+    fn parse(name: &'static str, s: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "invalid thread group: {} (expected THREADS:OP=WEIGHT,...[@OPS_PER_SEC], e.g. \
+                 4:insert=50,remove=50@100000)",
+                s
+            )
+        };
+        let (s, limit) = match s.find('@') {
+            Some(i) => {
+                let rate = s[i + 1..]
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|r| *r > 0)
+                    .ok_or_else(invalid)?;
+                (&s[..i], Some(rate))
+            }
+            None => (s, None),
+        };
+        let mut parts = s.splitn(2, ':');
+        let threads = parts
+            .next()
+            .and_then(|t| t.parse::<usize>().ok())
+            .ok_or_else(invalid)?;
+        let mut weights = [0; 3];
+        for entry in parts.next().ok_or_else(invalid)?.split(',') {
+            let mut kv = entry.splitn(2, '=');
+            let op = kv.next().unwrap();
+            let i = Op::NAMES
+                .iter()
+                .position(|n| *n == op)
+                .ok_or_else(invalid)?;
+            weights[i] = kv
+                .next()
+                .and_then(|w| w.parse::<i32>().ok())
+                .filter(|w| *w >= 0)
+                .ok_or_else(invalid)?;
+        }
+        let op_dist = WeightedIndex::new(&weights).map_err(|_| invalid())?;
+        Ok(ThreadGroup {
+            name,
+            threads,
+            weights,
+            op_dist,
+            limit: limit.map(|rate| TokenBucket::new(rate, threads as u64)),
+            stats: Mutex::new((0, Duration::default())),
+        })
+    }
+}
+
+impl fmt::Display for ThreadGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mix = Op::NAMES
+            .iter()
+            .zip(self.weights.iter())
+            .filter(|(_, w)| **w > 0)
+            .map(|(n, w)| format!("{}={}", n, w))
+            .collect::<Vec<_>>();
+        write!(f, "{}:{}:{}", self.name, self.threads, mix.join(","))?;
+        if let Some(limit) = &self.limit {
+            write!(f, "@{}", limit.rate())?;
+        }
+        Ok(())
+    }
+}
+
+/// A CPU that the workers can be pinned to.
+#[derive(Clone, Copy)]
+struct Cpu {
+    id: usize,
+    socket: usize,
+    core: usize,
+}
+
+/// The work of the workers pinned to a CPU, a core or a socket.
+#[derive(Clone, Copy, Default)]
+struct CpuStats {
+    threads: usize,
+    ops: u64,
+    /// The longest elapsed time of the workers.
+    elapsed: Duration,
+    retries: u64,
+    retires: u64,
+}
+
+impl CpuStats {
+    fn merge(&mut self, other: &CpuStats) {
+        self.threads += other.threads;
+        self.ops += other.ops;
+        self.elapsed = max(self.elapsed, other.elapsed);
+        self.retries += other.retries;
+        self.retires += other.retires;
+    }
+}
+
+/// Pins the workers to the CPUs, and breaks down the results by socket and core.
+struct Pinning {
+    /// The CPUs that the process may run on. Worker `tid` is pinned to `cpus[tid % cpus.len()]`.
+    cpus: Vec<Cpu>,
+    /// The CPU and the work of each worker in the current run.
+    workers: Mutex<Vec<(Cpu, CpuStats)>>,
+}
+
+impl Pinning {
+    fn cpu(&self, tid: usize) -> Cpu {
+        self.cpus[tid % self.cpus.len()]
+    }
+
+    /// Prints the work of the current run per socket, and per core in each socket.
+    fn report(&self, config: &Config) {
+        let workers = mem::replace(&mut *self.workers.lock().unwrap(), Vec::new());
+        let mut sockets = BTreeMap::<usize, CpuStats>::new();
+        let mut cores = BTreeMap::<(usize, usize), CpuStats>::new();
+        for (cpu, stats) in &workers {
+            sockets.entry(cpu.socket).or_default().merge(stats);
+            cores
+                .entry((cpu.socket, cpu.core))
+                .or_default()
+                .merge(stats);
+        }
+        let line = |stats: &CpuStats| {
+            format!(
+                "{} threads, ops/s: {}, retries: {}, retires: {}",
+                stats.threads,
+                config.throughput(stats.ops, stats.elapsed),
+                stats.retries,
+                stats.retires
+            )
+        };
+        for (socket, stats) in &sockets {
+            println!("socket {}: {}", socket, line(stats));
+            for ((_, core), stats) in cores.range((*socket, 0)..=(*socket, usize::max_value())) {
+                println!("  core {}: {}", core, line(stats));
+            }
+        }
+    }
+}
+
+/// How often the workers publish their progress.
+const PROGRESS_OPS: u64 = 256;
+/// How often the progress is printed.
+const PROGRESS_PERIOD: Duration = Duration::from_secs(1);
+
+/// The largest `--storm-size`, in millions of keys, far more than fit in memory. It bounds the
+/// number of keys so that it is a valid `usize`.
+const MAX_STORM_SIZE: f64 = 1e4;
+
+/// The phases of `Preset::DeleteStorm`. Worker `tid` inserts the keys `tid`, `tid + threads`, ...
+/// below `size`, waits for the others, and then removes the same keys, so that all workers empty
+/// the grown structure at once and the reclamation scheme has to deal with the garbage of the whole
+/// structure.
+struct Storm {
+    size: usize,
+    /// Set when all workers have finished their inserts.
+    released: AtomicBool,
+    phases: Mutex<StormPhases>,
+}
+
+#[derive(Default)]
+struct StormPhases {
+    /// The memory allocated before the inserts.
+    base: usize,
+    /// The workers that have finished their inserts, and those that have finished their removes.
+    grown: usize,
+    emptied: usize,
+    start: Option<Instant>,
+    /// When the last worker finished its inserts, and the memory allocated then.
+    grown_at: Option<(Instant, usize)>,
+    /// When the last worker finished its removes, and the memory allocated then.
+    emptied_at: Option<(Instant, usize)>,
+    /// The most memory allocated beyond the base and the remaining entries during the removes.
+    peak_garbage: usize,
+}
+
+impl Storm {
+    fn new(size: usize) -> Self {
+        Storm {
+            size,
+            released: AtomicBool::new(false),
+            phases: Mutex::new(StormPhases::default()),
+        }
+    }
+
+    /// Prepares a new run, before the structure is created.
+    fn reset(&self, allocated: usize) {
+        self.released.store(false, Ordering::Relaxed);
+        *self.phases.lock().unwrap() = StormPhases {
+            base: allocated,
+            ..StormPhases::default()
+        };
+    }
+
+    fn next_op(&self, config: &Config, tid: usize, ops: u64) -> Option<(Op, usize)> {
+        let share = (self.size + config.threads - 1 - tid) / config.threads;
+        let ops = ops as usize;
+        if ops == 0 {
+            let mut phases = self.phases.lock().unwrap();
+            phases.start.get_or_insert_with(Instant::now);
+        }
+        if ops == share {
+            self.wait_grown(config);
+        }
+        if ops < share {
+            Some((Op::Insert, tid + ops * config.threads))
+        } else if ops < 2 * share {
+            Some((Op::Remove, tid + (ops - share) * config.threads))
+        } else {
+            let mut phases = self.phases.lock().unwrap();
+            phases.emptied += 1;
+            if phases.emptied == config.threads {
+                phases.emptied_at = Some((Instant::now(), config.mem_sampler.sample()));
+            }
+            None
+        }
+    }
+
+    /// Waits until all workers have finished their inserts, or the run is stopped.
+    fn wait_grown(&self, config: &Config) {
+        {
+            let mut phases = self.phases.lock().unwrap();
+            phases.grown += 1;
+            if phases.grown == config.threads {
+                phases.grown_at = Some((Instant::now(), config.mem_sampler.sample()));
+                self.released.store(true, Ordering::Release);
+            }
+        }
+        while !self.released.load(Ordering::Acquire) && !interrupted() && !failed() {
+            thread::yield_now();
+        }
+    }
+
+    /// Takes a sample of the time series during the removes, when the structure holds `len`
+    /// entries and `allocated` bytes are allocated. The entries are assumed to take the memory
+    /// they took on average when the structure was full, and the rest is garbage.
+    fn sample(&self, len: usize, allocated: usize) {
+        let mut phases = self.phases.lock().unwrap();
+        let full = match phases.grown_at {
+            Some((_, full)) if phases.emptied_at.is_none() => full,
+            _ => return,
+        };
+        let per_entry = full.saturating_sub(phases.base) as f64 / max(self.size, 1) as f64;
+        let live = phases.base + (per_entry * len as f64) as usize;
+        phases.peak_garbage = max(phases.peak_garbage, allocated.saturating_sub(live));
+    }
+
+    fn report(&self) {
+        let phases = self.phases.lock().unwrap();
+        let (start, (grown, full), (emptied, left)) =
+            match (phases.start, phases.grown_at, phases.emptied_at) {
+                (Some(start), Some(grown), Some(emptied)) => (start, grown, emptied),
+                _ => {
+                    println!("storm: the run stopped before the structure was emptied");
+                    return;
+                }
+            };
+        let mib = |bytes: usize| bytes as f64 / (1 << 20) as f64;
+        println!(
+            "storm: grew to {} entries ({:.1} MiB) in {} ms, emptied in {} ms, peak garbage \
+             {:.1} MiB, {:.1} MiB left when emptied",
+            self.size,
+            mib(full.saturating_sub(phases.base)),
+            (grown - start).as_millis(),
+            (emptied - grown).as_millis(),
+            mib(phases.peak_garbage),
+            mib(left.saturating_sub(phases.base))
+        );
+    }
+}
+
+/// The keys of `Preset::Monotonic` start at this index, so that their decimal representations have
+/// the same length and sort in the order of their indices.
+const MONOTONIC_BASE: usize = 1_000_000_000_000;
+
+/// The approximate bounds of the keys of `Preset::Monotonic`. Each insert takes a new maximum and
+/// each remove takes the minimum, so that an ordered list degenerates into a queue that inserts at
+/// its tail and removes at its head, and a tree into a path that grows at one end and shrinks at
+/// the other. The bounds move before the operations on the keys complete, so the map may briefly
+/// miss the keys near them, and a remove that overtakes the insert of its key leaves that key
+/// behind the minimum.
+struct Monotonic {
+    /// The number of keys of the prefill, which the bounds start around.
+    prefill: usize,
+    /// The smallest key that has not been taken by a remove, and the next key to insert.
+    min: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl Monotonic {
+    fn new(prefill: usize) -> Self {
+        Monotonic {
+            prefill,
+            min: AtomicUsize::new(MONOTONIC_BASE),
+            max: AtomicUsize::new(MONOTONIC_BASE + prefill),
+        }
+    }
+
+    /// Prepares a new run, before the structure is created.
+    fn reset(&self) {
+        self.min.store(MONOTONIC_BASE, Ordering::Relaxed);
+        self.max
+            .store(MONOTONIC_BASE + self.prefill, Ordering::Relaxed);
+    }
+
+    /// The keys of the prefill, shuffled so that the trees are balanced before the run.
+    fn prefill_keys<R: Rng>(&self, rng: &mut R) -> Vec<usize> {
+        let mut keys = (MONOTONIC_BASE..MONOTONIC_BASE + self.prefill).collect::<Vec<_>>();
+        keys.shuffle(rng);
+        keys
+    }
+
+    /// The key of an operation of kind `op`, which becomes an insert if the map looks empty.
+    #[inline]
+    fn next_op<R: Rng>(&self, op: Op, rng: &mut R) -> (Op, usize) {
+        match op {
+            Op::Get => {
+                let min = self.min.load(Ordering::Relaxed);
+                let max = self.max.load(Ordering::Relaxed);
+                let k = if min < max {
+                    rng.gen_range(min, max)
+                } else {
+                    min
+                };
+                (Op::Get, k)
+            }
+            Op::Insert => (Op::Insert, self.max.fetch_add(1, Ordering::Relaxed)),
+            Op::Remove => {
+                let mut min = self.min.load(Ordering::Relaxed);
+                loop {
+                    if min >= self.max.load(Ordering::Relaxed) {
+                        return (Op::Insert, self.max.fetch_add(1, Ordering::Relaxed));
+                    }
+                    match self.min.compare_exchange_weak(
+                        min,
+                        min + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return (Op::Remove, min),
+                        Err(m) => min = m,
+                    }
+                }
+            }
+        }
+    }
+
+    fn report(&self) {
+        let min = self.min.load(Ordering::Relaxed) - MONOTONIC_BASE;
+        let max = self.max.load(Ordering::Relaxed) - MONOTONIC_BASE;
+        println!(
+            "monotonic: keys {}..{} after the run, {} inserted and {} removed",
+            min,
+            max,
+            max - self.prefill,
+            min
+        );
+    }
+}
+
+/// The times that the allocated memory took to return to its level before each stall window, once
+/// the stopped workers were resumed.
+#[derive(Default)]
+struct Recovery {
+    times: Vec<Duration>,
+    /// The windows after which the memory did not return before the next window or the end of the
+    /// run.
+    unrecovered: usize,
+}
+
+impl Recovery {
+    fn mean_ms(&self) -> Option<f64> {
+        if self.times.is_empty() {
+            return None;
+        }
+        let total = self.times.iter().sum::<Duration>();
+        Some(total.as_secs_f64() * 1000.0 / self.times.len() as f64)
+    }
 }
 
 struct Config {
@@ -79,725 +826,4501 @@ struct Config {
     aux_thread_period: Duration,
     non_coop: usize,
     non_coop_period: Duration,
+    stall_threads: usize,
+    stall_period: Duration,
+    stall_duration: Duration,
+    /// The workers `0..low_prio_threads` run at the nice level `low_prio_nice`.
+    low_prio_threads: usize,
+    low_prio_nice: i32,
+    /// The recovery after the stall windows of the last run.
+    recovery: Mutex<Recovery>,
+    /// The number of the shards of `ShardedHashMap`.
+    shards: usize,
+    /// The number of the independent instances of the map, which the workers choose from at random
+    /// for each operation.
+    instances: usize,
+    /// The number of the stages of `Pipeline`.
+    stages: usize,
+    /// Whether the stages of `Pipeline` pass the values through stacks rather than queues.
+    pipeline_stack: bool,
+    /// The probability that a tower of `SkipList` grows by each level.
+    skiplist_p: f64,
+    /// The maximum height of the towers of `SkipList`.
+    skiplist_max_level: usize,
+    /// The maximum length of the intervals of `IntervalMap`.
+    interval_len: usize,
     sampling: bool,
     sampling_period: Duration,
 
     get_rate: usize,
     op_dist: WeightedIndex<i32>,
+    /// If not empty, the workers are divided into the groups, which replace `op_dist`.
+    groups: Vec<ThreadGroup>,
+    pinning: Option<Pinning>,
+    gc_policy: GcPolicy,
+    gc_threshold: usize,
+    /// Whether the retired objects are freed.
+    reclaim: bool,
+    range: usize,
     key_dist: Uniform<usize>,
+    key_size: KeySize,
+    /// The delay of the workers between two operations.
+    think_time: ThinkTime,
+    /// The shared library of the map of Ffi.
+    ffi_lib: Option<String>,
+    /// The seed of the random number generators of the workers and of the prefill.
+    seed: u64,
+    /// Nanoseconds that each comparison of two keys of a map takes at least.
+    slow_cmp: u64,
     prefill: usize,
     interval: u64,
     duration: Duration,
+    /// Run until each worker performs this many operations instead of for `duration`.
+    ops_per_thread: Option<u64>,
+    /// Replays the YCSB trace instead of generating the operations, until each worker has replayed
+    /// its share of the run phase.
+    ycsb: Option<Trace>,
+    ycsb_run: String,
+    preset: Option<Preset>,
+    /// The phases of `Preset::DeleteStorm`.
+    storm: Option<Storm>,
+    /// The bounds of the keys of `Preset::Monotonic`.
+    monotonic: Option<Monotonic>,
+    /// The fraction of the keys that are never removed, if any.
+    long_lived: Option<f64>,
+    /// The long-lived keys, which the prefill inserts first.
+    long_lived_keys: Vec<usize>,
+    /// The keys of the prefill loaded with `--prefill-file`, if any, in the order of their
+    /// sampling.
+    prefill_keys: Vec<usize>,
     ops_per_cs: OpsPerCs,
+    extend_read_sections: bool,
+    shield_cache: usize,
+    harris_stats: bool,
+    restart_stats: bool,
+    bonsai_stats: bool,
+    remove_phases: bool,
+    epoch_latency: bool,
+    /// Counts the hardware events of the workers (`perf_counters`).
+    perf: bool,
+    /// Checks the drops of the values of the maps (`audit`).
+    audit: bool,
+    /// Benchmarks the map as a set of keys without values.
+    set: bool,
+    /// The removes return the values by reference (`remove_get`) instead of moving them out.
+    remove_get: bool,
+    /// The gets run without a guard (`UnguardedMap`).
+    unguarded_reads: bool,
+    /// The updates move the keys between two maps (`TransferMap`).
+    transfers: bool,
+    /// The hash function of the hash maps.
+    hasher: HashFunction,
+    /// The bound of the size of HashMap, and how it evicts its entries.
+    capacity: Option<usize>,
+    eviction: Eviction,
+    /// The retries of the updates of the Natarajan-Mittal trees resume their seeks (`seek_stats`).
+    seek_reuse: bool,
+    /// The period after which each worker thread of a map is replaced by a new one.
+    thread_churn: Option<Duration>,
+    /// The number of successors that each scan, which replaces a get, walks.
+    scan: Option<usize>,
+    /// Whether the Harris lists mark the nodes they remove with a CAS instead of `fetch_or`.
+    cas_mark: bool,
+    /// The removes delete their entry only if it still holds the value they read (`remove_if`).
+    compare_and_delete: bool,
+    /// The last step of the back-off of ART and CuckooHashMap that spins instead of yielding.
+    backoff_spin: u32,
+
+    /// The outcomes of the map operations of the current run.
+    outcomes: Mutex<Outcomes>,
+
+    /// Publishes the progress of the workers for `report_progress`.
+    progress: Option<Progress>,
+    /// Whether `report_progress` draws the live terminal view instead of printing lines.
+    #[cfg(feature = "tui")]
+    tui: bool,
+
+    /// Records the size of the structure and the memory usage over time to this file.
+    time_series: Option<String>,
+    time_series_period: Duration,
+
+    history: Option<String>,
+    history_sample: usize,
+    history_capacity: usize,
+    /// Checks the histories for linearizability instead of benchmarking.
+    check: bool,
+    /// Whether the threads checksum the sequences of their operations and of the results.
+    checksum: bool,
+    /// The checksums of the threads of the last run, by id, the prefill's last.
+    checksums: Mutex<Vec<u64>>,
+
+    baseline: Option<Store>,
+    regression_threshold: f64,
 
     mem_sampler: MemSampler,
+    environment: Environment,
 }
 
-cfg_if! {
-    if #[cfg(all(not(feature = "sanitize"), target_os = "linux"))] {
-        extern crate jemalloc_ctl;
-        struct MemSampler {
-            epoch_mib: jemalloc_ctl::epoch_mib,
-            allocated_mib: jemalloc_ctl::stats::allocated_mib,
+impl Config {
+    /// Whether a worker that started at `start` and has performed `ops` operations continues.
+    #[inline]
+    fn running(&self, start: Instant, ops: u64) -> bool {
+        if interrupted() || failed() {
+            return false;
         }
-        impl MemSampler {
-            pub fn new() -> Self {
-                MemSampler {
-                    epoch_mib: jemalloc_ctl::epoch::mib().unwrap(),
-                    allocated_mib: jemalloc_ctl::stats::allocated::mib().unwrap(),
+        match self.ops_per_thread {
+            Some(n) => ops < n,
+            None => self.ycsb.is_some() || self.storm.is_some() || start.elapsed() < self.duration,
+        }
+    }
+
+    /// Whether the workers perform a fixed amount of work rather than run for `duration`.
+    fn fixed_work(&self) -> bool {
+        self.ops_per_thread.is_some() || self.ycsb.is_some() || self.storm.is_some()
+    }
+
+    /// Whether the auxiliary thread continues, given the number of finished workers.
+    fn aux_running(&self, start: Instant, finished: &AtomicUsize) -> bool {
+        if interrupted() || failed() {
+            false
+        } else if self.fixed_work() {
+            finished.load(Ordering::Acquire) < self.threads
+        } else {
+            start.elapsed() < self.duration
+        }
+    }
+
+    /// The next map operation of worker `tid`, which has performed `ops` operations, and the index
+    /// of its key. With a YCSB trace, the workers replay the run phase in a round-robin manner, and
+    /// `None` means that worker `tid` has replayed its share.
+    #[inline]
+    fn next_op<R: Rng>(&self, tid: usize, ops: u64, rng: &mut R) -> Option<(Op, usize)> {
+        let next = match &self.ycsb {
+            Some(trace) => trace
+                .run
+                .get(tid + ops as usize * self.threads)
+                .map(|&(op, k)| (Op::from(op), k)),
+            // The bomber's keys are out of the range of the readers.
+            None if self.garbage_bomber(tid) => {
+                let k = self.range + (ops / 2) as usize % self.range;
+                Some((if ops % 2 == 0 { Op::Insert } else { Op::Remove }, k))
+            }
+            None if self.storm.is_some() => self.storm.as_ref().unwrap().next_op(self, tid, ops),
+            None if self.monotonic.is_some() => {
+                let op = Op::OPS[self.op_dist(tid).sample(rng)];
+                Some(self.monotonic.as_ref().unwrap().next_op(op, rng))
+            }
+            None => {
+                let op = Op::OPS[self.op_dist(tid).sample(rng)];
+                let mut k = self.key_dist.sample(rng);
+                // The long-lived keys are only read.
+                while op != Op::Get && self.long_lived(k) {
+                    k = self.key_dist.sample(rng);
                 }
+                Some((op, k))
             }
-            pub fn sample(&self) -> usize {
-                self.epoch_mib.advance().unwrap();
-                self.allocated_mib.read().unwrap()
+        };
+        CURRENT_OP.with(|c| c.set(next.map(|(op, k)| (tid, op, k))));
+        if next.is_some() {
+            if let Some(limit) = self.group(tid).and_then(|g| g.limit.as_ref()) {
+                limit.acquire();
             }
         }
-    } else {
-        struct MemSampler {}
-        impl MemSampler {
-            pub fn new() -> Self {
-                println!("NOTE: Memory usage benchmark is supported only for linux.");
-                MemSampler {}
-            }
-            pub fn sample(&self) -> usize {
-                0
+        next
+    }
+
+    #[inline]
+    fn long_lived(&self, k: usize) -> bool {
+        self.long_lived
+            .map_or(false, |fraction| is_long_lived(fraction, k))
+    }
+
+    /// The index of the key of the `i`th insert of the prefill. All long-lived keys are inserted
+    /// first.
+    fn prefill_key<R: Rng>(&self, i: usize, rng: &mut R) -> usize {
+        if let Some(&k) = self.prefill_keys.get(i) {
+            return k;
+        }
+        if let Some(&k) = self.long_lived_keys.get(i) {
+            return k;
+        }
+        loop {
+            let k = self.key_dist.sample(rng);
+            if !self.long_lived(k) {
+                return k;
             }
         }
     }
-}
 
-fn main() {
-    let matches = App::new("pebr_benchmark")
-        .arg(
-            Arg::with_name("data structure")
-                .short("d")
-                .value_name("DS")
-                .possible_values(&DS::variants())
-                .required(true)
-                .case_insensitive(true)
-                .help("Data structure(s)"),
+    /// The key of index `k`.
+    #[inline]
+    fn map_key(&self, k: usize) -> String {
+        match &self.ycsb {
+            Some(trace) => trace.keys[k].clone(),
+            None => self.key_size.key(k),
+        }
+    }
+
+    /// Whether worker `tid` is the bomber of the garbage bomb, which inserts a key with a large
+    /// value and removes it right away, over and over. It is the last worker, and the others only
+    /// read.
+    #[inline]
+    fn garbage_bomber(&self, tid: usize) -> bool {
+        self.preset == Some(Preset::GarbageBomb) && tid == self.threads - 1
+    }
+
+    /// Removes `key` from `map` with `remove`, or with `remove_get` if `--remove-get` is set.
+    /// Returns whether `key` was found.
+    #[inline]
+    fn remove_ebr<K, M: ebr::ConcurrentMap<K, Value>>(
+        &self,
+        map: &M,
+        key: &K,
+        guard: &crossbeam_ebr::Guard,
+    ) -> bool {
+        if self.remove_get {
+            map.remove_get(key, guard).is_some()
+        } else {
+            map.remove(key, guard).is_some()
+        }
+    }
+
+    /// The value to insert with `key` by worker `tid`.
+    #[inline]
+    fn map_value<V: From<String>>(&self, tid: usize, key: &str) -> V {
+        V::from(if self.garbage_bomber(tid) {
+            format!("{:0>1$}", key, GARBAGE_BOMB_VALUE_SIZE)
+        } else {
+            key.to_string()
+        })
+    }
+
+    /// Identifies the configuration in the results store.
+    fn key(&self) -> String {
+        format!(
+            "ds={},mm={},threads={},range={},get_rate={},non_coop={},sampling_period={},\
+             ops_per_cs={},extend_read_sections={},key_size={},interval={},ops_per_thread={},\
+             shield_cache={},stall_threads={},stall_period={},stall_duration={},groups={},\
+             pin_threads={},gc_policy={},gc_threshold={},ycsb={},preset={},long_lived={},set={},\
+             prefill={},slow_cmp={},shards={},reclaim={},interval_len={},cas_mark={},\
+             think_time={},ffi_lib={},instances={},stages={},pipeline_stack={},skiplist_p={},\
+             skiplist_max_level={},remove_get={},unguarded_reads={},hasher={},transfers={},\
+             capacity={:?},eviction={},seek_reuse={},thread_churn={},low_prio_threads={},\
+             low_prio_nice={},compare_and_delete={},backoff_spin={}",
+            self.ds,
+            self.mm,
+            self.threads,
+            self.range,
+            self.get_rate,
+            self.non_coop,
+            self.sampling_period.as_millis(),
+            self.ops_per_cs,
+            self.extend_read_sections,
+            self.key_size,
+            self.interval,
+            self.ops_per_thread
+                .map_or("".to_string(), |n| n.to_string()),
+            self.shield_cache,
+            self.stall_threads,
+            self.stall_period.as_millis(),
+            self.stall_duration.as_millis(),
+            self.groups(),
+            self.pinning.is_some(),
+            self.gc_policy,
+            self.gc_threshold,
+            self.ycsb_run,
+            self.preset.map_or("".to_string(), |p| p.to_string()),
+            self.long_lived.map_or("".to_string(), |f| f.to_string()),
+            self.set,
+            self.prefill,
+            self.slow_cmp,
+            self.shards,
+            self.reclaim,
+            self.interval_len,
+            self.cas_mark,
+            self.think_time,
+            self.ffi_lib.as_ref().map_or("", |l| l),
+            self.instances,
+            self.stages,
+            self.pipeline_stack,
+            self.skiplist_p,
+            self.skiplist_max_level,
+            self.remove_get,
+            self.unguarded_reads,
+            self.hasher,
+            self.transfers,
+            self.capacity,
+            self.eviction,
+            self.seek_reuse,
+            self.thread_churn
+                .map_or("".to_string(), |p| p.as_millis().to_string()),
+            self.low_prio_threads,
+            self.low_prio_nice,
+            self.compare_and_delete,
+            self.backoff_spin,
         )
-        .arg(
-            Arg::with_name("memory manager")
-                .short("m")
-                .value_name("MM")
-                .possible_values(&MM::variants())
-                .required(true)
-                .case_insensitive(true)
-                .help("Memeory manager(s)"),
-        )
-        .arg(
-            Arg::with_name("threads")
-                .short("t")
-                .value_name("THREADS")
-                .takes_value(true)
-                .required(true)
-                .help("Numbers of threads to run."),
-        )
-        .arg(
-            Arg::with_name("non-coop")
-                .short("n")
-                .takes_value(true)
-                .help(
-                    "The degree of non-cooperation. \
-                     1: 1ms, 2: 10ms, 3: stall",
-                )
-                .possible_values(&["0", "1", "2", "3"])
-                .default_value("0"),
-        )
-        .arg(
-            Arg::with_name("get rate")
-                .short("g")
-                .takes_value(true)
-                .help(
-                    "The proportion of `get`(read) operations. \
-                     0: 0%, 1: 50%, 2: 90%",
-                )
-                .possible_values(&["0", "1", "2"])
-                .default_value("0"),
-        )
-        .arg(
-            Arg::with_name("range")
-                .short("r")
-                .value_name("RANGE")
-                .takes_value(true)
-                .help("Key range: [0..RANGE]")
-                .default_value("100000"),
-        )
-        .arg(
-            Arg::with_name("interval")
-                .short("i")
-                .value_name("INTERVAL")
-                .takes_value(true)
-                .help("Time interval in seconds to run the benchmark")
-                .default_value("10"),
-        )
-        .arg(
-            Arg::with_name("sampling period")
-                .short("s")
-                .value_name("MEM_SAMPLING_PERIOD")
-                .takes_value(true)
-                .help(
-                    "The period to query jemalloc stats.allocated (ms). 0 for no sampling. \
-                     Only supported on linux.",
-                )
-                .default_value("1"),
-        )
-        .arg(
-            Arg::with_name("ops per cs")
-                .short("c")
-                .value_name("OPS_PER_CS")
-                .takes_value(true)
-                .possible_values(&["1", "4"])
-                .help("Operations per each critical section")
-                .default_value("1"),
-        )
-        .arg(
-            Arg::with_name("output")
-                .short("o")
-                .value_name("OUTPUT")
-                .takes_value(true)
-                .help(
-                    "Output CSV filename. \
-                     Appends the data if the file already exists.\n\
-                     [default: results/<DS>.csv]",
-                ),
-        )
-        .get_matches();
+    }
 
-    let (config, mut output) = setup(matches);
-    match config.ops_per_cs {
-        OpsPerCs::One => bench::<U1>(&config, &mut output),
-        OpsPerCs::Four => bench::<U4>(&config, &mut output),
+    /// The thread group of worker `tid`. The workers are assigned to the groups in order.
+    fn group(&self, tid: usize) -> Option<&ThreadGroup> {
+        let mut end = 0;
+        for group in &self.groups {
+            end += group.threads;
+            if tid < end {
+                return Some(group);
+            }
+        }
+        None
     }
-}
 
-fn setup(m: ArgMatches) -> (Config, Writer<File>) {
-    let ds = value_t!(m, "data structure", DS).unwrap();
-    let mm = value_t!(m, "memory manager", MM).unwrap();
-    let threads = value_t!(m, "threads", usize).unwrap();
-    let non_coop = value_t!(m, "non-coop", usize).unwrap();
-    let get_rate = value_t!(m, "get rate", usize).unwrap();
-    let range = value_t!(m, "range", usize).unwrap();
-    let prefill = range / 2;
-    let key_dist = Uniform::from(0..range);
-    let interval = value_t!(m, "interval", u64).unwrap();
-    let sampling_period = value_t!(m, "sampling period", u64).unwrap();
-    let sampling = sampling_period > 0 && cfg!(all(not(feature = "sanitize"), target_os = "linux"));
-    let ops_per_cs = match value_t!(m, "ops per cs", usize).unwrap() {
-        1 => OpsPerCs::One,
-        4 => OpsPerCs::Four,
-        _ => panic!("ops_per_cs should be one or four"),
-    };
-    let duration = Duration::from_secs(interval);
+    /// The distribution of the operations of worker `tid`.
+    fn op_dist(&self, tid: usize) -> &WeightedIndex<i32> {
+        self.group(tid).map_or(&self.op_dist, |g| &g.op_dist)
+    }
 
-    let op_weights = match get_rate {
-        0 => &[0, 1, 1],
-        1 => &[2, 1, 1],
-        _ => &[18, 1, 1],
-    };
-    let op_dist = WeightedIndex::new(op_weights).unwrap();
+    /// Accounts the operations of worker `tid` to its group.
+    fn record_group(&self, tid: usize, ops: u64, elapsed: Duration) {
+        if let Some(group) = self.group(tid) {
+            let mut stats = group.stats.lock().unwrap();
+            stats.0 += ops;
+            stats.1 = max(stats.1, elapsed);
+        }
+    }
 
-    let output_name = &m
-        .value_of("output")
-        .map_or(format!("results/{}.csv", ds), |o| o.to_string());
-    create_dir_all("results").unwrap();
-    let output = match OpenOptions::new()
-        .read(true)
-        .write(true)
-        .append(true)
-        .open(output_name)
-    {
-        Ok(f) => csv::Writer::from_writer(f),
-        Err(_) => {
-            let f = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .open(output_name)
-                .unwrap();
-            let mut output = csv::Writer::from_writer(f);
-            // NOTE: `write_record` on `bench`
-            output
-                .write_record(&[
-                    // "timestamp",
-                    "ds",
-                    "mm",
-                    "threads",
-                    "sampling_period",
-                    "non_coop",
-                    "get_rate",
-                    "ops_per_cs",
-                    "throughput",
-                    "peak_mem",
-                    "avg_mem",
-                ])
-                .unwrap();
-            output.flush().unwrap();
-            output
+    /// The random number generator of worker `tid`, or of the prefill if `tid` is `threads`.
+    fn rng(&self, tid: usize) -> StdRng {
+        StdRng::seed_from_u64(self.seed.wrapping_add(tid as u64))
+    }
+
+    /// Pins worker `tid` to its CPU if pinning is enabled, and lowers its priority if it is one of
+    /// the low-priority workers.
+    fn pin(&self, tid: usize) {
+        if let Some(pinning) = &self.pinning {
+            set_affinity(pinning.cpu(tid).id);
         }
-    };
-    let mem_sampler = MemSampler::new();
-    let config = Config {
-        ds,
-        mm,
-        threads,
+        if tid < self.low_prio_threads {
+            if let Err(e) = set_nice(self.low_prio_nice) {
+                eprintln!(
+                    "cannot set the nice level of worker {} to {}: {}",
+                    tid, self.low_prio_nice, e
+                );
+                process::exit(2);
+            }
+        }
+    }
 
-        aux_thread: if sampling || non_coop > 0 { 1 } else { 0 },
-        aux_thread_period: Duration::from_millis(1),
-        non_coop,
-        non_coop_period: match non_coop {
-            1 => Duration::from_millis(1),
-            2 => Duration::from_millis(10),
-            // No repin if -n0 or -n3
-            _ => Duration::from_secs(interval),
-        },
-        sampling,
-        sampling_period: Duration::from_millis(sampling_period),
+    /// Accounts the operations of worker `tid` and the retries and retires of the current thread
+    /// to the CPU of the worker.
+    fn record_cpu(&self, tid: usize, ops: u64, elapsed: Duration) {
+        if let Some(pinning) = &self.pinning {
+            let (retries, retires) = op_stats::take_local();
+            let stats = CpuStats {
+                threads: 1,
+                ops,
+                elapsed,
+                retries,
+                retires,
+            };
+            pinning
+                .workers
+                .lock()
+                .unwrap()
+                .push((pinning.cpu(tid), stats));
+        }
+    }
 
-        get_rate,
-        op_dist,
-        key_dist,
-        prefill,
-        interval,
-        duration,
-        ops_per_cs,
+    /// Publishes the progress of worker `tid`, which has performed `ops` operations, every
+    /// `PROGRESS_OPS` operations.
+    #[inline]
+    fn publish_progress(&self, tid: usize, ops: u64) {
+        if let Some(progress) = &self.progress {
+            if ops % PROGRESS_OPS == 0 {
+                let retires = op_stats::retires();
+                progress.publish(tid, Snapshot { ops, retires });
+            }
+        }
+    }
 
-        mem_sampler,
-    };
-    (config, output)
+    fn groups(&self) -> String {
+        self.groups
+            .iter()
+            .map(|g| g.to_string())
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// The instance of the map that a worker operates on next.
+    #[inline]
+    fn instance<R: Rng>(&self, rng: &mut R) -> usize {
+        if self.instances == 1 {
+            0
+        } else {
+            rng.gen_range(0, self.instances)
+        }
+    }
+
+    /// The number of threads that start together at the barrier.
+    fn parties(&self) -> usize {
+        self.threads
+            + self.aux_thread
+            + self.progress.is_some() as usize
+            + self.time_series.is_some() as usize
+    }
+
+    /// The throughput of `ops` operations, where the slowest worker took `elapsed`. An interrupted
+    /// run is measured against `elapsed` rather than the interval.
+    fn throughput(&self, ops: u64, elapsed: Duration) -> u64 {
+        if self.fixed_work() || interrupted() {
+            (ops as f64 / elapsed.as_secs_f64()) as u64
+        } else {
+            ops / self.interval
+        }
+    }
 }
 
-fn bench<N: Unsigned>(config: &Config, output: &mut Writer<File>) {
-    println!(
-        "{}: {}, {} threads, n{}, c{}, g{}",
-        config.ds, config.mm, config.threads, config.non_coop, config.ops_per_cs, config.get_rate
-    );
-    let (ops_per_sec, peak_mem, avg_mem) = match config.mm {
-        MM::NR => match config.ds {
-            DS::HList => {
-                bench_map_nr::<ebr::HList<String, String>>(config, PrefillStrategy::Decreasing)
-            }
-            DS::HMList => {
-                bench_map_nr::<ebr::HMList<String, String>>(config, PrefillStrategy::Decreasing)
-            }
-            DS::HHSList => {
-                bench_map_nr::<ebr::HHSList<String, String>>(config, PrefillStrategy::Decreasing)
-            }
-            DS::HashMap => {
-                bench_map_nr::<ebr::HashMap<String, String>>(config, PrefillStrategy::Decreasing)
-            }
-            DS::NMTree => {
-                bench_map_nr::<ebr::NMTreeMap<String, String>>(config, PrefillStrategy::Random)
-            }
-            DS::BonsaiTree => {
-                bench_map_nr::<ebr::BonsaiTreeMap<String, String>>(config, PrefillStrategy::Random)
+cfg_if! {
+    if #[cfg(all(not(feature = "sanitize"), target_os = "linux"))] {
+        extern crate jemalloc_ctl;
+        struct MemSampler {
+            epoch_mib: jemalloc_ctl::epoch_mib,
+            allocated_mib: jemalloc_ctl::stats::allocated_mib,
+        }
+        impl MemSampler {
+            pub fn new() -> Self {
+                MemSampler {
+                    epoch_mib: jemalloc_ctl::epoch::mib().unwrap(),
+                    allocated_mib: jemalloc_ctl::stats::allocated::mib().unwrap(),
+                }
             }
-        },
-        MM::EBR => match config.ds {
-            DS::HList => {
-                bench_map_ebr::<ebr::HList<String, String>, N>(config, PrefillStrategy::Decreasing)
+            pub fn sample(&self) -> usize {
+                self.epoch_mib.advance().unwrap();
+                self.allocated_mib.read().unwrap()
             }
-            DS::HMList => {
-                bench_map_ebr::<ebr::HMList<String, String>, N>(config, PrefillStrategy::Decreasing)
+        }
+    } else {
+        struct MemSampler {}
+        impl MemSampler {
+            pub fn new() -> Self {
+                println!("NOTE: Memory usage benchmark is supported only for linux.");
+                MemSampler {}
             }
-            DS::HHSList => bench_map_ebr::<ebr::HHSList<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::HashMap => bench_map_ebr::<ebr::HashMap<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::NMTree => {
-                bench_map_ebr::<ebr::NMTreeMap<String, String>, N>(config, PrefillStrategy::Random)
+            pub fn sample(&self) -> usize {
+                0
             }
-            DS::BonsaiTree => bench_map_ebr::<ebr::BonsaiTreeMap<String, String>, N>(
-                config,
-                PrefillStrategy::Random,
-            ),
-        },
-        MM::PEBR => match config.ds {
-            DS::HList => bench_map_pebr::<pebr::HList<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::HMList => bench_map_pebr::<pebr::HMList<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::HHSList => bench_map_pebr::<pebr::HHSList<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::HashMap => bench_map_pebr::<pebr::HashMap<String, String>, N>(
-                config,
-                PrefillStrategy::Decreasing,
-            ),
-            DS::NMTree => bench_map_pebr::<pebr::NMTreeMap<String, String>, N>(
-                config,
-                PrefillStrategy::Random,
-            ),
-            DS::BonsaiTree => bench_map_pebr::<pebr::BonsaiTreeMap<String, String>, N>(
-                config,
-                PrefillStrategy::Random,
-            ),
-        },
-    };
-    output
-        .write_record(&[
-            // chrono::Local::now().to_rfc3339(),
-            config.ds.to_string(),
-            config.mm.to_string(),
-            config.threads.to_string(),
-            config.sampling_period.as_millis().to_string(),
-            config.non_coop.to_string(),
-            config.get_rate.to_string(),
-            config.ops_per_cs.to_string(),
-            ops_per_sec.to_string(),
-            peak_mem.to_string(),
-            avg_mem.to_string(),
-        ])
-        .unwrap();
-    output.flush().unwrap();
-    println!(
-        "ops/s: {}, peak mem: {}, avg_mem: {}",
-        ops_per_sec, peak_mem, avg_mem
-    );
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum PrefillStrategy {
-    Random,
-    Decreasing,
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        /// The resident set size of the process in bytes.
+        fn rss() -> Option<usize> {
+            let statm = fs::read_to_string("/proc/self/statm").ok()?;
+            let pages = statm.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+            Some(pages * unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize)
+        }
+    } else {
+        fn rss() -> Option<usize> {
+            None
+        }
+    }
 }
 
-impl PrefillStrategy {
-    fn prefill_ebr<M: ebr::ConcurrentMap<String, String> + Send + Sync>(
-        self,
-        config: &Config,
-        map: &M,
-    ) {
-        let guard = unsafe { crossbeam_ebr::unprotected() };
-        let mut rng = rand::thread_rng();
-        match self {
-            PrefillStrategy::Random => {
-                for _ in 0..config.prefill {
-                    let key = config.key_dist.sample(&mut rng).to_string();
-                    let value = key.clone();
-                    map.insert(key, value, guard);
-                }
-            }
-            PrefillStrategy::Decreasing => {
-                let mut keys = Vec::with_capacity(config.prefill);
-                for _ in 0..config.prefill {
-                    keys.push(config.key_dist.sample(&mut rng));
-                }
-                keys.sort_by(|a, b| b.cmp(a));
-                for k in keys.drain(..) {
-                    let key = k.to_string();
-                    let value = key.clone();
-                    map.insert(key, value, guard);
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        extern crate libc;
+
+        /// Stops a rotating subset of the workers at arbitrary points of their execution for
+        /// `stall_duration` every `stall_period`, mimicking preemption by the OS. A stopped worker
+        /// waits in the handler of `SIGUSR1` until it receives `SIGUSR2`.
+        struct Staller {
+            enabled: bool,
+            workers: Mutex<Vec<libc::pthread_t>>,
+            /// Set when the controller has resumed all workers for the last time.
+            done: AtomicBool,
+            summary: Mutex<Option<String>>,
+        }
+
+        impl Staller {
+            fn new(config: &Config) -> Self {
+                Staller {
+                    enabled: config.stall_threads > 0,
+                    workers: Mutex::new(Vec::new()),
+                    done: AtomicBool::new(false),
+                    summary: Mutex::new(None),
                 }
             }
-        }
-        print!("prefilled... ");
-        stdout().flush().unwrap();
-    }
 
-    fn prefill_pebr<M: pebr::ConcurrentMap<String, String> + Send + Sync>(
-        self,
-        config: &Config,
-        map: &M,
-    ) {
-        let guard = unsafe { crossbeam_pebr::unprotected() };
-        let mut handle = M::handle(guard);
-        let mut rng = rand::thread_rng();
-        match self {
-            PrefillStrategy::Random => {
-                for _ in 0..config.prefill {
-                    let key = config.key_dist.sample(&mut rng).to_string();
-                    let value = key.clone();
-                    map.insert(&mut handle, key, value, guard);
+            /// Registers the current thread as a worker.
+            fn register(&self) {
+                if !self.enabled {
+                    return;
                 }
+                self.workers
+                    .lock()
+                    .unwrap()
+                    .push(unsafe { libc::pthread_self() });
             }
-            PrefillStrategy::Decreasing => {
-                let mut keys = Vec::with_capacity(config.prefill);
-                for _ in 0..config.prefill {
-                    keys.push(config.key_dist.sample(&mut rng));
+
+            /// Waits for the controller to finish, so that it doesn't signal an exited worker.
+            fn leave(&self) {
+                if !self.enabled {
+                    return;
                 }
-                keys.sort_by(|a, b| b.cmp(a));
-                for k in keys.drain(..) {
-                    let key = k.to_string();
-                    let value = key.clone();
-                    map.insert(&mut handle, key, value, guard);
+                while !self.done.load(Ordering::Acquire) {
+                    std::thread::sleep(Duration::from_millis(1));
                 }
             }
-        }
-        print!("prefilled... ");
-        stdout().flush().unwrap();
-    }
-}
 
-fn bench_map_nr<M: ebr::ConcurrentMap<String, String> + Send + Sync>(
-    config: &Config,
-    strategy: PrefillStrategy,
-) -> (u64, usize, usize) {
-    let map = &M::new();
-    strategy.prefill_ebr(config, map);
-
-    let barrier = &Arc::new(Barrier::new(config.threads + config.aux_thread));
-    let (ops_sender, ops_receiver) = mpsc::channel();
-    let (mem_sender, mem_receiver) = mpsc::channel();
-
-    scope(|s| {
-        if config.aux_thread > 0 {
-            let mem_sender = mem_sender.clone();
-            s.spawn(move |_| {
-                assert!(config.sampling);
-                let mut samples = 0usize;
-                let mut acc = 0usize;
-                let mut peak = 0usize;
-                barrier.clone().wait();
-
-                let start = Instant::now();
-                let mut next_sampling = start + config.sampling_period;
-                while start.elapsed() < config.duration {
-                    let now = Instant::now();
-                    if now > next_sampling {
-                        let allocated = config.mem_sampler.sample();
-                        samples += 1;
-                        acc += allocated;
-                        peak = max(peak, allocated);
-                        next_sampling = now + config.sampling_period;
-                    }
-                    std::thread::sleep(config.aux_thread_period);
+            /// The controller. Measures the growth of the memory usage while some workers are
+            /// stopped and while all of them are running, and the time the memory takes to return
+            /// to its level before each window once the workers are resumed.
+            fn run(&self, config: &Config, finished: &AtomicUsize) {
+                const RECOVERY_POLL: Duration = Duration::from_millis(1);
+                static INSTALL: Once = Once::new();
+                INSTALL.call_once(|| unsafe {
+                    Self::install(libc::SIGUSR1, Self::stop, &[libc::SIGUSR2]);
+                    Self::install(libc::SIGUSR2, Self::resume, &[]);
+                });
+                while self.workers.lock().unwrap().len() < config.threads {
+                    std::thread::sleep(Duration::from_millis(1));
                 }
-                mem_sender.send((peak, acc / samples)).unwrap();
-            });
-        } else {
-            mem_sender.send((0, 0)).unwrap();
-        }
+                let workers = self.workers.lock().unwrap().clone();
+                let stalled = min(config.stall_threads, workers.len());
 
-        for _ in 0..config.threads {
-            let ops_sender = ops_sender.clone();
-            s.spawn(move |_| {
-                let mut ops: u64 = 0;
-                let mut rng = rand::thread_rng();
-                barrier.clone().wait();
                 let start = Instant::now();
-
-                while start.elapsed() < config.duration {
-                    let key = config.key_dist.sample(&mut rng).to_string();
-                    match Op::OPS[config.op_dist.sample(&mut rng)] {
-                        Op::Get => {
-                            map.get(&key, unsafe { crossbeam_ebr::leaking() });
-                        }
-                        Op::Insert => {
-                            let value = key.clone();
-                            map.insert(key, value, unsafe { crossbeam_ebr::leaking() });
-                        }
-                        Op::Remove => {
-                            map.remove(&key, unsafe { crossbeam_ebr::leaking() });
+                let mut windows = 0;
+                let mut stopped = (0i64, Duration::default());
+                let mut running = (0i64, Duration::default());
+                let mut last = (Instant::now(), config.mem_sampler.sample() as i64);
+                let mut recovery = Recovery::default();
+                // The memory before the last window, while it has not returned to it.
+                let mut baseline = None;
+                loop {
+                    let next = last.0 + (config.stall_period - config.stall_duration);
+                    if let Some(baseline) = baseline.take() {
+                        loop {
+                            if config.mem_sampler.sample() as i64 <= baseline {
+                                recovery.times.push(last.0.elapsed());
+                                break;
+                            }
+                            if Instant::now() >= next || !config.aux_running(start, finished) {
+                                recovery.unrecovered += 1;
+                                break;
+                            }
+                            std::thread::sleep(RECOVERY_POLL);
                         }
                     }
-                    ops += 1;
-                }
-
-                ops_sender.send(ops).unwrap();
-            });
-        }
-    })
-    .unwrap();
-    println!("end");
-
-    let mut ops = 0;
-    for _ in 0..config.threads {
-        let local_ops = ops_receiver.recv().unwrap();
-        ops += local_ops;
-    }
-    let ops_per_sec = ops / config.interval;
-    let (peak_mem, avg_mem) = mem_receiver.recv().unwrap();
-    (ops_per_sec, peak_mem, avg_mem)
-}
-
-fn bench_map_ebr<M: ebr::ConcurrentMap<String, String> + Send + Sync, N: Unsigned>(
-    config: &Config,
-    strategy: PrefillStrategy,
-) -> (u64, usize, usize) {
-    let map = &M::new();
-    strategy.prefill_ebr(config, map);
-
-    let collector = &crossbeam_ebr::Collector::new();
-
-    let barrier = &Arc::new(Barrier::new(config.threads + config.aux_thread));
-    let (ops_sender, ops_receiver) = mpsc::channel();
-    let (mem_sender, mem_receiver) = mpsc::channel();
-
-    scope(|s| {
-        // sampling & interference thread
-        if config.aux_thread > 0 {
-            let mem_sender = mem_sender.clone();
-            s.spawn(move |_| {
-                let mut samples = 0usize;
-                let mut acc = 0usize;
-                let mut peak = 0usize;
-                let handle = collector.register();
-                barrier.clone().wait();
+                    if let Some(rest) = next.checked_duration_since(Instant::now()) {
+                        std::thread::sleep(rest);
+                    }
+                    if !config.aux_running(start, finished) {
+                        break;
+                    }
+                    let before = (Instant::now(), config.mem_sampler.sample() as i64);
+                    running.0 += before.1 - last.1;
+                    running.1 += before.0 - last.0;
 
-                let start = Instant::now();
-                // Immediately drop if no non-coop else keep it and repin periodically.
-                let mut guard = ManuallyDrop::new(handle.pin());
-                if config.non_coop == 0 {
-                    unsafe { ManuallyDrop::drop(&mut guard) };
-                }
-                let mut next_sampling = start + config.sampling_period;
-                let mut next_repin = start + config.non_coop_period;
-                while start.elapsed() < config.duration {
-                    let now = Instant::now();
-                    if now > next_sampling {
-                        let allocated = config.mem_sampler.sample();
-                        samples += 1;
-                        acc += allocated;
-                        peak = max(peak, allocated);
-                        next_sampling = now + config.sampling_period;
+                    let targets = (0..stalled)
+                        .map(|i| workers[(windows * stalled + i) % workers.len()])
+                        .collect::<Vec<_>>();
+                    for &t in &targets {
+                        unsafe { libc::pthread_kill(t, libc::SIGUSR1) };
                     }
-                    if now > next_repin {
-                        (*guard).repin();
-                        next_repin = now + config.non_coop_period;
+                    std::thread::sleep(config.stall_duration);
+                    for &t in &targets {
+                        unsafe { libc::pthread_kill(t, libc::SIGUSR2) };
                     }
-                    std::thread::sleep(config.aux_thread_period);
+                    // Sample after resuming, as a stopped worker may hold a lock of the allocator.
+                    last = (Instant::now(), config.mem_sampler.sample() as i64);
+                    stopped.0 += last.1 - before.1;
+                    stopped.1 += last.0 - before.0;
+                    baseline = Some(before.1);
+                    windows += 1;
                 }
+                self.done.store(true, Ordering::Release);
 
-                if config.non_coop > 0 {
-                    unsafe { ManuallyDrop::drop(&mut guard) };
-                }
+                let rate = |(growth, time): (i64, Duration)| {
+                    growth as f64 / time.as_secs_f64().max(1e-9)
+                };
+                let max = recovery.times.iter().max().cloned().unwrap_or_default();
+                *self.summary.lock().unwrap() = Some(format!(
+                    "stall: {} windows stopping {} workers, memory growth {:.0} B/s while \
+                     stopped, {:.0} B/s while running, recovery time {} on average, {:.1} ms at \
+                     most, {} windows unrecovered",
+                    windows,
+                    stalled,
+                    rate(stopped),
+                    rate(running),
+                    recovery
+                        .mean_ms()
+                        .map_or("-".to_string(), |ms| format!("{:.1} ms", ms)),
+                    max.as_secs_f64() * 1000.0,
+                    recovery.unrecovered
+                ));
+                *config.recovery.lock().unwrap() = recovery;
+            }
 
-                if config.sampling {
-                    mem_sender.send((peak, acc / samples)).unwrap();
-                } else {
-                    mem_sender.send((0, 0)).unwrap();
+            fn report(&self) {
+                if let Some(summary) = self.summary.lock().unwrap().take() {
+                    println!("{}", summary);
                 }
-            });
-        } else {
-            mem_sender.send((0, 0)).unwrap();
-        }
+            }
 
-        for _ in 0..config.threads {
-            let ops_sender = ops_sender.clone();
-            s.spawn(move |_| {
-                let mut ops: u64 = 0;
-                let mut rng = rand::thread_rng();
-                let handle = collector.register();
-                barrier.clone().wait();
-                let start = Instant::now();
+            unsafe fn install(
+                signal: libc::c_int,
+                handler: extern "C" fn(libc::c_int),
+                blocked: &[libc::c_int],
+            ) {
+                let mut action: libc::sigaction = mem::zeroed();
+                action.sa_sigaction = handler as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                for &b in blocked {
+                    libc::sigaddset(&mut action.sa_mask, b);
+                }
+                assert_eq!(libc::sigaction(signal, &action, ptr::null_mut()), 0);
+            }
 
-                let mut guard = handle.pin();
-                while start.elapsed() < config.duration {
-                    let key = config.key_dist.sample(&mut rng).to_string();
-                    match Op::OPS[config.op_dist.sample(&mut rng)] {
-                        Op::Get => {
-                            map.get(&key, &guard);
-                        }
-                        Op::Insert => {
-                            let value = key.clone();
-                            map.insert(key, value, &guard);
-                        }
-                        Op::Remove => {
-                            map.remove(&key, &guard);
-                        }
-                    }
-                    ops += 1;
-                    if ops % N::to_u64() == 0 {
-                        drop(guard);
-                        guard = handle.pin();
-                    }
+            extern "C" fn stop(_: libc::c_int) {
+                // `SIGUSR2` is blocked in the handler, so it stays pending even if it is sent
+                // before `sigsuspend`.
+                unsafe {
+                    let mut mask = mem::zeroed();
+                    libc::sigfillset(&mut mask);
+                    libc::sigdelset(&mut mask, libc::SIGUSR2);
+                    libc::sigsuspend(&mask);
                 }
+            }
 
-                ops_sender.send(ops).unwrap();
-            });
+            extern "C" fn resume(_: libc::c_int) {}
         }
-    })
-    .unwrap();
-    println!("end");
-
-    let mut ops = 0;
-    for _ in 0..config.threads {
-        let local_ops = ops_receiver.recv().unwrap();
-        ops += local_ops;
-    }
-    let ops_per_sec = ops / config.interval;
-    let (peak_mem, avg_mem) = mem_receiver.recv().unwrap();
-    (ops_per_sec, peak_mem, avg_mem)
-}
-
-fn bench_map_pebr<M: pebr::ConcurrentMap<String, String> + Send + Sync, N: Unsigned>(
-    config: &Config,
-    strategy: PrefillStrategy,
-) -> (u64, usize, usize) {
-    let map = &M::new();
-    strategy.prefill_pebr(config, map);
-
-    let collector = &crossbeam_pebr::Collector::new();
-
-    let barrier = &Arc::new(Barrier::new(config.threads + config.aux_thread));
-    let (ops_sender, ops_receiver) = mpsc::channel();
-    let (mem_sender, mem_receiver) = mpsc::channel();
-
-    scope(|s| {
-        // sampling & interference thread
-        if config.aux_thread > 0 {
-            let mem_sender = mem_sender.clone();
-            s.spawn(move |_| {
-                let mut samples = 0usize;
-                let mut acc = 0usize;
-                let mut peak = 0usize;
-                let handle = collector.register();
-                barrier.clone().wait();
+    } else {
+        struct Staller {}
 
-                let start = Instant::now();
-                // Immediately drop if no non-coop else keep it and repin periodically.
-                let mut guard = ManuallyDrop::new(handle.pin());
-                if config.non_coop == 0 {
-                    unsafe { ManuallyDrop::drop(&mut guard) };
-                }
-                let mut next_sampling = start + config.sampling_period;
-                let mut next_repin = start + config.non_coop_period;
-                while start.elapsed() < config.duration {
-                    let now = Instant::now();
-                    if now > next_sampling {
-                        let allocated = config.mem_sampler.sample();
-                        samples += 1;
-                        acc += allocated;
-                        peak = max(peak, allocated);
-                        next_sampling = now + config.sampling_period;
-                    }
-                    if now > next_repin {
-                        (*guard).repin();
-                        next_repin = now + config.non_coop_period;
-                    }
-                    std::thread::sleep(config.aux_thread_period);
+        impl Staller {
+            fn new(config: &Config) -> Self {
+                if config.stall_threads > 0 {
+                    println!("NOTE: Stall injection is supported only for linux.");
                 }
+                Staller {}
+            }
 
-                if config.non_coop > 0 {
-                    unsafe { ManuallyDrop::drop(&mut guard) };
-                }
+            fn register(&self) {}
 
-                if config.sampling {
-                    mem_sender.send((peak, acc / samples)).unwrap();
-                } else {
-                    mem_sender.send((0, 0)).unwrap();
-                }
-            });
-        } else {
-            mem_sender.send((0, 0)).unwrap();
+            fn leave(&self) {}
+
+            fn run(&self, _config: &Config, _finished: &AtomicUsize) {}
+
+            fn report(&self) {}
         }
+    }
+}
 
-        for _ in 0..config.threads {
-            let ops_sender = ops_sender.clone();
-            s.spawn(move |_| {
-                let mut ops: u64 = 0;
-                let mut rng = rand::thread_rng();
-                let handle = collector.register();
-                let mut map_handle = M::handle(&handle.pin());
-                barrier.clone().wait();
-                let start = Instant::now();
+/// Set by the first SIGINT or SIGTERM. The workers stop as if the run were over, so that the results
+/// of the completed part of the run are still written.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
-                let mut guard = handle.pin();
-                while start.elapsed() < config.duration {
-                    let key = config.key_dist.sample(&mut rng).to_string();
-                    match Op::OPS[config.op_dist.sample(&mut rng)] {
-                        Op::Get => {
-                            map.get(&mut map_handle, &key, &mut guard);
-                        }
-                        Op::Insert => {
-                            let value = key.clone();
-                            map.insert(&mut map_handle, key, value, &mut guard);
-                        }
-                        Op::Remove => {
-                            map.remove(&mut map_handle, &key, &mut guard);
-                        }
-                    }
-                    ops += 1;
-                    if ops % N::to_u64() == 0 {
-                        M::clear(&mut map_handle);
-                        guard.repin();
-                    }
-                }
+#[inline]
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
 
-                ops_sender.send(ops).unwrap();
-            });
-        }
-    })
-    .unwrap();
-    println!("end");
+/// The description of the first panic of the current run, set by the panic hook. The workers stop
+/// as if the run were over, and the run is recorded as failed.
+static FAILURE: AtomicPtr<String> = AtomicPtr::new(ptr::null_mut());
+
+/// Set if a run failed.
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The index of the current worker, its operation and the index of its key, described by the
+    /// panic hook.
+    static CURRENT_OP: Cell<Option<(usize, Op, usize)>> = Cell::new(None);
+}
+
+#[inline]
+fn failed() -> bool {
+    !FAILURE.load(Ordering::Relaxed).is_null()
+}
+
+/// Takes the description of the panic of the current run, if any.
+fn take_failure() -> Option<String> {
+    let failure = FAILURE.swap(ptr::null_mut(), Ordering::AcqRel);
+    if failure.is_null() {
+        None
+    } else {
+        Some(*unsafe { Box::from_raw(failure) })
+    }
+}
+
+/// Installs a panic hook that records the first panic of each run with the operation and the key
+/// of the panicking worker, and prints it with the default hook. The later panics, such as the
+/// ones of the threads that join the panicked worker, are not printed.
+fn install_panic_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(s) => s.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(s) => s.clone(),
+                None => "Box<Any>".to_string(),
+            },
+        };
+        let mut failure = format!(
+            "thread '{}' panicked at '{}'",
+            thread::current().name().unwrap_or("<unnamed>"),
+            message
+        );
+        if let Some(location) = info.location() {
+            failure += &format!(", {}:{}", location.file(), location.line());
+        }
+        if let Some((tid, op, k)) = CURRENT_OP.with(|c| c.get()) {
+            failure += &format!(" in worker {} during {:?} of key {}", tid, op, k);
+        }
+        let failure = Box::into_raw(Box::new(failure));
+        let first = FAILURE
+            .compare_exchange(
+                ptr::null_mut(),
+                failure,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+        if first {
+            default(info);
+        } else {
+            drop(unsafe { Box::from_raw(failure) });
+        }
+    }));
+}
+
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        /// Installs the handler of SIGINT and SIGTERM. The second signal exits right away.
+        fn install_interrupt_handler() {
+            extern "C" fn interrupt(signal: libc::c_int) {
+                if INTERRUPTED.swap(true, Ordering::Relaxed) {
+                    unsafe { libc::_exit(128 + signal) };
+                }
+            }
+            unsafe {
+                Staller::install(libc::SIGINT, interrupt, &[]);
+                Staller::install(libc::SIGTERM, interrupt, &[]);
+            }
+        }
+    } else {
+        fn install_interrupt_handler() {}
+    }
+}
+
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        /// The CPUs that the process may run on, with their topology from sysfs.
+        fn available_cpus() -> Vec<Cpu> {
+            let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+            let size = mem::size_of::<libc::cpu_set_t>();
+            assert_eq!(unsafe { libc::sched_getaffinity(0, size, &mut set) }, 0);
+            let topology = |id: usize, name: &str| {
+                let path = format!("/sys/devices/system/cpu/cpu{}/topology/{}", id, name);
+                fs::read_to_string(path)
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(0)
+            };
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&id| unsafe { libc::CPU_ISSET(id, &set) })
+                .map(|id| Cpu {
+                    id,
+                    socket: topology(id, "physical_package_id"),
+                    core: topology(id, "core_id"),
+                })
+                .collect()
+        }
+
+        /// Pins the current thread to CPU `id`.
+        fn set_affinity(id: usize) {
+            unsafe {
+                let mut set: libc::cpu_set_t = mem::zeroed();
+                libc::CPU_SET(id, &mut set);
+                let size = mem::size_of::<libc::cpu_set_t>();
+                assert_eq!(libc::sched_setaffinity(0, size, &set), 0);
+            }
+        }
+
+        /// Sets the nice level of the current thread, which Linux keeps per thread.
+        fn set_nice(nice: i32) -> io::Result<()> {
+            let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, tid, nice) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    } else {
+        fn available_cpus() -> Vec<Cpu> {
+            println!("NOTE: Pinning is supported only for linux.");
+            Vec::new()
+        }
+
+        fn set_affinity(_id: usize) {}
+
+        fn set_nice(_nice: i32) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn main() {
+    let matches = App::new("pebr_benchmark")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .args(&workload_args())
+        .arg(
+            Arg::with_name("memory manager")
+                .short("m")
+                .value_name("MM")
+                .possible_values(&MM::variants())
+                .required(true)
+                .case_insensitive(true)
+                .help("Memeory manager(s)"),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about(
+                    "Runs the same configuration on two memory managers \
+                     and reports the difference of each metric",
+                )
+                .args(&workload_args())
+                .arg(
+                    Arg::with_name("memory managers")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .min_values(2)
+                        .max_values(2)
+                        .case_insensitive(true)
+                        .help("The two memory managers to compare"),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .short("R")
+                        .value_name("RUNS")
+                        .takes_value(true)
+                        .help("Number of runs for each memory manager")
+                        .default_value("3"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about(
+                    "Runs short workloads and checks that their histories are linearizable. \
+                     Use a small key range (e.g. -r 16) for contention.",
+                )
+                .args(&workload_args())
+                .arg(
+                    Arg::with_name("memory manager")
+                        .short("m")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .case_insensitive(true)
+                        .help("Memeory manager"),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .short("R")
+                        .value_name("RUNS")
+                        .takes_value(true)
+                        .help("Number of histories to check")
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("soak")
+                .about(
+                    "Runs a workload for hours while sampling the resident set size, and fails \
+                     if it keeps growing after the warm-up (Linux only)",
+                )
+                .args(&workload_args())
+                .arg(
+                    Arg::with_name("memory manager")
+                        .short("m")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .case_insensitive(true)
+                        .help("Memeory manager"),
+                )
+                .arg(
+                    Arg::with_name("hours")
+                        .long("hours")
+                        .value_name("HOURS")
+                        .takes_value(true)
+                        .required(true)
+                        .conflicts_with_all(&["interval", "ops per thread", "ycsb load"])
+                        .help("Duration of the run in hours (fractions allowed)"),
+                )
+                .arg(
+                    Arg::with_name("max slope")
+                        .long("max-slope")
+                        .value_name("MIB_PER_HOUR")
+                        .takes_value(true)
+                        .help(
+                            "Fails if the resident set size grows faster than this after the \
+                             warm-up, according to a least-squares fit",
+                        )
+                        .default_value("16"),
+                )
+                .arg(
+                    Arg::with_name("warmup")
+                        .long("warmup")
+                        .value_name("PERCENT")
+                        .takes_value(true)
+                        .help("Leading part of the run that is not checked for growth")
+                        .default_value("25"),
+                )
+                .arg(
+                    Arg::with_name("rss period")
+                        .long("rss-period")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help("Period of sampling the resident set size")
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cold")
+                .about(
+                    "Measures the first operations on an empty structure with a fresh \
+                     collector, including the warm-up that steady-state runs hide",
+                )
+                .args(&workload_args())
+                .arg(
+                    Arg::with_name("memory manager")
+                        .short("m")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .case_insensitive(true)
+                        .help("Memeory manager"),
+                )
+                .arg(
+                    Arg::with_name("first")
+                        .long("first")
+                        .value_name("N")
+                        .takes_value(true)
+                        .conflicts_with_all(&[
+                            "interval",
+                            "ops per thread",
+                            "ycsb load",
+                            "long lived",
+                        ])
+                        .help("Number of operations of each worker to measure")
+                        .default_value("1000"),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .short("R")
+                        .value_name("RUNS")
+                        .takes_value(true)
+                        .help("Number of repetitions, each with a new structure and collector")
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("overhead")
+                .about(
+                    "Measures the cost of the counters that the workers update in each \
+                     operation, all enabled, relative to an operation of the workload",
+                )
+                .args(&workload_args())
+                .arg(
+                    Arg::with_name("memory manager")
+                        .short("m")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .case_insensitive(true)
+                        .help("Memeory manager"),
+                )
+                .arg(
+                    Arg::with_name("max overhead")
+                        .long("max-overhead")
+                        .value_name("PERCENT")
+                        .takes_value(true)
+                        .help("Fails if the counters take more of an operation than this")
+                        .default_value("1"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("matrix")
+                .about(
+                    "Runs every combination of the data structures, memory managers and numbers \
+                     of threads, each in a new process, and merges their results",
+                )
+                .arg(
+                    Arg::with_name("data structures")
+                        .short("d")
+                        .value_name("DS")
+                        .required(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .help("Data structures, skipping those that are not in this build"),
+                )
+                .arg(
+                    Arg::with_name("memory managers")
+                        .short("m")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .required(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .case_insensitive(true)
+                        .help("Memory managers"),
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .short("t")
+                        .value_name("THREADS")
+                        .required(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .help("Numbers of threads"),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .short("R")
+                        .value_name("RUNS")
+                        .takes_value(true)
+                        .help("Number of runs of each combination")
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .value_name("OUTPUT")
+                        .takes_value(true)
+                        .help(
+                            "Output CSV filename. \
+                             Appends the data if the file already exists.",
+                        )
+                        .default_value("results/matrix.csv"),
+                )
+                .arg(Arg::with_name("dry run").long("dry-run").help(
+                    "Validates every combination and prints the planned runs with their \
+                     estimated total duration, without running them",
+                ))
+                .arg(
+                    Arg::with_name("workload")
+                        .value_name("ARGS")
+                        .multiple(true)
+                        .last(true)
+                        .help("Arguments passed to every process, e.g. -- -i 10 -r 1000"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("summary")
+                .about(
+                    "Prints the throughput of CSV results as a table per workload, with a row per \
+                     data structure and memory manager and a column per number of threads",
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("CSV")
+                        .required(true)
+                        .multiple(true)
+                        .help("CSV files written by the benchmark"),
+                )
+                .arg(
+                    Arg::with_name("ratio to")
+                        .long("ratio-to")
+                        .value_name("MM")
+                        .possible_values(&MM::variants())
+                        .case_insensitive(true)
+                        .help(
+                            "Memory manager that the ratios are relative to \
+                             [default: the first of each table]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("latex")
+                        .long("latex")
+                        .help("Prints LaTeX tables instead of text"),
+                ),
+        )
+        .get_matches();
+    install_interrupt_handler();
+    install_panic_hook();
+
+    if let Some(m) = matches.subcommand_matches("compare") {
+        let mms = values_t!(m, "memory managers", MM).unwrap();
+        let runs = value_t!(m, "runs", usize).unwrap_or_else(|e| e.exit());
+        if runs == 0 {
+            eprintln!("compare: --runs must be positive");
+            process::exit(2);
+        }
+        check_mm(m, mms[1]).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+        let (mut config, mut output) = setup(m, mms[0]);
+        compare(&mut config, &mut output, (mms[0], mms[1]), runs);
+        exit_on_failure();
+        return;
+    }
+
+    if let Some(m) = matches.subcommand_matches("check") {
+        let mm = value_t!(m, "memory manager", MM).unwrap();
+        let runs = value_t!(m, "runs", usize).unwrap_or_else(|e| e.exit());
+        if runs == 0 {
+            eprintln!("check: --runs must be positive");
+            process::exit(2);
+        }
+        let (mut config, mut output) = setup(m, mm);
+        if !config.ds.is_map()
+            || config.scan.is_some()
+            || config.instances > 1
+            || config.capacity.is_some()
+        {
+            eprintln!("check: only maps are supported, without --scan, --instances and --capacity");
+            process::exit(2);
+        }
+        config.check = true;
+        if m.occurrences_of("history capacity") == 0 {
+            config.history_capacity = 1000;
+        }
+        // The interval only bounds the runs since the threads stop when their buffers are full.
+        if m.occurrences_of("interval") == 0 {
+            config.interval = 1;
+            config.duration = Duration::from_secs(1);
+        }
+        for _ in 0..runs {
+            if interrupted() {
+                break;
+            }
+            run(&config, &mut output);
+        }
+        return;
+    }
+
+    if let Some(m) = matches.subcommand_matches("soak") {
+        let mm = value_t!(m, "memory manager", MM).unwrap();
+        let hours = value_t!(m, "hours", f64).unwrap_or_else(|e| e.exit());
+        let max_slope = value_t!(m, "max slope", f64).unwrap_or_else(|e| e.exit());
+        let warmup = value_t!(m, "warmup", f64).unwrap_or_else(|e| e.exit());
+        let period = value_t!(m, "rss period", u64).unwrap_or_else(|e| e.exit());
+        if hours.is_nan() || hours <= 0.0 || !(0.0..100.0).contains(&warmup) || period == 0 {
+            eprintln!("soak: --hours and --rss-period must be positive and --warmup in [0, 100)");
+            process::exit(2);
+        }
+        if rss().is_none() {
+            eprintln!("soak: sampling the resident set size is supported only for linux");
+            process::exit(2);
+        }
+        let (mut config, mut output) = setup(m, mm);
+        config.duration = Duration::from_secs_f64(hours * 3600.0);
+        config.interval = max(config.duration.as_secs(), 1);
+        soak(
+            &config,
+            &mut output,
+            Duration::from_secs(period),
+            warmup / 100.0,
+            max_slope,
+        );
+        return;
+    }
+
+    if let Some(m) = matches.subcommand_matches("cold") {
+        let mm = value_t!(m, "memory manager", MM).unwrap();
+        let first = value_t!(m, "first", u64).unwrap_or_else(|e| e.exit());
+        let runs = value_t!(m, "runs", usize).unwrap_or_else(|e| e.exit());
+        if first == 0 || runs == 0 {
+            eprintln!("cold: --first and --runs must be positive");
+            process::exit(2);
+        }
+        let (mut config, mut output) = setup(m, mm);
+        config.prefill = 0;
+        config.ops_per_thread = Some(first);
+        cold_start(&config, &mut output, runs);
+        exit_on_failure();
+        return;
+    }
+    if let Some(m) = matches.subcommand_matches("overhead") {
+        let mm = value_t!(m, "memory manager", MM).unwrap();
+        let max_overhead = value_t!(m, "max overhead", f64).unwrap_or_else(|e| e.exit());
+        let (config, mut output) = setup(m, mm);
+        measure_overhead(&config, &mut output, max_overhead);
+        exit_on_failure();
+        return;
+    }
+
+    if let Some(m) = matches.subcommand_matches("matrix") {
+        // A structure of a family that is not compiled in is skipped, so that a sweep over all of
+        // them runs in any build.
+        let dss = m
+            .values_of("data structures")
+            .unwrap()
+            .filter_map(|ds| match ds.parse::<DS>() {
+                Ok(ds) => Some(ds),
+                Err(e) => {
+                    println!("matrix: skipping {}", e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let mms = values_t!(m, "memory managers", MM).unwrap();
+        let threads = values_t!(m, "threads", usize).unwrap_or_else(|e| e.exit());
+        let runs = value_t!(m, "runs", usize).unwrap_or_else(|e| e.exit());
+        let output = Path::new(m.value_of("output").unwrap());
+        let workload = m.values_of("workload").map_or(Vec::new(), |v| v.collect());
+        if m.is_present("dry run") {
+            plan_matrix(&dss, &mms, &threads, runs, &workload);
+        } else {
+            matrix(&dss, &mms, &threads, runs, output, &workload);
+        }
+        return;
+    }
+
+    if let Some(m) = matches.subcommand_matches("summary") {
+        let base = m
+            .value_of("ratio to")
+            .map(|_| value_t!(m, "ratio to", MM).unwrap().to_string());
+        let format = if m.is_present("latex") {
+            Format::Latex
+        } else {
+            Format::Text
+        };
+        let mut runs = Vec::new();
+        for input in m.values_of("input").unwrap() {
+            match read_runs(Path::new(input)) {
+                Ok(r) => runs.extend(r),
+                Err(e) => {
+                    eprintln!("summary: cannot read {}: {}", input, e);
+                    process::exit(2);
+                }
+            }
+        }
+        print_summary(&runs, base.as_ref().map(String::as_str), format);
+        return;
+    }
+
+    let mm = value_t!(matches, "memory manager", MM).unwrap();
+    let (config, mut output) = setup(&matches, mm);
+    run(&config, &mut output);
+    exit_on_failure();
+}
+
+/// Exits with status 3 if a run failed, or 1 if a run regressed.
+fn exit_on_failure() {
+    if FAILED.load(Ordering::Relaxed) {
+        process::exit(3);
+    }
+    if REGRESSED.load(Ordering::Relaxed) || DIVERGED.load(Ordering::Relaxed) {
+        process::exit(1);
+    }
+}
+
+/// Arguments describing the workload, shared by all subcommands.
+fn workload_args() -> Vec<Arg<'static, 'static>> {
+    let mut args = vec![
+        Arg::with_name("data structure")
+            .short("d")
+            .value_name("DS")
+            .possible_values(&DS::variants())
+            .required(true)
+            .case_insensitive(true)
+            .help("Data structure(s)"),
+        Arg::with_name("threads")
+            .short("t")
+            .value_name("THREADS")
+            .takes_value(true)
+            .required_unless_one(&["readers", "writers"])
+            .conflicts_with_all(&["readers", "writers"])
+            .help("Numbers of threads to run."),
+        Arg::with_name("readers")
+            .long("readers")
+            .value_name("THREADS:MIX")
+            .takes_value(true)
+            .help(
+                "Group of threads with their own mix of operations instead of -t and -g, \
+                 e.g. 16:get=100, and optionally a limit of their total operations per second, \
+                 e.g. 16:get=100@1000000",
+            ),
+        Arg::with_name("writers")
+            .long("writers")
+            .value_name("THREADS:MIX")
+            .takes_value(true)
+            .help("Another group of threads, e.g. 4:insert=50,remove=50@100000"),
+        Arg::with_name("preset")
+            .long("preset")
+            .value_name("PRESET")
+            .possible_values(&Preset::variants())
+            .case_insensitive(true)
+            .conflicts_with_all(&["readers", "writers", "ycsb load"])
+            .help(
+                "Predefined workload. GarbageBomb: the last thread inserts keys with 4 KiB values \
+                 and removes them right away while the others only read, and the garbage over \
+                 time is recorded to --time-series (default: results/<DS>_<MM>_garbage.csv). \
+                 DeleteStorm: the threads insert --storm-size million keys, and then all remove \
+                 them at once (time series by default in results/<DS>_<MM>_storm.csv). \
+                 Monotonic: each insert takes a new maximum key and each remove the minimum one, \
+                 which makes the ordered lists queues",
+            ),
+        Arg::with_name("storm size")
+            .long("storm-size")
+            .value_name("MILLIONS")
+            .takes_value(true)
+            .help("Millions of keys that DeleteStorm inserts before removing them")
+            .default_value("1"),
+        Arg::with_name("non-coop")
+            .short("n")
+            .takes_value(true)
+            .help(
+                "The degree of non-cooperation. \
+                 1: 1ms, 2: 10ms, 3: stall",
+            )
+            .possible_values(&["0", "1", "2", "3"])
+            .default_value("0"),
+        Arg::with_name("get rate")
+            .short("g")
+            .takes_value(true)
+            .help(
+                "The proportion of `get`(read) operations. \
+                 0: 0%, 1: 50%, 2: 90%",
+            )
+            .possible_values(&["0", "1", "2"])
+            .default_value("0"),
+        Arg::with_name("range")
+            .short("r")
+            .value_name("RANGE")
+            .takes_value(true)
+            .help("Key range: [0..RANGE]")
+            .default_value("100000"),
+        Arg::with_name("key size")
+            .long("key-size")
+            .value_name("DIST")
+            .takes_value(true)
+            .help(
+                "Length distribution of the string keys: fixed (decimal representation), \
+                 exp:MEAN (exponential) or pareto:SCALE:SHAPE. \
+                 Keys are zero-padded to their length.",
+            )
+            .default_value("fixed"),
+        Arg::with_name("slow cmp")
+            .long("slow-cmp")
+            .value_name("NS")
+            .takes_value(true)
+            .help(
+                "Make each comparison of two keys of a map spin for at least NS nanoseconds, \
+                 which makes the traversals hold their protection for longer",
+            )
+            .default_value("0"),
+        Arg::with_name("ffi lib")
+            .long("ffi-lib")
+            .value_name("PATH")
+            .takes_value(true)
+            .help(
+                "Shared library of the map of -d Ffi, which implements the interface of \
+                 ffi/pebr_bench.h",
+            ),
+        Arg::with_name("drop cost")
+            .long("drop-cost-ns")
+            .value_name("N")
+            .takes_value(true)
+            .help(
+                "Make the destruction of each retired object spin for N nanoseconds, as if it \
+                 had an expensive destructor, and report which threads paid for them",
+            )
+            .default_value("0"),
+        Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .takes_value(true)
+            .help(
+                "Seed of the random number generators of the workers and of the prefill, \
+                 which is recorded with the results [default: random]",
+            ),
+        Arg::with_name("think time")
+            .long("think-time-ns")
+            .value_name("N[:DIST]")
+            .takes_value(true)
+            .help(
+                "Make each worker spin between two operations for N nanoseconds on average, \
+                 fixed, exp (exponentially distributed) or uniform (in [0, 2N]). \
+                 The workers stay pinned while thinking.",
+            )
+            .default_value("0"),
+        Arg::with_name("scan")
+            .long("scan")
+            .value_name("LEN")
+            .takes_value(true)
+            .help(
+                "Replace each get with a scan that walks LEN successors from the key, \
+                 for the Harris lists and NMTree",
+            ),
+        Arg::with_name("mark")
+            .long("mark")
+            .value_name("fetch-or|cas")
+            .possible_values(&["fetch-or", "cas"])
+            .help(
+                "How the Harris lists mark the node they remove: fetch_or on its next link, \
+                 or a CAS of the whole link",
+            )
+            .default_value("fetch-or"),
+        Arg::with_name("interval")
+            .short("i")
+            .value_name("INTERVAL")
+            .takes_value(true)
+            .help("Time interval in seconds to run the benchmark")
+            .default_value("10"),
+        Arg::with_name("ops per thread")
+            .long("ops-per-thread")
+            .value_name("OPS")
+            .takes_value(true)
+            .conflicts_with("interval")
+            .help(
+                "Run until every thread performs OPS operations, instead of for INTERVAL seconds",
+            ),
+        Arg::with_name("ycsb load")
+            .long("ycsb-load")
+            .value_name("FILE")
+            .takes_value(true)
+            .requires("ycsb run")
+            .conflicts_with_all(&["readers", "writers", "ops per thread"])
+            .help(
+                "Prefill the map by replaying the load phase of a YCSB trace \
+                 (the output of `ycsb load basic`), instead of inserting random keys",
+            ),
+        Arg::with_name("ycsb run")
+            .long("ycsb-run")
+            .value_name("FILE")
+            .takes_value(true)
+            .requires("ycsb load")
+            .help(
+                "Replay the run phase of a YCSB trace (the output of `ycsb run basic`) \
+                 on the workers in a round-robin manner, instead of running for INTERVAL seconds",
+            ),
+        Arg::with_name("long lived")
+            .long("long-lived")
+            .value_name("PERCENT")
+            .takes_value(true)
+            .conflicts_with_all(&["ycsb load", "preset"])
+            .help(
+                "Percentage of the keys that are long-lived: they are prefilled and only read, \
+                 while the other keys are inserted and removed",
+            ),
+        Arg::with_name("prefill file")
+            .long("prefill-file")
+            .value_name("FILE")
+            .takes_value(true)
+            .conflicts_with_all(&["ycsb load", "preset"])
+            .help(
+                "Load the keys of the prefill from FILE, or sample them and save them to FILE if \
+                 it does not exist, so that the repetitions of a sweep sample them once (maps only)",
+            ),
+        Arg::with_name("set")
+            .long("set")
+            .conflicts_with("preset")
+            .help(
+                "Benchmark the map as a set (`ConcurrentSet`), whose nodes hold no values \
+                 (maps only, NR, EBR and PEBR only)",
+            ),
+        Arg::with_name("unguarded reads")
+            .long("unguarded-reads")
+            .conflicts_with_all(&["set", "scan", "slow cmp", "mark"])
+            .help(
+                "Run the gets without a guard, which protects nothing, for the ceiling of the \
+                 reads (HHSList, NMTree and ART only, with NR, or EBR and --reclaim off)",
+            ),
+        Arg::with_name("transfers")
+            .long("transfers")
+            .conflicts_with_all(&[
+                "set",
+                "scan",
+                "unguarded reads",
+                "remove get",
+                "history",
+                "checksum",
+                "ycsb load",
+                "preset",
+            ])
+            .help(
+                "Run the transfer workload on two maps: the inserts move their key from the first \
+                 map to the second and the removes move it back, each with a single multi-word \
+                 CAS across the two maps (KCASTree only, NR and EBR only)",
+            ),
+        Arg::with_name("remove get")
+            .long("remove-get")
+            .conflicts_with_all(&["set", "scan"])
+            .help(
+                "Remove with `remove_get`, which returns the removed value by reference, valid \
+                 until the guard is unpinned, instead of moving it out (maps only, NR and EBR only)",
+            ),
+        Arg::with_name("compare and delete")
+            .long("compare-and-delete")
+            .conflicts_with_all(&["set", "scan", "remove get", "unguarded reads", "transfers"])
+            .help(
+                "Make each remove a compare-and-delete, which reads the value of its key and \
+                 removes the entry only if it still holds that value, with `remove_if` (the \
+                 Harris lists, TagList, VersionList and HashMap, with NR, EBR and PEBR)",
+            ),
+        Arg::with_name("backoff spin")
+            .long("backoff-spin")
+            .value_name("STEPS")
+            .takes_value(true)
+            .help(
+                "The last step of the exponential back-off of the waits of ART and CuckooHashMap \
+                 that spins, 2^step times, instead of yielding the CPU (at most 20)",
+            )
+            .default_value("6"),
+        Arg::with_name("sampling period")
+            .short("s")
+            .value_name("MEM_SAMPLING_PERIOD")
+            .takes_value(true)
+            .help(
+                "The period to query jemalloc stats.allocated (ms). 0 for no sampling. \
+                 Only supported on linux.",
+            )
+            .default_value("1"),
+        Arg::with_name("ops per cs")
+            .short("c")
+            .value_name("OPS_PER_CS")
+            .takes_value(true)
+            .possible_values(&["1", "4"])
+            .help("Operations per each critical section")
+            .default_value("1"),
+        Arg::with_name("extend read sections").long("extend-read-sections").help(
+            "Extend the PEBR critical sections that performed only `get`s over up to 16 batches \
+             of operations instead of repinning after each batch, which saves the fences of \
+             those repins. The reads themselves are unchanged.",
+        ),
+        Arg::with_name("stall threads")
+            .long("stall-threads")
+            .value_name("K")
+            .takes_value(true)
+            .help(
+                "Stop K workers at a time with signals, rotating over the workers, \
+                 to mimic preemption by the OS",
+            )
+            .default_value("0"),
+        Arg::with_name("stall period")
+            .long("stall-period")
+            .value_name("MS")
+            .takes_value(true)
+            .help("Time between the starts of the stop windows")
+            .default_value("100"),
+        Arg::with_name("stall duration")
+            .long("stall-duration")
+            .value_name("MS")
+            .takes_value(true)
+            .help("Length of each stop window")
+            .default_value("10"),
+        Arg::with_name("low prio threads")
+            .long("low-prio-threads")
+            .value_name("K")
+            .takes_value(true)
+            .help(
+                "Run the first K workers at a lower OS priority, the nice level of \
+                 --low-prio-nice, so that they are slowed down rather than stopped (Linux only)",
+            )
+            .default_value("0"),
+        Arg::with_name("low prio nice")
+            .long("low-prio-nice")
+            .value_name("NICE")
+            .takes_value(true)
+            .help("The nice level of the workers of --low-prio-threads, from 1 to 19")
+            .default_value("19"),
+        Arg::with_name("shards")
+            .long("shards")
+            .value_name("N")
+            .takes_value(true)
+            .help("Number of the shards of ShardedHashMap, each with its own EBR collector")
+            .default_value("16"),
+        Arg::with_name("instances")
+            .long("instances")
+            .value_name("M")
+            .takes_value(true)
+            .help(
+                "Number of the independent instances of the map, which share the reclamation \
+                 scheme. Each operation is on a random instance.",
+            )
+            .default_value("1"),
+        Arg::with_name("stages")
+            .long("stages")
+            .value_name("N")
+            .takes_value(true)
+            .help(
+                "Number of the stages of Pipeline. The workers are assigned to the stages in \
+                 turn.",
+            )
+            .default_value("2"),
+        Arg::with_name("pipeline")
+            .long("pipeline")
+            .value_name("queue|stack")
+            .possible_values(&["queue", "stack"])
+            .help("Whether the stages of Pipeline pass the values through queues or stacks")
+            .default_value("queue"),
+        Arg::with_name("interval len")
+            .long("interval-len")
+            .value_name("POINTS")
+            .takes_value(true)
+            .help(
+                "Maximum length of the intervals of IntervalMap. The length of an interval is \
+                 determined by its start.",
+            )
+            .default_value("16"),
+        Arg::with_name("shield cache")
+            .long("shield-cache")
+            .value_name("DEPTH")
+            .takes_value(true)
+            .help(
+                "Cache the shields of the first DEPTH nodes of the traversals of the PEBR lists \
+                 and NM tree in each thread, and report the hit rate. 0 disables the cache.",
+            )
+            .default_value("0"),
+        Arg::with_name("shield budget")
+            .long("shield-budget")
+            .value_name("SHIELDS")
+            .takes_value(true)
+            .help(
+                "The number of shields that the pool of each thread of the PEBR BonsaiTree and \
+                 KCASTree is expected to allocate at most. The pools exceed it if they must, and \
+                 the run reports by how much. 0 means unlimited.",
+            )
+            .default_value("0"),
+        Arg::with_name("pin threads").long("pin-threads").help(
+            "Pin the workers to the CPUs in a round-robin manner, and report the throughput, \
+             the operations restarted after an ejection and the retired nodes per socket and \
+             core (Linux only)",
+        ),
+        Arg::with_name("gc policy")
+            .long("gc-policy")
+            .value_name("POLICY")
+            .possible_values(&GcPolicy::NAMES)
+            .help(
+                "When the EBR workers flush their garbage at the end of a critical section: never \
+                 (lazy), always (eager), while the outstanding garbage exceeds --gc-threshold \
+                 (adaptive) or only when an operation finds no work or after a think time (idle)",
+            )
+            .default_value("lazy"),
+        Arg::with_name("gc threshold")
+            .long("gc-threshold")
+            .value_name("NODES")
+            .takes_value(true)
+            .help("Outstanding garbage above which the adaptive GC policy flushes")
+            .default_value("4096"),
+        Arg::with_name("reclaim")
+            .long("reclaim")
+            .value_name("on|off")
+            .possible_values(&["on", "off"])
+            .help(
+                "Whether retired objects are freed. With off, they are kept in a list that is \
+                 never collected, while the reclamation scheme otherwise runs as usual.",
+            )
+            .default_value("on"),
+        Arg::with_name("harris stats")
+            .long("harris-stats")
+            .help("Report how many nodes each cleanup CAS of HList unlinks"),
+        Arg::with_name("harris max batch")
+            .long("harris-max-batch")
+            .value_name("NODES")
+            .takes_value(true)
+            .help(
+                "Maximum number of nodes a cleanup CAS of HList unlinks. \
+                 1 unlinks nodes one by one like HMList.",
+            ),
+        Arg::with_name("skiplist p")
+            .long("skiplist-p")
+            .value_name("P")
+            .takes_value(true)
+            .help(
+                "The probability that a tower of SkipList grows by each level. A larger P makes \
+                 the towers higher and the searches shorter.",
+            )
+            .default_value("0.5"),
+        Arg::with_name("skiplist max level")
+            .long("skiplist-max-level")
+            .value_name("L")
+            .takes_value(true)
+            .help("The maximum height of the towers of SkipList, at most 32")
+            .default_value("20"),
+        Arg::with_name("bonsai stats").long("bonsai-stats").help(
+            "Report the nodes that each update of BonsaiTree copies and the bytes of nodes it \
+                 retires",
+        ),
+        Arg::with_name("bonsai weight")
+            .long("bonsai-weight")
+            .value_name("W")
+            .takes_value(true)
+            .help(
+                "Rotate a node of BonsaiTree when one of its subtrees is more than W times as \
+                 large as the other. A larger W rebalances less aggressively.",
+            )
+            .default_value("2"),
+        Arg::with_name("tree buckets")
+            .long("tree-buckets")
+            .value_name("N")
+            .takes_value(true)
+            .help(
+                "The number of buckets of HashTreeMap, each a NMTree. 1 makes it a single tree, \
+                 and more buckets make the trees smaller.",
+            )
+            .default_value("1024"),
+        Arg::with_name("hasher")
+            .long("hasher")
+            .value_name("FUNCTION")
+            .takes_value(true)
+            .possible_values(&["default", "sip", "fx", "ahash"])
+            .help(
+                "The hash function of the hash maps: `DefaultHasher` with fixed keys, or SipHash, \
+                 FxHash or aHash (with the ahash feature) keyed with the seed of the run",
+            )
+            .default_value("default"),
+        Arg::with_name("capacity")
+            .long("capacity")
+            .value_name("ENTRIES")
+            .takes_value(true)
+            .conflicts_with_all(&["set", "history", "checksum", "transfers"])
+            .help(
+                "Bound the size of the map: an insert that takes it over the bound evicts an \
+                 entry, so that the size holds whatever the operation mix (HashMap only, NR and \
+                 EBR only)",
+            ),
+        Arg::with_name("eviction")
+            .long("eviction")
+            .value_name("POLICY")
+            .possible_values(&Eviction::NAMES)
+            .help(
+                "The entry that an insert over --capacity evicts: the first of a random bucket \
+                 (random) or of the bucket of a hand that sweeps the buckets (sweep)",
+            )
+            .default_value("random"),
+        Arg::with_name("seek reuse").long("seek-reuse").help(
+            "Make a retried update of the Natarajan-Mittal trees resume its seek from the \
+             ancestor of the failed attempt instead of the root (NMTree and HashTreeMap only)",
+        ),
+        Arg::with_name("thread churn")
+            .long("thread-churn")
+            .value_name("MS")
+            .takes_value(true)
+            .help(
+                "Replace each worker thread by a new one every MS milliseconds, which registers \
+                 with the collector anew, so that the threads exit and their state is reclaimed \
+                 during the run (maps only, with EBR and PEBR)",
+            ),
+        Arg::with_name("epoch latency").long("epoch-latency").help(
+            "Report the histograms of the time from a retire to the epoch advance that makes \
+                 it reclaimable and to the actual free (EBR and PEBR)",
+        ),
+        Arg::with_name("stall event")
+            .long("stall-event-us")
+            .value_name("US")
+            .takes_value(true)
+            .help(
+                "Report the maximum latency of an operation of a map (except Ffi), and count the \
+                 operations slower than US microseconds as stall events",
+            ),
+        Arg::with_name("restart stats")
+            .long("restart-stats")
+            .help(
+                "Report the distribution of the number of restarts of each operation (the maps \
+                 of this crate only)",
+            ),
+        Arg::with_name("remove phases").long("remove-phases").help(
+            "Report the time that the removes spend searching, marking and unlinking their \
+             nodes (the Harris lists, HashMap, NMTree and HashTreeMap, with NR, EBR and PEBR)",
+        ),
+        Arg::with_name("restart bound")
+            .long("restart-bound")
+            .value_name("K")
+            .takes_value(true)
+            .help(
+                "Make a traversal of the Harris lists fall back to a slower validated traversal \
+                 after K consecutive failures",
+            ),
+        Arg::with_name("validate prev").long("validate-prev").help(
+            "Make every traversal of the Harris lists the validated one, which re-reads the link \
+             of the previous node at every step",
+        ),
+        Arg::with_name("progress").long("progress").help(
+            "Print the throughput, the number of retired nodes and the memory usage every second \
+             without stopping the workers",
+        ),
+        Arg::with_name("adaptive threshold")
+            .long("adaptive-threshold")
+            .value_name("PERCENT")
+            .takes_value(true)
+            .help(
+                "Percentage of failed traversals above which a thread switches AdaptiveList from \
+                 Harris-Michael's to Harris's cleanup",
+            )
+            .default_value("5"),
+        Arg::with_name("time series")
+            .long("time-series")
+            .value_name("FILE")
+            .takes_value(true)
+            .help(
+                "Record the approximate size of the structure and the memory usage \
+                 over time to FILE as CSV",
+            ),
+        Arg::with_name("time series period")
+            .long("time-series-period")
+            .value_name("MS")
+            .takes_value(true)
+            .help("Period of the time series")
+            .default_value("10"),
+        Arg::with_name("history")
+            .long("history")
+            .value_name("FILE")
+            .takes_value(true)
+            .help(
+                "Log the invocation and response time of each map operation to FILE \
+                 as JSON lines, for offline linearizability checking",
+            ),
+        Arg::with_name("history sample")
+            .long("history-sample")
+            .value_name("N")
+            .takes_value(true)
+            .help("Log only the operations on the keys divisible by N")
+            .default_value("1"),
+        Arg::with_name("history capacity")
+            .long("history-capacity")
+            .value_name("OPS")
+            .takes_value(true)
+            .help(
+                "Maximum number of logged operations per thread. \
+                 The history is cut when a thread exceeds it.",
+            )
+            .default_value("1000000"),
+        Arg::with_name("checksum").long("checksum").help(
+            "Checksum the operations of each thread, their keys and their results, to compare \
+             single-threaded runs of the same seed across memory managers",
+        ),
+        Arg::with_name("baseline")
+            .long("baseline")
+            .value_name("DIR")
+            .takes_value(true)
+            .help(
+                "Append the result to the results store in DIR, and report the metrics that \
+                 regress against the previous results of the same configuration",
+            ),
+        Arg::with_name("regression threshold")
+            .long("regression-threshold")
+            .value_name("PERCENT")
+            .takes_value(true)
+            .help("Minimum relative change of a metric reported as a regression")
+            .default_value("5"),
+        Arg::with_name("output")
+            .short("o")
+            .value_name("OUTPUT")
+            .takes_value(true)
+            .help(
+                "Output CSV filename. \
+                 Appends the data if the file already exists with the same columns.\n\
+                 [default: results/<DS>.csv]",
+            ),
+        Arg::with_name("dry run").long("dry-run").help(
+            "Validates the configuration and prints it with the duration of the run, without \
+             running it or writing the output",
+        ),
+    ];
+    if perf_counters::supported() {
+        args.push(Arg::with_name("perf").long("perf").help(
+            "Count the cycles, instructions, cache misses and remote NUMA accesses of the \
+             workers with perf events, and report them per operation",
+        ));
+    }
+    if cfg!(feature = "audit") {
+        args.push(Arg::with_name("audit").long("audit").help(
+            "Check that the values of the map alive after the run are exactly its entries, and \
+             that all of them are dropped with the map",
+        ));
+    }
+    if cfg!(feature = "tui") {
+        args.push(Arg::with_name("tui").long("tui").help(
+            "Show the throughput, the retired nodes, the memory usage and the activity of each \
+             worker in a view that is redrawn every second",
+        ));
+    }
+    args
+}
+
+fn run(config: &Config, output: &mut Writer<File>) -> Perf {
+    config.checksums.lock().unwrap().clear();
+    *config.recovery.lock().unwrap() = Recovery::default();
+    match config.ops_per_cs {
+        OpsPerCs::One => bench::<U1>(config, output),
+        OpsPerCs::Four => bench::<U4>(config, output),
+    }
+}
+
+/// The names of the registered structures that satisfy `supports`, for the messages of the options
+/// that only some of them support.
+fn supporting(
+    structures: &Registry<Bench<'static, U1>>,
+    supports: impl Fn(&Entry<Bench<'static, U1>>) -> bool,
+) -> String {
+    let names = structures
+        .entries()
+        .iter()
+        .filter(|e| supports(e))
+        .map(|e| e.name)
+        .collect::<Vec<_>>();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        Some((last, _)) => last.to_string(),
+        None => "none of the structures of this build".to_string(),
+    }
+}
+
+/// Checks that `mm` supports the data structure and the workload, or describes why not.
+fn check_mm(m: &ArgMatches, mm: MM) -> Result<(), String> {
+    let ds = value_t!(m, "data structure", DS).unwrap();
+    let structures = structures();
+    let entry = structures.get(ds.0).expect("parsed from the registry");
+    // The maps that the map workload of the driver runs, with its generic worker loop.
+    let map_workload = entry.kind == Kind::Map || entry.kind == Kind::External;
+    // Only the generic worker loops of the maps run their operations in the frames of the phases.
+    if cfg!(feature = "profiling")
+        && (!map_workload
+            || mm == MM::RC
+            || m.is_present("set")
+            || m.is_present("scan")
+            || m.is_present("unguarded reads")
+            || m.is_present("transfers"))
+    {
+        return Err(
+            "the profiling feature supports only maps except ShardedHashMap and Ffi, without RC, \
+             --set, --scan, --unguarded-reads and --transfers"
+                .to_string(),
+        );
+    }
+    if mm == MM::RC && (entry.port(Scheme::RC).is_none() || !tagged::dwcas_available()) {
+        return Err(format!(
+            "RC supports only {}, and needs double-width CAS",
+            supporting(&structures, |e| e.port(Scheme::RC).is_some())
+        ));
+    }
+    if mm == MM::STW
+        && (entry.kind != Kind::Map
+            || m.is_present("set")
+            || m.is_present("scan")
+            || m.value_of("mark") == Some("cas"))
+    {
+        return Err(
+            "STW supports only the maps of this crate except ShardedHashMap, without --set, \
+             --scan and --mark cas"
+                .to_string(),
+        );
+    }
+    let port = match entry.port(mm.scheme()) {
+        Some(port) => port,
+        None => {
+            let mms = [MM::NR, MM::EBR, MM::PEBR, MM::RC, MM::STW]
+                .iter()
+                .filter(|&&mm| entry.port(mm.scheme()).is_some())
+                .filter(|&&mm| mm != MM::STW || entry.kind == Kind::Map)
+                .map(|mm| mm.to_string())
+                .collect::<Vec<_>>();
+            return Err(format!("{} supports only {}", ds, mms.join(" and ")));
+        }
+    };
+    // Only the workers of the maps follow the mixes of their groups and account their operations.
+    if (m.is_present("readers") || m.is_present("writers")) && !ds.is_map() {
+        return Err("--readers and --writers support only maps".to_string());
+    }
+    if m.is_present("set") && port.set.is_none() {
+        return Err(format!(
+            "--set supports only the maps with a set, and {} has none with {}",
+            ds, mm
+        ));
+    }
+    if m.is_present("remove get") && (!map_workload || mm == MM::PEBR || mm == MM::RC) {
+        return Err("--remove-get supports only the maps of EBR, with NR and EBR".to_string());
+    }
+    if m.is_present("unguarded reads")
+        && (port.unguarded.is_none()
+            || !(mm == MM::NR || mm == MM::EBR && m.value_of("reclaim") == Some("off")))
+    {
+        return Err(format!(
+            "--unguarded-reads supports only {}, with NR, or EBR and --reclaim off",
+            supporting(&structures, |e| e
+                .port(Scheme::EBR)
+                .map_or(false, |p| p.unguarded.is_some()))
+        ));
+    }
+    if m.is_present("capacity") && (ds != DS("HashMap") || (mm != MM::NR && mm != MM::EBR)) {
+        return Err("--capacity supports only HashMap, with NR and EBR".to_string());
+    }
+    if m.is_present("remove phases") {
+        let instrumented = match ds {
+            DS("HList") | DS("HMList") | DS("HHSList") | DS("AdaptiveList") => true,
+            DS("HashMap") | DS("NMTree") | DS("HashTreeMap") => true,
+            _ => false,
+        };
+        if !instrumented || mm == MM::RC {
+            return Err(
+                "--remove-phases supports only the Harris lists, HashMap, NMTree and \
+                 HashTreeMap, with NR, EBR and PEBR"
+                    .to_string(),
+            );
+        }
+    }
+    if m.is_present("seek reuse") && ![DS("NMTree"), DS("HashTreeMap")].contains(&ds) {
+        return Err("--seek-reuse supports only NMTree and HashTreeMap".to_string());
+    }
+    // The other workers do not finish their operations in the histogram, and the other maps do not
+    // count their restarts.
+    if m.is_present("restart stats")
+        && (!ds.is_map() || ds == DS("Ffi") || entry.kind == Kind::External)
+    {
+        return Err("--restart-stats supports only the maps of this crate".to_string());
+    }
+    // The worker of Ffi does not measure its operations.
+    if m.is_present("stall event") {
+        let us = value_t!(m, "stall event", u64).unwrap_or_else(|e| e.exit());
+        if us == 0 || !ds.is_map() || ds == DS("Ffi") {
+            return Err(
+                "--stall-event-us should be positive, and supports only maps except Ffi"
+                    .to_string(),
+            );
+        }
+    }
+    if m.is_present("thread churn")
+        && (!map_workload || (mm != MM::EBR && mm != MM::PEBR) || m.is_present("set"))
+    {
+        return Err(
+            "--thread-churn supports only maps, with EBR and PEBR, without --set".to_string(),
+        );
+    }
+    // The perf events and the signals of the stalls follow the first thread of a worker.
+    if m.is_present("thread churn")
+        && (m.is_present("perf") || m.value_of("stall threads") != Some("0"))
+    {
+        return Err("--thread-churn cannot be combined with --perf or --stall-threads".to_string());
+    }
+    // An unprivileged thread may only lower its priority, i.e., raise its nice level.
+    let low_prio_nice = value_t!(m, "low prio nice", i32).unwrap_or_else(|e| e.exit());
+    if !(1..=19).contains(&low_prio_nice) {
+        return Err("--low-prio-nice should be in [1, 19]".to_string());
+    }
+    let backoff_spin = value_t!(m, "backoff spin", u32).unwrap_or_else(|e| e.exit());
+    if backoff_spin > backoff::MAX_SPIN_LIMIT {
+        return Err(format!(
+            "--backoff-spin should be at most {}",
+            backoff::MAX_SPIN_LIMIT
+        ));
+    }
+    if m.is_present("transfers") && (port.transfers.is_none() || (mm != MM::NR && mm != MM::EBR)) {
+        return Err(format!(
+            "--transfers supports only {}, with NR and EBR",
+            supporting(&structures, |e| e
+                .port(Scheme::EBR)
+                .map_or(false, |p| p.transfers.is_some()))
+        ));
+    }
+    // The shards pin their own collectors.
+    if ds == DS("ShardedHashMap") && mm != MM::EBR {
+        return Err("ShardedHashMap supports only EBR".to_string());
+    }
+    if m.value_of("reclaim") == Some("off") && mm == MM::NR {
+        return Err("--reclaim off needs a reclamation scheme, EBR, PEBR or RC".to_string());
+    }
+    let slow_cmp = value_t!(m, "slow cmp", u64).unwrap_or_else(|e| e.exit());
+    if m.is_present("compare and delete")
+        && (port.compare_and_delete.is_none() || m.value_of("mark") == Some("cas") || slow_cmp > 0)
+    {
+        return Err(format!(
+            "--compare-and-delete supports only {}, with NR, EBR and PEBR, without --mark cas \
+             and --slow-cmp",
+            supporting(&structures, |e| e
+                .port(Scheme::EBR)
+                .map_or(false, |p| p.compare_and_delete.is_some()))
+        ));
+    }
+    if slow_cmp > 0 && (port.slow_key.is_none() || m.is_present("set")) {
+        return Err(format!(
+            "--slow-cmp supports only the maps with SlowKeys, without --set, and {} has none \
+             with {}",
+            ds, mm
+        ));
+    }
+    if ds == DS("Ffi") && (!m.is_present("ffi lib") || mm != MM::NR) {
+        return Err(
+            "Ffi needs --ffi-lib, and supports only NR, since the map reclaims its own memory"
+                .to_string(),
+        );
+    }
+    if m.is_present("epoch latency")
+        && ((mm != MM::EBR && mm != MM::PEBR) || ds == DS("ShardedHashMap"))
+    {
+        return Err(
+            "--epoch-latency supports only EBR and PEBR, except for ShardedHashMap".to_string(),
+        );
+    }
+    if m.is_present("scan")
+        && (port.scan.is_none() || m.is_present("set") || slow_cmp > 0 || m.is_present("history"))
+    {
+        return Err(format!(
+            "--scan supports only {}, with NR, EBR and PEBR, without --set, --slow-cmp and \
+             --history",
+            supporting(&structures, |e| e
+                .port(Scheme::EBR)
+                .map_or(false, |p| p.scan.is_some()))
+        ));
+    }
+    let instances = value_t!(m, "instances", usize).unwrap_or_else(|e| e.exit());
+    if instances > 1
+        && (entry.kind != Kind::Map
+            || m.is_present("set")
+            || slow_cmp > 0
+            || m.is_present("scan")
+            || m.value_of("mark") == Some("cas")
+            || m.is_present("history"))
+    {
+        return Err(
+            "--instances supports only the maps of this crate except ShardedHashMap, \
+             without --set, --slow-cmp, --scan, --mark cas and --history"
+                .to_string(),
+        );
+    }
+    // The threads of KCASTree leak the garbage in their limbo when they exit.
+    if m.is_present("audit")
+        && (!map_workload || ds == DS("KCASTree") || m.is_present("set") || m.is_present("scan"))
+    {
+        return Err("--audit supports only maps except ShardedHashMap, KCASTree and Ffi, without --set and \
+             --scan".to_string());
+    }
+    if m.is_present("checksum")
+        && (!ds.is_map()
+            || entry.kind == Kind::External
+            || m.is_present("scan")
+            || m.value_of("mark") == Some("cas")
+            || !(m.is_present("ops per thread") || m.is_present("ycsb run")))
+    {
+        return Err(
+            "--checksum supports only the maps of this crate, without --scan and --mark cas, and \
+             needs --ops-per-thread or --ycsb-run"
+                .to_string(),
+        );
+    }
+    if m.value_of("mark") == Some("cas")
+        && (port.cas_mark.is_none() || m.is_present("set") || slow_cmp > 0 || m.is_present("scan"))
+    {
+        return Err(format!(
+            "--mark cas supports only {}, with NR, EBR and PEBR, without --set, --slow-cmp and \
+             --scan",
+            supporting(&structures, |e| e
+                .port(Scheme::EBR)
+                .map_or(false, |p| p.cas_mark.is_some()))
+        ));
+    }
+    Ok(())
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn setup(m: &ArgMatches, mm: MM) -> (Config, Writer<File>) {
+    let ds = value_t!(m, "data structure", DS).unwrap();
+    let mut groups = Vec::new();
+    for name in &["readers", "writers"] {
+        if let Some(group) = m.value_of(name) {
+            groups.push(ThreadGroup::parse(name, group).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(2);
+            }));
+        }
+    }
+    check_mm(m, mm).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    if load_ordering::NEXT != Ordering::Acquire {
+        println!(
+            "NOTE: The Harris lists load their next fields with {:?}, for experiments only.",
+            load_ordering::NEXT
+        );
+    }
+    if ds == DS("VersionList") && !tagged::dwcas_available() {
+        eprintln!("VersionList needs double-width CAS, which this processor lacks");
+        process::exit(2);
+    }
+    let threads = if groups.is_empty() {
+        value_t!(m, "threads", usize).unwrap()
+    } else {
+        groups.iter().map(|g| g.threads).sum()
+    };
+    if ds == DS("MpscQueue") && threads < 2 {
+        eprintln!("MpscQueue needs at least 2 threads, a consumer and a producer");
+        process::exit(2);
+    }
+    let preset = m
+        .value_of("preset")
+        .map(|_| value_t!(m, "preset", Preset).unwrap_or_else(|e| e.exit()));
+    if preset == Some(Preset::GarbageBomb) {
+        if !ds.is_map() || threads < 2 || mm == MM::NR {
+            eprintln!("GarbageBomb needs a map, at least 2 threads and a reclamation scheme");
+            process::exit(2);
+        }
+        // The groups only report the throughput of the readers and of the bomber separately.
+        groups = vec![
+            ThreadGroup::parse("readers", &format!("{}:get=100", threads - 1)).unwrap(),
+            ThreadGroup::parse("writers", "1:insert=1,remove=1").unwrap(),
+        ];
+    }
+    let storm = if preset == Some(Preset::DeleteStorm) {
+        let size = value_t!(m, "storm size", f64).unwrap_or_else(|e| e.exit());
+        if ds.kind() != Kind::Map
+            || mm == MM::NR
+            || m.is_present("scan")
+            || m.value_of("mark") == Some("cas")
+            || value_t!(m, "slow cmp", u64).unwrap_or_else(|e| e.exit()) > 0
+            || value_t!(m, "instances", usize).unwrap_or_else(|e| e.exit()) > 1
+            || !(size > 0.0 && size <= MAX_STORM_SIZE)
+        {
+            eprintln!(
+                "DeleteStorm needs a map of this crate except ShardedHashMap, a reclamation \
+                 scheme and a --storm-size in (0, {}], without --scan, --mark cas, --slow-cmp \
+                 and --instances",
+                MAX_STORM_SIZE
+            );
+            process::exit(2);
+        }
+        Some(Storm::new((size * 1e6) as usize))
+    } else {
+        None
+    };
+    let ycsb = match (m.value_of("ycsb load"), m.value_of("ycsb run")) {
+        (Some(load), Some(run)) => {
+            if !ds.is_map() {
+                eprintln!("--ycsb-load and --ycsb-run support only maps");
+                process::exit(2);
+            }
+            let trace = Trace::open(Path::new(load), Path::new(run)).unwrap_or_else(|e| {
+                eprintln!("ycsb: cannot read the trace: {}", e);
+                process::exit(2);
+            });
+            println!(
+                "ycsb: {} keys, {} load operations, {} run operations ({} scans skipped)",
+                trace.keys.len(),
+                trace.load.len(),
+                trace.run.len(),
+                trace.scans
+            );
+            Some(trace)
+        }
+        _ => None,
+    };
+    let ycsb_run = m.value_of("ycsb run").unwrap_or("").to_string();
+    let set = m.is_present("set");
+    let scan = m
+        .value_of("scan")
+        .map(|_| value_t!(m, "scan", usize).unwrap_or_else(|e| e.exit()));
+    let cas_mark = m.value_of("mark").unwrap() == "cas";
+    let non_coop = value_t!(m, "non-coop", usize).unwrap();
+    let get_rate = value_t!(m, "get rate", usize).unwrap();
+    // The storm inserts its keys itself.
+    let range = storm
+        .as_ref()
+        .map_or_else(|| value_t!(m, "range", usize).unwrap(), |s| s.size);
+    let long_lived = m.value_of("long lived").map(|_| {
+        let percent = value_t!(m, "long lived", f64).unwrap_or_else(|e| e.exit());
+        if !ds.is_map() || !(0.0..100.0).contains(&percent) {
+            eprintln!("--long-lived needs a map and a percentage less than 100");
+            process::exit(2);
+        }
+        percent / 100.0
+    });
+    let long_lived_keys = match long_lived {
+        Some(fraction) => (0..range).filter(|&k| is_long_lived(fraction, k)).collect(),
+        None => Vec::new(),
+    };
+    // Half of the other keys are in the map in the steady state.
+    let prefill = match (&ycsb, &storm) {
+        (Some(trace), _) => trace.load.len(),
+        (None, Some(_)) => 0,
+        (None, None) => long_lived_keys.len() + (range - long_lived_keys.len()) / 2,
+    };
+    let monotonic = if preset == Some(Preset::Monotonic) {
+        if !ds.is_map()
+            || ds.kind() == Kind::External
+            || long_lived.is_some()
+            || value_t!(m, "key size", KeySize).unwrap_or_else(|e| e.exit()) != KeySize::Fixed
+            || m.is_present("prefill file")
+            || m.is_present("transfers")
+            || m.is_present("checksum")
+        {
+            eprintln!(
+                "Monotonic needs a map of this crate and --key-size fixed, without --long-lived, \
+                 --prefill-file, --transfers and --checksum"
+            );
+            process::exit(2);
+        }
+        Some(Monotonic::new(prefill))
+    } else {
+        None
+    };
+    let key_dist = Uniform::from(0..range);
+    let key_size = value_t!(m, "key size", KeySize).unwrap_or_else(|e| e.exit());
+    let think_time = value_t!(m, "think time", ThinkTime).unwrap_or_else(|e| e.exit());
+    let ffi_lib = m.value_of("ffi lib").map(|l| l.to_string());
+    let drop_cost = value_t!(m, "drop cost", u64).unwrap_or_else(|e| e.exit());
+    drop_cost::set_cost(drop_cost);
+    let seed = if m.is_present("seed") {
+        value_t!(m, "seed", u64).unwrap_or_else(|e| e.exit())
+    } else {
+        rand::random()
+    };
+    let slow_cmp = value_t!(m, "slow cmp", u64).unwrap_or_else(|e| e.exit());
+    slow_key::set_delay(slow_cmp);
+    let interval = value_t!(m, "interval", u64).unwrap();
+    let sampling_period = value_t!(m, "sampling period", u64).unwrap();
+    let sampling = sampling_period > 0 && cfg!(all(not(feature = "sanitize"), target_os = "linux"));
+    let ops_per_cs = match value_t!(m, "ops per cs", usize).unwrap() {
+        1 => OpsPerCs::One,
+        4 => OpsPerCs::Four,
+        _ => panic!("ops_per_cs should be one or four"),
+    };
+    let extend_read_sections = m.is_present("extend read sections");
+    let stall_threads = value_t!(m, "stall threads", usize).unwrap_or_else(|e| e.exit());
+    let stall_period = value_t!(m, "stall period", u64).unwrap_or_else(|e| e.exit());
+    let stall_duration = value_t!(m, "stall duration", u64).unwrap_or_else(|e| e.exit());
+    if stall_threads > 0 && stall_duration >= stall_period {
+        eprintln!("--stall-duration should be less than --stall-period");
+        process::exit(2);
+    }
+    let low_prio_threads = value_t!(m, "low prio threads", usize).unwrap_or_else(|e| e.exit());
+    if low_prio_threads > threads {
+        eprintln!("--low-prio-threads should be at most the workers");
+        process::exit(2);
+    }
+    let low_prio_nice = value_t!(m, "low prio nice", i32).unwrap_or_else(|e| e.exit());
+    let shards = value_t!(m, "shards", usize).unwrap_or_else(|e| e.exit());
+    if shards == 0 {
+        eprintln!("--shards should be positive");
+        process::exit(2);
+    }
+    let instances = value_t!(m, "instances", usize).unwrap_or_else(|e| e.exit());
+    if instances == 0 {
+        eprintln!("--instances should be positive");
+        process::exit(2);
+    }
+    let stages = value_t!(m, "stages", usize).unwrap_or_else(|e| e.exit());
+    if stages == 0 || (ds == DS("Pipeline") && threads < stages) {
+        eprintln!("--stages should be positive, and Pipeline needs a worker for each stage");
+        process::exit(2);
+    }
+    let pipeline_stack = m.value_of("pipeline").unwrap() == "stack";
+    let interval_len = value_t!(m, "interval len", usize).unwrap_or_else(|e| e.exit());
+    if interval_len == 0 {
+        eprintln!("--interval-len should be positive");
+        process::exit(2);
+    }
+    let shield_cache = value_t!(m, "shield cache", usize).unwrap_or_else(|e| e.exit());
+    pebr::shield_cache::set_size(shield_cache);
+    let shield_budget = value_t!(m, "shield budget", usize).unwrap_or_else(|e| e.exit());
+    pebr::shield_pool::set_budget(shield_budget);
+    let pinning = if m.is_present("pin threads") {
+        let cpus = available_cpus();
+        if cpus.is_empty() {
+            None
+        } else {
+            op_stats::enable();
+            Some(Pinning {
+                cpus,
+                workers: Mutex::new(Vec::new()),
+            })
+        }
+    } else {
+        None
+    };
+    let gc_policy = value_t!(m, "gc policy", GcPolicy).unwrap_or_else(|e| e.exit());
+    let gc_threshold = value_t!(m, "gc threshold", usize).unwrap_or_else(|e| e.exit());
+    let reclaim = m.value_of("reclaim").unwrap() == "on";
+    if !reclaim {
+        pebr_benchmark::reclaim::disable();
+    }
+    let harris_stats = m.is_present("harris stats");
+    if harris_stats {
+        harris_stats::enable();
+    }
+    if m.is_present("harris max batch") {
+        let max_batch = value_t!(m, "harris max batch", usize).unwrap_or_else(|e| e.exit());
+        harris_stats::set_max_batch(max(max_batch, 1));
+    }
+    if m.is_present("stall event") {
+        let us = value_t!(m, "stall event", u64).unwrap_or_else(|e| e.exit());
+        op_latency::set_threshold(us * 1000);
+    }
+    let restart_stats = m.is_present("restart stats");
+    if restart_stats {
+        restarts::enable();
+        if [DS("NMTree"), DS("HashTreeMap")].contains(&ds) {
+            seek_stats::enable();
+        }
+    }
+    let backoff_spin = value_t!(m, "backoff spin", u32).unwrap_or_else(|e| e.exit());
+    backoff::set_spin_limit(backoff_spin);
+    let seek_reuse = m.is_present("seek reuse");
+    seek_stats::set_reuse(seek_reuse);
+    let thread_churn = if m.is_present("thread churn") {
+        let ms = value_t!(m, "thread churn", u64).unwrap_or_else(|e| e.exit());
+        if ms == 0 {
+            eprintln!("--thread-churn should be positive");
+            process::exit(2);
+        }
+        Some(Duration::from_millis(ms))
+    } else {
+        None
+    };
+    if m.is_present("restart bound") {
+        let bound = value_t!(m, "restart bound", usize).unwrap_or_else(|e| e.exit());
+        restarts::set_bound(max(bound, 1));
+    }
+    if m.is_present("validate prev") {
+        restarts::validate_always();
+    }
+    let perf = m.is_present("perf");
+    if perf {
+        perf_counters::enable();
+    }
+    let epoch_latency = m.is_present("epoch latency");
+    if epoch_latency {
+        epoch_latency::enable();
+    }
+    let bonsai_stats = m.is_present("bonsai stats");
+    if bonsai_stats {
+        bonsai_stats::enable();
+    }
+    let bonsai_weight = value_t!(m, "bonsai weight", usize).unwrap_or_else(|e| e.exit());
+    if bonsai_weight == 0 {
+        eprintln!("--bonsai-weight should be positive");
+        process::exit(2);
+    }
+    bonsai_stats::set_weight(bonsai_weight);
+    let remove_phases = m.is_present("remove phases");
+    if remove_phases {
+        remove_phases::enable();
+    }
+    let tree_buckets = value_t!(m, "tree buckets", usize).unwrap_or_else(|e| e.exit());
+    if tree_buckets == 0 {
+        eprintln!("--tree-buckets should be positive");
+        process::exit(2);
+    }
+    #[cfg(feature = "hash-maps")]
+    ebr::hash_tree_map::set_buckets(tree_buckets);
+    let skiplist_p = value_t!(m, "skiplist p", f64).unwrap_or_else(|e| e.exit());
+    let skiplist_max_level = value_t!(m, "skiplist max level", usize).unwrap_or_else(|e| e.exit());
+    if !(0.0..1.0).contains(&skiplist_p)
+        || skiplist_max_level == 0
+        || skiplist_max_level > skiplist_stats::MAX_HEIGHT
+    {
+        eprintln!("--skiplist-p should be in [0, 1) and --skiplist-max-level in [1, 32]");
+        process::exit(2);
+    }
+    skiplist_stats::set_p(skiplist_p);
+    skiplist_stats::set_max_level(skiplist_max_level);
+    if ds == DS("SkipList") {
+        skiplist_stats::enable();
+    }
+    #[cfg(feature = "hash-maps")]
+    pebr::hash_tree_map::set_buckets(tree_buckets);
+    let hasher = value_t!(m, "hasher", HashFunction).unwrap_or_else(|e| e.exit());
+    if hasher == HashFunction::AHash && !cfg!(feature = "ahash") {
+        eprintln!("--hasher ahash needs the ahash feature");
+        process::exit(2);
+    }
+    hashing::set_function(hasher, seed);
+    if ds == DS("HashMap") {
+        hashing::enable();
+    }
+    let capacity = m.value_of("capacity").map(|_| {
+        value_t!(m, "capacity", usize)
+            .ok()
+            .filter(|&c| c > 0)
+            .unwrap_or_else(|| {
+                eprintln!("--capacity must be a positive number of entries");
+                process::exit(2);
+            })
+    });
+    let eviction = value_t!(m, "eviction", Eviction).unwrap_or_else(|e| e.exit());
+    eviction::set_capacity(capacity, eviction);
+    // The keys are in the range, so the map holds at most `range` entries.
+    #[cfg(feature = "hash-maps")]
+    ebr::cuckoo_hash_map::set_capacity(range);
+    let garbage_bomb = preset == Some(Preset::GarbageBomb);
+    let tui = m.is_present("tui");
+    let progress = if m.is_present("progress") || garbage_bomb || tui {
+        op_stats::enable();
+        Some(Progress::new(threads))
+    } else {
+        None
+    };
+    let adaptive_threshold = value_t!(m, "adaptive threshold", usize).unwrap_or_else(|e| e.exit());
+    harris_stats::set_adaptive_threshold(adaptive_threshold);
+    let time_series = m
+        .value_of("time series")
+        .map(|t| t.to_string())
+        .or_else(|| {
+            if garbage_bomb {
+                Some(format!("results/{}_{}_garbage.csv", ds, mm))
+            } else if storm.is_some() {
+                Some(format!("results/{}_{}_storm.csv", ds, mm))
+            } else {
+                None
+            }
+        });
+    let time_series_period = value_t!(m, "time series period", u64).unwrap_or_else(|e| e.exit());
+    let history = m.value_of("history").map(|h| h.to_string());
+    let history_sample = value_t!(m, "history sample", usize).unwrap();
+    let history_capacity = value_t!(m, "history capacity", usize).unwrap();
+    let checksum = m.is_present("checksum");
+    let duration = Duration::from_secs(interval);
+    let ops_per_thread = m
+        .value_of("ops per thread")
+        .map(|_| value_t!(m, "ops per thread", u64).unwrap_or_else(|e| e.exit()));
+    let baseline = m.value_of("baseline").map(|dir| {
+        Store::open(Path::new(dir)).unwrap_or_else(|e| {
+            eprintln!("baseline: cannot open {}: {}", dir, e);
+            process::exit(2);
+        })
+    });
+    let regression_threshold =
+        value_t!(m, "regression threshold", f64).unwrap_or_else(|e| e.exit());
+
+    let op_weights = match get_rate {
+        0 => &[0, 1, 1],
+        1 => &[2, 1, 1],
+        _ => &[18, 1, 1],
+    };
+    let op_dist = WeightedIndex::new(op_weights).unwrap();
+
+    let mem_sampler = MemSampler::new();
+    let environment = Environment::capture();
+    let mut config = Config {
+        ds,
+        mm,
+        threads,
+
+        aux_thread: if sampling || non_coop > 0 || epoch_latency {
+            1
+        } else {
+            0
+        },
+        aux_thread_period: Duration::from_millis(1),
+        non_coop,
+        non_coop_period: match non_coop {
+            1 => Duration::from_millis(1),
+            2 => Duration::from_millis(10),
+            // No repin if -n0 or -n3
+            _ => Duration::from_secs(interval),
+        },
+        stall_threads,
+        stall_period: Duration::from_millis(stall_period),
+        stall_duration: Duration::from_millis(stall_duration),
+        low_prio_threads,
+        low_prio_nice,
+        recovery: Mutex::new(Recovery::default()),
+        shards,
+        instances,
+        stages,
+        pipeline_stack,
+        skiplist_p,
+        skiplist_max_level,
+        interval_len,
+        sampling,
+        sampling_period: Duration::from_millis(sampling_period),
+
+        get_rate,
+        op_dist,
+        groups,
+        pinning,
+        gc_policy,
+        gc_threshold,
+        reclaim,
+        range,
+        key_dist,
+        key_size,
+        think_time,
+        ffi_lib,
+        seed,
+        slow_cmp,
+        prefill,
+        interval,
+        duration,
+        ops_per_thread,
+        ycsb,
+        ycsb_run,
+        preset,
+        storm,
+        monotonic,
+        long_lived,
+        long_lived_keys,
+        prefill_keys: Vec::new(),
+        ops_per_cs,
+        extend_read_sections,
+        shield_cache,
+        harris_stats,
+        restart_stats,
+        bonsai_stats,
+        remove_phases,
+        epoch_latency,
+        perf,
+        audit: m.is_present("audit"),
+        set,
+        remove_get: m.is_present("remove get"),
+        unguarded_reads: m.is_present("unguarded reads"),
+        transfers: m.is_present("transfers"),
+        hasher,
+        capacity,
+        eviction,
+        seek_reuse,
+        thread_churn,
+        scan,
+        cas_mark,
+        compare_and_delete: m.is_present("compare and delete"),
+        backoff_spin,
+
+        outcomes: Mutex::new(Outcomes::default()),
+        progress,
+        #[cfg(feature = "tui")]
+        tui,
+        time_series,
+        time_series_period: Duration::from_millis(time_series_period),
+
+        history,
+        history_sample,
+        history_capacity,
+        check: false,
+        checksum,
+        checksums: Mutex::new(Vec::new()),
+
+        baseline,
+        regression_threshold,
+
+        mem_sampler,
+        environment,
+    };
+    let prefill_file = m.value_of("prefill file");
+    if prefill_file.is_some() && !config.ds.is_map() && config.ds != DS("IntervalMap") {
+        eprintln!("--prefill-file supports only maps");
+        process::exit(2);
+    }
+    if m.is_present("dry run") {
+        print_plan(&config);
+        process::exit(0);
+    }
+    if let Some(path) = prefill_file {
+        config.prefill_keys = load_prefill_keys(&config, Path::new(path));
+    }
+    if let Some(monotonic) = &config.monotonic {
+        let keys = monotonic.prefill_keys(&mut config.rng(config.threads));
+        config.prefill_keys = keys;
+    }
+    let output = open_output(m, &config.ds);
+    (config, output)
+}
+
+/// Opens the CSV file of the results, and writes its header if the file is new or empty. Exits if
+/// the file has other columns, e.g. those of an older version, since the records would not line up
+/// with them.
+fn open_output(m: &ArgMatches, ds: &DS) -> Writer<File> {
+    let output_name = &m
+        .value_of("output")
+        .map_or(format!("results/{}.csv", ds), |o| o.to_string());
+    create_dir_all("results").unwrap();
+    // NOTE: `write_record` on `bench`
+    let mut header = vec![
+        // "timestamp",
+        "ds",
+        "mm",
+        "threads",
+        "sampling_period",
+        "non_coop",
+        "get_rate",
+        "ops_per_cs",
+        "throughput",
+        "peak_mem",
+        "avg_mem",
+        "build_throughput",
+        "extend_read_sections",
+        "key_size",
+        "ops_per_thread",
+        "shield_cache",
+        "stall_threads",
+        "stall_period",
+        "stall_duration",
+        "groups",
+        "pin_threads",
+        "gc_policy",
+        "gc_threshold",
+        "ycsb",
+        "preset",
+        "insert_fail_rate",
+        "remove_fail_rate",
+        "long_lived",
+        "set",
+        "prefill",
+        "slow_cmp",
+        "shards",
+        "scan",
+        "reclaim",
+        "interval_len",
+        "mark",
+        "think_time",
+        "ffi_lib",
+        "seed",
+        "failure",
+        "max_op_latency_us",
+        "stall_events",
+        "instances",
+        "stages",
+        "pipeline",
+        "skiplist_p",
+        "skiplist_max_level",
+        "checksum",
+        "recovery_ms",
+        "remove_get",
+        "unguarded_reads",
+        "hasher",
+        "transfers",
+        "capacity",
+        "eviction",
+        "seek_reuse",
+        "thread_churn_ms",
+        "low_prio_threads",
+        "low_prio_nice",
+        "compare_and_delete",
+        "backoff_spin",
+    ];
+    header.extend(Environment::COLUMNS.iter());
+    let f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .append(true)
+        .create(true)
+        .open(output_name)
+        .unwrap();
+    let columns = csv::Reader::from_path(output_name)
+        .and_then(|mut reader| reader.headers().map(|h| h.clone()))
+        .unwrap_or_default();
+    let mut output = csv::Writer::from_writer(f);
+    if columns.is_empty() {
+        output.write_record(&header).unwrap();
+        output.flush().unwrap();
+    } else if !columns.iter().eq(header.iter().copied()) {
+        eprintln!(
+            "{} has other columns than the results of this version, so the results would not \
+             line up with them; write them to another file with -o",
+            output_name
+        );
+        process::exit(2);
+    }
+    output
+}
+
+/// Prints the configuration that a run would measure, and how long its workers would run, for
+/// `--dry-run`. The last line is read by `matrix --dry-run`.
+fn print_plan(config: &Config) {
+    println!("dry run: {}", config.key());
+    println!(
+        "dry run: {} workers after a prefill of {} keys",
+        config.threads, config.prefill
+    );
+    if config.fixed_work() {
+        println!("dry run: duration unknown (fixed work)");
+    } else {
+        println!("dry run: duration {} s", config.duration.as_secs());
+    }
+}
+
+/// Loads the keys of the prefill from `path`, or samples them and saves them to `path` if it does
+/// not exist.
+fn load_prefill_keys(config: &Config, path: &Path) -> Vec<usize> {
+    if path.exists() {
+        let loaded = PrefillKeys::load(path).unwrap_or_else(|e| {
+            eprintln!("--prefill-file: cannot read {}: {}", path.display(), e);
+            process::exit(2);
+        });
+        if loaded.range != config.range
+            || loaded.long_lived != config.long_lived_keys.len()
+            || loaded.keys.len() != config.prefill
+        {
+            eprintln!(
+                "--prefill-file: {} holds {} keys of the range {} with {} long-lived keys, \
+                 not {} keys of the range {} with {} long-lived keys",
+                path.display(),
+                loaded.keys.len(),
+                loaded.range,
+                loaded.long_lived,
+                config.prefill,
+                config.range,
+                config.long_lived_keys.len()
+            );
+            process::exit(2);
+        }
+        println!(
+            "prefill: {} keys loaded from {} (seed {})",
+            loaded.keys.len(),
+            path.display(),
+            loaded.seed
+        );
+        return loaded.keys;
+    }
+    // The same keys as the prefill samples without the file.
+    let mut rng = config.rng(config.threads);
+    let keys = PrefillKeys {
+        range: config.range,
+        long_lived: config.long_lived_keys.len(),
+        seed: config.seed,
+        keys: (0..config.prefill)
+            .map(|i| config.prefill_key(i, &mut rng))
+            .collect(),
+    };
+    keys.save(path).unwrap_or_else(|e| {
+        eprintln!("--prefill-file: cannot write {}: {}", path.display(), e);
+        process::exit(2);
+    });
+    println!(
+        "prefill: {} keys saved to {}",
+        keys.keys.len(),
+        path.display()
+    );
+    keys.keys
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn bench<N: Unsigned>(config: &Config, output: &mut Writer<File>) -> Perf {
+    println!(
+        "{}: {}, {} threads, n{}, c{}, g{}",
+        config.ds, config.mm, config.threads, config.non_coop, config.ops_per_cs, config.get_rate
+    );
+    if let Some(storm) = &config.storm {
+        storm.reset(config.mem_sampler.sample());
+    }
+    if let Some(monotonic) = &config.monotonic {
+        monotonic.reset();
+    }
+    if config.audit {
+        audit::reset();
+    }
+    // A panic of a worker stops the other workers, and the run is recorded as failed so that the
+    // rest of a sweep goes on.
+    let registry = Registry::new();
+    let perf = panic::catch_unwind(AssertUnwindSafe(|| {
+        bench_registered::<N>(config, &registry)
+    }))
+    .and_then(|perf| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            audit_dropped(config);
+            perf
+        }))
+    });
+    let (perf, failure) = match perf {
+        Ok(perf) => {
+            if let Some(storm) = &config.storm {
+                storm.report();
+            }
+            if let Some(monotonic) = &config.monotonic {
+                monotonic.report();
+            }
+            (perf, None)
+        }
+        Err(_) => {
+            let failure = take_failure().unwrap_or_else(|| "unknown panic".to_string());
+            println!(
+                "FAILED: {} {} {} threads (seed {}): {}",
+                config.ds, config.mm, config.threads, config.seed, failure
+            );
+            FAILED.store(true, Ordering::Relaxed);
+            let perf = Perf {
+                ops_per_sec: 0,
+                peak_mem: 0,
+                avg_mem: 0,
+                build_ops_per_sec: 0,
+            };
+            (perf, Some(failure))
+        }
+    };
+    let outcomes = mem::replace(&mut *config.outcomes.lock().unwrap(), Outcomes::default());
+    let checksum = match failure {
+        None if config.checksum => Some(Checksum::combine(&config.checksums.lock().unwrap())),
+        _ => None,
+    };
+    let latency = if op_latency::enabled() {
+        Some(op_latency::take())
+    } else {
+        None
+    };
+    if config.check {
+        return perf;
+    }
+    if interrupted() {
+        println!("interrupted: the results cover the completed part of the run");
+    }
+    let mut record = vec![
+        // chrono::Local::now().to_rfc3339(),
+        config.ds.to_string(),
+        config.mm.to_string(),
+        config.threads.to_string(),
+        config.sampling_period.as_millis().to_string(),
+        config.non_coop.to_string(),
+        config.get_rate.to_string(),
+        config.ops_per_cs.to_string(),
+        perf.ops_per_sec.to_string(),
+        perf.peak_mem.to_string(),
+        perf.avg_mem.to_string(),
+        perf.build_ops_per_sec.to_string(),
+        config.extend_read_sections.to_string(),
+        config.key_size.to_string(),
+        config
+            .ops_per_thread
+            .map_or("".to_string(), |n| n.to_string()),
+        config.shield_cache.to_string(),
+        config.stall_threads.to_string(),
+        config.stall_period.as_millis().to_string(),
+        config.stall_duration.as_millis().to_string(),
+        config.groups(),
+        config.pinning.is_some().to_string(),
+        config.gc_policy.to_string(),
+        config.gc_threshold.to_string(),
+        config.ycsb_run.clone(),
+        config.preset.map_or("".to_string(), |p| p.to_string()),
+        outcomes
+            .fail_rate(Op::Insert)
+            .map_or("".to_string(), |r| r.to_string()),
+        outcomes
+            .fail_rate(Op::Remove)
+            .map_or("".to_string(), |r| r.to_string()),
+        config.long_lived.map_or("".to_string(), |f| f.to_string()),
+        config.set.to_string(),
+        config.prefill.to_string(),
+        config.slow_cmp.to_string(),
+        config.shards.to_string(),
+        config.scan.map_or("".to_string(), |n| n.to_string()),
+        config.reclaim.to_string(),
+        config.interval_len.to_string(),
+        if config.cas_mark { "cas" } else { "fetch-or" }.to_string(),
+        config.think_time.to_string(),
+        config.ffi_lib.clone().unwrap_or_default(),
+        config.seed.to_string(),
+        failure.clone().unwrap_or_default(),
+        latency.map_or("".to_string(), |l| (l.max_ns / 1000).to_string()),
+        latency.map_or("".to_string(), |l| l.stalls.to_string()),
+        config.instances.to_string(),
+        config.stages.to_string(),
+        if config.pipeline_stack {
+            "stack"
+        } else {
+            "queue"
+        }
+        .to_string(),
+        config.skiplist_p.to_string(),
+        config.skiplist_max_level.to_string(),
+        checksum.map_or("".to_string(), |c| format!("{:016x}", c)),
+        config
+            .recovery
+            .lock()
+            .unwrap()
+            .mean_ms()
+            .map_or("".to_string(), |ms| format!("{:.3}", ms)),
+        config.remove_get.to_string(),
+        config.unguarded_reads.to_string(),
+        config.hasher.to_string(),
+        config.transfers.to_string(),
+        config.capacity.map_or(String::new(), |c| c.to_string()),
+        config.eviction.to_string(),
+        config.seek_reuse.to_string(),
+        config
+            .thread_churn
+            .map_or(String::new(), |p| p.as_millis().to_string()),
+        config.low_prio_threads.to_string(),
+        config.low_prio_nice.to_string(),
+        config.compare_and_delete.to_string(),
+        config.backoff_spin.to_string(),
+    ];
+    record.extend(config.environment.values());
+    output.write_record(&record).unwrap();
+    output.flush().unwrap();
+    if failure.is_some() {
+        return perf;
+    }
+    println!(
+        "ops/s: {}, peak mem: {}, avg_mem: {}, build ops/s: {}",
+        perf.ops_per_sec, perf.peak_mem, perf.avg_mem, perf.build_ops_per_sec
+    );
+    if config.ds.is_map() {
+        let percent = |op| {
+            outcomes
+                .fail_rate(op)
+                .map_or("-".to_string(), |r| format!("{:.2}%", r * 100.0))
+        };
+        println!(
+            "failed inserts: {} of {}, failed removes: {} of {}",
+            percent(Op::Insert),
+            outcomes.ops[Op::Insert as usize],
+            percent(Op::Remove),
+            outcomes.ops[Op::Remove as usize]
+        );
+    }
+    if let Some(checksum) = checksum {
+        print_checksums(config, checksum);
+    }
+    for group in &config.groups {
+        let (ops, elapsed) =
+            mem::replace(&mut *group.stats.lock().unwrap(), (0, Duration::default()));
+        print!(
+            "{}: {} threads, ops/s: {}",
+            group.name,
+            group.threads,
+            config.throughput(ops, elapsed)
+        );
+        match &group.limit {
+            Some(limit) => println!(" (limit: {})", limit.rate()),
+            None => println!(),
+        }
+    }
+    if let Some(pinning) = &config.pinning {
+        pinning.report(config);
+    }
+    if config.harris_stats {
+        print_harris_stats();
+    }
+    if config.restart_stats {
+        print_restart_stats();
+        if [DS("NMTree"), DS("HashTreeMap")].contains(&config.ds) {
+            print_seek_stats(config.mm);
+        }
+    }
+    if config.bonsai_stats {
+        print_bonsai_stats();
+    }
+    if config.remove_phases {
+        print_remove_phases();
+    }
+    if config.ds == DS("SkipList") {
+        print_skiplist_stats();
+    }
+    if config.ds == DS("HashMap") {
+        print_hash_chains();
+    }
+    if config.capacity.is_some() {
+        println!("eviction: {} entries evicted", eviction::take());
+    }
+    if config.epoch_latency {
+        print_epoch_latency(config.mm);
+    }
+    if config.perf {
+        print_perf_counters();
+    }
+    if let Some(latency) = latency {
+        println!(
+            "op latency ({}): max {} us, {} stall events",
+            config.mm,
+            latency.max_ns / 1000,
+            latency.stalls
+        );
+    }
+    if drop_cost::cost() > 0 {
+        let (workers, others) = drop_cost::take();
+        println!(
+            "drop cost: {} destructions of {} ns by the workers, {} by the other threads",
+            workers,
+            drop_cost::cost(),
+            others
+        );
+    }
+    if config.ds == DS("AdaptiveList") {
+        println!(
+            "adaptive cleanup: {} switches",
+            harris_stats::take_adaptive_switches()
+        );
+    }
+    if config.mm == MM::PEBR && config.shield_cache > 0 {
+        let (hits, misses) = pebr::shield_cache::take_stats();
+        println!(
+            "shield cache: {} hits, {} misses ({:.1}% hit rate)",
+            hits,
+            misses,
+            100.0 * hits as f64 / max(hits + misses, 1) as f64
+        );
+    }
+    if config.mm == MM::PEBR {
+        print_shield_pool_stats();
+    }
+    if let Some(store) = &config.baseline {
+        if interrupted() {
+            println!("baseline: the interrupted run is not recorded");
+        } else {
+            record_result(config, store, &perf);
+        }
+    }
+    perf
+}
+
+/// Benchmarks the registered maps with the memory manager of the configuration.
+struct Bench<'a, N> {
+    config: &'a Config,
+    _marker: PhantomData<N>,
+}
+
+impl<'a, N: Unsigned> Driver for Bench<'a, N> {
+    type Value = Value;
+    type Output = Perf;
+
+    fn ebr_map<M: ebr::ConcurrentMap<String, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::map::ebr::<String, M, N>(self.config, prefill)
+    }
+    fn ebr_set<S: ebr::ConcurrentSet<String> + Send + Sync>(&self, prefill: Prefill) -> Perf {
+        adapter::map::set_ebr::<S, N>(self.config, prefill)
+    }
+    fn ebr_slow_key<M: ebr::ConcurrentMap<SlowKey, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::map::ebr::<SlowKey, M, N>(self.config, prefill)
+    }
+
+    fn pebr_map<M: pebr::ConcurrentMap<String, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::map::pebr::<String, M, N>(self.config, prefill)
+    }
+    fn pebr_set<S: pebr::ConcurrentSet<String> + Send + Sync>(&self, prefill: Prefill) -> Perf {
+        adapter::map::set_pebr::<S, N>(self.config, prefill)
+    }
+    fn pebr_slow_key<M: pebr::ConcurrentMap<SlowKey, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::map::pebr::<SlowKey, M, N>(self.config, prefill)
+    }
+
+    #[cfg(feature = "lists")]
+    fn rc_map<M: rc::ConcurrentMap<String, Value> + Send + Sync>(&self, prefill: Prefill) -> Perf {
+        adapter::map::rc::<String, M>(self.config, prefill)
+    }
+    #[cfg(feature = "lists")]
+    fn rc_slow_key<M: rc::ConcurrentMap<SlowKey, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::map::rc::<SlowKey, M>(self.config, prefill)
+    }
+
+    fn ebr_scan<M: ebr::OrderedMap<String, String> + Send + Sync>(&self, prefill: Prefill) -> Perf {
+        adapter::scan::ebr::<M, N>(self.config, prefill)
+    }
+    fn pebr_scan<M: pebr::OrderedMap<String, String> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::scan::pebr::<M, N>(self.config, prefill)
+    }
+    fn ebr_unguarded<M: ebr::UnguardedMap<String, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::unguarded::ebr::<M>(self.config, prefill)
+    }
+    #[cfg(feature = "trees")]
+    fn ebr_transfers<M: ebr::TransferMap<String, Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Perf {
+        adapter::transfer::ebr::<M, N>(self.config, prefill)
+    }
+
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map(&self, prefill: Prefill) -> Perf {
+        adapter::sharded::ebr::<String>(self.config, prefill)
+    }
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map_slow_key(&self, prefill: Prefill) -> Perf {
+        adapter::sharded::ebr::<SlowKey>(self.config, prefill)
+    }
+    fn ffi_map(&self, prefill: Prefill) -> Perf {
+        adapter::ffi::run(self.config, prefill)
+    }
+
+    #[cfg(feature = "queues")]
+    fn ebr_bag<B: ebr::ConcurrentBag<String> + Send + Sync>(&self) -> Perf {
+        adapter::bag::ebr::<B, N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_bag<B: pebr::ConcurrentBag<String> + Send + Sync>(&self) -> Perf {
+        adapter::bag::pebr::<B, N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_priority_queue<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync>(&self) -> Perf {
+        adapter::priority_queue::ebr::<Q, N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_priority_queue<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync>(&self) -> Perf {
+        adapter::priority_queue::pebr::<Q, N>(self.config)
+    }
+    #[cfg(feature = "counters")]
+    fn ebr_counter<C: ebr::ConcurrentCounter + Send + Sync>(&self) -> Perf {
+        adapter::counter::ebr::<C, N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_mpsc_queue(&self) -> Perf {
+        adapter::mpsc_queue::ebr::<N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn pebr_mpsc_queue(&self) -> Perf {
+        adapter::mpsc_queue::pebr::<N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_deque(&self) -> Perf {
+        adapter::deque::ebr::<N>(self.config)
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_pipeline(&self) -> Perf {
+        if self.config.pipeline_stack {
+            adapter::pipeline::ebr::<ebr::bag::Stack<String>, N>(self.config)
+        } else {
+            adapter::pipeline::ebr::<ebr::Deque<String>, N>(self.config)
+        }
+    }
+    #[cfg(feature = "queues")]
+    fn ebr_queue_map(&self) -> Perf {
+        adapter::queue_map::ebr::<N>(self.config)
+    }
+    #[cfg(feature = "lists")]
+    fn ebr_interval_map(&self) -> Perf {
+        adapter::interval_map::ebr::<N>(self.config)
+    }
+    #[cfg(feature = "hash-maps")]
+    fn ebr_bitmap(&self) -> Perf {
+        adapter::bitmap::ebr::<N>(self.config)
+    }
+    #[cfg(feature = "counters")]
+    fn ebr_config_map(&self) -> Perf {
+        adapter::config_map::ebr::<N>(self.config)
+    }
+}
+
+/// Benchmarks the registered structure of the configuration with the workload of its options.
+fn bench_registered<'a, N: Unsigned>(
+    config: &'a Config,
+    registry: &Registry<Bench<'a, N>>,
+) -> Perf {
+    let entry = registry.get(config.ds.0).expect("checked by setup");
+    let port = entry.port(config.mm.scheme()).expect("checked by setup");
+    let run = if config.set {
+        port.set
+    } else if config.slow_cmp > 0 {
+        port.slow_key
+    } else if config.scan.is_some() {
+        port.scan
+    } else if config.unguarded_reads {
+        port.unguarded
+    } else if config.transfers {
+        port.transfers
+    } else if config.cas_mark {
+        port.cas_mark
+    } else if config.compare_and_delete {
+        port.compare_and_delete
+    } else {
+        Some(port.map)
+    };
+    let run = run.expect("checked by setup");
+    let bench = Bench {
+        config,
+        _marker: PhantomData,
+    };
+    run(&bench, entry.prefill)
+}
+
+/// Set if a run regressed against the baseline.
+static REGRESSED: AtomicBool = AtomicBool::new(false);
+/// Set if the checksums of the runs of `compare` differ.
+static DIVERGED: AtomicBool = AtomicBool::new(false);
+
+/// Compares the result with the baseline of the configuration, and appends it to the store.
+fn record_result(config: &Config, store: &Store, perf: &Perf) {
+    let record = Record {
+        key: config.key(),
+        env: config.environment.fields(),
+        metrics: perf
+            .metrics()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    };
+    let result = store.load(&record.key).and_then(|baseline| {
+        store.append(&record)?;
+        Ok(baseline)
+    });
+    let baseline = result.unwrap_or_else(|e| {
+        eprintln!("baseline: {}", e);
+        process::exit(2);
+    });
+    if baseline.is_empty() {
+        println!("baseline: no previous results of this configuration");
+        return;
+    }
+    let regressions = results::regressions(
+        &baseline,
+        &record,
+        config.regression_threshold,
+        &["peak_mem", "avg_mem"],
+    );
+    if regressions.is_empty() {
+        println!("baseline: no regression against {} runs", baseline.len());
+        return;
+    }
+    REGRESSED.store(true, Ordering::Relaxed);
+    for r in regressions {
+        println!(
+            "REGRESSION: {} {:.1} -> {:.1} ({:+.2}%) against {} runs",
+            r.metric,
+            r.baseline,
+            r.value,
+            r.change,
+            baseline.len()
+        );
+    }
+}
+
+/// Prints the distribution of the number of nodes unlinked by each cleanup CAS of HList.
+fn print_harris_stats() {
+    let histogram = harris_stats::take();
+    let total = histogram.iter().sum::<usize>();
+    println!("cleanup CASes: {}", total);
+    for (i, count) in histogram.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        let range = if i == 0 {
+            "1".to_string()
+        } else if i == harris_stats::BUCKETS - 1 {
+            format!("{}-", 1usize << i)
+        } else {
+            format!("{}-{}", 1usize << i, (1usize << (i + 1)) - 1)
+        };
+        println!(
+            "  {:>11} nodes: {:>10} ({:.1}%)",
+            range,
+            count,
+            100.0 * *count as f64 / total as f64
+        );
+    }
+}
+
+/// Prints the path copying of the updates of BonsaiTree.
+fn print_bonsai_stats() {
+    let stats = bonsai_stats::take();
+    let updates = max(stats.updates, 1) as f64;
+    println!(
+        "bonsai updates: {}, copied nodes per update: {:.2}, aborted copies per update: {:.2}, \
+         garbage bytes per update: {:.1}",
+        stats.updates,
+        stats.copies as f64 / updates,
+        stats.aborted_copies as f64 / updates,
+        stats.garbage_bytes as f64 / updates
+    );
+}
+
+/// Prints the time of the removes in each of their phases.
+fn print_remove_phases() {
+    let stats = remove_phases::take();
+    let removes = max(stats.removes, 1) as f64;
+    let total = max(stats.nanos.iter().sum::<u64>(), 1) as f64;
+    println!(
+        "removes: {}, marked: {} ({:.1}%)",
+        stats.removes,
+        stats.marked,
+        100.0 * stats.marked as f64 / removes
+    );
+    for phase in Phase::PHASES.iter() {
+        let nanos = stats.nanos[*phase as usize];
+        println!(
+            "  {:>6}: {:>8.1} ns per remove ({:.1}%)",
+            Phase::NAMES[*phase as usize],
+            nanos as f64 / removes,
+            100.0 * nanos as f64 / total
+        );
+    }
+}
+
+/// Prints the seeks of the Natarajan-Mittal trees, and how the retries resumed them.
+fn print_seek_stats(mm: MM) {
+    let stats = seek_stats::take();
+    let retries = max(stats.resumed + stats.fallbacks, 1) as f64;
+    let nodes = if mm == MM::PEBR {
+        "defended"
+    } else {
+        "visited"
+    };
+    println!(
+        "seeks: {}, nodes {} per seek: {:.2}, retries resumed: {} ({:.1}%), fell back to the \
+         root: {}",
+        stats.seeks,
+        nodes,
+        stats.visited as f64 / max(stats.seeks, 1) as f64,
+        stats.resumed,
+        100.0 * stats.resumed as f64 / retries,
+        stats.fallbacks
+    );
+}
+
+/// Prints the checksum of the run and those of its threads.
+fn print_checksums(config: &Config, checksum: u64) {
+    println!("checksum: {:016x}", checksum);
+    for (client, c) in config.checksums.lock().unwrap().iter().enumerate() {
+        if client == config.threads {
+            println!("checksum: prefill: {:016x}", c);
+        } else {
+            println!("checksum: thread {}: {:016x}", client, c);
+        }
+    }
+}
+
+/// Prints the distribution of the heights of the towers that the workers inserted.
+fn print_skiplist_stats() {
+    let histogram = skiplist_stats::take();
+    let nodes = histogram.iter().sum::<u64>();
+    if nodes == 0 {
+        return;
+    }
+    let levels = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (i as u64 + 1) * n)
+        .sum::<u64>();
+    let max_height = histogram.iter().rposition(|&n| n > 0).unwrap() + 1;
+    println!(
+        "skiplist: {} inserted nodes, mean height: {:.2}, max height: {}",
+        nodes,
+        levels as f64 / nodes as f64,
+        max_height
+    );
+    for (i, n) in histogram[..max_height].iter().enumerate() {
+        println!(
+            "skiplist: height {:2}: {:6.2}%",
+            i + 1,
+            100.0 * *n as f64 / nodes as f64
+        );
+    }
+}
+
+/// Prints the lengths of the chains of the hash maps, at the end of the run.
+fn print_hash_chains() {
+    let chains = hashing::take();
+    if chains.entries == 0 {
+        return;
+    }
+    println!(
+        "hash chains: {} entries in {} buckets, {} empty, {} collisions, \
+         mean chain of an entry: {:.2}, longest chain: {}",
+        chains.entries,
+        chains.buckets,
+        chains.empty,
+        chains.collisions(),
+        chains.mean_chain(),
+        chains.longest
+    );
+}
+
+/// Prints the histograms of the advance and the free latencies of the retired objects.
+fn print_epoch_latency(mm: MM) {
+    let (advance, free) = epoch_latency::take();
+    println!(
+        "epoch latency ({}): {} advance probes, {} free probes",
+        mm,
+        advance.iter().sum::<usize>(),
+        free.iter().sum::<usize>()
+    );
+    for (i, (a, f)) in advance.iter().zip(free.iter()).enumerate() {
+        if *a == 0 && *f == 0 {
+            continue;
+        }
+        let range = if i == 0 {
+            "0-1".to_string()
+        } else if i == epoch_latency::BUCKETS - 1 {
+            format!("{}-", 1usize << i)
+        } else {
+            format!("{}-{}", 1usize << i, (1usize << (i + 1)) - 1)
+        };
+        println!("  {:>13} us: advance {:>10}, free {:>10}", range, a, f);
+    }
+}
+
+/// Prints the hardware events of the workers, in total and per operation.
+fn print_perf_counters() {
+    let counts = perf_counters::take();
+    let ops = max(counts.ops, 1) as f64;
+    let events = perf_counters::EVENTS
+        .iter()
+        .zip(counts.events.iter())
+        .map(|(name, count)| match count {
+            Some(count) => format!("{}: {} ({:.2}/op)", name, count, *count as f64 / ops),
+            None => format!("{}: unavailable", name),
+        })
+        .collect::<Vec<_>>();
+    println!("perf: {}", events.join(", "));
+}
+
+/// Prints the distribution of the number of restarts of each operation.
+fn print_restart_stats() {
+    let histogram = restarts::take();
+    let total = histogram.iter().sum::<usize>();
+    println!("operations: {}", total);
+    for (i, count) in histogram.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        let range = if i <= 1 {
+            i.to_string()
+        } else if i == restarts::BUCKETS - 1 {
+            format!("{}-", 1usize << (i - 1))
+        } else {
+            format!("{}-{}", 1usize << (i - 1), (1usize << i) - 1)
+        };
+        println!(
+            "  {:>11} restarts: {:>10} ({:.1}%)",
+            range,
+            count,
+            100.0 * *count as f64 / total as f64
+        );
+    }
+}
+
+/// Measurements of a single benchmark run.
+struct Perf {
+    /// Throughput of the mixed workload in the steady state.
+    ops_per_sec: u64,
+    peak_mem: usize,
+    avg_mem: usize,
+    /// Throughput of the insert-only prefill (build) phase, which does not depend on the scheme
+    /// (`report_build`).
+    build_ops_per_sec: u64,
+}
+
+impl Perf {
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("throughput", self.ops_per_sec as f64),
+            ("peak_mem", self.peak_mem as f64),
+            ("avg_mem", self.avg_mem as f64),
+            ("build_throughput", self.build_ops_per_sec as f64),
+        ]
+    }
+}
+
+/// Runs the configuration `runs` times with each of the two memory managers (interleaved), and
+/// prints the relative difference of the mean of each metric. A difference is marked with `*` if
+/// it is significant at the 95% level according to Welch's t-test. With `--checksum`, it also
+/// reports the runs whose checksums differ, and with `--stall-threads`, it also compares the mean
+/// recovery time of the runs that recovered from a window.
+fn compare(config: &mut Config, output: &mut Writer<File>, mms: (MM, MM), runs: usize) {
+    let mut perfs = (Vec::new(), Vec::new());
+    let mut recoveries = (Vec::new(), Vec::new());
+    let mut diverged = Vec::new();
+    for r in 0..runs {
+        println!("compare: run {}/{}", r + 1, runs);
+        config.mm = mms.0;
+        let a = run(config, output);
+        if interrupted() {
+            break;
+        }
+        let checksum = Checksum::combine(&config.checksums.lock().unwrap());
+        let recovery = config.recovery.lock().unwrap().mean_ms();
+        config.mm = mms.1;
+        let b = run(config, output);
+        if interrupted() {
+            break;
+        }
+        if config.checksum && Checksum::combine(&config.checksums.lock().unwrap()) != checksum {
+            diverged.push(r + 1);
+        }
+        // A run without a recovered window has no recovery time.
+        if let (Some(a), Some(b)) = (recovery, config.recovery.lock().unwrap().mean_ms()) {
+            recoveries.0.push(a);
+            recoveries.1.push(b);
+        }
+        perfs.0.push(a);
+        perfs.1.push(b);
+    }
+    // Only the runs of both memory managers that completed are compared.
+    if perfs.0.is_empty() {
+        println!("compare: interrupted before completing a run of both memory managers");
+        return;
+    }
+    if config.checksum {
+        if diverged.is_empty() {
+            println!(
+                "checksum: {} and {} agree in all {} runs",
+                mms.0,
+                mms.1,
+                perfs.0.len()
+            );
+        } else {
+            DIVERGED.store(true, Ordering::Relaxed);
+            println!(
+                "CHECKSUM MISMATCH: {} and {} diverged in runs {:?}",
+                mms.0, mms.1, diverged
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "{:<20}{:>18}{:>18}{:>12}",
+        "metric",
+        format!("{} (mean)", mms.0),
+        format!("{} (mean)", mms.1),
+        "delta"
+    );
+    let metrics = (
+        perfs.0.iter().map(Perf::metrics).collect::<Vec<_>>(),
+        perfs.1.iter().map(Perf::metrics).collect::<Vec<_>>(),
+    );
+    let mut rows = metrics.0[0]
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| {
+            (
+                *name,
+                metrics.0.iter().map(|m| m[i].1).collect::<Vec<_>>(),
+                metrics.1.iter().map(|m| m[i].1).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    if !recoveries.0.is_empty() {
+        rows.push(("recovery_ms", recoveries.0, recoveries.1));
+    }
+    for (name, a, b) in rows {
+        let (mean_a, mean_b) = (mean(&a), mean(&b));
+        let delta = if mean_a == 0.0 {
+            "n/a".to_string()
+        } else {
+            format!("{:+.2}%", (mean_b - mean_a) / mean_a * 100.0)
+        };
+        let marker = if welch_significant(&a, &b) {
+            " *"
+        } else {
+            "  "
+        };
+        println!(
+            "{:<20}{:>18.1}{:>18.1}{:>12}{}",
+            name, mean_a, mean_b, delta, marker
+        );
+    }
+}
+
+/// Prints the counts of the shield pools of the run, if any, and warns if they exceeded the budget.
+fn print_shield_pool_stats() {
+    let stats = pebr::shield_pool::take_stats();
+    if stats.allocations == 0 {
+        return;
+    }
+    println!(
+        "shield pool: {} allocations, {} reuses ({:.1}% hit rate), at most {} shields per thread",
+        stats.allocations,
+        stats.hits,
+        100.0 * stats.hits as f64 / (stats.hits + stats.allocations) as f64,
+        stats.peak
+    );
+    if stats.over_budget > 0 {
+        eprintln!(
+            "shield pool: allocated {} shields beyond the budget of {} per thread",
+            stats.over_budget,
+            pebr::shield_pool::budget()
+        );
+    }
+}
+
+/// Runs each combination of the data structures, memory managers and numbers of threads `runs`
+/// times, each in a new process of this executable so that the state of the allocator and of the
+/// collectors doesn't carry over from one configuration to the next. Each process writes its own
+/// CSV file, which is then appended to `output`.
+///
+/// The combinations that a process rejects as unsupported (status 2) are skipped. A process whose
+/// run panicked (status 3) still writes the run, marked with its failure. Exits with status 1 if
+/// any process failed.
+fn matrix(
+    dss: &[DS],
+    mms: &[MM],
+    threads: &[usize],
+    runs: usize,
+    output: &Path,
+    workload: &[&str],
+) {
+    let exe = env::current_exe().unwrap_or_else(|e| {
+        eprintln!("matrix: cannot locate the executable: {}", e);
+        process::exit(2);
+    });
+    let parts = output.with_extension("parts");
+    create_dir_all(&parts).unwrap();
+    let total = runs * dss.len() * mms.len() * threads.len();
+    let mut done = 0;
+    let (mut skipped, mut failed) = (Vec::new(), Vec::new());
+    let mut results = Vec::new();
+    'runs: for _ in 0..runs {
+        for ds in dss {
+            for mm in mms {
+                for t in threads {
+                    // The processes receive the interrupt too, and end their runs early.
+                    if interrupted() {
+                        break 'runs;
+                    }
+                    done += 1;
+                    let name = format!("-d {} -m {} -t {}", ds, mm, t);
+                    println!("matrix: {}/{}: {}", done, total, name);
+                    let part = parts.join(format!("{}.csv", done));
+                    let status = Command::new(&exe)
+                        .args(&["-d", &ds.to_string(), "-m", &mm.to_string()])
+                        .args(&["-t", &t.to_string()])
+                        .arg("-o")
+                        .arg(&part)
+                        .args(workload)
+                        .status()
+                        .unwrap_or_else(|e| {
+                            eprintln!("matrix: cannot run {}: {}", exe.display(), e);
+                            process::exit(2);
+                        });
+                    if part.exists() {
+                        match read_runs(&part) {
+                            Ok(r) => results.extend(r),
+                            Err(e) => {
+                                eprintln!("matrix: cannot read {}: {}", part.display(), e);
+                                process::exit(2);
+                            }
+                        }
+                        if let Err(e) = merge_csv(&part, output) {
+                            eprintln!("matrix: cannot merge {}: {}", part.display(), e);
+                            process::exit(2);
+                        }
+                        fs::remove_file(&part).unwrap();
+                    }
+                    match status.code() {
+                        Some(0) => {}
+                        Some(2) => skipped.push(name),
+                        Some(3) => failed.push(format!("{} (panicked)", name)),
+                        _ => failed.push(name),
+                    }
+                }
+            }
+        }
+    }
+    let _ = fs::remove_dir(&parts);
+
+    println!(
+        "matrix: {} of {} runs done, written to {}",
+        done,
+        total,
+        output.display()
+    );
+    for name in &skipped {
+        println!("matrix: skipped (unsupported): {}", name);
+    }
+    for name in &failed {
+        println!("matrix: failed: {}", name);
+    }
+    if !results.is_empty() {
+        println!();
+        print_summary(&results, Some(&mms[0].to_string()), Format::Text);
+    }
+    if !failed.is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Prints the runs that `matrix` would perform, after validating each combination with a dry run
+/// of its process, and the total duration of their workers. Exits with status 2 if a combination
+/// is invalid, e.g., because of a typo in the arguments of the workload.
+fn plan_matrix(dss: &[DS], mms: &[MM], threads: &[usize], runs: usize, workload: &[&str]) {
+    let exe = env::current_exe().unwrap_or_else(|e| {
+        eprintln!("matrix: cannot locate the executable: {}", e);
+        process::exit(2);
+    });
+    let (mut planned, mut skipped, mut invalid) = (0, Vec::new(), Vec::new());
+    let (mut secs, mut unknown) = (0, 0);
+    println!("{:<40} {:>10}", "run", "duration");
+    for ds in dss {
+        for mm in mms {
+            for t in threads {
+                let name = format!("-d {} -m {} -t {}", ds, mm, t);
+                let out = Command::new(&exe)
+                    .args(&["-d", &ds.to_string(), "-m", &mm.to_string()])
+                    .args(&["-t", &t.to_string()])
+                    .args(workload)
+                    .arg("--dry-run")
+                    .output()
+                    .unwrap_or_else(|e| {
+                        eprintln!("matrix: cannot run {}: {}", exe.display(), e);
+                        process::exit(2);
+                    });
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                let reason = stderr.lines().next().unwrap_or("").to_string();
+                match out.status.code() {
+                    Some(0) => {}
+                    Some(2) => {
+                        skipped.push(format!("{} ({})", name, reason));
+                        continue;
+                    }
+                    _ => {
+                        invalid.push(format!("{} ({})", name, reason));
+                        continue;
+                    }
+                }
+                planned += 1;
+                let prefix = "dry run: duration ";
+                let duration = String::from_utf8_lossy(&out.stdout)
+                    .lines()
+                    .filter(|l| l.starts_with(prefix))
+                    .last()
+                    .and_then(|l| l[prefix.len()..].trim_end_matches(" s").parse::<u64>().ok());
+                match duration {
+                    Some(d) => {
+                        secs += d;
+                        println!("{:<40} {:>8} s", name, d);
+                    }
+                    None => {
+                        unknown += 1;
+                        println!("{:<40} {:>10}", name, "unknown");
+                    }
+                }
+            }
+        }
+    }
+
+    for name in &skipped {
+        println!("matrix: skipped (unsupported): {}", name);
+    }
+    for name in &invalid {
+        println!("matrix: invalid: {}", name);
+    }
+    print!(
+        "matrix: {} runs of {} combinations, whose workers run for {:.1} h",
+        runs * planned,
+        planned,
+        (runs as u64 * secs) as f64 / 3600.0
+    );
+    if unknown > 0 {
+        print!(", besides {} combinations of fixed work", unknown);
+    }
+    println!(", plus the prefills");
+    if !invalid.is_empty() {
+        process::exit(2);
+    }
+}
+
+/// The columns of the results that tell the workloads of `summary` apart.
+const SUMMARY_WORKLOAD: [&str; 7] = [
+    "get_rate",
+    "non_coop",
+    "ops_per_cs",
+    "groups",
+    "ycsb",
+    "preset",
+    "instances",
+];
+
+/// Reads the runs of the CSV file `path` that did not fail.
+fn read_runs(path: &Path) -> csv::Result<Vec<summary::Run>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let header = reader.headers()?.clone();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let invalid = |msg: &str| csv::Error::from(io::Error::new(io::ErrorKind::InvalidData, msg));
+    let (ds, mm, threads, throughput) = match (
+        column("ds"),
+        column("mm"),
+        column("threads"),
+        column("throughput"),
+    ) {
+        (Some(ds), Some(mm), Some(threads), Some(throughput)) => (ds, mm, threads, throughput),
+        _ => return Err(invalid("missing the ds, mm, threads or throughput column")),
+    };
+    let failure = column("failure");
+    let workload = SUMMARY_WORKLOAD
+        .iter()
+        .filter_map(|name| column(name).map(|i| (name, i)))
+        .collect::<Vec<_>>();
+
+    let mut runs = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        if failure.map_or(false, |i| !record[i].is_empty()) {
+            continue;
+        }
+        let workload = workload
+            .iter()
+            .filter(|(_, i)| !record[*i].is_empty())
+            .map(|(name, i)| format!("{}={}", name, &record[*i]))
+            .collect::<Vec<_>>();
+        runs.push(summary::Run {
+            workload: workload.join(", "),
+            ds: record[ds].to_string(),
+            mm: record[mm].to_string(),
+            threads: record[threads]
+                .parse()
+                .map_err(|_| invalid("invalid threads"))?,
+            throughput: record[throughput]
+                .parse()
+                .map_err(|_| invalid("invalid throughput"))?,
+        });
+    }
+    Ok(runs)
+}
+
+/// Prints the pivoted tables of `runs`, with the ratios relative to `base`.
+fn print_summary(runs: &[summary::Run], base: Option<&str>, format: Format) {
+    for (i, table) in summary::tables(runs, base).iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", table.render(format));
+    }
+}
+
+/// Appends the records of the CSV file `part` to `output`, writing the header first if `output` is
+/// new. Fails if `output` has other columns.
+fn merge_csv(part: &Path, output: &Path) -> csv::Result<()> {
+    let mut reader = csv::Reader::from_path(part)?;
+    let header = reader.headers()?.clone();
+    let exists = output.exists();
+    if exists
+        && !csv::Reader::from_path(output)?
+            .headers()?
+            .iter()
+            .eq(header.iter())
+    {
+        let e = io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the columns differ from the output",
+        );
+        return Err(e.into());
+    }
+    let file = OpenOptions::new().create(true).append(true).open(output)?;
+    let mut writer = csv::Writer::from_writer(file);
+    if !exists {
+        writer.write_record(&header)?;
+    }
+    for record in reader.records() {
+        writer.write_record(&record?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Runs the workload while sampling the resident set size every `period`, and exits with status 1
+/// if it grows faster than `max_slope` MiB per hour after the leading `warmup` fraction of the run.
+fn soak(config: &Config, output: &mut Writer<File>, period: Duration, warmup: f64, max_slope: f64) {
+    const MIB: f64 = (1 << 20) as f64;
+    let done = AtomicBool::new(false);
+    let samples = scope(|s| {
+        let sampler = s.spawn(|_| {
+            let start = Instant::now();
+            let mut samples = Vec::new();
+            let mut next = start;
+            while !done.load(Ordering::Acquire) {
+                if Instant::now() >= next {
+                    samples.push((start.elapsed().as_secs_f64(), rss().unwrap() as f64));
+                    next += period;
+                }
+                // Sleep briefly so that the sampler stops soon after the run.
+                std::thread::sleep(min(period, Duration::from_millis(100)));
+            }
+            samples
+        });
+        run(config, output);
+        done.store(true, Ordering::Release);
+        sampler.join().unwrap()
+    })
+    .unwrap();
+
+    let end = samples.last().map_or(0.0, |s| s.0);
+    let steady = samples
+        .into_iter()
+        .filter(|s| s.0 >= end * warmup)
+        .collect::<Vec<_>>();
+    if steady.len() < 2 {
+        eprintln!("soak: too few samples after the warm-up to fit the growth of memory");
+        process::exit(2);
+    }
+    // The slope of the least-squares fit, in bytes per second.
+    let (ts, rss) = steady.iter().cloned().unzip::<_, _, Vec<_>, Vec<_>>();
+    let (mean_t, mean_rss) = (mean(&ts), mean(&rss));
+    let cov = steady
+        .iter()
+        .map(|s| (s.0 - mean_t) * (s.1 - mean_rss))
+        .sum::<f64>();
+    let var = ts.iter().map(|t| (t - mean_t) * (t - mean_t)).sum::<f64>();
+    let slope = cov / var * 3600.0 / MIB;
+    println!(
+        "soak: resident set size {:.1} MiB -> {:.1} MiB after the warm-up ({} samples), \
+         growing {:.2} MiB/h (limit {} MiB/h)",
+        rss[0] / MIB,
+        rss[rss.len() - 1] / MIB,
+        steady.len(),
+        slope,
+        max_slope
+    );
+    if slope > max_slope {
+        eprintln!("soak: the resident set size grows faster than the limit");
+        process::exit(1);
+    }
+}
+
+/// Runs the configuration `runs` times from an empty structure, and prints the latency of the first
+/// operations of the slowest worker in each run. Every run creates a new structure and collector,
+/// so that the allocation of the first nodes and the bootstrapping of the epochs are measured each
+/// time, while the allocator stays warm after the first run.
+fn cold_start(config: &Config, output: &mut Writer<File>, runs: usize) {
+    let first = config.ops_per_thread.unwrap();
+    let mut latencies = Vec::new();
+    for r in 0..runs {
+        if interrupted() {
+            break;
+        }
+        let perf = run(config, output);
+        // The throughput is measured against the slowest worker.
+        let latency = 1e9 * config.threads as f64 / max(perf.ops_per_sec, 1) as f64;
+        println!(
+            "cold: run {}/{}, {:.0} ns/op over the first {} operations",
+            r + 1,
+            runs,
+            latency,
+            first
+        );
+        latencies.push(latency);
+    }
+    if latencies.is_empty() {
+        return;
+    }
+    let (lo, hi) = latencies
+        .iter()
+        .fold((std::f64::MAX, 0f64), |(lo, hi), &l| (lo.min(l), hi.max(l)));
+    println!(
+        "cold: {:.0} ns/op in the first run, {:.0} ns/op on average over {} runs (min {:.0}, \
+         max {:.0})",
+        latencies[0],
+        mean(&latencies),
+        latencies.len(),
+        lo,
+        hi
+    );
+}
+
+/// The operations of each thread in the microbenchmark of the counters.
+const OVERHEAD_OPS: u64 = 1 << 22;
+
+/// Runs the configuration, then times the counters that a worker updates in an operation, all
+/// enabled, on as many threads without the structure, and exits with 1 if they take more than
+/// `max_percent` of the time of an operation of the run. The clock read of `--stall-event-us` is
+/// timed as well but reported apart, as it is a timer rather than a counter.
+fn measure_overhead(config: &Config, output: &mut Writer<File>, max_percent: f64) {
+    let perf = run(config, output);
+    if perf.ops_per_sec == 0 {
+        eprintln!("overhead: the run made no operations");
+        process::exit(3);
+    }
+    // The throughput is measured against the slowest worker.
+    let op_ns = 1e9 * config.threads as f64 / perf.ops_per_sec as f64;
+
+    op_stats::enable();
+    restarts::enable();
+    harris_stats::enable();
+    bonsai_stats::enable();
+    // The hooks of an operation that retries once and retires a node, and the update of the size of
+    // the structure, whose stripes are shared by all the threads.
+    let len = StripedCounter::new();
+    let counters_ns = time_per_op(config.threads, |tid, ops, outcomes| {
+        len.add(if ops % 2 == 0 { 1 } else { -1 });
+        restarts::record();
+        op_stats::retry();
+        op_stats::retire();
+        harris_stats::record(1);
+        bonsai_stats::record_commit(1, 64);
+        outcomes.record(Op::OPS[(ops % 3) as usize], ops % 2 == 0);
+        restarts::finish_op();
+        config.publish_progress(tid, ops);
+    });
+    // Every operation is timed, and none is a stall.
+    op_latency::set_threshold(u64::max_value());
+    let timer_ns = time_per_op(config.threads, |_, _, _| op_latency::finish_op());
+    op_latency::set_threshold(0);
+    op_latency::take();
+
+    let percent = 100.0 * counters_ns / op_ns;
+    println!(
+        "overhead: {:.2} ns of counters per operation, {:.2}% of {:.0} ns/op (at most {}%)",
+        counters_ns, percent, op_ns, max_percent
+    );
+    println!(
+        "overhead: {:.2} ns more per operation with --stall-event-us, {:.2}%",
+        timer_ns,
+        100.0 * timer_ns / op_ns
+    );
+    if percent > max_percent {
+        eprintln!(
+            "overhead: the counters take more than {}% of an operation",
+            max_percent
+        );
+        process::exit(1);
+    }
+}
+
+/// Calls `hook` for `OVERHEAD_OPS` operations on each of `threads` threads, and returns the
+/// nanoseconds per operation of the slowest thread.
+fn time_per_op<F>(threads: usize, hook: F) -> f64
+where
+    F: Fn(usize, u64, &mut Outcomes) + Sync,
+{
+    let hook = &hook;
+    let (elapsed, ops) = scope(|s| {
+        let handles = (0..threads)
+            .map(|tid| {
+                s.spawn(move |_| {
+                    let mut outcomes = Outcomes::default();
+                    let start = Instant::now();
+                    for ops in 1..=OVERHEAD_OPS {
+                        hook(tid, ops, &mut outcomes);
+                    }
+                    (start.elapsed(), outcomes.ops.iter().sum::<u64>())
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .fold((Duration::default(), 0), |(e, n), (elapsed, ops)| {
+                (max(e, elapsed), n + ops)
+            })
+    })
+    .unwrap();
+    // Keeps the outcomes, which are otherwise unused, from being optimized out.
+    assert!(ops <= OVERHEAD_OPS * threads as u64);
+    elapsed.as_nanos() as f64 / OVERHEAD_OPS as f64
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+/// Unbiased sample variance.
+fn variance(xs: &[f64]) -> f64 {
+    let m = mean(xs);
+    xs.iter().map(|x| (x - m) * (x - m)).sum::<f64>() / (xs.len() - 1) as f64
+}
+
+/// Two-sided Welch's t-test at the 95% level.
+fn welch_significant(a: &[f64], b: &[f64]) -> bool {
+    // Two-sided 95% critical values of Student's t-distribution for 1..=30 degrees of freedom.
+    const T_CRITICAL: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+
+    if a.len() < 2 || b.len() < 2 {
+        return false;
+    }
+    let (va, vb) = (variance(a) / a.len() as f64, variance(b) / b.len() as f64);
+    let diff = (mean(b) - mean(a)).abs();
+    if va + vb == 0.0 {
+        return diff > 0.0;
+    }
+    let t = diff / (va + vb).sqrt();
+    let df =
+        (va + vb) * (va + vb) / (va * va / (a.len() - 1) as f64 + vb * vb / (b.len() - 1) as f64);
+    let df = max(df.floor() as usize, 1);
+    t > T_CRITICAL.get(df - 1).cloned().unwrap_or(1.960)
+}
+
+/// Checks with `--audit` that every value of a run was dropped once the map was dropped.
+fn audit_dropped(config: &Config) {
+    if !config.audit || config.mm == MM::NR || !config.reclaim {
+        return;
+    }
+    let live = audit::live();
+    assert_eq!(live, 0, "audit: {} values outlived the map", live);
+}
+
+/// A thread's log of operations for offline linearizability checking.
+///
+/// Only the operations on the sampled keys (`key % history_sample == 0`) are logged. Since
+/// linearizability is compositional, the history restricted to a subset of the keys can be checked
+/// on its own. A thread stops logging when its buffer is full, and the merged history is cut at the
+/// earliest such moment so that it still contains every operation invoked before the cut.
+struct History {
+    client: usize,
+    start: Instant,
+    /// 0 if logging is disabled.
+    sample: usize,
+    capacity: usize,
+    events: Vec<Event>,
+    /// The time the buffer got full.
+    full_at: Option<u64>,
+    /// Whether the thread stops when the buffer gets full.
+    stop: bool,
+    /// The checksum of the operations of the thread, if `--checksum`.
+    checksum: Option<Checksum>,
+}
+
+/// The invocation time of an operation that is only added to the checksum.
+const UNLOGGED: u64 = u64::max_value();
+
+struct Event {
+    op: Op,
+    key: usize,
+    /// Whether `get` found the key, `insert` inserted it, or `remove` removed it. Since the value
+    /// always equals the key, this determines the return value.
+    ok: bool,
+    call: u64,
+    ret: u64,
+}
+
+impl History {
+    fn new(config: &Config, client: usize, start: Instant) -> Self {
+        let (sample, capacity) = if config.history.is_some() || config.check {
+            // The prefill thread logs every insertion it makes.
+            let capacity = if client == config.threads {
+                max(config.history_capacity, config.prefill)
+            } else {
+                config.history_capacity
+            };
+            (config.history_sample, capacity)
+        } else {
+            (0, 0)
+        };
+        History {
+            client,
+            start,
+            sample,
+            capacity,
+            events: Vec::with_capacity(capacity),
+            full_at: None,
+            stop: config.check,
+            checksum: if config.checksum {
+                Some(Checksum::default())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Returns true if the thread should stop because its buffer is full.
+    #[inline]
+    fn done(&self) -> bool {
+        self.stop && self.events.len() == self.capacity
+    }
+
+    /// Returns the invocation time if the operation on `key` should be logged, or `UNLOGGED` if it
+    /// should only be added to the checksum.
+    #[inline]
+    fn invoke(&mut self, key: usize) -> Option<u64> {
+        if self.sample == 0 || key % self.sample != 0 || self.full_at.is_some() {
+            return self.unlogged();
+        }
+        let now = self.now();
+        if self.events.len() == self.capacity {
+            self.full_at = Some(now);
+            return self.unlogged();
+        }
+        Some(now)
+    }
+
+    #[inline]
+    fn unlogged(&self) -> Option<u64> {
+        self.checksum.map(|_| UNLOGGED)
+    }
+
+    #[inline]
+    fn respond(&mut self, call: u64, op: Op, key: usize, ok: bool) {
+        if let Some(checksum) = &mut self.checksum {
+            checksum.add(op as u8, key as u64, ok);
+        }
+        if call == UNLOGGED {
+            return;
+        }
+        let ret = self.now();
+        self.events.push(Event {
+            op,
+            key,
+            ok,
+            call,
+            ret,
+        });
+    }
+
+    #[inline]
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+}
+
+fn finish_history(config: &Config, mut histories: Vec<History>) {
+    if config.checksum {
+        histories.sort_by_key(|h| h.client);
+        *config.checksums.lock().unwrap() = histories
+            .iter()
+            .map(|h| h.checksum.unwrap().value())
+            .collect();
+    }
+    if config.check {
+        check_history(histories);
+    } else {
+        write_history(config, histories);
+    }
+}
+
+/// Checks the merged histories for linearizability, and exits if they are not linearizable.
+fn check_history(histories: Vec<History>) {
+    let history = histories
+        .iter()
+        .flat_map(|h| {
+            h.events.iter().map(move |e| Operation {
+                client: h.client,
+                op: match e.op {
+                    Op::Get => MapOp::Get,
+                    Op::Insert => MapOp::Insert,
+                    Op::Remove => MapOp::Remove,
+                },
+                key: e.key,
+                output: e.ok,
+                call: e.call,
+                ret: e.ret,
+            })
+        })
+        .collect::<Vec<_>>();
+    match linearizability::check(&history) {
+        Ok(()) => println!("linearizable ({} operations)", history.len()),
+        Err(key) => {
+            println!("NOT linearizable on key {}:", key);
+            let mut ops = history.iter().filter(|o| o.key == key).collect::<Vec<_>>();
+            ops.sort_by_key(|o| o.call);
+            for o in ops {
+                println!(
+                    "  client {}: {:?} -> {} [{}, {}]",
+                    o.client, o.op, o.output, o.call, o.ret
+                );
+            }
+            process::exit(1);
+        }
+    }
+}
+
+/// Writes the merged histories as JSON lines of Porcupine-style operations, sorted by invocation
+/// time. The client of the prefill operations is `config.threads`.
+fn write_history(config: &Config, histories: Vec<History>) {
+    let path = match &config.history {
+        Some(path) => path,
+        None => return,
+    };
+
+    let cut = histories
+        .iter()
+        .filter_map(|h| h.full_at)
+        .min()
+        .unwrap_or(u64::max_value());
+    let mut events = histories
+        .iter()
+        .flat_map(|h| h.events.iter().map(move |e| (h.client, e)))
+        .filter(|(_, e)| e.call < cut)
+        .collect::<Vec<_>>();
+    events.sort_by_key(|(_, e)| e.call);
+
+    let mut writer = BufWriter::new(File::create(path).unwrap());
+    for (client, e) in &events {
+        writeln!(
+            writer,
+            "{{\"client_id\":{},\"input\":{{\"op\":\"{}\",\"key\":{}}},\"output\":{},\"call\":{},\"return\":{}}}",
+            client,
+            format!("{:?}", e.op).to_lowercase(),
+            e.key,
+            e.ok,
+            e.call,
+            e.ret
+        )
+        .unwrap();
+    }
+    writer.flush().unwrap();
+    println!("history: {} operations logged to {}", events.len(), path);
+    if cut != u64::max_value() {
+        println!("history: cut at {} ns due to --history-capacity", cut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_mm, workload_args, KeySize, MAX_KEY_SIZE, MM};
+    use clap::App;
+
+    /// Checks the workload of the arguments `args` followed by `extra` with `mm`.
+    fn check(mm: MM, args: &[&str], extra: &[&str]) -> Result<(), String> {
+        let m = App::new("test")
+            .args(&workload_args())
+            .get_matches_from(["test"].iter().chain(args).chain(extra));
+        check_mm(&m, mm)
+    }
+
+    #[test]
+    fn key_size() {
+        assert!("exp:inf".parse::<KeySize>().is_err());
+        assert!("pareto:1:NaN".parse::<KeySize>().is_err());
+        // The heavy tail overflows to infinite lengths, which are clamped.
+        let pareto = "pareto:1:0.001".parse::<KeySize>().unwrap();
+        assert!((0..1000).all(|k| pareto.key(k).len() <= MAX_KEY_SIZE));
+    }
+
+    #[test]
+    fn check_thread_churn() {
+        let churn = ["-d", "HMList", "-t", "4", "--thread-churn", "10"];
+        assert!(check(MM::EBR, &churn, &[]).is_ok());
+        assert!(check(MM::PEBR, &churn, &[]).is_ok());
+        assert!(check(MM::NR, &churn, &[]).is_err());
+        assert!(check(MM::RC, &churn, &[]).is_err());
+        assert!(check(MM::EBR, &churn, &["--set"]).is_err());
+        assert!(check(MM::EBR, &churn, &["--stall-threads", "1"]).is_err());
+        let queue = ["-d", "Bag", "-t", "4", "--thread-churn", "10"];
+        assert!(check(MM::EBR, &queue, &[]).is_err());
+
+        // The replacement threads are pinned and prioritized like the workers.
+        assert!(check(MM::EBR, &churn, &["--pin-threads"]).is_ok());
+        assert!(check(MM::EBR, &churn, &["--low-prio-threads", "1"]).is_ok());
+    }
+
+    #[test]
+    fn check_low_prio_nice() {
+        let low_prio = ["-d", "HMList", "-t", "4", "--low-prio-threads", "1"];
+        assert!(check(MM::EBR, &low_prio, &[]).is_ok());
+        assert!(check(MM::EBR, &low_prio, &["--low-prio-nice", "1"]).is_ok());
+        assert!(check(MM::EBR, &low_prio, &["--low-prio-nice", "0"]).is_err());
+        assert!(check(MM::EBR, &low_prio, &["--low-prio-nice", "20"]).is_err());
+    }
+
+    #[test]
+    fn check_backoff_spin() {
+        let map = ["-d", "ART", "-t", "4"];
+        assert!(check(MM::EBR, &map, &["--backoff-spin", "0"]).is_ok());
+        assert!(check(MM::EBR, &map, &["--backoff-spin", "20"]).is_ok());
+        assert!(check(MM::EBR, &map, &["--backoff-spin", "21"]).is_err());
+    }
+
+    #[test]
+    fn check_per_op_stats() {
+        let map = ["-d", "HMList", "-t", "4"];
+        let queue = ["-d", "Bag", "-t", "4"];
+        let ffi = ["-d", "Ffi", "-t", "4", "--ffi-lib", "libmap.so"];
+
+        assert!(check(MM::EBR, &map, &["--restart-stats"]).is_ok());
+        assert!(check(MM::EBR, &queue, &["--restart-stats"]).is_err());
+        assert!(check(MM::NR, &ffi, &["--restart-stats"]).is_err());
+
+        assert!(check(MM::EBR, &map, &["--stall-event-us", "100"]).is_ok());
+        assert!(check(MM::EBR, &map, &["--stall-event-us", "0"]).is_err());
+        assert!(check(MM::EBR, &queue, &["--stall-event-us", "100"]).is_err());
+        assert!(check(MM::NR, &ffi, &["--stall-event-us", "100"]).is_err());
 
-    let mut ops = 0;
-    for _ in 0..config.threads {
-        let local_ops = ops_receiver.recv().unwrap();
-        ops += local_ops;
+        assert!(check(MM::EBR, &["-d", "HMList"], &["--readers", "4:get=100"]).is_ok());
+        assert!(check(MM::EBR, &["-d", "Bag"], &["--readers", "4:get=100"]).is_err());
     }
-    let ops_per_sec = ops / config.interval;
-    let (peak_mem, avg_mem) = mem_receiver.recv().unwrap();
-    (ops_per_sec, peak_mem, avg_mem)
 }
@@ -0,0 +1,96 @@
+//! The maximum latency of the operations of the maps, and the operations that stall.
+//!
+//! Averages hide the operations that wait for a long time, e.g., for a collection or behind a
+//! preempted thread, so robustness needs a metric of its own. When a threshold is set, each worker
+//! measures the latency of each of its operations as the time since it finished the previous one,
+//! which also covers the repinning and the collection of garbage between operations (and the think
+//! time, if any). It records the maximum latency and counts the operations slower than the
+//! threshold as stall events. The first operation of a worker is not measured. The counts are
+//! merged into the global ones when the thread exits.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+static THRESHOLD_NS: AtomicU64 = AtomicU64::new(0);
+static MAX_NS: AtomicU64 = AtomicU64::new(0);
+static STALLS: AtomicU64 = AtomicU64::new(0);
+
+/// The latencies of the operations.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Stats {
+    /// The maximum latency of an operation in nanoseconds.
+    pub max_ns: u64,
+    /// The operations slower than the threshold.
+    pub stalls: u64,
+}
+
+struct Local {
+    /// When the thread finished its previous operation.
+    last: Option<Instant>,
+    stats: Stats,
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        let mut max = MAX_NS.load(Ordering::Relaxed);
+        while max < self.stats.max_ns {
+            match MAX_NS.compare_exchange_weak(
+                max,
+                self.stats.max_ns,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => max = current,
+            }
+        }
+        STALLS.fetch_add(self.stats.stalls, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local {
+        last: None,
+        stats: Stats::default(),
+    });
+}
+
+/// Counts the operations slower than `ns` nanoseconds as stall events. 0 turns off the
+/// measurement.
+pub fn set_threshold(ns: u64) {
+    THRESHOLD_NS.store(ns, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    THRESHOLD_NS.load(Ordering::Relaxed) > 0
+}
+
+/// Records the latency of the operation that the current thread has just finished.
+#[inline]
+pub fn finish_op() {
+    if !enabled() {
+        return;
+    }
+    let now = Instant::now();
+    LOCAL.with(|local| {
+        let local = &mut *local.borrow_mut();
+        if let Some(last) = local.last {
+            let ns = (now - last).as_nanos() as u64;
+            local.stats.max_ns = local.stats.max_ns.max(ns);
+            if ns > THRESHOLD_NS.load(Ordering::Relaxed) {
+                local.stats.stalls += 1;
+            }
+        }
+        local.last = Some(now);
+    });
+}
+
+/// Takes the latencies recorded by the exited threads.
+pub fn take() -> Stats {
+    Stats {
+        max_ns: MAX_NS.swap(0, Ordering::Relaxed),
+        stalls: STALLS.swap(0, Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,55 @@
+//! Per-thread counts of the reclamation events of the data structures.
+//!
+//! When enabled, each thread counts the nodes it retires (`defer_destroy`) and the operations it
+//! restarts because PEBR ejected it. The counts are thread-local, so a worker takes its own counts
+//! at the end of a run, e.g. to aggregate them per CPU.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static RETRIES: Cell<u64> = Cell::new(0);
+    static RETIRES: Cell<u64> = Cell::new(0);
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Records that an operation is restarted after an ejection.
+#[inline]
+pub fn retry() {
+    if ENABLED.load(Ordering::Relaxed) {
+        RETRIES.with(|r| r.set(r.get() + 1));
+    }
+}
+
+/// Records that a node is retired.
+#[inline]
+pub fn retire() {
+    if ENABLED.load(Ordering::Relaxed) {
+        RETIRES.with(|r| r.set(r.get() + 1));
+    }
+}
+
+/// The number of retires of the current thread since it last took its counts.
+pub fn retires() -> u64 {
+    RETIRES.with(|r| r.get())
+}
+
+/// Takes the numbers of retries and retires of the current thread.
+pub fn take_local() -> (u64, u64) {
+    (
+        RETRIES.with(|r| r.replace(0)),
+        RETIRES.with(|r| r.replace(0)),
+    )
+}
+
+/// Adds to the current thread the numbers of retries and retires that another thread took, e.g.
+/// one that ran the operations of the same worker.
+pub fn add_local((retries, retires): (u64, u64)) {
+    RETRIES.with(|r| r.set(r.get() + retries));
+    RETIRES.with(|r| r.set(r.get() + retires));
+}
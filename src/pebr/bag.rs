@@ -0,0 +1,222 @@
+use super::concurrent_bag::ConcurrentBag;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shield, ShieldError};
+
+use std::cmp;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug)]
+struct Node<T> {
+    data: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+/// Treiber stack of the values added by the threads owning a slot of the bag.
+struct Stack<T> {
+    head: Atomic<Node<T>>,
+}
+
+impl<T> Stack<T> {
+    fn new() -> Self {
+        Stack {
+            head: Atomic::null(),
+        }
+    }
+
+    fn push(&self, data: T, guard: &Guard) {
+        let mut node = Owned::new(Node {
+            data: ManuallyDrop::new(data),
+            next: Atomic::null(),
+        });
+
+        loop {
+            let head = self.head.load(Ordering::Relaxed, guard);
+            node.next.store(head, Ordering::Relaxed);
+            match self
+                .head
+                .compare_and_set(head, node, Ordering::Release, guard)
+            {
+                Ok(_) => return,
+                Err(e) => node = e.new,
+            }
+        }
+    }
+
+    fn pop(&self, shield: &mut Shield<Node<T>>, guard: &Guard) -> Result<Option<T>, ShieldError> {
+        loop {
+            let head = self.head.load(Ordering::Acquire, guard);
+            if head.is_null() {
+                return Ok(None);
+            }
+
+            shield.defend(head, guard)?;
+            let head_ref = unsafe { head.deref() };
+            let next = head_ref.next.load(Ordering::Relaxed, guard);
+
+            if self
+                .head
+                .compare_and_set(head, next, Ordering::Relaxed, guard)
+                .is_ok()
+            {
+                unsafe {
+                    let data = ptr::read(&head_ref.data);
+                    op_stats::retire();
+                    guard.retire(head);
+                    return Ok(Some(ManuallyDrop::into_inner(data)));
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+
+            while !curr.is_null() {
+                let curr_ref = curr.deref_mut();
+                let next = curr_ref.next.load(Ordering::Relaxed, unprotected());
+                ManuallyDrop::drop(&mut curr_ref.data);
+                drop(curr.into_owned());
+                curr = next;
+            }
+        }
+    }
+}
+
+/// A lock-free bag consisting of per-thread stacks. A thread adds values to its own stack, and
+/// steals values from the other threads' stacks when its own stack is empty.
+pub struct Bag<T> {
+    stacks: Vec<Stack<T>>,
+    /// The number of handles created so far. Threads are assigned to the stacks round-robin.
+    registered: AtomicUsize,
+    len: StripedCounter,
+}
+
+pub struct BagHandle<T> {
+    /// The index of the thread's own stack.
+    index: usize,
+    shield: Shield<Node<T>>,
+}
+
+impl<T> BagHandle<T> {
+    pub fn release(&mut self) {
+        self.shield.release();
+    }
+}
+
+impl<T> Bag<T> {
+    pub fn with_slots(n: usize) -> Self {
+        assert!(n > 0);
+        let mut stacks = Vec::with_capacity(n);
+        for _ in 0..n {
+            stacks.push(Stack::new());
+        }
+
+        Bag {
+            stacks,
+            registered: AtomicUsize::new(0),
+            len: StripedCounter::new(),
+        }
+    }
+
+    pub fn handle(&self, guard: &Guard) -> BagHandle<T> {
+        let index = self.registered.fetch_add(1, Ordering::Relaxed) % self.stacks.len();
+        BagHandle {
+            index,
+            shield: Shield::null(guard),
+        }
+    }
+
+    pub fn add(&self, handle: &mut BagHandle<T>, value: T, guard: &Guard) {
+        self.stacks[handle.index].push(value, guard);
+    }
+
+    fn try_remove_any_inner(
+        &self,
+        handle: &mut BagHandle<T>,
+        guard: &Guard,
+    ) -> Result<Option<T>, ShieldError> {
+        if let Some(value) = self.stacks[handle.index].pop(&mut handle.shield, guard)? {
+            return Ok(Some(value));
+        }
+
+        // Steal from the other stacks that are in use.
+        let slots = cmp::min(self.registered.load(Ordering::Relaxed), self.stacks.len());
+        for i in 1..slots {
+            let victim = (handle.index + i) % slots;
+            if let Some(value) = self.stacks[victim].pop(&mut handle.shield, guard)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn try_remove_any(&self, handle: &mut BagHandle<T>, guard: &mut Guard) -> Option<T> {
+        loop {
+            match self.try_remove_any_inner(handle, unsafe { &mut *(guard as *mut Guard) }) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+}
+
+impl<T> ConcurrentBag<T> for Bag<T> {
+    type Handle = BagHandle<T>;
+
+    fn new() -> Self {
+        Self::with_slots(256)
+    }
+
+    fn handle(&self, guard: &Guard) -> Self::Handle {
+        self.handle(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn add(&self, handle: &mut Self::Handle, value: T, guard: &mut Guard) {
+        self.add(handle, value, guard);
+        self.len.add(1);
+    }
+    #[inline]
+    fn try_remove_any(&self, handle: &mut Self::Handle, guard: &mut Guard) -> Option<T> {
+        let removed = self.try_remove_any(handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// Registers the bag.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("Bag", Scheme::PEBR, |d: &D, _| d.pebr_bag::<Bag<String>>());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bag;
+    use crate::pebr::concurrent_bag;
+
+    #[test]
+    fn smoke_bag() {
+        concurrent_bag::tests::smoke::<Bag<i32>>();
+    }
+}
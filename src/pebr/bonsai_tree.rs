@@ -2,12 +2,16 @@ use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shared, Shield, ShieldEr
 
 use super::concurrent_map::ConcurrentMap;
 use super::shield_pool::ShieldPool;
+use crate::bonsai_stats;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
 
 use std::cmp;
+use std::mem;
 use std::sync::atomic::Ordering;
 
-static WEIGHT: usize = 2;
-
 // TODO: optimization from the paper? IBR paper doesn't do that
 
 bitflags! {
@@ -97,7 +101,7 @@ where
     K: Ord + Clone,
     V: Clone,
 {
-    fn new<'g>(guard: &'g Guard) -> Self {
+    fn new(guard: &Guard) -> Self {
         Self {
             root_shield: Shield::null(guard),
             shields: ShieldPool::new(),
@@ -109,6 +113,7 @@ where
     /// Destroy the newly created state (self) that lost the race (reclaim_state)
     fn abort(&mut self) {
         self.root_shield.release();
+        bonsai_stats::record_abort(self.new_nodes.len());
         self.retired_nodes.clear();
 
         for node in self.new_nodes.drain(..) {
@@ -119,6 +124,10 @@ where
     /// Retire the old state replaced by the new_state and the new_state.retired_nodes
     fn commit(&mut self, guard: &Guard) {
         self.root_shield.release();
+        bonsai_stats::record_commit(
+            self.new_nodes.len(),
+            self.retired_nodes.len() * mem::size_of::<Node<K, V>>(),
+        );
         self.new_nodes.clear();
 
         for node in self.retired_nodes.drain(..) {
@@ -130,7 +139,8 @@ where
                 node.deref()
                     .right
                     .store(Node::retired_node(), Ordering::Release);
-                guard.defer_destroy(node);
+                op_stats::retire();
+                guard.retire(node);
             }
         }
     }
@@ -191,12 +201,13 @@ where
 
         let l_size = Node::node_size(left);
         let r_size = Node::node_size(right);
+        let weight = bonsai_stats::weight();
         let res = if r_size > 0
-            && ((l_size > 0 && r_size > WEIGHT * l_size) || (l_size == 0 && r_size > WEIGHT))
+            && ((l_size > 0 && r_size > weight * l_size) || (l_size == 0 && r_size > weight))
         {
             self.mk_balanced_left(left, right, key, value, guard)
         } else if l_size > 0
-            && ((r_size > 0 && l_size > WEIGHT * r_size) || (r_size == 0 && l_size > WEIGHT))
+            && ((r_size > 0 && l_size > weight * r_size) || (r_size == 0 && l_size > weight))
         {
             self.mk_balanced_right(left, right, key, value, guard)
         } else {
@@ -231,10 +242,11 @@ where
         }
 
         // double left rotation
-        return self.double_left(left, right, right_left, right_right, key, value, guard);
+        self.double_left(left, right, right_left, right_right, key, value, guard)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn single_left<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -255,10 +267,11 @@ where
             guard,
         );
         self.retire_node(right);
-        return res;
+        res
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn double_left<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -326,10 +339,11 @@ where
             return Ok(self.single_right(left, right, left_right, left_left, key, value, guard));
         }
         // double right rotation
-        return self.double_right(left, right, left_right, left_left, key, value, guard);
+        self.double_right(left, right, left_right, left_left, key, value, guard)
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn single_right<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -350,10 +364,11 @@ where
             guard,
         );
         self.retire_node(left);
-        return res;
+        res
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn double_right<'g>(
         &mut self,
         left: Shared<'g, Node<K, V>>,
@@ -446,6 +461,7 @@ where
     }
 
     #[inline]
+    #[allow(clippy::type_complexity)]
     fn do_remove<'g>(
         &mut self,
         node: Shared<'g, Node<K, V>>,
@@ -498,6 +514,7 @@ where
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn pull_leftmost<'g>(
         &mut self,
         node: Shared<'g, Node<K, V>>,
@@ -531,9 +548,10 @@ where
             guard,
         );
         self.retire_node(node);
-        return Ok((right, succ));
+        Ok((right, succ))
     }
 
+    #[allow(clippy::type_complexity)]
     fn pull_rightmost<'g>(
         &mut self,
         node: Shared<'g, Node<K, V>>,
@@ -567,12 +585,23 @@ where
             guard,
         );
         self.retire_node(node);
-        return Ok((left, succ));
+        Ok((left, succ))
     }
 }
 
 pub struct BonsaiTreeMap<K, V> {
     root: Atomic<Node<K, V>>,
+    len: StripedCounter,
+}
+
+impl<K, V> Default for BonsaiTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<K, V> BonsaiTreeMap<K, V>
@@ -583,6 +612,7 @@ where
     pub fn new() -> Self {
         Self {
             root: Atomic::null(),
+            len: StripedCounter::new(),
         }
     }
 
@@ -603,6 +633,7 @@ where
                 Ok(r) => return r,
                 Err(ShieldError::Ejected) => {
                     state.root_shield.release();
+                    op_stats::retry();
                     guard.repin();
                 }
             }
@@ -629,6 +660,7 @@ where
             }
 
             if Node::is_retired_spot(node, guard) {
+                restarts::record();
                 continue;
             }
 
@@ -650,11 +682,13 @@ where
             {
                 Err(ShieldError::Ejected) => {
                     state.abort();
+                    op_stats::retry();
                     guard.repin();
                 }
                 Ok((new_root, inserted)) => {
                     if Node::is_retired(new_root) {
                         state.abort();
+                        restarts::record();
                         continue;
                     }
 
@@ -668,6 +702,7 @@ where
                     }
 
                     state.abort();
+                    restarts::record();
                 }
             }
         }
@@ -683,11 +718,13 @@ where
             {
                 Err(ShieldError::Ejected) => {
                     state.abort();
+                    op_stats::retry();
                     guard.repin();
                 }
                 Ok((new_root, value)) => {
                     if Node::is_retired(new_root) {
                         state.abort();
+                        restarts::record();
                         continue;
                     }
 
@@ -701,6 +738,7 @@ where
                     }
 
                     state.abort();
+                    restarts::record();
                 }
             }
         }
@@ -760,13 +798,30 @@ where
 
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.insert(key, value, handle, guard)
+        let inserted = self.insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
 
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.remove(key, handle, guard)
+        let removed = self.remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
     }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    pebr;
+    BonsaiTree => BonsaiTreeMap, Random;
 }
 
 #[cfg(test)]
@@ -1,10 +1,11 @@
+use crate::alloc_fault::AllocError;
 use crossbeam_pebr::Guard;
 
 pub trait ConcurrentMap<K, V> {
     type Handle;
 
     fn new() -> Self;
-    fn handle<'g>(guard: &'g Guard) -> Self::Handle;
+    fn handle(guard: &Guard) -> Self::Handle;
     fn clear(handle: &mut Self::Handle);
 
     fn get<'g>(
@@ -14,7 +15,21 @@ pub trait ConcurrentMap<K, V> {
         guard: &'g mut Guard,
     ) -> Option<&'g V>;
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool;
+    /// Like `insert`, but fails with `AllocError`, leaving the map unchanged, instead of aborting
+    /// if a node cannot be allocated (`alloc_fault`). The maps of PEBR allocate as `insert` does
+    /// and never fail.
+    fn try_insert(
+        &self,
+        handle: &mut Self::Handle,
+        key: K,
+        value: V,
+        guard: &mut Guard,
+    ) -> Result<bool, AllocError> {
+        Ok(self.insert(handle, key, value, guard))
+    }
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V>;
+    /// The approximate number of entries. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
 }
 
 #[cfg(test)]
@@ -24,11 +39,18 @@ pub mod tests {
     use crossbeam_pebr::pin;
     use crossbeam_utils::thread;
     use rand::prelude::*;
+    use std::collections::BTreeMap;
 
     const THREADS: i32 = 30;
     const ELEMENTS_PER_THREADS: i32 = 1000;
 
     pub fn smoke<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        smoke_keyed::<i32, M>(|i| i);
+    }
+
+    /// Like `smoke`, with the keys that `key` makes of the integers, e.g., to smoke-test the
+    /// maps of strings of the registry.
+    pub fn smoke_keyed<K, M: ConcurrentMap<K, String> + Send + Sync>(key: fn(i32) -> K) {
         let map = &M::new();
 
         thread::scope(|s| {
@@ -40,12 +62,13 @@ pub mod tests {
                         (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
                     keys.shuffle(&mut rng);
                     for i in keys {
-                        assert!(map.insert(&mut handle, i, i.to_string(), &mut pin()));
+                        assert!(map.insert(&mut handle, key(i), i.to_string(), &mut pin()));
                     }
                 });
             }
         })
         .unwrap();
+        assert_eq!(map.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
 
         thread::scope(|s| {
             for t in 0..(THREADS / 2) {
@@ -58,13 +81,17 @@ pub mod tests {
                     for i in keys {
                         assert_eq!(
                             i.to_string(),
-                            map.remove(&mut handle, &i, &mut pin()).unwrap()
+                            map.remove(&mut handle, &key(i), &mut pin()).unwrap()
                         );
                     }
                 });
             }
         })
         .unwrap();
+        assert_eq!(
+            map.len_approx(),
+            (THREADS / 2 * ELEMENTS_PER_THREADS) as usize
+        );
 
         thread::scope(|s| {
             for t in (THREADS / 2)..THREADS {
@@ -77,7 +104,7 @@ pub mod tests {
                     for i in keys {
                         assert_eq!(
                             i.to_string(),
-                            *map.get(&mut handle, &i, &mut pin()).unwrap()
+                            *map.get(&mut handle, &key(i), &mut pin()).unwrap()
                         );
                     }
                 });
@@ -85,4 +112,57 @@ pub mod tests {
         })
         .unwrap();
     }
+
+    /// Validates every operation against a shadow map. Each thread owns the keys that are
+    /// congruent to its id, so the results of its operations on them are determined by its own
+    /// shadow, while the keys of the other threads are updated around them.
+    pub fn shadow<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        const THREADS: i32 = 8;
+        const KEYS: i32 = 400;
+        const OPS: usize = 10_000;
+        let map = &M::new();
+
+        let shadows = thread::scope(|s| {
+            let threads = (0..THREADS)
+                .map(|t| {
+                    s.spawn(move |_| {
+                        let mut handle = M::handle(&pin());
+                        let mut rng = rand::thread_rng();
+                        let mut shadow = BTreeMap::new();
+                        for i in 0..OPS {
+                            let k = rng.gen_range(0, KEYS / THREADS) * THREADS + t;
+                            let guard = &mut pin();
+                            match rng.gen_range(0, 3) {
+                                0 => assert_eq!(map.get(&mut handle, &k, guard), shadow.get(&k)),
+                                1 => {
+                                    let value = format!("{}-{}", k, i);
+                                    let inserted = map.insert(&mut handle, k, value.clone(), guard);
+                                    assert_eq!(inserted, !shadow.contains_key(&k));
+                                    shadow.entry(k).or_insert(value);
+                                }
+                                _ => assert_eq!(
+                                    map.remove(&mut handle, &k, guard),
+                                    shadow.remove(&k)
+                                ),
+                            }
+                        }
+                        shadow
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|t| t.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        let mut handle = M::handle(&pin());
+        let guard = &mut pin();
+        let expected = shadows.into_iter().flatten().collect::<BTreeMap<_, _>>();
+        for k in 0..KEYS {
+            assert_eq!(map.get(&mut handle, &k, guard), expected.get(&k));
+        }
+        assert_eq!(map.len_approx(), expected.len());
+    }
 }
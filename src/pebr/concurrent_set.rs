@@ -0,0 +1,164 @@
+use super::concurrent_map::ConcurrentMap;
+use crossbeam_pebr::Guard;
+
+pub trait ConcurrentSet<K> {
+    type Handle;
+
+    fn new() -> Self;
+    fn handle(guard: &Guard) -> Self::Handle;
+    fn clear(handle: &mut Self::Handle);
+
+    fn contains(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> bool;
+    fn insert(&self, handle: &mut Self::Handle, key: K, guard: &mut Guard) -> bool;
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> bool;
+    /// The approximate number of keys. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
+}
+
+/// The set of the keys of a map whose values are `()`, which take no space in the nodes and cost
+/// nothing to copy out.
+pub struct MapSet<M> {
+    map: M,
+}
+
+impl<K, M> ConcurrentSet<K> for MapSet<M>
+where
+    M: ConcurrentMap<K, ()>,
+{
+    type Handle = M::Handle;
+
+    fn new() -> Self {
+        Self { map: M::new() }
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        M::handle(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        M::clear(handle);
+    }
+
+    #[inline]
+    fn contains(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> bool {
+        self.map.get(handle, key, guard).is_some()
+    }
+
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, guard: &mut Guard) -> bool {
+        self.map.insert(handle, key, (), guard)
+    }
+
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> bool {
+        self.map.remove(handle, key, guard).is_some()
+    }
+
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.map.len_approx()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate rand;
+    use super::ConcurrentSet;
+    #[cfg(any(feature = "lists", feature = "trees"))]
+    use super::MapSet;
+    #[cfg(feature = "trees")]
+    use crate::pebr::{BonsaiTreeMap, NMTreeMap};
+    #[cfg(feature = "lists")]
+    use crate::pebr::{HList, HMList};
+    use crossbeam_pebr::pin;
+    use crossbeam_utils::thread;
+    use rand::prelude::*;
+
+    const THREADS: i32 = 30;
+    const ELEMENTS_PER_THREADS: i32 = 1000;
+
+    pub fn smoke<S: ConcurrentSet<i32> + Send + Sync>() {
+        smoke_keyed::<i32, S>(|i| i);
+    }
+
+    /// Like `smoke`, with the keys that `key` makes of the integers, e.g., to smoke-test the
+    /// sets of strings of the registry.
+    pub fn smoke_keyed<K, S: ConcurrentSet<K> + Send + Sync>(key: fn(i32) -> K) {
+        let set = &S::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let mut handle = S::handle(&pin());
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert!(set.insert(&mut handle, key(i), &mut pin()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(set.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
+
+        thread::scope(|s| {
+            for t in 0..(THREADS / 2) {
+                s.spawn(move |_| {
+                    let mut handle = S::handle(&pin());
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert!(set.remove(&mut handle, &key(i), &mut pin()));
+                        assert!(!set.contains(&mut handle, &key(i), &mut pin()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            set.len_approx(),
+            (THREADS / 2 * ELEMENTS_PER_THREADS) as usize
+        );
+
+        thread::scope(|s| {
+            for t in (THREADS / 2)..THREADS {
+                s.spawn(move |_| {
+                    let mut handle = S::handle(&pin());
+                    for i in (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t) {
+                        assert!(set.contains(&mut handle, &key(i), &mut pin()));
+                        assert!(!set.insert(&mut handle, key(i), &mut pin()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "lists")]
+    fn smoke_h_list_set() {
+        smoke::<MapSet<HList<i32, ()>>>();
+    }
+
+    #[test]
+    #[cfg(feature = "lists")]
+    fn smoke_hm_list_set() {
+        smoke::<MapSet<HMList<i32, ()>>>();
+    }
+
+    #[test]
+    #[cfg(feature = "trees")]
+    fn smoke_nm_tree_set() {
+        smoke::<MapSet<NMTreeMap<i32, ()>>>();
+    }
+
+    #[test]
+    #[cfg(feature = "trees")]
+    fn smoke_bonsai_tree_set() {
+        smoke::<MapSet<BonsaiTreeMap<i32, ()>>>();
+    }
+}
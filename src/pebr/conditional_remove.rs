@@ -0,0 +1,148 @@
+use super::concurrent_map::ConcurrentMap;
+use crossbeam_pebr::Guard;
+
+/// A map that removes an entry only on a condition of its value, for the compare-and-delete of a
+/// cache, e.g., to evict an entry only if it is still the one that was read.
+///
+/// The condition is checked on the node that the remove marks, so the remove is atomic with respect
+/// to the other updates of the entry. The lists, the marked lists and the hash map implement it.
+pub trait ConditionalRemove<K, V>: ConcurrentMap<K, V> {
+    /// Removes the entry of `key` only if `pred` holds for its value.
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool;
+}
+
+/// A map whose removes are the compare-and-deletes of a cache: a remove reads the value of its key,
+/// and then removes the entry only if it still holds an equal value, with `remove_if`.
+pub struct CompareAndDelete<M> {
+    map: M,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for CompareAndDelete<M>
+where
+    V: Clone + PartialEq,
+    M: ConditionalRemove<K, V>,
+{
+    type Handle = M::Handle;
+
+    fn new() -> Self {
+        Self { map: M::new() }
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        M::handle(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        M::clear(handle);
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &'g K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.map.get(handle, key, guard)
+    }
+
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        self.map.insert(handle, key, value, guard)
+    }
+
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        // The handle protects the value that was read only until its next use.
+        let read = self.map.get(handle, key, guard)?.clone();
+        self.map
+            .remove_if(handle, key, |value| *value == read, guard)
+    }
+
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.map.len_approx()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    #[cfg(any(feature = "lists", feature = "hash-maps"))]
+    use super::CompareAndDelete;
+    use super::ConditionalRemove;
+    #[cfg(any(feature = "lists", feature = "hash-maps"))]
+    use crate::pebr::concurrent_map;
+    #[cfg(feature = "lists")]
+    use crate::pebr::HMList;
+    #[cfg(feature = "hash-maps")]
+    use crate::pebr::HashMap;
+    use crossbeam_pebr::pin;
+    use crossbeam_utils::thread;
+
+    /// Races the threads to remove every entry on the condition of its value. Only the threads
+    /// whose condition holds may remove an entry, and exactly one of them does.
+    pub fn remove_if<M: ConditionalRemove<i32, String> + Send + Sync>() {
+        const THREADS: i32 = 8;
+        const VALUES: i32 = 4;
+        const KEYS: i32 = 1000;
+        let map = &M::new();
+
+        let value = |k: i32| (k % VALUES).to_string();
+        let mut handle = M::handle(&pin());
+        for k in 0..KEYS {
+            assert!(map.insert(&mut handle, k, value(k), &mut pin()));
+        }
+        assert_eq!(
+            map.remove_if(&mut handle, &1, |v| v == "0", &mut pin()),
+            None
+        );
+        assert_eq!(
+            map.get(&mut handle, &1, &mut pin()).cloned(),
+            Some(value(1))
+        );
+
+        let removed = thread::scope(|s| {
+            let threads = (0..THREADS)
+                .map(|t| {
+                    s.spawn(move |_| {
+                        let mut handle = M::handle(&pin());
+                        let expected = value(t);
+                        let mut removed = 0;
+                        for k in 0..KEYS {
+                            let pred = |v: &String| *v == expected;
+                            if let Some(v) = map.remove_if(&mut handle, &k, pred, &mut pin()) {
+                                assert_eq!(v, expected);
+                                removed += 1;
+                            }
+                        }
+                        removed
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads.into_iter().map(|t| t.join().unwrap()).sum::<i32>()
+        })
+        .unwrap();
+        assert_eq!(removed, KEYS);
+        assert_eq!(map.len_approx(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "lists")]
+    fn smoke_compare_and_delete_hm_list() {
+        concurrent_map::tests::smoke::<CompareAndDelete<HMList<i32, String>>>();
+    }
+
+    #[test]
+    #[cfg(feature = "hash-maps")]
+    fn smoke_compare_and_delete_hashmap() {
+        concurrent_map::tests::smoke::<CompareAndDelete<HashMap<i32, String>>>();
+    }
+}
@@ -0,0 +1,132 @@
+//! A hash map whose buckets are Natarajan-Mittal trees instead of lists.
+//!
+//! With fewer buckets than `HashMap`, each bucket holds many keys, but a traversal still visits
+//! only the logarithmic path of its key in the tree of the bucket, so it holds its shields over
+//! fewer nodes. The bucket count is read from `set_buckets` when a map is created.
+
+use super::concurrent_map::ConcurrentMap;
+use super::natarajan_mittal_tree::{NMTreeMap, SeekRecord};
+use crate::hashing::Hashing;
+use crate::striped_counter::StripedCounter;
+use crossbeam_pebr::Guard;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUCKETS: AtomicUsize = AtomicUsize::new(1024);
+
+/// Sets the number of buckets of the maps created afterwards.
+pub fn set_buckets(buckets: usize) {
+    assert!(buckets > 0);
+    BUCKETS.store(buckets, Ordering::Relaxed);
+}
+
+pub struct HashTreeMap<K, V> {
+    buckets: Vec<NMTreeMap<K, V>>,
+    len: StripedCounter,
+    hashing: Hashing,
+}
+
+impl<K, V> HashTreeMap<K, V>
+where
+    K: Ord + Clone + Hash + 'static,
+    V: Clone + 'static,
+{
+    pub fn with_buckets(n: usize) -> Self {
+        assert!(n > 0);
+        HashTreeMap {
+            buckets: (0..n).map(|_| NMTreeMap::new()).collect(),
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+        }
+    }
+
+    #[inline]
+    fn bucket(&self, k: &K) -> &NMTreeMap<K, V> {
+        let index = self.hashing.hash(k) as usize % self.buckets.len();
+        unsafe { self.buckets.get_unchecked(index) }
+    }
+
+    pub fn get<'g>(
+        &'g self,
+        record: &'g mut SeekRecord<K, V>,
+        k: &'g K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.bucket(k).get(k, record, guard)
+    }
+
+    pub fn insert(&self, record: &mut SeekRecord<K, V>, k: K, v: V, guard: &mut Guard) -> bool {
+        self.bucket(&k).insert(k, v, record, guard).is_ok()
+    }
+
+    pub fn remove(&self, record: &mut SeekRecord<K, V>, k: &K, guard: &mut Guard) -> Option<V> {
+        self.bucket(k).remove(k, record, guard)
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for HashTreeMap<K, V>
+where
+    K: Ord + Clone + Hash + 'static,
+    V: Clone + 'static,
+{
+    /// A record shared by the trees of all the buckets.
+    type Handle = SeekRecord<K, V>;
+
+    fn new() -> Self {
+        Self::with_buckets(BUCKETS.load(Ordering::Relaxed))
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        <NMTreeMap<K, V> as ConcurrentMap<K, V>>::handle(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        <NMTreeMap<K, V> as ConcurrentMap<K, V>>::clear(handle);
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &'g K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.get(handle, key, guard)
+    }
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.insert(handle, key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        let removed = self.remove(handle, key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    pebr;
+    HashTreeMap => HashTreeMap, Random;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashTreeMap;
+    use crate::pebr::concurrent_map;
+
+    #[test]
+    fn smoke_hash_tree_map() {
+        concurrent_map::tests::smoke::<HashTreeMap<i32, String>>();
+    }
+}
@@ -0,0 +1,430 @@
+//! A leaf-oriented binary search tree whose updates are multi-word CASes.
+//!
+//! The entries are in the leaves, and each internal node has two children. An insertion replaces a
+//! leaf with an internal node whose children are the leaf and a new one, with an MCAS of the child
+//! pointer of the parent. A removal replaces the parent of the leaf with the sibling of the leaf,
+//! and marks both child pointers of the parent so that no other update modifies the removed parent,
+//! with a single MCAS of the three words.
+
+use crossbeam_pebr::{unprotected, Guard, Owned, Shared, Shield, ShieldError};
+
+use super::concurrent_map::ConcurrentMap;
+use super::mcas::{Helper, Mcas, Word};
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use std::cmp;
+use std::mem;
+
+/// Marks the child pointers of a removed internal node. The low 2 bits are reserved for MCAS.
+const MARK: usize = 4;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Key<K> {
+    Fin(K),
+    Inf,
+}
+
+impl<K: Ord> Key<K> {
+    fn cmp(&self, rhs: &K) -> cmp::Ordering {
+        match self {
+            Key::Fin(k) => k.cmp(rhs),
+            Key::Inf => cmp::Ordering::Greater,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Direction {
+    L,
+    R,
+}
+
+impl Direction {
+    fn other(self) -> Self {
+        match self {
+            Direction::L => Direction::R,
+            Direction::R => Direction::L,
+        }
+    }
+}
+
+/// Aligned so that the pointers to nodes leave room for the mark.
+#[repr(align(8))]
+struct Node<K, V> {
+    key: Key<K>,
+    value: Option<V>,
+    /// The children of an internal node, possibly marked. Zero in a leaf.
+    left: Word,
+    right: Word,
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    fn leaf(key: Key<K>, value: Option<V>) -> Self {
+        Self {
+            key,
+            value,
+            left: Word::new(0),
+            right: Word::new(0),
+        }
+    }
+
+    /// Keys less than `key` go left.
+    fn internal(key: Key<K>, left: usize, right: usize) -> Self {
+        Self {
+            key,
+            value: None,
+            left: Word::new(left),
+            right: Word::new(right),
+        }
+    }
+
+    fn child(&self, dir: Direction) -> &Word {
+        match dir {
+            Direction::L => &self.left,
+            Direction::R => &self.right,
+        }
+    }
+
+    fn dir(&self, key: &K) -> Direction {
+        if self.key.cmp(key) == cmp::Ordering::Greater {
+            Direction::L
+        } else {
+            Direction::R
+        }
+    }
+}
+
+/// Converts a possibly marked word to a pointer to a node.
+fn from_word<'g, K, V>(word: usize) -> Shared<'g, Node<K, V>> {
+    Shared::from((word & !MARK) as *const Node<K, V>)
+}
+
+fn into_word<K, V>(node: Shared<'_, Node<K, V>>) -> usize {
+    node.as_raw() as usize
+}
+
+pub struct Cursor<K, V> {
+    gp: Shield<Node<K, V>>,
+    gp_dir: Direction,
+    p: Shield<Node<K, V>>,
+    p_dir: Direction,
+    l: Shield<Node<K, V>>,
+    /// The child pointer of `p` to `l`, possibly marked.
+    l_word: usize,
+    helper: Helper,
+}
+
+impl<K, V> Cursor<K, V> {
+    fn new(guard: &Guard) -> Self {
+        Self {
+            gp: Shield::null(guard),
+            gp_dir: Direction::L,
+            p: Shield::null(guard),
+            p_dir: Direction::L,
+            l: Shield::null(guard),
+            l_word: 0,
+            helper: Helper::new(),
+        }
+    }
+
+    fn release(&mut self) {
+        self.gp.release();
+        self.p.release();
+        self.l.release();
+    }
+}
+
+pub struct KCASTreeMap<K, V> {
+    r: Node<K, V>,
+    len: StripedCounter,
+}
+
+impl<K, V> Default for KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for KCASTreeMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack = vec![self.r.left.value(), self.r.right.value()];
+        while let Some(word) = stack.pop() {
+            if word == 0 {
+                continue;
+            }
+            let mut node = unsafe { from_word::<K, V>(word).into_owned() };
+            stack.push(node.left.value());
+            stack.push(node.right.value());
+        }
+    }
+}
+
+impl<K, V> KCASTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        // As in the Natarajan-Mittal tree, the sentinels with infinite keys make sure that a leaf
+        // with a finite key has a parent and a grandparent.
+        //          r
+        //         / \
+        //        s  inf2
+        //       / \
+        //   inf0   inf1
+        let guard = unsafe { unprotected() };
+        let leaf = || into_word(Owned::new(Node::<K, V>::leaf(Key::Inf, None)).into_shared(guard));
+        let s = Owned::new(Node::<K, V>::internal(Key::Inf, leaf(), leaf())).into_shared(guard);
+        Self {
+            r: Node::internal(Key::Inf, into_word(s), leaf()),
+            len: StripedCounter::new(),
+        }
+    }
+
+    fn seek(&self, key: &K, cursor: &mut Cursor<K, V>, guard: &Guard) -> Result<(), ShieldError> {
+        unsafe { cursor.p.defend_fake(Shared::from(&self.r as *const _)) };
+        cursor.p_dir = Direction::L;
+        cursor.l_word = self.r.left.read(&mut cursor.helper, guard)?;
+        cursor.l.defend(from_word(cursor.l_word), guard)?;
+        loop {
+            let l = unsafe { cursor.l.deref() };
+            let dir = l.dir(key);
+            let next = l.child(dir).read(&mut cursor.helper, guard)?;
+            if next == 0 {
+                return Ok(());
+            }
+            mem::swap(&mut cursor.gp, &mut cursor.p);
+            mem::swap(&mut cursor.p, &mut cursor.l);
+            cursor.gp_dir = cursor.p_dir;
+            cursor.p_dir = dir;
+            cursor.l_word = next;
+            cursor.l.defend(from_word(next), guard)?;
+        }
+    }
+
+    pub fn get_inner<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g Guard,
+    ) -> Result<Option<&'g V>, ShieldError> {
+        self.seek(key, cursor, guard)?;
+        let l = unsafe { cursor.l.deref() };
+        if l.key.cmp(key) == cmp::Ordering::Equal {
+            Ok(l.value.as_ref())
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        loop {
+            match self.get_inner(
+                key,
+                unsafe { &mut *(cursor as *mut Cursor<K, V>) },
+                unsafe { &mut *(guard as *mut Guard) },
+            ) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    fn insert_inner(
+        &self,
+        key: &K,
+        leaf: Shared<'_, Node<K, V>>,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Result<bool, ShieldError> {
+        loop {
+            self.seek(key, cursor, guard)?;
+            if cursor.l_word & MARK != 0 {
+                // The parent is removed.
+                restarts::record();
+                continue;
+            }
+            let (left, right) = match unsafe { cursor.l.deref() }.key.cmp(key) {
+                cmp::Ordering::Equal => return Ok(false),
+                cmp::Ordering::Greater => (leaf, cursor.l.shared()),
+                cmp::Ordering::Less => (cursor.l.shared(), leaf),
+            };
+            let internal = Owned::new(Node::<K, V>::internal(
+                unsafe { right.deref() }.key.clone(),
+                into_word(left),
+                into_word(right),
+            ))
+            .into_shared(unsafe { unprotected() });
+
+            let p = unsafe { cursor.p.deref() };
+            let mut op = Mcas::new();
+            op.add(p, p.child(cursor.p_dir), cursor.l_word, into_word(internal));
+            if op.execute(&mut cursor.helper, guard) {
+                return Ok(true);
+            }
+            drop(unsafe { internal.into_owned() });
+            restarts::record();
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V, cursor: &mut Cursor<K, V>, guard: &mut Guard) -> bool {
+        let leaf = Owned::new(Node::<K, V>::leaf(Key::Fin(key.clone()), Some(value)))
+            .into_shared(unsafe { unprotected() });
+        loop {
+            match self.insert_inner(&key, leaf, cursor, guard) {
+                Ok(true) => return true,
+                Ok(false) => {
+                    drop(unsafe { leaf.into_owned() });
+                    return false;
+                }
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    fn remove_inner(
+        &self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Result<Option<V>, ShieldError> {
+        loop {
+            self.seek(key, cursor, guard)?;
+            let l = unsafe { cursor.l.deref() };
+            if l.key.cmp(key) != cmp::Ordering::Equal {
+                return Ok(None);
+            }
+            let p = unsafe { cursor.p.deref() };
+            let sibling_dir = cursor.p_dir.other();
+            let sibling = p.child(sibling_dir).read(&mut cursor.helper, guard)?;
+            if (cursor.l_word | sibling) & MARK != 0 {
+                // The parent is removed.
+                restarts::record();
+                continue;
+            }
+
+            let value = l.value.clone();
+            let gp = unsafe { cursor.gp.deref() };
+            let p_word = into_word(cursor.p.shared());
+            let mut op = Mcas::new();
+            op.add(gp, gp.child(cursor.gp_dir), p_word, sibling);
+            op.add(
+                p,
+                p.child(cursor.p_dir),
+                cursor.l_word,
+                cursor.l_word | MARK,
+            );
+            op.add(p, p.child(sibling_dir), sibling, sibling | MARK);
+            if op.execute(&mut cursor.helper, guard) {
+                unsafe {
+                    op_stats::retire();
+                    guard.retire(cursor.p.shared());
+                    op_stats::retire();
+                    guard.retire(cursor.l.shared());
+                }
+                return Ok(value);
+            }
+            restarts::record();
+        }
+    }
+
+    pub fn remove(&self, key: &K, cursor: &mut Cursor<K, V>, guard: &mut Guard) -> Option<V> {
+        loop {
+            match self.remove_inner(key, cursor, guard) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for KCASTreeMap<K, V>
+where
+    K: Ord + Clone + 'static,
+    V: Clone + 'static,
+{
+    type Handle = Cursor<K, V>;
+
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        Cursor::new(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &'g K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.get(key, handle, guard)
+    }
+
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        let removed = self.remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    pebr;
+    KCASTree => KCASTreeMap, Random;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KCASTreeMap;
+    use crate::pebr::concurrent_map;
+
+    #[test]
+    fn smoke_kcas_tree() {
+        concurrent_map::tests::smoke::<KCASTreeMap<i32, String>>();
+    }
+}
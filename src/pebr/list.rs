@@ -1,7 +1,23 @@
 use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use super::ordered_map::OrderedMap;
+use super::shield_cache::ShieldCache;
+use crate::cas_fault::pebr::CompareAndSet;
+use crate::harris_stats;
+use crate::load_ordering;
+use crate::op_stats;
+use crate::profiling;
+use crate::reclaim::pebr::Retire;
+use crate::remove_phases::{self, Phase};
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crate::tagged::{FetchOr, MarkOp};
 use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Pointer, Shared, Shield, ShieldError};
 
 use std::cmp::Ordering::{Equal, Greater, Less};
+use std::marker::PhantomData;
+#[cfg(feature = "sentinel-lists")]
+use std::mem::MaybeUninit;
 use std::mem::{self, ManuallyDrop};
 use std::ptr;
 use std::sync::atomic::Ordering;
@@ -19,25 +35,48 @@ struct Node<K, V> {
     value: ManuallyDrop<V>,
 }
 
-pub struct List<K, V> {
+#[cfg(feature = "sentinel-lists")]
+impl<K, V> Node<K, V> {
+    /// Allocates a sentinel that links to `next`. Its key and value are left uninitialized, as
+    /// they are never read.
+    fn sentinel(next: Shared<'_, Self>) -> Shared<'_, Self> {
+        let mut node = Box::new(MaybeUninit::<Self>::uninit());
+        unsafe { ptr::write(&mut (*node.as_mut_ptr()).next, Atomic::from(next)) };
+        Shared::from(Box::into_raw(node) as *const Self)
+    }
+
+    unsafe fn free_sentinel(node: Shared<'_, Self>) {
+        drop(Box::from_raw(node.as_raw() as *mut MaybeUninit<Self>));
+    }
+}
+
+/// With the `sentinel-lists` feature, the list starts with a head sentinel and ends with a tail
+/// sentinel that are never retired, so that no link of the list is null and `Cursor::prev` always
+/// protects a real node. Otherwise `head` links to the first node and the last node links to null.
+pub struct List<K, V, M = FetchOr> {
     head: Atomic<Node<K, V>>,
+    #[cfg(feature = "sentinel-lists")]
+    tail: Atomic<Node<K, V>>,
+    _marker: PhantomData<M>,
 }
 
-impl<K, V> Default for List<K, V>
+impl<K, V, M> Default for List<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<K, V> Drop for List<K, V> {
+impl<K, V, M> Drop for List<K, V, M> {
     fn drop(&mut self) {
         unsafe {
-            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+            let mut curr = self.first().load(Ordering::Relaxed, unprotected());
+            let end = self.end();
 
-            while !curr.is_null() {
+            while curr.with_tag(0) != end {
                 let curr_ref = curr.deref_mut();
                 let next = curr_ref.next.load(Ordering::Relaxed, unprotected());
                 if next.tag() == 0 {
@@ -46,13 +85,83 @@ impl<K, V> Drop for List<K, V> {
                 drop(curr.into_owned());
                 curr = next;
             }
+
+            #[cfg(feature = "sentinel-lists")]
+            {
+                Node::free_sentinel(self.start());
+                Node::free_sentinel(end);
+            }
         }
     }
 }
 
+impl<K, V, M> List<K, V, M> {
+    /// The node that links to the first node.
+    #[cfg(feature = "sentinel-lists")]
+    #[inline]
+    fn start(&self) -> Shared<'_, Node<K, V>> {
+        unsafe { self.head.load(Ordering::Relaxed, unprotected()) }
+    }
+
+    /// The node that links to the first node, faked by `self.head`.
+    #[cfg(not(feature = "sentinel-lists"))]
+    #[inline]
+    fn start(&self) -> Shared<'_, Node<K, V>> {
+        // HACK(@jeehoonkang): we're unsafely assuming the first 8 bytes of both `Node<K, V>`
+        // and `List<K, V>` are `Atomic<Node<K, V>>`.
+        unsafe { Shared::from_usize(&self.head as *const _ as usize) }
+    }
+
+    /// The link to the first node.
+    #[cfg(feature = "sentinel-lists")]
+    #[inline]
+    fn first(&self) -> &Atomic<Node<K, V>> {
+        unsafe { &self.start().deref().next }
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
+    #[inline]
+    fn first(&self) -> &Atomic<Node<K, V>> {
+        &self.head
+    }
+
+    /// The pointer that ends the list.
+    #[cfg(feature = "sentinel-lists")]
+    #[inline]
+    fn end(&self) -> Shared<'_, Node<K, V>> {
+        unsafe { self.tail.load(Ordering::Relaxed, unprotected()) }
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
+    #[inline]
+    fn end(&self) -> Shared<'_, Node<K, V>> {
+        Shared::null()
+    }
+
+    /// The number of entries, i.e. the unmarked nodes, counted without synchronization.
+    #[cfg(any(feature = "hash-maps", feature = "queues"))]
+    pub(crate) fn len_exclusive(&mut self) -> usize {
+        let mut len = 0;
+        unsafe {
+            let mut curr = self.first().load(Ordering::Relaxed, unprotected());
+            let end = self.end();
+            while curr.with_tag(0) != end {
+                let next = curr.deref().next.load(Ordering::Relaxed, unprotected());
+                len += (next.tag() == 0) as usize;
+                curr = next;
+            }
+        }
+        len
+    }
+}
+
 pub struct Cursor<K, V> {
     prev: Shield<Node<K, V>>,
     curr: Shield<Node<K, V>>,
+    /// Protects the nodes near the head.
+    cache: ShieldCache<Node<K, V>>,
+    /// The pointer that ends the list of the current operation.
+    end: usize,
 }
 
 impl<K, V> Cursor<K, V> {
@@ -60,29 +169,72 @@ impl<K, V> Cursor<K, V> {
         Self {
             prev: Shield::null(guard),
             curr: Shield::null(guard),
+            cache: ShieldCache::new(guard),
+            end: 0,
         }
     }
 
+    /// Releases the shields of the cursor. The cache is kept for the next operations.
     pub fn release(&mut self) {
         self.prev.release();
         self.curr.release();
     }
 
-    fn init_find(&mut self, head: &Atomic<Node<K, V>>) {
-        // HACK(@jeehoonkang): we're unsafely assuming the first 8 bytes of both `Node<K, V>`
-        // and `List<K, V>` are `Atomic<Node<K, V>>`.
+    /// Makes `prev` protect `start`, which is never retired, and `end` end the list.
+    fn init_find(&mut self, start: Shared<'_, Node<K, V>>, end: Shared<'_, Node<K, V>>) {
+        self.end = end.into_usize();
+        unsafe { self.prev.defend_fake(start) };
+    }
+
+    /// Whether `ptr`, which may be tagged, ends the list.
+    #[inline]
+    fn is_end(&self, ptr: Shared<'_, Node<K, V>>) -> bool {
+        if cfg!(feature = "sentinel-lists") {
+            ptr.with_tag(0).into_usize() == self.end
+        } else {
+            ptr.is_null()
+        }
+    }
+}
+
+/// Unlinks the marked nodes from `prev_next` to `curr` (exclusive) with a single CAS on `prev.next`.
+#[inline]
+fn unlink<'g, K, V>(
+    prev: &Node<K, V>,
+    prev_next: Shared<'g, Node<K, V>>,
+    curr: Shared<'g, Node<K, V>>,
+    guard: &'g Guard,
+) -> Result<(), FindError> {
+    if prev
+        .next
+        .cas(prev_next, curr, Ordering::Release, guard)
+        .is_err()
+    {
+        return Err(FindError::Retry);
+    }
+
+    // defer_destroy from cursor.prev.load() to cursor.curr (exclusive)
+    let mut node = prev_next;
+    let mut batch = 0;
+    while node.with_tag(0) != curr {
+        let node_ref = unsafe { node.as_ref().unwrap() };
+        let next = node_ref.next.load(Ordering::Acquire, guard);
         unsafe {
-            self.prev
-                .defend_fake(Shared::from_usize(head as *const _ as usize));
+            op_stats::retire();
+            guard.retire(node);
         }
+        node = next;
+        batch += 1;
     }
+    harris_stats::record(batch);
+    Ok(())
 }
 
 /// Note `guard: &'g Guard`. The inner functions should fail if ejected. Repinning is the job of
 /// the wrapper function.
 ///
-/// Each find function expects `self.prev` to fake-defend
-/// `Shared::from_usize(&list.head as *const Atomic<Node<K, V>> as usize)`.
+/// Each find function expects `self.prev` to fake-defend the start of the list (see
+/// `Cursor::init_find`).
 impl<K, V> Cursor<K, V>
 where
     K: Ord,
@@ -113,38 +265,50 @@ where
             }
         }
 
-        let head = unsafe { &*(prev_s.shared().into_usize() as *const Atomic<Node<K, V>>) };
+        let head = unsafe { &prev_s.deref().next };
         let mut curr = head.load(Ordering::Acquire, guard);
+        let max_batch = harris_stats::max_batch();
         let mut prev_next = curr;
+        let mut batch = 0;
+        let mut depth = 0;
 
         let found = 'found: loop {
             for _ in 0..2 {
-                if curr.is_null() {
+                if self.is_end(curr) {
                     unsafe { curr_s.defend_fake(curr) };
                     break 'found false;
                 }
 
-                curr_s.defend(curr, guard).map_err(FindError::ShieldError)?;
+                self.cache
+                    .defend(depth, curr, &mut curr_s, guard)
+                    .map_err(FindError::ShieldError)?;
+                depth += 1;
                 let curr_node = unsafe { curr.deref() };
 
-                let next = curr_node.next.load(Ordering::Acquire, guard);
+                let next = curr_node.next.load(load_ordering::NEXT, guard);
 
                 // - finding stage is done if cursor.curr advancement stops
                 // - advance cursor.curr if (.next is marked) || (cursor.curr < key)
                 // - stop cursor.curr if (not marked) && (cursor.curr >= key)
                 // - advance cursor.prev if not marked
                 match (curr_node.key.cmp(key), next.tag()) {
-                    (Less, tag) => {
+                    (Less, 0) => {
+                        curr = next;
+                        mem::swap(&mut prev_s, &mut curr_s);
+                        prev_next = next;
+                        batch = 0;
+                    }
+                    (cmp, 0) => break 'found cmp == Equal,
+                    _ => {
                         curr = next.with_tag(0);
-                        if tag == 0 {
-                            let t = prev_s;
-                            prev_s = curr_s;
-                            curr_s = t;
-                            prev_next = next;
+                        batch += 1;
+                        // Unlink the chain so far if it's too long.
+                        if batch == max_batch {
+                            unlink(unsafe { prev_s.deref() }, prev_next, curr, guard)?;
+                            prev_next = curr;
+                            batch = 0;
                         }
                     }
-                    (cmp, 0) => break 'found cmp == Equal,
-                    _ => curr = next.with_tag(0),
                 }
             }
         };
@@ -155,48 +319,65 @@ where
         }
 
         // cleanup marked nodes between prev and curr
-        if unsafe { prev_s.deref() }
-            .next
-            .compare_and_set(prev_next, curr, Ordering::Release, guard)
-            .is_err()
-        {
-            return Err(FindError::Retry);
-        }
-
-        // defer_destroy from cursor.prev.load() to cursor.curr (exclusive)
-        let mut node = prev_next;
-        loop {
-            if node.with_tag(0) == curr {
-                return Ok(found);
-            }
-            let node_ref = unsafe { node.as_ref().unwrap() };
-            let next = node_ref.next.load(Ordering::Acquire, guard);
-            unsafe {
-                guard.defer_destroy(node);
-            }
-            node = next;
-        }
+        unlink(unsafe { prev_s.deref() }, prev_next, curr, guard)?;
+        Ok(found)
     }
 
     #[inline]
     fn find_harris_michael<'g>(&mut self, key: &K, guard: &'g Guard) -> Result<bool, FindError> {
-        let head = unsafe { &*(self.prev.shared().into_usize() as *const Atomic<Node<K, V>>) };
+        self.find_harris_michael_inner(key, false, guard)
+    }
+
+    /// Cleans up like `find_harris_michael`, but validates that `prev` still points to `curr`
+    /// before every step. When the validation or a cleanup CAS fails, it resumes from `prev` rather
+    /// than failing, and fails only if the node of `prev` is removed. The extra load makes it
+    /// slower, so the operations fall back to it only after many failures (see `restarts`).
+    #[inline]
+    fn find_validated<'g>(&mut self, key: &K, guard: &'g Guard) -> Result<bool, FindError> {
+        self.find_harris_michael_inner(key, true, guard)
+    }
+
+    /// The traversal of `find_harris_michael`, and with `validate` of `find_validated`. The callers
+    /// pass a constant, so each of them gets a copy without the branches of the other.
+    #[inline(always)]
+    fn find_harris_michael_inner<'g>(
+        &mut self,
+        key: &K,
+        validate: bool,
+        guard: &'g Guard,
+    ) -> Result<bool, FindError> {
+        let head = unsafe { &self.prev.deref().next };
         let mut curr = head.load(Ordering::Acquire, guard);
+        let mut depth = 0;
 
-        let result = 'result: loop {
+        'result: loop {
             for _ in 0..2 {
                 debug_assert_eq!(curr.tag(), 0);
-                if curr.is_null() {
+                if self.is_end(curr) {
                     unsafe { self.curr.defend_fake(curr) };
                     break 'result Ok(false);
                 }
 
-                self.curr
-                    .defend(curr, guard)
+                self.cache
+                    .defend(depth, curr, &mut self.curr, guard)
                     .map_err(FindError::ShieldError)?;
+                depth += 1;
                 let curr_node = unsafe { curr.deref() };
 
-                let mut next = curr_node.next.load(Ordering::Acquire, guard);
+                let mut next = curr_node.next.load(load_ordering::NEXT, guard);
+
+                if validate {
+                    let prev_next = unsafe { self.prev.deref() }
+                        .next
+                        .load(Ordering::Acquire, guard);
+                    if prev_next != curr {
+                        if prev_next.tag() != 0 {
+                            break 'result Err(FindError::Retry);
+                        }
+                        curr = prev_next;
+                        continue;
+                    }
+                }
 
                 if next.tag() == 0 {
                     match curr_node.key.cmp(key) {
@@ -208,18 +389,36 @@ where
                     next = next.with_tag(0);
                     if unsafe { self.prev.deref() }
                         .next
-                        .compare_and_set(curr, next, Ordering::Release, guard)
+                        .cas(curr, next, Ordering::Release, guard)
                         .is_ok()
                     {
-                        unsafe { guard.defer_destroy(curr) };
+                        op_stats::retire();
+                        unsafe { guard.retire(curr) };
+                    } else if validate {
+                        // The validation resumes from `prev`.
+                        continue;
                     } else {
                         break 'result Err(FindError::Retry);
                     }
                 }
                 curr = next;
             }
-        };
+        }
+    }
 
+    /// Clean up like `find_harris_michael`, or like `find_harris` while the current thread sees
+    /// many failed CASes (see `harris_stats::record_traversal`).
+    #[inline]
+    fn find_adaptive<'g>(&mut self, key: &K, guard: &'g Guard) -> Result<bool, FindError> {
+        let result = if harris_stats::adaptive_harris() {
+            self.find_harris(key, guard)
+        } else {
+            self.find_harris_michael(key, guard)
+        };
+        // An ejection is not a failed CAS.
+        if let Ok(_) | Err(FindError::Retry) = result {
+            harris_stats::record_traversal(result.is_err());
+        }
         result
     }
 
@@ -248,25 +447,27 @@ where
             }
         }
 
-        let head = unsafe { &*(prev_s.shared().into_usize() as *const Atomic<Node<K, V>>) };
+        let head = unsafe { &prev_s.deref().next };
         let mut curr = head.load(Ordering::Acquire, guard);
+        let mut depth = 0;
 
         loop {
             for _ in 0..2 {
-                if curr.is_null() {
+                if self.is_end(curr) {
                     unsafe { curr_s.defend_fake(curr) };
                     return Ok(false);
                 }
 
-                curr_s.defend(curr, guard).map_err(FindError::ShieldError)?;
+                self.cache
+                    .defend(depth, curr, &mut curr_s, guard)
+                    .map_err(FindError::ShieldError)?;
+                depth += 1;
                 let curr_node = unsafe { curr.deref() };
 
                 match curr_node.key.cmp(key) {
                     Less => {
-                        curr = curr_node.next.load(Ordering::Acquire, guard);
-                        let t = prev_s;
-                        prev_s = curr_s;
-                        curr_s = t;
+                        curr = curr_node.next.load(load_ordering::NEXT, guard);
+                        mem::swap(&mut prev_s, &mut curr_s);
                         continue;
                     }
                     Equal => return Ok(curr_node.next.load(Ordering::Relaxed, guard).tag() == 0),
@@ -277,13 +478,44 @@ where
     }
 }
 
-impl<K, V> List<K, V>
+impl<K, V, M> List<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
+    #[cfg(feature = "sentinel-lists")]
+    pub fn new() -> Self {
+        let tail = Node::sentinel(Shared::null());
+        List {
+            head: Atomic::from(Node::sentinel(tail)),
+            tail: Atomic::from(tail),
+            _marker: PhantomData,
+        }
+    }
+
+    #[cfg(not(feature = "sentinel-lists"))]
     pub fn new() -> Self {
         List {
             head: Atomic::null(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Marks `link` and returns its previous value, like `fetch_or(1)`.
+    #[inline]
+    fn mark<'g>(link: &Atomic<Node<K, V>>, guard: &'g Guard) -> Shared<'g, Node<K, V>> {
+        if !M::CAS {
+            return link.fetch_or(1, Ordering::Relaxed, guard);
+        }
+        let mut next = link.load(Ordering::Relaxed, guard);
+        loop {
+            if next.tag() == 1 {
+                return next;
+            }
+            match link.cas(next, next.with_tag(1), Ordering::Relaxed, guard) {
+                Ok(_) => return next,
+                Err(e) => next = e.current,
+            }
         }
     }
 
@@ -299,12 +531,26 @@ where
         F: Fn(&mut Cursor<K, V>, &K, &'g Guard) -> Result<bool, FindError>,
     {
         // TODO: we want to use `FindError::retry()`, but it requires higher-kinded things...
+        let mut failures = 0;
         loop {
-            cursor.init_find(&self.head);
-            match find(cursor, key, unsafe { &*(guard as *mut Guard) }) {
+            cursor.init_find(self.start(), self.end());
+            let result = profiling::find(|| {
+                if restarts::exhausted(failures) {
+                    cursor.find_validated(key, unsafe { &*(guard as *mut Guard) })
+                } else {
+                    find(cursor, key, unsafe { &*(guard as *mut Guard) })
+                }
+            });
+            match result {
                 Ok(r) => return r,
-                Err(FindError::Retry) => continue,
-                Err(FindError::ShieldError(ShieldError::Ejected)) => guard.repin(),
+                Err(FindError::Retry) => {
+                    failures += 1;
+                    restarts::record();
+                }
+                Err(FindError::ShieldError(ShieldError::Ejected)) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
             }
         }
     }
@@ -329,37 +575,44 @@ where
         }
     }
 
+    /// Fails with `FindError::Retry` when the CAS fails, so that the caller counts the failure.
     fn insert_inner<'g, F>(
         &'g self,
         mut node: Shared<'g, Node<K, V>>,
         find: &F,
+        validated: bool,
         cursor: &mut Cursor<K, V>,
         guard: &'g mut Guard,
     ) -> Result<bool, FindError>
     where
         F: Fn(&mut Cursor<K, V>, &K, &'g Guard) -> Result<bool, FindError>,
     {
-        loop {
-            cursor.init_find(&self.head);
-            let found = find(cursor, unsafe { &node.deref().key }, guard)?;
-            if found {
-                unsafe {
-                    ManuallyDrop::drop(&mut node.deref_mut().value);
-                    drop(node.into_owned());
-                }
-                return Ok(false);
+        cursor.init_find(self.start(), self.end());
+        let key = unsafe { &node.deref().key };
+        let found = if validated {
+            cursor.find_validated(key, guard)?
+        } else {
+            find(cursor, key, guard)?
+        };
+        if found {
+            unsafe {
+                ManuallyDrop::drop(&mut node.deref_mut().value);
+                drop(node.into_owned());
             }
+            return Ok(false);
+        }
 
-            unsafe { node.deref() }
-                .next
-                .store(cursor.curr.shared(), Ordering::Relaxed);
-            if unsafe { cursor.prev.deref() }
-                .next
-                .compare_and_set(cursor.curr.shared(), node, Ordering::Release, guard)
-                .is_ok()
-            {
-                return Ok(true);
-            }
+        unsafe { node.deref() }
+            .next
+            .store(cursor.curr.shared(), Ordering::Relaxed);
+        if unsafe { cursor.prev.deref() }
+            .next
+            .cas(cursor.curr.shared(), node, Ordering::Release, guard)
+            .is_ok()
+        {
+            Ok(true)
+        } else {
+            Err(FindError::Retry)
         }
     }
 
@@ -376,74 +629,124 @@ where
         F: Fn(&mut Cursor<K, V>, &K, &'g Guard) -> Result<bool, FindError>,
     {
         let node = Owned::new(Node {
-            key: key,
+            key,
             value: ManuallyDrop::new(value),
             next: Atomic::null(),
         })
         .into_shared(unsafe { unprotected() });
 
+        let mut failures = 0;
         loop {
-            match self.insert_inner(node, &find, cursor, unsafe { &mut *(guard as *mut Guard) }) {
+            match self.insert_inner(node, &find, restarts::exhausted(failures), cursor, unsafe {
+                &mut *(guard as *mut Guard)
+            }) {
                 Ok(r) => return r,
-                Err(FindError::Retry) => continue,
-                Err(FindError::ShieldError(ShieldError::Ejected)) => guard.repin(),
+                Err(FindError::Retry) => {
+                    failures += 1;
+                    restarts::record();
+                }
+                Err(FindError::ShieldError(ShieldError::Ejected)) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
             }
         }
     }
 
-    fn remove_inner<'g, F>(
+    /// Fails with `FindError::Retry` when another thread has marked the node first, so that the
+    /// caller counts the failure.
+    #[allow(clippy::too_many_arguments)]
+    fn remove_inner<'g, F, P>(
         &'g self,
         key: &K,
+        pred: &mut P,
         find: &F,
+        validated: bool,
         cursor: &mut Cursor<K, V>,
+        timer: &mut remove_phases::Timer,
         guard: &'g mut Guard,
     ) -> Result<Option<V>, FindError>
     where
         F: Fn(&mut Cursor<K, V>, &K, &'g Guard) -> Result<bool, FindError>,
+        P: FnMut(&V) -> bool,
     {
-        loop {
-            cursor.init_find(&self.head);
-            let found = find(cursor, key, guard)?;
-            if !found {
-                return Ok(None);
-            }
+        cursor.init_find(self.start(), self.end());
+        let found = if validated {
+            cursor.find_validated(key, guard)?
+        } else {
+            find(cursor, key, guard)?
+        };
+        if !found {
+            return Ok(None);
+        }
 
-            let curr_node = unsafe { cursor.curr.as_ref() }.unwrap();
-            let next = curr_node.next.fetch_or(1, Ordering::Relaxed, guard);
-            if next.tag() == 1 {
-                continue;
-            }
+        let curr_node = unsafe { cursor.curr.as_ref() }.unwrap();
+        // The value of a node never changes, so the node is marked only after `pred` is checked.
+        if !pred(&curr_node.value) {
+            return Ok(None);
+        }
+        timer.lap(Phase::Search);
+        let next = Self::mark(&curr_node.next, guard);
+        if next.tag() == 1 {
+            return Err(FindError::Retry);
+        }
+        timer.lap(Phase::Mark);
 
-            let value = unsafe { ptr::read(&curr_node.value) };
+        let value = unsafe { ptr::read(&curr_node.value) };
 
-            if unsafe { cursor.prev.deref() }
-                .next
-                .compare_and_set(cursor.curr.shared(), next, Ordering::Release, guard)
-                .is_ok()
-            {
-                unsafe { guard.defer_destroy(cursor.curr.shared()) };
-            }
-
-            return Ok(Some(ManuallyDrop::into_inner(value)));
+        if unsafe { cursor.prev.deref() }
+            .next
+            .cas(cursor.curr.shared(), next, Ordering::Release, guard)
+            .is_ok()
+        {
+            op_stats::retire();
+            unsafe { guard.retire(cursor.curr.shared()) };
         }
+
+        Ok(Some(ManuallyDrop::into_inner(value)))
     }
 
+    /// Removes the entry of `key` if `pred` holds for its value.
     #[inline]
-    fn remove<'g, F>(
+    fn remove<'g, F, P>(
         &'g self,
         key: &K,
+        mut pred: P,
         find: F,
         cursor: &mut Cursor<K, V>,
         guard: &'g mut Guard,
     ) -> Option<V>
     where
         F: Fn(&mut Cursor<K, V>, &K, &'g Guard) -> Result<bool, FindError>,
+        P: FnMut(&V) -> bool,
     {
+        let mut failures = 0;
+        let mut timer = remove_phases::Timer::start();
         loop {
-            match self.remove_inner(key, &find, cursor, unsafe { &mut *(guard as *mut Guard) }) {
-                Ok(r) => return r,
-                Err(FindError::Retry) => continue,
-                Err(FindError::ShieldError(ShieldError::Ejected)) => guard.repin(),
+            match self.remove_inner(
+                key,
+                &mut pred,
+                &find,
+                restarts::exhausted(failures),
+                cursor,
+                &mut timer,
+                unsafe { &mut *(guard as *mut Guard) },
+            ) {
+                Ok(r) => {
+                    match r {
+                        Some(_) => timer.finish(Phase::Unlink, true),
+                        None => timer.finish(Phase::Search, false),
+                    }
+                    return r;
+                }
+                Err(FindError::Retry) => {
+                    failures += 1;
+                    restarts::record();
+                }
+                Err(FindError::ShieldError(ShieldError::Ejected)) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
             }
         }
     }
@@ -473,7 +776,20 @@ where
         cursor: &mut Cursor<K, V>,
         guard: &'g mut Guard,
     ) -> Option<V> {
-        self.remove(key, Cursor::find_harris, cursor, guard)
+        self.remove(key, |_| true, Cursor::find_harris, cursor, guard)
+    }
+
+    pub fn harris_remove_if<P>(
+        &self,
+        key: &K,
+        pred: P,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris, cursor, guard)
     }
 
     pub fn harris_michael_get<'g>(
@@ -501,7 +817,61 @@ where
         cursor: &mut Cursor<K, V>,
         guard: &mut Guard,
     ) -> Option<V> {
-        self.remove(key, Cursor::find_harris_michael, cursor, guard)
+        self.remove(key, |_| true, Cursor::find_harris_michael, cursor, guard)
+    }
+
+    pub fn harris_michael_remove_if<P>(
+        &self,
+        key: &K,
+        pred: P,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris_michael, cursor, guard)
+    }
+
+    pub fn adaptive_get<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.get(key, Cursor::find_adaptive, cursor, guard)
+    }
+
+    pub fn adaptive_insert(
+        &self,
+        key: K,
+        value: V,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> bool {
+        self.insert(key, value, Cursor::find_adaptive, cursor, guard)
+    }
+
+    pub fn adaptive_remove(
+        &self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Option<V> {
+        self.remove(key, |_| true, Cursor::find_adaptive, cursor, guard)
+    }
+
+    pub fn adaptive_remove_if<P>(
+        &self,
+        key: &K,
+        pred: P,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_adaptive, cursor, guard)
     }
 
     pub fn harris_herlihy_shavit_get<'g>(
@@ -529,22 +899,132 @@ where
         cursor: &mut Cursor<K, V>,
         guard: &mut Guard,
     ) -> Option<V> {
-        self.remove(key, Cursor::find_harris_michael, cursor, guard)
+        self.remove(key, |_| true, Cursor::find_harris_michael, cursor, guard)
+    }
+
+    pub fn harris_herlihy_shavit_remove_if<P>(
+        &self,
+        key: &K,
+        pred: P,
+        cursor: &mut Cursor<K, V>,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        self.remove(key, pred, Cursor::find_harris_michael, cursor, guard)
+    }
+
+    /// Finds the first unmarked node with a key greater than `key` into `cursor.curr`, passing
+    /// over the marked nodes without unlinking them.
+    fn next_inner(
+        &self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        let mut curr = self.first().load(Ordering::Acquire, guard);
+        let end = self.end();
+        while curr != end {
+            cursor.curr.defend(curr, guard)?;
+            let curr_node = unsafe { curr.deref() };
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 && curr_node.key > *key {
+                return Ok(true);
+            }
+            curr = next.with_tag(0);
+        }
+        Ok(false)
+    }
+
+    pub fn next<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        loop {
+            match self.next_inner(key, cursor, unsafe { &*(guard as *mut Guard) }) {
+                Ok(true) => {
+                    let node = unsafe { cursor.curr.deref() };
+                    return Some((&node.key, &*node.value));
+                }
+                Ok(false) => return None,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    /// Finds the last unmarked node with a key less than `key` into `cursor.prev`, passing over
+    /// the marked nodes without unlinking them.
+    fn prev_inner(
+        &self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        let mut found = false;
+        let mut curr = self.first().load(Ordering::Acquire, guard);
+        let end = self.end();
+        while curr != end {
+            cursor.curr.defend(curr, guard)?;
+            let curr_node = unsafe { curr.deref() };
+            if curr_node.key >= *key {
+                break;
+            }
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                // Keep the candidate protected.
+                mem::swap(&mut cursor.prev, &mut cursor.curr);
+                found = true;
+            }
+            curr = next.with_tag(0);
+        }
+        Ok(found)
+    }
+
+    pub fn prev<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        loop {
+            match self.prev_inner(key, cursor, unsafe { &*(guard as *mut Guard) }) {
+                Ok(true) => {
+                    let node = unsafe { cursor.prev.deref() };
+                    return Some((&node.key, &*node.value));
+                }
+                Ok(false) => return None,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
     }
 }
 
-pub struct HList<K, V> {
-    inner: List<K, V>,
+pub struct HList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
 }
 
-impl<K, V> ConcurrentMap<K, V> for HList<K, V>
+impl<K, V, M> ConcurrentMap<K, V> for HList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
 {
     type Handle = Cursor<K, V>;
 
     fn new() -> Self {
-        HList { inner: List::new() }
+        HList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     fn handle(guard: &Guard) -> Self::Handle {
@@ -566,26 +1046,92 @@ where
     }
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.inner.harris_insert(key, value, handle, guard)
+        let inserted = self.inner.harris_insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.inner.harris_remove(key, handle, guard)
+        let removed = self.inner.harris_remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
-pub struct HMList<K, V> {
-    inner: List<K, V>,
+impl<K, V, M> ConditionalRemove<K, V> for HList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.harris_remove_if(key, pred, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
 }
 
-impl<K, V> ConcurrentMap<K, V> for HMList<K, V>
+impl<K, V, M> OrderedMap<K, V> for HList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, handle, guard)
+    }
+    #[inline]
+    fn prev<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, handle, guard)
+    }
+}
+
+pub struct HMList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for HMList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
 {
     type Handle = Cursor<K, V>;
 
     fn new() -> Self {
-        HMList { inner: List::new() }
+        HMList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     fn handle(guard: &Guard) -> Self::Handle {
@@ -607,26 +1153,94 @@ where
     }
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.inner.harris_michael_insert(key, value, handle, guard)
+        let inserted = self.inner.harris_michael_insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.inner.harris_michael_remove(key, handle, guard)
+        let removed = self.inner.harris_michael_remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
-pub struct HHSList<K, V> {
-    inner: List<K, V>,
+impl<K, V, M> ConditionalRemove<K, V> for HMList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self
+            .inner
+            .harris_michael_remove_if(key, pred, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
 }
 
-impl<K, V> ConcurrentMap<K, V> for HHSList<K, V>
+impl<K, V, M> OrderedMap<K, V> for HMList<K, V, M>
 where
     K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, handle, guard)
+    }
+    #[inline]
+    fn prev<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, handle, guard)
+    }
+}
+
+pub struct HHSList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
 {
     type Handle = Cursor<K, V>;
 
     fn new() -> Self {
-        HHSList { inner: List::new() }
+        HHSList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
     }
 
     fn handle(guard: &Guard) -> Self::Handle {
@@ -648,19 +1262,200 @@ where
     }
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.inner
-            .harris_herlihy_shavit_insert(key, value, handle, guard)
+        let inserted = self
+            .inner
+            .harris_herlihy_shavit_insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.inner.harris_herlihy_shavit_remove(key, handle, guard)
+        let removed = self.inner.harris_herlihy_shavit_remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, M> ConditionalRemove<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self
+            .inner
+            .harris_herlihy_shavit_remove_if(key, pred, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
     }
 }
 
+impl<K, V, M> OrderedMap<K, V> for HHSList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, handle, guard)
+    }
+    #[inline]
+    fn prev<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, handle, guard)
+    }
+}
+
+pub struct AdaptiveList<K, V, M = FetchOr> {
+    inner: List<K, V, M>,
+    len: StripedCounter,
+}
+
+impl<K, V, M> ConcurrentMap<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    type Handle = Cursor<K, V>;
+
+    fn new() -> Self {
+        AdaptiveList {
+            inner: List::new(),
+            len: StripedCounter::new(),
+        }
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        Cursor::new(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.inner.adaptive_get(key, handle, guard)
+    }
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.inner.adaptive_insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        let removed = self.inner.adaptive_remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, M> ConditionalRemove<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.inner.adaptive_remove_if(key, pred, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+impl<K, V, M> OrderedMap<K, V> for AdaptiveList<K, V, M>
+where
+    K: Ord,
+    M: MarkOp,
+{
+    #[inline]
+    fn next<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.next(key, handle, guard)
+    }
+    #[inline]
+    fn prev<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.inner.prev(key, handle, guard)
+    }
+}
+
+register_maps! {
+    pebr;
+    HList => HList, Decreasing, with scan cas_mark compare_and_delete;
+    HMList => HMList, Decreasing, with scan cas_mark compare_and_delete;
+    HHSList => HHSList, Decreasing, with scan cas_mark compare_and_delete;
+    AdaptiveList => AdaptiveList, Decreasing, with scan cas_mark compare_and_delete;
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{HHSList, HList, HMList};
-    use crate::pebr::concurrent_map;
+    use super::{AdaptiveList, HHSList, HList, HMList};
+    use crate::pebr::{concurrent_map, conditional_remove, ordered_map};
+    use crate::restarts;
+    use crate::tagged::CasMark;
 
     #[test]
     fn smoke_h_list() {
@@ -676,4 +1471,87 @@ mod tests {
     fn smoke_hhs_list() {
         concurrent_map::tests::smoke::<HHSList<i32, String>>();
     }
+
+    #[test]
+    fn smoke_adaptive_list() {
+        concurrent_map::tests::smoke::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_h_list() {
+        concurrent_map::tests::shadow::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_hm_list() {
+        concurrent_map::tests::shadow::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_hhs_list() {
+        concurrent_map::tests::shadow::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn shadow_adaptive_list() {
+        concurrent_map::tests::shadow::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_h_list() {
+        conditional_remove::tests::remove_if::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_hm_list() {
+        conditional_remove::tests::remove_if::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_hhs_list() {
+        conditional_remove::tests::remove_if::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_adaptive_list() {
+        conditional_remove::tests::remove_if::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_h_list_cas_mark() {
+        concurrent_map::tests::smoke::<HList<i32, String, CasMark>>();
+    }
+
+    #[test]
+    fn smoke_hm_list_cas_mark() {
+        concurrent_map::tests::smoke::<HMList<i32, String, CasMark>>();
+    }
+
+    #[test]
+    fn ordered_h_list() {
+        ordered_map::tests::ordered::<HList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_hm_list() {
+        ordered_map::tests::ordered::<HMList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_hhs_list() {
+        ordered_map::tests::ordered::<HHSList<i32, String>>();
+    }
+
+    #[test]
+    fn ordered_adaptive_list() {
+        ordered_map::tests::ordered::<AdaptiveList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_h_list_bounded_restarts() {
+        // Other tests may run under the bound too, which only makes them take the validated
+        // traversal after their first failure.
+        restarts::set_bound(1);
+        concurrent_map::tests::smoke::<HList<i32, String>>();
+    }
 }
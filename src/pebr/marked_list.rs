@@ -0,0 +1,419 @@
+//! Harris-Michael lists whose links are marked with tag bits or with version counters (see
+//! `crate::tagged`). Apart from the links they are the same, so that the difference in throughput
+//! is the cost of the marking.
+
+use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crate::tagged::{Link, Snapshot, TagLink, VersionLink};
+use crossbeam_pebr::{unprotected, Guard, Owned, Shared, Shield, ShieldError};
+
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+struct Node<K, V, L> {
+    next: L,
+    key: K,
+    value: V,
+}
+
+fn shared<'g, K, V, L>(ptr: usize) -> Shared<'g, Node<K, V, L>> {
+    Shared::from(ptr as *const Node<K, V, L>)
+}
+
+pub struct Cursor<K, V, L> {
+    prev: Shield<Node<K, V, L>>,
+    curr: Shield<Node<K, V, L>>,
+    /// The link to `curr`, which is the head of the list or in the node protected by `prev`.
+    prev_link: *const L,
+    prev_snap: Snapshot,
+}
+
+impl<K, V, L> Cursor<K, V, L>
+where
+    L: Link,
+{
+    fn new(guard: &Guard) -> Self {
+        Self {
+            prev: Shield::null(guard),
+            curr: Shield::null(guard),
+            prev_link: ptr::null(),
+            prev_snap: L::new(0).load(),
+        }
+    }
+
+    fn release(&mut self) {
+        self.prev.release();
+        self.curr.release();
+    }
+
+    fn prev_link(&self) -> &L {
+        unsafe { &*self.prev_link }
+    }
+}
+
+pub struct MarkedList<K, V, L: Link> {
+    head: L,
+    len: StripedCounter,
+    _marker: PhantomData<Box<Node<K, V, L>>>,
+}
+
+pub type TagList<K, V> = MarkedList<K, V, TagLink>;
+pub type VersionList<K, V> = MarkedList<K, V, VersionLink>;
+
+impl<K, V, L> Default for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, L> Drop for MarkedList<K, V, L>
+where
+    L: Link,
+{
+    fn drop(&mut self) {
+        let mut ptr = self.head.load().ptr;
+        while ptr != 0 {
+            let node = unsafe { shared::<K, V, L>(ptr).into_owned() };
+            ptr = node.next.load().ptr;
+        }
+    }
+}
+
+impl<K, V, L> MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    pub fn new() -> Self {
+        L::audit::<Node<K, V, L>>();
+        Self {
+            head: L::new(0),
+            len: StripedCounter::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns whether `key` is found. Afterwards, `cursor.prev_link` is the link to the first node
+    /// whose key is not less than `key`, protected by `cursor.curr`. Unlinks the removed nodes on
+    /// the way one by one.
+    fn find(
+        &self,
+        key: &K,
+        cursor: &mut Cursor<K, V, L>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        'retry: loop {
+            cursor.prev_link = &self.head;
+            cursor.prev_snap = self.head.load();
+            loop {
+                if cursor.prev_snap.ptr == 0 {
+                    return Ok(false);
+                }
+                cursor.curr.defend(shared(cursor.prev_snap.ptr), guard)?;
+                let curr = unsafe { cursor.curr.deref() };
+                let next_snap = curr.next.load();
+                if next_snap.marked {
+                    match cursor
+                        .prev_link()
+                        .cas(cursor.prev_snap, next_snap.ptr, false)
+                    {
+                        Ok(snap) => unsafe {
+                            op_stats::retire();
+                            guard.retire(cursor.curr.shared());
+                            cursor.prev_snap = snap;
+                        },
+                        Err(_) => {
+                            restarts::record();
+                            continue 'retry;
+                        }
+                    }
+                    continue;
+                }
+                match curr.key.cmp(key) {
+                    Less => {
+                        mem::swap(&mut cursor.prev, &mut cursor.curr);
+                        cursor.prev_link = &unsafe { cursor.prev.deref() }.next;
+                        cursor.prev_snap = next_snap;
+                    }
+                    Equal => return Ok(true),
+                    Greater => return Ok(false),
+                }
+            }
+        }
+    }
+
+    pub fn get_inner<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V, L>,
+        guard: &'g Guard,
+    ) -> Result<Option<&'g V>, ShieldError> {
+        if self.find(key, cursor, guard)? {
+            Ok(Some(&unsafe { cursor.curr.deref() }.value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V, L>,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        loop {
+            match self.get_inner(
+                key,
+                unsafe { &mut *(cursor as *mut Cursor<K, V, L>) },
+                unsafe { &mut *(guard as *mut Guard) },
+            ) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    fn insert_inner(
+        &self,
+        mut new: Shared<'_, Node<K, V, L>>,
+        cursor: &mut Cursor<K, V, L>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        loop {
+            if self.find(&unsafe { new.deref() }.key, cursor, guard)? {
+                return Ok(false);
+            }
+            unsafe { new.deref_mut() }.next.set(cursor.prev_snap.ptr);
+            if cursor
+                .prev_link()
+                .cas(cursor.prev_snap, new.as_raw() as usize, false)
+                .is_ok()
+            {
+                return Ok(true);
+            }
+            restarts::record();
+        }
+    }
+
+    pub fn insert(
+        &self,
+        key: K,
+        value: V,
+        cursor: &mut Cursor<K, V, L>,
+        guard: &mut Guard,
+    ) -> bool {
+        let new = Owned::new(Node {
+            next: L::new(0),
+            key,
+            value,
+        })
+        .into_shared(unsafe { unprotected() });
+        loop {
+            match self.insert_inner(new, cursor, guard) {
+                Ok(true) => return true,
+                Ok(false) => {
+                    drop(unsafe { new.into_owned() });
+                    return false;
+                }
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    fn remove_inner<P>(
+        &self,
+        key: &K,
+        pred: &mut P,
+        cursor: &mut Cursor<K, V, L>,
+        guard: &Guard,
+    ) -> Result<Option<V>, ShieldError>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        loop {
+            if !self.find(key, cursor, guard)? {
+                return Ok(None);
+            }
+            let curr = unsafe { cursor.curr.deref() };
+            if !pred(&curr.value) {
+                return Ok(None);
+            }
+            let next_snap = curr.next.load();
+            if next_snap.marked || curr.next.cas(next_snap, next_snap.ptr, true).is_err() {
+                restarts::record();
+                continue;
+            }
+
+            let value = curr.value.clone();
+            if cursor
+                .prev_link()
+                .cas(cursor.prev_snap, next_snap.ptr, false)
+                .is_ok()
+            {
+                unsafe {
+                    op_stats::retire();
+                    guard.retire(cursor.curr.shared());
+                }
+            } else {
+                // Let `find` unlink it. The node is already removed, so an ejection is ignored and
+                // leaves the unlinking to the other threads.
+                let _ = self.find(key, cursor, guard);
+            }
+            return Ok(Some(value));
+        }
+    }
+
+    pub fn remove(&self, key: &K, cursor: &mut Cursor<K, V, L>, guard: &mut Guard) -> Option<V> {
+        self.remove_if(key, |_| true, cursor, guard)
+    }
+
+    /// Removes the entry of `key` if `pred` holds for its value, which never changes.
+    pub fn remove_if<P>(
+        &self,
+        key: &K,
+        mut pred: P,
+        cursor: &mut Cursor<K, V, L>,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        loop {
+            match self.remove_inner(key, &mut pred, cursor, guard) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+}
+
+impl<K, V, L> ConcurrentMap<K, V> for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    type Handle = Cursor<K, V, L>;
+
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        Cursor::new(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &'g K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.get(key, handle, guard)
+    }
+
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        let removed = self.remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<K, V, L> ConditionalRemove<K, V> for MarkedList<K, V, L>
+where
+    K: Ord,
+    V: Clone,
+    L: Link,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.remove_if(key, pred, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+register_maps! {
+    pebr;
+    TagList => TagList, Decreasing, with compare_and_delete;
+    VersionList => VersionList, Decreasing, with compare_and_delete;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TagList, VersionList};
+    use crate::pebr::{concurrent_map, conditional_remove};
+    use crate::tagged;
+
+    #[test]
+    fn smoke_tag_list() {
+        concurrent_map::tests::smoke::<TagList<i32, String>>();
+    }
+
+    #[test]
+    fn remove_if_tag_list() {
+        conditional_remove::tests::remove_if::<TagList<i32, String>>();
+    }
+
+    #[test]
+    fn smoke_version_list() {
+        if !tagged::dwcas_available() {
+            return;
+        }
+        concurrent_map::tests::smoke::<VersionList<i32, String>>();
+    }
+}
@@ -0,0 +1,393 @@
+//! Harris, Fraser and Pratt's multi-word compare-and-swap (MCAS), built from their restricted
+//! double-compare single-swap (RDCSS).
+//!
+//! See Timothy L. Harris, Keir Fraser and Ian A. Pratt, A Practical Multi-Word Compare-and-Swap
+//! Operation, DISC 2002, and the EBR version of this module for the algorithm.
+//!
+//! A descriptor is retired only after it is removed from all of the words, so a descriptor read
+//! from a word is protected by shielding it. The objects containing the words of an MCAS may have
+//! been retired before the helper pinned, however. The owner of an MCAS keeps them shielded until
+//! the MCAS is removed from all of the words, so a helper shields them and then validates that the
+//! MCAS is still in the word where it was found. Hence `Mcas::add` takes the object containing the
+//! word, and the owner must keep it shielded while executing the MCAS.
+
+use super::shield_pool::ShieldPool;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crossbeam_pebr::{unprotected, Guard, Owned, Shared, ShieldError};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const RDCSS: usize = 1;
+const MCAS: usize = 2;
+const TAGS: usize = 3;
+
+const UNDECIDED: usize = 0;
+const SUCCEEDED: usize = 1;
+const FAILED: usize = 2;
+
+/// A word that MCAS operates on. The low 2 bits of its values must be zero.
+#[derive(Debug)]
+pub struct Word {
+    inner: AtomicUsize,
+}
+
+struct Entry {
+    /// The object containing `word`.
+    base: usize,
+    word: *const Word,
+    old: usize,
+    new: usize,
+}
+
+struct McasDesc {
+    status: AtomicUsize,
+    /// Sorted by the addresses of the words.
+    entries: Vec<Entry>,
+}
+
+impl McasDesc {
+    /// The value of `word`, where the descriptor is installed.
+    fn value(&self, word: &Word) -> usize {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.word == word as *const _)
+            .unwrap();
+        if self.status.load(Ordering::Acquire) == SUCCEEDED {
+            entry.new
+        } else {
+            entry.old
+        }
+    }
+}
+
+/// Installs the tagged MCAS descriptor `mcas` in `word` if the word has `old` and the MCAS is
+/// undecided.
+struct RdcssDesc {
+    mcas: usize,
+    word: *const Word,
+    old: usize,
+}
+
+/// The shields of a thread for the descriptors and the objects it reads or helps.
+pub struct Helper {
+    pool: ShieldPool<u8>,
+}
+
+impl Default for Helper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Helper {
+    pub fn new() -> Self {
+        Self {
+            pool: ShieldPool::new(),
+        }
+    }
+}
+
+fn erase(ptr: usize) -> Shared<'static, u8> {
+    Shared::from((ptr & !TAGS) as *const u8)
+}
+
+impl Word {
+    pub fn new(value: usize) -> Self {
+        debug_assert_eq!(value & TAGS, 0);
+        Self {
+            inner: AtomicUsize::new(value),
+        }
+    }
+
+    /// Reads the value. If an MCAS is in progress on the word, the value is the new one if the MCAS
+    /// has succeeded and the old one otherwise.
+    pub fn read(&self, helper: &mut Helper, guard: &Guard) -> Result<usize, ShieldError> {
+        let value = self.inner.load(Ordering::Acquire);
+        if value & TAGS == 0 {
+            return Ok(value);
+        }
+        let _shield = helper.pool.defend(erase(value), guard)?;
+        Ok(match value & TAGS {
+            // An RDCSS is installed only while its MCAS is undecided.
+            RDCSS => unsafe { &*((value & !TAGS) as *const RdcssDesc) }.old,
+            _ => unsafe { &*((value & !TAGS) as *const McasDesc) }.value(self),
+        })
+    }
+
+    /// The value when no operation can be in progress, e.g. when dropping the structure.
+    pub fn value(&mut self) -> usize {
+        let value = *self.inner.get_mut();
+        debug_assert_eq!(value & TAGS, 0);
+        value
+    }
+}
+
+/// A multi-word CAS under construction.
+pub struct Mcas<'w> {
+    entries: Vec<Entry>,
+    _marker: PhantomData<&'w Word>,
+}
+
+impl<'w> Default for Mcas<'w> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'w> Mcas<'w> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds the CAS of `word`, a field of `base`, from `old` to `new`. The words of an MCAS must be
+    /// distinct.
+    pub fn add<T>(&mut self, base: &'w T, word: &'w Word, old: usize, new: usize) {
+        debug_assert_eq!((old | new) & TAGS, 0);
+        self.entries.push(Entry {
+            base: base as *const T as usize,
+            word,
+            old,
+            new,
+        });
+    }
+
+    /// Atomically replaces the old values of the words with the new ones if all of the words have
+    /// the old values. Returns whether it succeeded.
+    ///
+    /// The bases must be shielded by the caller. If the thread is ejected while helping other
+    /// MCASes, it repins and resumes.
+    pub fn execute(mut self, helper: &mut Helper, guard: &mut Guard) -> bool {
+        self.entries.sort_by_key(|e| e.word as usize);
+        let desc = Owned::new(McasDesc {
+            status: AtomicUsize::new(UNDECIDED),
+            entries: self.entries,
+        })
+        .into_shared(unsafe { unprotected() });
+        let succeeded = loop {
+            match unsafe { help(desc.as_raw() as usize | MCAS, helper, guard) } {
+                Ok(succeeded) => break succeeded,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        };
+        unsafe { guard.retire(desc) };
+        succeeded
+    }
+}
+
+/// Runs the tagged MCAS descriptor `desc` to completion. Returns whether it succeeded.
+///
+/// The descriptor and the bases of its entries must be protected.
+unsafe fn help(desc: usize, helper: &mut Helper, guard: &Guard) -> Result<bool, ShieldError> {
+    let mcas = &*((desc & !TAGS) as *const McasDesc);
+    if mcas.status.load(Ordering::Acquire) == UNDECIDED {
+        let mut status = SUCCEEDED;
+        'entries: for entry in &mcas.entries {
+            loop {
+                let current = rdcss(desc, entry, helper, guard)?;
+                if current & TAGS == MCAS && current != desc {
+                    help_other(&*entry.word, current, helper, guard)?;
+                    continue;
+                }
+                if current != desc && current != entry.old {
+                    status = FAILED;
+                    break 'entries;
+                }
+                break;
+            }
+        }
+        let _ =
+            mcas.status
+                .compare_exchange(UNDECIDED, status, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    let succeeded = mcas.status.load(Ordering::Acquire) == SUCCEEDED;
+    for entry in &mcas.entries {
+        let value = if succeeded { entry.new } else { entry.old };
+        release(&*entry.word, desc, value, helper, guard)?;
+    }
+    Ok(succeeded)
+}
+
+/// Helps the tagged MCAS descriptor `desc` read from `word`, which must be protected.
+unsafe fn help_other(
+    word: &Word,
+    desc: usize,
+    helper: &mut Helper,
+    guard: &Guard,
+) -> Result<(), ShieldError> {
+    let _desc = helper.pool.defend(erase(desc), guard)?;
+    let mcas = &*((desc & !TAGS) as *const McasDesc);
+    let mut bases = Vec::with_capacity(mcas.entries.len());
+    for entry in &mcas.entries {
+        bases.push(helper.pool.defend(erase(entry.base), guard)?);
+    }
+    // Otherwise, the MCAS is done and the bases may have been reclaimed before they were shielded.
+    if word.inner.load(Ordering::Acquire) == desc {
+        help(desc, helper, guard)?;
+    }
+    Ok(())
+}
+
+/// Installs `desc` in the word of `entry` with RDCSS. Returns the old value if it did, and the
+/// value of the word otherwise, after completing the RDCSSes in the way.
+unsafe fn rdcss(
+    desc: usize,
+    entry: &Entry,
+    helper: &mut Helper,
+    guard: &Guard,
+) -> Result<usize, ShieldError> {
+    let word = &(*entry.word).inner;
+    let mut rdcss_desc = None;
+    loop {
+        let current = word.load(Ordering::Acquire);
+        if current & TAGS == RDCSS {
+            complete_other(&*entry.word, current, helper, guard)?;
+            continue;
+        }
+        if current != entry.old {
+            return Ok(current);
+        }
+
+        let shared = rdcss_desc
+            .take()
+            .unwrap_or_else(|| {
+                Owned::new(RdcssDesc {
+                    mcas: desc,
+                    word: entry.word,
+                    old: entry.old,
+                })
+            })
+            .into_shared(guard);
+        let tagged = shared.as_raw() as usize | RDCSS;
+        match word.compare_exchange(entry.old, tagged, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {
+                complete(tagged);
+                guard.retire(shared);
+                return Ok(entry.old);
+            }
+            Err(_) => rdcss_desc = Some(shared.into_owned()),
+        }
+    }
+}
+
+/// Replaces the tagged RDCSS descriptor `desc` with its MCAS descriptor if the MCAS is undecided,
+/// and with the old value otherwise. The MCAS descriptor must be protected.
+unsafe fn complete(desc: usize) {
+    let rdcss = &*((desc & !TAGS) as *const RdcssDesc);
+    let status = &(*((rdcss.mcas & !TAGS) as *const McasDesc)).status;
+    let value = if status.load(Ordering::Acquire) == UNDECIDED {
+        rdcss.mcas
+    } else {
+        rdcss.old
+    };
+    let _ = (*rdcss.word)
+        .inner
+        .compare_exchange(desc, value, Ordering::AcqRel, Ordering::Relaxed);
+}
+
+/// Completes the tagged RDCSS descriptor `desc` read from `word`, which must be protected.
+unsafe fn complete_other(
+    word: &Word,
+    desc: usize,
+    helper: &mut Helper,
+    guard: &Guard,
+) -> Result<(), ShieldError> {
+    let _desc = helper.pool.defend(erase(desc), guard)?;
+    let rdcss = &*((desc & !TAGS) as *const RdcssDesc);
+    // The installer of the RDCSS protects the MCAS descriptor until it removes the RDCSS.
+    let _mcas = helper.pool.defend(erase(rdcss.mcas), guard)?;
+    if word.inner.load(Ordering::Acquire) == desc {
+        complete(desc);
+    }
+    Ok(())
+}
+
+/// Replaces the decided MCAS descriptor `desc` in `word` with `value`.
+///
+/// An RDCSS in the way is completed, since it may have read the status of `desc` before it was
+/// decided and would install `desc` again after `desc` is retired.
+unsafe fn release(
+    word: &Word,
+    desc: usize,
+    value: usize,
+    helper: &mut Helper,
+    guard: &Guard,
+) -> Result<(), ShieldError> {
+    loop {
+        let current = word.inner.load(Ordering::Acquire);
+        if current & TAGS == RDCSS {
+            complete_other(word, current, helper, guard)?;
+            continue;
+        }
+        if current != desc
+            || word
+                .inner
+                .compare_exchange(desc, value, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Helper, Mcas, Word};
+    use crossbeam_pebr::pin;
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+    const INCREMENTS: usize = 10_000;
+
+    #[test]
+    fn concurrent_increments() {
+        let words = &(0..4).map(|_| Word::new(0)).collect::<Vec<_>>();
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let helper = &mut Helper::new();
+                    // Each thread increments an overlapping set of 3 words atomically.
+                    let indices = [t % 4, (t + 1) % 4, (t + 2) % 4];
+                    for _ in 0..INCREMENTS {
+                        loop {
+                            let guard = &mut pin();
+                            let mut mcas = Mcas::new();
+                            for &i in &indices {
+                                let old = words[i].read(helper, guard).unwrap();
+                                mcas.add(words, &words[i], old, old + 4);
+                            }
+                            if mcas.execute(helper, guard) {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        let helper = &mut Helper::new();
+        let guard = &mut pin();
+        let total = words
+            .iter()
+            .map(|w| w.read(helper, guard).unwrap())
+            .sum::<usize>();
+        assert_eq!(total, 4 * 3 * THREADS * INCREMENTS);
+
+        // An MCAS fails if any word has a different value.
+        let old = words[0].read(helper, guard).unwrap();
+        let mut mcas = Mcas::new();
+        mcas.add(words, &words[0], old, 0);
+        mcas.add(words, &words[1], 4, 0);
+        assert!(!mcas.execute(helper, guard));
+        assert_eq!(words[0].read(helper, guard).unwrap(), old);
+    }
+}
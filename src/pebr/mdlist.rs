@@ -0,0 +1,560 @@
+//! Lock-free priority queue based on the multi-dimensional linked list (MDList) of Zhang and Dechev,
+//! "A Lock-Free Priority Queue Design Based on Multi-Dimensional Linked Lists", TPDS 2016.
+//!
+//! A key is mapped to `DIMENSION` coordinates, its base-`2^BITS` digits from the most significant
+//! one. The nodes form a tree rooted at a dummy node with the coordinates all zero, where the child
+//! of a node in dimension `d` has the same first `d` coordinates as the node and a larger `d`-th
+//! one. Visiting a node and then its children from the highest dimension enumerates the keys in the
+//! increasing order.
+//!
+//! A node has up to `DIMENSION` children, and inserting a node between a node `curr` and its parent
+//! makes the new node adopt some children of `curr`. The adoption is described by a descriptor that
+//! the other threads help to complete before touching the adopted children.
+//!
+//! `try_remove_min` logically deletes the first live node of the traversal from the root. Deleted
+//! nodes are physically removed when a new node with the same key takes their place, or when all of
+//! their children are gone: then the null children are frozen, and the node is unlinked from its
+//! parent. Unlike the paper, there is no deletion stack nor batched purge of the deleted prefix, and
+//! `try_remove_min` may miss the keys inserted concurrently before its position of the traversal.
+
+use super::concurrent_priority_queue::ConcurrentPriorityQueue;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shared, Shield, ShieldError};
+
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const DIMENSION: usize = 8;
+/// The number of bits of a coordinate.
+const BITS: usize = 4;
+/// The keys are less than this.
+pub const KEY_LIMIT: usize = (1 << (DIMENSION * BITS)) - 1;
+
+/// Tag of a child that is adopted by another node, or of a null child that will be filled by
+/// adoption or is not valid.
+const ADP: usize = 1;
+/// Tag of a null child of a deleted node that is being unlinked.
+const DEAD: usize = 2;
+
+/// The `dim`-th coordinate of `key`.
+#[inline]
+fn coord(key: usize, dim: usize) -> usize {
+    (key >> ((DIMENSION - 1 - dim) * BITS)) & ((1 << BITS) - 1)
+}
+
+struct Node<V> {
+    /// The key plus one. The root has the key zero.
+    key: usize,
+    /// `None` for the root.
+    value: ManuallyDrop<Option<V>>,
+    deleted: AtomicBool,
+    child: [Atomic<Node<V>>; DIMENSION],
+    adesc: Atomic<Desc<V>>,
+}
+
+/// Adoption descriptor: the new node adopts the children of `curr` in the dimensions `[dp, dc)`.
+struct Desc<V> {
+    curr: Atomic<Node<V>>,
+    dp: usize,
+    dc: usize,
+    /// Whether the new node replaces the deleted `curr`, instead of becoming its parent.
+    replace: bool,
+}
+
+impl<V> Node<V> {
+    fn new(key: usize, value: Option<V>) -> Self {
+        Self {
+            key,
+            value: ManuallyDrop::new(value),
+            deleted: AtomicBool::new(false),
+            child: Default::default(),
+            adesc: Atomic::null(),
+        }
+    }
+}
+
+/// The result of `locate`. `pred` is the last node whose coordinates are less than the key, and
+/// `curr` is its child in dimension `dp`, the first `dc` coordinates of which match the key.
+struct Location<'g, V> {
+    /// The parent of `pred`, unless `pred` is the root.
+    ppred: Shared<'g, Node<V>>,
+    /// The dimension of `pred` as a child of `ppred`.
+    pdp: usize,
+    pred: Shared<'g, Node<V>>,
+    dp: usize,
+    /// Tagged.
+    curr: Shared<'g, Node<V>>,
+    dc: usize,
+}
+
+pub struct MDListHandle<V> {
+    ppred: Shield<Node<V>>,
+    pred: Shield<Node<V>>,
+    curr: Shield<Node<V>>,
+    /// The new node of `insert`, protected until its adoption is completed.
+    node: Shield<Node<V>>,
+    /// The descriptor being helped and its `curr`.
+    desc: Shield<Desc<V>>,
+    desc_curr: Shield<Node<V>>,
+    /// The path of `try_remove_min`, except for the root.
+    path: Vec<Shield<Node<V>>>,
+}
+
+impl<V> MDListHandle<V> {
+    pub fn release(&mut self) {
+        self.ppred.release();
+        self.pred.release();
+        self.curr.release();
+        self.node.release();
+        self.desc.release();
+        self.desc_curr.release();
+        for shield in &mut self.path {
+            shield.release();
+        }
+    }
+}
+
+pub struct MDList<V> {
+    root: Node<V>,
+    len: StripedCounter,
+}
+
+impl<V> Default for MDList<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Drop for MDList<V> {
+    fn drop(&mut self) {
+        unsafe {
+            let guard = unprotected();
+            let mut stack = vec![Shared::from(&self.root as *const Node<V>)];
+            while let Some(node) = stack.pop() {
+                let node_ref = node.deref();
+                // The untagged children are the ones the node owns.
+                for child in &node_ref.child {
+                    let child = child.load(Ordering::Relaxed, guard);
+                    if child.tag() == 0 && !child.is_null() {
+                        stack.push(child);
+                    }
+                }
+                if node_ref.key != 0 {
+                    let mut node = node.into_owned();
+                    if !node.deleted.load(Ordering::Relaxed) {
+                        ManuallyDrop::drop(&mut node.value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<V> MDList<V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(0, None),
+            len: StripedCounter::new(),
+        }
+    }
+
+    pub fn handle(&self, guard: &Guard) -> MDListHandle<V> {
+        MDListHandle {
+            ppred: Shield::null(guard),
+            pred: Shield::null(guard),
+            curr: Shield::null(guard),
+            node: Shield::null(guard),
+            desc: Shield::null(guard),
+            desc_curr: Shield::null(guard),
+            path: Vec::new(),
+        }
+    }
+
+    /// Completes the adoption of `node`, if any.
+    fn help(
+        &self,
+        node: &Node<V>,
+        handle: &mut MDListHandle<V>,
+        guard: &Guard,
+    ) -> Result<(), ShieldError> {
+        let desc = node.adesc.load(Ordering::Acquire, guard);
+        if desc.is_null() {
+            return Ok(());
+        }
+        handle.desc.defend(desc, guard)?;
+        let desc_ref = unsafe { desc.deref() };
+        let curr = desc_ref.curr.load(Ordering::Relaxed, guard);
+        handle.desc_curr.defend(curr, guard)?;
+        let curr_ref = unsafe { curr.deref() };
+
+        for dim in desc_ref.dp..desc_ref.dc {
+            let child = curr_ref.child[dim]
+                .fetch_or(ADP, Ordering::AcqRel, guard)
+                .with_tag(0);
+            // Fails if another thread has already done it.
+            let _ = node.child[dim].compare_and_set(
+                Shared::null().with_tag(ADP),
+                child,
+                Ordering::Release,
+                guard,
+            );
+        }
+
+        if node
+            .adesc
+            .compare_and_set(desc, Shared::null(), Ordering::AcqRel, guard)
+            .is_ok()
+        {
+            unsafe {
+                if desc_ref.replace {
+                    op_stats::retire();
+                    guard.retire(curr);
+                }
+                op_stats::retire();
+                guard.retire(desc);
+            }
+        }
+        Ok(())
+    }
+
+    /// Protects `ppred`, `pred` and `curr` of the location with the shields of the same names.
+    fn locate<'g>(
+        &'g self,
+        key: usize,
+        handle: &mut MDListHandle<V>,
+        guard: &'g Guard,
+    ) -> Result<Location<'g, V>, ShieldError> {
+        // The root is never reclaimed.
+        unsafe {
+            handle
+                .curr
+                .defend_fake(Shared::from(&self.root as *const Node<V>))
+        };
+        let mut loc = Location {
+            ppred: Shared::null(),
+            pdp: 0,
+            pred: Shared::null(),
+            dp: 0,
+            curr: Shared::from(&self.root as *const Node<V>),
+            dc: 0,
+        };
+
+        while loc.dc < DIMENSION {
+            while let Some(curr_ref) = unsafe { loc.curr.with_tag(0).as_ref() } {
+                if coord(key, loc.dc) <= coord(curr_ref.key, loc.dc) {
+                    break;
+                }
+                // The children being adopted are not filled yet.
+                self.help(curr_ref, handle, guard)?;
+                mem::swap(&mut handle.ppred, &mut handle.pred);
+                mem::swap(&mut handle.pred, &mut handle.curr);
+                loc.ppred = loc.pred;
+                loc.pdp = loc.dp;
+                loc.pred = loc.curr.with_tag(0);
+                loc.dp = loc.dc;
+                loc.curr = curr_ref.child[loc.dc].load(Ordering::Acquire, guard);
+                handle.curr.defend(loc.curr.with_tag(0), guard)?;
+            }
+            match unsafe { loc.curr.with_tag(0).as_ref() } {
+                Some(curr_ref) if coord(key, loc.dc) == coord(curr_ref.key, loc.dc) => loc.dc += 1,
+                _ => break,
+            }
+        }
+        Ok(loc)
+    }
+
+    /// Returns `false` if the key is in the queue. Otherwise, links `node` and protects it with
+    /// `handle.node`.
+    fn insert_inner(
+        &self,
+        node: Shared<'_, Node<V>>,
+        handle: &mut MDListHandle<V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        let node_ref = unsafe { node.deref() };
+        let key = node_ref.key;
+
+        loop {
+            let loc = self.locate(key, handle, guard)?;
+            if loc.curr.tag() & ADP != 0 {
+                // The children of `pred` are being adopted.
+                continue;
+            }
+
+            // The new node becomes the child of `pred` in dimension `dp`. Unless it replaces
+            // `curr`, `curr` becomes its child in dimension `dc`.
+            let (pred, dp, curr, dc, replace) = if loc.dc == DIMENSION {
+                let curr_ref = unsafe { loc.curr.with_tag(0).deref() };
+                if !curr_ref.deleted.load(Ordering::Acquire) {
+                    return Ok(false);
+                }
+                (loc.pred, loc.dp, loc.curr, DIMENSION, true)
+            } else if loc.curr.tag() == DEAD {
+                // `pred` is being unlinked, and its children in the dimensions from `dp` are gone.
+                // Take its place adopting the others.
+                (loc.ppred, loc.pdp, loc.pred, loc.dp, true)
+            } else {
+                (loc.pred, loc.dp, loc.curr, loc.dc, false)
+            };
+
+            if let Some(curr_ref) = unsafe { curr.as_ref() } {
+                if dp != dc {
+                    self.help(curr_ref, handle, guard)?;
+                }
+            }
+
+            for dim in 0..DIMENSION {
+                let child = if dim < dc {
+                    Shared::null().with_tag(ADP)
+                } else if dim == dc && !replace {
+                    curr
+                } else {
+                    Shared::null()
+                };
+                node_ref.child[dim].store(child, Ordering::Relaxed);
+            }
+            let desc = if dp < dc {
+                Owned::new(Desc {
+                    curr: Atomic::from(curr),
+                    dp,
+                    dc,
+                    replace,
+                })
+                .into_shared(guard)
+            } else {
+                Shared::null()
+            };
+            node_ref.adesc.store(desc, Ordering::Relaxed);
+
+            if let Err(e) = handle.node.defend(node, guard) {
+                if !desc.is_null() {
+                    drop(unsafe { desc.into_owned() });
+                }
+                return Err(e);
+            }
+            let pred_ref = unsafe { pred.deref() };
+            if pred_ref.child[dp]
+                .compare_and_set(curr, node, Ordering::AcqRel, guard)
+                .is_ok()
+            {
+                if desc.is_null() && replace {
+                    op_stats::retire();
+                    unsafe { guard.retire(curr) };
+                }
+                return Ok(true);
+            }
+            if !desc.is_null() {
+                drop(unsafe { desc.into_owned() });
+            }
+        }
+    }
+
+    pub fn insert(
+        &self,
+        key: usize,
+        value: V,
+        handle: &mut MDListHandle<V>,
+        guard: &mut Guard,
+    ) -> bool {
+        assert!(key < KEY_LIMIT);
+        let node =
+            Owned::new(Node::new(key + 1, Some(value))).into_shared(unsafe { unprotected() });
+
+        let inserted = loop {
+            match self.insert_inner(node, handle, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => break r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        };
+        if !inserted {
+            unsafe {
+                let mut node = node.into_owned();
+                ManuallyDrop::drop(&mut node.value);
+            }
+            return false;
+        }
+
+        // Complete the adoption by the new node, which is still protected by `handle.node`.
+        loop {
+            match self.help(unsafe { node.deref() }, handle, unsafe {
+                &*(guard as *mut Guard)
+            }) {
+                Ok(()) => return true,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    /// Unlinks `node`, the child of `parent` in dimension `dim`, if it is deleted and has no
+    /// children.
+    fn try_unlink(
+        &self,
+        parent: &Node<V>,
+        node: Shared<'_, Node<V>>,
+        dim: usize,
+        handle: &mut MDListHandle<V>,
+        guard: &Guard,
+    ) -> Result<(), ShieldError> {
+        let node_ref = unsafe { node.deref() };
+        if !node_ref.deleted.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        self.help(node_ref, handle, guard)?;
+
+        // Freeze the null children from the highest dimension, so that a new node can take the
+        // place of `node` if `node` still has children in the lower dimensions.
+        for d in (dim..DIMENSION).rev() {
+            if let Err(e) = node_ref.child[d].compare_and_set(
+                Shared::null(),
+                Shared::null().with_tag(DEAD),
+                Ordering::AcqRel,
+                guard,
+            ) {
+                if e.current != Shared::null().with_tag(DEAD) {
+                    return Ok(());
+                }
+            }
+        }
+
+        if parent.child[dim]
+            .compare_and_set(node, Shared::null(), Ordering::AcqRel, guard)
+            .is_ok()
+        {
+            op_stats::retire();
+            unsafe { guard.retire(node) };
+        }
+        Ok(())
+    }
+
+    /// Protects the nodes of the path with `handle.path`.
+    fn try_remove_min_inner(
+        &self,
+        handle: &mut MDListHandle<V>,
+        guard: &Guard,
+    ) -> Result<Option<(usize, V)>, ShieldError> {
+        // The path of the traversal. Each entry is a node, its dimension as a child, and the
+        // dimension of the next child to visit.
+        let mut stack = vec![(Shared::from(&self.root as *const Node<V>), 0, DIMENSION)];
+
+        while let Some(&mut (node, dim, ref mut next)) = stack.last_mut() {
+            let node_ref = unsafe { node.deref() };
+            if *next == dim {
+                stack.pop();
+                if let Some(&(parent, _, _)) = stack.last() {
+                    self.try_unlink(unsafe { parent.deref() }, node, dim, handle, guard)?;
+                }
+                continue;
+            }
+            if *next == DIMENSION {
+                self.help(node_ref, handle, guard)?;
+            }
+            *next -= 1;
+            let child_dim = *next;
+
+            let child = node_ref.child[child_dim]
+                .load(Ordering::Acquire, guard)
+                .with_tag(0);
+            if child.is_null() {
+                continue;
+            }
+            let depth = stack.len();
+            if handle.path.len() < depth {
+                handle.path.push(Shield::null(guard));
+            }
+            handle.path[depth - 1].defend(child, guard)?;
+            let child_ref = unsafe { child.deref() };
+            if !child_ref.deleted.load(Ordering::Acquire)
+                && child_ref
+                    .deleted
+                    .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+            {
+                let value = unsafe { ManuallyDrop::into_inner(ptr::read(&child_ref.value)) };
+                return Ok(Some((child_ref.key - 1, value.unwrap())));
+            }
+            stack.push((child, child_dim, DIMENSION));
+        }
+        Ok(None)
+    }
+
+    pub fn try_remove_min(
+        &self,
+        handle: &mut MDListHandle<V>,
+        guard: &mut Guard,
+    ) -> Option<(usize, V)> {
+        loop {
+            match self.try_remove_min_inner(handle, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+}
+
+impl<V> ConcurrentPriorityQueue<V> for MDList<V> {
+    type Handle = MDListHandle<V>;
+
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn handle(&self, guard: &Guard) -> Self::Handle {
+        self.handle(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: usize, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn try_remove_min(&self, handle: &mut Self::Handle, guard: &mut Guard) -> Option<(usize, V)> {
+        let removed = self.try_remove_min(handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+/// Registers the priority queue.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("MDList", Scheme::PEBR, |d: &D, _| {
+        d.pebr_priority_queue::<MDList<String>>()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MDList;
+    use crate::pebr::concurrent_priority_queue;
+
+    #[test]
+    fn smoke_mdlist() {
+        concurrent_priority_queue::tests::smoke::<MDList<String>>();
+    }
+}
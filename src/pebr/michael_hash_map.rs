@@ -1,13 +1,28 @@
+// Clippy takes the `HashMap` of this module for the one of std, which has a hasher parameter.
+#![allow(clippy::implicit_hasher)]
+
 use super::concurrent_map::ConcurrentMap;
+use super::conditional_remove::ConditionalRemove;
+use crate::hashing::{self, Hashing};
+use crate::striped_counter::StripedCounter;
 use crossbeam_pebr::Guard;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
 
 pub use super::list::Cursor;
-use super::list::HMList;
+use super::list::List;
 
 pub struct HashMap<K, V> {
-    buckets: Vec<HMList<K, V>>,
+    buckets: Vec<List<K, V>>,
+    len: StripedCounter,
+    hashing: Hashing,
+}
+
+impl<K, V> Drop for HashMap<K, V> {
+    fn drop(&mut self) {
+        if hashing::enabled() {
+            hashing::record_chains(self.buckets.iter_mut().map(List::len_exclusive));
+        }
+    }
 }
 
 impl<K, V> HashMap<K, V>
@@ -17,23 +32,25 @@ where
     pub fn with_capacity(n: usize) -> Self {
         let mut buckets = Vec::with_capacity(n);
         for _ in 0..n {
-            buckets.push(HMList::new());
+            buckets.push(List::new());
         }
 
-        HashMap { buckets }
+        HashMap {
+            buckets,
+            len: StripedCounter::new(),
+            hashing: Hashing::current(),
+        }
     }
 
     #[inline]
-    pub fn get_bucket(&self, index: usize) -> &HMList<K, V> {
+    pub fn get_bucket(&self, index: usize) -> &List<K, V> {
         unsafe { self.buckets.get_unchecked(index % self.buckets.len()) }
     }
 
     // TODO(@jeehoonkang): we're converting u64 to usize, which may lose information.
     #[inline]
-    fn hash(k: &K) -> usize {
-        let mut s = DefaultHasher::new();
-        k.hash(&mut s);
-        s.finish() as usize
+    fn hash(&self, k: &K) -> usize {
+        self.hashing.hash(k) as usize
     }
 
     pub fn get<'g>(
@@ -42,18 +59,34 @@ where
         k: &'g K,
         guard: &'g mut Guard,
     ) -> Option<&'g V> {
-        let i = Self::hash(k);
-        self.get_bucket(i).get(cursor, k, guard)
+        let i = self.hash(k);
+        self.get_bucket(i).harris_michael_get(k, cursor, guard)
     }
 
     pub fn insert(&self, cursor: &mut Cursor<K, V>, k: K, v: V, guard: &mut Guard) -> bool {
-        let i = Self::hash(&k);
-        self.get_bucket(i).insert(cursor, k, v, guard)
+        let i = self.hash(&k);
+        self.get_bucket(i)
+            .harris_michael_insert(k, v, cursor, guard)
     }
 
     pub fn remove(&self, cursor: &mut Cursor<K, V>, k: &K, guard: &mut Guard) -> Option<V> {
-        let i = Self::hash(&k);
-        self.get_bucket(i).remove(cursor, k, guard)
+        let i = self.hash(&k);
+        self.get_bucket(i).harris_michael_remove(k, cursor, guard)
+    }
+
+    pub fn remove_if<P>(
+        &self,
+        cursor: &mut Cursor<K, V>,
+        k: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let i = self.hash(&k);
+        self.get_bucket(i)
+            .harris_michael_remove_if(k, pred, cursor, guard)
     }
 }
 
@@ -86,21 +119,66 @@ where
     }
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.insert(handle, key, value, guard)
+        let inserted = self.insert(handle, key, value, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.remove(handle, key, guard)
+        let removed = self.remove(handle, key, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
+impl<K, V> ConditionalRemove<K, V> for HashMap<K, V>
+where
+    K: Ord + Hash,
+{
+    #[inline]
+    fn remove_if<P>(
+        &self,
+        handle: &mut Self::Handle,
+        key: &K,
+        pred: P,
+        guard: &mut Guard,
+    ) -> Option<V>
+    where
+        P: FnMut(&V) -> bool,
+    {
+        let removed = self.remove_if(handle, key, pred, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+}
+
+register_maps! {
+    pebr;
+    HashMap => HashMap, Decreasing, with compare_and_delete;
+}
+
 #[cfg(test)]
 mod tests {
     use super::HashMap;
-    use crate::pebr::concurrent_map;
+    use crate::pebr::{concurrent_map, conditional_remove};
 
     #[test]
     fn smoke_hashmap() {
         concurrent_map::tests::smoke::<HashMap<i32, String>>();
     }
+
+    #[test]
+    fn remove_if_hashmap() {
+        conditional_remove::tests::remove_if::<HashMap<i32, String>>();
+    }
 }
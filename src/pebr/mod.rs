@@ -1,15 +1,64 @@
+#[cfg(feature = "trees")]
+pub mod mcas;
+pub mod shield_cache;
 pub mod shield_pool;
 
+pub mod concurrent_bag;
 pub mod concurrent_map;
+pub mod concurrent_priority_queue;
+pub mod concurrent_set;
+pub mod conditional_remove;
+pub mod ordered_map;
 
+#[cfg(feature = "queues")]
+pub mod bag;
+#[cfg(feature = "trees")]
 pub mod bonsai_tree;
+#[cfg(feature = "hash-maps")]
+pub mod hash_tree_map;
+#[cfg(feature = "trees")]
+pub mod kcas_tree;
+#[cfg(any(feature = "lists", feature = "hash-maps", feature = "queues"))]
 pub mod list;
+#[cfg(feature = "lists")]
+pub mod marked_list;
+#[cfg(feature = "queues")]
+pub mod mdlist;
+#[cfg(any(feature = "hash-maps", feature = "queues"))]
 pub mod michael_hash_map;
+#[cfg(feature = "queues")]
+pub mod mpsc_queue;
+#[cfg(any(feature = "trees", feature = "hash-maps"))]
 pub mod natarajan_mittal_tree;
+#[cfg(feature = "lists")]
+pub mod unrolled_list;
 
+pub use self::concurrent_bag::ConcurrentBag;
 pub use self::concurrent_map::ConcurrentMap;
+pub use self::concurrent_priority_queue::ConcurrentPriorityQueue;
+pub use self::concurrent_set::{ConcurrentSet, MapSet};
+pub use self::conditional_remove::{CompareAndDelete, ConditionalRemove};
+pub use self::ordered_map::OrderedMap;
 
+#[cfg(feature = "queues")]
+pub use self::bag::Bag;
+#[cfg(feature = "trees")]
 pub use self::bonsai_tree::BonsaiTreeMap;
-pub use self::list::{HHSList, HList, HMList};
+#[cfg(feature = "hash-maps")]
+pub use self::hash_tree_map::HashTreeMap;
+#[cfg(feature = "trees")]
+pub use self::kcas_tree::KCASTreeMap;
+#[cfg(any(feature = "lists", feature = "hash-maps", feature = "queues"))]
+pub use self::list::{AdaptiveList, HHSList, HList, HMList};
+#[cfg(feature = "lists")]
+pub use self::marked_list::{TagList, VersionList};
+#[cfg(feature = "queues")]
+pub use self::mdlist::MDList;
+#[cfg(any(feature = "hash-maps", feature = "queues"))]
 pub use self::michael_hash_map::HashMap;
+#[cfg(feature = "queues")]
+pub use self::mpsc_queue::MpscQueue;
+#[cfg(any(feature = "trees", feature = "hash-maps"))]
 pub use self::natarajan_mittal_tree::NMTreeMap;
+#[cfg(feature = "lists")]
+pub use self::unrolled_list::UnrolledList;
@@ -0,0 +1,233 @@
+//! Vyukov's intrusive multi-producer single-consumer queue.
+//!
+//! The nodes are allocated by the caller, and the queue never allocates or retires a node: `push`
+//! transfers the ownership of a node to the queue, and `Consumer::pop` transfers it back so that the
+//! caller can reuse it. A producer accesses only its own node and the previous head, which the
+//! consumer doesn't pop until the producer links it, so a popped node is never accessed by the
+//! queue again. The operations still run in critical sections, which isolates the overhead of the
+//! reclamation protocol from that of the allocator.
+
+use crate::registry::{Driver, Registry, Scheme};
+use crate::striped_counter::StripedCounter;
+use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shared};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub struct Node<T> {
+    next: Atomic<Node<T>>,
+    /// `None` only for the stub.
+    value: Option<T>,
+}
+
+impl<T> Node<T> {
+    pub fn new(value: T) -> Box<Self> {
+        Box::new(Node {
+            next: Atomic::null(),
+            value: Some(value),
+        })
+    }
+
+    pub fn value(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+
+    pub fn value_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+
+    pub fn into_value(self) -> T {
+        self.value.unwrap()
+    }
+}
+
+pub struct MpscQueue<T> {
+    /// The last pushed node.
+    head: Atomic<Node<T>>,
+    /// The first node, accessed only by the consumer.
+    tail: Atomic<Node<T>>,
+    /// The node that is pushed when the consumer is about to pop the last node, so that the queue
+    /// is never empty.
+    stub: Atomic<Node<T>>,
+    /// Whether a consumer exists.
+    consumed: AtomicBool,
+    len: StripedCounter,
+}
+
+/// The single consumer of a queue.
+pub struct Consumer<'q, T> {
+    queue: &'q MpscQueue<T>,
+}
+
+impl<T> Default for MpscQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> MpscQueue<T> {
+    pub fn new() -> Self {
+        let stub = Owned::new(Node {
+            next: Atomic::null(),
+            value: None,
+        })
+        .into_shared(unsafe { unprotected() });
+        MpscQueue {
+            head: Atomic::from(stub),
+            tail: Atomic::from(stub),
+            stub: Atomic::from(stub),
+            consumed: AtomicBool::new(false),
+            len: StripedCounter::new(),
+        }
+    }
+
+    /// Pushes `node`, transferring its ownership to the queue.
+    pub fn push(&self, node: Box<Node<T>>, guard: &Guard) {
+        self.push_node(Owned::<Node<T>>::from(node).into_shared(guard), guard);
+        self.len.add(1);
+    }
+
+    fn push_node<'g>(&self, node: Shared<'g, Node<T>>, guard: &'g Guard) {
+        unsafe { node.deref() }
+            .next
+            .store(Shared::null(), Ordering::Relaxed);
+        let prev = self.head.swap(node, Ordering::AcqRel, guard);
+        // Until this store, `prev` is the last node visible to the consumer, so it is not popped.
+        unsafe { prev.deref() }.next.store(node, Ordering::Release);
+    }
+
+    /// Becomes the consumer of the queue. Returns `None` if the queue already has one.
+    pub fn consumer(&self) -> Option<Consumer<'_, T>> {
+        if self.consumed.swap(true, Ordering::Acquire) {
+            return None;
+        }
+        Some(Consumer { queue: self })
+    }
+
+    pub fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+impl<T> Consumer<'_, T> {
+    /// Pops the first node, transferring its ownership to the caller. Returns `None` if the queue
+    /// is empty or a producer has not linked the only node yet.
+    pub fn pop(&mut self, guard: &Guard) -> Option<Box<Node<T>>> {
+        let queue = self.queue;
+        let stub = queue.stub.load(Ordering::Relaxed, guard);
+        let mut tail = queue.tail.load(Ordering::Relaxed, guard);
+        let mut next = unsafe { tail.deref() }.next.load(Ordering::Acquire, guard);
+        if tail == stub {
+            if next.is_null() {
+                return None;
+            }
+            queue.tail.store(next, Ordering::Relaxed);
+            tail = next;
+            next = unsafe { next.deref() }.next.load(Ordering::Acquire, guard);
+        }
+
+        if next.is_null() {
+            if tail != queue.head.load(Ordering::Acquire, guard) {
+                // A producer is between swapping `head` and linking its node.
+                return None;
+            }
+            // Push the stub to pop `tail`, the last node.
+            queue.push_node(stub, guard);
+            next = unsafe { tail.deref() }.next.load(Ordering::Acquire, guard);
+            if next.is_null() {
+                return None;
+            }
+        }
+
+        queue.tail.store(next, Ordering::Relaxed);
+        queue.len.add(-1);
+        Some(unsafe { Box::from_raw(tail.as_raw() as *mut Node<T>) })
+    }
+}
+
+impl<T> Drop for Consumer<'_, T> {
+    fn drop(&mut self) {
+        self.queue.consumed.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for MpscQueue<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let guard = unprotected();
+            let stub = self.stub.load(Ordering::Relaxed, guard);
+            let mut stub_dropped = false;
+            let mut curr = self.tail.load(Ordering::Relaxed, guard);
+            while !curr.is_null() {
+                let next = curr.deref().next.load(Ordering::Relaxed, guard);
+                stub_dropped |= curr == stub;
+                drop(curr.into_owned());
+                curr = next;
+            }
+            if !stub_dropped {
+                drop(stub.into_owned());
+            }
+        }
+    }
+}
+
+/// Registers the queue.
+pub fn register<D: Driver>(registry: &mut Registry<D>) {
+    registry.register_other("MpscQueue", Scheme::PEBR, |d: &D, _| d.pebr_mpsc_queue());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MpscQueue, Node};
+    use crossbeam_pebr::pin;
+    use crossbeam_utils::thread;
+
+    const PRODUCERS: usize = 10;
+    const ELEMENTS_PER_PRODUCER: usize = 1000;
+
+    #[test]
+    fn smoke_mpsc_queue() {
+        let queue = &MpscQueue::new();
+
+        thread::scope(|s| {
+            for t in 0..PRODUCERS {
+                s.spawn(move |_| {
+                    for i in 0..ELEMENTS_PER_PRODUCER {
+                        queue.push(Node::new((t, i.to_string())), &pin());
+                    }
+                });
+            }
+
+            let mut consumer = queue.consumer().unwrap();
+            assert!(queue.consumer().is_none());
+            // The nodes of each producer are popped in the order they are pushed.
+            let mut next = vec![0; PRODUCERS];
+            let mut popped = 0;
+            while popped < PRODUCERS * ELEMENTS_PER_PRODUCER {
+                if let Some(node) = consumer.pop(&pin()) {
+                    let (t, i) = node.into_value();
+                    assert_eq!(i, next[t].to_string());
+                    next[t] += 1;
+                    popped += 1;
+                }
+            }
+            assert!(consumer.pop(&pin()).is_none());
+        })
+        .unwrap();
+        assert_eq!(queue.len_approx(), 0);
+
+        // A popped node can be pushed again.
+        let mut consumer = queue.consumer().unwrap();
+        let mut node = Node::new((0, String::new()));
+        for i in 0..10 {
+            node.value_mut().1 = i.to_string();
+            queue.push(node, &pin());
+            node = consumer.pop(&pin()).unwrap();
+            assert_eq!(node.value().1, i.to_string());
+        }
+        drop(consumer);
+
+        // The nodes left in the queue are dropped with it.
+        queue.push(node, &pin());
+        queue.push(Node::new((1, "1".to_string())), &pin());
+    }
+}
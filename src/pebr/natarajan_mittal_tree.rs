@@ -1,6 +1,14 @@
-use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shared, Shield, ShieldError};
+use super::shield_cache::{self, ShieldCache};
+use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Pointer, Shared, Shield, ShieldError};
 
 use super::concurrent_map::ConcurrentMap;
+use super::ordered_map::OrderedMap;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::remove_phases::{self, Phase};
+use crate::restarts;
+use crate::seek_stats;
+use crate::striped_counter::StripedCounter;
 use std::cmp;
 use std::mem;
 use std::sync::atomic::Ordering;
@@ -110,6 +118,14 @@ where
         }
     }
 
+    /// The key and the value of a leaf with a finite key.
+    fn entry(&self) -> (&K, &V) {
+        match &self.key {
+            Key::Fin(key) => (key, self.value.as_ref().unwrap()),
+            Key::Inf => unreachable!("sentinel leaf"),
+        }
+    }
+
     /// Make a new internal node, consuming the given left and right nodes,
     /// using the right node's key.
     fn new_internal(left: Node<K, V>, right: Node<K, V>) -> Node<K, V> {
@@ -122,6 +138,20 @@ where
     }
 }
 
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    /// The child toward `key`, and its direction.
+    fn child<'g>(&self, key: &K, guard: &'g Guard) -> (Direction, Shared<'g, Node<K, V>>) {
+        if self.key.cmp(key) == cmp::Ordering::Greater {
+            (Direction::L, self.left.load(Ordering::Acquire, guard))
+        } else {
+            (Direction::R, self.right.load(Ordering::Acquire, guard))
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum Direction {
     L,
@@ -144,6 +174,8 @@ pub struct SeekRecord<K, V> {
     leaf: Shield<Node<K, V>>,
     /// The direction of leaf from parent.
     leaf_dir: Direction,
+    /// Protects the nodes near the root.
+    cache: ShieldCache<Node<K, V>>,
 }
 
 // TODO(@jeehoonkang): code duplication...
@@ -156,9 +188,11 @@ impl<K, V> SeekRecord<K, V> {
             parent: Shield::null(guard),
             leaf: Shield::null(guard),
             leaf_dir: Direction::L,
+            cache: ShieldCache::new(guard),
         }
     }
 
+    /// Releases the shields of the record. The cache is kept for the next operations.
     fn release(&mut self) {
         self.ancestor.release();
         self.successor.release();
@@ -191,6 +225,9 @@ impl<K, V> SeekRecord<K, V> {
 // COMMENT(@jeehoonkang): write down the invariant of the tree
 pub struct NMTreeMap<K, V> {
     r: Node<K, V>,
+    len: StripedCounter,
+    /// Whether a retry resumes its seek from the ancestor of the failed attempt (`seek_stats`).
+    reuse_seeks: bool,
 }
 
 impl<K, V> Default for NMTreeMap<K, V>
@@ -245,7 +282,11 @@ where
         let inf2 = Node::new_leaf(Key::Inf, None);
         let s = Node::new_internal(inf0, inf1);
         let r = Node::new_internal(s, inf2);
-        NMTreeMap { r }
+        NMTreeMap {
+            r,
+            len: StripedCounter::new(),
+            reuse_seeks: seek_stats::reuse(),
+        }
     }
 
     // All `Shared<_>` fields are unmarked.
@@ -271,16 +312,97 @@ where
         unsafe {
             record.parent.defend_fake(s);
         }
-        record.leaf.defend(leaf, guard)?;
+        record.cache.defend(0, leaf, &mut record.leaf, guard)?;
         record.leaf_dir = Direction::L;
 
-        let mut prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
-        let mut curr_dir = Direction::L;
-        let mut curr = unsafe { record.leaf.deref() }
+        let prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
+        let curr_dir = Direction::L;
+        let curr = unsafe { record.leaf.deref() }
             .left
             .load(Ordering::Relaxed, guard);
+        self.descend(key, record, prev_tag, curr_dir, curr, 1, guard)
+    }
 
+    /// Seeks `key` again into `record` after a failed attempt of an update. With seek reuse,
+    /// resumes from the ancestor of `record` if it is still in the tree, and otherwise seeks from
+    /// the root.
+    fn reseek(
+        &self,
+        key: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &Guard,
+    ) -> Result<(), ShieldError> {
+        if !self.reuse_seeks {
+            return self.seek(key, record, guard);
+        }
+        let resumed = self.resume_seek(key, record, guard)?;
+        seek_stats::record_retry(resumed);
+        if !resumed {
+            self.seek(key, record, guard)?;
+        }
+        Ok(())
+    }
+
+    /// Seeks `key` from `record.ancestor`, a node that was on the path of `key`, keeping its shield.
+    /// Returns `Ok(false)` if the edge of the ancestor toward `key` is marked or leads to a leaf, as
+    /// then the ancestor may be removed.
+    fn resume_seek(
+        &self,
+        key: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        // Fails if the thread was ejected since it protected the ancestor. The pointer is copied out
+        // of the shield, which can't be borrowed while it is defended again.
+        let ancestor = unsafe { Shared::from_usize(record.ancestor.shared().into_usize()) };
+        record.ancestor.defend(ancestor, guard)?;
+        let (successor_dir, successor) = unsafe { record.ancestor.deref() }.child(key, guard);
+        if successor.tag() != Marks::empty().bits() {
+            return Ok(false);
+        }
+        record.successor.defend(successor, guard)?;
+        let (leaf_dir, leaf) = unsafe { record.successor.deref() }.child(key, guard);
+        if leaf.is_null() {
+            return Ok(false);
+        }
+        record.successor_dir = successor_dir;
+        record.parent.defend(successor, guard)?;
+        record
+            .leaf
+            .defend(leaf.with_tag(Marks::empty().bits()), guard)?;
+        record.leaf_dir = leaf_dir;
+
+        let prev_tag = Marks::from_bits_truncate(leaf.tag()).tag();
+        let (curr_dir, curr) = unsafe { record.leaf.deref() }.child(key, guard);
+        // The resumed seek starts below the depths that the cache protects.
+        self.descend(
+            key,
+            record,
+            prev_tag,
+            curr_dir,
+            curr,
+            shield_cache::size(),
+            guard,
+        )?;
+        Ok(true)
+    }
+
+    /// Continues a seek of `key` from `record.leaf`, at `depth`, down to a leaf. `prev_tag` is
+    /// whether the edge to `record.leaf` is tagged, and `curr` is its child toward `key`.
+    #[allow(clippy::too_many_arguments)]
+    fn descend<'g>(
+        &self,
+        key: &K,
+        record: &mut SeekRecord<K, V>,
+        mut prev_tag: bool,
+        mut curr_dir: Direction,
+        mut curr: Shared<'g, Node<K, V>>,
+        mut depth: usize,
+        guard: &'g Guard,
+    ) -> Result<(), ShieldError> {
+        let mut visited = 1;
         while !curr.is_null() {
+            visited += 1;
             if !prev_tag {
                 // untagged edge: advance ancestor and successor pointers
                 record.ancestor.defend(record.parent.shared(), guard)?;
@@ -290,23 +412,23 @@ where
 
             // advance parent and leaf pointers
             mem::swap(&mut record.parent, &mut record.leaf);
-            record
-                .leaf
-                .defend(curr.with_tag(Marks::empty().bits()), guard)?;
+            record.cache.defend(
+                depth,
+                curr.with_tag(Marks::empty().bits()),
+                &mut record.leaf,
+                guard,
+            )?;
+            depth += 1;
             record.leaf_dir = curr_dir;
 
             // update other variables
             prev_tag = Marks::from_bits_truncate(curr.tag()).tag();
-            let curr_node = unsafe { record.leaf.deref() };
-            if curr_node.key.cmp(key) == cmp::Ordering::Greater {
-                curr_dir = Direction::L;
-                curr = curr_node.left.load(Ordering::Acquire, guard);
-            } else {
-                curr_dir = Direction::R;
-                curr = curr_node.right.load(Ordering::Acquire, guard);
-            }
+            let (dir, next) = unsafe { record.leaf.deref() }.child(key, guard);
+            curr_dir = dir;
+            curr = next;
         }
 
+        seek_stats::record_seek(visited);
         Ok(())
     }
 
@@ -360,7 +482,8 @@ where
 
                     stack.push(node_ref.left.load(Ordering::Relaxed, guard));
                     stack.push(node_ref.right.load(Ordering::Relaxed, guard));
-                    guard.defer_destroy(node);
+                    op_stats::retire();
+                    guard.retire(node);
                 }
             }
         }
@@ -400,7 +523,189 @@ where
                 unsafe { &mut *(guard as *mut Guard) },
             ) {
                 Ok(r) => return r,
-                Err(ShieldError::Ejected) => guard.repin(),
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    /// Returns the entries with keys in `[lo, hi)` in the increasing order of the keys, protecting
+    /// each visited node with `record.leaf`.
+    fn range_inner(
+        &self,
+        lo: &K,
+        hi: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &Guard,
+    ) -> Result<Vec<(K, V)>, ShieldError> {
+        let mut entries = Vec::new();
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            record
+                .leaf
+                .defend(node.with_tag(Marks::empty().bits()), guard)?;
+            let node_ref = unsafe { record.leaf.deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                // Skip the leaves that are being removed.
+                if Marks::from_bits_truncate(node.tag()).flag() {
+                    continue;
+                }
+                if let Key::Fin(key) = &node_ref.key {
+                    if lo <= key && key < hi {
+                        entries.push((key.clone(), node_ref.value.clone().unwrap()));
+                    }
+                }
+                continue;
+            }
+
+            // The keys in the left subtree are less than the node's key, and the others are in the
+            // right subtree. Push the left one last to visit it first.
+            if node_ref.key.cmp(hi) == cmp::Ordering::Less {
+                stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            }
+            if node_ref.key.cmp(lo) == cmp::Ordering::Greater {
+                stack.push(left);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the entries with keys in `[lo, hi)` in the increasing order of the keys.
+    ///
+    /// The in-order traversal is not atomic: it reflects the concurrent updates to the part of the
+    /// tree that it has not visited yet. It restarts if ejected.
+    pub fn range(
+        &self,
+        lo: &K,
+        hi: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &mut Guard,
+    ) -> Vec<(K, V)> {
+        loop {
+            match self.range_inner(lo, hi, record, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => return r,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    /// Finds the leaf with the smallest key greater than `key` into `record.leaf`. Like
+    /// `range_inner`, it walks the leaves in order from `key`, skipping the ones that are being
+    /// removed.
+    fn next_inner(
+        &self,
+        key: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            record
+                .leaf
+                .defend(node.with_tag(Marks::empty().bits()), guard)?;
+            let node_ref = unsafe { record.leaf.deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                if !Marks::from_bits_truncate(node.tag()).flag()
+                    && node_ref.key.cmp(key) == cmp::Ordering::Greater
+                {
+                    // The infinite keys are of the sentinel leaves.
+                    return Ok(match node_ref.key {
+                        Key::Fin(_) => true,
+                        Key::Inf => false,
+                    });
+                }
+                continue;
+            }
+
+            stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            if node_ref.key.cmp(key) == cmp::Ordering::Greater {
+                stack.push(left);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn next<'g>(
+        &'g self,
+        key: &K,
+        record: &'g mut SeekRecord<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        loop {
+            match self.next_inner(key, record, unsafe { &*(guard as *mut Guard) }) {
+                Ok(true) => return Some(unsafe { record.leaf.deref() }.entry()),
+                Ok(false) => return None,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    /// Finds the leaf with the largest key less than `key` into `record.leaf`, walking the leaves
+    /// in the reverse order.
+    fn prev_inner(
+        &self,
+        key: &K,
+        record: &mut SeekRecord<K, V>,
+        guard: &Guard,
+    ) -> Result<bool, ShieldError> {
+        let mut stack = vec![self.r.left.load(Ordering::Acquire, guard)];
+
+        while let Some(node) = stack.pop() {
+            record
+                .leaf
+                .defend(node.with_tag(Marks::empty().bits()), guard)?;
+            let node_ref = unsafe { record.leaf.deref() };
+            let left = node_ref.left.load(Ordering::Acquire, guard);
+
+            if left.is_null() {
+                if !Marks::from_bits_truncate(node.tag()).flag()
+                    && node_ref.key.cmp(key) == cmp::Ordering::Less
+                {
+                    return Ok(true);
+                }
+                continue;
+            }
+
+            // Push the right subtree last to visit it first, if it may have a key less than `key`.
+            stack.push(left);
+            if node_ref.key.cmp(key) == cmp::Ordering::Less {
+                stack.push(node_ref.right.load(Ordering::Acquire, guard));
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn prev<'g>(
+        &'g self,
+        key: &K,
+        record: &'g mut SeekRecord<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        loop {
+            match self.prev_inner(key, record, unsafe { &*(guard as *mut Guard) }) {
+                Ok(true) => return Some(unsafe { record.leaf.deref() }.entry()),
+                Ok(false) => return None,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
             }
         }
     }
@@ -424,13 +729,20 @@ where
         })
         .into_shared(unsafe { unprotected() });
 
+        let mut retry = false;
         loop {
-            self.seek(key, record, guard).map_err(|e| unsafe {
+            let seek = if retry {
+                self.reseek(key, record, guard)
+            } else {
+                self.seek(key, record, guard)
+            };
+            seek.map_err(|e| unsafe {
                 let value = new_leaf.deref_mut().value.take().unwrap();
                 drop(new_leaf.into_owned());
                 drop(new_internal.into_owned());
                 (value, Some(e))
             })?;
+            retry = true;
             let leaf = record.leaf.shared();
 
             let (new_left, new_right) = match unsafe { leaf.deref() }.key.cmp(key) {
@@ -457,6 +769,7 @@ where
             {
                 Ok(_) => return Ok(()),
                 Err(e) => {
+                    restarts::record();
                     // Insertion failed. Help the conflicting remove operation if needed.
                     // NOTE: The paper version checks if any of the mark is set, which is redundant.
                     if e.current.with_tag(Marks::empty().bits()) == leaf {
@@ -482,6 +795,7 @@ where
                 Err((v, None)) => return Err((key, v)),
                 Err((v, Some(ShieldError::Ejected))) => {
                     value = v;
+                    op_stats::retry();
                     guard.repin();
                 }
             }
@@ -493,15 +807,15 @@ where
         &self,
         key: &K,
         record: &mut SeekRecord<K, V>,
+        timer: &mut remove_phases::Timer,
         guard: &Guard,
     ) -> Result<Option<V>, ShieldError> {
         // NOTE: The paper version uses one big loop for both phases.
         // injection phase
         //
         // `leaf` and `value` are the snapshot of the node to be deleted.
+        self.seek(key, record, guard)?;
         let (leaf, value) = loop {
-            self.seek(key, record, guard)?;
-
             // candidates
             let leaf = record.leaf.shared();
             let leaf_node = unsafe { record.leaf.as_ref().unwrap() };
@@ -512,6 +826,7 @@ where
 
             // Copy the value before the physical deletion.
             let value = leaf_node.value.as_ref().unwrap().clone();
+            timer.lap(Phase::Search);
 
             // Try injecting the deletion flag.
             match record.leaf_addr().compare_and_set(
@@ -521,6 +836,7 @@ where
                 &guard,
             ) {
                 Ok(_) => {
+                    timer.lap(Phase::Mark);
                     // Finalize the node to be removed
                     if self.cleanup(&record, guard) {
                         return Ok(Some(value));
@@ -530,6 +846,7 @@ where
                     break (leaf, value);
                 }
                 Err(e) => {
+                    restarts::record();
                     // Flagging failed.
                     // case 1. record.leaf_addr(e.current) points to another node: restart.
                     // case 2. Another thread flagged/tagged the edge to leaf: help and restart
@@ -537,15 +854,17 @@ where
                     if leaf == e.current.with_tag(Marks::empty().bits()) {
                         self.cleanup(&record, guard);
                     }
+                    timer.lap(Phase::Mark);
                 }
             }
+            self.reseek(key, record, guard)?;
         };
 
         let leaf = Shared::from(leaf.as_raw());
 
         // cleanup phase
         loop {
-            self.seek(key, record, guard)?;
+            self.reseek(key, record, guard)?;
             if record.leaf.shared() != leaf {
                 // The edge to leaf flagged for deletion was removed by a helping thread
                 return Ok(Some(value));
@@ -555,16 +874,29 @@ where
             if self.cleanup(&record, guard) {
                 return Ok(Some(value));
             }
+            restarts::record();
         }
     }
 
     pub fn remove(&self, key: &K, record: &mut SeekRecord<K, V>, guard: &mut Guard) -> Option<V> {
         // TODO(@jeehoonkang): we want to use `FindError::retry`, but it requires higher-kinded
         // things...
+        let mut timer = remove_phases::Timer::start();
         loop {
-            match self.remove_inner(key, record, unsafe { &mut *(guard as *mut Guard) }) {
-                Ok(r) => return r,
-                Err(ShieldError::Ejected) => guard.repin(),
+            match self.remove_inner(key, record, &mut timer, unsafe {
+                &mut *(guard as *mut Guard)
+            }) {
+                Ok(r) => {
+                    match r {
+                        Some(_) => timer.finish(Phase::Unlink, true),
+                        None => timer.finish(Phase::Search, false),
+                    }
+                    return r;
+                }
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
             }
         }
     }
@@ -601,22 +933,92 @@ where
 
     #[inline]
     fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
-        self.insert(key, value, handle, guard).is_ok()
+        let inserted = self.insert(key, value, handle, guard).is_ok();
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
     }
 
     #[inline]
     fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
-        self.remove(key, handle, guard)
+        let removed = self.remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
     }
 }
 
+impl<K, V> OrderedMap<K, V> for NMTreeMap<K, V>
+where
+    K: Ord + Clone + 'static,
+    V: Clone + 'static,
+{
+    #[inline]
+    fn next<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.next(key, handle, guard)
+    }
+    #[inline]
+    fn prev<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<(&'g K, &'g V)> {
+        self.prev(key, handle, guard)
+    }
+}
+
+register_maps! {
+    pebr;
+    NMTree => NMTreeMap, Random, with scan;
+}
+
 #[cfg(test)]
 mod tests {
-    use super::NMTreeMap;
-    use crate::pebr::concurrent_map;
+    use super::{NMTreeMap, SeekRecord};
+    use crate::pebr::{concurrent_map, ordered_map};
+    use crossbeam_pebr::pin;
 
     #[test]
     fn smoke_nm_tree() {
         concurrent_map::tests::smoke::<NMTreeMap<i32, String>>();
     }
+
+    #[test]
+    fn ordered_nm_tree() {
+        ordered_map::tests::ordered::<NMTreeMap<i32, String>>();
+    }
+
+    #[test]
+    fn range_nm_tree() {
+        let map = NMTreeMap::new();
+        let guard = &mut pin();
+        let record = &mut SeekRecord::new(guard);
+        for i in (0..100).rev() {
+            assert!(map.insert(i * 2, i.to_string(), record, guard).is_ok());
+        }
+        for i in 0..10 {
+            assert!(map.remove(&(i * 10), record, guard).is_some());
+        }
+
+        let entries = map.range(&15, &61, record, guard);
+        let expected = (8..31)
+            .filter(|i| i % 5 != 0)
+            .map(|i| (i * 2, i.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+        assert!(map.range(&61, &61, record, guard).is_empty());
+        assert_eq!(map.range(&0, &1000, record, guard).len(), 90);
+    }
 }
@@ -0,0 +1,95 @@
+//! Per-thread cache of the shields protecting the first nodes of the recent traversals.
+//!
+//! Every traversal starts from the head of a list or the root of a tree, so the nodes near them are
+//! visited again and again. The cache dedicates a shield to each of the first `size()` depths of a
+//! traversal. Visiting the node that the shield of the depth already protects skips the fence of
+//! `Shield::defend`, and the traversal's own shield just fake-defends the node. Shields survive
+//! repinning and ejection, so the cache is kept across operations and critical sections.
+//!
+//! The cache keeps up to `size()` nodes per thread from being reclaimed. It is disabled (size 0) by
+//! default.
+
+use crossbeam_pebr::{Guard, Shared, Shield, ShieldError};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SIZE: AtomicUsize = AtomicUsize::new(0);
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of cached depths of the caches created afterwards.
+pub fn set_size(size: usize) {
+    SIZE.store(size, Ordering::Relaxed);
+}
+
+pub fn size() -> usize {
+    SIZE.load(Ordering::Relaxed)
+}
+
+/// Takes the numbers of hits and misses of the dropped caches.
+pub fn take_stats() -> (usize, usize) {
+    (
+        HITS.swap(0, Ordering::Relaxed),
+        MISSES.swap(0, Ordering::Relaxed),
+    )
+}
+
+pub struct ShieldCache<T> {
+    /// The shield of each depth.
+    shields: Vec<Shield<T>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<T> ShieldCache<T> {
+    pub fn new(guard: &Guard) -> Self {
+        Self {
+            shields: (0..size()).map(|_| Shield::null(guard)).collect(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Protects `ptr`, the node at `depth` of a traversal, and makes `shield` point to it.
+    ///
+    /// If `depth` is cached, `shield` only fake-defends `ptr`, and the node stays protected until
+    /// the next traversal reaches `depth` or the cache is released.
+    #[inline]
+    pub fn defend<'g>(
+        &mut self,
+        depth: usize,
+        ptr: Shared<'g, T>,
+        shield: &mut Shield<T>,
+        guard: &Guard,
+    ) -> Result<(), ShieldError> {
+        let cached = some_or!(
+            self.shields.get_mut(depth),
+            return shield.defend(ptr, guard)
+        );
+        if cached.shared() == ptr {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            if let Err(e) = cached.defend(ptr, guard) {
+                // The failed shield may point to `ptr` without protecting it.
+                cached.release();
+                return Err(e);
+            }
+        }
+        unsafe { shield.defend_fake(ptr) };
+        Ok(())
+    }
+
+    pub fn release(&mut self) {
+        for shield in &mut self.shields {
+            shield.release();
+        }
+    }
+}
+
+impl<T> Drop for ShieldCache<T> {
+    fn drop(&mut self) {
+        HITS.fetch_add(self.hits, Ordering::Relaxed);
+        MISSES.fetch_add(self.misses, Ordering::Relaxed);
+    }
+}
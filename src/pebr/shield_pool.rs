@@ -1,12 +1,69 @@
+//! Thread-local pools of shields, for the structures that protect an unbounded number of nodes at a
+//! time.
+//!
+//! A pool reuses the shields released by its handles, and allocates a new shield only if none is
+//! available. The shields of a pool are never freed before the pool, and each of them is a slot
+//! that the reclaimers scan, so the pools count their allocations and reuses. A pool may exceed
+//! the budget of shields set by `set_budget`: it keeps allocating so that the operations still
+//! complete, but counts the shields beyond the budget so that the run reports them.
+
 use core::ops::Deref;
 use crossbeam_pebr::{Guard, Shared, Shield, ShieldError};
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUDGET: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static OVER_BUDGET: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of shields that a pool is expected to allocate at most. 0 means unlimited.
+pub fn set_budget(budget: usize) {
+    BUDGET.store(budget, Ordering::Relaxed);
+}
+
+pub fn budget() -> usize {
+    BUDGET.load(Ordering::Relaxed)
+}
+
+/// The counts of the dropped pools.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Shields allocated because none was available.
+    pub allocations: usize,
+    /// Shields reused from the pools.
+    pub hits: usize,
+    /// Allocations beyond the budget.
+    pub over_budget: usize,
+    /// The largest number of shields of a pool.
+    pub peak: usize,
+}
+
+/// Takes the counts of the dropped pools.
+pub fn take_stats() -> Stats {
+    Stats {
+        allocations: ALLOCATIONS.swap(0, Ordering::Relaxed),
+        hits: HITS.swap(0, Ordering::Relaxed),
+        over_budget: OVER_BUDGET.swap(0, Ordering::Relaxed),
+        peak: PEAK.swap(0, Ordering::Relaxed),
+    }
+}
+
 /// Thread-local pool of shields
 #[derive(Debug)]
 pub struct ShieldPool<T> {
     shields: Vec<*mut Shield<T>>,
     /// Indices of available shields in `shields`.
     available: Vec<usize>,
+    hits: usize,
+    over_budget: usize,
+}
+
+impl<T> Default for ShieldPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> ShieldPool<T> {
@@ -14,6 +71,8 @@ impl<T> ShieldPool<T> {
         ShieldPool {
             shields: Vec::new(),
             available: Vec::new(),
+            hits: 0,
+            over_budget: 0,
         }
     }
 
@@ -23,12 +82,17 @@ impl<T> ShieldPool<T> {
         guard: &Guard,
     ) -> Result<ShieldHandle<T>, ShieldError> {
         if let Some(index) = self.available.pop() {
+            self.hits += 1;
             let shield_ref = unsafe { &mut **self.shields.get_unchecked(index) };
             shield_ref.defend(ptr, guard)?;
             return Ok(ShieldHandle { pool: self, index });
         }
         let new_shield = Box::into_raw(Box::new(Shield::new(ptr, guard)?));
         let index = self.shields.len();
+        let budget = budget();
+        if budget > 0 && index >= budget {
+            self.over_budget += 1;
+        }
         self.shields.push(new_shield);
         Ok(ShieldHandle { pool: self, index })
     }
@@ -36,6 +100,21 @@ impl<T> ShieldPool<T> {
 
 impl<T> Drop for ShieldPool<T> {
     fn drop(&mut self) {
+        ALLOCATIONS.fetch_add(self.shields.len(), Ordering::Relaxed);
+        HITS.fetch_add(self.hits, Ordering::Relaxed);
+        OVER_BUDGET.fetch_add(self.over_budget, Ordering::Relaxed);
+        let mut peak = PEAK.load(Ordering::Relaxed);
+        while peak < self.shields.len() {
+            match PEAK.compare_exchange_weak(
+                peak,
+                self.shields.len(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(p) => peak = p,
+            }
+        }
         for s in self.shields.drain(..) {
             unsafe { drop(Box::from_raw(s)) }
         }
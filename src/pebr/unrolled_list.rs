@@ -0,0 +1,402 @@
+//! Lock-free unrolled linked list.
+//!
+//! See `ebr::unrolled_list` for the algorithm. The traversals protect the current node and its
+//! predecessor with the two shields of the cursor.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::op_stats;
+use crate::reclaim::pebr::Retire;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crossbeam_pebr::{unprotected, Atomic, Guard, Owned, Shared, Shield, ShieldError};
+
+use std::mem;
+use std::sync::atomic::Ordering;
+
+const CHUNK_SIZE: usize = 16;
+
+enum FindError {
+    Retry,
+    ShieldError(ShieldError),
+}
+
+struct Node<K, V> {
+    /// Tag 1: frozen, pointing to the replacement.
+    next: Atomic<Node<K, V>>,
+    /// Sorted by the keys. Empty only for the head sentinel.
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Ord,
+{
+    fn new(entries: Vec<(K, V)>, next: Shared<'_, Node<K, V>>) -> Owned<Self> {
+        Owned::new(Node {
+            next: Atomic::from(next),
+            entries,
+        })
+    }
+
+    #[inline]
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+
+    /// Whether `key` belongs to this node, given that it doesn't belong to the previous ones.
+    #[inline]
+    fn covers(&self, key: &K, next: Shared<'_, Node<K, V>>) -> bool {
+        next.is_null() || self.entries.last().map_or(false, |(k, _)| k >= key)
+    }
+}
+
+pub struct Cursor<K, V> {
+    prev: Shield<Node<K, V>>,
+    curr: Shield<Node<K, V>>,
+}
+
+impl<K, V> Cursor<K, V> {
+    pub fn new(guard: &Guard) -> Self {
+        Self {
+            prev: Shield::null(guard),
+            curr: Shield::null(guard),
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.prev.release();
+        self.curr.release();
+    }
+}
+
+pub struct UnrolledList<K, V> {
+    /// The head sentinel. It is never retired.
+    head: Atomic<Node<K, V>>,
+    len: StripedCounter,
+}
+
+impl<K, V> Drop for UnrolledList<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let mut curr = self.head.load(Ordering::Relaxed, unprotected());
+            while !curr.is_null() {
+                let next = curr.deref().next.load(Ordering::Relaxed, unprotected());
+                drop(curr.into_owned());
+                curr = next.with_tag(0);
+            }
+        }
+    }
+}
+
+impl<K, V> Default for UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        UnrolledList {
+            head: Atomic::new(Node {
+                next: Atomic::null(),
+                entries: Vec::new(),
+            }),
+            len: StripedCounter::new(),
+        }
+    }
+
+    /// Makes `cursor.curr` the first unfrozen node that covers `key` and `cursor.prev` its
+    /// predecessor, unlinking the frozen nodes on the way. `cursor.curr` is null if the list is
+    /// empty.
+    fn find<'g>(
+        &'g self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &'g Guard,
+    ) -> Result<(), FindError> {
+        let head = self.head.load(Ordering::Acquire, guard);
+        unsafe { cursor.prev.defend_fake(head) };
+        let mut curr = unsafe { head.deref() }.next.load(Ordering::Acquire, guard);
+        loop {
+            if curr.is_null() {
+                unsafe { cursor.curr.defend_fake(curr) };
+                return Ok(());
+            }
+            cursor
+                .curr
+                .defend(curr, guard)
+                .map_err(FindError::ShieldError)?;
+            let curr_node = unsafe { curr.deref() };
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 1 {
+                let next = next.with_tag(0);
+                if unsafe { cursor.prev.deref() }
+                    .next
+                    .compare_and_set(curr, next, Ordering::Release, guard)
+                    .is_err()
+                {
+                    return Err(FindError::Retry);
+                }
+                op_stats::retire();
+                unsafe { guard.retire(curr) };
+                curr = next;
+                continue;
+            }
+            if curr_node.covers(key, next) {
+                return Ok(());
+            }
+            mem::swap(&mut cursor.prev, &mut cursor.curr);
+            curr = next;
+        }
+    }
+
+    /// Replaces `cursor.curr`, whose successor is `next`, with `new`. Returns false if
+    /// `cursor.curr` has changed.
+    fn replace<'g>(
+        &'g self,
+        cursor: &Cursor<K, V>,
+        next: Shared<'g, Node<K, V>>,
+        new: Shared<'g, Node<K, V>>,
+        guard: &'g Guard,
+    ) -> bool {
+        let curr = cursor.curr.shared();
+        if unsafe { curr.deref() }
+            .next
+            .compare_and_set(next, new.with_tag(1), Ordering::AcqRel, guard)
+            .is_err()
+        {
+            return false;
+        }
+        if unsafe { cursor.prev.deref() }
+            .next
+            .compare_and_set(curr, new, Ordering::Release, guard)
+            .is_ok()
+        {
+            op_stats::retire();
+            unsafe { guard.retire(curr) };
+        }
+        true
+    }
+
+    /// Makes `cursor.curr` the node that has the up-to-date entries for `key`, and returns the
+    /// index of `key` in it.
+    fn get_inner<'g>(
+        &'g self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &'g Guard,
+    ) -> Result<Option<usize>, ShieldError> {
+        let head = self.head.load(Ordering::Acquire, guard);
+        let mut curr = unsafe { head.deref() }.next.load(Ordering::Acquire, guard);
+        loop {
+            if curr.is_null() {
+                return Ok(None);
+            }
+            cursor.curr.defend(curr, guard)?;
+            let curr_node = unsafe { curr.deref() };
+            let next = curr_node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 && curr_node.covers(key, next) {
+                return Ok(curr_node.search(key).ok());
+            }
+            // If frozen, the replacement has the up-to-date entries.
+            mem::swap(&mut cursor.prev, &mut cursor.curr);
+            curr = next.with_tag(0);
+        }
+    }
+
+    pub fn get<'g>(
+        &'g self,
+        key: &K,
+        cursor: &'g mut Cursor<K, V>,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        let index = loop {
+            match self.get_inner(key, cursor, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => break r?,
+                Err(ShieldError::Ejected) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        };
+        Some(unsafe { &cursor.curr.deref().entries[index].1 })
+    }
+
+    fn insert_inner<'g>(
+        &'g self,
+        key: &K,
+        value: &V,
+        cursor: &mut Cursor<K, V>,
+        guard: &'g Guard,
+    ) -> Result<bool, FindError> {
+        self.find(key, cursor, guard)?;
+        let curr_node = some_or!(unsafe { cursor.curr.as_ref() }, {
+            let new = Node::new(vec![(key.clone(), value.clone())], Shared::null());
+            return unsafe { cursor.prev.deref() }
+                .next
+                .compare_and_set(Shared::null(), new, Ordering::Release, guard)
+                .map(|_| true)
+                .map_err(|_| FindError::Retry);
+        });
+        let next = curr_node.next.load(Ordering::Acquire, guard);
+        if next.tag() == 1 {
+            return Err(FindError::Retry);
+        }
+        let index = match curr_node.search(key) {
+            Ok(_) => return Ok(false),
+            Err(index) => index,
+        };
+
+        let mut entries = curr_node.entries.clone();
+        entries.insert(index, (key.clone(), value.clone()));
+        let split = entries.len() > CHUNK_SIZE;
+        let new = if split {
+            let right = entries.split_off(entries.len() / 2);
+            let right = Node::new(right, next).into_shared(guard);
+            Node::new(entries, right).into_shared(guard)
+        } else {
+            Node::new(entries, next).into_shared(guard)
+        };
+        if self.replace(cursor, next, new, guard) {
+            return Ok(true);
+        }
+        unsafe {
+            let new = new.into_owned();
+            if split {
+                drop(new.next.load(Ordering::Relaxed, guard).into_owned());
+            }
+        }
+        Err(FindError::Retry)
+    }
+
+    pub fn insert(&self, key: K, value: V, cursor: &mut Cursor<K, V>, guard: &mut Guard) -> bool {
+        loop {
+            match self.insert_inner(&key, &value, cursor, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => return r,
+                Err(FindError::Retry) => restarts::record(),
+                Err(FindError::ShieldError(ShieldError::Ejected)) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+
+    fn remove_inner<'g>(
+        &'g self,
+        key: &K,
+        cursor: &mut Cursor<K, V>,
+        guard: &'g Guard,
+    ) -> Result<Option<V>, FindError> {
+        self.find(key, cursor, guard)?;
+        let curr_node = some_or!(unsafe { cursor.curr.as_ref() }, return Ok(None));
+        let next = curr_node.next.load(Ordering::Acquire, guard);
+        if next.tag() == 1 {
+            return Err(FindError::Retry);
+        }
+        let index = some_or!(curr_node.search(key).ok(), return Ok(None));
+
+        let value = curr_node.entries[index].1.clone();
+        let new = if curr_node.entries.len() == 1 {
+            next
+        } else {
+            let mut entries = curr_node.entries.clone();
+            entries.remove(index);
+            Node::new(entries, next).into_shared(guard)
+        };
+        if self.replace(cursor, next, new, guard) {
+            return Ok(Some(value));
+        }
+        if new != next {
+            drop(unsafe { new.into_owned() });
+        }
+        Err(FindError::Retry)
+    }
+
+    pub fn remove(&self, key: &K, cursor: &mut Cursor<K, V>, guard: &mut Guard) -> Option<V> {
+        loop {
+            match self.remove_inner(key, cursor, unsafe { &*(guard as *mut Guard) }) {
+                Ok(r) => return r,
+                Err(FindError::Retry) => restarts::record(),
+                Err(FindError::ShieldError(ShieldError::Ejected)) => {
+                    op_stats::retry();
+                    guard.repin();
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> ConcurrentMap<K, V> for UnrolledList<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    type Handle = Cursor<K, V>;
+
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn handle(guard: &Guard) -> Self::Handle {
+        Cursor::new(guard)
+    }
+
+    fn clear(handle: &mut Self::Handle) {
+        handle.release();
+    }
+
+    #[inline]
+    fn get<'g>(
+        &'g self,
+        handle: &'g mut Self::Handle,
+        key: &K,
+        guard: &'g mut Guard,
+    ) -> Option<&'g V> {
+        self.get(key, handle, guard)
+    }
+    #[inline]
+    fn insert(&self, handle: &mut Self::Handle, key: K, value: V, guard: &mut Guard) -> bool {
+        let inserted = self.insert(key, value, handle, guard);
+        if inserted {
+            self.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, handle: &mut Self::Handle, key: &K, guard: &mut Guard) -> Option<V> {
+        let removed = self.remove(key, handle, guard);
+        if removed.is_some() {
+            self.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.len.approx()
+    }
+}
+
+register_maps! {
+    pebr;
+    UnrolledList => UnrolledList, Decreasing;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnrolledList;
+    use crate::pebr::concurrent_map;
+
+    #[test]
+    fn smoke_unrolled_list() {
+        concurrent_map::tests::smoke::<UnrolledList<i32, String>>();
+    }
+}
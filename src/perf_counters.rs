@@ -0,0 +1,200 @@
+//! Hardware event counters of the worker threads, to attribute scalability cliffs.
+//!
+//! When a structure stops scaling at high thread counts, the throughput alone does not tell
+//! whether the threads contend on a few cache lines, miss the cache more as the garbage grows, or
+//! access the memory of a remote NUMA node. When enabled, each worker opens a counter of each of
+//! `EVENTS` for itself with `perf_event_open` before its first operation and reads it after its
+//! last one, and the counts of the workers are added up for the run.
+//!
+//! The counters need the `perf` feature and Linux. An event that the processor or the
+//! `perf_event_paranoid` setting does not allow is left out.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// The events that are counted: CPU cycles, retired instructions, last-level cache misses, and
+/// loads that missed the local NUMA node, i.e., were served by a remote node.
+pub const EVENTS: [&str; 4] = ["cycles", "instructions", "cache misses", "remote accesses"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COUNTS: [AtomicU64; 4] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+/// The number of workers for which each counter was opened.
+static OPENED: [AtomicUsize; 4] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+static OPS: AtomicU64 = AtomicU64::new(0);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether this build can count the events.
+pub fn supported() -> bool {
+    cfg!(all(feature = "perf", target_os = "linux"))
+}
+
+/// The counts of the events of the workers of a run.
+#[derive(Clone, Copy, Debug)]
+pub struct Counts {
+    /// The count of each of `EVENTS`, or `None` if its counter could not be opened.
+    pub events: [Option<u64>; 4],
+    /// The operations of the workers.
+    pub ops: u64,
+}
+
+/// Takes the counts of the workers that have stopped their counters.
+pub fn take() -> Counts {
+    let mut events = [None; 4];
+    for (i, event) in events.iter_mut().enumerate() {
+        let count = COUNTS[i].swap(0, Ordering::Relaxed);
+        if OPENED[i].swap(0, Ordering::Relaxed) > 0 {
+            *event = Some(count);
+        }
+    }
+    Counts {
+        events,
+        ops: OPS.swap(0, Ordering::Relaxed),
+    }
+}
+
+cfg_if! {
+    if #[cfg(all(feature = "perf", target_os = "linux"))] {
+        use perf_event_open_sys::bindings::{self, perf_event_attr};
+        use perf_event_open_sys::{ioctls, perf_event_open};
+        use std::cell::RefCell;
+        use std::mem;
+
+        /// A counter of an event of the current thread, opened disabled.
+        struct Counter {
+            fd: libc::c_int,
+        }
+
+        impl Counter {
+            fn open(type_: u32, config: u64) -> Option<Self> {
+                let mut attr = perf_event_attr::default();
+                attr.size = mem::size_of::<perf_event_attr>() as u32;
+                attr.type_ = type_;
+                attr.config = config;
+                attr.set_disabled(1);
+                attr.set_exclude_kernel(1);
+                attr.set_exclude_hv(1);
+                // The calling thread, on any CPU.
+                let fd = unsafe { perf_event_open(&mut attr, 0, -1, -1, 0) };
+                if fd < 0 {
+                    return None;
+                }
+                Some(Counter { fd })
+            }
+
+            fn enable(&self) -> Option<()> {
+                if unsafe { ioctls::ENABLE(self.fd, 0) } < 0 {
+                    return None;
+                }
+                Some(())
+            }
+
+            fn read(&self) -> Option<u64> {
+                let mut count = 0u64;
+                let len = mem::size_of::<u64>();
+                let read = unsafe { libc::read(self.fd, &mut count as *mut u64 as *mut _, len) };
+                if read != len as isize {
+                    return None;
+                }
+                Some(count)
+            }
+        }
+
+        impl Drop for Counter {
+            fn drop(&mut self) {
+                unsafe { libc::close(self.fd) };
+            }
+        }
+
+        thread_local! {
+            static COUNTERS: RefCell<Vec<Option<Counter>>> = RefCell::new(Vec::new());
+        }
+
+        fn add(event: usize, count: u64) {
+            COUNTS[event].fetch_add(count, Ordering::Relaxed);
+            OPENED[event].fetch_add(1, Ordering::Relaxed);
+        }
+
+        /// The type and the config of `perf_event_attr` of each of `EVENTS`.
+        fn event(i: usize) -> (u32, u64) {
+            match i {
+                0 => (
+                    bindings::perf_type_id_PERF_TYPE_HARDWARE,
+                    bindings::perf_hw_id_PERF_COUNT_HW_CPU_CYCLES as u64,
+                ),
+                1 => (
+                    bindings::perf_type_id_PERF_TYPE_HARDWARE,
+                    bindings::perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS as u64,
+                ),
+                2 => (
+                    bindings::perf_type_id_PERF_TYPE_HARDWARE,
+                    bindings::perf_hw_id_PERF_COUNT_HW_CACHE_MISSES as u64,
+                ),
+                // The cache events are configured with the cache, the operation and the result, a
+                // byte each.
+                _ => (
+                    bindings::perf_type_id_PERF_TYPE_HW_CACHE,
+                    bindings::perf_hw_cache_id_PERF_COUNT_HW_CACHE_NODE as u64
+                        | (bindings::perf_hw_cache_op_id_PERF_COUNT_HW_CACHE_OP_READ as u64) << 8
+                        | (bindings::perf_hw_cache_op_result_id_PERF_COUNT_HW_CACHE_RESULT_MISS
+                            as u64)
+                            << 16,
+                ),
+            }
+        }
+
+        /// Opens and enables the counters of the current worker.
+        pub fn start() {
+            if !enabled() {
+                return;
+            }
+            let counters = (0..EVENTS.len())
+                .map(|i| {
+                    let (type_, config) = event(i);
+                    let counter = Counter::open(type_, config)?;
+                    counter.enable()?;
+                    Some(counter)
+                })
+                .collect();
+            COUNTERS.with(|c| *c.borrow_mut() = counters);
+        }
+
+        /// Reads and closes the counters of the current worker, which performed `ops` operations.
+        pub fn stop(ops: u64) {
+            if !enabled() {
+                return;
+            }
+            let counters = COUNTERS.with(|c| c.replace(Vec::new()));
+            for (i, counter) in counters.into_iter().enumerate() {
+                if let Some(count) = counter.and_then(|c| c.read()) {
+                    add(i, count);
+                }
+            }
+            OPS.fetch_add(ops, Ordering::Relaxed);
+        }
+    } else {
+        pub fn start() {}
+
+        pub fn stop(ops: u64) {
+            if enabled() {
+                OPS.fetch_add(ops, Ordering::Relaxed);
+            }
+        }
+    }
+}
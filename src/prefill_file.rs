@@ -0,0 +1,147 @@
+//! Checkpoints of the keys of the prefill.
+//!
+//! The prefill of a map inserts the keys that the random number generator of the prefill samples,
+//! minus the long-lived keys, which it inserts first. With hundreds of millions of keys, sampling
+//! them again at each repetition of a sweep takes a while, so `--prefill-file` samples them once,
+//! saves them, and the later runs load them. The file holds the keys in the order of their
+//! sampling, before the decreasing prefill sorts them, so that a file serves both prefills.
+//!
+//! The format is the magic `PEBRKEYS`, then the range of the keys, the number of long-lived keys,
+//! the seed of the sampling and the number of keys, followed by the keys, all little-endian `u64`s.
+//! The range and the number of long-lived keys identify the configurations that the file fits.
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"PEBRKEYS";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PrefillKeys {
+    /// The keys are in `0..range`.
+    pub range: usize,
+    /// The number of long-lived keys at the start of `keys`.
+    pub long_lived: usize,
+    /// The seed that the keys were sampled with.
+    pub seed: u64,
+    pub keys: Vec<usize>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let value = read_u64(reader)?;
+    usize::try_from(value).map_err(|_| invalid(format!("{} does not fit in a usize", value)))
+}
+
+impl PrefillKeys {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a file of prefill keys".to_string()));
+        }
+        let range = read_usize(&mut reader)?;
+        let long_lived = read_usize(&mut reader)?;
+        let seed = read_u64(&mut reader)?;
+        let len = read_usize(&mut reader)?;
+        if long_lived > len {
+            return Err(invalid(format!(
+                "{} long-lived keys out of {}",
+                long_lived, len
+            )));
+        }
+        // Not trusting `len` for the allocation, in case the file is truncated.
+        let mut keys = Vec::new();
+        for _ in 0..len {
+            let key = read_usize(&mut reader)?;
+            if key >= range {
+                return Err(invalid(format!("key {} out of the range {}", key, range)));
+            }
+            keys.push(key);
+        }
+        Ok(Self {
+            range,
+            long_lived,
+            seed,
+            keys,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        let header = [
+            self.range as u64,
+            self.long_lived as u64,
+            self.seed,
+            self.keys.len() as u64,
+        ];
+        for value in header.iter() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for &key in &self.keys {
+            writer.write_all(&(key as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrefillKeys;
+
+    #[test]
+    fn round_trip() {
+        let keys = PrefillKeys {
+            range: 100,
+            long_lived: 2,
+            seed: u64::max_value(),
+            keys: vec![3, 97, 42, 0, 42],
+        };
+        let mut bytes = Vec::new();
+        keys.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 4 * 8 + 5 * 8);
+        assert_eq!(PrefillKeys::read(&bytes[..]).unwrap(), keys);
+    }
+
+    #[test]
+    fn reject() {
+        let keys = PrefillKeys {
+            range: 10,
+            long_lived: 0,
+            seed: 7,
+            keys: vec![1, 2, 3],
+        };
+        let mut bytes = Vec::new();
+        keys.write(&mut bytes).unwrap();
+
+        assert!(PrefillKeys::read(&bytes[..bytes.len() - 1]).is_err());
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(PrefillKeys::read(&wrong_magic[..]).is_err());
+        // The last key, 3, becomes 10.
+        let mut out_of_range = bytes.clone();
+        let last = out_of_range.len() - 8;
+        out_of_range[last] = 10;
+        assert!(PrefillKeys::read(&out_of_range[..]).is_err());
+    }
+}
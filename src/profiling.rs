@@ -0,0 +1,43 @@
+//! Markers of the phases of the operations, for sampling profilers such as `perf` and VTune.
+//!
+//! The workers reach the structures through closures that are inlined into their loops, and the
+//! structures take their traversals as closures too, so a flamegraph of a run attributes most of
+//! its time to the closure of the worker. With the `profiling` feature, each function below runs
+//! its closure in a frame of its own that is never inlined and whose symbol names the phase, e.g.,
+//! `pebr_benchmark::profiling::find`, so that the samples fall under the phases in the call
+//! stacks. The stacks need frame pointers or debug info to be unwound, e.g.,
+//!
+//! ```text
+//! RUSTFLAGS="-C force-frame-pointers=yes" cargo build --release --features profiling
+//! perf record -g ./target/release/pebr-benchmark -d HMList -m EBR -t 16
+//! ```
+//!
+//! Without the feature, the functions are inlined and cost nothing.
+
+macro_rules! phases {
+    ($($(#[$attr:meta])* $name:ident;)*) => {
+        $(
+            $(#[$attr])*
+            #[cfg_attr(feature = "profiling", inline(never))]
+            #[cfg_attr(not(feature = "profiling"), inline(always))]
+            pub fn $name<R, F: FnOnce() -> R>(f: F) -> R {
+                f()
+            }
+        )*
+    };
+}
+
+phases! {
+    /// A get of a map by a worker.
+    get;
+    /// An insert into a map by a worker.
+    insert;
+    /// A remove from a map by a worker.
+    remove;
+    /// A traversal of a Harris list, including its restarts.
+    find;
+    /// The start of a critical section, in which the collector also collects every few pins.
+    pin;
+    /// A flush of the garbage by the policy of the EBR collector (`gc_policy`).
+    collect;
+}
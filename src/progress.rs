@@ -0,0 +1,144 @@
+//! Live progress of the workers, published through per-worker sequence locks.
+//!
+//! Each worker periodically publishes its counters to its own cache-padded slot, and a reporter
+//! reads the slots without writing to them, so that reading the progress doesn't perturb the
+//! workers. Only the worker writes to its slot, so a write just makes the sequence number odd while
+//! storing the counters, and a read retries if the sequence number is odd or changed meanwhile.
+
+use crossbeam_utils::CachePadded;
+use std::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub ops: u64,
+    pub retires: u64,
+}
+
+#[derive(Default)]
+struct Slot {
+    seq: AtomicUsize,
+    ops: AtomicU64,
+    retires: AtomicU64,
+}
+
+pub struct Progress {
+    slots: Vec<CachePadded<Slot>>,
+}
+
+impl Progress {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            slots: (0..workers)
+                .map(|_| CachePadded::new(Slot::default()))
+                .collect(),
+        }
+    }
+
+    /// Publishes the counters of worker `tid`. Only the worker may call this.
+    #[inline]
+    pub fn publish(&self, tid: usize, snapshot: Snapshot) {
+        let slot = &self.slots[tid];
+        let seq = slot.seq.load(Ordering::Relaxed);
+        slot.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        slot.ops.store(snapshot.ops, Ordering::Relaxed);
+        slot.retires.store(snapshot.retires, Ordering::Relaxed);
+        slot.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    pub fn workers(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Reads the last counters published by worker `tid`.
+    pub fn read(&self, tid: usize) -> Snapshot {
+        let slot = &self.slots[tid];
+        loop {
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq % 2 == 0 {
+                let snapshot = Snapshot {
+                    ops: slot.ops.load(Ordering::Relaxed),
+                    retires: slot.retires.load(Ordering::Relaxed),
+                };
+                fence(Ordering::Acquire);
+                if slot.seq.load(Ordering::Relaxed) == seq {
+                    return snapshot;
+                }
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// The sum of the last counters published by the workers.
+    pub fn total(&self) -> Snapshot {
+        (0..self.slots.len())
+            .map(|tid| self.read(tid))
+            .fold(Snapshot::default(), |acc, s| Snapshot {
+                ops: acc.ops + s.ops,
+                retires: acc.retires + s.retires,
+            })
+    }
+
+    /// Clears the counters for a new run. The workers must not publish concurrently.
+    pub fn reset(&self) {
+        for tid in 0..self.slots.len() {
+            self.publish(tid, Snapshot::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Progress, Snapshot};
+    use crossbeam_utils::thread;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn consistent_snapshots() {
+        const WORKERS: usize = 4;
+        let progress = &Progress::new(WORKERS);
+        let done = &AtomicBool::new(false);
+        thread::scope(|s| {
+            let reader = s.spawn(move |_| {
+                while !done.load(Ordering::Relaxed) {
+                    for tid in 0..WORKERS {
+                        let snapshot = progress.read(tid);
+                        assert_eq!(snapshot.retires, 2 * snapshot.ops);
+                    }
+                }
+            });
+            let workers = (0..WORKERS)
+                .map(|tid| {
+                    s.spawn(move |_| {
+                        for i in 1..=100_000 {
+                            progress.publish(
+                                tid,
+                                Snapshot {
+                                    ops: i,
+                                    retires: 2 * i,
+                                },
+                            );
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for worker in workers {
+                worker.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+            reader.join().unwrap();
+        })
+        .unwrap();
+        assert_eq!(
+            progress.total(),
+            Snapshot {
+                ops: 100_000 * WORKERS as u64,
+                retires: 200_000 * WORKERS as u64,
+            }
+        );
+
+        progress.reset();
+        assert_eq!(progress.total(), Snapshot::default());
+    }
+}
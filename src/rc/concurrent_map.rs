@@ -0,0 +1,84 @@
+use std::ops::Deref;
+
+pub trait ConcurrentMap<K, V> {
+    /// A reference to a value, which keeps the value alive until it is dropped.
+    type Ref: Deref<Target = V>;
+
+    fn new() -> Self;
+    fn get(&self, key: &K) -> Option<Self::Ref>;
+    fn insert(&self, key: K, value: V) -> bool;
+    fn remove(&self, key: &K) -> Option<V>;
+    /// The approximate number of entries. It is exact in the absence of concurrent updates.
+    fn len_approx(&self) -> usize;
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate rand;
+    use super::ConcurrentMap;
+    use crossbeam_utils::thread;
+    use rand::prelude::*;
+
+    const THREADS: i32 = 30;
+    const ELEMENTS_PER_THREADS: i32 = 1000;
+
+    pub fn smoke<M: ConcurrentMap<i32, String> + Send + Sync>() {
+        smoke_keyed::<i32, M>(|i| i);
+    }
+
+    /// Like `smoke`, with the keys that `key` makes of the integers, e.g., to smoke-test the
+    /// maps of strings of the registry.
+    pub fn smoke_keyed<K, M: ConcurrentMap<K, String> + Send + Sync>(key: fn(i32) -> K) {
+        let map = &M::new();
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                s.spawn(move |_| {
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert!(map.insert(key(i), i.to_string()));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(map.len_approx(), (THREADS * ELEMENTS_PER_THREADS) as usize);
+
+        thread::scope(|s| {
+            for t in 0..(THREADS / 2) {
+                s.spawn(move |_| {
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert_eq!(i.to_string(), map.remove(&key(i)).unwrap());
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            map.len_approx(),
+            (THREADS / 2 * ELEMENTS_PER_THREADS) as usize
+        );
+
+        thread::scope(|s| {
+            for t in (THREADS / 2)..THREADS {
+                s.spawn(move |_| {
+                    let mut rng = rand::thread_rng();
+                    let mut keys: Vec<i32> =
+                        (0..ELEMENTS_PER_THREADS).map(|k| k * THREADS + t).collect();
+                    keys.shuffle(&mut rng);
+                    for i in keys {
+                        assert_eq!(i.to_string(), *map.get(&key(i)).unwrap());
+                    }
+                });
+            }
+        })
+        .unwrap();
+    }
+}
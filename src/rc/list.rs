@@ -0,0 +1,517 @@
+//! The Harris-Michael list with differential reference counting.
+//!
+//! Each node counts the links and the threads that refer to it (the internal count), and each link
+//! counts the threads that are acquiring a reference through it (the external count), next to the
+//! pointer. A thread acquires a reference to the target of a link by incrementing the external
+//! count, which keeps the target alive since the link refers to it, then incrementing the internal
+//! count of the target, and finally giving back its external count. A thread that replaces the
+//! pointer of a link moves the external count to the internal count of the old target, which makes
+//! up for the threads that could not give back theirs. A node is freed as soon as its internal
+//! count drops to zero, releasing its reference to the next node in turn.
+
+use super::concurrent_map::ConcurrentMap;
+use crate::drop_cost;
+use crate::op_stats;
+use crate::reclaim;
+use crate::restarts;
+use crate::striped_counter::StripedCounter;
+use crate::tagged::dwcas;
+
+use std::cell::UnsafeCell;
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+/// Marks the link of a removed node.
+const MARK: usize = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Word {
+    /// Possibly marked.
+    ptr: usize,
+    /// The external count.
+    count: u32,
+    /// Incremented when the pointer is replaced, so that a thread gives back its external count
+    /// only to the link it took it from. Marking doesn't replace the pointer.
+    version: u32,
+}
+
+impl Word {
+    fn pack(self) -> u128 {
+        (self.version as u128) << 96 | (self.count as u128) << 64 | self.ptr as u128
+    }
+
+    fn unpack(word: u128) -> Self {
+        Self {
+            ptr: word as usize,
+            count: (word >> 64) as u32,
+            version: (word >> 96) as u32,
+        }
+    }
+}
+
+#[repr(align(16))]
+struct Link {
+    inner: UnsafeCell<u128>,
+}
+
+unsafe impl Send for Link {}
+unsafe impl Sync for Link {}
+
+impl Link {
+    fn new(ptr: usize) -> Self {
+        Self {
+            inner: UnsafeCell::new(
+                Word {
+                    ptr,
+                    count: 0,
+                    version: 0,
+                }
+                .pack(),
+            ),
+        }
+    }
+
+    #[inline]
+    fn load(&self) -> Word {
+        // A CAS that replaces the word with itself if it is zero, which doesn't change it either way.
+        Word::unpack(unsafe { dwcas(self.inner.get(), 0, 0) })
+    }
+
+    #[inline]
+    fn cas(&self, current: Word, new: Word) -> Result<(), Word> {
+        let prev = unsafe { dwcas(self.inner.get(), current.pack(), new.pack()) };
+        if prev == current.pack() {
+            Ok(())
+        } else {
+            Err(Word::unpack(prev))
+        }
+    }
+
+    /// Points the link of a node that is not shared yet to `ptr`, and returns the old pointer.
+    unsafe fn set(&self, ptr: usize) -> usize {
+        let old = Word::unpack(*self.inner.get());
+        *self.inner.get() = Word { ptr, ..old }.pack();
+        old.ptr
+    }
+
+    /// Replaces the unmarked pointer `expected` with `new`, to which the caller has added a
+    /// reference for the link.
+    fn replace<K, V>(&self, expected: usize, new: usize) -> bool {
+        let mut current = self.load();
+        loop {
+            if current.ptr != expected {
+                return false;
+            }
+            let replaced = Word {
+                ptr: new,
+                count: 0,
+                version: current.version.wrapping_add(1),
+            };
+            match self.cas(current, replaced) {
+                Ok(()) => break,
+                Err(word) => current = word,
+            }
+        }
+        // Move the external count to the old target, and drop the reference of the link.
+        unsafe {
+            if current.count > 0 {
+                node::<K, V>(expected)
+                    .refs
+                    .fetch_add(current.count as usize - 1, Ordering::Relaxed);
+            } else {
+                release::<K, V>(expected);
+            }
+        }
+        true
+    }
+
+    /// Marks the link, and returns the unmarked pointer. Fails if it is already marked.
+    fn mark(&self) -> Result<usize, ()> {
+        let mut current = self.load();
+        loop {
+            if current.ptr & MARK != 0 {
+                return Err(());
+            }
+            let marked = Word {
+                ptr: current.ptr | MARK,
+                ..current
+            };
+            match self.cas(current, marked) {
+                Ok(()) => return Ok(current.ptr),
+                Err(word) => current = word,
+            }
+        }
+    }
+}
+
+struct Node<K, V> {
+    refs: AtomicUsize,
+    next: Link,
+    key: K,
+    value: V,
+}
+
+unsafe fn node<'a, K, V>(ptr: usize) -> &'a Node<K, V> {
+    &*(ptr as *const Node<K, V>)
+}
+
+/// Adds a reference to the node at `ptr`, if any.
+unsafe fn retain<K, V>(ptr: usize) {
+    if ptr != 0 {
+        node::<K, V>(ptr).refs.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Drops a reference to the node at `ptr`, if any, freeing the nodes that are no longer referred
+/// to.
+unsafe fn release<K, V>(mut ptr: usize) {
+    while ptr != 0 {
+        if node::<K, V>(ptr).refs.fetch_sub(1, Ordering::Release) != 1 {
+            return;
+        }
+        fence(Ordering::Acquire);
+        let next = node::<K, V>(ptr).next.load().ptr & !MARK;
+        if reclaim::enabled() {
+            drop_cost::pay();
+            drop(Box::from_raw(ptr as *mut Node<K, V>));
+        } else {
+            reclaim::keep(ptr);
+        }
+        ptr = next;
+    }
+}
+
+/// A reference to a node, or to no node.
+pub struct Ref<K, V> {
+    ptr: usize,
+    _marker: PhantomData<Box<Node<K, V>>>,
+}
+
+impl<K, V> Ref<K, V> {
+    fn null() -> Self {
+        Self {
+            ptr: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Acquires a reference to the target of `link`, and returns whether the link is marked.
+    fn acquire(link: &Link) -> (Self, bool) {
+        let mut current = link.load();
+        let borrowed = loop {
+            if current.ptr & !MARK == 0 {
+                return (Self::null(), current.ptr & MARK != 0);
+            }
+            let borrowed = Word {
+                count: current.count + 1,
+                ..current
+            };
+            match link.cas(current, borrowed) {
+                Ok(()) => break borrowed,
+                Err(word) => current = word,
+            }
+        };
+        let ptr = borrowed.ptr & !MARK;
+        unsafe { retain::<K, V>(ptr) };
+
+        // Give back the external count, unless the pointer was replaced in the meantime and the
+        // count was moved to the node.
+        current = borrowed;
+        loop {
+            let returned = Word {
+                count: current.count - 1,
+                ..current
+            };
+            match link.cas(current, returned) {
+                Ok(()) => break,
+                Err(word) if word.version != borrowed.version => {
+                    unsafe { release::<K, V>(ptr) };
+                    break;
+                }
+                Err(word) => current = word,
+            }
+        }
+        (
+            Self {
+                ptr,
+                _marker: PhantomData,
+            },
+            borrowed.ptr & MARK != 0,
+        )
+    }
+
+    fn node(&self) -> Option<&Node<K, V>> {
+        unsafe { (self.ptr as *const Node<K, V>).as_ref() }
+    }
+}
+
+impl<K, V> Drop for Ref<K, V> {
+    fn drop(&mut self) {
+        unsafe { release::<K, V>(self.ptr) };
+    }
+}
+
+impl<K, V> Deref for Ref<K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.node().unwrap().value
+    }
+}
+
+pub struct List<K, V> {
+    head: Link,
+    len: StripedCounter,
+    _marker: PhantomData<Box<Node<K, V>>>,
+}
+
+impl<K, V> Default for List<K, V>
+where
+    K: Ord,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for List<K, V> {
+    fn drop(&mut self) {
+        unsafe { release::<K, V>(self.head.load().ptr) };
+    }
+}
+
+impl<K, V> List<K, V>
+where
+    K: Ord,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            head: Link::new(0),
+            len: StripedCounter::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The link to the node `prev`, or the head.
+    fn link<'a>(&'a self, prev: &'a Ref<K, V>) -> &'a Link {
+        prev.node().map_or(&self.head, |node| &node.next)
+    }
+
+    /// Returns whether `key` is found, a reference to the last node whose key is less than `key`
+    /// (null if none) and a reference to the next node. Unlinks the removed nodes on the way one by
+    /// one.
+    fn find(&self, key: &K) -> (bool, Ref<K, V>, Ref<K, V>) {
+        'retry: loop {
+            let mut prev = Ref::<K, V>::null();
+            let mut curr = Ref::<K, V>::acquire(&self.head).0;
+            loop {
+                let curr_node = some_or!(curr.node(), return (false, prev, curr));
+                let (next, marked) = Ref::acquire(&curr_node.next);
+                if marked {
+                    unsafe { retain::<K, V>(next.ptr) };
+                    if !self.link(&prev).replace::<K, V>(curr.ptr, next.ptr) {
+                        unsafe { release::<K, V>(next.ptr) };
+                        restarts::record();
+                        continue 'retry;
+                    }
+                    op_stats::retire();
+                    curr = next;
+                    continue;
+                }
+                match curr_node.key.cmp(key) {
+                    Less => {
+                        prev = curr;
+                        curr = next;
+                    }
+                    Equal => return (true, prev, curr),
+                    Greater => return (false, prev, curr),
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<Ref<K, V>> {
+        let (found, _, curr) = self.find(key);
+        if found {
+            Some(curr)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) -> bool {
+        // The reference of this thread.
+        let new = Box::into_raw(Box::new(Node {
+            refs: AtomicUsize::new(1),
+            next: Link::new(0),
+            key,
+            value,
+        })) as usize;
+        let new_node = unsafe { node::<K, V>(new) };
+        loop {
+            let (found, prev, curr) = self.find(&new_node.key);
+            if found {
+                unsafe { release::<K, V>(new) };
+                return false;
+            }
+            unsafe {
+                retain::<K, V>(curr.ptr);
+                release::<K, V>(new_node.next.set(curr.ptr));
+                retain::<K, V>(new);
+            }
+            if self.link(&prev).replace::<K, V>(curr.ptr, new) {
+                unsafe { release::<K, V>(new) };
+                return true;
+            }
+            unsafe { release::<K, V>(new) };
+            restarts::record();
+        }
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        loop {
+            let (found, prev, curr) = self.find(key);
+            if !found {
+                return None;
+            }
+            let curr_node = curr.node().unwrap();
+            let next = match curr_node.next.mark() {
+                Ok(next) => next,
+                Err(()) => {
+                    restarts::record();
+                    continue;
+                }
+            };
+
+            let value = curr_node.value.clone();
+            unsafe { retain::<K, V>(next) };
+            if self.link(&prev).replace::<K, V>(curr.ptr, next) {
+                op_stats::retire();
+            } else {
+                unsafe { release::<K, V>(next) };
+                // Let `find` unlink it.
+                self.find(key);
+            }
+            return Some(value);
+        }
+    }
+}
+
+pub struct HMList<K, V> {
+    inner: List<K, V>,
+}
+
+impl<K, V> ConcurrentMap<K, V> for HMList<K, V>
+where
+    K: Ord,
+    V: Clone,
+{
+    type Ref = Ref<K, V>;
+
+    fn new() -> Self {
+        HMList { inner: List::new() }
+    }
+
+    #[inline]
+    fn get(&self, key: &K) -> Option<Self::Ref> {
+        self.inner.get(key)
+    }
+    #[inline]
+    fn insert(&self, key: K, value: V) -> bool {
+        let inserted = self.inner.insert(key, value);
+        if inserted {
+            self.inner.len.add(1);
+        }
+        inserted
+    }
+    #[inline]
+    fn remove(&self, key: &K) -> Option<V> {
+        let removed = self.inner.remove(key);
+        if removed.is_some() {
+            self.inner.len.add(-1);
+        }
+        removed
+    }
+    #[inline]
+    fn len_approx(&self) -> usize {
+        self.inner.len.approx()
+    }
+}
+
+register_maps! {
+    rc;
+    HMList => HMList, Decreasing;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HMList;
+    use crate::rc::concurrent_map::{self, ConcurrentMap};
+    use crate::tagged;
+    use crossbeam_utils::thread;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn smoke_hm_list() {
+        if !tagged::dwcas_available() {
+            return;
+        }
+        concurrent_map::tests::smoke::<HMList<i32, String>>();
+    }
+
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Counted {
+        fn new() -> Self {
+            LIVE.fetch_add(1, Ordering::Relaxed);
+            Counted
+        }
+    }
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            Self::new()
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            LIVE.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn frees_nodes() {
+        if !tagged::dwcas_available() {
+            return;
+        }
+        let map = &HMList::new();
+        thread::scope(|s| {
+            for t in 0..8 {
+                s.spawn(move |_| {
+                    for i in 0..10000 {
+                        let key = (i * 7 + t) % 64;
+                        if i % 2 == 0 {
+                            map.insert(key, Counted::new());
+                        } else {
+                            map.remove(&key);
+                        }
+                        map.get(&((key + 1) % 64));
+                    }
+                });
+            }
+        })
+        .unwrap();
+        for key in 0..64 {
+            map.remove(&key);
+        }
+        assert_eq!(map.len_approx(), 0);
+        assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod concurrent_map;
+
+pub mod list;
+
+pub use self::concurrent_map::ConcurrentMap;
+
+pub use self::list::HMList;
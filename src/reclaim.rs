@@ -0,0 +1,113 @@
+//! The switch that turns off the freeing of retired objects, for every reclamation scheme.
+//!
+//! The data structures retire their nodes through `Retire::retire` instead of `defer_destroy`.
+//! While reclamation is off, a retired object is appended to a thread-local list that is never
+//! collected, and everything else (pinning, shields, reference counts, the deferred functions of
+//! the descriptors) runs as usual. Comparing a run with reclamation off to the same run with it on
+//! separates the overhead of the protocol of a scheme from the cost of actually freeing memory.
+//! Under `-m STW`, the EBR structures retire their nodes to the collector of `stw` instead.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFF: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The addresses of the objects the thread retired while reclamation was off. The objects are
+    /// leaked when the thread exits.
+    static KEPT: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+}
+
+/// Turns off reclamation for the objects retired afterwards.
+pub fn disable() {
+    OFF.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    !OFF.load(Ordering::Relaxed)
+}
+
+/// Keeps the retired object at `ptr` forever.
+#[inline]
+pub fn keep(ptr: usize) {
+    // Objects retired while the thread exits are just leaked.
+    let _ = KEPT.try_with(|k| k.borrow_mut().push(ptr));
+}
+
+/// Destroys the retired object at `ptr` after paying the cost of `drop_cost`.
+pub(crate) unsafe fn destroy<T>(ptr: usize) {
+    crate::drop_cost::pay();
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+/// Retiring that honors the switch, for the EBR structures.
+pub mod ebr {
+    use crate::drop_cost;
+    use crate::epoch_latency;
+    use crate::stw;
+    use crossbeam_ebr::{Guard, Shared};
+
+    pub trait Retire {
+        /// Like `defer_destroy`, but keeps `ptr` forever if reclamation is off.
+        ///
+        /// # Safety
+        ///
+        /// The same as `defer_destroy`.
+        unsafe fn retire<T>(&self, ptr: Shared<'_, T>);
+    }
+
+    impl Retire for Guard {
+        #[inline]
+        unsafe fn retire<T>(&self, ptr: Shared<'_, T>) {
+            if super::enabled() {
+                if stw::enabled() {
+                    stw::retire(ptr.as_raw() as usize, super::destroy::<T>);
+                    return;
+                }
+                if drop_cost::cost() > 0 {
+                    let ptr = ptr.as_raw() as usize;
+                    self.defer_unchecked(move || super::destroy::<T>(ptr));
+                } else {
+                    self.defer_destroy(ptr);
+                }
+                epoch_latency::ebr::after_retire(self);
+            } else {
+                super::keep(ptr.as_raw() as usize);
+            }
+        }
+    }
+}
+
+/// Retiring that honors the switch, for the PEBR structures.
+pub mod pebr {
+    use crate::drop_cost;
+    use crate::epoch_latency;
+    use crossbeam_pebr::{Guard, Shared};
+
+    pub trait Retire {
+        /// Like `defer_destroy`, but keeps `ptr` forever if reclamation is off.
+        ///
+        /// # Safety
+        ///
+        /// The same as `defer_destroy`.
+        unsafe fn retire<T>(&self, ptr: Shared<'_, T>);
+    }
+
+    impl Retire for Guard {
+        #[inline]
+        unsafe fn retire<T>(&self, ptr: Shared<'_, T>) {
+            if super::enabled() {
+                if drop_cost::cost() > 0 {
+                    let ptr = ptr.as_raw() as usize;
+                    self.defer_unchecked(move || super::destroy::<T>(ptr));
+                } else {
+                    self.defer_destroy(ptr);
+                }
+                epoch_latency::pebr::after_retire(self);
+            } else {
+                super::keep(ptr.as_raw() as usize);
+            }
+        }
+    }
+}
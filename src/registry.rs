@@ -0,0 +1,724 @@
+//! The registry of the data structures of this crate, by name.
+//!
+//! The driver benchmarks a structure with one of a few generic functions per memory manager and
+//! per workload, e.g., a map as a map, as a set or with `SlowKey`s. Rather than matching the name
+//! of every structure for every combination, it implements `Driver`, whose generic methods wrap
+//! those functions, and each module registers its ports, which instantiate the methods with the
+//! type of each of its structures. The modules of maps do so with `register_maps!`. `Registry::new`
+//! collects the registrations of the modules that are compiled in, so that a new map of a
+//! registered module needs only a line in the `register_maps!` of its module, and the driver
+//! accepts the names of the registered structures only. The line also registers the map for the
+//! smoke test of all the registered ports, below.
+
+#[cfg(feature = "lists")]
+use crate::rc;
+use crate::slow_key::SlowKey;
+use crate::{ebr, pebr};
+
+/// The order in which the keys are inserted before a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefill {
+    Random,
+    /// So that each insert into a sorted list is at its head.
+    Decreasing,
+}
+
+/// What a registered structure is, for the options that only some structures support.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A map of this crate, which the driver runs with its map workload.
+    Map,
+    /// A map whose workload runs its threads itself: `ShardedHashMap` pins the collectors of its
+    /// shards, and `Ffi` reclaims its own memory.
+    Standalone,
+    /// A map of another crate, behind the `ConcurrentMap` adapter of its feature.
+    External,
+    /// A structure other than a map, with a workload of its own.
+    Other,
+}
+
+/// The reclamation scheme of a port of a map. NR runs the EBR ports without reclamation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    EBR,
+    PEBR,
+    RC,
+}
+
+/// Benchmarks a structure, given its type.
+pub trait Driver {
+    type Value: Clone + PartialEq + Send + Sync + 'static;
+    type Output;
+
+    fn ebr_map<M: ebr::ConcurrentMap<String, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    fn ebr_set<S: ebr::ConcurrentSet<String> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    fn ebr_slow_key<M: ebr::ConcurrentMap<SlowKey, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+
+    fn pebr_map<M: pebr::ConcurrentMap<String, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    fn pebr_set<S: pebr::ConcurrentSet<String> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    fn pebr_slow_key<M: pebr::ConcurrentMap<SlowKey, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+
+    #[cfg(feature = "lists")]
+    fn rc_map<M: rc::ConcurrentMap<String, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    #[cfg(feature = "lists")]
+    fn rc_slow_key<M: rc::ConcurrentMap<SlowKey, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+
+    /// The map workload with the reads of `--scan`.
+    fn ebr_scan<M: ebr::OrderedMap<String, String> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    fn pebr_scan<M: pebr::OrderedMap<String, String> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    /// The map workload with the reads of `--unguarded-reads`.
+    fn ebr_unguarded<M: ebr::UnguardedMap<String, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+    /// The workload of `--transfers`, on two instances of the map.
+    #[cfg(feature = "trees")]
+    fn ebr_transfers<M: ebr::TransferMap<String, Self::Value> + Send + Sync>(
+        &self,
+        prefill: Prefill,
+    ) -> Self::Output;
+
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map(&self, prefill: Prefill) -> Self::Output;
+    #[cfg(feature = "hash-maps")]
+    fn sharded_hash_map_slow_key(&self, prefill: Prefill) -> Self::Output;
+    fn ffi_map(&self, prefill: Prefill) -> Self::Output;
+
+    // The structures other than maps, each with a workload of its own.
+    #[cfg(feature = "queues")]
+    fn ebr_bag<B: ebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn pebr_bag<B: pebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn ebr_priority_queue<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+        &self,
+    ) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn pebr_priority_queue<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+        &self,
+    ) -> Self::Output;
+    #[cfg(feature = "counters")]
+    fn ebr_counter<C: ebr::ConcurrentCounter + Send + Sync>(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn ebr_mpsc_queue(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn pebr_mpsc_queue(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn ebr_deque(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn ebr_pipeline(&self) -> Self::Output;
+    #[cfg(feature = "queues")]
+    fn ebr_queue_map(&self) -> Self::Output;
+    #[cfg(feature = "lists")]
+    fn ebr_interval_map(&self) -> Self::Output;
+    #[cfg(feature = "hash-maps")]
+    fn ebr_bitmap(&self) -> Self::Output;
+    #[cfg(feature = "counters")]
+    fn ebr_config_map(&self) -> Self::Output;
+}
+
+/// A benchmark of a port of a structure.
+pub type Run<D> = fn(&D, Prefill) -> <D as Driver>::Output;
+
+/// The benchmarks of a port of a structure. Each of the options is `None` if the port does not
+/// support its workload.
+pub struct Port<D: Driver> {
+    /// The map workload, or the workload of a structure other than a map.
+    pub map: Run<D>,
+    pub set: Option<Run<D>>,
+    pub slow_key: Option<Run<D>>,
+    pub scan: Option<Run<D>>,
+    pub unguarded: Option<Run<D>>,
+    pub transfers: Option<Run<D>>,
+    /// The map with its nodes marked by CAS (`--mark cas`).
+    pub cas_mark: Option<Run<D>>,
+    /// The map with its removes made compare-and-deletes (`--compare-and-delete`).
+    pub compare_and_delete: Option<Run<D>>,
+}
+
+impl<D: Driver> Port<D> {
+    /// A port with only the workload `map`.
+    pub fn new(map: Run<D>) -> Self {
+        Self {
+            map,
+            set: None,
+            slow_key: None,
+            scan: None,
+            unguarded: None,
+            transfers: None,
+            cas_mark: None,
+            compare_and_delete: None,
+        }
+    }
+}
+
+/// A registered structure, with its ports.
+pub struct Entry<D: Driver> {
+    pub name: &'static str,
+    pub kind: Kind,
+    /// The order of the prefill of a map.
+    pub prefill: Prefill,
+    ebr: Option<Port<D>>,
+    pebr: Option<Port<D>>,
+    rc: Option<Port<D>>,
+}
+
+impl<D: Driver> Entry<D> {
+    pub fn port(&self, scheme: Scheme) -> Option<&Port<D>> {
+        match scheme {
+            Scheme::EBR => self.ebr.as_ref(),
+            Scheme::PEBR => self.pebr.as_ref(),
+            Scheme::RC => self.rc.as_ref(),
+        }
+    }
+}
+
+pub struct Registry<D: Driver> {
+    entries: Vec<Entry<D>>,
+}
+
+impl<D: Driver> Default for Registry<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: Driver> Registry<D> {
+    /// Creates a registry of the structures of the families that are compiled in.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            entries: Vec::new(),
+        };
+        #[cfg(feature = "lists")]
+        {
+            ebr::list::register(&mut registry);
+            ebr::marked_list::register(&mut registry);
+            ebr::unrolled_list::register(&mut registry);
+            ebr::skip_list::register(&mut registry);
+            ebr::interval_map::register(&mut registry);
+            pebr::list::register(&mut registry);
+            pebr::marked_list::register(&mut registry);
+            pebr::unrolled_list::register(&mut registry);
+            rc::list::register(&mut registry);
+        }
+        #[cfg(feature = "hash-maps")]
+        {
+            ebr::michael_hash_map::register(&mut registry);
+            ebr::sharded_hash_map::register(&mut registry);
+            ebr::hash_tree_map::register(&mut registry);
+            ebr::cuckoo_hash_map::register(&mut registry);
+            ebr::bitmap::register(&mut registry);
+            pebr::michael_hash_map::register(&mut registry);
+            pebr::hash_tree_map::register(&mut registry);
+        }
+        #[cfg(feature = "trees")]
+        {
+            ebr::natarajan_mittal_tree::register(&mut registry);
+            ebr::art::register(&mut registry);
+            ebr::bonsai_tree::register(&mut registry);
+            ebr::kcas_tree::register(&mut registry);
+            pebr::natarajan_mittal_tree::register(&mut registry);
+            pebr::bonsai_tree::register(&mut registry);
+            pebr::kcas_tree::register(&mut registry);
+        }
+        #[cfg(feature = "queues")]
+        {
+            ebr::bag::register(&mut registry);
+            ebr::mdlist::register(&mut registry);
+            ebr::mpsc_queue::register(&mut registry);
+            ebr::deque::register(&mut registry);
+            ebr::queue_map::register(&mut registry);
+            pebr::bag::register(&mut registry);
+            pebr::mdlist::register(&mut registry);
+            pebr::mpsc_queue::register(&mut registry);
+        }
+        #[cfg(feature = "counters")]
+        {
+            ebr::concurrent_counter::register(&mut registry);
+            ebr::combining_tree::register(&mut registry);
+            ebr::config_map::register(&mut registry);
+        }
+        #[cfg(feature = "dashmap")]
+        ebr::external::register(&mut registry);
+        crate::ffi::register(&mut registry);
+        registry
+    }
+
+    /// Registers the port to `scheme` of the structure `name`.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        kind: Kind,
+        prefill: Prefill,
+        scheme: Scheme,
+        port: Port<D>,
+    ) {
+        let index = match self.entries.iter().position(|e| e.name == name) {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry {
+                    name,
+                    kind,
+                    prefill,
+                    ebr: None,
+                    pebr: None,
+                    rc: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        assert_eq!(entry.kind, kind, "{} has two kinds", name);
+        assert_eq!(entry.prefill, prefill, "{} has two prefills", name);
+        let slot = match scheme {
+            Scheme::EBR => &mut entry.ebr,
+            Scheme::PEBR => &mut entry.pebr,
+            Scheme::RC => &mut entry.rc,
+        };
+        assert!(slot.is_none(), "{} has two {:?} ports", name, scheme);
+        *slot = Some(port);
+    }
+
+    /// Registers the port to `scheme` of `name`, a structure other than a map, whose workload
+    /// `run` prefills it itself.
+    pub fn register_other(&mut self, name: &'static str, scheme: Scheme, run: Run<D>) {
+        self.register(name, Kind::Other, Prefill::Random, scheme, Port::new(run));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Entry<D>> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    pub fn entries(&self) -> &[Entry<D>] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Driver, Kind, Prefill, Registry, Scheme};
+    #[cfg(feature = "lists")]
+    use crate::rc;
+    use crate::slow_key::SlowKey;
+    use crate::{ebr, pebr, tagged};
+    use std::any::type_name;
+
+    /// Returns the method and the type it was called with.
+    struct Names;
+
+    impl Driver for Names {
+        type Value = String;
+        type Output = (&'static str, &'static str, Prefill);
+
+        fn ebr_map<M: ebr::ConcurrentMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_map", type_name::<M>(), prefill)
+        }
+        fn ebr_set<S: ebr::ConcurrentSet<String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_set", type_name::<S>(), prefill)
+        }
+        fn ebr_slow_key<M: ebr::ConcurrentMap<SlowKey, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_slow_key", type_name::<M>(), prefill)
+        }
+        fn pebr_map<M: pebr::ConcurrentMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("pebr_map", type_name::<M>(), prefill)
+        }
+        fn pebr_set<S: pebr::ConcurrentSet<String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("pebr_set", type_name::<S>(), prefill)
+        }
+        fn pebr_slow_key<M: pebr::ConcurrentMap<SlowKey, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("pebr_slow_key", type_name::<M>(), prefill)
+        }
+        #[cfg(feature = "lists")]
+        fn rc_map<M: rc::ConcurrentMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("rc_map", type_name::<M>(), prefill)
+        }
+        #[cfg(feature = "lists")]
+        fn rc_slow_key<M: rc::ConcurrentMap<SlowKey, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("rc_slow_key", type_name::<M>(), prefill)
+        }
+        fn ebr_scan<M: ebr::OrderedMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_scan", type_name::<M>(), prefill)
+        }
+        fn pebr_scan<M: pebr::OrderedMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("pebr_scan", type_name::<M>(), prefill)
+        }
+        fn ebr_unguarded<M: ebr::UnguardedMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_unguarded", type_name::<M>(), prefill)
+        }
+        #[cfg(feature = "trees")]
+        fn ebr_transfers<M: ebr::TransferMap<String, String> + Send + Sync>(
+            &self,
+            prefill: Prefill,
+        ) -> Self::Output {
+            ("ebr_transfers", type_name::<M>(), prefill)
+        }
+        #[cfg(feature = "hash-maps")]
+        fn sharded_hash_map(&self, prefill: Prefill) -> Self::Output {
+            ("sharded_hash_map", "", prefill)
+        }
+        #[cfg(feature = "hash-maps")]
+        fn sharded_hash_map_slow_key(&self, prefill: Prefill) -> Self::Output {
+            ("sharded_hash_map_slow_key", "", prefill)
+        }
+        fn ffi_map(&self, prefill: Prefill) -> Self::Output {
+            ("ffi_map", "", prefill)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_bag<B: ebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output {
+            ("ebr_bag", type_name::<B>(), Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn pebr_bag<B: pebr::ConcurrentBag<String> + Send + Sync>(&self) -> Self::Output {
+            ("pebr_bag", type_name::<B>(), Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_priority_queue<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+            &self,
+        ) -> Self::Output {
+            ("ebr_priority_queue", type_name::<Q>(), Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn pebr_priority_queue<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync>(
+            &self,
+        ) -> Self::Output {
+            ("pebr_priority_queue", type_name::<Q>(), Prefill::Random)
+        }
+        #[cfg(feature = "counters")]
+        fn ebr_counter<C: ebr::ConcurrentCounter + Send + Sync>(&self) -> Self::Output {
+            ("ebr_counter", type_name::<C>(), Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_mpsc_queue(&self) -> Self::Output {
+            ("ebr_mpsc_queue", "", Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn pebr_mpsc_queue(&self) -> Self::Output {
+            ("pebr_mpsc_queue", "", Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_deque(&self) -> Self::Output {
+            ("ebr_deque", "", Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_pipeline(&self) -> Self::Output {
+            ("ebr_pipeline", "", Prefill::Random)
+        }
+        #[cfg(feature = "queues")]
+        fn ebr_queue_map(&self) -> Self::Output {
+            ("ebr_queue_map", "", Prefill::Random)
+        }
+        #[cfg(feature = "lists")]
+        fn ebr_interval_map(&self) -> Self::Output {
+            ("ebr_interval_map", "", Prefill::Random)
+        }
+        #[cfg(feature = "hash-maps")]
+        fn ebr_bitmap(&self) -> Self::Output {
+            ("ebr_bitmap", "", Prefill::Random)
+        }
+        #[cfg(feature = "counters")]
+        fn ebr_config_map(&self) -> Self::Output {
+            ("ebr_config_map", "", Prefill::Random)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "lists")]
+    fn lists() {
+        let registry = Registry::<Names>::new();
+        let entry = registry.get("HMList").unwrap();
+        assert_eq!(entry.prefill, Prefill::Decreasing);
+
+        let ebr = entry.port(Scheme::EBR).unwrap();
+        let (method, ty, prefill) = (ebr.map)(&Names, entry.prefill);
+        assert_eq!(method, "ebr_map");
+        assert!(ty.contains("ebr::list::HMList<alloc::string::String"));
+        assert_eq!(prefill, Prefill::Decreasing);
+        let (method, ty, _) = (ebr.set.unwrap())(&Names, entry.prefill);
+        assert_eq!(method, "ebr_set");
+        assert!(ty.contains("MapSet<pebr_benchmark::ebr::list::HMList"));
+        let (method, ty, _) = (ebr.slow_key.unwrap())(&Names, entry.prefill);
+        assert_eq!(method, "ebr_slow_key");
+        assert!(ty.contains("HMList<pebr_benchmark::slow_key::SlowKey"));
+
+        let (method, ty, _) = (entry.port(Scheme::PEBR).unwrap().map)(&Names, entry.prefill);
+        assert_eq!(method, "pebr_map");
+        assert!(ty.contains("pebr::list::HMList"));
+
+        let rc = entry.port(Scheme::RC).unwrap();
+        assert!(rc.set.is_none());
+        assert_eq!((rc.map)(&Names, entry.prefill).0, "rc_map");
+        assert_eq!(
+            (rc.slow_key.unwrap())(&Names, entry.prefill).0,
+            "rc_slow_key"
+        );
+
+        // The skiplist has no PEBR port.
+        let entry = registry.get("SkipList").unwrap();
+        assert_eq!(entry.prefill, Prefill::Random);
+        assert!(entry.port(Scheme::EBR).is_some());
+        assert!(entry.port(Scheme::PEBR).is_none());
+        assert!(entry.port(Scheme::RC).is_none());
+    }
+
+    /// The workloads of the Harris lists other than the map.
+    #[test]
+    #[cfg(feature = "lists")]
+    fn list_workloads() {
+        let registry = Registry::<Names>::new();
+        let entry = registry.get("HMList").unwrap();
+        let ebr = entry.port(Scheme::EBR).unwrap();
+        let (method, ty, _) = (ebr.cas_mark.unwrap())(&Names, entry.prefill);
+        assert_eq!(method, "ebr_map");
+        assert!(ty.contains("pebr_benchmark::tagged::CasMark>"));
+        let (method, ty, _) = (ebr.compare_and_delete.unwrap())(&Names, entry.prefill);
+        assert_eq!(method, "ebr_map");
+        assert!(ty.contains("CompareAndDelete<pebr_benchmark::ebr::list::HMList"));
+        assert_eq!((ebr.scan.unwrap())(&Names, entry.prefill).0, "ebr_scan");
+        assert!(ebr.unguarded.is_none());
+        let hhs = registry.get("HHSList").unwrap();
+        assert!(hhs.port(Scheme::EBR).unwrap().unguarded.is_some());
+        assert!(hhs.port(Scheme::PEBR).unwrap().unguarded.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "trees")]
+    fn trees() {
+        let registry = Registry::<Names>::new();
+        let entry = registry.get("NMTree").unwrap();
+        assert_eq!(entry.prefill, Prefill::Random);
+        let (method, ty, _) = (entry.port(Scheme::PEBR).unwrap().map)(&Names, entry.prefill);
+        assert_eq!(method, "pebr_map");
+        assert!(ty.contains("pebr::natarajan_mittal_tree::NMTreeMap"));
+
+        // ART compares the bytes of its keys.
+        let entry = registry.get("ART").unwrap();
+        let ebr = entry.port(Scheme::EBR).unwrap();
+        assert!(ebr.set.is_some());
+        assert!(ebr.slow_key.is_none());
+        assert!(entry.port(Scheme::PEBR).is_none());
+
+        let entry = registry.get("KCASTree").unwrap();
+        let ebr = entry.port(Scheme::EBR).unwrap();
+        let (method, ty, _) = (ebr.transfers.unwrap())(&Names, entry.prefill);
+        assert_eq!(method, "ebr_transfers");
+        assert!(ty.contains("kcas_tree::KCASTreeMap"));
+        assert!(entry.port(Scheme::PEBR).unwrap().transfers.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "queues")]
+    fn queues() {
+        let registry = Registry::<Names>::new();
+        let entry = registry.get("Bag").unwrap();
+        assert_eq!(entry.kind, Kind::Other);
+        let (method, ty, _) = (entry.port(Scheme::PEBR).unwrap().map)(&Names, entry.prefill);
+        assert_eq!(method, "pebr_bag");
+        assert!(ty.contains("pebr::bag::Bag<alloc::string::String>"));
+
+        // The deques and the pipeline have no PEBR port.
+        let entry = registry.get("Pipeline").unwrap();
+        let (method, _, _) = (entry.port(Scheme::EBR).unwrap().map)(&Names, entry.prefill);
+        assert_eq!(method, "ebr_pipeline");
+        assert!(entry.port(Scheme::PEBR).is_none());
+    }
+
+    #[test]
+    fn families() {
+        let registry = Registry::<Names>::new();
+        let names = registry
+            .entries()
+            .iter()
+            .map(|e| e.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names.contains(&"HList"), cfg!(feature = "lists"));
+        assert_eq!(names.contains(&"HashMap"), cfg!(feature = "hash-maps"));
+        assert_eq!(names.contains(&"KCASTree"), cfg!(feature = "trees"));
+        assert_eq!(names.contains(&"Bag"), cfg!(feature = "queues"));
+        assert_eq!(names.contains(&"ConfigMap"), cfg!(feature = "counters"));
+        assert_eq!(names.contains(&"DashMap"), cfg!(feature = "dashmap"));
+        let kinds = [
+            ("HashMap", Kind::Map),
+            ("ShardedHashMap", Kind::Standalone),
+            ("Ffi", Kind::Standalone),
+            ("DashMap", Kind::External),
+            ("IntervalMap", Kind::Other),
+            ("MpscQueue", Kind::Other),
+            ("StripedCounter", Kind::Other),
+        ];
+        for &(name, kind) in &kinds {
+            if let Some(entry) = registry.get(name) {
+                assert_eq!(entry.kind, kind, "{}", name);
+            }
+        }
+        assert!(registry.get("Ffi").is_some());
+    }
+
+    /// Smoke-tests the ports that it is given with the `smoke` tests of their interfaces, with
+    /// the keys made strings.
+    struct Smoke;
+
+    impl Driver for Smoke {
+        type Value = String;
+        type Output = ();
+
+        fn ebr_map<M: ebr::ConcurrentMap<String, String> + Send + Sync>(&self, _: Prefill) {
+            ebr::concurrent_map::tests::smoke_keyed::<String, M>(|i| i.to_string());
+        }
+        fn ebr_set<S: ebr::ConcurrentSet<String> + Send + Sync>(&self, _: Prefill) {
+            ebr::concurrent_set::tests::smoke_keyed::<String, S>(|i| i.to_string());
+        }
+        fn ebr_slow_key<M: ebr::ConcurrentMap<SlowKey, String> + Send + Sync>(&self, _: Prefill) {
+            ebr::concurrent_map::tests::smoke_keyed::<SlowKey, M>(|i| i.to_string().into());
+        }
+        fn pebr_map<M: pebr::ConcurrentMap<String, String> + Send + Sync>(&self, _: Prefill) {
+            pebr::concurrent_map::tests::smoke_keyed::<String, M>(|i| i.to_string());
+        }
+        fn pebr_set<S: pebr::ConcurrentSet<String> + Send + Sync>(&self, _: Prefill) {
+            pebr::concurrent_set::tests::smoke_keyed::<String, S>(|i| i.to_string());
+        }
+        fn pebr_slow_key<M: pebr::ConcurrentMap<SlowKey, String> + Send + Sync>(&self, _: Prefill) {
+            pebr::concurrent_map::tests::smoke_keyed::<SlowKey, M>(|i| i.to_string().into());
+        }
+        #[cfg(feature = "lists")]
+        fn rc_map<M: rc::ConcurrentMap<String, String> + Send + Sync>(&self, _: Prefill) {
+            rc::concurrent_map::tests::smoke_keyed::<String, M>(|i| i.to_string());
+        }
+        #[cfg(feature = "lists")]
+        fn rc_slow_key<M: rc::ConcurrentMap<SlowKey, String> + Send + Sync>(&self, _: Prefill) {
+            rc::concurrent_map::tests::smoke_keyed::<SlowKey, M>(|i| i.to_string().into());
+        }
+        // The map of these ports is that of the map workload, which the test already runs.
+        fn ebr_scan<M: ebr::OrderedMap<String, String> + Send + Sync>(&self, _: Prefill) {}
+        fn pebr_scan<M: pebr::OrderedMap<String, String> + Send + Sync>(&self, _: Prefill) {}
+        fn ebr_unguarded<M: ebr::UnguardedMap<String, String> + Send + Sync>(&self, _: Prefill) {}
+        #[cfg(feature = "trees")]
+        fn ebr_transfers<M: ebr::TransferMap<String, String> + Send + Sync>(&self, _: Prefill) {}
+
+        // The other structures are tested in their modules.
+        #[cfg(feature = "hash-maps")]
+        fn sharded_hash_map(&self, _: Prefill) {}
+        #[cfg(feature = "hash-maps")]
+        fn sharded_hash_map_slow_key(&self, _: Prefill) {}
+        fn ffi_map(&self, _: Prefill) {}
+        #[cfg(feature = "queues")]
+        fn ebr_bag<B: ebr::ConcurrentBag<String> + Send + Sync>(&self) {}
+        #[cfg(feature = "queues")]
+        fn pebr_bag<B: pebr::ConcurrentBag<String> + Send + Sync>(&self) {}
+        #[cfg(feature = "queues")]
+        fn ebr_priority_queue<Q: ebr::ConcurrentPriorityQueue<String> + Send + Sync>(&self) {}
+        #[cfg(feature = "queues")]
+        fn pebr_priority_queue<Q: pebr::ConcurrentPriorityQueue<String> + Send + Sync>(&self) {}
+        #[cfg(feature = "counters")]
+        fn ebr_counter<C: ebr::ConcurrentCounter + Send + Sync>(&self) {}
+        #[cfg(feature = "queues")]
+        fn ebr_mpsc_queue(&self) {}
+        #[cfg(feature = "queues")]
+        fn pebr_mpsc_queue(&self) {}
+        #[cfg(feature = "queues")]
+        fn ebr_deque(&self) {}
+        #[cfg(feature = "queues")]
+        fn ebr_pipeline(&self) {}
+        #[cfg(feature = "queues")]
+        fn ebr_queue_map(&self) {}
+        #[cfg(feature = "lists")]
+        fn ebr_interval_map(&self) {}
+        #[cfg(feature = "hash-maps")]
+        fn ebr_bitmap(&self) {}
+        #[cfg(feature = "counters")]
+        fn ebr_config_map(&self) {}
+    }
+
+    /// Smoke-tests every registered port, so that a new map gets the test with its registration.
+    #[test]
+    fn smoke() {
+        let registry = Registry::<Smoke>::new();
+        for entry in registry.entries() {
+            for &scheme in &[Scheme::EBR, Scheme::PEBR, Scheme::RC] {
+                // VersionList and the RC ports need a double-width CAS.
+                if (scheme == Scheme::RC || entry.name == "VersionList")
+                    && !tagged::dwcas_available()
+                {
+                    continue;
+                }
+                if let Some(port) = entry.port(scheme) {
+                    (port.map)(&Smoke, entry.prefill);
+                    let others = [
+                        port.set,
+                        port.slow_key,
+                        port.scan,
+                        port.unguarded,
+                        port.transfers,
+                        port.cas_mark,
+                        port.compare_and_delete,
+                    ];
+                    for other in others.iter().flatten() {
+                        other(&Smoke, entry.prefill);
+                    }
+                }
+            }
+        }
+    }
+}
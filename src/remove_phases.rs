@@ -0,0 +1,116 @@
+//! The time that the removes of the Harris lists and of the Natarajan-Mittal trees spend in each of
+//! their phases, so that a remove is not measured as a whole.
+//!
+//! A remove searches the node of its key, deletes it logically by marking it (flagging the edge to
+//! the leaf, in the trees), and deletes it physically by unlinking it, which in the trees may take a
+//! cleanup phase of further seeks and CASes. The timer of a remove adds the time since its previous
+//! lap to the phase that just ended: the search, including the restarts after another thread
+//! marked the node first and, under PEBR, after an ejection; the mark, including the failed
+//! injections of the trees and the help they give; and the unlink with its cleanup. A remove that
+//! does not find its key is a search only.
+//!
+//! When enabled, each thread sums the time of each phase and counts its removes, and the sums are
+//! merged into the global ones when the thread exits.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Search,
+    Mark,
+    Unlink,
+}
+
+impl Phase {
+    pub const PHASES: [Phase; 3] = [Phase::Search, Phase::Mark, Phase::Unlink];
+    pub const NAMES: [&'static str; 3] = ["search", "mark", "unlink"];
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NANOS: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static REMOVES: AtomicU64 = AtomicU64::new(0);
+static MARKED: AtomicU64 = AtomicU64::new(0);
+
+/// The time of the removes in each phase.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Stats {
+    /// The nanoseconds spent in each of `Phase::PHASES`.
+    pub nanos: [u64; 3],
+    pub removes: u64,
+    /// The removes that marked their node.
+    pub marked: u64,
+}
+
+struct Local(Stats);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        for (global, local) in NANOS.iter().zip(self.0.nanos.iter()) {
+            global.fetch_add(*local, Ordering::Relaxed);
+        }
+        REMOVES.fetch_add(self.0.removes, Ordering::Relaxed);
+        MARKED.fetch_add(self.0.marked, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(Stats::default()));
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// The timer of a remove, which does nothing unless enabled.
+pub struct Timer(Option<Instant>);
+
+impl Timer {
+    #[inline]
+    pub fn start() -> Self {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return Timer(None);
+        }
+        Timer(Some(Instant::now()))
+    }
+
+    /// Adds the time since the previous lap to `phase`.
+    #[inline]
+    pub fn lap(&mut self, phase: Phase) {
+        if let Some(last) = self.0 {
+            let now = Instant::now();
+            let nanos = (now - last).as_nanos() as u64;
+            LOCAL.with(|local| local.borrow_mut().0.nanos[phase as usize] += nanos);
+            self.0 = Some(now);
+        }
+    }
+
+    /// Adds the time since the previous lap to `phase`, and counts the remove, which marked its
+    /// node or not.
+    #[inline]
+    pub fn finish(mut self, phase: Phase, marked: bool) {
+        if self.0.is_none() {
+            return;
+        }
+        self.lap(phase);
+        LOCAL.with(|local| {
+            let stats = &mut local.borrow_mut().0;
+            stats.removes += 1;
+            stats.marked += marked as u64;
+        });
+    }
+}
+
+/// Takes the time recorded by the exited threads.
+pub fn take() -> Stats {
+    let mut nanos = [0; 3];
+    for (n, global) in nanos.iter_mut().zip(NANOS.iter()) {
+        *n = global.swap(0, Ordering::Relaxed);
+    }
+    Stats {
+        nanos,
+        removes: REMOVES.swap(0, Ordering::Relaxed),
+        marked: MARKED.swap(0, Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,109 @@
+//! Restarts of the operations of the maps, and the bounded-restart mode of the Harris lists.
+//!
+//! An operation restarts when a CAS fails or a traversal finds that its position was removed, and
+//! starts over from the root or the head. When enabled, each thread counts the restarts of its
+//! current operation, and the benchmark records the count in a per-thread histogram at the end of
+//! every operation. The histograms are merged into the global one when the threads exit.
+//!
+//! Under extreme contention, the traversals of the Harris lists (`HList`, `HMList`, `HHSList`,
+//! `AdaptiveList` and `HashMap`) may fail over and over. With a bound, a traversal that failed that
+//! many times in a row falls back to `find_validated`, which re-reads the link of the previous node
+//! before every step and resumes from there instead of from the head when a CAS fails.
+//!
+//! The validated traversal is Harris-Michael's traversal with an extra load of the previous link at
+//! each step, and with `validate_always` every traversal takes it, so that comparing a run of
+//! `HMList` with and without it measures the cost of the validation alone.
+
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Bucket 0 counts the operations without restarts, bucket `i > 0` those with `[2^(i-1), 2^i)`
+/// restarts, and the last bucket also counts the ones with more.
+pub const BUCKETS: usize = 16;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BOUND: AtomicUsize = AtomicUsize::new(usize::max_value());
+static HISTOGRAM: [AtomicUsize; BUCKETS] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+struct Local([usize; BUCKETS]);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        for (global, local) in HISTOGRAM.iter().zip(self.0.iter()) {
+            global.fetch_add(*local, Ordering::Relaxed);
+        }
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local([0; BUCKETS]));
+    static CURRENT: Cell<usize> = Cell::new(0);
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Makes the traversals of the Harris lists fall back to the validated traversal after `bound`
+/// consecutive failures.
+pub fn set_bound(bound: usize) {
+    assert!(bound > 0);
+    BOUND.store(bound, Ordering::Relaxed);
+}
+
+/// Makes every traversal of the Harris lists a validated one from the start, to measure what the
+/// validation costs.
+pub fn validate_always() {
+    BOUND.store(0, Ordering::Relaxed);
+}
+
+/// Whether a traversal that failed `failures` times in a row falls back to the validated one.
+#[inline]
+pub fn exhausted(failures: usize) -> bool {
+    failures >= BOUND.load(Ordering::Relaxed)
+}
+
+/// Records that the current operation restarts.
+#[inline]
+pub fn record() {
+    if ENABLED.load(Ordering::Relaxed) {
+        CURRENT.with(|c| c.set(c.get() + 1));
+    }
+}
+
+/// Records the restarts of the operation that the current thread has just finished.
+#[inline]
+pub fn finish_op() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let restarts = CURRENT.with(|c| c.replace(0));
+    let bucket = (0usize.leading_zeros() - restarts.leading_zeros()) as usize;
+    LOCAL.with(|local| local.borrow_mut().0[bucket.min(BUCKETS - 1)] += 1);
+}
+
+/// Takes the histogram recorded by the exited threads.
+pub fn take() -> [usize; BUCKETS] {
+    let mut histogram = [0; BUCKETS];
+    for (h, global) in histogram.iter_mut().zip(HISTOGRAM.iter()) {
+        *h = global.swap(0, Ordering::Relaxed);
+    }
+    histogram
+}
@@ -0,0 +1,264 @@
+//! Append-only store of benchmark results, and regression detection against it.
+//!
+//! The store is a JSON lines file where each line is a flat object holding the key of the
+//! configuration and the metrics of a run, e.g. `{"key":"ds=HList,mm=EBR","throughput":1234}`.
+//! Runs of the same configuration share the key, and the mean of the stored runs serves as the
+//! baseline of a new run. A record also holds the environment of the run as string fields, e.g.
+//! `"hostname":"bench1"`, which are not compared.
+
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The result of a run.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Record {
+    pub key: String,
+    /// The environment of the run, e.g. the host and the commit.
+    pub env: Vec<(String, String)>,
+    pub metrics: Vec<(String, f64)>,
+}
+
+impl Record {
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"key\":{}", quote(&self.key));
+        for (name, value) in &self.env {
+            json += &format!(",{}:{}", quote(name), quote(value));
+        }
+        for (name, value) in &self.metrics {
+            json += &format!(",{}:{}", quote(name), value);
+        }
+        json + "}"
+    }
+
+    /// Parses a line written by `to_json`.
+    pub fn from_json(line: &str) -> Option<Self> {
+        let mut parser = Parser(line.trim().chars().peekable());
+        parser.expect('{')?;
+        let mut key = None;
+        let mut env = Vec::new();
+        let mut metrics = Vec::new();
+        loop {
+            let name = parser.string()?;
+            parser.expect(':')?;
+            if name == "key" {
+                key = Some(parser.string()?);
+            } else if parser.0.peek() == Some(&'"') {
+                env.push((name, parser.string()?));
+            } else {
+                metrics.push((name, parser.number()?));
+            }
+            match parser.0.next()? {
+                ',' => continue,
+                '}' => break,
+                _ => return None,
+            }
+        }
+        if parser.0.next().is_some() {
+            return None;
+        }
+        Some(Record {
+            key: key?,
+            env,
+            metrics,
+        })
+    }
+
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        self.metrics
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| *v)
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = "\"".to_string();
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted + "\""
+}
+
+struct Parser<I: Iterator<Item = char>>(std::iter::Peekable<I>);
+
+impl<I: Iterator<Item = char>> Parser<I> {
+    fn expect(&mut self, c: char) -> Option<()> {
+        if self.0.next()? == c {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.0.next()? {
+                '"' => return Some(s),
+                '\\' => s.push(self.0.next()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        let mut s = String::new();
+        while let Some(&c) = self.0.peek() {
+            if c == ',' || c == '}' {
+                break;
+            }
+            s.push(c);
+            self.0.next();
+        }
+        s.parse().ok()
+    }
+}
+
+/// The results store in a directory.
+pub struct Store {
+    path: PathBuf,
+}
+
+impl Store {
+    const FILE_NAME: &'static str = "results.jsonl";
+
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        create_dir_all(dir)?;
+        Ok(Store {
+            path: dir.join(Self::FILE_NAME),
+        })
+    }
+
+    /// Loads the records of the configuration `key`. Malformed lines are skipped.
+    pub fn load(&self, key: &str) -> io::Result<Vec<Record>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Some(record) = Record::from_json(&line?) {
+                if record.key == key {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn append(&self, record: &Record) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record.to_json())
+    }
+}
+
+/// A metric that got worse than the baseline by more than the threshold.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Regression {
+    pub metric: String,
+    /// The mean of the baseline records.
+    pub baseline: f64,
+    pub value: f64,
+    /// The relative change in percent.
+    pub change: f64,
+}
+
+/// Compares the metrics of `record` with the mean of the `baseline` records. A metric regresses if
+/// it is worse by more than `threshold` percent, where the metrics in `lower_is_better` are worse
+/// when they increase and the others when they decrease. Metrics whose baseline is zero are
+/// skipped.
+pub fn regressions(
+    baseline: &[Record],
+    record: &Record,
+    threshold: f64,
+    lower_is_better: &[&str],
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for (name, value) in &record.metrics {
+        let values = baseline
+            .iter()
+            .filter_map(|r| r.metric(name))
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            continue;
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        if mean == 0.0 {
+            continue;
+        }
+        let change = (value - mean) / mean * 100.0;
+        let worse = if lower_is_better.contains(&name.as_str()) {
+            change
+        } else {
+            -change
+        };
+        if worse > threshold {
+            regressions.push(Regression {
+                metric: name.clone(),
+                baseline: mean,
+                value: *value,
+                change,
+            });
+        }
+    }
+    regressions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{regressions, Record};
+
+    fn record(key: &str, throughput: f64, peak_mem: f64) -> Record {
+        Record {
+            key: key.to_string(),
+            env: vec![("hostname".to_string(), "bench\"1\"".to_string())],
+            metrics: vec![
+                ("throughput".to_string(), throughput),
+                ("peak_mem".to_string(), peak_mem),
+            ],
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let r = record("ds=HList,key_size=\"exp\\32\"", 1234.0, 0.5);
+        assert_eq!(Record::from_json(&r.to_json()), Some(r));
+        assert_eq!(
+            Record::from_json("{\"key\":\"k\",\"throughput\":1e3}\n"),
+            Some(Record {
+                key: "k".to_string(),
+                env: Vec::new(),
+                metrics: vec![("throughput".to_string(), 1000.0)],
+            })
+        );
+        assert_eq!(Record::from_json("{\"throughput\":1}"), None);
+        assert_eq!(Record::from_json("{\"key\":\"k\",\"throughput\":x}"), None);
+        assert_eq!(Record::from_json("{\"key\":\"k\""), None);
+    }
+
+    #[test]
+    fn detect_regressions() {
+        let baseline = [record("k", 90.0, 100.0), record("k", 110.0, 100.0)];
+        let lower = ["peak_mem"];
+        assert!(regressions(&baseline, &record("k", 96.0, 104.0), 5.0, &lower).is_empty());
+        assert!(regressions(&baseline, &record("k", 200.0, 50.0), 5.0, &lower).is_empty());
+        assert!(regressions(&[], &record("k", 1.0, 1000.0), 5.0, &lower).is_empty());
+
+        let r = regressions(&baseline, &record("k", 80.0, 120.0), 5.0, &lower);
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0].metric, "throughput");
+        assert!((r[0].baseline - 100.0).abs() < 1e-9);
+        assert!((r[0].change + 20.0).abs() < 1e-9);
+        assert_eq!(r[1].metric, "peak_mem");
+        assert!((r[1].change - 20.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,106 @@
+//! Statistics and tuning of the seeks of the Natarajan-Mittal trees (`NMTree` and the buckets of
+//! `HashTreeMap`).
+//!
+//! An update of the tree seeks the leaf of its key from the root, and seeks again from the root
+//! whenever its CAS fails. With seek reuse, a retry instead resumes from the ancestor of the seek
+//! record of the failed attempt, i.e., the last node on the path whose edge toward the key was
+//! untagged, skipping the upper part of the path. The ancestor is still in the tree if its edge
+//! toward the key is still unmarked and leads to an internal node, since the removal of a node
+//! marks both its edges and only the edges to leaves are flagged. Otherwise the retry seeks from
+//! the root. Under PEBR, a resumed seek also keeps the shield of the ancestor instead of defending
+//! the upper part of the path again. A map reads the setting when it is created.
+//!
+//! When enabled, each thread counts its seeks, the nodes that they visited (and, under PEBR,
+//! defended), and the retries that resumed from the ancestor or fell back to the root. The counts
+//! are merged into the global ones when the thread exits.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REUSE: AtomicBool = AtomicBool::new(false);
+static SEEKS: AtomicU64 = AtomicU64::new(0);
+static VISITED: AtomicU64 = AtomicU64::new(0);
+static RESUMED: AtomicU64 = AtomicU64::new(0);
+static FALLBACKS: AtomicU64 = AtomicU64::new(0);
+
+/// The counts of the seeks.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Stats {
+    /// The seeks, from the root or resumed.
+    pub seeks: u64,
+    /// The nodes that the seeks visited.
+    pub visited: u64,
+    /// The retries that resumed from the ancestor of the failed attempt.
+    pub resumed: u64,
+    /// The retries that sought from the root as the ancestor was removed.
+    pub fallbacks: u64,
+}
+
+struct Local(Stats);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        SEEKS.fetch_add(self.0.seeks, Ordering::Relaxed);
+        VISITED.fetch_add(self.0.visited, Ordering::Relaxed);
+        RESUMED.fetch_add(self.0.resumed, Ordering::Relaxed);
+        FALLBACKS.fetch_add(self.0.fallbacks, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(Stats::default()));
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Makes the retries of the trees created afterwards resume from the ancestor of the failed
+/// attempt.
+pub fn set_reuse(reuse: bool) {
+    REUSE.store(reuse, Ordering::Relaxed);
+}
+
+pub fn reuse() -> bool {
+    REUSE.load(Ordering::Relaxed)
+}
+
+/// Records a seek that visited `visited` nodes.
+#[inline]
+pub fn record_seek(visited: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    LOCAL.with(|local| {
+        let stats = &mut local.borrow_mut().0;
+        stats.seeks += 1;
+        stats.visited += visited as u64;
+    });
+}
+
+/// Records a retry that resumed from the ancestor, or fell back to the root if not `resumed`.
+#[inline]
+pub fn record_retry(resumed: bool) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    LOCAL.with(|local| {
+        let stats = &mut local.borrow_mut().0;
+        if resumed {
+            stats.resumed += 1;
+        } else {
+            stats.fallbacks += 1;
+        }
+    });
+}
+
+/// Takes the counts recorded by the exited threads.
+pub fn take() -> Stats {
+    Stats {
+        seeks: SEEKS.swap(0, Ordering::Relaxed),
+        visited: VISITED.swap(0, Ordering::Relaxed),
+        resumed: RESUMED.swap(0, Ordering::Relaxed),
+        fallbacks: FALLBACKS.swap(0, Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,112 @@
+//! Statistics and tuning of the towers of `SkipList`.
+//!
+//! A node of the skiplist is linked into the levels below its height. A new node is 1 level high,
+//! and grows by another level with probability `p` for each level up to the maximum level, so that
+//! the expected height is about `1 / (1 - p)`. Higher towers make the searches shorter, but every
+//! level of a node is another link that an update has to mark, snip and link, and another pointer
+//! that a traversal has to protect under PEBR. When enabled, each thread counts the heights of the
+//! nodes it inserted, and the counts are merged into the global ones when the thread exits.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// The highest maximum level.
+pub const MAX_HEIGHT: usize = 32;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// `p` as a fraction of `u32::max_value()`.
+static P: AtomicU32 = AtomicU32::new(1 << 31);
+static MAX_LEVEL: AtomicUsize = AtomicUsize::new(20);
+static HISTOGRAM: [AtomicU64; MAX_HEIGHT] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+struct Local([u64; MAX_HEIGHT]);
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        for (global, local) in HISTOGRAM.iter().zip(self.0.iter()) {
+            global.fetch_add(*local, Ordering::Relaxed);
+        }
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local([0; MAX_HEIGHT]));
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Sets the probability that a tower grows by each level, for the skiplists created afterwards.
+pub fn set_p(p: f64) {
+    assert!((0.0..1.0).contains(&p));
+    P.store((p * f64::from(u32::max_value())) as u32, Ordering::Relaxed);
+}
+
+/// The probability as a fraction of `u32::max_value()`.
+#[inline]
+pub fn p() -> u32 {
+    P.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum height of the towers, for the skiplists created afterwards.
+pub fn set_max_level(max_level: usize) {
+    assert!(max_level > 0 && max_level <= MAX_HEIGHT);
+    MAX_LEVEL.store(max_level, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn max_level() -> usize {
+    MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Records that the current thread inserted a node of `height` levels.
+#[inline]
+pub fn record(height: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let _ = LOCAL.try_with(|local| local.borrow_mut().0[height - 1] += 1);
+}
+
+/// Takes the number of the inserted nodes of each height `i + 1`, recorded by the exited threads.
+pub fn take() -> [u64; MAX_HEIGHT] {
+    let mut histogram = [0; MAX_HEIGHT];
+    for (h, global) in histogram.iter_mut().zip(HISTOGRAM.iter()) {
+        *h = global.swap(0, Ordering::Relaxed);
+    }
+    histogram
+}
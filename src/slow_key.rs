@@ -0,0 +1,79 @@
+//! Keys whose comparison is deliberately slow.
+//!
+//! Every comparison of two `SlowKey`s spins for the configured delay before comparing the strings.
+//! A traversal then holds its guard (or shields) for longer, which isolates how the length of the
+//! critical sections affects each scheme: the longer an EBR thread stays pinned, the longer it
+//! holds back the epoch. Hashing is not slowed down.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{self, AtomicU64};
+use std::time::{Duration, Instant};
+
+static DELAY_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Makes every comparison of `SlowKey`s take at least `ns` nanoseconds.
+pub fn set_delay(ns: u64) {
+    DELAY_NS.store(ns, atomic::Ordering::Relaxed);
+}
+
+#[inline]
+fn spin() {
+    let ns = DELAY_NS.load(atomic::Ordering::Relaxed);
+    if ns == 0 {
+        return;
+    }
+    let delay = Duration::from_nanos(ns);
+    let start = Instant::now();
+    while start.elapsed() < delay {}
+}
+
+/// A string key that spins before every comparison.
+#[derive(Clone, Debug)]
+pub struct SlowKey(String);
+
+impl From<String> for SlowKey {
+    fn from(key: String) -> Self {
+        SlowKey(key)
+    }
+}
+
+impl Deref for SlowKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for SlowKey {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        spin();
+        self.0 == other.0
+    }
+}
+
+impl Eq for SlowKey {}
+
+impl PartialOrd for SlowKey {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SlowKey {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        spin();
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for SlowKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
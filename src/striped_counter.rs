@@ -0,0 +1,79 @@
+//! Striped counter for the approximate sizes of the data structures.
+//!
+//! Each thread updates one of `STRIPES` cache-padded counters, so that concurrent updates rarely
+//! contend on a cache line. Reading sums the stripes without synchronizing with the updates, so the
+//! sum is exact only in the absence of concurrent updates.
+
+use crossbeam_utils::CachePadded;
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+const STRIPES: usize = 16;
+
+static NEXT_STRIPE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static STRIPE: usize = NEXT_STRIPE.fetch_add(1, Ordering::Relaxed) % STRIPES;
+}
+
+pub struct StripedCounter {
+    stripes: Vec<CachePadded<AtomicIsize>>,
+}
+
+impl Default for StripedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StripedCounter {
+    pub fn new() -> Self {
+        Self {
+            stripes: (0..STRIPES)
+                .map(|_| CachePadded::new(AtomicIsize::new(0)))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    pub fn add(&self, delta: isize) {
+        let stripe = STRIPE.with(|s| *s);
+        self.stripes[stripe].fetch_add(delta, Ordering::Relaxed);
+    }
+
+    /// The sum of the stripes. It may be negative if an update is observed before the one that
+    /// happened before it.
+    pub fn sum(&self) -> isize {
+        self.stripes.iter().map(|s| s.load(Ordering::Relaxed)).sum()
+    }
+
+    /// The sum clamped to zero, for counting elements.
+    pub fn approx(&self) -> usize {
+        self.sum().max(0) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StripedCounter;
+    use crossbeam_utils::thread;
+
+    #[test]
+    fn concurrent_adds() {
+        let counter = &StripedCounter::new();
+        thread::scope(|s| {
+            for t in 0..32 {
+                s.spawn(move |_| {
+                    for _ in 0..1000 {
+                        counter.add(if t % 4 == 0 { -1 } else { 2 });
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert_eq!(counter.sum(), 8 * -1000 + 24 * 2000);
+        assert_eq!(counter.approx(), 40000);
+
+        counter.add(-40001);
+        assert_eq!(counter.approx(), 0);
+    }
+}
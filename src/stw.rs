@@ -0,0 +1,177 @@
+//! A deliberately simple stop-the-world reclamation scheme (`-m STW`), as a lower bound of
+//! sophistication for the comparison with the other schemes.
+//!
+//! The scheme is a global reader-writer lock. Every operation of a worker holds the read side, and
+//! the structures retire their objects into a global list instead of deferring them. A collector
+//! thread takes the write side every `PERIOD`, which waits for the operations in flight and holds
+//! off the new ones, and frees the whole list: with no operation in flight, no thread holds a
+//! reference to an object retired before. The workers pause during a collection like the mutators
+//! of a garbage collector, and the time the collector holds the write side is reported as the
+//! pause.
+//!
+//! The lock prefers the collector, which would otherwise starve behind the stream of operations.
+//! The structures run their EBR ports with the guard of NR, so the deferred functions other than
+//! the retires, e.g. those of the descriptors, are leaked as under NR.
+
+use std::cell::RefCell;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// The period of the collections.
+pub const PERIOD: Duration = Duration::from_millis(10);
+
+/// The number of retired objects that a thread buffers before moving them to the global list.
+const BATCH: usize = 64;
+
+/// The bit of `STATE` that the collector sets to take the write side.
+const COLLECTOR: usize = !(usize::max_value() >> 1);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// The operations in flight, and `COLLECTOR` while the collector holds or waits for the write
+/// side.
+static STATE: AtomicUsize = AtomicUsize::new(0);
+
+static COLLECTIONS: AtomicU64 = AtomicU64::new(0);
+static FREED: AtomicU64 = AtomicU64::new(0);
+static PAUSE_NANOS: AtomicU64 = AtomicU64::new(0);
+static MAX_PAUSE_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// A retired object and the function that frees it.
+struct Garbage {
+    ptr: usize,
+    destroy: unsafe fn(usize),
+}
+
+static INIT: Once = Once::new();
+static mut GARBAGE: *const Mutex<Vec<Garbage>> = ptr::null();
+
+fn garbage() -> &'static Mutex<Vec<Garbage>> {
+    INIT.call_once(|| unsafe {
+        GARBAGE = Box::into_raw(Box::new(Mutex::new(Vec::new())));
+    });
+    unsafe { &*GARBAGE }
+}
+
+/// The objects that the thread retired since it last moved them to the global list.
+struct Local(Vec<Garbage>);
+
+impl Local {
+    fn flush(&mut self) {
+        garbage().lock().unwrap().append(&mut self.0);
+    }
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local(Vec::new()));
+}
+
+/// Turns the scheme on or off for the next run.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Retires the object at `ptr`, which `destroy` frees at a collection after the retiring operation
+/// has finished.
+#[inline]
+pub fn retire(ptr: usize, destroy: unsafe fn(usize)) {
+    let buffered = LOCAL.try_with(|local| {
+        let mut local = local.borrow_mut();
+        local.0.push(Garbage { ptr, destroy });
+        if local.0.len() >= BATCH {
+            local.flush();
+        }
+    });
+    // The objects retired while the thread exits go to the global list directly.
+    if buffered.is_err() {
+        garbage().lock().unwrap().push(Garbage { ptr, destroy });
+    }
+}
+
+/// The read side of the lock, which an operation holds while it runs.
+pub struct ReadGuard(bool);
+
+/// Holds off the collections until the returned guard is dropped, if the scheme is on.
+#[inline]
+pub fn read() -> ReadGuard {
+    if !enabled() {
+        return ReadGuard(false);
+    }
+    loop {
+        if STATE.fetch_add(1, Ordering::SeqCst) & COLLECTOR == 0 {
+            return ReadGuard(true);
+        }
+        // The collector is waiting or collecting: step back and let it finish.
+        STATE.fetch_sub(1, Ordering::Release);
+        while STATE.load(Ordering::Relaxed) & COLLECTOR != 0 {
+            atomic::spin_loop_hint();
+        }
+    }
+}
+
+impl Drop for ReadGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if self.0 {
+            STATE.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+/// Takes the write side and frees all the objects retired so far, including those that the calling
+/// thread buffers. There is a single collector at a time.
+pub fn collect() {
+    LOCAL.with(|local| local.borrow_mut().flush());
+    let start = Instant::now();
+    STATE.fetch_or(COLLECTOR, Ordering::SeqCst);
+    while STATE.load(Ordering::Acquire) != COLLECTOR {
+        atomic::spin_loop_hint();
+    }
+    let objects = mem::replace(&mut *garbage().lock().unwrap(), Vec::new());
+    for g in &objects {
+        unsafe { (g.destroy)(g.ptr) };
+    }
+    STATE.fetch_and(!COLLECTOR, Ordering::Release);
+
+    let pause = start.elapsed().as_nanos() as u64;
+    COLLECTIONS.fetch_add(1, Ordering::Relaxed);
+    FREED.fetch_add(objects.len() as u64, Ordering::Relaxed);
+    PAUSE_NANOS.fetch_add(pause, Ordering::Relaxed);
+    // There is a single collector.
+    if pause > MAX_PAUSE_NANOS.load(Ordering::Relaxed) {
+        MAX_PAUSE_NANOS.store(pause, Ordering::Relaxed);
+    }
+}
+
+/// The collections of a run.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Stats {
+    pub collections: u64,
+    pub freed: u64,
+    /// The total and the longest time the collector held the write side, in ns.
+    pub pause_nanos: u64,
+    pub max_pause_nanos: u64,
+}
+
+/// Takes the statistics of the collections so far.
+pub fn take() -> Stats {
+    Stats {
+        collections: COLLECTIONS.swap(0, Ordering::Relaxed),
+        freed: FREED.swap(0, Ordering::Relaxed),
+        pause_nanos: PAUSE_NANOS.swap(0, Ordering::Relaxed),
+        max_pause_nanos: MAX_PAUSE_NANOS.swap(0, Ordering::Relaxed),
+    }
+}
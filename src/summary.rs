@@ -0,0 +1,297 @@
+//! Pivoted summary tables of the results, like the tables of the paper.
+//!
+//! The runs are grouped twice: into a table per workload, and in each table into a row per data
+//! structure and memory manager and a column per number of threads. A cell holds the mean
+//! throughput of the runs in millions of operations per second, and its ratio to the throughput of
+//! the base memory manager with the same structure and threads.
+
+/// A run that did not fail.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Run {
+    /// The parameters that tell the tables apart, e.g. `get_rate=0, non_coop=0`.
+    pub workload: String,
+    pub ds: String,
+    pub mm: String,
+    pub threads: usize,
+    /// Operations per second.
+    pub throughput: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Text,
+    Latex,
+}
+
+/// A memory manager with its mean throughput for each number of threads.
+pub type Means = (String, Vec<Option<f64>>);
+
+/// The runs of a workload, pivoted.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Table {
+    pub workload: String,
+    /// The memory manager that the ratios are relative to.
+    pub base: String,
+    pub threads: Vec<usize>,
+    /// Each structure with the mean throughput of each of its memory managers for each number of
+    /// threads, in the order they first appear in the runs.
+    pub rows: Vec<(String, Vec<Means>)>,
+}
+
+/// Appends `x` to `v` if it is absent, and returns its index.
+fn position<T: PartialEq>(v: &mut Vec<T>, x: T) -> usize {
+    match v.iter().position(|y| *y == x) {
+        Some(i) => i,
+        None => {
+            v.push(x);
+            v.len() - 1
+        }
+    }
+}
+
+/// Groups `runs` into a table per workload. The ratios are relative to `base` if a table has its
+/// runs, and otherwise to the first memory manager of the table.
+pub fn tables(runs: &[Run], base: Option<&str>) -> Vec<Table> {
+    let mut workloads = Vec::new();
+    for run in runs {
+        position(&mut workloads, run.workload.as_str());
+    }
+    workloads
+        .into_iter()
+        .map(|workload| {
+            let runs = runs
+                .iter()
+                .filter(|r| r.workload == workload)
+                .collect::<Vec<_>>();
+            table(workload, &runs, base)
+        })
+        .collect()
+}
+
+fn table(workload: &str, runs: &[&Run], base: Option<&str>) -> Table {
+    let mut threads = runs.iter().map(|r| r.threads).collect::<Vec<_>>();
+    threads.sort();
+    threads.dedup();
+    let (mut dss, mut mms) = (Vec::new(), Vec::new());
+    for run in runs {
+        position(&mut dss, run.ds.as_str());
+        position(&mut mms, run.mm.as_str());
+    }
+
+    // The sum and the number of the throughputs of each structure, memory manager and threads.
+    let index = |d: usize, m: usize, t: usize| (d * mms.len() + m) * threads.len() + t;
+    let mut sums = vec![(0.0, 0); dss.len() * mms.len() * threads.len()];
+    for run in runs {
+        let d = dss.iter().position(|ds| *ds == run.ds).unwrap();
+        let m = mms.iter().position(|mm| *mm == run.mm).unwrap();
+        let t = threads.binary_search(&run.threads).unwrap();
+        let sum = &mut sums[index(d, m, t)];
+        sum.0 += run.throughput;
+        sum.1 += 1;
+    }
+
+    let rows = dss
+        .iter()
+        .enumerate()
+        .map(|(d, ds)| {
+            let mms = mms
+                .iter()
+                .enumerate()
+                .map(|(m, mm)| {
+                    let cells = (0..threads.len())
+                        .map(|t| match sums[index(d, m, t)] {
+                            (_, 0) => None,
+                            (sum, n) => Some(sum / n as f64),
+                        })
+                        .collect::<Vec<_>>();
+                    (mm.to_string(), cells)
+                })
+                .filter(|(_, cells)| cells.iter().any(Option::is_some))
+                .collect();
+            (ds.to_string(), mms)
+        })
+        .collect();
+    Table {
+        workload: workload.to_string(),
+        base: base
+            .filter(|b| mms.contains(b))
+            .unwrap_or(mms[0])
+            .to_string(),
+        threads,
+        rows,
+    }
+}
+
+/// Escapes the special characters of LaTeX.
+fn latex(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if "_%&#$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Table {
+    /// The caption of the table.
+    pub fn caption(&self) -> String {
+        let workload = if self.workload.is_empty() {
+            "all runs"
+        } else {
+            &self.workload
+        };
+        format!("{} (M ops/s, ratio to {})", workload, self.base)
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let mut grid = vec![["DS", "MM"]
+            .iter()
+            .map(|s| s.to_string())
+            .chain(self.threads.iter().map(usize::to_string))
+            .collect::<Vec<_>>()];
+        // The index of the first row of each structure.
+        let mut firsts = Vec::new();
+        for (ds, mms) in &self.rows {
+            firsts.push(grid.len());
+            let base = mms.iter().find(|(mm, _)| *mm == self.base);
+            for (i, (mm, cells)) in mms.iter().enumerate() {
+                let mut row = vec![if i == 0 { ds.clone() } else { String::new() }, mm.clone()];
+                for (t, cell) in cells.iter().enumerate() {
+                    let base = base.and_then(|(_, cells)| cells[t]).filter(|b| *b > 0.0);
+                    row.push(match (cell, base) {
+                        (None, _) => "-".to_string(),
+                        (Some(x), None) => format!("{:.3}", x / 1e6),
+                        (Some(x), Some(b)) => format!("{:.3} ({:.2})", x / 1e6, x / b),
+                    });
+                }
+                grid.push(row);
+            }
+        }
+
+        match format {
+            Format::Text => {
+                let widths = (0..grid[0].len())
+                    .map(|c| grid.iter().map(|row| row[c].len()).max().unwrap())
+                    .collect::<Vec<_>>();
+                let mut text = self.caption() + "\n";
+                for row in &grid {
+                    let mut line = String::new();
+                    for (c, cell) in row.iter().enumerate() {
+                        if c < 2 {
+                            line += &format!("{:<w$}  ", cell, w = widths[c]);
+                        } else {
+                            line += &format!("{:>w$}  ", cell, w = widths[c]);
+                        }
+                    }
+                    text += line.trim_end();
+                    text += "\n";
+                }
+                text
+            }
+            Format::Latex => {
+                let mut text = format!("% {}\n", latex(&self.caption()));
+                text += &format!(
+                    "\\begin{{tabular}}{{ll{}}}\n",
+                    "r".repeat(self.threads.len())
+                );
+                text += "\\hline\n";
+                for (r, row) in grid.iter().enumerate() {
+                    if firsts.contains(&r) {
+                        text += "\\hline\n";
+                    }
+                    let cells = row.iter().map(|c| latex(c)).collect::<Vec<_>>();
+                    text += &format!("{} \\\\\n", cells.join(" & "));
+                }
+                text += "\\hline\n\\end{tabular}\n";
+                text
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tables, Format, Run};
+
+    fn run(workload: &str, ds: &str, mm: &str, threads: usize, throughput: f64) -> Run {
+        Run {
+            workload: workload.to_string(),
+            ds: ds.to_string(),
+            mm: mm.to_string(),
+            threads,
+            throughput,
+        }
+    }
+
+    #[test]
+    fn pivot() {
+        let runs = [
+            run("get_rate=0", "HList", "EBR", 4, 2e6),
+            run("get_rate=0", "HList", "PEBR", 4, 1e6),
+            run("get_rate=0", "HList", "EBR", 1, 1e6),
+            run("get_rate=0", "HList", "EBR", 4, 4e6),
+            run("get_rate=1", "HList", "EBR", 1, 5e6),
+            run("get_rate=0", "NMTree", "PEBR", 1, 3e6),
+        ];
+        let tables = tables(&runs, None);
+        assert_eq!(tables.len(), 2);
+        let t = &tables[0];
+        assert_eq!(t.workload, "get_rate=0");
+        assert_eq!(t.base, "EBR");
+        assert_eq!(t.threads, vec![1, 4]);
+        assert_eq!(
+            t.rows,
+            vec![
+                (
+                    "HList".to_string(),
+                    vec![
+                        ("EBR".to_string(), vec![Some(1e6), Some(3e6)]),
+                        ("PEBR".to_string(), vec![None, Some(1e6)]),
+                    ]
+                ),
+                (
+                    "NMTree".to_string(),
+                    vec![("PEBR".to_string(), vec![Some(3e6), None])]
+                ),
+            ]
+        );
+        assert_eq!(
+            t.render(Format::Text),
+            "get_rate=0 (M ops/s, ratio to EBR)\n\
+             DS      MM               1             4\n\
+             HList   EBR   1.000 (1.00)  3.000 (1.00)\n\
+             \x20       PEBR             -  1.000 (0.33)\n\
+             NMTree  PEBR         3.000             -\n"
+        );
+        assert_eq!(tables[1].rows[0].1[0].1, vec![Some(5e6)]);
+
+        let t = &super::tables(&runs, Some("PEBR"))[0];
+        assert_eq!(t.base, "PEBR");
+        assert_eq!(super::tables(&runs, Some("NR"))[0].base, "EBR");
+    }
+
+    #[test]
+    fn latex() {
+        let runs = [
+            run("get_rate=0", "HList", "EBR", 1, 1e6),
+            run("get_rate=0", "HList", "PEBR", 1, 2e6),
+            run("get_rate=0", "NMTree", "EBR", 1, 1e6),
+        ];
+        assert_eq!(
+            tables(&runs, None)[0].render(Format::Latex),
+            "% get\\_rate=0 (M ops/s, ratio to EBR)\n\
+             \\begin{tabular}{llr}\n\
+             \\hline\n\
+             DS & MM & 1 \\\\\n\
+             \\hline\n\
+             HList & EBR & 1.000 (1.00) \\\\\n\
+             \x20& PEBR & 2.000 (2.00) \\\\\n\
+             \\hline\n\
+             NMTree & EBR & 1.000 (1.00) \\\\\n\
+             \\hline\n\
+             \\end{tabular}\n"
+        );
+    }
+}
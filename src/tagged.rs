@@ -0,0 +1,309 @@
+//! Two ways of marking the link to the next node as removed, to compare them under each reclamation
+//! scheme: in the low bits of the pointer ([`TagLink`]), or in a version counter that is stored next
+//! to the pointer and updated together with it by a double-width CAS ([`VersionLink`]).
+//!
+//! Tagging needs the nodes to be aligned enough to leave the tag bits unused, which [`audit`]
+//! checks. The version counter needs no spare bits and also rules out ABA on the link, but the
+//! double-width CAS is only available on x86-64 processors with `cmpxchg16b` (see
+//! [`dwcas_available`]).
+//!
+//! Independently of where the mark is, the Harris lists can set it with a single `fetch_or` or with
+//! a CAS loop on the whole link ([`MarkOp`]). `fetch_or` is one instruction on x86, but a loop of
+//! load-linked/store-conditional on other platforms.
+
+use std::any;
+use std::cell::UnsafeCell;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of low bits that are zero in every pointer to a `T`.
+pub fn tag_bits<T>() -> u32 {
+    mem::align_of::<T>().trailing_zeros()
+}
+
+/// Panics unless the pointers to `T` leave room for `bits` tag bits.
+pub fn audit<T>(bits: u32) {
+    assert!(
+        tag_bits::<T>() >= bits,
+        "{} needs {} tag bits, but its alignment leaves {}",
+        any::type_name::<T>(),
+        bits,
+        tag_bits::<T>()
+    );
+}
+
+/// Whether this processor supports double-width CAS.
+#[cfg(target_arch = "x86_64")]
+pub fn dwcas_available() -> bool {
+    is_x86_feature_detected!("cmpxchg16b")
+}
+
+/// Whether this processor supports double-width CAS.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn dwcas_available() -> bool {
+    false
+}
+
+/// Compares `*dst` with `old` and replaces it with `new` if equal, returning the previous value.
+///
+/// Written in assembly since the intrinsic falls back to a libatomic call unless the whole crate is
+/// compiled with `cmpxchg16b`. `rbx` can't be an operand, so the low half of `new` is swapped into
+/// it. The other operands are in fixed registers, since LLVM may allocate `rbx` for them. `dst` must
+/// be aligned to 16 bytes.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub(crate) unsafe fn dwcas(dst: *mut u128, old: u128, new: u128) -> u128 {
+    let lo: u64;
+    let hi: u64;
+    let _rsi: u64;
+    asm!("xchgq %rsi, %rbx
+          lock cmpxchg16b ($5)
+          movq %rsi, %rbx"
+         : "={rax}"(lo), "={rdx}"(hi), "={rsi}"(_rsi)
+         : "{rax}"(old as u64), "{rdx}"((old >> 64) as u64), "{rdi}"(dst),
+           "{rsi}"(new as u64), "{rcx}"((new >> 64) as u64)
+         : "memory", "cc"
+         : "volatile");
+    (hi as u128) << 64 | lo as u128
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) unsafe fn dwcas(_dst: *mut u128, _old: u128, _new: u128) -> u128 {
+    unreachable!("double-width CAS is not available")
+}
+
+/// A snapshot of a link, to be compared against by [`Link::cas`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Snapshot {
+    pub ptr: usize,
+    pub marked: bool,
+    version: u64,
+}
+
+/// The link from a node to the next one, which is marked when the node is removed.
+pub trait Link: Send + Sync {
+    /// An unmarked link to `ptr`.
+    fn new(ptr: usize) -> Self;
+
+    /// Panics if the links can't point to `N`s on this machine.
+    fn audit<N>();
+
+    fn load(&self) -> Snapshot;
+
+    /// Points the link of a node that is not shared yet to `ptr`.
+    fn set(&mut self, ptr: usize);
+
+    /// Points the link to `ptr`, marked if `mark`, if it has not changed since `current` was
+    /// taken. Returns the new snapshot, or the current one on failure.
+    fn cas(&self, current: Snapshot, ptr: usize, mark: bool) -> Result<Snapshot, Snapshot>;
+}
+
+/// Marks the lowest bit of the pointer.
+#[derive(Debug)]
+pub struct TagLink {
+    inner: AtomicUsize,
+}
+
+impl TagLink {
+    fn snapshot(word: usize) -> Snapshot {
+        Snapshot {
+            ptr: word & !1,
+            marked: word & 1 != 0,
+            version: 0,
+        }
+    }
+}
+
+impl Link for TagLink {
+    fn new(ptr: usize) -> Self {
+        debug_assert_eq!(ptr & 1, 0);
+        Self {
+            inner: AtomicUsize::new(ptr),
+        }
+    }
+
+    fn audit<N>() {
+        audit::<N>(1);
+    }
+
+    #[inline]
+    fn load(&self) -> Snapshot {
+        Self::snapshot(self.inner.load(Ordering::Acquire))
+    }
+
+    fn set(&mut self, ptr: usize) {
+        *self.inner.get_mut() = ptr;
+    }
+
+    #[inline]
+    fn cas(&self, current: Snapshot, ptr: usize, mark: bool) -> Result<Snapshot, Snapshot> {
+        let new = ptr | mark as usize;
+        self.inner
+            .compare_exchange(
+                current.ptr | current.marked as usize,
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map(|_| Self::snapshot(new))
+            .map_err(Self::snapshot)
+    }
+}
+
+/// Pairs the pointer with a version that is incremented by every update, and is odd iff the link
+/// is marked.
+#[derive(Debug)]
+#[repr(align(16))]
+pub struct VersionLink {
+    /// The pointer in the low half, and the version in the high half.
+    inner: UnsafeCell<u128>,
+}
+
+unsafe impl Send for VersionLink {}
+unsafe impl Sync for VersionLink {}
+
+impl VersionLink {
+    fn pack(ptr: usize, version: u64) -> u128 {
+        (version as u128) << 64 | ptr as u128
+    }
+
+    fn snapshot(word: u128) -> Snapshot {
+        let version = (word >> 64) as u64;
+        Snapshot {
+            ptr: word as usize,
+            marked: version & 1 != 0,
+            version,
+        }
+    }
+}
+
+impl Link for VersionLink {
+    fn new(ptr: usize) -> Self {
+        Self {
+            inner: UnsafeCell::new(Self::pack(ptr, 0)),
+        }
+    }
+
+    fn audit<N>() {
+        assert!(
+            dwcas_available(),
+            "version links need double-width CAS, which this processor lacks"
+        );
+    }
+
+    #[inline]
+    fn load(&self) -> Snapshot {
+        // A CAS that replaces the value with itself if it is zero, which doesn't change it either
+        // way.
+        Self::snapshot(unsafe { dwcas(self.inner.get(), 0, 0) })
+    }
+
+    fn set(&mut self, ptr: usize) {
+        // Not shared yet, so the plain accesses don't race.
+        let inner = self.inner.get();
+        unsafe {
+            let version = Self::snapshot(*inner).version;
+            *inner = Self::pack(ptr, version);
+        }
+    }
+
+    #[inline]
+    fn cas(&self, current: Snapshot, ptr: usize, mark: bool) -> Result<Snapshot, Snapshot> {
+        let mut version = current.version.wrapping_add(1);
+        if (version & 1 != 0) != mark {
+            version = version.wrapping_add(1);
+        }
+        let old = Self::pack(current.ptr, current.version);
+        let new = Self::pack(ptr, version);
+        let prev = unsafe { dwcas(self.inner.get(), old, new) };
+        if prev == old {
+            Ok(Self::snapshot(new))
+        } else {
+            Err(Self::snapshot(prev))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dwcas_available, tag_bits, Link, TagLink, VersionLink};
+    use crossbeam_utils::thread;
+
+    const THREADS: usize = 8;
+    const INCREMENTS: usize = 10000;
+
+    /// Increments the pointer of a link from many threads, stopping once it is marked.
+    fn increments<L: Link>() {
+        let link = L::new(0);
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|_| {
+                    for _ in 0..INCREMENTS {
+                        let mut current = link.load();
+                        while let Err(snapshot) = link.cas(current, current.ptr + 2, false) {
+                            current = snapshot;
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+        let current = link.load();
+        assert_eq!(current.ptr, THREADS * INCREMENTS * 2);
+        assert!(!current.marked);
+
+        let marked = link.cas(current, current.ptr, true).unwrap();
+        assert!(marked.marked);
+        assert!(link.cas(current, 0, false).is_err());
+        assert_eq!(link.load(), marked);
+    }
+
+    #[test]
+    fn tag_bits_of_aligned_types() {
+        assert_eq!(tag_bits::<u8>(), 0);
+        assert_eq!(tag_bits::<u64>(), 3);
+        TagLink::audit::<u64>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn audit_unaligned() {
+        TagLink::audit::<u8>();
+    }
+
+    #[test]
+    fn tag_link() {
+        increments::<TagLink>();
+    }
+
+    #[test]
+    fn version_link() {
+        if !dwcas_available() {
+            return;
+        }
+        increments::<VersionLink>();
+    }
+}
+
+/// How the Harris lists set the mark of the link of a node they remove, chosen at compile time
+/// with the type parameter of the lists.
+pub trait MarkOp: Send + Sync + 'static {
+    /// Whether the mark is set by a CAS loop on the whole link instead of `fetch_or`.
+    const CAS: bool;
+}
+
+/// Marks with `fetch_or`.
+#[derive(Debug)]
+pub struct FetchOr;
+
+impl MarkOp for FetchOr {
+    const CAS: bool = false;
+}
+
+/// Marks with a CAS of the pointer and the tag together, retried until the link is marked.
+#[derive(Debug)]
+pub struct CasMark;
+
+impl MarkOp for CasMark {
+    const CAS: bool = true;
+}
@@ -0,0 +1,101 @@
+//! A token bucket that limits the rate of the operations of a group of workers, e.g., the writers
+//! of `--writers 4:insert=50,remove=50@100000`.
+//!
+//! The bucket is kept as the time of the next free slot, one interval of the rate after the last
+//! taken one, or the current time if the bucket has been idle. An operation takes the next slot and
+//! waits until the slot, less the time of `burst - 1` intervals, so that up to `burst` operations
+//! run right away after an idle period. The slots are taken with a CAS, so that the workers of a
+//! group share the rate. A worker waits by spinning, keeping its CPU and its guard, like the think
+//! time.
+
+use std::cmp::max;
+use std::sync::atomic::{self, AtomicU64, Ordering};
+use std::time::Instant;
+
+pub struct TokenBucket {
+    /// The operations per second.
+    rate: u64,
+    /// The time between two slots, and how long before its slot an operation may run, in ns.
+    interval: u64,
+    tolerance: u64,
+    epoch: Instant,
+    /// The next free slot, in ns since `epoch`.
+    next: AtomicU64,
+}
+
+impl TokenBucket {
+    /// Creates a bucket of `rate` operations per second that holds `burst` of them.
+    pub fn new(rate: u64, burst: u64) -> Self {
+        assert!(rate > 0, "the rate of a token bucket should be positive");
+        let interval = max(1_000_000_000 / rate, 1);
+        Self {
+            rate,
+            interval,
+            tolerance: interval * (max(burst, 1) - 1),
+            epoch: Instant::now(),
+            next: AtomicU64::new(0),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Takes a token, and waits until it is due.
+    #[inline]
+    pub fn acquire(&self) {
+        let now = self.now();
+        let mut next = self.next.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = max(next, now);
+            match self.next.compare_exchange_weak(
+                next,
+                slot + self.interval,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break slot,
+                Err(n) => next = n,
+            }
+        };
+        let due = slot.saturating_sub(self.tolerance);
+        while self.now() < due {
+            atomic::spin_loop_hint();
+        }
+    }
+
+    #[inline]
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBucket;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rate() {
+        // 1 op per ms, without a burst: the 20th op is due 19 ms after the first.
+        let bucket = TokenBucket::new(1000, 1);
+        let start = Instant::now();
+        for _ in 0..20 {
+            bucket.acquire();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(19));
+    }
+
+    #[test]
+    fn burst() {
+        // 1 op per 100 ms, and a burst of 5 that runs right away.
+        let bucket = TokenBucket::new(10, 5);
+        let start = Instant::now();
+        for _ in 0..5 {
+            bucket.acquire();
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+        bucket.acquire();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,189 @@
+//! A live view of a run in the terminal, redrawn in place every progress period.
+//!
+//! A frame shows the throughput of the last period and of the whole run with a sparkline of the
+//! recent periods, the retired nodes and the memory usage, and an activity bar per worker with its
+//! throughput in the last period relative to the busiest worker. The view is drawn with ANSI escape
+//! codes, so it needs no terminal library, and the last frame stays on the screen after the run.
+//! With many workers, the bars shrink to one cell per worker so that the frame fits the screen.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// The levels of the cells of the sparkline and of the compact bars.
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Above this many workers, each worker gets a cell instead of a line.
+const MAX_BAR_LINES: usize = 32;
+
+/// The counters of a progress period.
+#[derive(Debug, Default)]
+pub struct Frame {
+    pub elapsed: Duration,
+    pub ops_per_sec: u64,
+    pub overall_ops_per_sec: u64,
+    pub retired: u64,
+    pub retired_per_sec: u64,
+    pub mem: usize,
+    /// The throughput of each worker in the period.
+    pub workers: Vec<u64>,
+}
+
+pub struct Tui {
+    title: String,
+    width: usize,
+    /// The throughput of the recent periods, the oldest first.
+    history: VecDeque<u64>,
+}
+
+impl Tui {
+    /// A view titled `title`, as wide as `$COLUMNS` or 80 columns.
+    pub fn new(title: String) -> Self {
+        let width = env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(80);
+        Self::with_width(title, width)
+    }
+
+    pub fn with_width(title: String, width: usize) -> Self {
+        let width = width.max(40);
+        Self {
+            title,
+            width,
+            history: VecDeque::with_capacity(width),
+        }
+    }
+
+    /// Redraws the screen with `frame`.
+    pub fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        let screen = self.render(frame);
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        // Hide the cursor, move it home and clear the screen.
+        write!(stdout, "\x1b[?25l\x1b[H\x1b[J{}", screen)?;
+        stdout.flush()
+    }
+
+    /// The text of the frame, after adding its throughput to the history.
+    pub fn render(&mut self, frame: &Frame) -> String {
+        let spark_width = self.width - 2;
+        if self.history.len() == spark_width {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame.ops_per_sec);
+
+        let mut s = String::new();
+        writeln!(s, "{} | {:.0}s", self.title, frame.elapsed.as_secs_f64()).unwrap();
+        writeln!(
+            s,
+            "ops/s: {} (overall {})",
+            frame.ops_per_sec, frame.overall_ops_per_sec
+        )
+        .unwrap();
+        let peak = self.history.iter().cloned().max().unwrap_or(0);
+        let spark = self
+            .history
+            .iter()
+            .map(|&v| level(v, peak))
+            .collect::<String>();
+        writeln!(s, "[{:<w$}]", spark, w = spark_width).unwrap();
+        writeln!(
+            s,
+            "retired: {} ({}/s), mem: {}",
+            frame.retired, frame.retired_per_sec, frame.mem
+        )
+        .unwrap();
+        writeln!(s).unwrap();
+
+        let busiest = frame.workers.iter().cloned().max().unwrap_or(0);
+        if frame.workers.len() <= MAX_BAR_LINES {
+            // "tid |bar| ops/s"
+            let bar_width = self.width - 20;
+            for (tid, &ops) in frame.workers.iter().enumerate() {
+                let filled = if busiest == 0 {
+                    0
+                } else {
+                    (ops as u128 * bar_width as u128 / busiest as u128) as usize
+                };
+                writeln!(
+                    s,
+                    "{:>4} |{}{}| {}",
+                    tid,
+                    "█".repeat(filled),
+                    " ".repeat(bar_width - filled),
+                    ops
+                )
+                .unwrap();
+            }
+        } else {
+            let row = self.width - 6;
+            for (i, chunk) in frame.workers.chunks(row).enumerate() {
+                let cells = chunk.iter().map(|&v| level(v, busiest)).collect::<String>();
+                writeln!(s, "{:>4} {}", i * row, cells).unwrap();
+            }
+        }
+        s
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        // Show the cursor again.
+        print!("\x1b[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// The cell of `value` on a scale up to `peak`.
+fn level(value: u64, peak: u64) -> char {
+    if peak == 0 {
+        return ' ';
+    }
+    let i = (value as u128 * (LEVELS.len() - 1) as u128 / peak as u128) as usize;
+    LEVELS[i]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Frame, Tui, MAX_BAR_LINES};
+    use std::time::Duration;
+
+    #[test]
+    fn render() {
+        let mut tui = Tui::with_width("HMList: EBR".to_string(), 60);
+        let frame = Frame {
+            elapsed: Duration::from_secs(3),
+            ops_per_sec: 300,
+            overall_ops_per_sec: 250,
+            retired: 1000,
+            retired_per_sec: 100,
+            mem: 4096,
+            workers: vec![100, 200, 0],
+        };
+        let screen = tui.render(&frame);
+        let lines = screen.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "HMList: EBR | 3s");
+        assert_eq!(lines[2], format!("[█{}]", " ".repeat(57)));
+        assert_eq!(lines[3], "retired: 1000 (100/s), mem: 4096");
+        assert_eq!(
+            lines[5],
+            format!("   0 |{}{}| 100", "█".repeat(20), " ".repeat(20))
+        );
+        assert_eq!(lines[6], format!("   1 |{}| 200", "█".repeat(40)));
+        assert_eq!(lines[7], format!("   2 |{}| 0", " ".repeat(40)));
+
+        let frame = Frame {
+            ops_per_sec: 150,
+            workers: vec![7; MAX_BAR_LINES + 1],
+            ..Frame::default()
+        };
+        let screen = tui.render(&frame);
+        let lines = screen.lines().collect::<Vec<_>>();
+        assert!(lines[2].starts_with("[█▄ "));
+        assert_eq!(lines[5], format!("   0 {}", "█".repeat(MAX_BAR_LINES + 1)));
+        assert_eq!(lines.len(), 6);
+    }
+}
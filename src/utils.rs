@@ -19,3 +19,95 @@ macro_rules! some_or {
         }
     }};
 }
+
+#[macro_export]
+/// Defines `register`, which registers the port of each map of the module to `ebr`, `pebr` or
+/// `rc` in a `Registry`, under a name, with the order of its prefill. `without slow_key` marks a
+/// map that does not support `SlowKey`s, and `with` lists the other workloads of the map, by the
+/// fields of `Port`.
+///
+/// ```ignore
+/// register_maps! {
+///     ebr;
+///     NMTree => NMTreeMap, Random, with scan unguarded;
+///     ART => ARTMap, Random, without slow_key, with scan unguarded;
+/// }
+/// ```
+macro_rules! register_maps {
+    (@scheme ebr) => {
+        $crate::registry::Scheme::EBR
+    };
+    (@scheme pebr) => {
+        $crate::registry::Scheme::PEBR
+    };
+    (@scheme rc) => {
+        $crate::registry::Scheme::RC
+    };
+    (@slow_key $method:ident, $map:ident) => {
+        Some(|d: &D, p| d.$method::<$map<$crate::slow_key::SlowKey, D::Value>>(p))
+    };
+    (@slow_key $method:ident, $map:ident, slow_key) => {
+        None
+    };
+    (@port ebr, $map:ident $(, $without:ident)?) => {
+        $crate::registry::Port {
+            set: Some(|d: &D, p| d.ebr_set::<$crate::ebr::MapSet<$map<String, ()>>>(p)),
+            slow_key: register_maps!(@slow_key ebr_slow_key, $map $(, $without)?),
+            ..$crate::registry::Port::new(|d: &D, p| d.ebr_map::<$map<String, D::Value>>(p))
+        }
+    };
+    (@port pebr, $map:ident $(, $without:ident)?) => {
+        $crate::registry::Port {
+            set: Some(|d: &D, p| d.pebr_set::<$crate::pebr::MapSet<$map<String, ()>>>(p)),
+            slow_key: register_maps!(@slow_key pebr_slow_key, $map $(, $without)?),
+            ..$crate::registry::Port::new(|d: &D, p| d.pebr_map::<$map<String, D::Value>>(p))
+        }
+    };
+    (@port rc, $map:ident $(, $without:ident)?) => {
+        $crate::registry::Port {
+            slow_key: register_maps!(@slow_key rc_slow_key, $map $(, $without)?),
+            ..$crate::registry::Port::new(|d: &D, p| d.rc_map::<$map<String, D::Value>>(p))
+        }
+    };
+    (@with ebr, $map:ident, scan) => {
+        |d: &D, p| d.ebr_scan::<$map<String, String>>(p)
+    };
+    (@with pebr, $map:ident, scan) => {
+        |d: &D, p| d.pebr_scan::<$map<String, String>>(p)
+    };
+    (@with ebr, $map:ident, unguarded) => {
+        |d: &D, p| d.ebr_unguarded::<$map<String, D::Value>>(p)
+    };
+    (@with ebr, $map:ident, transfers) => {
+        |d: &D, p| d.ebr_transfers::<$map<String, D::Value>>(p)
+    };
+    (@with ebr, $map:ident, cas_mark) => {
+        |d: &D, p| d.ebr_map::<$map<String, D::Value, $crate::tagged::CasMark>>(p)
+    };
+    (@with pebr, $map:ident, cas_mark) => {
+        |d: &D, p| d.pebr_map::<$map<String, D::Value, $crate::tagged::CasMark>>(p)
+    };
+    (@with ebr, $map:ident, compare_and_delete) => {
+        |d: &D, p| d.ebr_map::<$crate::ebr::CompareAndDelete<$map<String, D::Value>>>(p)
+    };
+    (@with pebr, $map:ident, compare_and_delete) => {
+        |d: &D, p| d.pebr_map::<$crate::pebr::CompareAndDelete<$map<String, D::Value>>>(p)
+    };
+    ($scheme:ident; $($name:ident => $map:ident, $prefill:ident $(, without $without:ident)? $(, with $($with:ident)+)?;)*) => {
+        /// Registers the maps of the module.
+        pub fn register<D: $crate::registry::Driver>(registry: &mut $crate::registry::Registry<D>) {
+            $(
+                #[allow(unused_mut)]
+                let mut port = register_maps!(@port $scheme, $map $(, $without)?);
+                $($(port.$with = Some(register_maps!(@with $scheme, $map, $with));)+)?
+                registry.register(
+                    stringify!($name),
+                    $crate::registry::Kind::Map,
+                    $crate::registry::Prefill::$prefill,
+                    register_maps!(@scheme $scheme),
+                    port,
+                );
+            )*
+        }
+    };
+}
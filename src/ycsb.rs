@@ -0,0 +1,124 @@
+//! Import of YCSB workload traces.
+//!
+//! A trace is the output of the YCSB client with the `basic` binding, which prints each operation
+//! it performs, e.g. `INSERT usertable user6284781860667377211 [ field0=... ]` or
+//! `READ usertable user1234 [ <all fields>]`. The load phase (`ycsb load basic ...`) and the run
+//! phase (`ycsb run basic ...`) are separate traces.
+//!
+//! The operations are translated to map operations: `READ` to `get`, `INSERT` to `insert`,
+//! `DELETE` to `remove`, and `UPDATE`, which the maps don't support, to a `remove` followed by an
+//! `insert`. `SCAN`s have no counterpart and are skipped. The other lines, e.g. the status messages
+//! and the measurements of the client, are ignored.
+//!
+//! The keys are numbered in the order of their first appearance, so that the operations refer to
+//! them by index like the operations on the generated keys of the benchmark.
+
+use crate::linearizability::MapOp;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct Trace {
+    pub keys: Vec<String>,
+    /// The operations of the load phase and the indices of their keys.
+    pub load: Vec<(MapOp, usize)>,
+    /// The operations of the run phase and the indices of their keys.
+    pub run: Vec<(MapOp, usize)>,
+    /// The number of skipped `SCAN`s.
+    pub scans: usize,
+    indices: HashMap<String, usize>,
+}
+
+impl Trace {
+    pub fn open(load: &Path, run: &Path) -> io::Result<Self> {
+        Self::read(
+            BufReader::new(File::open(load)?),
+            BufReader::new(File::open(run)?),
+        )
+    }
+
+    pub fn read<L: BufRead, R: BufRead>(load: L, run: R) -> io::Result<Self> {
+        let mut trace = Trace::default();
+        trace.load = trace.parse(load)?;
+        trace.run = trace.parse(run)?;
+        trace.indices = HashMap::new();
+        Ok(trace)
+    }
+
+    fn parse<R: BufRead>(&mut self, reader: R) -> io::Result<Vec<(MapOp, usize)>> {
+        let mut ops = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let mut tokens = line.split_whitespace();
+            let (name, key) = match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some(name), Some(_table), Some(key)) => (name, key),
+                _ => continue,
+            };
+            let translated: &[MapOp] = match name {
+                "READ" => &[MapOp::Get],
+                "INSERT" => &[MapOp::Insert],
+                "DELETE" => &[MapOp::Remove],
+                "UPDATE" => &[MapOp::Remove, MapOp::Insert],
+                "SCAN" => {
+                    self.scans += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            let index = self.index(key);
+            ops.extend(translated.iter().map(|&op| (op, index)));
+        }
+        Ok(ops)
+    }
+
+    fn index(&mut self, key: &str) -> usize {
+        if let Some(&index) = self.indices.get(key) {
+            return index;
+        }
+        let index = self.keys.len();
+        self.keys.push(key.to_string());
+        self.indices.insert(key.to_string(), index);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trace;
+    use crate::linearizability::MapOp;
+
+    #[test]
+    fn parse_trace() {
+        let load = "\
+Loading workload...
+Starting test.
+INSERT usertable user42 [ field0=abc field1=def ]
+INSERT usertable user7 [ field0=ghi ]
+[OVERALL], RunTime(ms), 12
+";
+        let run = "\
+READ usertable user7 [ <all fields>]
+UPDATE usertable user42 [ field1=xyz ]
+SCAN usertable user1 10 [ <all fields>]
+INSERT usertable user3 [ field0=jkl ]
+DELETE usertable user7
+[READ], Operations, 1
+";
+        let trace = Trace::read(load.as_bytes(), run.as_bytes()).unwrap();
+        assert_eq!(trace.keys, vec!["user42", "user7", "user3"]);
+        assert_eq!(trace.load, vec![(MapOp::Insert, 0), (MapOp::Insert, 1)]);
+        assert_eq!(
+            trace.run,
+            vec![
+                (MapOp::Get, 1),
+                (MapOp::Remove, 0),
+                (MapOp::Insert, 0),
+                (MapOp::Insert, 2),
+                (MapOp::Remove, 1),
+            ]
+        );
+        assert_eq!(trace.scans, 1);
+    }
+}